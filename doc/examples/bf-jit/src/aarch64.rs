@@ -1,4 +1,3 @@
-#![feature(proc_macro_hygiene)]
 extern crate dynasmrt;
 extern crate dynasm;
 
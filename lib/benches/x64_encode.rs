@@ -0,0 +1,100 @@
+//! Compares compiling static x64 instructions through a `BasicAssembler` with encoding them
+//! directly into a buffer.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use dynasm::arch::x64::{AssembleX64, Archx64, InstructionX64, MAX_INSTRUCTION_LEN};
+use dynasm::arch::x64::ast::{CleanArg, Instruction, Register, RegId};
+use dynasm::{BasicAssembler, Ident, Number, Size, Stmt, Value};
+
+fn reg(size: Size, id: RegId) -> CleanArg {
+    CleanArg::Direct { reg: Register::new_static(size, id) }
+}
+
+fn mem(size: Size, base: RegId, index: RegId, scale: isize, disp: i32) -> CleanArg {
+    CleanArg::Indirect {
+        nosplit: false,
+        size: Some(size),
+        disp_size: None,
+        base: Some(Register::new_static(Size::QWORD, base)),
+        index: Some((Register::new_static(Size::QWORD, index), scale, None)),
+        disp: Some(Value::Number(Number::dword(disp as u32).make_signed(true))),
+    }
+}
+
+fn imm(value: u32) -> CleanArg {
+    CleanArg::Immediate { value: Value::Number(Number::dword(value)) }
+}
+
+fn imm8(value: u8) -> CleanArg {
+    CleanArg::Immediate { value: Value::Number(Number::byte(value)) }
+}
+
+/// A mix of instructions a simple JIT would emit.
+fn instructions() -> Vec<(&'static str, Vec<CleanArg>)> {
+    vec![
+        ("mov", vec![reg(Size::QWORD, RegId::RAX), reg(Size::QWORD, RegId::R12)]),
+        ("add", vec![mem(Size::DWORD, RegId::RBX, RegId::RCX, 4, 8), imm8(5)]),
+        ("mov", vec![reg(Size::QWORD, RegId::R9), mem(Size::QWORD, RegId::RSP, RegId::RDX, 8, -0x100)]),
+        ("imul", vec![reg(Size::DWORD, RegId::RSI), reg(Size::DWORD, RegId::RDI), imm(0x1234)]),
+        ("vaddps", vec![reg(Size::HWORD, RegId::XMM1), reg(Size::HWORD, RegId::XMM2), reg(Size::HWORD, RegId::XMM11)]),
+        ("cmp", vec![reg(Size::BYTE, RegId::RAX), imm8(0x7F)]),
+    ]
+}
+
+fn basic_assembler(arch: &Archx64, instructions: &[(&'static str, Vec<CleanArg>)], out: &mut Vec<u8>) {
+    for (name, args) in instructions {
+        let instruction = InstructionX64 {
+            inst: Instruction { idents: vec![Ident { name: name.to_string() }] },
+            args: args.clone(),
+        };
+        let mut assembler = BasicAssembler { stmts: Vec::new() };
+        assembler.compile_instruction(arch, instruction).unwrap();
+
+        for stmt in assembler.stmts {
+            match stmt {
+                Stmt::Const(Value::Number(number)) => out.extend(number.to_le_bytes()),
+                Stmt::Extend(bytes) => out.extend(bytes),
+                _ => unreachable!()
+            }
+        }
+    }
+}
+
+fn encode_static(arch: &Archx64, instructions: &[(&'static str, Vec<CleanArg>)], out: &mut Vec<u8>) {
+    let mut buf = [0; MAX_INSTRUCTION_LEN];
+    for (name, args) in instructions {
+        let len = arch.encode_static(&[name], args, &mut buf).unwrap();
+        out.extend_from_slice(&buf[.. len]);
+    }
+}
+
+fn encode_static_extend(arch: &Archx64, instructions: &[(&'static str, Vec<CleanArg>)], out: &mut Vec<u8>) {
+    for (name, args) in instructions {
+        arch.encode_static_extend(&[name], args, out).unwrap();
+    }
+}
+
+fn bench_encode(c: &mut Criterion) {
+    let arch = Archx64::default();
+    let instructions = instructions();
+    let mut out = Vec::with_capacity(4096);
+
+    let mut group = c.benchmark_group("x64_encode");
+    group.bench_function("basic_assembler", |b| b.iter(|| {
+        out.clear();
+        basic_assembler(&arch, black_box(&instructions), &mut out);
+    }));
+    group.bench_function("encode_static", |b| b.iter(|| {
+        out.clear();
+        encode_static(&arch, black_box(&instructions), &mut out);
+    }));
+    group.bench_function("encode_static_extend", |b| b.iter(|| {
+        out.clear();
+        encode_static_extend(&arch, black_box(&instructions), &mut out);
+    }));
+    group.finish();
+}
+
+criterion_group!(benches, bench_encode);
+criterion_main!(benches);
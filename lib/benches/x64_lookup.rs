@@ -0,0 +1,75 @@
+//! Measures the lookups done while assembling x64 instructions: finding a mnemonic, parsing a
+//! register name and matching the operands against the forms of a mnemonic.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use dynasm::arch::x64::{introspect, parser, Archx64, X86Mode, MAX_INSTRUCTION_LEN};
+use dynasm::arch::x64::ast::{CleanArg, Register, RegId};
+use dynasm::{Number, Size, Value};
+
+const MNEMONICS: &[&str] = &[
+    "mov", "add", "lea", "push", "pop", "call", "jmp", "cmp", "jne", "cmovz",
+    "vaddps", "movdqa", "pshufb", "imul", "xor", "ret", "shl", "test", "setnz", "vfmadd231ps",
+];
+
+const REGISTERS: &[&str] = &[
+    "rax", "r12", "eax", "r9d", "xmm3", "ymm15", "al", "spl", "cr0", "st0",
+];
+
+fn reg(size: Size, id: RegId) -> CleanArg {
+    CleanArg::Direct { reg: Register::new_static(size, id) }
+}
+
+fn mem(size: Size, base: RegId) -> CleanArg {
+    CleanArg::Indirect {
+        nosplit: false,
+        size: Some(size),
+        disp_size: None,
+        base: Some(Register::new_static(Size::QWORD, base)),
+        index: None,
+        disp: Some(Value::Number(Number::byte(0x10).make_signed(true))),
+    }
+}
+
+fn imm8(value: u8) -> CleanArg {
+    CleanArg::Immediate { value: Value::Number(Number::byte(value)) }
+}
+
+/// Instructions whose mnemonics have many forms, matching one of the later ones.
+fn instructions() -> Vec<(&'static str, Vec<CleanArg>)> {
+    vec![
+        ("mov", vec![mem(Size::WORD, RegId::RBX), reg(Size::WORD, RegId::ES)]),
+        ("add", vec![mem(Size::QWORD, RegId::RSI), imm8(3)]),
+        ("cmp", vec![reg(Size::DWORD, RegId::RDX), mem(Size::DWORD, RegId::R8)]),
+        ("imul", vec![reg(Size::QWORD, RegId::RCX), reg(Size::QWORD, RegId::RDX), imm8(9)]),
+        ("movq", vec![reg(Size::QWORD, RegId::RAX), reg(Size::OWORD, RegId::XMM4)]),
+        ("shl", vec![reg(Size::DWORD, RegId::RAX), reg(Size::BYTE, RegId::RCX)]),
+    ]
+}
+
+fn bench_lookup(c: &mut Criterion) {
+    let arch = Archx64::default();
+    let instructions = instructions();
+
+    let mut group = c.benchmark_group("x64_lookup");
+    group.bench_function("mnemonic", |b| b.iter(|| {
+        for &name in MNEMONICS {
+            black_box(introspect::forms(black_box(name)).is_some());
+        }
+    }));
+    group.bench_function("register", |b| b.iter(|| {
+        for &name in REGISTERS {
+            black_box(parser::lookup_register(black_box(name), X86Mode::Long).is_ok());
+        }
+    }));
+    group.bench_function("match", |b| b.iter(|| {
+        let mut buf = [0; MAX_INSTRUCTION_LEN];
+        for (name, args) in &instructions {
+            black_box(arch.encode_static(&[name], black_box(args), &mut buf).unwrap());
+        }
+    }));
+    group.finish();
+}
+
+criterion_group!(benches, bench_lookup);
+criterion_main!(benches);
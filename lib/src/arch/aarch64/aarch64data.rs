@@ -1,41 +1,43 @@
 use crate::common::Size;
 use super::ast::Modifier;
+use super::semantics::{Semantics, describe};
 
 use lazy_static::lazy_static;
-use std::collections::{HashMap, hash_map};
+use crate::collections::{HashMap, hash_map};
 
+/// A description of a piece of an instruction's syntax, used to match its operands.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Matcher {
-    // a literal "."
+    /// a literal "."
     Dot,
 
-    // a specific literal (basically just an ident)
+    /// a specific literal (basically just an ident)
     Lit(&'static str),
 
-    // immediate literal
+    /// immediate literal
     LitInt(u32),
 
-    // float literal
+    /// float literal
     LitFloat(f32),
 
-    // a random ident
+    /// a random ident
     Ident,
 
-    // a condition code literal
+    /// a condition code literal
     Cond,
 
-    // immediate
+    /// immediate
     Imm,
 
-    // Wregisters, XRegisters, etc. match any static register in their family except for SP
+    /// Wregisters, XRegisters, etc. match any static register in their family except for SP
     W,
     X,
 
-    // same but addressing the stack pointer instead of the zero register. match any static register in their family except for ZR
+    /// same but addressing the stack pointer instead of the zero register. match any static register in their family except for ZR
     WSP,
     XSP,
 
-    // scalar simd regs
+    /// scalar simd regs
     B,
     H,
     S,
@@ -54,29 +56,29 @@ pub enum Matcher {
     /// vector register with elements of the specified size, with the specified lane count, with an element specifier
     VStaticElement(Size, u8),
 
-    // register list with .0 items, with the elements of size .1
+    /// register list with .0 items, with the elements of size .1
     RegList(u8, Size),
-    // register list with .0 items, with the elements of size .1 and a lane count of .2
+    /// register list with .0 items, with the elements of size .1 and a lane count of .2
     RegListStatic(u8, Size, u8),
-    // register list with element specifier. It has .0 items with a size of .1
+    /// register list with element specifier. It has .0 items with a size of .1
     RegListElement(u8, Size),
 
-    // jump offsets
+    /// jump offsets
     Offset,
 
-    // references
+    /// references
     RefBase,
     RefOffset,
     RefPre,
     RefIndex,
 
-    // a single modifier
+    /// a single modifier
     LitMod(Modifier),
 
-    // a set of allowed modifiers
+    /// a set of allowed modifiers
     Mod(&'static [Modifier]),
 
-    // possible op mnemnonic end (everything after this point uses the default encoding)
+    /// possible op mnemnonic end (everything after this point uses the default encoding)
     End,
 }
 
@@ -153,7 +155,9 @@ pub enum SpecialComm {
     SPLIT_FLOAT_IMMEDIATE,
 }
 
+// the names match the relocations in dynasmrt::aarch64
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum Relocation {
     // b, bl 26 bits, dword aligned
     B = 0,
@@ -189,11 +193,13 @@ pub struct Opdata {
     /// A set of matchers capable of matching the instruction encoding that this instruction represents.
     pub matchers: &'static [Matcher],
     /// A sequence of encoder commands that check the matched instruction on validity and whose output gets orred together with the original template at runtime.
-    pub commands: &'static [Command]
+    pub commands: &'static [Command],
+    /// The effects of the instruction on registers, memory and flags.
+    pub semantics: Semantics,
 }
 
 macro_rules! SingleOp {
-    ( $base:expr, [ $( $matcher:expr ),* ], [ $( $command:expr ),* ] ) => {
+    ( $name:tt, $base:expr, [ $( $matcher:expr ),* ], [ $( $command:expr ),* ] ) => {
         {
             const MATCHERS: &'static [Matcher] = {
                 #[allow(unused_imports)]
@@ -213,6 +219,7 @@ macro_rules! SingleOp {
                 base: $base,
                 matchers: MATCHERS,
                 commands: COMMANDS,
+                semantics: describe($name.as_bytes(), MATCHERS),
             }
         }
     }
@@ -224,7 +231,7 @@ macro_rules! Ops {
             (
                 $name,
                 &[ $(
-                    SingleOp!( $base, [ $( $matcher ),* ], [ $( $command ),* ] )
+                    SingleOp!( $name, $base, [ $( $matcher ),* ], [ $( $command ),* ] )
                 ),+ ] as &[_]
             )
         ),* ]
@@ -235,7 +242,6 @@ pub fn get_mnemonic_data(name: &str) -> Option<&'static [Opdata]> {
     OPMAP.get(&name).cloned()
 }
 
-#[allow(dead_code)]
 pub fn mnemnonics() -> hash_map::Keys<'static, &'static str, &'static [Opdata]> {
     OPMAP.keys()
 }
@@ -1,7 +1,7 @@
-use syn;
-use proc_macro2::Span;
+use crate::arch::ErrorSpan;
+use crate::common::{Expr, Ident, Size, Jump, Value};
 
-use crate::common::{Size, Jump};
+use alloc::vec::Vec;
 
 
 /// A complete abstraction of an aarch64 register access.
@@ -24,14 +24,14 @@ pub struct RegVector {
     pub kind: RegKind,
     pub element_size: Size,
     pub lanes: Option<u8>,
-    pub element: Option<syn::Expr>
+    pub element: Option<Value>
 }
 
 // Register id without indication of its usage. Either a static Regid or a family identifier + expression to choose the register
 #[derive(Debug, Clone)]
 pub enum RegKind {
     Static(RegId),
-    Dynamic(RegFamily, syn::Expr)
+    Dynamic(RegFamily, Expr)
 }
 
 // a register identifier. This identifies an architecturally completely separate register.
@@ -156,11 +156,7 @@ impl RegVector {
     /// Returns the full size of this vector register (element size * lanecount).
     /// Returns None if lanes was not set
     pub fn full_size(&self) -> Option<u16> {
-        if let Some(lanes) = self.lanes {
-            Some(u16::from(lanes) * u16::from(self.element_size.in_bytes()))
-        } else { 
-            None
-        }
+        self.lanes.map(|lanes| u16::from(lanes) * u16::from(self.element_size.in_bytes()))
     }
 }
 
@@ -263,11 +259,11 @@ impl Modifier {
 #[derive(Debug, Clone)]
 pub struct ModifyExpr {
     pub op: Modifier,
-    pub expr: Option<syn::Expr>
+    pub expr: Option<Value>
 }
 
 impl ModifyExpr {
-    pub fn new(op: Modifier, expr: Option<syn::Expr>) -> ModifyExpr {
+    pub fn new(op: Modifier, expr: Option<Value>) -> ModifyExpr {
         ModifyExpr {
             op,
             expr
@@ -279,52 +275,45 @@ impl ModifyExpr {
  * Memory ref item types
  */
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum RefItem {
     Direct {
-        span: Span,
         reg: Register
     },
     Immediate {
-        value: syn::Expr
+        value: Value
     },
     Modifier {
-        span: Span,
         modifier: ModifyExpr
     }
 }
 
 // basic parse results, before we start doing any kind of checking
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum RawArg {
     // A memory reference
     Reference {
-        span: Span,
         items: Vec<RefItem>,
         bang: bool
     },
     // A register list, defined as first - last
     DashList {
-        span: Span,
         first: Register,
         last: Register,
-        element: Option<syn::Expr>
+        element: Option<Value>
     },
     // A register list, defined as item, item, item, item
     CommaList{
-        span: Span,
         items: Vec<Register>,
-        element: Option<syn::Expr>
+        element: Option<Value>
     },
     AmountList {
-        span: Span,
         first: Register,
-        amount: syn::Expr,
-        element: Option<syn::Expr>
+        amount: Value,
+        element: Option<Value>
     },
     // direct register reference
     Direct {
-        span: Span,
         reg: Register
     },
     // jump target. Also used by PC-rel loads etc
@@ -334,72 +323,80 @@ pub enum RawArg {
     // just an arbitrary expression
     Immediate {
         prefixed: bool,
-        value: syn::Expr
+        value: Value
+    },
+    // a floating point literal. These can only be encoded statically.
+    FloatImmediate {
+        prefixed: bool,
+        value: f64
     },
     // a modifier
     Modifier {
-        span: Span,
         modifier: ModifyExpr
     },
     // a dot
-    Dot {
-        span: Span
-    },
+    Dot,
     // an ident, not intended to be parsed as an expression
     Lit {
-        ident: syn::Ident
+        ident: Ident
     }
 }
 
 // Contains the actual instruction mnemnonic.
 #[derive(Debug)]
 pub struct Instruction {
-    pub span: Span,
-    pub ident: syn::Ident
+    pub ident: Ident
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum RefKind {
     Base,
-    Offset(syn::Expr),
+    Offset(Value),
     Indexed(Register, Option<ModifyExpr>),
-    PreIndexed(syn::Expr),
+    PreIndexed(Value),
 }
 
 // sanitized parse results
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum CleanArg {
     Reference {
-        span: Span,
+        span: ErrorSpan,
         base: Register,
         kind: RefKind
     },
     RegList {
-        span: Span,
+        span: ErrorSpan,
         first: Register,
         amount: u8,
-        element: Option<syn::Expr>
+        element: Option<Value>
     },
     Direct {
-        span: Span,
+        span: ErrorSpan,
         reg: Register
     },
     JumpTarget {
         jump: Jump
     },
     Immediate {
+        span: ErrorSpan,
+        prefixed: bool,
+        value: Value,
+    },
+    FloatImmediate {
+        span: ErrorSpan,
         prefixed: bool,
-        value: syn::Expr,
+        value: f64,
     },
     Modifier {
-        span: Span,
+        span: ErrorSpan,
         modifier: ModifyExpr
     },
     Dot {
-        span: Span
+        span: ErrorSpan
     },
     Lit {
-        ident: syn::Ident
+        span: ErrorSpan,
+        ident: Ident
     }
 }
 
@@ -407,21 +404,27 @@ pub enum CleanArg {
 #[derive(Debug)]
 pub enum FlatArg {
     Direct {
-        span: Span,
+        span: ErrorSpan,
         reg: RegKind
     },
     Immediate {
-        value: syn::Expr,
+        span: ErrorSpan,
+        value: Value,
+    },
+    FloatImmediate {
+        span: ErrorSpan,
+        value: f64,
     },
     Modifier {
-        span: Span,
+        span: ErrorSpan,
         modifier: Modifier,
     },
     JumpTarget {
         jump: Jump
     },
     Lit {
-        ident: syn::Ident
+        span: ErrorSpan,
+        ident: Ident
     },
     Default
 }
@@ -1,22 +1,37 @@
-use super::matching::MatchData;
+use super::matching::{self, MatchData, as_number};
 use super::aarch64data::{Command, COND_MAP, SPECIAL_IDENT_MAP, SpecialComm, Relocation};
-use super::Context;
-use super::ast::{FlatArg, RegKind, RegId, Modifier};
+use super::{Context, Error};
+use super::ast::{Instruction, RawArg, CleanArg, FlatArg, Register, RegKind, RegId, RegFamily, Modifier};
 use super::encoding_helpers;
 
-use crate::common::{Stmt, Size, delimited, emit_error_at, bitmask};
-use crate::parse_helpers::{as_ident, as_number, as_float, as_signed_number};
+use crate::arch::{BasicExprBuilderExt, ErrorSpan};
+use crate::common::{Expr, Number, NumericRepr, Stmt, Size, Value, bitmask};
 
-use syn::spanned::Spanned;
-use quote::{quote, quote_spanned};
-use proc_macro2::TokenStream;
+use alloc::vec::Vec;
 
-pub(super) fn compile_instruction(ctx: &mut Context, data: MatchData) -> Result<(), Option<String>> {
+pub(super) fn compile_instruction(ctx: &mut Context, instruction: Instruction, args: Vec<RawArg>) -> Result<(), Error> {
+    // sanitize our arg list to remove any structures that cannot be matched on
+    let args = matching::sanitize_args(ctx, args)?;
+
+    // mov with a wide immediate is a pseudo-instruction that can expand to several instructions
+    if instruction.ident.name == "mov" {
+        if let [CleanArg::Direct { reg: Register::Scalar(dest), .. }, CleanArg::Immediate { span, value, .. }] = &args[..] {
+            if dest.kind.family() == RegFamily::INTEGER {
+                return compile_mov_immediate(ctx, &dest.kind, dest.size(), *span, *value);
+            }
+        }
+    }
+
+    let match_data = matching::match_instruction(ctx, &instruction, args)?;
+    encode_instruction(ctx, match_data)
+}
+
+pub(super) fn encode_instruction(ctx: &mut Context, data: MatchData) -> Result<(), Error> {
     let mut cursor = 0usize;
 
     // All static bitfields (compile-time constant) will be encoded into this map of (offset, bitfield)
     let mut statics = Vec::new();
-    // All dynamic bitfields (run-time determined) will be encoded into this map of (offset, TokenStream)
+    // All dynamic bitfields (run-time determined) will be encoded into this list of already shifted expressions
     let mut dynamics = Vec::new();
     // Any relocations will be encoded into this list
     let mut relocations = Vec::new();
@@ -49,22 +64,22 @@ pub(super) fn compile_instruction(ctx: &mut Context, data: MatchData) -> Result<
                 },
                 Command::REven(offset) => {
                     if id.code() & 1 != 0 {
-                        emit_error_at(span, "Field only supports even registers".into());
-                        return Err(None);
+                        ctx.state.emit_error_at(span, format_args!("Field only supports even registers"));
+                        return Err(Error::Fatal);
                     }
                     statics.push((offset, u32::from(id.code())));
                 },
                 Command::RNoZr(offset) => {
                     if id.code() == 31 {
-                        emit_error_at(span, "Field does not support register the zr/sp register".into());
-                        return Err(None);
+                        ctx.state.emit_error_at(span, format_args!("Field does not support register the zr/sp register"));
+                        return Err(Error::Fatal);
                     }
                     statics.push((offset, u32::from(id.code())));
                 },
                 Command::R4(offset) => {
                     if id.code() >= 16 {
-                        emit_error_at(span, "Field only supports register numbers 0-15".into());
-                        return Err(None);
+                        ctx.state.emit_error_at(span, format_args!("Field only supports register numbers 0-15"));
+                        return Err(Error::Fatal);
                     }
                     statics.push((offset, u32::from(id.code())));
                 },
@@ -72,12 +87,12 @@ pub(super) fn compile_instruction(ctx: &mut Context, data: MatchData) -> Result<
                     if let Some(FlatArg::Direct { span: _prevspan, reg: ref prevreg } ) = data.args.get(cursor - 1) {
                         match prevreg {
                             RegKind::Static(previd) => if id.code() != ((previd.code() + 1) % 32) {
-                                emit_error_at(span, "Invalid register. This register has to be the register after the previous argument.".into());
-                                return Err(None);
+                                ctx.state.emit_error_at(span, format_args!("Invalid register. This register has to be the register after the previous argument."));
+                                return Err(Error::Fatal);
                             },
                             RegKind::Dynamic(_, _) => if id != RegId::XZR {
-                                emit_error_at(span, "Please use XZR here to indicate that it should be the register after the previous argument.".into());
-                                return Err(None);
+                                ctx.state.emit_error_at(span, format_args!("Please use XZR here to indicate that it should be the register after the previous argument."));
+                                return Err(Error::Fatal);
                             }
                         }
                     } else {
@@ -86,26 +101,20 @@ pub(super) fn compile_instruction(ctx: &mut Context, data: MatchData) -> Result<
                 },
                 _ => panic!("Invalid argument processor")
             },
-            FlatArg::Direct { span, reg: RegKind::Dynamic(_, ref expr) } => match *command {
+            FlatArg::Direct { span, reg: RegKind::Dynamic(_, expr) } => match *command {
                 Command::R(offset)
                 | Command::RNoZr(offset) => {
-                    dynamics.push((offset, quote_spanned!{ span=>
-                        #expr & 0x1F
-                    }));
+                    dynamics.push(ctx.state.mask_shift_else_err(expr, 0x1F, offset as i8)?);
                 },
                 Command::REven(offset) => {
-                    dynamics.push((offset, quote_spanned!{ span=>
-                        #expr & 0x1E
-                    }));
+                    dynamics.push(ctx.state.mask_shift_else_err(expr, 0x1E, offset as i8)?);
                 },
                 Command::R4(offset) => {
-                    dynamics.push((offset, quote_spanned!{ span=>
-                        #expr & 0xF
-                    }));
+                    dynamics.push(ctx.state.mask_shift_else_err(expr, 0xF, offset as i8)?);
                 },
                 Command::RNext => {
-                    emit_error_at(span, "This register is constrained to be the register after the previous argument's register. As such, it does not support dynamic registers. Please substitute it with XZR to indicate this".into());
-                    return Err(None);
+                    ctx.state.emit_error_at(span, format_args!("This register is constrained to be the register after the previous argument's register. As such, it does not support dynamic registers. Please substitute it with XZR to indicate this"));
+                    return Err(Error::Fatal);
                 },
                 _ => panic!("Invalid argument processor")
             },
@@ -143,179 +152,159 @@ pub(super) fn compile_instruction(ctx: &mut Context, data: MatchData) -> Result<
                 },
                 _ => panic!("Invalid argument processor")
             },
-            FlatArg::Immediate { ref value } => match *command {
-
-                // Condition codes, literals
-                Command::Cond(offset) => {
-                    let name = as_ident(value).expect("bad command data").to_string();
-                    let bits = *COND_MAP.get(&&*name).expect("bad command data");
-                    statics.push((offset, u32::from(bits)))
-                },
-                Command::CondInv(offset) => {
-                    let name = as_ident(value).expect("bad command data").to_string();
-                    let bits = *COND_MAP.get(&&*name).expect("bad command data");
-                    statics.push((offset, u32::from(bits) ^ 1))
-                },
-                Command::LitList(offset, listname) => {
-                    let name = as_ident(value).expect("bad command data").to_string();
-                    let list = SPECIAL_IDENT_MAP.get(listname).expect("bad command data");
-                    if let Some(&bits) = list.get(&&*name) {
-                        statics.push((offset, bits));
-                    } else {
-                        emit_error_at(value.span(), "Unknown literal".into());
-                        return Err(None);
-                    }
-                },
+            FlatArg::Immediate { span, value } => match *command {
 
                 // unsigned integer encodings
                 Command::Ubits(offset, bitlen) => {
                     let mask = bitmask(bitlen);
-                    if let Some(value) = unsigned_rangecheck(value, 0, mask, 0) {
-                        statics.push((offset, value?));
-                    } else {
-                        dynamics.push((offset, quote_spanned!{ value.span()=>
-                            #value & #mask
-                        }));
+                    match value {
+                        Value::Number(number) => statics.push((offset, unsigned_rangecheck(ctx, span, number, 0, mask, 0)?)),
+                        Value::Expr(expr) => dynamics.push(dynamic_field(ctx, expr, mask, 0, offset)?),
                     }
                 },
                 Command::Uscaled(offset, bitlen, shift) => {
                     let mask = bitmask(bitlen);
-                    if let Some(value) = unsigned_rangecheck(value, 0, mask, shift) {
-                        statics.push((offset, value?));
-                    } else {
-                        dynamics.push((offset, quote_spanned!{ value.span()=>
-                            (#value >> #shift) & #mask
-                        }));
+                    match value {
+                        Value::Number(number) => statics.push((offset, unsigned_rangecheck(ctx, span, number, 0, mask, shift)?)),
+                        Value::Expr(expr) => dynamics.push(dynamic_field(ctx, expr, mask, shift, offset)?),
                     }
                 },
                 Command::Uslice(offset, bitlen, shift) => {
                     let mask = bitmask(bitlen);
-                    if let Some(value) = as_number(value) {
-                        statics.push((offset, ((value as u32) >> shift) & mask));
-                    } else {
-                        dynamics.push((offset, quote_spanned!{ value.span()=>
-                            (#value >> #shift) & #mask
-                        }));
+                    match value {
+                        Value::Number(number) => statics.push((offset, ((number.as_u64() >> shift) as u32) & mask)),
+                        Value::Expr(expr) => dynamics.push(dynamic_field(ctx, expr, mask, shift, offset)?),
                     }
                 },
                 Command::Ulist(offset, options) => {
-                    if let Some(number) = as_number(value) {
+                    if let Some(number) = as_number(&value) {
                         if let Some(i) = options.iter().rposition(|&n| u64::from(n) == number) {
                             statics.push((offset, i as u32));
                         } else {
-                            emit_error_at(value.span(), "Impossible value".into());
-                            return Err(None);
+                            ctx.state.emit_error_at(span, format_args!("Impossible value"));
+                            return Err(Error::Fatal);
                         }
                     } else {
-                        dynamics.push((offset, quote_spanned!{ value.span()=>
-                            [#(#options),*].iter().rposition(|&n| n as u32 == #value).expect("impossible value") as u32
-                        }));
+                        ctx.state.emit_error_at(span, format_args!("This immediate can only be one of a limited set of values and must be known statically"));
+                        return Err(Error::Fatal);
                     }
                 },
                 Command::Urange(offset, min, max) => {
                     let max = u32::from(max);
                     let min = u32::from(min);
-                    if let Some(value) = unsigned_rangecheck(value, min, max, 0) {
-                        statics.push((offset, value? - min));
-                    } else {
-                        let range = max - min;
-                        let mask = range.next_power_of_two() - 1;
-                        dynamics.push((offset, quote_spanned!{ value.span()=>
-                            (#value - #min) & #mask
-                        }));
+                    match value {
+                        Value::Number(number) => statics.push((offset, unsigned_rangecheck(ctx, span, number, min, max, 0)? - min)),
+                        Value::Expr(expr) => {
+                            let range = max - min;
+                            let mask = range.next_power_of_two() - 1;
+                            let expr = if min != 0 {
+                                ctx.state.add_else_err(expr, signed_value(-i64::from(min)))?
+                            } else {
+                                expr
+                            };
+                            dynamics.push(dynamic_field(ctx, expr, mask, 0, offset)?);
+                        }
                     }
                 },
                 Command::Usub(offset, bitlen, addval) => {
                     let mask = bitmask(bitlen);
                     let addval = u32::from(addval);
-                    if let Some(value) = unsigned_rangecheck(value, addval - mask, addval, 0) {
-                        statics.push((offset, addval - value?));
-                    } else {
-                        dynamics.push((offset, quote_spanned!{ value.span()=> 
-                            (#addval - #value) & #mask
-                        }));
+                    match value {
+                        Value::Number(number) => statics.push((offset, addval - unsigned_rangecheck(ctx, span, number, addval - mask, addval, 0)?)),
+                        Value::Expr(expr) => {
+                            // addval - value == !value + (addval + 1)
+                            let expr = ctx.state.neg_else_err(expr)?;
+                            let expr = ctx.state.add_else_err(expr, Value::Dword(addval + 1))?;
+                            dynamics.push(dynamic_field(ctx, expr, mask, 0, offset)?);
+                        }
                     }
                 },
                 Command::Unegmod(offset, bitlen) => {
                     let mask = bitmask(bitlen);
                     let addval = 1u32 << bitlen;
-                    if let Some(value) = unsigned_rangecheck(value, 0, mask, 0) {
-                        statics.push((offset, (addval - value?) & mask));
-                    } else {
-                        dynamics.push((offset, quote_spanned!{ value.span()=> 
-                            (#addval - #value) & #mask
-                        }));
+                    match value {
+                        Value::Number(number) => statics.push((offset, (addval - unsigned_rangecheck(ctx, span, number, 0, mask, 0)?) & mask)),
+                        Value::Expr(expr) => {
+                            // addval - value == !value + (addval + 1)
+                            let expr = ctx.state.neg_else_err(expr)?;
+                            let expr = ctx.state.add_else_err(expr, Value::Dword(addval + 1))?;
+                            dynamics.push(dynamic_field(ctx, expr, mask, 0, offset)?);
+                        }
                     }
                 },
                 Command::Usumdec(offset, bitlen) => {
                     let mask = bitmask(bitlen);
-                    if let Some(FlatArg::Immediate {value: leftvalue } ) = data.args.get(cursor - 1) {
-                        dynamics.push((offset, quote_spanned!{ value.span()=> 
-                            (#leftvalue + #value - 1) & #mask
-                        }));
+                    let leftvalue = if let Some(FlatArg::Immediate { value: leftvalue, .. } ) = data.args.get(cursor - 1) {
+                        *leftvalue
                     } else {
                         panic!("Bad encoding data, previous argument was not an immediate");
-                    }
+                    };
+
+                    let (expr, addend) = match (leftvalue, value) {
+                        (Value::Number(left), Value::Number(right)) => {
+                            let sum = left.as_u64().wrapping_add(right.as_u64()).wrapping_sub(1);
+                            statics.push((offset, (sum as u32) & mask));
+                            cursor += 1;
+                            continue;
+                        },
+                        (Value::Expr(left), Value::Expr(_)) => (ctx.state.add_else_err(left, value)?, -1),
+                        (Value::Expr(expr), Value::Number(number))
+                        | (Value::Number(number), Value::Expr(expr)) => (expr, number.as_i64() - 1),
+                    };
+                    let expr = ctx.state.add_else_err(expr, signed_value(addend))?;
+                    dynamics.push(dynamic_field(ctx, expr, mask, 0, offset)?);
                 },
                 Command::Ufields(bitfields) => {
                     let mask = bitmask(bitfields.len() as u8);
-                    if let Some(value) = unsigned_rangecheck(value, 0, mask, 0) {
-                        let value = value?;
-                        for (i, &field) in bitfields.iter().rev().enumerate() {
-                            statics.push((field as u8, (value >> i) & 1));
-                        }
-                    } else {
-                        for (i, &field) in bitfields.iter().rev().enumerate() {
-                            dynamics.push((field as u8, quote_spanned!{ value.span()=> 
-                                (#value >> #i) & 1
-                            }));
+                    match value {
+                        Value::Number(number) => {
+                            let value = unsigned_rangecheck(ctx, span, number, 0, mask, 0)?;
+                            for (i, &field) in bitfields.iter().rev().enumerate() {
+                                statics.push((field, (value >> i) & 1));
+                            }
+                        },
+                        Value::Expr(expr) => {
+                            for (i, &field) in bitfields.iter().rev().enumerate() {
+                                dynamics.push(dynamic_field(ctx, expr, 1, i as u8, field)?);
+                            }
                         }
                     }
                 },
 
-                // signed integer encoding
+                // signed integer encodings
                 Command::Sbits(offset, bitlen) => {
                     let mask = bitmask(bitlen);
                     let half = -1i32 << (bitlen - 1);
-                    if let Some(value) = signed_rangecheck(value, half, mask as i32 + half, 0) {
-                        statics.push((offset, (value? as u32) & mask));
-                    } else {
-                        dynamics.push((offset, quote_spanned!{ value.span()=>
-                            (#value as u32) & #mask
-                        }));
+                    match value {
+                        Value::Number(number) => statics.push((offset, (signed_rangecheck(ctx, span, number, half, mask as i32 + half, 0)? as u32) & mask)),
+                        Value::Expr(expr) => dynamics.push(dynamic_field(ctx, expr, mask, 0, offset)?),
                     }
                 },
                 Command::Sscaled(offset, bitlen, shift) => {
                     let mask = bitmask(bitlen);
                     let half = -1i32 << (bitlen - 1);
-                    if let Some(value) = signed_rangecheck(value, half, mask as i32 - half, shift) {
-                        statics.push((offset, (value? as u32) & mask));
-                    } else {
-                        dynamics.push((offset, quote_spanned!{ value.span()=>
-                            ((#value >> #shift) as u32) & #mask
-                        }));
+                    match value {
+                        Value::Number(number) => statics.push((offset, (signed_rangecheck(ctx, span, number, half, mask as i32 + half, shift)? as u32) & mask)),
+                        Value::Expr(expr) => dynamics.push(dynamic_field(ctx, expr, mask, shift, offset)?),
                     }
                 },
                 Command::Sslice(offset, bitlen, shift) => {
                     let mask = bitmask(bitlen);
-                    if let Some(value) = as_signed_number(value) {
-                        statics.push((offset, ((value >> shift) as u32) & mask));
-                    } else {
-                        dynamics.push((offset, quote_spanned!{ value.span()=>
-                            ((#value >> #shift) as u32) & #mask
-                        }));
+                    match value {
+                        Value::Number(number) => statics.push((offset, ((number.as_i64() >> shift) as u32) & mask)),
+                        Value::Expr(expr) => dynamics.push(dynamic_field(ctx, expr, mask, shift, offset)?),
                     }
                 },
 
                 // nonconsuming integer checks
                 Command::BUbits(bitlen) => {
                     let mask = bitmask(bitlen);
-                    if let Some(value) = unsigned_rangecheck(value, 0, mask, 0) {
-                        value?;
+                    if let Value::Number(number) = value {
+                        unsigned_rangecheck(ctx, span, number, 0, mask, 0)?;
                     }
                 },
                 Command::BUsum(bitlen) => {
-                    let prev = if let Some(FlatArg::Immediate {value: leftvalue } ) = data.args.get(cursor - 1) {
+                    let prev = if let Some(FlatArg::Immediate { value: leftvalue, .. } ) = data.args.get(cursor - 1) {
                         leftvalue
                     } else {
                         panic!("Bad encoding data, previous argument was not an immediate");
@@ -324,27 +313,27 @@ pub(super) fn compile_instruction(ctx: &mut Context, data: MatchData) -> Result<
                     if let Some(value) = as_number(prev) {
                         max -= value as u32;
                     }
-                    if let Some(value) = unsigned_rangecheck(value, 1, max, 0) {
-                        value?;
+                    if let Value::Number(number) = value {
+                        unsigned_rangecheck(ctx, span, number, 1, max, 0)?;
                     }
                 },
                 Command::BSscaled(bitlen, shift) => {
                     let mask = bitmask(bitlen);
                     let half = -1i32 << (bitlen - 1);
-                    if let Some(value) = signed_rangecheck(value, half, mask as i32 + half, shift) {
-                        value?;
+                    if let Value::Number(number) = value {
+                        signed_rangecheck(ctx, span, number, half, mask as i32 + half, shift)?;
                     }
                 },
                 Command::BUrange(min, max) => {
                     let min = u32::from(min);
                     let max = u32::from(max);
-                    if let Some(value) = unsigned_rangecheck(value, min, max, 0) {
-                        value?;
+                    if let Value::Number(number) = value {
+                        unsigned_rangecheck(ctx, span, number, min, max, 0)?;
                     }
                 },
 
                 // specials. These have some more involved code.
-                Command::Special(offset, special) => handle_special_immediates(offset, special, value, &mut statics, &mut dynamics)?,
+                Command::Special(offset, special) => handle_special_immediates(ctx, span, offset, special, value, &mut statics)?,
 
                 // jump targets also accept immediates
                 Command::Offset(relocation) => match relocation {
@@ -353,12 +342,9 @@ pub(super) fn compile_instruction(ctx: &mut Context, data: MatchData) -> Result<
                         let bits = 26;
                         let mask = bitmask(bits);
                         let half = -1i32 << (bits - 1);
-                        if let Some(value) = signed_rangecheck(value, half, mask as i32 + half, 2) {
-                            statics.push((0, (value? as u32) & mask));
-                        } else {
-                            dynamics.push((0, quote_spanned!{ value.span()=>
-                                ((#value >> 2) as u32) & #mask
-                            }));
+                        match value {
+                            Value::Number(number) => statics.push((0, (signed_rangecheck(ctx, span, number, half, mask as i32 + half, 2)? as u32) & mask)),
+                            Value::Expr(expr) => dynamics.push(dynamic_field(ctx, expr, mask, 2, 0)?),
                         }
                     },
                     // b.cond, cbnz, cbz, ldr, ldrsw, prfm: 19 bits, dword aligned
@@ -366,12 +352,9 @@ pub(super) fn compile_instruction(ctx: &mut Context, data: MatchData) -> Result<
                         let bits = 19;
                         let mask = bitmask(bits);
                         let half = -1i32 << (bits - 1);
-                        if let Some(value) = signed_rangecheck(value, half, mask as i32 + half, 2) {
-                            statics.push((5, (value? as u32) & mask));
-                        } else {
-                            dynamics.push((5, quote_spanned!{ value.span()=>
-                                ((#value >> 2) as u32) & #mask
-                            }));
+                        match value {
+                            Value::Number(number) => statics.push((5, (signed_rangecheck(ctx, span, number, half, mask as i32 + half, 2)? as u32) & mask)),
+                            Value::Expr(expr) => dynamics.push(dynamic_field(ctx, expr, mask, 2, 5)?),
                         }
                     },
                     // adr split 21 bit, byte aligned
@@ -379,17 +362,16 @@ pub(super) fn compile_instruction(ctx: &mut Context, data: MatchData) -> Result<
                         let bits = 21;
                         let mask = bitmask(bits);
                         let half = -1i32 << (bits - 1);
-                        if let Some(value) = signed_rangecheck(value, half, mask as i32 + half, 0) {
-                            let value = value?;
-                            statics.push((5, ((value >> 2) as u32) & 0x7FFFF));
-                            statics.push((29, (value as u32) & 3));
-                        } else {
-                            dynamics.push((5, quote_spanned!{ value.span()=>
-                                ((#value >> 2) as u32) & 0x7FFFF
-                            }));
-                            dynamics.push((29, quote_spanned!{ value.span()=>
-                                (#value as u32) & 3
-                            }));
+                        match value {
+                            Value::Number(number) => {
+                                let value = signed_rangecheck(ctx, span, number, half, mask as i32 + half, 0)?;
+                                statics.push((5, ((value >> 2) as u32) & 0x7FFFF));
+                                statics.push((29, (value as u32) & 3));
+                            },
+                            Value::Expr(expr) => {
+                                dynamics.push(dynamic_field(ctx, expr, 0x7FFFF, 2, 5)?);
+                                dynamics.push(dynamic_field(ctx, expr, 3, 0, 29)?);
+                            }
                         }
                     },
                     // adrp split 21 bit, 4096-byte aligned
@@ -397,17 +379,16 @@ pub(super) fn compile_instruction(ctx: &mut Context, data: MatchData) -> Result<
                         let bits = 21;
                         let mask = bitmask(bits);
                         let half = -1i32 << (bits - 1);
-                        if let Some(value) = signed_rangecheck(value, half, mask as i32 + half, 12) {
-                            let value = value?;
-                            statics.push((5, ((value >> 2) as u32) & 0x7FFFF));
-                            statics.push((29, (value as u32) & 3));
-                        } else {
-                            dynamics.push((5, quote_spanned!{ value.span()=>
-                                ((#value >> 14) as u32) & 0x7FFFF
-                            }));
-                            dynamics.push((29, quote_spanned!{ value.span()=>
-                                ((#value >> 12) as u32) & 3
-                            }));
+                        match value {
+                            Value::Number(number) => {
+                                let value = signed_rangecheck(ctx, span, number, half, mask as i32 + half, 12)?;
+                                statics.push((5, ((value >> 2) as u32) & 0x7FFFF));
+                                statics.push((29, (value as u32) & 3));
+                            },
+                            Value::Expr(expr) => {
+                                dynamics.push(dynamic_field(ctx, expr, 0x7FFFF, 14, 5)?);
+                                dynamics.push(dynamic_field(ctx, expr, 3, 12, 29)?);
+                            }
                         }
                     },
                     // tbnz, tbz: 14 bits, dword aligned
@@ -415,12 +396,9 @@ pub(super) fn compile_instruction(ctx: &mut Context, data: MatchData) -> Result<
                         let bits = 14;
                         let mask = bitmask(bits);
                         let half = -1i32 << (bits - 1);
-                        if let Some(value) = signed_rangecheck(value, half, mask as i32 + half, 2) {
-                            statics.push((5, (value? as u32) & mask));
-                        } else {
-                            dynamics.push((5, quote_spanned!{ value.span()=>
-                                ((#value >> 2) as u32) & #mask
-                            }));
+                        match value {
+                            Value::Number(number) => statics.push((5, (signed_rangecheck(ctx, span, number, half, mask as i32 + half, 2)? as u32) & mask)),
+                            Value::Expr(expr) => dynamics.push(dynamic_field(ctx, expr, mask, 2, 5)?),
                         }
                     },
                     Relocation::LITERAL8
@@ -431,6 +409,10 @@ pub(super) fn compile_instruction(ctx: &mut Context, data: MatchData) -> Result<
 
                 _ => panic!("Invalid argument processor")
             },
+            FlatArg::FloatImmediate { span, value } => match *command {
+                Command::Special(offset, special) => handle_special_float(ctx, span, offset, special, value, &mut statics)?,
+                _ => panic!("Invalid argument processor")
+            },
             FlatArg::Default => match *command {
                 // Registers default to R31
                 Command::R(offset) => {
@@ -478,27 +460,24 @@ pub(super) fn compile_instruction(ctx: &mut Context, data: MatchData) -> Result<
                 },
                 _ => panic!("Invalid argument processor")
             },
-            FlatArg::Lit { ref ident } => match *command {
+            FlatArg::Lit { span, ref ident } => match *command {
 
                 // Condition codes, literals
                 Command::Cond(offset) => {
-                    let name = ident.to_string();
-                    let bits = *COND_MAP.get(&&*name).expect("bad command data");
+                    let bits = *COND_MAP.get(&*ident.name).expect("bad command data");
                     statics.push((offset, u32::from(bits)))
                 },
                 Command::CondInv(offset) => {
-                    let name = ident.to_string();
-                    let bits = *COND_MAP.get(&&*name).expect("bad command data");
+                    let bits = *COND_MAP.get(&*ident.name).expect("bad command data");
                     statics.push((offset, u32::from(bits) ^ 1))
                 },
                 Command::LitList(offset, listname) => {
-                    let name = ident.to_string();
                     let list = SPECIAL_IDENT_MAP.get(listname).expect("bad command data");
-                    if let Some(&bits) = list.get(&&*name) {
+                    if let Some(&bits) = list.get(&*ident.name) {
                         statics.push((offset, bits));
                     } else {
-                        emit_error_at(ident.span(), "Unknown literal".into());
-                        return Err(None);
+                        ctx.state.emit_error_at(span, format_args!("Unknown literal"));
+                        return Err(Error::Fatal);
                     }
                 },
                 _ => panic!("Invalid argument processor")
@@ -528,206 +507,299 @@ pub(super) fn compile_instruction(ctx: &mut Context, data: MatchData) -> Result<
         bits |= value << offset;
     }
 
-    // generate code to be emitted for dynamics
-    if !dynamics.is_empty() {
-        let mut res = quote!{
-            #bits
-        };
-        for (offset, expr) in dynamics {
-            res = quote!{
-                #res | ((#expr) << #offset)
-            };
-        }
-        ctx.state.stmts.push(Stmt::ExprUnsigned(delimited(res), Size::DWORD));
-    } else {
-        ctx.state.stmts.push(Stmt::Const(u64::from(bits), Size::DWORD));
-    }
+    emit_word(ctx, bits, dynamics)?;
 
     // generate code to be emitted for relocations
-    ctx.state.stmts.extend(relocations);
+    for stmt in relocations {
+        ctx.state.push(stmt);
+    }
 
     Ok(())
 }
 
-fn handle_special_immediates(offset: u8, special: SpecialComm, imm: &syn::Expr, statics: &mut Vec<(u8, u32)>, dynamics: &mut Vec<(u8, TokenStream)>) -> Result<(), Option<String>> {
+/// Emits a single instruction word, combining the static bits with any dynamic bitfields.
+fn emit_word(ctx: &mut Context, bits: u32, dynamics: Vec<Expr>) -> Result<(), Error> {
+    let mut dynamics = dynamics.into_iter();
+
+    let mut expr = if let Some(expr) = dynamics.next() {
+        expr
+    } else {
+        ctx.state.push(Stmt::Const(Value::Dword(bits)));
+        return Ok(());
+    };
+
+    for field in dynamics {
+        expr = ctx.state.bit_or_else_err(expr, field.into())?;
+    }
+    if bits != 0 {
+        expr = ctx.state.bit_or_else_err(expr, Value::Dword(bits))?;
+    }
+
+    expr.repr = NumericRepr::U32;
+    ctx.state.push(Stmt::Const(Value::Expr(expr)));
+    Ok(())
+}
+
+/// Builds the bitfield `((value >> shift) & mask) << offset` from a dynamic value.
+fn dynamic_field(ctx: &mut Context, value: Expr, mask: u32, shift: u8, offset: u8) -> Result<Expr, Error> {
+    let mask = u64::from(mask) << shift;
+    Ok(ctx.state.mask_shift_else_err(value, mask, offset as i8 - shift as i8)?)
+}
+
+/// A signed 64-bit constant, used as an addend to dynamic values.
+fn signed_value(value: i64) -> Value {
+    Value::Number(Number::from_u64_and_repr(value as u64, NumericRepr::I64))
+}
+
+/// Interprets a number as a 32-bit value, accepting both signed and unsigned representations.
+fn as_u32_number(number: Number) -> Option<u32> {
+    number.convert(NumericRepr::U32)
+        .or_else(|| number.convert(NumericRepr::I32))
+        .map(Number::as_u32)
+}
+
+/// Interprets a number as a 64-bit value, accepting both signed and unsigned representations.
+fn as_u64_number(number: Number) -> Option<u64> {
+    number.convert(NumericRepr::U64)
+        .or_else(|| number.convert(NumericRepr::I64))
+        .map(Number::as_u64)
+}
+
+fn handle_special_immediates(ctx: &mut Context, span: ErrorSpan, offset: u8, special: SpecialComm, imm: Value, statics: &mut Vec<(u8, u32)>) -> Result<(), Error> {
+    let number = match imm {
+        Value::Number(number) => number,
+        Value::Expr(_) => {
+            ctx.state.emit_error_at(span, format_args!("This immediate cannot be encoded dynamically"));
+            return Err(Error::Fatal);
+        }
+    };
+
     match special {
-        SpecialComm::INVERTED_WIDE_IMMEDIATE_X => if let Some(number) = as_number(imm) {
+        SpecialComm::INVERTED_WIDE_IMMEDIATE_X => if let Some(number) = as_u64_number(number) {
             if let Some(encoded) = encoding_helpers::encode_wide_immediate_64bit(!number) {
-                statics.push((offset, encoded as u32));
+                statics.push((offset, encoded));
                 return Ok(());
             }
-        } else {
-            dynamics.push((offset, quote_spanned!{ imm.span()=>
-                {
-                    let value: u64 = !#imm;
-                    let offset = value.trailing_zeros() & 0b110000;
-                    ((0xFFFFu64 & (value >> offset)) as u32) | (offset << 12)
-                }
-            }));
-            return Ok(());
         },
-        SpecialComm::INVERTED_WIDE_IMMEDIATE_W => if let Some(number) = as_number(imm) {
-            if number <= u64::from(std::u32::MAX) {
-                if let Some(encoded) = encoding_helpers::encode_wide_immediate_32bit(!(number as u32)) {
-                    statics.push((offset, encoded as u32));
-                    return Ok(());
-                }
+        SpecialComm::INVERTED_WIDE_IMMEDIATE_W => if let Some(number) = as_u32_number(number) {
+            if let Some(encoded) = encoding_helpers::encode_wide_immediate_32bit(!number) {
+                statics.push((offset, encoded));
+                return Ok(());
             }
-        } else {
-            dynamics.push((offset, quote_spanned!{ imm.span()=>
-                {
-                    let value: u64 = !#imm;
-                    let offset = value.trailing_zeros() & 0b10000;
-                    ((0xFFFFu64 & (value >> offset)) as u32) | (offset << 12)
-                }
-            }));
-            return Ok(());
         },
-        SpecialComm::WIDE_IMMEDIATE_X => if let Some(number) = as_number(imm) {
+        SpecialComm::WIDE_IMMEDIATE_X => if let Some(number) = as_u64_number(number) {
             if let Some(encoded) = encoding_helpers::encode_wide_immediate_64bit(number) {
-                statics.push((offset, encoded as u32));
+                statics.push((offset, encoded));
                 return Ok(());
             }
-        } else {
-            dynamics.push((offset, quote_spanned!{ imm.span()=>
-                {
-                    let value: u64 = #imm;
-                    let offset = value.trailing_zeros() & 0b110000;
-                    ((0xFFFFu64 & (value >> offset)) as u32) | (offset << 12)
-                }
-            }));
-            return Ok(());
         },
-        SpecialComm::WIDE_IMMEDIATE_W => if let Some(number) = as_number(imm) {
-            if number <= u64::from(std::u32::MAX) {
-                if let Some(encoded) = encoding_helpers::encode_wide_immediate_32bit(number as u32) {
-                    statics.push((offset, encoded as u32));
-                    return Ok(());
-                }
+        SpecialComm::WIDE_IMMEDIATE_W => if let Some(number) = as_u32_number(number) {
+            if let Some(encoded) = encoding_helpers::encode_wide_immediate_32bit(number) {
+                statics.push((offset, encoded));
+                return Ok(());
             }
-        } else {
-            dynamics.push((offset, quote_spanned!{ imm.span()=>
-                {
-                    let value: u64 = #imm;
-                    let offset = value.trailing_zeros() & 0b10000;
-                    ((0xFFFFu64 & (value >> offset)) as u32) | (offset << 12)
-                }
-            }));
-            return Ok(());
         },
-        SpecialComm::STRETCHED_IMMEDIATE => if let Some(number) = as_number(imm) {
+        SpecialComm::STRETCHED_IMMEDIATE => if let Some(number) = as_u64_number(number) {
             if let Some(encoded) = encoding_helpers::encode_stretched_immediate(number) {
-                statics.push((offset, encoded & 0x1F as u32));
-                statics.push((offset + 6, encoded & 0xE0 as u32));
+                statics.push((offset, encoded & 0x1F));
+                statics.push((offset + 6, encoded & 0xE0));
                 return Ok(());
             }
-        } else {
-            dynamics.push((offset, quote_spanned!{ imm.span()=>
-                {
-                    let value: u64 = #imm;
-                    let mut masked = value & 0x8040201008040201;
-                    masked |= masked >> 32;
-                    masked |= masked >> 16;
-                    masked |= masked >> 8;
-                    let masked = masked as u32;
-                    ((masked & 0xE0) << 6) | (masked & 0x1F) 
-                }
-            }));
-            return Ok(());
-        },
-        SpecialComm::LOGICAL_IMMEDIATE_W => if let Some(number) = as_number(imm) {
-            if number <= u64::from(std::u32::MAX) {
-                if let Some(encoded) = encoding_helpers::encode_logical_immediate_32bit(number as u32) {
-                    statics.push((offset, u32::from(encoded)));
-                    return Ok(());
-                }
-            }
-        } else {
-            dynamics.push((offset, quote_spanned!{ imm.span()=>
-                dynasmrt::aarch64::encode_logical_immediate_32bit(#imm).expect("Impossible logical immediate") as u32
-            }));
-            return Ok(());
         },
-        SpecialComm::LOGICAL_IMMEDIATE_X => if let Some(number) = as_number(imm) {
-            if let Some(encoded) = encoding_helpers::encode_logical_immediate_64bit(number) {
+        SpecialComm::LOGICAL_IMMEDIATE_W => if let Some(number) = as_u32_number(number) {
+            if let Some(encoded) = encoding_helpers::encode_logical_immediate_32bit(number) {
                 statics.push((offset, u32::from(encoded)));
                 return Ok(());
             }
-        } else {
-            dynamics.push((offset, quote_spanned!{ imm.span()=>
-                dynasmrt::aarch64::encode_logical_immediate_64bit(#imm).expect("Impossible logical immediate") as u32
-            }));
-            return Ok(());
         },
-        SpecialComm::FLOAT_IMMEDIATE => if let Some(number) = as_float(imm) {
-            if let Some(encoded) = encoding_helpers::encode_floating_point_immediate(number as f32) {
+        SpecialComm::LOGICAL_IMMEDIATE_X => if let Some(number) = as_u64_number(number) {
+            if let Some(encoded) = encoding_helpers::encode_logical_immediate_64bit(number) {
                 statics.push((offset, u32::from(encoded)));
                 return Ok(());
             }
-        } else {
-            dynamics.push((offset, quote_spanned!{ imm.span()=>
-                {
-                    let value: f32 = #imm;
-                    let bits = value.to_bits();
-                    ((bits >> 24) & 0x80) | ((bits >> 19) & 0x7F)
-                }
-            }));
+        },
+        SpecialComm::FLOAT_IMMEDIATE
+        | SpecialComm::SPLIT_FLOAT_IMMEDIATE => {
+            ctx.state.emit_error_at(span, format_args!("Expected a floating point literal"));
+            return Err(Error::Fatal);
+        }
+    }
+
+    ctx.state.emit_error_at(span, format_args!("Impossible to encode immediate"));
+    Err(Error::Fatal)
+}
+
+fn handle_special_float(ctx: &mut Context, span: ErrorSpan, offset: u8, special: SpecialComm, imm: f64, statics: &mut Vec<(u8, u32)>) -> Result<(), Error> {
+    match special {
+        SpecialComm::FLOAT_IMMEDIATE => if let Some(encoded) = encoding_helpers::encode_floating_point_immediate(imm as f32) {
+            statics.push((offset, u32::from(encoded)));
             return Ok(());
         },
-        SpecialComm::SPLIT_FLOAT_IMMEDIATE => if let Some(number) = as_float(imm) {
-            if let Some(encoded) = encoding_helpers::encode_floating_point_immediate(number as f32) {
-                statics.push((offset, u32::from(encoded & 0x1F)));
-                statics.push((offset + 6, u32::from(encoded & 0xE0)));
-                return Ok(());
-            }
-        } else {
-            dynamics.push((offset, quote_spanned!{ imm.span()=>
-                {
-                    let value: f32 = #imm;
-                    let bits = value.to_bits();
-                    ((bits >> 18) & 0x80) | ((bits >> 13) & 0x60) | ((bits >> 19) & 0x1F)
-                }
-            }));
+        SpecialComm::SPLIT_FLOAT_IMMEDIATE => if let Some(encoded) = encoding_helpers::encode_floating_point_immediate(imm as f32) {
+            statics.push((offset, u32::from(encoded & 0x1F)));
+            statics.push((offset + 6, u32::from(encoded & 0xE0)));
             return Ok(());
         },
+        _ => panic!("Invalid argument processor")
     }
 
-    emit_error_at(imm.span(), "Impossible to encode immediate".into());
-    Err(None)
+    ctx.state.emit_error_at(span, format_args!("Impossible to encode immediate"));
+    Err(Error::Fatal)
 }
 
-fn unsigned_rangecheck(expr: &syn::Expr, min: u32, max: u32, scale: u8) -> Option<Result<u32, Option<String>>> {
-    let value = as_number(expr)?;
+fn unsigned_rangecheck(ctx: &mut Context, span: ErrorSpan, number: Number, min: u32, max: u32, scale: u8) -> Result<u32, Error> {
+    let value = number.as_u64();
     let scaled = value >> scale;
 
-    Some(if (scaled << scale) != value {
-        emit_error_at(expr.span(), "Unrepresentable value".into());
-        Err(None)
+    if (scaled << scale) != value {
+        ctx.state.emit_error_at(span, format_args!("Unrepresentable value"));
+        Err(Error::Fatal)
     } else if scaled > u64::from(max) {
-        emit_error_at(expr.span(), "Value too large".into());
-        Err(None)
+        ctx.state.emit_error_at(span, format_args!("Value too large"));
+        Err(Error::Fatal)
     } else if scaled < u64::from(min) {
-        emit_error_at(expr.span(), "Value too small".into());
-        Err(None)
+        ctx.state.emit_error_at(span, format_args!("Value too small"));
+        Err(Error::Fatal)
     } else {
         Ok(scaled as u32)
-    })
+    }
 }
 
-fn signed_rangecheck(expr: &syn::Expr, min: i32, max: i32, scale: u8) -> Option<Result<i32, Option<String>>> {
-    let value = as_signed_number(expr)?;
+fn signed_rangecheck(ctx: &mut Context, span: ErrorSpan, number: Number, min: i32, max: i32, scale: u8) -> Result<i32, Error> {
+    let value = number.as_i64();
     let scaled = value >> scale;
 
-    Some(if (scaled << scale) != value {
-        emit_error_at(expr.span(), "Unrepresentable value".into());
-        Err(None)
+    if (scaled << scale) != value {
+        ctx.state.emit_error_at(span, format_args!("Unrepresentable value"));
+        Err(Error::Fatal)
     } else if scaled > i64::from(max) {
-        emit_error_at(expr.span(), "Value too large".into());
-        Err(None)
+        ctx.state.emit_error_at(span, format_args!("Value too large"));
+        Err(Error::Fatal)
     } else if scaled < i64::from(min) {
-        emit_error_at(expr.span(), "Value too small".into());
-        Err(None)
+        ctx.state.emit_error_at(span, format_args!("Value too small"));
+        Err(Error::Fatal)
     } else {
         Ok(scaled as i32)
-    })
+    }
+}
+
+/*
+ * The mov pseudo-instruction
+ */
+
+const MOVN_X: u32 = 0x9280_0000;
+const MOVZ_X: u32 = 0xD280_0000;
+const MOVK_X: u32 = 0xF280_0000;
+const ORR_X:  u32 = 0xB200_03E0;
+const MOVN_W: u32 = 0x1280_0000;
+const MOVZ_W: u32 = 0x5280_0000;
+const MOVK_W: u32 = 0x7280_0000;
+const ORR_W:  u32 = 0x3200_03E0;
+
+/// Loads an arbitrary immediate into a general purpose register.
+///
+/// Static values are materialized with the shortest sequence of movz/movn/orr/movk
+/// instructions. Dynamic values always use a movz followed by a movk for every remaining
+/// halfword of the register, as their value cannot be inspected.
+fn compile_mov_immediate(ctx: &mut Context, dest: &RegKind, size: Size, span: ErrorSpan, value: Value) -> Result<(), Error> {
+    let wide = size == Size::QWORD;
+    let (movn, movz, movk, orr) = if wide {
+        (MOVN_X, MOVZ_X, MOVK_X, ORR_X)
+    } else {
+        (MOVN_W, MOVZ_W, MOVK_W, ORR_W)
+    };
+    let halfwords = if wide { 4 } else { 2 };
+
+    let (rd, rd_dynamic) = match *dest {
+        RegKind::Static(id) => (u32::from(id.code()), None),
+        RegKind::Dynamic(_, expr) => (0, Some(expr)),
+    };
+
+    let emit = |ctx: &mut Context, bits: u32| -> Result<(), Error> {
+        let mut dynamics = Vec::new();
+        if let Some(expr) = rd_dynamic {
+            dynamics.push(ctx.state.mask_shift_else_err(expr, 0x1F, 0)?);
+        }
+        emit_word(ctx, bits | rd, dynamics)
+    };
+
+    let expr = match value {
+        Value::Expr(expr) => expr,
+        Value::Number(number) => {
+            let number = if wide { as_u64_number(number) } else { as_u32_number(number).map(u64::from) };
+            let number = match number {
+                Some(number) => number,
+                None => {
+                    ctx.state.emit_error_at(span, format_args!("Immediate does not fit in the destination register"));
+                    return Err(Error::Fatal);
+                }
+            };
+            let inverted = if wide { !number } else { u64::from(!(number as u32)) };
+
+            // single instruction forms
+            if let Some(encoded) = encode_wide_immediate(number, wide) {
+                return emit(ctx, movz | (encoded << 5));
+            }
+            if let Some(encoded) = encode_wide_immediate(inverted, wide) {
+                return emit(ctx, movn | (encoded << 5));
+            }
+            // orr treats register 31 as sp, so it is not usable for xzr or unknown registers.
+            if rd_dynamic.is_none() && rd != 31 {
+                let encoded = if wide {
+                    encoding_helpers::encode_logical_immediate_64bit(number)
+                } else {
+                    encoding_helpers::encode_logical_immediate_32bit(number as u32)
+                };
+                if let Some(encoded) = encoded {
+                    return emit(ctx, orr | (u32::from(encoded) << 10));
+                }
+            }
+
+            // pick whichever of movz or movn leaves less halfwords to be patched up by movk.
+            let halfword = |i: u32| ((number >> (16 * i)) & 0xFFFF) as u32;
+            let zeros = (0..halfwords).filter(|&i| halfword(i) == 0).count();
+            let ones = (0..halfwords).filter(|&i| halfword(i) == 0xFFFF).count();
+            let (first, skip) = if ones > zeros { (movn, 0xFFFF) } else { (movz, 0) };
+
+            let mut first_emitted = false;
+            for i in 0..halfwords {
+                let imm16 = halfword(i);
+                if imm16 == skip {
+                    continue;
+                }
+
+                let bits = (i << 21) | if first_emitted {
+                    movk | (imm16 << 5)
+                } else if first == movn {
+                    movn | ((!imm16 & 0xFFFF) << 5)
+                } else {
+                    movz | (imm16 << 5)
+                };
+                first_emitted = true;
+                emit(ctx, bits)?;
+            }
+            return Ok(());
+        }
+    };
+
+    for i in 0..halfwords {
+        let base = if i == 0 { movz } else { movk | (i << 21) };
+        let mut dynamics = Vec::new();
+        if let Some(expr) = rd_dynamic {
+            dynamics.push(ctx.state.mask_shift_else_err(expr, 0x1F, 0)?);
+        }
+        dynamics.push(ctx.state.mask_shift_else_err(expr, 0xFFFF << (16 * i), 5 - 16 * i as i8)?);
+        emit_word(ctx, base | rd, dynamics)?;
+    }
+
+    Ok(())
+}
+
+/// Encodes a value as a 16-bit immediate and halfword shift, in the layout used by movz/movn.
+fn encode_wide_immediate(value: u64, wide: bool) -> Option<u32> {
+    if wide {
+        encoding_helpers::encode_wide_immediate_64bit(value)
+    } else {
+        encoding_helpers::encode_wide_immediate_32bit(value as u32)
+    }
 }
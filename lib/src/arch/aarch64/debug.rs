@@ -2,7 +2,11 @@ use super::ast::Modifier;
 use super::aarch64data::{Opdata, Matcher, Command, Relocation, SpecialComm};
 use crate::common::Size;
 
-use std::fmt::Write;
+use core::fmt::Write;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::format;
 
 
 #[cfg(feature = "dynasm_opmap")]
@@ -16,9 +20,8 @@ pub fn create_opmap() -> String {
         // get the data for this mnemnonic
         let data = super::aarch64data::get_mnemonic_data(mnemnonic).unwrap();
         // format the data for the opmap docs
-        let formats = data.into_iter()
-            .map(|x| format_opdata(mnemnonic, x))
-            .flat_map(|x| x)
+        let formats = data.iter()
+            .flat_map(|x| format_opdata(mnemnonic, x))
             .map(|x| x.replace(">>> ", ""))
             .collect::<Vec<_>>();
 
@@ -41,9 +44,8 @@ pub fn extract_opmap() -> String {
         let data = super::aarch64data::get_mnemonic_data(mnemnonic).unwrap();
 
         buf.extend(
-            data.into_iter()
-            .map(|x| extract_opdata(mnemnonic, x))
-            .flat_map(|x| x)
+            data.iter()
+            .flat_map(|x| extract_opdata(mnemnonic, x))
         );
     }
 
@@ -63,10 +65,7 @@ pub fn format_opdata_list(name: &str, data: &[Opdata]) -> String {
 
 pub fn format_opdata(name: &str, data: &Opdata) -> Vec<String> {
 
-    let has_simd_full_width = data.matchers.iter().any(|m| match m {
-        Matcher::V(_) | Matcher::RegList(_, _) => true,
-        _ => false
-    });
+    let has_simd_full_width = data.matchers.iter().any(|m| matches!(m, Matcher::V(_) | Matcher::RegList(_, _)));
 
     let form_count = 1 + has_simd_full_width as u8;
     let mut forms = Vec::new();
@@ -91,13 +90,13 @@ pub fn format_opdata(name: &str, data: &Opdata) -> Vec<String> {
                 continue;
             } else if let Matcher::Dot = matcher {
                 after_dot = true;
-                buf.push_str(".");
+                buf.push('.');
                 continue;
             }
 
             if first {
                 if !after_dot {
-                    buf.push_str(" ");
+                    buf.push(' ');
                     first = false;
                 }
                 after_dot = false;
@@ -625,10 +624,7 @@ fn emit_constraints(name: &str, prevname: &str, commands: &[Command], buf: &mut
 #[cfg(feature = "dynasm_extract")]
 pub fn extract_opdata(name: &str, data: &Opdata) -> Vec<String> {
 
-    let has_simd_full_width = data.matchers.iter().any(|m| match m {
-        Matcher::V(_) | Matcher::RegList(_, _) => true,
-        _ => false
-    });
+    let has_simd_full_width = data.matchers.iter().any(|m| matches!(m, Matcher::V(_) | Matcher::RegList(_, _)));
 
     let form_count = 1 + has_simd_full_width as u8;
     let mut forms = Vec::new();
@@ -651,13 +647,13 @@ pub fn extract_opdata(name: &str, data: &Opdata) -> Vec<String> {
                 continue;
             } else if let Matcher::Dot = matcher {
                 after_dot = true;
-                buf.push_str(".");
+                buf.push('.');
                 continue;
             }
 
             if first {
                 if !after_dot {
-                    buf.push_str(" ");
+                    buf.push(' ');
                     first = false;
                 }
                 after_dot = false;
@@ -754,11 +750,11 @@ fn extract_constraints(args: &[ArgWithCommands]) -> Vec<String> {
     for (i, arg) in args.iter().enumerate() {
         for command in &arg.commands {
             let constraint = match command {
-                Command::R(_) => format!("R(32)"),
-                Command::REven(_) => format!("R(32, 2)"),
-                Command::RNoZr(_) => format!("R(31)"),
-                Command::R4(_) => format!("R(16)"),
-                Command::RNext => format!("RNext()"),
+                Command::R(_) => "R(32)".to_string(),
+                Command::REven(_) => "R(32, 2)".to_string(),
+                Command::RNoZr(_) => "R(31)".to_string(),
+                Command::R4(_) => "R(16)".to_string(),
+                Command::RNext => "RNext()".to_string(),
                 Command::Ubits(_, bits)
                 | Command::BUbits(bits) => format!("Range(0, {}, 1)", 1u32 << bits),
                 Command::Uscaled(_, bits, scale) => format!("Range(0, {}, {})", 1u32 << (bits + scale), 1u32 << scale),
@@ -776,15 +772,15 @@ fn extract_constraints(args: &[ArgWithCommands]) -> Vec<String> {
                 Command::Sbits(_, bits) => format!("Range(-{}, {}, 1)", 1u32 << (bits - 1), 1u32 << (bits - 1)),
                 Command::Sscaled(_, bits, scale)
                 | Command::BSscaled(bits, scale) => format!("Range(-{}, {}, {})", 1u32 << (bits + scale - 1), 1u32 << (bits + scale - 1), 1u32 << scale),
-                Command::Special(_, SpecialComm::WIDE_IMMEDIATE_W) => format!("Special('wide_w')"),
-                | Command::Special(_, SpecialComm::WIDE_IMMEDIATE_X) => format!("Special('wide_x')"),
-                | Command::Special(_, SpecialComm::INVERTED_WIDE_IMMEDIATE_W) => format!("Special('inverted_w')"),
-                | Command::Special(_, SpecialComm::INVERTED_WIDE_IMMEDIATE_X) => format!("Special('inverted_x')"),
-                Command::Special(_, SpecialComm::LOGICAL_IMMEDIATE_W) => format!("Special('logical_w')"),
-                | Command::Special(_, SpecialComm::LOGICAL_IMMEDIATE_X) => format!("Special('logical_x')"),
+                Command::Special(_, SpecialComm::WIDE_IMMEDIATE_W) => "Special('wide_w')".to_string(),
+                | Command::Special(_, SpecialComm::WIDE_IMMEDIATE_X) => "Special('wide_x')".to_string(),
+                | Command::Special(_, SpecialComm::INVERTED_WIDE_IMMEDIATE_W) => "Special('inverted_w')".to_string(),
+                | Command::Special(_, SpecialComm::INVERTED_WIDE_IMMEDIATE_X) => "Special('inverted_x')".to_string(),
+                Command::Special(_, SpecialComm::LOGICAL_IMMEDIATE_W) => "Special('logical_w')".to_string(),
+                | Command::Special(_, SpecialComm::LOGICAL_IMMEDIATE_X) => "Special('logical_x')".to_string(),
                 Command::Special(_, SpecialComm::FLOAT_IMMEDIATE)
-                | Command::Special(_, SpecialComm::SPLIT_FLOAT_IMMEDIATE) => format!("Special('float')"),
-                Command::Special(_, SpecialComm::STRETCHED_IMMEDIATE) => format!("Special('stretched')"),
+                | Command::Special(_, SpecialComm::SPLIT_FLOAT_IMMEDIATE) => "Special('float')".to_string(),
+                Command::Special(_, SpecialComm::STRETCHED_IMMEDIATE) => "Special('stretched')".to_string(),
                 Command::Offset(Relocation::B) => format!("Range(-{}, {}, {})", 1<<27, 1<<27, 4),
                 Command::Offset(Relocation::BCOND) => format!("Range(-{}, {}, {})", 1<<18, 1<<18, 4),
                 Command::Offset(Relocation::ADR) => format!("Range(-{}, {}, {})", 1<<20, 1<<20, 1),
@@ -0,0 +1,804 @@
+//! A disassembler for aarch64 machine code.
+//!
+//! Rather than carrying a second description of the instruction set, this works backwards from
+//! the opmap used by the assembler. Every template in the opmap is checked against the
+//! instruction word, after which the encoding commands are walked to reconstruct the operands.
+//! As the opmap contains a lot of aliases that only cover part of an encoding space, any
+//! reconstruction is then fed back through the assembler and only accepted if it encodes to
+//! exactly the same word again. This guarantees that the output can be reassembled by dynasm.
+
+use super::Context;
+use super::ast::{Instruction, CleanArg, RefKind, Register, RegScalar, RegVector, RegKind, RegId, Modifier, ModifyExpr};
+use super::aarch64data::{Opdata, Matcher, Command, SpecialComm, Relocation, SPECIAL_IDENT_MAP, get_mnemonic_data, mnemnonics};
+use super::parser::AARCH64_REGISTERS;
+use super::{matching, compiler, encoding_helpers};
+
+use crate::arch::{BasicExprBuilder, ErrorSpan};
+use crate::common::{Expr, Ident, Number, NumericRepr, Size, Stmt, Value, bitmask};
+
+use lazy_static::lazy_static;
+
+use core::fmt::{self, Write};
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+
+/// Disassembles a single instruction word into dynasm syntax.
+///
+/// Branch targets and other pc-relative operands are printed as immediate offsets from the
+/// address of the instruction. Returns `None` if the word is not a known instruction.
+pub fn disassemble(word: u32) -> Option<String> {
+    decode(word).map(|decoded| decoded.text)
+}
+
+/// Disassembles a buffer of little-endian instruction words into a listing.
+///
+/// Every line contains the address, the instruction word and its disassembly. Words that cannot
+/// be decoded are shown as `.dword` directives, and pc-relative operands are annotated with the
+/// absolute address they refer to, assuming that the buffer was loaded at `address`.
+pub fn disassemble_listing(buf: &[u8], address: u64) -> String {
+    let mut listing = String::new();
+
+    for (i, chunk) in buf.chunks(4).enumerate() {
+        let pc = address.wrapping_add(4 * i as u64);
+
+        if chunk.len() != 4 {
+            let bytes: Vec<_> = chunk.iter().map(|b| format!("0x{:02x}", b)).collect();
+            writeln!(listing, "{:08x}: {:8}  .byte {}", pc, "", bytes.join(", ")).unwrap();
+            continue;
+        }
+
+        let word = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        match decode(word) {
+            Some(Decoded { text, target: Some((relocation, offset)) }) => {
+                let base = if relocation == Relocation::ADRP { pc & !0xFFF } else { pc };
+                let target = base.wrapping_add(offset as u64);
+                writeln!(listing, "{:08x}: {:08x}  {:40} // => 0x{:x}", pc, word, text, target).unwrap();
+            },
+            Some(Decoded { text, target: None }) => {
+                writeln!(listing, "{:08x}: {:08x}  {}", pc, word, text).unwrap();
+            },
+            None => {
+                writeln!(listing, "{:08x}: {:08x}  .dword 0x{:08x}", pc, word, word).unwrap();
+            }
+        }
+    }
+
+    listing
+}
+
+/// The result of decoding an instruction word.
+struct Decoded {
+    /// The instruction, formatted in dynasm syntax.
+    text: String,
+    /// The relocation kind and offset of a pc-relative operand, if the instruction has one.
+    target: Option<(Relocation, i64)>,
+}
+
+/// An opmap template, together with the bits of its encoding that aren't touched by any command.
+struct Candidate {
+    name: &'static str,
+    data: &'static Opdata,
+    mask: u32,
+}
+
+lazy_static! {
+    /// All templates in the opmap, sorted so the most specific ones are tried first.
+    /// This causes aliases (like `mov` for `orr`) to be preferred over the instructions they alias.
+    static ref CANDIDATES: Vec<Candidate> = {
+        let mut candidates = Vec::new();
+
+        for &name in mnemnonics() {
+            for data in get_mnemonic_data(name).unwrap() {
+                let mask = !data.commands.iter().fold(0, |mask, command| mask | command_fields(command));
+                candidates.push(Candidate { name, data, mask });
+            }
+        }
+
+        candidates.sort_by_key(|c| (
+            core::cmp::Reverse(c.mask.count_ones()),
+            DEPRIORITIZED.contains(&c.name),
+            c.data.matchers.iter().map(Matcher::flatarg_count).sum::<usize>(),
+            c.name,
+        ));
+        candidates
+    };
+}
+
+/// Mnemonics that are only picked when no other equally specific template describes the encoding.
+/// These are either the raw forms of more readable aliases, or aliases that are conventionally only
+/// used when their alternative doesn't apply.
+const DEPRIORITIZED: &[&str] = &[
+    "sbfm", "ubfm", "bfm", "sbfiz", "ubfiz", "bfi", "orn", "sys", "sysl", "hint", "dup", "ins", "umov", "csinc", "csinv", "csneg"
+];
+
+/// The canonical names of the condition codes, indexed by encoding.
+const CONDITIONS: [&str; 16] = ["eq", "ne", "hs", "lo", "mi", "pl", "vs", "vc", "hi", "ls", "ge", "lt", "gt", "le", "al", "nv"];
+
+/// Returns the bits of an instruction that are written by an encoding command.
+fn command_fields(command: &Command) -> u32 {
+    let field = |offset: u8, bits: u8| bitmask(bits) << offset;
+
+    match *command {
+        Command::R(o)
+        | Command::REven(o)
+        | Command::RNoZr(o) => field(o, 5),
+        Command::R4(o) => field(o, 4),
+        Command::Ubits(o, n)
+        | Command::Uscaled(o, n, _)
+        | Command::Usub(o, n, _)
+        | Command::Unegmod(o, n)
+        | Command::Usumdec(o, n)
+        | Command::Sbits(o, n)
+        | Command::Sscaled(o, n, _)
+        | Command::Uslice(o, n, _)
+        | Command::Sslice(o, n, _) => field(o, n),
+        Command::Ulist(o, options) => field(o, options.len().next_power_of_two().trailing_zeros() as u8),
+        Command::Urange(o, min, max) => field(o, 8 - (max - min).leading_zeros() as u8),
+        Command::Ufields(fields) => fields.iter().fold(0, |mask, &o| mask | field(o, 1)),
+        Command::Special(o, special) => match special {
+            // 32-bit forms cannot use the top bit of their shift or the N bit
+            SpecialComm::INVERTED_WIDE_IMMEDIATE_W
+            | SpecialComm::WIDE_IMMEDIATE_W => field(o, 17),
+            SpecialComm::INVERTED_WIDE_IMMEDIATE_X
+            | SpecialComm::WIDE_IMMEDIATE_X => field(o, 18),
+            SpecialComm::STRETCHED_IMMEDIATE
+            | SpecialComm::SPLIT_FLOAT_IMMEDIATE => field(o, 5) | field(o + 11, 3),
+            SpecialComm::LOGICAL_IMMEDIATE_W => field(o, 12),
+            SpecialComm::LOGICAL_IMMEDIATE_X => field(o, 13),
+            SpecialComm::FLOAT_IMMEDIATE => field(o, 8),
+        },
+        Command::Rwidth(o) => field(o, 1),
+        Command::Rotates(o) => field(o, 2),
+        Command::ExtendsW(o)
+        | Command::ExtendsX(o) => field(o, 3),
+        Command::Cond(o)
+        | Command::CondInv(o) => field(o, 4),
+        Command::LitList(o, name) => {
+            let all = SPECIAL_IDENT_MAP[name].values().fold(0, |all, &value| all | value);
+            all << o
+        },
+        Command::Offset(relocation) => match relocation {
+            Relocation::B => field(0, 26),
+            Relocation::BCOND => field(5, 19),
+            Relocation::TBZ => field(5, 14),
+            Relocation::ADR
+            | Relocation::ADRP => field(5, 19) | field(29, 2),
+            Relocation::LITERAL8
+            | Relocation::LITERAL16
+            | Relocation::LITERAL32
+            | Relocation::LITERAL64 => 0,
+        },
+        Command::RNext
+        | Command::BUbits(_)
+        | Command::BUsum(_)
+        | Command::BSscaled(_, _)
+        | Command::BUrange(_, _)
+        | Command::A
+        | Command::C => 0,
+    }
+}
+
+/// Find the first template that describes the instruction word, and format it.
+fn decode(word: u32) -> Option<Decoded> {
+    CANDIDATES.iter()
+        .filter(|c| word & c.mask == c.data.base & c.mask)
+        .find_map(|c| decode_candidate(word, c))
+}
+
+/// A decoded flat argument.
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    /// Not encoded at all.
+    Default,
+    Reg(u8),
+    Unsigned(u64),
+    Signed(i64),
+    Float(f32),
+    /// An extend modifier, together with whether it can be written as LSL.
+    Extend(Modifier, bool),
+    Modifier(Modifier),
+    Lit(&'static str),
+}
+
+impl Field {
+    fn number(self) -> Option<Number> {
+        match self {
+            Field::Default => Some(Number::from_u64_and_repr(0, NumericRepr::U64)),
+            Field::Unsigned(value) => Some(Number::from_u64_and_repr(value, NumericRepr::U64)),
+            Field::Signed(value) => Some(Number::from_u64_and_repr(value as u64, NumericRepr::I64)),
+            _ => None
+        }
+    }
+}
+
+fn decode_candidate(word: u32, candidate: &Candidate) -> Option<Decoded> {
+    let data = candidate.data;
+    let extract = |offset: u8, bits: u8| (word >> offset) & bitmask(bits);
+    let sign_extend = |value: u64, bits: u8| ((value << (64 - bits)) as i64) >> (64 - bits);
+
+    let count = data.matchers.iter().map(Matcher::flatarg_count).sum();
+    let mut fields = vec![Field::Default; count];
+    let mut sign_bits = vec![0u8; count];
+    let mut full_width = true;
+    let mut target = None;
+    let mut cursor = 0usize;
+
+    // walk the commands like the encoder does, but extract the fields instead.
+    for command in data.commands {
+        match *command {
+            Command::A => {
+                cursor += 1;
+                continue;
+            },
+            Command::C => {
+                cursor -= 1;
+                continue;
+            },
+            Command::Rwidth(offset) => {
+                full_width = extract(offset, 1) != 0;
+                continue;
+            },
+            _ => ()
+        }
+
+        let prev = if cursor > 0 { fields[cursor - 1] } else { Field::Default };
+        let field = match *command {
+            Command::R(o)
+            | Command::REven(o)
+            | Command::RNoZr(o) => Field::Reg(extract(o, 5) as u8),
+            Command::R4(o) => Field::Reg(extract(o, 4) as u8),
+            Command::RNext => match prev {
+                Field::Reg(code) => Field::Reg((code + 1) % 32),
+                _ => return None
+            },
+
+            Command::Ubits(o, n) => Field::Unsigned(u64::from(extract(o, n))),
+            Command::Uscaled(o, n, s) => Field::Unsigned(u64::from(extract(o, n)) << s),
+            Command::Ulist(o, options) => {
+                let index = extract(o, options.len().next_power_of_two().trailing_zeros() as u8);
+                Field::Unsigned(u64::from(*options.get(index as usize)?))
+            },
+            Command::Urange(o, min, max) => Field::Unsigned(u64::from(extract(o, 8 - (max - min).leading_zeros() as u8) + u32::from(min))),
+            Command::Usub(o, n, addval) => Field::Unsigned(u64::from(u32::from(addval).checked_sub(extract(o, n))?)),
+            Command::Unegmod(o, n) => Field::Unsigned(u64::from(((1 << n) - extract(o, n)) & bitmask(n))),
+            Command::Usumdec(o, n) => match prev {
+                Field::Unsigned(prev) => Field::Unsigned(u64::from(extract(o, n)).wrapping_add(1).wrapping_sub(prev) & u64::from(bitmask(n))),
+                _ => return None
+            },
+            Command::Ufields(offsets) => {
+                let value = offsets.iter().fold(0, |value, &o| (value << 1) | u64::from(extract(o, 1)));
+                Field::Unsigned(value)
+            },
+
+            Command::Sbits(o, n) => Field::Signed(sign_extend(u64::from(extract(o, n)), n)),
+            Command::Sscaled(o, n, s) => Field::Signed(sign_extend(u64::from(extract(o, n)), n) << s),
+
+            // slices accumulate into the current argument without advancing
+            Command::Uslice(o, n, s)
+            | Command::Sslice(o, n, s) => {
+                let value = match fields[cursor] {
+                    Field::Unsigned(value) => value,
+                    _ => 0
+                };
+                if let Command::Sslice(..) = *command {
+                    sign_bits[cursor] = sign_bits[cursor].max(n + s);
+                }
+                fields[cursor] = Field::Unsigned(value | (u64::from(extract(o, n)) << s));
+                continue;
+            },
+
+            Command::BUbits(_)
+            | Command::BUsum(_)
+            | Command::BSscaled(_, _)
+            | Command::BUrange(_, _) => continue,
+
+            Command::Special(o, special) => decode_special(o, special, &extract)?,
+
+            Command::Rotates(o) => Field::Modifier([Modifier::LSL, Modifier::LSR, Modifier::ASR, Modifier::ROR][extract(o, 2) as usize]),
+            Command::ExtendsW(o)
+            | Command::ExtendsX(o) => {
+                let lsl = if let Command::ExtendsW(_) = *command { 0b010 } else { 0b011 };
+                let code = extract(o, 3);
+                let modifier = [
+                    Modifier::UXTB, Modifier::UXTH, Modifier::UXTW, Modifier::UXTX,
+                    Modifier::SXTB, Modifier::SXTH, Modifier::SXTW, Modifier::SXTX
+                ][code as usize];
+                Field::Extend(modifier, code == lsl)
+            },
+
+            Command::Cond(o) => Field::Lit(CONDITIONS[extract(o, 4) as usize]),
+            Command::CondInv(o) => match extract(o, 4) ^ 1 {
+                // inverting AL would result in NV, which isn't a valid alias
+                14 | 15 => return None,
+                cond => Field::Lit(CONDITIONS[cond as usize]),
+            },
+            Command::LitList(o, name) => {
+                let value = (word & command_fields(command)) >> o;
+                let ident = SPECIAL_IDENT_MAP[name].iter()
+                    .filter(|&(_, &v)| v == value)
+                    .map(|(&ident, _)| ident)
+                    .min()?;
+                Field::Lit(ident)
+            },
+
+            Command::Offset(relocation) => {
+                let offset = match relocation {
+                    Relocation::B => sign_extend(u64::from(extract(0, 26)), 26) << 2,
+                    Relocation::BCOND => sign_extend(u64::from(extract(5, 19)), 19) << 2,
+                    Relocation::TBZ => sign_extend(u64::from(extract(5, 14)), 14) << 2,
+                    Relocation::ADR
+                    | Relocation::ADRP => {
+                        let value = sign_extend(u64::from((extract(5, 19) << 2) | extract(29, 2)), 21);
+                        if relocation == Relocation::ADRP { value << 12 } else { value }
+                    },
+                    _ => return None
+                };
+                target = Some((relocation, offset));
+                Field::Signed(offset)
+            },
+
+            Command::A
+            | Command::C
+            | Command::Rwidth(_) => unreachable!()
+        };
+
+        // commands revisiting an argument only get to check it, when the result is reassembled.
+        if let Field::Default = fields[cursor] {
+            fields[cursor] = field;
+        }
+        cursor += 1;
+    }
+
+    for (field, &sign_bit) in fields.iter_mut().zip(&sign_bits) {
+        if let (Field::Unsigned(value), true) = (*field, sign_bit != 0) {
+            *field = Field::Signed(sign_extend(value, sign_bit));
+        }
+    }
+
+    // rebuild the argument list, with any alternative spellings for each argument
+    let mut options = Vec::new();
+    let mut ends = Vec::new();
+    let mut fields = fields.into_iter();
+
+    for matcher in data.matchers {
+        if let Matcher::End = matcher {
+            ends.push(options.len());
+            continue;
+        }
+
+        let flat: Vec<_> = fields.by_ref().take(matcher.flatarg_count()).collect();
+
+        // an omitted extend defaults to the one equivalent to LSL, which is confusing to read unless
+        // that could've been written as LSL in the first place.
+        if let (Matcher::Mod(allowed), Some(&Field::Extend(_, lsl))) = (matcher, flat.first()) {
+            if !(lsl && allowed.contains(&Modifier::LSL)) {
+                ends.clear();
+            }
+        }
+
+        options.push(rebuild_arg(matcher, &flat, full_width)?);
+    }
+
+    // try the shortest forms first, so default values get omitted.
+    ends.push(options.len());
+    for end in ends {
+        let mut indices = vec![0usize; end];
+
+        loop {
+            let args: Vec<_> = indices.iter().enumerate().map(|(i, &j)| options[i][j].clone()).collect();
+            if reassembles_to(candidate.name, args.clone(), word) {
+                return Some(Decoded {
+                    text: format_instruction(candidate.name, &args),
+                    target,
+                });
+            }
+
+            // advance to the next combination of alternative spellings
+            match (0 .. end).find(|&i| indices[i] + 1 < options[i].len()) {
+                Some(i) => {
+                    indices[i] += 1;
+                    indices[.. i].iter_mut().for_each(|j| *j = 0);
+                },
+                None => break
+            }
+        }
+    }
+
+    None
+}
+
+fn decode_special(offset: u8, special: SpecialComm, extract: &dyn Fn(u8, u8) -> u32) -> Option<Field> {
+    let split = || extract(offset, 5) | (extract(offset + 11, 3) << 5);
+
+    Some(match special {
+        SpecialComm::WIDE_IMMEDIATE_W => Field::Unsigned(u64::from(encoding_helpers::decode_wide_immediate_32bit(extract(offset, 18))?)),
+        SpecialComm::WIDE_IMMEDIATE_X => Field::Unsigned(encoding_helpers::decode_wide_immediate_64bit(extract(offset, 18))),
+        SpecialComm::INVERTED_WIDE_IMMEDIATE_W => Field::Unsigned(u64::from(!encoding_helpers::decode_wide_immediate_32bit(extract(offset, 18))?)),
+        SpecialComm::INVERTED_WIDE_IMMEDIATE_X => Field::Unsigned(!encoding_helpers::decode_wide_immediate_64bit(extract(offset, 18))),
+        SpecialComm::STRETCHED_IMMEDIATE => Field::Unsigned(encoding_helpers::decode_stretched_immediate(split())),
+        SpecialComm::LOGICAL_IMMEDIATE_W => Field::Unsigned(u64::from(encoding_helpers::decode_logical_immediate_32bit(extract(offset, 13) as u16)?)),
+        SpecialComm::LOGICAL_IMMEDIATE_X => Field::Unsigned(encoding_helpers::decode_logical_immediate_64bit(extract(offset, 13) as u16)?),
+        SpecialComm::FLOAT_IMMEDIATE => Field::Float(encoding_helpers::decode_floating_point_immediate(extract(offset, 8) as u8)),
+        SpecialComm::SPLIT_FLOAT_IMMEDIATE => Field::Float(encoding_helpers::decode_floating_point_immediate(split() as u8)),
+    })
+}
+
+/// Turns the flat fields belonging to a matcher back into an argument, listing alternative
+/// spellings in order of preference.
+fn rebuild_arg(matcher: &Matcher, flat: &[Field], full_width: bool) -> Option<Vec<CleanArg>> {
+    let span = ErrorSpan::argument(0);
+    let reg = |index: usize| match flat.get(index) {
+        Some(&Field::Reg(code)) => Some(code),
+        _ => None
+    };
+    let immediate = |index: usize| flat.get(index).and_then(|f| f.number()).map(Value::Number);
+    let lanes = |size: Size| Some((if full_width { 16 } else { 8 }) / size.in_bytes());
+
+    let single = |arg| Some(vec![arg]);
+    let direct = |reg| single(CleanArg::Direct { span, reg });
+
+    match *matcher {
+        Matcher::Dot => single(CleanArg::Dot { span }),
+        Matcher::Lit(name) => single(CleanArg::Lit { span, ident: ident(name) }),
+        Matcher::LitInt(value) => single(CleanArg::Immediate { span, prefixed: true, value: Value::Number(Number::from_u64_and_repr(u64::from(value), NumericRepr::U64)) }),
+        Matcher::LitFloat(value) => single(CleanArg::FloatImmediate { span, prefixed: true, value: f64::from(value) }),
+        Matcher::Ident
+        | Matcher::Cond => match flat[0] {
+            Field::Lit(name) => single(CleanArg::Lit { span, ident: ident(name) }),
+            _ => None
+        },
+        Matcher::Imm
+        | Matcher::Offset => match flat[0] {
+            Field::Float(value) => single(CleanArg::FloatImmediate { span, prefixed: true, value: f64::from(value) }),
+            _ => single(CleanArg::Immediate { span, prefixed: true, value: immediate(0)? }),
+        },
+        Matcher::W => direct(integer(reg(0)?, Size::DWORD, false)),
+        Matcher::X => direct(integer(reg(0)?, Size::QWORD, false)),
+        Matcher::WSP => direct(integer(reg(0)?, Size::DWORD, true)),
+        Matcher::XSP => direct(integer(reg(0)?, Size::QWORD, true)),
+        Matcher::B => direct(simd(reg(0)?, Size::BYTE)),
+        Matcher::H => direct(simd(reg(0)?, Size::WORD)),
+        Matcher::S => direct(simd(reg(0)?, Size::DWORD)),
+        Matcher::D => direct(simd(reg(0)?, Size::QWORD)),
+        Matcher::Q => direct(simd(reg(0)?, Size::OWORD)),
+        Matcher::V(size) => direct(vector(reg(0)?, size, lanes(size), None)),
+        Matcher::VStatic(size, count) => direct(vector(reg(0)?, size, Some(count), None)),
+        Matcher::VElement(size) => direct(vector(reg(0)?, size, None, immediate(1))),
+        Matcher::VElementStatic(size, element) => direct(vector(reg(0)?, size, None, Some(Value::Byte(element)))),
+        Matcher::VStaticElement(size, count) => direct(vector(reg(0)?, size, Some(count), immediate(1))),
+        Matcher::RegList(amount, size) => single(CleanArg::RegList { span, first: vector(reg(0)?, size, lanes(size), None), amount, element: None }),
+        Matcher::RegListStatic(amount, size, count) => single(CleanArg::RegList { span, first: vector(reg(0)?, size, Some(count), None), amount, element: None }),
+        Matcher::RegListElement(amount, size) => single(CleanArg::RegList { span, first: vector(reg(0)?, size, None, None), amount, element: immediate(1) }),
+        Matcher::RefBase => single(CleanArg::Reference { span, base: integer(reg(0)?, Size::QWORD, true), kind: RefKind::Base }),
+        Matcher::RefOffset => {
+            let base = integer(reg(0)?, Size::QWORD, true);
+            let offset = immediate(1)?;
+            let mut options = Vec::new();
+            if is_zero(&offset) {
+                options.push(CleanArg::Reference { span, base: base.clone(), kind: RefKind::Base });
+            }
+            options.push(CleanArg::Reference { span, base, kind: RefKind::Offset(offset) });
+            Some(options)
+        },
+        Matcher::RefPre => single(CleanArg::Reference { span, base: integer(reg(0)?, Size::QWORD, true), kind: RefKind::PreIndexed(immediate(1)?) }),
+        Matcher::RefIndex => {
+            let base = integer(reg(0)?, Size::QWORD, true);
+            let (modifier, lsl) = match flat[2] {
+                Field::Extend(modifier, lsl) => (modifier, lsl),
+                Field::Default => (Modifier::UXTX, true),
+                _ => return None
+            };
+            // only the extends that the assembler allows in references are valid
+            let (modifier, index_size) = match modifier {
+                _ if lsl => (Modifier::LSL, Size::QWORD),
+                Modifier::SXTX => (modifier, Size::QWORD),
+                Modifier::UXTW | Modifier::SXTW => (modifier, Size::DWORD),
+                _ => return None
+            };
+            let index = integer(reg(1)?, index_size, false);
+
+            let options = modifier_options(modifier, immediate(3)?).into_iter()
+                .map(|m| CleanArg::Reference { span, base: base.clone(), kind: RefKind::Indexed(index.clone(), m) });
+            let mut options: Vec<_> = options.collect();
+            if modifier == Modifier::LSL && is_zero(&immediate(3)?) {
+                options.insert(0, CleanArg::Reference { span, base, kind: RefKind::Indexed(index, None) });
+            }
+            Some(options)
+        },
+        Matcher::LitMod(modifier) => single(CleanArg::Modifier { span, modifier: ModifyExpr::new(modifier, Some(immediate(0)?)) }),
+        Matcher::Mod(allowed) => {
+            // extends that are equivalent to LSL are preferably written as such
+            let modifiers = match flat[0] {
+                Field::Modifier(modifier) => vec![modifier],
+                Field::Extend(modifier, true) if allowed.contains(&Modifier::LSL) => vec![Modifier::LSL, modifier],
+                Field::Extend(modifier, _) => vec![modifier],
+                _ => return None
+            };
+            let amount = immediate(1)?;
+            let options = modifiers.into_iter()
+                .flat_map(|modifier| modifier_options(modifier, amount))
+                .flatten()
+                .map(|modifier| CleanArg::Modifier { span, modifier });
+            Some(options.collect())
+        },
+        Matcher::End => None,
+    }
+}
+
+/// The ways to write a modifier with the given amount. Extends without an amount can be written without it.
+fn modifier_options(modifier: Modifier, amount: Value) -> Vec<Option<ModifyExpr>> {
+    let mut options = Vec::new();
+    if !modifier.expr_required() && is_zero(&amount) {
+        options.push(Some(ModifyExpr::new(modifier, None)));
+    }
+    options.push(Some(ModifyExpr::new(modifier, Some(amount))));
+    options
+}
+
+fn is_zero(value: &Value) -> bool {
+    matches!(value, Value::Number(number) if number.as_u64() == 0)
+}
+
+fn ident(name: &str) -> Ident {
+    Ident { name: name.into() }
+}
+
+/// Looks up a register by name in the parser's register table.
+fn register_id(name: &str) -> RegId {
+    AARCH64_REGISTERS.iter()
+        .find(|&&(n, _)| n == name)
+        .map(|&(_, (id, _))| id)
+        .expect("register missing from the register table")
+}
+
+/// An integer register, where register 31 either refers to the zero register or the stack pointer.
+fn integer(code: u8, size: Size, sp: bool) -> Register {
+    let id = match (code, sp) {
+        (31, true) => RegId::SP,
+        (31, false) => RegId::XZR,
+        (code, _) => register_id(&format!("x{}", code)),
+    };
+    Register::Scalar(RegScalar { kind: RegKind::Static(id), size })
+}
+
+fn simd(code: u8, size: Size) -> Register {
+    Register::Scalar(RegScalar { kind: RegKind::Static(register_id(&format!("v{}", code))), size })
+}
+
+fn vector(code: u8, element_size: Size, lanes: Option<u8>, element: Option<Value>) -> Register {
+    Register::Vector(RegVector {
+        kind: RegKind::Static(register_id(&format!("v{}", code))),
+        element_size,
+        lanes,
+        element,
+    })
+}
+
+/// Checks if assembling the instruction results in exactly the given word.
+fn reassembles_to(name: &str, args: Vec<CleanArg>, word: u32) -> bool {
+    let mut verifier = Verifier::default();
+    let mut ctx = Context { state: &mut verifier };
+    let instruction = Instruction { ident: ident(name) };
+
+    let opdata = match get_mnemonic_data(&instruction.ident.name) {
+        Some(opdata) => opdata,
+        None => return false
+    };
+
+    // the assembler picks the first template that matches, so this has to check that one.
+    let data = match opdata.iter().find_map(|data| matching::match_args(&args, data).map(|m| (data, m))) {
+        Some((data, mut match_data)) => {
+            matching::flatten_args(args, data, &mut match_data);
+            match_data
+        },
+        None => return false
+    };
+
+    if compiler::encode_instruction(&mut ctx, data).is_err() {
+        return false;
+    }
+
+    match &verifier.stmts[..] {
+        [Stmt::Const(Value::Number(number))] => number.as_u32() == word,
+        _ => false
+    }
+}
+
+/// An expression builder that only accepts static values, and silently drops any errors.
+#[derive(Default)]
+pub(super) struct Verifier {
+    stmts: Vec<Stmt>,
+}
+
+impl BasicExprBuilder for Verifier {
+    fn push(&mut self, stmt: Stmt) {
+        self.stmts.push(stmt);
+    }
+
+    fn bit_or(&mut self, _: Expr, _: Value) -> Option<Expr> {
+        None
+    }
+
+    fn bit_and(&mut self, _: Expr, _: Value) -> Option<Expr> {
+        None
+    }
+
+    fn bit_xor(&mut self, _: Expr, _: Value) -> Option<Expr> {
+        None
+    }
+
+    fn add(&mut self, _: Expr, _: Value) -> Option<Expr> {
+        None
+    }
+
+    fn mul(&mut self, _: Expr, _: Value) -> Option<Expr> {
+        None
+    }
+
+    fn neg(&mut self, _: Expr) -> Option<Expr> {
+        None
+    }
+
+    fn log2(&mut self, _: Expr) -> Option<Expr> {
+        None
+    }
+
+    fn mask_shift(&mut self, _: Expr, _: u64, _: i8) -> Option<Expr> {
+        None
+    }
+
+    fn emit_error_at(&mut self, _: ErrorSpan, _: fmt::Arguments) {}
+}
+
+/*
+ * Formatting of the decoded instruction
+ */
+
+fn format_instruction(name: &str, args: &[CleanArg]) -> String {
+    let mut buf = name.to_string();
+    let mut first = true;
+    let mut after_dot = false;
+
+    for arg in args {
+        if let CleanArg::Dot { .. } = arg {
+            buf.push('.');
+            after_dot = true;
+            continue;
+        }
+
+        if first {
+            if !after_dot {
+                buf.push(' ');
+                first = false;
+            }
+            after_dot = false;
+        } else {
+            buf.push_str(", ");
+        }
+
+        format_arg(&mut buf, arg);
+    }
+
+    buf
+}
+
+fn format_arg(buf: &mut String, arg: &CleanArg) {
+    match arg {
+        CleanArg::Reference { base, kind, .. } => {
+            buf.push('[');
+            format_register(buf, base);
+            match kind {
+                RefKind::Base => buf.push(']'),
+                RefKind::Offset(offset) => {
+                    buf.push_str(", ");
+                    format_immediate(buf, offset);
+                    buf.push(']');
+                },
+                RefKind::PreIndexed(offset) => {
+                    buf.push_str(", ");
+                    format_immediate(buf, offset);
+                    buf.push_str("]!");
+                },
+                RefKind::Indexed(index, modifier) => {
+                    buf.push_str(", ");
+                    format_register(buf, index);
+                    if let Some(modifier) = modifier {
+                        buf.push_str(", ");
+                        format_modifier(buf, modifier);
+                    }
+                    buf.push(']');
+                }
+            }
+        },
+        CleanArg::RegList { first, amount, element, .. } => {
+            let first = first.assume_vector();
+            let code = first.kind.encode();
+            buf.push('{');
+            for i in 0 .. *amount {
+                if i != 0 {
+                    buf.push_str(", ");
+                }
+                write!(buf, "v{}", (code + i) % 32).unwrap();
+                format_vector_layout(buf, first);
+            }
+            buf.push('}');
+            if let Some(element) = element {
+                write!(buf, "[{}]", number(element)).unwrap();
+            }
+        },
+        CleanArg::Direct { reg, .. } => format_register(buf, reg),
+        CleanArg::JumpTarget { .. } => buf.push_str("<label>"),
+        CleanArg::Immediate { value, .. } => format_immediate(buf, value),
+        CleanArg::FloatImmediate { value, .. } => write!(buf, "#{:?}", value).unwrap(),
+        CleanArg::Modifier { modifier, .. } => format_modifier(buf, modifier),
+        CleanArg::Dot { .. } => buf.push('.'),
+        CleanArg::Lit { ident, .. } => buf.push_str(&ident.name),
+    }
+}
+
+fn format_register(buf: &mut String, reg: &Register) {
+    match reg {
+        Register::Scalar(s) => {
+            let id = match s.kind {
+                RegKind::Static(id) => id,
+                RegKind::Dynamic(_, _) => unreachable!()
+            };
+            let name = AARCH64_REGISTERS.iter()
+                .find(|&&(_, (i, size))| i == id && size == Some(s.size))
+                .map(|&(name, _)| name)
+                .expect("register missing from the register table");
+            buf.push_str(name);
+        },
+        Register::Vector(v) => {
+            write!(buf, "v{}", v.kind.encode()).unwrap();
+            format_vector_layout(buf, v);
+            if let Some(ref element) = v.element {
+                write!(buf, "[{}]", number(element)).unwrap();
+            }
+        }
+    }
+}
+
+fn format_vector_layout(buf: &mut String, v: &RegVector) {
+    buf.push('.');
+    buf.push_str(&super::debug::size_to_string(v.element_size).to_lowercase());
+    if let Some(lanes) = v.lanes {
+        write!(buf, "{}", lanes).unwrap();
+    }
+}
+
+fn format_modifier(buf: &mut String, modifier: &ModifyExpr) {
+    buf.push_str(&modifier.op.as_str().to_lowercase());
+    if let Some(ref amount) = modifier.expr {
+        buf.push(' ');
+        format_immediate(buf, amount);
+    }
+}
+
+fn format_immediate(buf: &mut String, value: &Value) {
+    buf.push('#');
+    buf.push_str(&number(value));
+}
+
+/// Formats a number. Small values are printed in decimal, larger ones in hexadecimal.
+fn number(value: &Value) -> String {
+    let number = match value {
+        Value::Number(number) => *number,
+        Value::Expr(_) => unreachable!()
+    };
+
+    let (negative, magnitude) = if number.repr().signed && number.as_i64() < 0 {
+        (true, number.as_i64().unsigned_abs())
+    } else {
+        (false, number.as_u64())
+    };
+
+    let sign = if negative { "-" } else { "" };
+    if magnitude < 10 {
+        format!("{}{}", sign, magnitude)
+    } else {
+        format!("{}0x{:x}", sign, magnitude)
+    }
+}
@@ -96,8 +96,85 @@ pub fn encode_wide_immediate_32bit(value: u32) -> Option<u32> {
     let offset = value.trailing_zeros() & 0b1_0000;
     let masked = 0xFFFF & (value >> offset);
     if (masked << offset) == value {
-        Some((masked as u32) | (offset << 12))
+        Some(masked | (offset << 12))
     } else {
         None
     }
 }
+
+pub fn decode_floating_point_immediate(encoded: u8) -> f32 {
+    // inverse of the above, expanding abcdefgh back into aBbbbbbc defgh000 00000000 00000000
+    let encoded = u32::from(encoded);
+    let b = (encoded >> 6) & 1;
+
+    let bits = ((encoded & 0x80) << 24)
+        | ((b ^ 1) << 30)
+        | ((b * 0x1F) << 25)
+        | ((encoded & 0x3F) << 19);
+    f32::from_bits(bits)
+}
+
+pub fn decode_logical_immediate_32bit(encoded: u16) -> Option<u32> {
+    // the N bit selects a 64-bit element, which cannot exist in a 32-bit register
+    if encoded & 0x1000 != 0 {
+        return None;
+    }
+
+    decode_logical_immediate_64bit(encoded).map(|value| value as u32)
+}
+
+pub fn decode_logical_immediate_64bit(encoded: u16) -> Option<u64> {
+    let n = u32::from((encoded >> 12) & 1);
+    let immr = u32::from((encoded >> 6) & 0x3F);
+    let imms = u32::from(encoded & 0x3F);
+
+    // the element size is determined by the highest bit set in N:NOT(imms)
+    let combined = (n << 6) | (!imms & 0x3F);
+    if combined <= 1 {
+        return None;
+    }
+    let element_size = 1u32 << (31 - combined.leading_zeros());
+    let levels = element_size - 1;
+
+    let ones = (imms & levels) + 1;
+    if ones == element_size {
+        return None;
+    }
+
+    // build the element, rotate it right by immr within the element and replicate it
+    let element = bitmask64(ones as u8);
+    let rotate = immr & levels;
+    let element = if rotate == 0 {
+        element
+    } else {
+        ((element >> rotate) | (element << (element_size - rotate))) & bitmask64(element_size as u8)
+    };
+
+    let mut value = 0u64;
+    for i in 0 .. 64 / element_size {
+        value |= element << (i * element_size);
+    }
+    Some(value)
+}
+
+pub fn decode_stretched_immediate(encoded: u32) -> u64 {
+    let mut value = 0u64;
+    for i in 0 .. 8 {
+        if encoded & (1 << i) != 0 {
+            value |= 0xFF << (i * 8);
+        }
+    }
+    value
+}
+
+pub fn decode_wide_immediate_64bit(encoded: u32) -> u64 {
+    u64::from(encoded & 0xFFFF) << ((encoded >> 12) & 0b11_0000)
+}
+
+pub fn decode_wide_immediate_32bit(encoded: u32) -> Option<u32> {
+    let offset = (encoded >> 12) & 0b11_0000;
+    if offset > 16 {
+        return None;
+    }
+    Some((encoded & 0xFFFF) << offset)
+}
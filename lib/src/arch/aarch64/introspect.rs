@@ -0,0 +1,100 @@
+//! Public, read-only view of the instruction tables used by the assembler.
+//!
+//! Every mnemonic maps to a list of forms, each describing one operand combination the
+//! assembler accepts as a sequence of `Matcher`s. aarch64 has no optional cpu features or
+//! alternative modes, so every form is always available.
+
+use super::aarch64data::{Opdata, Matcher, get_mnemonic_data, mnemnonics};
+use super::debug::format_opdata;
+use super::semantics::Semantics;
+
+use lazy_static::lazy_static;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// A single operand combination that an instruction accepts.
+#[derive(Debug, Clone, Copy)]
+pub struct Form {
+    mnemonic: &'static str,
+    data: &'static Opdata,
+}
+
+lazy_static! {
+    static ref MNEMONICS: Vec<&'static str> = {
+        let mut mnemonics: Vec<_> = mnemnonics().cloned().collect();
+        mnemonics.sort_unstable();
+        mnemonics
+    };
+}
+
+/// Returns all mnemonics known to the assembler, in alphabetical order.
+pub fn mnemonics() -> impl Iterator<Item=&'static str> {
+    MNEMONICS.iter().cloned()
+}
+
+/// Returns the forms of a mnemonic, in the order in which the assembler tries to match them.
+pub fn forms(mnemonic: &str) -> Option<impl Iterator<Item=Form>> {
+    let mnemonic = *MNEMONICS.iter().find(|&&m| m == mnemonic)?;
+    let data = get_mnemonic_data(mnemonic)?;
+    Some(data.iter().map(move |data| Form { mnemonic, data }))
+}
+
+impl Form {
+    /// The mnemonic this form belongs to.
+    pub fn mnemonic(&self) -> &'static str {
+        self.mnemonic
+    }
+
+    /// The matchers that the instruction's operands, as well as any suffixes after the mnemonic,
+    /// have to satisfy for this form to be chosen.
+    pub fn matchers(&self) -> &'static [Matcher] {
+        self.data.matchers
+    }
+
+    /// The instruction template that the operands are encoded into.
+    pub fn template(&self) -> u32 {
+        self.data.base
+    }
+
+    /// The effects of this form: which operands, registers and flags it reads and writes. Flags
+    /// that are used through a condition code are all listed as read.
+    pub fn semantics(&self) -> Semantics {
+        self.data.semantics
+    }
+
+    /// A human readable description of the syntax of this form, with the constraints on its
+    /// operands. Forms that take full-width and half-width vectors are described once per width.
+    pub fn syntax(&self) -> Vec<String> {
+        format_opdata(self.mnemonic, self.data)
+            .into_iter()
+            .map(|form| form.replace(">>> ", ""))
+            .collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_mnemonic_has_forms() {
+        let names: Vec<_> = mnemonics().collect();
+        assert!(names.windows(2).all(|w| w[0] < w[1]));
+        for name in names {
+            let mut forms = forms(name).unwrap_or_else(|| panic!("no forms for {}", name)).peekable();
+            assert!(forms.peek().is_some(), "{}", name);
+            assert!(forms.all(|form| form.mnemonic() == name), "{}", name);
+        }
+        assert!(forms("nonexistent").is_none());
+    }
+
+    #[test]
+    fn add_shifted_register() {
+        let form = forms("add").unwrap().next().unwrap();
+        assert!(matches!(form.matchers(), [Matcher::W, Matcher::W, Matcher::W, Matcher::End, Matcher::Mod(_)]));
+        assert_eq!(form.template(), 0x0B00_0000);
+        assert_eq!(form.syntax().len(), 1);
+    }
+}
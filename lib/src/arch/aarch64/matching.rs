@@ -1,25 +1,24 @@
 
-use proc_macro2::Span;
-
-use super::Context;
+use super::{Context, Error};
 use super::ast::{Instruction, RawArg, CleanArg, FlatArg, RefItem, Register, RegFamily, RefKind, Modifier};
 use super::aarch64data::{Opdata, Matcher, COND_MAP, get_mnemonic_data};
 use super::debug::format_opdata_list;
 
-use crate::common::{Size, JumpKind, emit_error_at};
-use crate::parse_helpers::{as_ident, as_number, as_float};
+use crate::arch::ErrorSpan;
+use crate::common::{Size, JumpKind, Value};
 
-/// Try finding an appropriate definition that matches the given instruction / arguments. 
-pub(super) fn match_instruction(_ctx: &mut Context, instruction: &Instruction, args: Vec<RawArg>) -> Result<MatchData, Option<String>> {
-    // sanitize our arg list to remove any structures that cannot be matched on
-    let args = sanitize_args(args)?;
+use alloc::vec::Vec;
+use alloc::format;
 
+/// Try finding an appropriate definition that matches the given instruction / arguments. 
+pub(super) fn match_instruction(ctx: &mut Context, instruction: &Instruction, args: Vec<CleanArg>) -> Result<MatchData, Error> {
     // get the possible matchers
-    let name = instruction.ident.to_string();
-    let opdata = if let Some(o) = get_mnemonic_data(&name) {
+    let name = &instruction.ident.name;
+    let opdata = if let Some(o) = get_mnemonic_data(name) {
         o
     } else {
-        return Err(Some(format!("Unknown instruction mnemonic '{}'", name)));
+        ctx.state.emit_error_at(ErrorSpan::instruction_part(0), format_args!("'{}' is not a valid instruction", name));
+        return Err(Error::Fatal);
     };
 
     // matching loop
@@ -33,9 +32,8 @@ pub(super) fn match_instruction(_ctx: &mut Context, instruction: &Instruction, a
         }
     }
 
-    Err(Some(
-        format!("'{}': instruction format mismatch, expected one of the following forms:\n{}", &name, format_opdata_list(&name, opdata))
-    ))
+    Err(format!("'{}': instruction format mismatch, expected one of the following forms:\n{}",
+        name, format_opdata_list(name, opdata)).into())
 }
 
 /// Sanitizes arguments, ensuring that:
@@ -43,50 +41,56 @@ pub(super) fn match_instruction(_ctx: &mut Context, instruction: &Instruction, a
 /// Vector register size specifications are possible (1B 2B 4B 8B 16B 1H 2H 4H 8H 1S 2S 4S 1D 2D)
 /// References obey the allowed formats and use only normal registers
 /// Reference modifiers are in the allowed set of modifiers
-fn sanitize_args(args: Vec<RawArg>) -> Result<Vec<CleanArg>, Option<String>> {
+pub(super) fn sanitize_args(ctx: &mut Context, args: Vec<RawArg>) -> Result<Vec<CleanArg>, Error> {
     let mut res = Vec::new();
 
-    for arg in args {
+    for (idx, arg) in args.into_iter().enumerate() {
+        let span = ErrorSpan::argument(idx);
+
         match arg {
             // direct register arguments: Validate vector register element size / lane count combination is possible
-            RawArg::Direct { span, reg } => {
-                sanitize_register(span, &reg)?;
+            RawArg::Direct { reg } => {
+                sanitize_register(ctx, span, &reg)?;
                 res.push(CleanArg::Direct { span, reg });
             },
             // offsets: validate that only relative jumps are allowed (no extern relocations)
             RawArg::JumpTarget { jump } => {
                 if let JumpKind::Bare(_) = jump.kind {
-                    emit_error_at(jump.span(), "Extern relocations are not allowed in aarch64".into());
-                    return Err(None);
+                    ctx.state.emit_error_at(span, format_args!("Extern relocations are not allowed in aarch64"));
+                    return Err(Error::Fatal);
                 }
                 res.push(CleanArg::JumpTarget { jump });
             },
             // modifier: LSL LSR ASR ROR and MSL require an immediate.
-            RawArg::Modifier { span, modifier } => {
+            RawArg::Modifier { modifier } => {
                 if modifier.expr.is_none() && modifier.op.expr_required() {
-                    emit_error_at(span, "LSL, LSR, ASR, ROR and MSL modifiers require a shift immediate.".into());
-                    return Err(None);
+                    ctx.state.emit_error_at(span, format_args!("LSL, LSR, ASR, ROR and MSL modifiers require a shift immediate."));
+                    return Err(Error::Fatal);
                 }
 
                 res.push(CleanArg::Modifier { span, modifier });
             },
             // dot: passthrough
-            RawArg::Dot { span } => {
+            RawArg::Dot => {
                 res.push(CleanArg::Dot { span } );
             },
             // lit: passthrough
             RawArg::Lit { ident } => {
-                res.push(CleanArg::Lit { ident } );
+                res.push(CleanArg::Lit { span, ident } );
             },
             // immediate: pass through
             RawArg::Immediate { value, prefixed } => {
-                res.push(CleanArg::Immediate { value, prefixed })
+                res.push(CleanArg::Immediate { span, value, prefixed })
+            },
+            // float immediate: pass through
+            RawArg::FloatImmediate { value, prefixed } => {
+                res.push(CleanArg::FloatImmediate { span, value, prefixed })
             },
             // reference: first, assert the used indexing mode (base, offset, pre-indexed, or register-indexed)
             // then, verify that the base register is always an XSP register
             // for the register-indexed mode, additionally verify that the index register is either an W or an X register
             // and that the appropriate extend mode is used (UXTW/SXTW for W, LSL/SXTX for X)
-            RawArg::Reference { span, items, bang } => {
+            RawArg::Reference { items, bang } => {
                 let mut items = items.into_iter();
                 let mut hit_end = false;
                 let mut kind = RefKind::Base;
@@ -95,10 +99,13 @@ fn sanitize_args(args: Vec<RawArg>) -> Result<Vec<CleanArg>, Option<String>> {
                 let base = match items.next() {
                     Some(RefItem::Direct { reg, .. }) => reg,
                     Some(_) => {
-                        emit_error_at(span, "First item in a reference list has to be a register".into());
-                        return Err(None);
+                        ctx.state.emit_error_at(span, format_args!("First item in a reference list has to be a register"));
+                        return Err(Error::Fatal);
                     },
-                    None => unreachable!("Cannot create empty references in the parser")
+                    None => {
+                        ctx.state.emit_error_at(span, format_args!("Empty reference list"));
+                        return Err(Error::Fatal);
+                    }
                 };
 
                 // second item is either a register or an offset
@@ -110,8 +117,8 @@ fn sanitize_args(args: Vec<RawArg>) -> Result<Vec<CleanArg>, Option<String>> {
                         kind = RefKind::Offset(value);
                     },
                     Some(RefItem::Modifier { .. }) => {
-                        emit_error_at(span, "Cannot have a modifier without index register or offset".into());
-                        return Err(None);
+                        ctx.state.emit_error_at(span, format_args!("Cannot have a modifier without index register or offset"));
+                        return Err(Error::Fatal);
                     },
                     None => hit_end = true
                 }
@@ -123,8 +130,8 @@ fn sanitize_args(args: Vec<RawArg>) -> Result<Vec<CleanArg>, Option<String>> {
                             *modifier = Some(m)
                         },
                         Some(_) => {
-                            emit_error_at(span, "Too many items in reference list".into());
-                            return Err(None);
+                            ctx.state.emit_error_at(span, format_args!("Too many items in reference list"));
+                            return Err(Error::Fatal);
                         },
                         None => hit_end = true
                     }
@@ -132,8 +139,8 @@ fn sanitize_args(args: Vec<RawArg>) -> Result<Vec<CleanArg>, Option<String>> {
 
                 // there should not be any more items in the reference
                 if !hit_end && items.next().is_some() {
-                    emit_error_at(span, "Too many items in reference list".into());
-                    return Err(None);
+                    ctx.state.emit_error_at(span, format_args!("Too many items in reference list"));
+                    return Err(Error::Fatal);
                 }
 
                 // determine the mode. Currently post-indexed is just handled by parsing said arg at match time.
@@ -141,36 +148,36 @@ fn sanitize_args(args: Vec<RawArg>) -> Result<Vec<CleanArg>, Option<String>> {
                     if let RefKind::Offset(offset) = kind {
                         kind = RefKind::PreIndexed(offset);
                     } else {
-                        emit_error_at(span, "Cannot use pre-indexed addressing without an immediate offset.".into());
-                        return Err(None);
+                        ctx.state.emit_error_at(span, format_args!("Cannot use pre-indexed addressing without an immediate offset."));
+                        return Err(Error::Fatal);
                     }
                 }
 
                 // sanitizaiton
                 // base can only be a Xn|SP reg
                 if !(base.size() == Size::QWORD && (base.family() == RegFamily::INTEGERSP || (base.family() == RegFamily::INTEGER && !base.kind().is_zero_reg()))) {
-                    emit_error_at(span, "Base register can only be a Xn|SP register".into());
-                    return Err(None);
+                    ctx.state.emit_error_at(span, format_args!("Base register can only be a Xn|SP register"));
+                    return Err(Error::Fatal);
                 }
 
                 // index can only be a Xn or Wn reg
                 if let RefKind::Indexed(ref index, ref modifier) = kind {
                     if index.family() != RegFamily::INTEGER {
-                        emit_error_at(span, "Index register can only be a Xn or Wn register".into());
-                        return Err(None);
+                        ctx.state.emit_error_at(span, format_args!("Index register can only be a Xn or Wn register"));
+                        return Err(Error::Fatal);
                     }
 
                     // limited set of allowed modifiers.
                     if let Some(ref m) = modifier {
                         if if index.size() == Size::QWORD {m.op != Modifier::LSL && m.op != Modifier::SXTX} else {m.op != Modifier::SXTW && m.op != Modifier::UXTW} {
-                            emit_error_at(span, "Invalid modifier for the selected base register type".into());
-                            return Err(None);
+                            ctx.state.emit_error_at(span, format_args!("Invalid modifier for the selected base register type"));
+                            return Err(Error::Fatal);
                         }
 
                         // LSL requires a stated immediate
                         if m.op.expr_required() && m.expr.is_none() {
-                            emit_error_at(span, "LSL reference modifier requires an immediate".into());
-                            return Err(None);
+                            ctx.state.emit_error_at(span, format_args!("LSL reference modifier requires an immediate"));
+                            return Err(Error::Fatal);
                         }
                     }
                 }
@@ -183,10 +190,10 @@ fn sanitize_args(args: Vec<RawArg>) -> Result<Vec<CleanArg>, Option<String>> {
             },
             // registerlist in dash notation: verify that all used registers have the same element size / lane count.
             // then, canonicalize it to first register / count and confirm it is a valid bare vector register
-            RawArg::DashList { span, first, last, element } => {
+            RawArg::DashList { first, last, element } => {
                 let mut s = ListSanitizer::new();
-                s.sanitize(span, &first)?;
-                s.sanitize(span, &last)?;
+                s.sanitize(ctx, span, &first)?;
+                s.sanitize(ctx, span, &last)?;
 
                 let first_code = first.kind().encode();
                 let last_code = last.kind().encode();
@@ -206,28 +213,34 @@ fn sanitize_args(args: Vec<RawArg>) -> Result<Vec<CleanArg>, Option<String>> {
             },
             // registerlist in comma notation: verify that all used registers have the same element size / lane count.
             // then, canonicalize it to first register / count and confirm it is a valid bare vector register
-            RawArg::CommaList { span, items, element } => {
+            RawArg::CommaList { items, element } => {
                 if items.len() > 32 {
-                    emit_error_at(span, "Too many registers in register list.".into());
-                    return Err(None);
+                    ctx.state.emit_error_at(span, format_args!("Too many registers in register list."));
+                    return Err(Error::Fatal);
                 }
                 let amount = items.len() as u8;
 
                 let mut items = items.into_iter();
-                let first = items.next().unwrap();
+                let first = match items.next() {
+                    Some(first) => first,
+                    None => {
+                        ctx.state.emit_error_at(span, format_args!("Empty register list"));
+                        return Err(Error::Fatal);
+                    }
+                };
 
                 let mut s = ListSanitizer::new();
-                s.sanitize(span, &first)?;
+                s.sanitize(ctx, span, &first)?;
                 let code = first.kind().encode();
                 let mut next_code = code;
 
 
                 for item in items {
-                    s.sanitize(span, &item)?;
+                    s.sanitize(ctx, span, &item)?;
                     next_code = (next_code + 1) % 32;
                     if item.kind().encode() != next_code {
-                        emit_error_at(span, "Registers in register list are not monotonically incrementing".into());
-                        return Err(None);
+                        ctx.state.emit_error_at(span, format_args!("Registers in register list are not monotonically incrementing"));
+                        return Err(Error::Fatal);
                     }
                 }
 
@@ -239,28 +252,28 @@ fn sanitize_args(args: Vec<RawArg>) -> Result<Vec<CleanArg>, Option<String>> {
                 })
             },
             // registerlist in amount notation: verify the register and confirm it is a valid bare vector register
-            RawArg::AmountList { span, first, amount, element } => {
-                sanitize_register(span, &first)?;
+            RawArg::AmountList { first, amount, element } => {
+                sanitize_register(ctx, span, &first)?;
                 if let Register::Vector(v) = &first {
                     if v.element.is_some() {
-                        emit_error_at(span, "Cannot use element specifiers inside of register lists.".into());
-                        return Err(None);
+                        ctx.state.emit_error_at(span, format_args!("Cannot use element specifiers inside of register lists."));
+                        return Err(Error::Fatal);
                     }
                 } else {
-                    emit_error_at(span, "Can only use vector registers in register lists.".into());
-                    return Err(None);
+                    ctx.state.emit_error_at(span, format_args!("Can only use vector registers in register lists."));
+                    return Err(Error::Fatal);
                 }
 
                 // ensure amount is a constant usize
                 let amount = if let Some(amount) = as_number(&amount) {
                     if amount > 32 {
-                        emit_error_at(span, "Too many registers in register list.".into());
-                        return Err(None);
+                        ctx.state.emit_error_at(span, format_args!("Too many registers in register list."));
+                        return Err(Error::Fatal);
                     }
                     amount as u8
                 } else {
-                    emit_error_at(span, "Register list requires a contant amount of registers specified".into());
-                    return Err(None);
+                    ctx.state.emit_error_at(span, format_args!("Register list requires a contant amount of registers specified"));
+                    return Err(Error::Fatal);
                 };
 
                 res.push(CleanArg::RegList {
@@ -290,23 +303,23 @@ impl ListSanitizer {
     }
 
     // check if this register spec is valid in a register list
-    fn sanitize(&mut self, span: Span, register: &Register) -> Result<(), Option<String>> {
-        sanitize_register(span, register)?;
+    fn sanitize(&mut self, ctx: &mut Context, span: ErrorSpan, register: &Register) -> Result<(), Error> {
+        sanitize_register(ctx, span, register)?;
         if let Register::Vector(v) = register {
             if v.element.is_some() {
-                emit_error_at(span, "Cannot use element specifiers inside of register lists.".into());
-                return Err(None);
+                ctx.state.emit_error_at(span, format_args!("Cannot use element specifiers inside of register lists."));
+                return Err(Error::Fatal);
             }
 
             if v.kind.is_dynamic() {
-                emit_error_at(span, "Cannot use dynamic registers inside of a comma/dash register list.".into());
-                return Err(None);
+                ctx.state.emit_error_at(span, format_args!("Cannot use dynamic registers inside of a comma/dash register list."));
+                return Err(Error::Fatal);
             }
 
             if let Some(size) = self.element_size {
                 if size != v.element_size {
-                    emit_error_at(span, "Inconsistent element sizes.".into());
-                    return Err(None);
+                    ctx.state.emit_error_at(span, format_args!("Inconsistent element sizes."));
+                    return Err(Error::Fatal);
                 }
             } else {
                 self.element_size = Some(v.element_size)
@@ -314,27 +327,27 @@ impl ListSanitizer {
 
             if let Some(lanes) = self.lanes {
                 if lanes != v.lanes {
-                    emit_error_at(span, "Inconsistent lane count.".into());
-                    return Err(None);
+                    ctx.state.emit_error_at(span, format_args!("Inconsistent lane count."));
+                    return Err(Error::Fatal);
                 }
             } else {
                 self.lanes = Some(v.lanes);
             }
         } else {
-            emit_error_at(span, "Can only use vector registers in register lists.".into());
-            return Err(None);
+            ctx.state.emit_error_at(span, format_args!("Can only use vector registers in register lists."));
+            return Err(Error::Fatal);
         }
         Ok(())
     }
 }
 
 // check that the register spec is possible
-fn sanitize_register(span: Span, register: &Register) -> Result<(), Option<String>> {
+fn sanitize_register(ctx: &mut Context, span: ErrorSpan, register: &Register) -> Result<(), Error> {
     if let Register::Vector(v) = register {
         if let Some(total) = v.full_size() {
             if total > 16 {
-                emit_error_at(span, "Overly wide vector register.".into());
-                return Err(None)
+                ctx.state.emit_error_at(span, format_args!("Overly wide vector register."));
+                return Err(Error::Fatal)
             }
         }
     }
@@ -454,30 +467,16 @@ impl Matcher {
                 }
             },
             CleanArg::JumpTarget { .. } => *self == Matcher::Offset,
-            CleanArg::Immediate { prefixed: true, value } => match self {
+            CleanArg::Immediate { value, .. } => match self {
                 Matcher::Imm
                 | Matcher::Offset => true,
                 Matcher::LitInt(v) => as_number(value) == Some(u64::from(*v)),
-                Matcher::LitFloat(v) => as_float(value) == Some(f64::from(*v)),
                 _ => false,
             },
-            CleanArg::Immediate { prefixed: false, value} => match self {
+            CleanArg::FloatImmediate { value, .. } => match self {
                 Matcher::Imm => true,
-                Matcher::Offset => true,
-                Matcher::Ident => as_ident(value).is_some(),
-                Matcher::Cond => if let Some(i) = as_ident(value) {
-                    COND_MAP.contains_key(&&*i.to_string())
-                } else {
-                    false
-                },
-                Matcher::Lit(s) => if let Some(i) = as_ident(value) {
-                    i == s
-                } else {
-                    false
-                },
-                Matcher::LitInt(v) => as_number(value) == Some(u64::from(*v)),
-                Matcher::LitFloat(v) => as_float(value) == Some(f64::from(*v)),
-                _ => false
+                Matcher::LitFloat(v) => *value == f64::from(*v),
+                _ => false,
             },
             CleanArg::Modifier { modifier, .. } => {
                 if let Matcher::Mod(list) = self {
@@ -489,10 +488,10 @@ impl Matcher {
                 }
             },
             CleanArg::Dot { .. } => *self == Matcher::Dot,
-            CleanArg::Lit { ident } => match self {
+            CleanArg::Lit { ident, .. } => match self {
                 Matcher::Ident => true,
-                Matcher::Cond => COND_MAP.contains_key(&&*ident.to_string()),
-                Matcher::Lit(s) => ident == s,
+                Matcher::Cond => COND_MAP.contains_key(&*ident.name),
+                Matcher::Lit(s) => ident.name == *s,
                 _ => false
             }
         }
@@ -569,7 +568,7 @@ pub fn match_args(args: &[CleanArg], data: &'static Opdata) -> Option<MatchData>
 }
 
 /// flatten the arg list into a linear sequence of encodable elements
-fn flatten_args(args: Vec<CleanArg>, data: &Opdata, ctx: &mut MatchData) {
+pub(super) fn flatten_args(args: Vec<CleanArg>, data: &Opdata, ctx: &mut MatchData) {
     let mut source_args = args.into_iter();
     let mut new_args = Vec::new();
 
@@ -586,15 +585,15 @@ fn flatten_args(args: Vec<CleanArg>, data: &Opdata, ctx: &mut MatchData) {
                     match kind {
                         RefKind::Base => (),
                         RefKind::Offset(value) =>
-                            new_args.push(FlatArg::Immediate { value } ),
+                            new_args.push(FlatArg::Immediate { span, value } ),
                         RefKind::PreIndexed(value) =>
-                            new_args.push(FlatArg::Immediate { value } ),
+                            new_args.push(FlatArg::Immediate { span, value } ),
                         RefKind::Indexed(index, modifier) => {
                             new_args.push(FlatArg::Direct { span, reg: index.kind_owned() } );
                             if let Some(modifier) = modifier {
                                 new_args.push(FlatArg::Modifier { span, modifier: modifier.op } );
                                 if let Some(expr) = modifier.expr {
-                                    new_args.push(FlatArg::Immediate { value: expr } );
+                                    new_args.push(FlatArg::Immediate { span, value: expr } );
                                 }
                             }
                         }
@@ -603,7 +602,7 @@ fn flatten_args(args: Vec<CleanArg>, data: &Opdata, ctx: &mut MatchData) {
                 CleanArg::RegList { span, first, element, .. } => {
                     new_args.push(FlatArg::Direct { span, reg: first.kind_owned() } );
                     if let Some(element) = element {
-                        new_args.push(FlatArg::Immediate { value: element } );
+                        new_args.push(FlatArg::Immediate { span, value: element } );
                     }
                 },
                 CleanArg::Direct { span, reg } => {
@@ -614,7 +613,7 @@ fn flatten_args(args: Vec<CleanArg>, data: &Opdata, ctx: &mut MatchData) {
                         Register::Vector(v) => {
                             new_args.push(FlatArg::Direct { span, reg: v.kind });
                             if let Some(element) = v.element {
-                                new_args.push(FlatArg::Immediate { value: element });
+                                new_args.push(FlatArg::Immediate { span, value: element });
                             }
                         }
                     }
@@ -622,26 +621,37 @@ fn flatten_args(args: Vec<CleanArg>, data: &Opdata, ctx: &mut MatchData) {
                 CleanArg::JumpTarget { jump } => {
                     new_args.push(FlatArg::JumpTarget { jump } );
                 },
-                CleanArg::Immediate { value, .. } => {
-                    new_args.push(FlatArg::Immediate { value } );
+                CleanArg::Immediate { span, value, .. } => {
+                    new_args.push(FlatArg::Immediate { span, value } );
+                },
+                CleanArg::FloatImmediate { span, value, .. } => {
+                    new_args.push(FlatArg::FloatImmediate { span, value } );
                 },
                 CleanArg::Modifier { span, modifier } => {
                     if arg_count >= 2 {
                         new_args.push(FlatArg::Modifier { span, modifier: modifier.op } );
                     }
                     if let Some(expr) = modifier.expr {
-                        new_args.push(FlatArg::Immediate { value: expr });
+                        new_args.push(FlatArg::Immediate { span, value: expr });
                     }
                 },
                 CleanArg::Dot { .. } => (),
-                CleanArg::Lit { ident } => {
-                    new_args.push(FlatArg::Lit { ident });
+                CleanArg::Lit { span, ident } => {
+                    new_args.push(FlatArg::Lit { span, ident });
                 }
             }
         }
 
         new_args.resize_with(arg_count, || FlatArg::Default);
 
-        ctx.args.extend(new_args.drain(..))
+        ctx.args.append(&mut new_args)
+    }
+}
+
+/// The value of a statically known immediate, if it is one.
+pub(super) fn as_number(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(number) => Some(number.as_u64()),
+        Value::Expr(_) => None,
     }
 }
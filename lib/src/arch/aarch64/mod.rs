@@ -1,35 +1,103 @@
-use syn::parse;
-
-mod ast;
-mod parser;
+pub mod ast;
+pub mod parser;
 mod matching;
 mod compiler;
 mod aarch64data;
 mod encoding_helpers;
 mod debug;
+mod decoder;
+mod semantics;
+pub mod introspect;
+
+use alloc::borrow::Cow;
+use core::fmt;
 
-use crate::State;
-use crate::common::{Size, Stmt, Jump, emit_error_at};
-use crate::arch::Arch;
+use crate::arch::{Arch, Error as ExprBuilderError, BasicExprBuilder};
+use crate::common::{Size, Stmt, Jump};
 use self::aarch64data::Relocation;
 
 #[cfg(feature = "dynasm_opmap")]
 pub use debug::create_opmap;
 #[cfg(feature = "dynasm_extract")]
 pub use debug::extract_opmap;
+pub use decoder::{disassemble, disassemble_listing};
+pub use aarch64data::Matcher;
+pub use semantics::{Nzcv, RegSet, Semantics};
+pub use crate::arch::Access;
+
+use alloc::string::String;
+use alloc::vec::Vec;
 
-struct Context<'a, 'b: 'a> {
-    pub state: &'a mut State<'b>
+struct Context<'a> {
+    pub state: &'a mut dyn BasicExprBuilder,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct ArchAarch64 {
 
 }
 
-impl Default for ArchAarch64 {
-    fn default() -> ArchAarch64 {
-        ArchAarch64 { }
+#[derive(Debug)]
+pub struct InstructionAarch64 {
+    pub inst: ast::Instruction,
+    pub args: Vec<ast::RawArg>,
+}
+
+pub trait AssembleAarch64 {
+    /// Turn an expression into binary format.
+    /// May error when dynamic data is present at locations that only support static values.
+    fn compile_instruction(&mut self, arch: &ArchAarch64, _: InstructionAarch64) -> Result<(), Error>;
+
+    /// Create an instruction composed from dynamic data.
+    /// Only available when the type is also capable of building new composite expressions.
+    fn build_instruction(&mut self, arch: &ArchAarch64, _: InstructionAarch64) -> Result<(), Error>
+        where Self: BasicExprBuilder;
+}
+
+/// An error while assembling, either an error in the environment or during processing.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// An error happened within the expression builder.
+    Expr(ExprBuilderError),
+    /// An error without occurred where diagnostics offer no introspection.
+    Generic {
+        message: Cow<'static, str>,
+    },
+    /// Some unspecified consistency check did not succeed.
+    /// When this occurs we have emitted one or several diagnostic messages.
+    Fatal,
+}
+
+impl From<ExprBuilderError> for Error {
+    fn from(err: ExprBuilderError) -> Self {
+        Error::Expr(err)
+    }
+}
+
+impl From<&'static str> for Error {
+    fn from(message: &'static str) -> Self {
+        Error::Generic {
+            message: Cow::Borrowed(message),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Expr(e) => e.fmt(f),
+            Error::Generic { message } => f.write_str(message),
+            Error::Fatal => f.write_str("Failed to assemble the instruction"),
+        }
+    }
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::Generic {
+            message: Cow::Owned(message),
+        }
     }
 }
 
@@ -38,61 +106,48 @@ impl Arch for ArchAarch64 {
         "aarch64"
     }
 
-    fn set_features(&mut self, features: &[syn::Ident]) {
-        if let Some(feature) = features.first() {
-            emit_error_at(feature.span(), "Arch aarch64 has no known features".into());
+    fn set_features(&mut self, features: &[String]) {
+        if !features.is_empty() {
+            eprintln!("Arch aarch64 has no known features");
         }
     }
 
     fn handle_static_reloc(&self, stmts: &mut Vec<Stmt>, reloc: Jump, size: Size) {
-        let span = reloc.span();
-
         let relocation = match size {
             Size::BYTE => Relocation::LITERAL8,
             Size::WORD => Relocation::LITERAL16,
             Size::DWORD => Relocation::LITERAL32,
             Size::QWORD => Relocation::LITERAL64,
             _ => {
-                emit_error_at(span, "Relocation of unsupported size for the current target architecture".into());
+                eprintln!("Relocation of unsupported size for the current target architecture");
                 return;
             }
         };
         let data = [relocation.to_id()];
 
-        stmts.push(Stmt::Const(0, size));
+        stmts.push(Stmt::zeroed(size));
         stmts.push(reloc.encode(&data));
     }
 
     fn default_align(&self) -> u8 {
         0
     }
+}
 
-    fn compile_instruction(&self, state: &mut State, input: parse::ParseStream) -> parse::Result<()> {
-        let mut ctx = Context {
-            state
-        };
-
-        let (instruction, args) = parser::parse_instruction(&mut ctx, input)?;
-        let span = instruction.span;
+impl<T: BasicExprBuilder> AssembleAarch64 for T {
+    fn compile_instruction(&mut self, _: &ArchAarch64, instruction: InstructionAarch64) -> Result<(), Error> {
+        let InstructionAarch64 { inst, args } = instruction;
 
-        let match_data = match matching::match_instruction(&mut ctx, &instruction, args) {
-            Err(None) => return Ok(()),
-            Err(Some(e)) => {
-                emit_error_at(span, e);
-                return Ok(())
-            }
-            Ok(m) => m
+        let mut ctx = Context {
+            state: self,
         };
 
-        match compiler::compile_instruction(&mut ctx, match_data) {
-            Err(None) => return Ok(()),
-            Err(Some(e)) => {
-                emit_error_at(span, e);
-                return Ok(())
-            }
-            Ok(()) => ()
-        }
+        compiler::compile_instruction(&mut ctx, inst, args)
+    }
 
-        Ok(())
+    fn build_instruction(&mut self, arch: &ArchAarch64, instruction: InstructionAarch64) -> Result<(), Error>
+        where Self: BasicExprBuilder
+    {
+        AssembleAarch64::compile_instruction(self, arch, instruction)
     }
-}
\ No newline at end of file
+}
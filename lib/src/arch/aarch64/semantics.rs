@@ -0,0 +1,537 @@
+//! Which operands, registers and flags an instruction reads and writes.
+//!
+//! Every form in the instruction tables carries a description of its effects on the state that
+//! is visible to a program: how each of its operands is accessed, which registers and memory it
+//! accesses implicitly, and which bits of NZCV it uses and defines. The descriptions are computed
+//! while the tables are built. Most instructions write their first operand and read the others,
+//! so only the exceptions to that are listed, together with the memory instructions, which are
+//! recognized by their mnemonic.
+//!
+//! The flags that a form uses through a condition code, or that a system register access touches,
+//! are only known once the instruction's arguments are. The descriptions of the forms list all
+//! flags that any instruction of the form may access, while `ArchAarch64::semantics` narrows them
+//! down for a specific instruction.
+
+use super::{matching, ArchAarch64, Context, Error, InstructionAarch64};
+use super::aarch64data::{Matcher, COND_MAP};
+use super::ast::{CleanArg, RegId};
+use super::decoder::Verifier;
+use super::matching::as_number;
+
+use crate::arch::Access;
+
+use bitflags::bitflags;
+
+use alloc::vec::Vec;
+
+bitflags! {
+    /// The condition flags in PSTATE that instructions use or define, at their positions in the
+    /// NZCV system register.
+    pub struct Nzcv: u32 {
+        const N = 0x8000_0000; // negative
+        const Z = 0x4000_0000; // zero
+        const C = 0x2000_0000; // carry
+        const V = 0x1000_0000; // overflow
+    }
+}
+
+/// A set of registers. Registers are identified by their full width, so `w0` and `x0` both stand
+/// for `x0`, and `b0` up to `q0` for `v0`. The zero register is never tracked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RegSet {
+    bits: u64,
+}
+
+/// The tracked registers, in the order of their bits in a `RegSet`.
+const TRACKED: [RegId; 64] = [
+    RegId::X0, RegId::X1, RegId::X2, RegId::X3, RegId::X4, RegId::X5, RegId::X6, RegId::X7,
+    RegId::X8, RegId::X9, RegId::X10, RegId::X11, RegId::X12, RegId::X13, RegId::X14, RegId::X15,
+    RegId::X16, RegId::X17, RegId::X18, RegId::X19, RegId::X20, RegId::X21, RegId::X22, RegId::X23,
+    RegId::X24, RegId::X25, RegId::X26, RegId::X27, RegId::X28, RegId::X29, RegId::X30, RegId::SP,
+    RegId::V0, RegId::V1, RegId::V2, RegId::V3, RegId::V4, RegId::V5, RegId::V6, RegId::V7,
+    RegId::V8, RegId::V9, RegId::V10, RegId::V11, RegId::V12, RegId::V13, RegId::V14, RegId::V15,
+    RegId::V16, RegId::V17, RegId::V18, RegId::V19, RegId::V20, RegId::V21, RegId::V22, RegId::V23,
+    RegId::V24, RegId::V25, RegId::V26, RegId::V27, RegId::V28, RegId::V29, RegId::V30, RegId::V31,
+];
+
+impl RegSet {
+    /// The set without any registers.
+    pub const fn empty() -> RegSet {
+        RegSet { bits: 0 }
+    }
+
+    const fn of(regs: &[RegId]) -> RegSet {
+        let mut bits = 0;
+        let mut i = 0;
+        while i < regs.len() {
+            bits |= RegSet::bit(regs[i]);
+            i += 1;
+        }
+        RegSet { bits }
+    }
+
+    const fn bit(reg: RegId) -> u64 {
+        match reg {
+            RegId::XZR => 0,
+            RegId::SP => 1 << 31,
+            _ => 1 << ((reg as u8 & 0x1F) | ((reg as u8 & 0x40) >> 1)),
+        }
+    }
+
+    const fn union(self, other: RegSet) -> RegSet {
+        RegSet { bits: self.bits | other.bits }
+    }
+
+    /// Returns if `reg` is in the set. The zero register is never in the set.
+    pub fn contains(&self, reg: RegId) -> bool {
+        self.bits & RegSet::bit(reg) != 0
+    }
+
+    /// Returns if the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    /// The registers in the set, with the general purpose registers and the stack pointer first.
+    pub fn iter(&self) -> impl Iterator<Item=RegId> {
+        let bits = self.bits;
+        TRACKED.iter().enumerate().filter(move |&(i, _)| bits & (1 << i) != 0).map(|(_, &reg)| reg)
+    }
+}
+
+/// The maximum amount of operands of a form, not counting the optional ones.
+const MAX_OPERANDS: usize = 5;
+
+/// The effects of an instruction form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Semantics {
+    operands: [Access; MAX_OPERANDS],
+    operand_count: u8,
+    writeback: bool,
+    implicit_reads: RegSet,
+    implicit_writes: RegSet,
+    implicit_memory: Access,
+    flags_read: Nzcv,
+    flags_written: Nzcv,
+}
+
+impl Semantics {
+    /// How each of the operands is accessed, in the order in which they are written. Like the
+    /// matchers of a form, this includes the `.` and condition of `b.cond` and any modifiers.
+    /// Register lists are accessed as a whole.
+    pub fn operands(&self) -> &[Access] {
+        &self.operands[.. usize::from(self.operand_count)]
+    }
+
+    /// Returns if the base register of the memory operand is updated, as with pre- and
+    /// post-indexed addressing.
+    pub fn writeback(&self) -> bool {
+        self.writeback
+    }
+
+    /// The registers that are read without being an operand, like `x30` for `ret`.
+    pub fn implicit_reads(&self) -> RegSet {
+        self.implicit_reads
+    }
+
+    /// The registers that are written without being an operand, like `x30` for `bl`.
+    pub fn implicit_writes(&self) -> RegSet {
+        self.implicit_writes
+    }
+
+    /// How memory that isn't referenced by an operand is accessed, like the literal pool by
+    /// `ldr x0, label`.
+    pub fn implicit_memory(&self) -> Access {
+        self.implicit_memory
+    }
+
+    /// The flags that are used.
+    pub fn flags_read(&self) -> Nzcv {
+        self.flags_read
+    }
+
+    /// The flags that are defined.
+    pub fn flags_written(&self) -> Nzcv {
+        self.flags_written
+    }
+
+    /// Describes a form with the given matchers and operand accesses. Only registers and memory
+    /// references are accessed, so `access` lists just those, in order. Forms that are
+    /// described together can have less operands than listed.
+    const fn form(matchers: &[Matcher], access: &[Access]) -> Semantics {
+        let mut operands = [Access::Unused; MAX_OPERANDS];
+        let mut count = 0;
+        let mut accessed = 0;
+        let mut writeback = false;
+        let mut flags_read = Nzcv::empty();
+
+        let mut i = 0;
+        while i < matchers.len() {
+            match matchers[i] {
+                Matcher::End => {
+                    i += 1;
+                    continue;
+                },
+                // the condition is only known once the instruction is
+                Matcher::Cond => flags_read = Nzcv::all(),
+                // a post-index follows the base register
+                Matcher::RefPre => writeback = true,
+                Matcher::RefBase => writeback = i + 1 < matchers.len(),
+                _ => ()
+            }
+
+            if is_accessed(&matchers[i]) {
+                assert!(accessed < access.len(), "missing operand access");
+                operands[count] = access[accessed];
+                accessed += 1;
+            }
+            count += 1;
+            i += 1;
+        }
+
+        Semantics {
+            operands,
+            operand_count: count as u8,
+            writeback,
+            implicit_reads: RegSet::empty(),
+            implicit_writes: RegSet::empty(),
+            implicit_memory: Access::Unused,
+            flags_read,
+            flags_written: Nzcv::empty(),
+        }
+    }
+
+    /// Describes a load or store. The registers in front of the memory reference are accessed
+    /// as `first` and `rest`, the referenced memory as `memory`, and any registers after it are
+    /// read. Forms without a memory reference access a literal instead.
+    const fn transfer(matchers: &[Matcher], first: Access, rest: Access, memory: Access) -> Semantics {
+        let mut access = [Access::Read; MAX_OPERANDS];
+        let mut seen = false;
+        let mut count = 0;
+
+        let mut i = 0;
+        while i < matchers.len() {
+            if is_accessed(&matchers[i]) {
+                match matchers[i] {
+                    Matcher::RefBase | Matcher::RefOffset | Matcher::RefPre | Matcher::RefIndex => {
+                        access[count] = memory;
+                        seen = true;
+                    },
+                    _ if !seen => access[count] = if count == 0 { first } else { rest },
+                    _ => ()
+                }
+                count += 1;
+            }
+            i += 1;
+        }
+
+        let semantics = Semantics::form(matchers, &access);
+        if seen {
+            semantics
+        } else {
+            semantics.memory(memory)
+        }
+    }
+
+    const fn reads(mut self, regs: RegSet) -> Semantics {
+        self.implicit_reads = self.implicit_reads.union(regs);
+        self
+    }
+
+    const fn writes(mut self, regs: RegSet) -> Semantics {
+        self.implicit_writes = self.implicit_writes.union(regs);
+        self
+    }
+
+    const fn modifies(self, regs: RegSet) -> Semantics {
+        self.reads(regs).writes(regs)
+    }
+
+    const fn memory(mut self, access: Access) -> Semantics {
+        self.implicit_memory = self.implicit_memory.union(access);
+        self
+    }
+
+    const fn uses(mut self, flags: Nzcv) -> Semantics {
+        self.flags_read = self.flags_read.union(flags);
+        self
+    }
+
+    const fn sets(mut self, flags: Nzcv) -> Semantics {
+        self.flags_written = self.flags_written.union(flags);
+        self
+    }
+
+    /// Narrows the description of the form that `mnemonic` was matched to down to the effects of
+    /// the instruction with the given arguments.
+    fn refine(mut self, mnemonic: &str, matchers: &[Matcher], args: &[CleanArg]) -> Semantics {
+        // optional operands that were left out
+        self.operand_count = self.operand_count.min(args.len() as u8);
+
+        let operands = matchers.iter().filter(|&&matcher| matcher != Matcher::End);
+        let mut immediates = Vec::new();
+        for (matcher, arg) in operands.zip(args) {
+            match (matcher, arg) {
+                (Matcher::Cond, CleanArg::Lit { ident, .. }) => {
+                    self.flags_read = COND_MAP.get(ident.name.as_str()).map_or(Nzcv::all(), |&cond| condition(cond));
+                },
+                (Matcher::Imm, CleanArg::Immediate { value, .. }) => immediates.push(as_number(value)),
+                _ => ()
+            }
+        }
+
+        match (mnemonic, &immediates[..]) {
+            // only the nzcv system register holds flags
+            ("mrs", &[Some(sysreg)]) if sysreg != NZCV_SYSREG => self.flags_read = Nzcv::empty(),
+            ("msr", &[Some(sysreg)]) if sysreg != NZCV_SYSREG => self.flags_written = Nzcv::empty(),
+            // the mask selects the flags that are set
+            ("rmif", &[_, Some(mask)]) => {
+                self.flags_read = Nzcv::empty();
+                self.flags_written = Nzcv::from_bits_truncate((mask as u32 & 0xF) << 28);
+            },
+            _ => ()
+        }
+        self
+    }
+}
+
+/// The encoding of the nzcv system register in `mrs` and `msr`.
+const NZCV_SYSREG: u64 = 0x5A10;
+
+const U: Access = Access::Unused;
+const R: Access = Access::Read;
+const W: Access = Access::Write;
+const RW: Access = Access::ReadWrite;
+
+const N: Nzcv = Nzcv::N;
+const Z: Nzcv = Nzcv::Z;
+const C: Nzcv = Nzcv::C;
+const V: Nzcv = Nzcv::V;
+const NZCV: Nzcv = Nzcv::all();
+
+/// All operands are read.
+const READ: &[Access] = &[R, R, R, R, R];
+
+/// The first operand is both read and written, the others are read.
+const MODIFY: &[Access] = &[RW, R, R, R, R];
+
+/// The flags tested by a condition code.
+fn condition(cond: u8) -> Nzcv {
+    match cond >> 1 {
+        0 => Z,
+        1 => C,
+        2 => N,
+        3 => V,
+        4 => C | Z,
+        5 => N | V,
+        6 => N | Z | V,
+        _ => Nzcv::empty()
+    }
+}
+
+const fn is_accessed(matcher: &Matcher) -> bool {
+    matches!(matcher,
+        Matcher::W | Matcher::X | Matcher::WSP | Matcher::XSP |
+        Matcher::B | Matcher::H | Matcher::S | Matcher::D | Matcher::Q |
+        Matcher::V(_) | Matcher::VStatic(_, _) | Matcher::VElement(_) |
+        Matcher::VElementStatic(_, _) | Matcher::VStaticElement(_, _) |
+        Matcher::RegList(_, _) | Matcher::RegListStatic(_, _, _) | Matcher::RegListElement(_, _) |
+        Matcher::RefBase | Matcher::RefOffset | Matcher::RefPre | Matcher::RefIndex
+    )
+}
+
+/// Returns if a matcher picks a single element of a vector register, which leaves the other
+/// elements as they are when it's written.
+const fn is_element(matcher: &Matcher) -> bool {
+    matches!(matcher,
+        Matcher::VElement(_) | Matcher::VElementStatic(_, _) | Matcher::VStaticElement(_, _) |
+        Matcher::RegListElement(_, _)
+    )
+}
+
+const fn starts_with(name: &[u8], prefix: &[u8]) -> bool {
+    if name.len() < prefix.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < prefix.len() {
+        if name[i] != prefix[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Returns if `op` is the operation of an atomic memory instruction, as in `ldadd` or `stadd`.
+const fn is_atomic(op: &[u8]) -> bool {
+    starts_with(op, b"add") || starts_with(op, b"clr") || starts_with(op, b"eor") ||
+    starts_with(op, b"set") || starts_with(op, b"smax") || starts_with(op, b"smin") ||
+    starts_with(op, b"umax") || starts_with(op, b"umin")
+}
+
+/// Describes the effects of a form of the mnemonic `name` with the given matchers.
+pub(super) const fn describe(name: &[u8], matchers: &[Matcher]) -> Semantics {
+    if let Some(semantics) = memory(name, matchers) {
+        semantics
+    } else {
+        general(name, matchers)
+    }
+}
+
+/// Loads, stores, atomics and prefetches.
+const fn memory(name: &[u8], matchers: &[Matcher]) -> Option<Semantics> {
+    let transfer = Semantics::transfer;
+
+    // a single element is loaded into each register of the list
+    let element = !matchers.is_empty() && is_element(&matchers[0]);
+
+    Some(match name {
+        [b'c', b'a', b's', b'p', ..] => Semantics::form(matchers, &[RW, RW, R, R, RW]),
+        [b'c', b'a', b's', ..] => transfer(matchers, RW, R, RW),
+        [b's', b'w', b'p', ..] => transfer(matchers, R, W, RW),
+        [b'p', b'r', b'f', ..] => transfer(matchers, U, U, U),
+        [b'l', b'd', op @ ..] if is_atomic(op) => transfer(matchers, R, W, RW),
+        [b's', b't', op @ ..] if is_atomic(op) => transfer(matchers, R, R, RW),
+        // the status of exclusive stores is written to the first operand
+        [b's', b't', b'x', ..] |
+        [b's', b't', b'l', b'x', ..] => transfer(matchers, W, R, W),
+        [b'l', b'd', ..] if element => transfer(matchers, RW, RW, R),
+        [b'l', b'd', ..] => transfer(matchers, W, W, R),
+        [b's', b't', ..] => transfer(matchers, R, R, W),
+        _ => return None
+    })
+}
+
+const fn general(name: &[u8], matchers: &[Matcher]) -> Semantics {
+    use super::ast::RegId::*;
+
+    let form = Semantics::form;
+    let regs = RegSet::of;
+
+    match name {
+        // flags
+        b"adds" | b"subs" | b"negs" | b"ands" | b"bics" => form(matchers, &[W, R, R, R]).sets(NZCV),
+        b"cmp" | b"cmn" | b"tst" | b"fcmp" | b"fcmpe" => form(matchers, READ).sets(NZCV),
+        b"ccmp" | b"ccmn" | b"fccmp" | b"fccmpe" => form(matchers, READ).sets(NZCV),
+        b"adc" | b"sbc" | b"ngc" => form(matchers, &[W, R, R]).uses(C),
+        b"adcs" | b"sbcs" | b"ngcs" => form(matchers, &[W, R, R]).uses(C).sets(NZCV),
+        b"cfinv" => form(matchers, &[]).uses(C).sets(C),
+        b"setf8" | b"setf16" => form(matchers, READ).sets(N.union(Z).union(V)),
+        b"rmif" => form(matchers, READ).uses(NZCV).sets(NZCV),
+        b"fjcvtzs" => form(matchers, &[W, R]).sets(NZCV),
+        b"mrs" => form(matchers, &[W]).uses(NZCV),
+        // the immediate form writes PSTATE fields other than the flags
+        b"msr" if matches!(matchers[0], Matcher::Imm) => form(matchers, &[R]).sets(NZCV),
+        b"eret" | b"drps" => form(matchers, &[]).sets(NZCV),
+        b"eretaa" | b"eretab" => form(matchers, &[]).reads(regs(&[SP])).sets(NZCV),
+
+        // branches
+        b"bl" => form(matchers, &[]).writes(regs(&[X30])),
+        b"blr" | b"blraa" | b"blrab" | b"blraaz" | b"blrabz" => form(matchers, READ).writes(regs(&[X30])),
+        b"ret" if matchers.is_empty() => form(matchers, &[]).reads(regs(&[X30])),
+        b"retaa" | b"retab" => form(matchers, &[]).reads(regs(&[X30, SP])),
+        b"br" | b"braa" | b"brab" | b"braaz" | b"brabz" | b"ret" |
+        b"cbz" | b"cbnz" | b"tbz" | b"tbnz" => form(matchers, READ),
+
+        // pointer authentication
+        b"pacia" | b"pacib" | b"pacda" | b"pacdb" | b"autia" | b"autib" | b"autda" | b"autdb" |
+        b"paciza" | b"pacizb" | b"pacdza" | b"pacdzb" | b"autiza" | b"autizb" | b"autdza" | b"autdzb" |
+        b"xpaci" | b"xpacd" => form(matchers, MODIFY),
+        b"pacia1716" | b"pacib1716" | b"autia1716" | b"autib1716" => form(matchers, &[]).modifies(regs(&[X17])).reads(regs(&[X16])),
+        b"paciasp" | b"pacibsp" | b"autiasp" | b"autibsp" => form(matchers, &[]).modifies(regs(&[X30])).reads(regs(&[SP])),
+        b"paciaz" | b"pacibz" | b"autiaz" | b"autibz" | b"xpaclri" => form(matchers, &[]).modifies(regs(&[X30])),
+
+        // system
+        b"dc" => form(matchers, &[R]).memory(W),
+        b"sys" | b"at" | b"ic" | b"tlbi" | b"cfp" | b"dvp" | b"cpp" => form(matchers, READ),
+
+        // instructions that only replace part of their destination
+        b"movk" | b"bfm" | b"bfi" | b"bfxil" | b"bfc" | b"tbx" |
+        b"bsl" | b"bit" | b"bif" | b"sli" | b"sri" | b"ssra" | b"usra" | b"srsra" | b"ursra" |
+        b"xtn2" | b"sqxtn2" | b"uqxtn2" | b"sqxtun2" | b"fcvtn2" | b"fcvtxn2" |
+        b"shrn2" | b"rshrn2" | b"sqshrn2" | b"uqshrn2" | b"sqrshrn2" | b"uqrshrn2" | b"sqshrun2" | b"sqrshrun2" |
+        b"addhn2" | b"raddhn2" | b"subhn2" | b"rsubhn2" => form(matchers, MODIFY),
+
+        // accumulating instructions
+        b"mla" | b"mls" | b"fmla" | b"fmls" | b"fmlal" | b"fmlal2" | b"fmlsl" | b"fmlsl2" | b"fcmla" |
+        b"sdot" | b"udot" | b"sqrdmlah" | b"sqrdmlsh" | b"suqadd" | b"usqadd" |
+        b"saba" | b"uaba" | b"sabal" | b"sabal2" | b"uabal" | b"uabal2" | b"sadalp" | b"uadalp" |
+        b"smlal" | b"smlal2" | b"smlsl" | b"smlsl2" | b"umlal" | b"umlal2" | b"umlsl" | b"umlsl2" |
+        b"sqdmlal" | b"sqdmlal2" | b"sqdmlsl" | b"sqdmlsl2" => form(matchers, MODIFY),
+
+        // cryptography, which updates its state in place
+        b"aese" | b"aesd" | b"sha1c" | b"sha1m" | b"sha1p" | b"sha1su0" | b"sha1su1" |
+        b"sha256h" | b"sha256h2" | b"sha256su0" | b"sha256su1" |
+        b"sha512h" | b"sha512h2" | b"sha512su0" | b"sha512su1" |
+        b"sm3partw1" | b"sm3partw2" | b"sm3tt1a" | b"sm3tt1b" | b"sm3tt2a" | b"sm3tt2b" | b"sm4e" => form(matchers, MODIFY),
+
+        // the vector immediate forms combine the immediate with the destination
+        b"orr" | b"bic" if matches!(matchers[0], Matcher::V(_)) && matches!(matchers[1], Matcher::Imm) => form(matchers, MODIFY),
+
+        _ if !matchers.is_empty() && is_element(&matchers[0]) => form(matchers, MODIFY),
+        _ => form(matchers, &[W, R, R, R, R]),
+    }
+}
+
+impl ArchAarch64 {
+    /// Returns the effects of `instruction`, with the flags that it uses through its condition
+    /// code or touches through the nzcv system register narrowed down to those.
+    pub fn semantics(&self, instruction: &InstructionAarch64) -> Result<Semantics, Error> {
+        let mut verifier = Verifier::default();
+        let mut ctx = Context {
+            state: &mut verifier,
+        };
+
+        let args = matching::sanitize_args(&mut ctx, instruction.args.clone())?;
+        let data = matching::match_instruction(&mut ctx, &instruction.inst, args.clone())?.data;
+        Ok(data.semantics.refine(&instruction.inst.ident.name, data.matchers, &args))
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::ast::{Instruction, RawArg, RegKind, RegScalar, Register};
+    use crate::common::{Ident, Jump, JumpKind, Size};
+
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn x(id: RegId) -> RawArg {
+        RawArg::Direct { reg: Register::Scalar(RegScalar { kind: RegKind::Static(id), size: Size::QWORD }) }
+    }
+
+    fn semantics(name: &str, args: Vec<RawArg>) -> Semantics {
+        let instruction = InstructionAarch64 { inst: Instruction { ident: Ident { name: name.to_string() } }, args };
+        ArchAarch64::default().semantics(&instruction).unwrap()
+    }
+
+    fn b_cond(cond: &str) -> Semantics {
+        let target = Jump::new(JumpKind::Global(Ident { name: "target".to_string() }), None);
+        semantics("b", vec![RawArg::Dot, RawArg::Lit { ident: Ident { name: cond.to_string() } }, RawArg::JumpTarget { jump: target }])
+    }
+
+    #[test]
+    fn flags() {
+        let add = semantics("add", vec![x(RegId::X0), x(RegId::X1), x(RegId::X2)]);
+        assert_eq!(add.operands(), [W, R, R]);
+        assert_eq!(add.flags_read(), Nzcv::empty());
+        assert_eq!(add.flags_written(), Nzcv::empty());
+
+        let adds = semantics("adds", vec![x(RegId::X0), x(RegId::X1), x(RegId::X2)]);
+        assert_eq!(adds.operands(), [W, R, R]);
+        assert_eq!(adds.flags_read(), Nzcv::empty());
+        assert_eq!(adds.flags_written(), Nzcv::all());
+
+        // the condition of a branch selects the flags it uses
+        let beq = b_cond("eq");
+        assert_eq!(beq.flags_read(), Z);
+        assert_eq!(beq.flags_written(), Nzcv::empty());
+        assert!(beq.operands().iter().all(|&access| access == U));
+        assert_eq!(b_cond("lt").flags_read(), N | V);
+        assert_eq!(b_cond("hi").flags_read(), C | Z);
+    }
+}
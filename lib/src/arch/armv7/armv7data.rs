@@ -0,0 +1,419 @@
+use crate::collections::{HashMap, hash_map};
+use core::fmt::{self, Display};
+
+use lazy_static::lazy_static;
+use bitflags::bitflags;
+
+use super::ArmMode;
+use super::ast::{Modifier, RegId};
+
+use alloc::vec::Vec;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Matcher {
+    // core registers
+    /// any core register
+    R,
+    /// a static core register in the range r0-r7
+    Lo,
+    /// a static core register in the range r8-r15
+    Hi,
+    /// the same static register as the first argument
+    Same,
+    /// a specific static register
+    Reg(RegId),
+    /// a core register, optionally followed by a writeback marker
+    RBang,
+
+    // floating point / simd registers
+    S,
+    D,
+    Q,
+
+    // immediates
+    /// any immediate
+    Imm,
+    /// a static immediate that fits into the given field
+    SmallImm(&'static ImmField),
+    /// a static immediate that can be encoded as a modified immediate, after the given transformation
+    ModImm(ModImmKind),
+    /// a static floating point immediate that can be encoded in 8 bits
+    FImm,
+    /// the floating point immediate 0.0
+    FZero,
+
+    // modifiers
+    /// a shift by an immediate, or rrx
+    Shift,
+    /// a shift by a register
+    ShiftReg,
+    /// a rotation by 8, 16 or 24 bits
+    Ror,
+
+    // references
+    /// [rn, #±imm], [rn, #±imm]! or [rn], #±imm
+    Ref,
+    /// [rn, #±imm]
+    RefOffset,
+    /// [rn, #imm], with a non-negative immediate
+    RefPos,
+    /// [rn, #imm], with rn in r0-r7 and a static immediate that fits into the given field
+    RefLo(&'static ImmField),
+    /// [sp, #imm], with a static immediate that fits into the given field
+    RefSp(&'static ImmField),
+    /// [rn]
+    RefBase,
+    /// [rn, ±rm{, shift}], [rn, ±rm{, shift}]! or [rn], ±rm{, shift}
+    RefIdx,
+    /// [rn, ±rm], [rn, ±rm]! or [rn], ±rm
+    RefIdxPlain,
+    /// [rn, rm{, lsl #imm}]
+    RefIdxLsl,
+    /// [rn, rm], with rn and rm in r0-r7
+    RefIdxLo,
+
+    // register lists
+    /// a list of core registers
+    List,
+    /// a list of exactly one core register
+    ListOne,
+    /// a list of registers in the range r0-r7, and optionally the given register
+    ListLo(RegId),
+    /// a list of consecutive single precision registers
+    SList,
+    /// a list of consecutive double precision registers
+    DList,
+
+    // jump offsets
+    Offset,
+
+    // literals
+    /// the given special register name
+    Lit(&'static str),
+    /// a barrier option
+    Barrier,
+
+    // possible op mnemnonic end (everything after this point uses the default encoding)
+    End,
+}
+
+/// A transformation applied to a value before it is encoded as a modified immediate.
+/// This allows instructions to accept values that can only be encoded by their complement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModImmKind {
+    Plain,
+    Inverted,
+    Negated,
+}
+
+impl ModImmKind {
+    pub fn apply(self, value: u32) -> u32 {
+        match self {
+            ModImmKind::Plain => value,
+            ModImmKind::Inverted => !value,
+            ModImmKind::Negated => value.wrapping_neg(),
+        }
+    }
+}
+
+/// The layout of an immediate field within an instruction.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ImmField {
+    /// The amount of significant bits of the immediate.
+    pub bits: u8,
+    /// The amount of low bits that have to be zero and are not encoded.
+    pub scale: u8,
+    /// Slices of the immediate that are scattered over the instruction, as (source bit, length, destination bit).
+    pub fields: &'static [(u8, u8, u8)],
+}
+
+impl ImmField {
+    /// Returns true if the given static value can be encoded in this field.
+    pub fn fits(&self, value: u64) -> bool {
+        value & ((1 << self.scale) - 1) == 0 && value >> self.bits == 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    // commands that advance the argument pointer
+    R(u8), // encode a core register, reference base or index register into a 4-bit bitfield.
+    REven(u8), // same as R, but requires that the register is even.
+    RNext, // encode that this register should be the previous register, plus one
+    Rlo(u8), // encode a register in the range r0-r7 into a 3-bit bitfield.
+    RSplit(u8, u8), // encode a register into a 3-bit bitfield at .0 and its highest bit at .1.
+    Sreg(u8, u8), // encode a single precision register into a 4-bit bitfield at .0 and its lowest bit at .1.
+    Dreg(u8, u8), // encode a double precision register into a 4-bit bitfield at .0 and its highest bit at .1.
+    Qreg(u8, u8), // encode a quad register as the double precision register it overlaps with.
+
+    Imm(&'static ImmField), // encodes an immediate according to the given field layout
+    UImm(&'static ImmField, u8), // encodes the magnitude of an immediate, and its sign as the U bit at .1
+    WidthDec(&'static ImmField), // encodes the value - 1. The previous argument is the lsb of the bitfield.
+    Usumdec(&'static ImmField), // encodes the value of the previous arg + the value of the current arg - 1
+    ModImm(ModImmKind), // encodes a modified immediate
+    FImm, // encodes a floating point immediate into imm4H:imm4L
+
+    ShiftAmt(Modifier, &'static ImmField), // encodes the amount of a shift instruction
+    Shift, // encodes a shift modifier
+    ShiftReg, // encodes a shift modifier by a register
+    IndexLsl(u8), // encodes a lsl modifier of an index register into a 2-bit bitfield
+    Rotation(u8), // encodes a rotation into a 2-bit bitfield
+
+    Mode(u8, u8), // encodes the addressing mode of a reference into the P bit at .0 and the W bit at .1
+    Index(u8, u8), // encodes an index register at .0 and its sign as the U bit at .1
+    Bang(u8), // encodes a writeback marker
+
+    List, // encodes a core register list as a 16-bit bitmask
+    ListOne(u8), // encodes the single register of a register list into a 4-bit bitfield
+    ListLo(u8), // encodes the low registers of a register list as an 8-bit bitmask, and the extra register at .0
+    VList, // encodes the first register and the length of a floating point register list
+
+    Barrier, // encodes a barrier option, defaulting to sy
+
+    // Offsets
+    Offset(Relocation),
+
+    // special commands
+    A, // advances the argument pointer, only needed to skip over an argument.
+    C, // moves the argument pointer back.
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum Relocation {
+    // A32 b, bl: 26 bits, word aligned
+    A32_B = 0,
+    // A32 blx: 26 bits, halfword aligned
+    A32_BLX = 1,
+    // A32 ldr, ldrb: 12 bits + sign
+    A32_LDR = 2,
+    // A32 ldrh, ldrsb, ldrsh, ldrd: 8 bits + sign, split
+    A32_LDRH = 3,
+    // vldr: 10 bits + sign, word aligned
+    A32_VLDR = 4,
+    // T32 cbz, cbnz: 7 bits, halfword aligned, forward only
+    T32_CBZ = 5,
+    // T16 b<c>: 9 bits, halfword aligned
+    T32_B8 = 6,
+    // T16 b: 12 bits, halfword aligned
+    T32_B11 = 7,
+    // T32 b<c>.w: 21 bits, halfword aligned
+    T32_B20 = 8,
+    // T32 b.w, bl: 25 bits, halfword aligned
+    T32_B24 = 9,
+    // T32 blx: 25 bits, word aligned
+    T32_BLX = 10,
+    // T16 ldr: 10 bits, word aligned, forward only
+    T32_LDR_N = 11,
+    // T32 ldr, ldrb, ldrh, ldrsb, ldrsh: 12 bits + sign
+    T32_LDR = 12,
+    // T32 vldr: 10 bits + sign, word aligned
+    T32_VLDR = 13,
+    // 8-bit literal
+    LITERAL8 = 14,
+    // 16-bit literal
+    LITERAL16 = 15,
+    // 32-bit literal
+    LITERAL32 = 17,
+    // 64-bit literal
+    LITERAL64 = 21,
+}
+
+impl Relocation {
+    pub fn to_id(self) -> u8 {
+        self as u8
+    }
+}
+
+bitflags! {
+    pub struct Flags: u8 {
+        /// This instruction cannot be executed conditionally.
+        const UNCOND     = 0x01;
+        /// A 16-bit thumb encoding.
+        const NARROW     = 0x02;
+        /// A 16-bit thumb encoding that only sets the flags outside of an IT block. Only used when no condition is given.
+        const OUTSIDE_IT = 0x04;
+        /// A 16-bit thumb encoding that only leaves the flags alone inside of an IT block. Only used when a condition is given.
+        const INSIDE_IT  = 0x08;
+    }
+}
+
+impl Flags {
+    const fn make(bits: u8) -> Flags {
+        Flags { bits }
+    }
+}
+
+bitflags! {
+    pub struct Features: u32 {
+        const V7    = 0x0000_0001;
+        const VFP   = 0x0000_0002;
+        const VFPV4 = 0x0000_0004;
+        const NEON  = 0x0000_0008;
+        const IDIV  = 0x0000_0010;
+    }
+}
+
+impl Features {
+    const fn make(bits: u32) -> Features {
+        Features { bits }
+    }
+
+    pub fn from_name(name: &str) -> Option<Features> {
+        match name {
+            "v7" => Some(Features::V7),
+            "vfp" => Some(Features::VFP),
+            "vfpv4" => Some(Features::VFPV4),
+            "neon" => Some(Features::NEON),
+            "idiv" => Some(Features::IDIV),
+            _ => None
+        }
+    }
+}
+
+impl Display for Features {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut keys = Vec::new();
+        if self.contains(Features::V7) { keys.push("v7"); }
+        if self.contains(Features::VFP) { keys.push("vfp"); }
+        if self.contains(Features::VFPV4) { keys.push("vfpv4"); }
+        if self.contains(Features::NEON) { keys.push("neon"); }
+        if self.contains(Features::IDIV) { keys.push("idiv"); }
+        for (i, k) in keys.into_iter().enumerate() {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str(k)?;
+        }
+        Ok(())
+    }
+}
+
+/*
+ * Immediate field layouts
+ */
+
+macro_rules! ImmField {
+    ($bits:expr, $scale:expr, [ $( ($src:expr, $len:expr, $dst:expr) ),* ]) => {
+        ImmField { bits: $bits, scale: $scale, fields: &[ $( ($src, $len, $dst) ),* ] }
+    }
+}
+
+// A32 formats
+pub const IMM24: ImmField = ImmField!(24, 0, [(0, 24, 0)]);
+pub const IMM16_A: ImmField = ImmField!(16, 0, [(0, 12, 0), (12, 4, 16)]);
+pub const IMM16_SPLIT: ImmField = ImmField!(16, 0, [(0, 4, 0), (4, 12, 8)]);
+pub const IMM12: ImmField = ImmField!(12, 0, [(0, 12, 0)]);
+pub const IMM8_SPLIT: ImmField = ImmField!(8, 0, [(0, 4, 0), (4, 4, 8)]);
+pub const IMM5_A: ImmField = ImmField!(5, 0, [(0, 5, 7)]);
+pub const IMM5_16: ImmField = ImmField!(5, 0, [(0, 5, 16)]);
+
+// T32 formats
+pub const IMM16_T: ImmField = ImmField!(16, 0, [(0, 8, 0), (8, 3, 12), (11, 1, 26), (12, 4, 16)]);
+pub const IMM12_T: ImmField = ImmField!(12, 0, [(0, 8, 0), (8, 3, 12), (11, 1, 26)]);
+pub const IMM5_T: ImmField = ImmField!(5, 0, [(0, 2, 6), (2, 3, 12)]);
+pub const IMM5_0: ImmField = ImmField!(5, 0, [(0, 5, 0)]);
+
+// shared formats
+pub const IMM8: ImmField = ImmField!(8, 0, [(0, 8, 0)]);
+pub const IMM8_S2: ImmField = ImmField!(10, 2, [(2, 8, 0)]);
+
+// T16 formats
+pub const ZERO: ImmField = ImmField!(0, 0, []);
+pub const IMM3_N: ImmField = ImmField!(3, 0, [(0, 3, 6)]);
+pub const IMM5_N: ImmField = ImmField!(5, 0, [(0, 5, 6)]);
+pub const IMM5_S1_N: ImmField = ImmField!(6, 1, [(1, 5, 6)]);
+pub const IMM5_S2_N: ImmField = ImmField!(7, 2, [(2, 5, 6)]);
+pub const IMM7_S2_N: ImmField = ImmField!(9, 2, [(2, 7, 0)]);
+
+#[derive(Debug, Clone, Copy)]
+pub struct Opdata {
+    /// The base template for the encoding. Narrow thumb instructions only use the low 16 bits,
+    /// wide thumb instructions contain the first halfword in the high 16 bits.
+    pub base: u32,
+    /// A set of matchers capable of matching the instruction encoding that this instruction represents.
+    pub matchers: &'static [Matcher],
+    /// A sequence of encoder commands that check the matched instruction on validity and whose output gets orred together with the original template at runtime.
+    pub commands: &'static [Command],
+    /// Restrictions on when this encoding can be used.
+    pub flags: Flags,
+    /// The extension this instruction is part of.
+    pub features: Features,
+}
+
+macro_rules! SingleOp {
+    ( $base:expr, [ $( $matcher:expr ),* ], [ $( $command:expr ),* ], [ $( $flag:ident ),* ], $( $feature:ident )|+ ) => {
+        {
+            const MATCHERS: &'static [Matcher] = {
+                #[allow(unused_imports)]
+                use self::Matcher::*;
+                &[ $(
+                    $matcher
+                ),* ]
+            };
+            const COMMANDS: &'static [Command] = {
+                #[allow(unused_imports)]
+                use self::Command::*;
+                &[ $(
+                    $command
+                ),* ]
+            };
+            Opdata {
+                base: $base,
+                matchers: MATCHERS,
+                commands: COMMANDS,
+                flags: Flags::make( 0 $( | Flags::$flag.bits() )* ),
+                features: Features::make( $( Features::$feature.bits() )|+ ),
+            }
+        }
+    }
+}
+
+macro_rules! Ops {
+    ( $( $name:tt = [ $( $base:tt = [ $( $matcher:expr ),* ] => [ $( $command:expr ),* ], [ $( $flag:ident ),* ], $( $feature:ident )|+ ; )+ ] )* ) => {
+        [ $(
+            (
+                $name,
+                &[ $(
+                    SingleOp!( $base, [ $( $matcher ),* ], [ $( $command ),* ], [ $( $flag ),* ], $( $feature )|+ )
+                ),+ ] as &[_]
+            )
+        ),* ]
+    }
+}
+
+pub fn get_mnemonic_data(name: &str, mode: ArmMode) -> Option<&'static [Opdata]> {
+    match mode {
+        ArmMode::Arm => A32_OPMAP.get(&name).cloned(),
+        ArmMode::Thumb => T32_OPMAP.get(&name).cloned(),
+    }
+}
+
+#[allow(dead_code)]
+pub fn mnemnonics(mode: ArmMode) -> hash_map::Keys<'static, &'static str, &'static [Opdata]> {
+    match mode {
+        ArmMode::Arm => A32_OPMAP.keys(),
+        ArmMode::Thumb => T32_OPMAP.keys(),
+    }
+}
+
+lazy_static! {
+    static ref A32_OPMAP: HashMap<&'static str, &'static [Opdata]> = {
+        use self::Relocation::*;
+        use self::ModImmKind::*;
+        use super::ast::Modifier::*;
+
+        static MAP: &[(&str, &[Opdata])] = &include!("opmap_a32.rs");
+        MAP.iter().cloned().collect()
+    };
+
+    static ref T32_OPMAP: HashMap<&'static str, &'static [Opdata]> = {
+        use self::Relocation::*;
+        use self::ModImmKind::*;
+        use super::ast::Modifier::*;
+        use super::ast::RegId::*;
+
+        static MAP: &[(&str, &[Opdata])] = &include!("opmap_t32.rs");
+        MAP.iter().cloned().collect()
+    };
+}
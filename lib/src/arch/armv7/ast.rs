@@ -0,0 +1,310 @@
+use crate::arch::ErrorSpan;
+use crate::common::{Expr, Ident, Jump, Value};
+
+use alloc::vec::Vec;
+
+
+/// An armv7 register. Either a static register or a family identifier + expression to choose the register
+#[derive(Debug, Clone)]
+pub enum Register {
+    Static(RegId),
+    Dynamic(RegFamily, Expr)
+}
+
+// a register identifier. This identifies an architecturally completely separate register.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum RegId {
+    // core registers
+    R0 = 0x00, R1 = 0x01, R2 = 0x02, R3 = 0x03,
+    R4 = 0x04, R5 = 0x05, R6 = 0x06, R7 = 0x07,
+    R8 = 0x08, R9 = 0x09, R10= 0x0A, R11= 0x0B,
+    R12= 0x0C, SP = 0x0D, LR = 0x0E, PC = 0x0F,
+
+    // single precision floating point registers
+    S0 = 0x20, S1 = 0x21, S2 = 0x22, S3 = 0x23,
+    S4 = 0x24, S5 = 0x25, S6 = 0x26, S7 = 0x27,
+    S8 = 0x28, S9 = 0x29, S10= 0x2A, S11= 0x2B,
+    S12= 0x2C, S13= 0x2D, S14= 0x2E, S15= 0x2F,
+    S16= 0x30, S17= 0x31, S18= 0x32, S19= 0x33,
+    S20= 0x34, S21= 0x35, S22= 0x36, S23= 0x37,
+    S24= 0x38, S25= 0x39, S26= 0x3A, S27= 0x3B,
+    S28= 0x3C, S29= 0x3D, S30= 0x3E, S31= 0x3F,
+
+    // double precision floating point / 64-bit simd registers
+    D0 = 0x40, D1 = 0x41, D2 = 0x42, D3 = 0x43,
+    D4 = 0x44, D5 = 0x45, D6 = 0x46, D7 = 0x47,
+    D8 = 0x48, D9 = 0x49, D10= 0x4A, D11= 0x4B,
+    D12= 0x4C, D13= 0x4D, D14= 0x4E, D15= 0x4F,
+    D16= 0x50, D17= 0x51, D18= 0x52, D19= 0x53,
+    D20= 0x54, D21= 0x55, D22= 0x56, D23= 0x57,
+    D24= 0x58, D25= 0x59, D26= 0x5A, D27= 0x5B,
+    D28= 0x5C, D29= 0x5D, D30= 0x5E, D31= 0x5F,
+
+    // 128-bit simd registers
+    Q0 = 0x60, Q1 = 0x61, Q2 = 0x62, Q3 = 0x63,
+    Q4 = 0x64, Q5 = 0x65, Q6 = 0x66, Q7 = 0x67,
+    Q8 = 0x68, Q9 = 0x69, Q10= 0x6A, Q11= 0x6B,
+    Q12= 0x6C, Q13= 0x6D, Q14= 0x6E, Q15= 0x6F,
+}
+
+// register family. CORE = r0-r15, SINGLE = s0-s31, DOUBLE = d0-d31, QUAD = q0-q15
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum RegFamily {
+    CORE   = 0,
+    SINGLE = 1,
+    DOUBLE = 2,
+    QUAD   = 3,
+}
+
+impl RegId {
+    /// Encode this RegId in a 5-bit value
+    pub fn code(self) -> u8 {
+        self as u8 & 0x1F
+    }
+
+    /// Returns what family this Regid is from
+    pub fn family(self) -> RegFamily {
+        match self as u8 >> 5 {
+            0 => RegFamily::CORE,
+            1 => RegFamily::SINGLE,
+            2 => RegFamily::DOUBLE,
+            3 => RegFamily::QUAD,
+            _ => unreachable!()
+        }
+    }
+}
+
+impl Register {
+    /// Get the 5-bit code of this Register. Returns None if it was dynamic
+    pub fn code(&self) -> Option<u8> {
+        match self {
+            Register::Static(id) => Some(id.code()),
+            Register::Dynamic(_, _) => None
+        }
+    }
+
+    /// Returns the family that this register is of
+    pub fn family(&self) -> RegFamily {
+        match *self {
+            Register::Static(id) => id.family(),
+            Register::Dynamic(family, _) => family
+        }
+    }
+
+    /// Returns true if this Register is dynamic
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            Register::Static(_) => false,
+            Register::Dynamic(_, _) => true
+        }
+    }
+
+    /// Returns true if this is a static core register in the range r0-r7
+    pub fn is_low(&self) -> bool {
+        match self {
+            Register::Static(id) => id.family() == RegFamily::CORE && id.code() < 8,
+            Register::Dynamic(_, _) => false
+        }
+    }
+
+    /// Returns true if this is the given static register
+    pub fn is(&self, reg: RegId) -> bool {
+        match self {
+            Register::Static(id) => *id == reg,
+            Register::Dynamic(_, _) => false
+        }
+    }
+}
+
+/**
+ * Modifier types
+ */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Modifier {
+    LSL = 0,
+    LSR = 1,
+    ASR = 2,
+    ROR = 3,
+    RRX = 4,
+}
+
+impl Modifier {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Modifier::LSL => "LSL",
+            Modifier::LSR => "LSR",
+            Modifier::ASR => "ASR",
+            Modifier::ROR => "ROR",
+            Modifier::RRX => "RRX",
+        }
+    }
+
+    /// The 2-bit shift type this modifier is encoded as. RRX is encoded as ROR #0.
+    pub fn shift_type(self) -> u32 {
+        match self {
+            Modifier::RRX => 3,
+            m => m as u32
+        }
+    }
+}
+
+/// The amount of a shift modifier.
+#[derive(Debug, Clone)]
+pub enum ShiftAmount {
+    Immediate(Value),
+    Register(Register),
+}
+
+#[derive(Debug, Clone)]
+pub struct ModifyExpr {
+    pub op: Modifier,
+    pub amount: Option<ShiftAmount>
+}
+
+impl ModifyExpr {
+    pub fn new(op: Modifier, amount: Option<ShiftAmount>) -> ModifyExpr {
+        ModifyExpr {
+            op,
+            amount
+        }
+    }
+}
+
+/**
+ * Memory ref item types
+ */
+
+#[derive(Debug)]
+pub enum RefItem {
+    Direct {
+        reg: Register,
+        // a subtracted index register, i.e. [r0, -r1]
+        negated: bool,
+    },
+    Immediate {
+        value: Value
+    },
+    Modifier {
+        modifier: ModifyExpr
+    }
+}
+
+/// An item of a register list. Either a single register or a range of registers.
+#[derive(Debug)]
+pub enum RegListItem {
+    Single(Register),
+    Range(Register, Register),
+}
+
+// basic parse results, before we start doing any kind of checking
+#[derive(Debug)]
+pub enum RawArg {
+    // A memory reference
+    Reference {
+        items: Vec<RefItem>,
+        bang: bool
+    },
+    // A register list, i.e. {r0-r3, lr}
+    RegList {
+        items: Vec<RegListItem>
+    },
+    // direct register reference, with an optional writeback marker (r0!)
+    Direct {
+        reg: Register,
+        bang: bool
+    },
+    // a subtracted register, as used by post-indexed addressing
+    Negated {
+        reg: Register
+    },
+    // jump target. Also used by PC-relative loads
+    JumpTarget {
+        jump: Jump
+    },
+    // just an arbitrary expression
+    Immediate {
+        value: Value
+    },
+    // a floating point literal. These can only be encoded statically.
+    FloatImmediate {
+        value: f64
+    },
+    // a shift modifier
+    Modifier {
+        modifier: ModifyExpr
+    },
+    // an ident, not intended to be parsed as an expression. Condition codes, barrier options and
+    // special registers.
+    Lit {
+        ident: Ident
+    }
+}
+
+// Contains the actual instruction mnemnonic.
+#[derive(Debug)]
+pub struct Instruction {
+    pub ident: Ident
+}
+
+/// The addressing mode of a memory reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexMode {
+    // [base, offset]
+    Offset,
+    // [base, offset]!
+    PreIndexed,
+    // [base], offset
+    PostIndexed,
+}
+
+// flat arg list after matching, for encoding
+#[derive(Debug)]
+pub enum FlatArg {
+    Direct {
+        span: ErrorSpan,
+        reg: Register
+    },
+    // an index register of a memory reference
+    Index {
+        span: ErrorSpan,
+        reg: Register,
+        negated: bool
+    },
+    // the addressing mode of a memory reference
+    Mode {
+        span: ErrorSpan,
+        mode: IndexMode
+    },
+    // a writeback marker
+    Bang {
+        span: ErrorSpan,
+        bang: bool
+    },
+    // a list of static registers of a single family, as a bitmask of register codes
+    RegList {
+        span: ErrorSpan,
+        family: RegFamily,
+        mask: u32
+    },
+    Immediate {
+        span: ErrorSpan,
+        value: Value
+    },
+    FloatImmediate {
+        span: ErrorSpan,
+        value: f64
+    },
+    Modifier {
+        span: ErrorSpan,
+        modifier: ModifyExpr
+    },
+    JumpTarget {
+        jump: Jump
+    },
+    Lit {
+        span: ErrorSpan,
+        ident: Ident
+    },
+    Default
+}
@@ -0,0 +1,564 @@
+use super::matching::{self, MatchData, static_u32};
+use super::armv7data::{Command, ImmField, Flags, IMM5_A, IMM5_T};
+use super::encoding_helpers::{encode_modified_immediate, encode_floating_point_immediate};
+use super::parser::{ARMV7_CONDITION_MAP, ARMV7_BARRIER_OPTION_MAP};
+use super::{Context, Error, ArmMode};
+use super::ast::{Instruction, RawArg, FlatArg, Register, RegFamily, Modifier, ModifyExpr, ShiftAmount, IndexMode};
+
+use crate::arch::{BasicExprBuilderExt, ErrorSpan};
+use crate::common::{Expr, Number, NumericRepr, Stmt, Value, bitmask64};
+
+use alloc::vec::Vec;
+use alloc::format;
+
+pub(super) fn compile_instruction(ctx: &mut Context, instruction: Instruction, args: Vec<RawArg>) -> Result<(), Error> {
+    let name = &*instruction.ident.name;
+
+    // the it instruction encodes the conditions of the instructions that follow it
+    if is_it_instruction(name) {
+        return compile_it(ctx, name, args);
+    }
+
+    let match_data = matching::match_instruction(ctx, name, args)?;
+
+    // determine if the features required for this op are fulfilled
+    if !ctx.features.contains(match_data.data.features) {
+        return Err(Error::DisabledFeatures(match_data.data.features - ctx.features));
+    }
+
+    encode_instruction(ctx, match_data)
+}
+
+fn encode_instruction(ctx: &mut Context, data: MatchData) -> Result<(), Error> {
+    let mut cursor = 0usize;
+
+    // All static bitfields (compile-time constant) will be encoded into this map of (offset, bitfield)
+    let mut statics = Vec::new();
+    // All dynamic bitfields (run-time determined) will be encoded into this list of already shifted expressions
+    let mut dynamics = Vec::new();
+    // Any relocations will be encoded into this list
+    let mut relocations = Vec::new();
+
+    for command in data.data.commands.iter() {
+        // special commands that don't check the current arg
+        match *command {
+            Command::C => {
+                cursor -= 1;
+                continue;
+            },
+            Command::A => {
+                cursor += 1;
+                continue;
+            },
+            _ => ()
+        }
+
+        let arg = data.args.get(cursor).expect("Invalid encoding data, tried to process more arguments than given");
+
+        match *arg {
+            FlatArg::Direct { span, ref reg } => encode_register_command(ctx, span, reg, *command, &data.args, cursor, &mut statics, &mut dynamics)?,
+            FlatArg::Index { span, ref reg, negated } => match *command {
+                Command::Index(offset, ubit) => {
+                    encode_register(ctx, reg, 0xF, &[(offset, 0)], &mut statics, &mut dynamics)?;
+                    if !negated {
+                        statics.push((ubit, 1));
+                    }
+                },
+                command => encode_register_command(ctx, span, reg, command, &data.args, cursor, &mut statics, &mut dynamics)?,
+            },
+            FlatArg::Mode { mode, .. } => match *command {
+                Command::Mode(p, w) => match mode {
+                    IndexMode::Offset => statics.push((p, 1)),
+                    IndexMode::PreIndexed => {
+                        statics.push((p, 1));
+                        statics.push((w, 1));
+                    },
+                    // A32 uses P=0 W=1 for unprivileged accesses instead
+                    IndexMode::PostIndexed => if ctx.mode == ArmMode::Thumb {
+                        statics.push((w, 1));
+                    },
+                },
+                _ => panic!("Invalid argument processor")
+            },
+            FlatArg::Bang { bang, .. } => match *command {
+                Command::Bang(offset) => if bang {
+                    statics.push((offset, 1));
+                },
+                _ => panic!("Invalid argument processor")
+            },
+            FlatArg::RegList { family, mask, .. } => match *command {
+                Command::List => statics.push((0, mask)),
+                Command::ListOne(offset) => statics.push((offset, mask.trailing_zeros())),
+                Command::ListLo(bit) => {
+                    statics.push((0, mask & 0xFF));
+                    if mask >> 8 != 0 {
+                        statics.push((bit, 1));
+                    }
+                },
+                Command::VList => {
+                    let first = mask.trailing_zeros();
+                    let count = mask.count_ones();
+                    match family {
+                        RegFamily::SINGLE => {
+                            statics.push((12, first >> 1));
+                            statics.push((22, first & 1));
+                            statics.push((0, count));
+                        },
+                        RegFamily::DOUBLE => {
+                            statics.push((12, first & 0xF));
+                            statics.push((22, first >> 4));
+                            statics.push((0, count * 2));
+                        },
+                        _ => panic!("Invalid argument processor")
+                    }
+                },
+                _ => panic!("Invalid argument processor")
+            },
+            FlatArg::Immediate { span, value } => match *command {
+                Command::Imm(field) => encode_field(ctx, span, field, value, &mut statics, &mut dynamics)?,
+                Command::UImm(field, ubit) => match value {
+                    Value::Number(number) => {
+                        let value = as_i128(number);
+                        if value < 0 {
+                            encode_static_field(ctx, span, field, -value, &mut statics)?;
+                        } else {
+                            encode_static_field(ctx, span, field, value, &mut statics)?;
+                            statics.push((ubit, 1));
+                        }
+                    },
+                    // dynamic offsets are assumed to be positive
+                    Value::Expr(expr) => {
+                        encode_dynamic_field(ctx, field, expr, &mut dynamics)?;
+                        statics.push((ubit, 1));
+                    }
+                },
+                Command::WidthDec(field) => {
+                    let lsb = previous_immediate(&data.args, cursor);
+                    match (lsb, value) {
+                        (Value::Number(lsb), Value::Number(width)) => {
+                            let width = check_bitfield(ctx, span, lsb, width)?;
+                            encode_static_field(ctx, span, field, width - 1, &mut statics)?;
+                        },
+                        (_, Value::Number(width)) => {
+                            let width = as_i128(width);
+                            if !(1 ..= 32).contains(&width) {
+                                ctx.state.emit_error_at(span, format_args!("Bitfield width has to be in the range 1-32"));
+                                return Err(Error::Fatal);
+                            }
+                            encode_static_field(ctx, span, field, width - 1, &mut statics)?;
+                        },
+                        (_, Value::Expr(width)) => {
+                            let width = ctx.state.add_else_err(width, signed_value(-1))?;
+                            encode_dynamic_field(ctx, field, width, &mut dynamics)?;
+                        }
+                    }
+                },
+                Command::Usumdec(field) => {
+                    let lsb = previous_immediate(&data.args, cursor);
+                    match (lsb, value) {
+                        (Value::Number(lsb_number), Value::Number(width)) => {
+                            let width = check_bitfield(ctx, span, lsb_number, width)?;
+                            encode_static_field(ctx, span, field, as_i128(lsb_number) + width - 1, &mut statics)?;
+                        },
+                        (Value::Expr(lsb), Value::Number(width)) => {
+                            let msb = ctx.state.add_else_err(lsb, signed_value(as_i128(width) as i64 - 1))?;
+                            encode_dynamic_field(ctx, field, msb, &mut dynamics)?;
+                        },
+                        (lsb, Value::Expr(width)) => {
+                            let msb = ctx.state.add_else_err(width, lsb)?;
+                            let msb = ctx.state.add_else_err(msb, signed_value(-1))?;
+                            encode_dynamic_field(ctx, field, msb, &mut dynamics)?;
+                        }
+                    }
+                },
+                Command::ModImm(kind) => {
+                    let encoded = match value {
+                        Value::Number(number) => static_u32(number)
+                            .and_then(|value| encode_modified_immediate(ctx.mode, kind.apply(value))),
+                        Value::Expr(_) => None
+                    };
+                    match encoded {
+                        Some(encoded) => statics.push((0, encoded)),
+                        None => {
+                            ctx.state.emit_error_at(span, format_args!("Value cannot be encoded as a modified immediate"));
+                            return Err(Error::Fatal);
+                        }
+                    }
+                },
+                Command::ShiftAmt(modifier, field) => encode_shift_amount(ctx, span, modifier, field, value, &mut statics, &mut dynamics)?,
+                _ => panic!("Invalid argument processor")
+            },
+            FlatArg::FloatImmediate { span, value } => match *command {
+                Command::FImm => match encode_floating_point_immediate(value) {
+                    Some(imm8) => {
+                        statics.push((16, u32::from(imm8 >> 4)));
+                        statics.push((0, u32::from(imm8 & 0xF)));
+                    },
+                    None => {
+                        ctx.state.emit_error_at(span, format_args!("Immediate cannot be encoded as a floating point immediate"));
+                        return Err(Error::Fatal);
+                    }
+                },
+                _ => panic!("Invalid argument processor")
+            },
+            FlatArg::Modifier { span, ref modifier } => match *command {
+                Command::Shift => {
+                    let (type_offset, field) = match ctx.mode {
+                        ArmMode::Arm => (5, &IMM5_A),
+                        ArmMode::Thumb => (4, &IMM5_T),
+                    };
+                    statics.push((type_offset, modifier.op.shift_type()));
+                    if let Some(ShiftAmount::Immediate(value)) = modifier.amount {
+                        encode_shift_amount(ctx, span, modifier.op, field, value, &mut statics, &mut dynamics)?;
+                    }
+                },
+                Command::ShiftReg => {
+                    statics.push((5, modifier.op.shift_type()));
+                    match modifier.amount {
+                        Some(ShiftAmount::Register(ref reg)) => encode_register(ctx, reg, 0xF, &[(8, 0)], &mut statics, &mut dynamics)?,
+                        _ => panic!("Invalid argument processor")
+                    }
+                },
+                Command::IndexLsl(offset) => {
+                    let value = shift_immediate(modifier);
+                    match value {
+                        Value::Number(number) => {
+                            let amount = as_i128(number);
+                            if !(0 ..= 3).contains(&amount) {
+                                ctx.state.emit_error_at(span, format_args!("Index shift has to be in the range 0-3"));
+                                return Err(Error::Fatal);
+                            }
+                            statics.push((offset, amount as u32));
+                        },
+                        Value::Expr(expr) => dynamics.push(ctx.state.mask_shift_else_err(expr, 0x3, offset as i8)?),
+                    }
+                },
+                Command::Rotation(offset) => {
+                    let value = shift_immediate(modifier);
+                    match value {
+                        Value::Number(number) => {
+                            let amount = as_i128(number);
+                            if amount & 7 != 0 || !(0 ..= 24).contains(&amount) {
+                                ctx.state.emit_error_at(span, format_args!("Rotation has to be 0, 8, 16 or 24"));
+                                return Err(Error::Fatal);
+                            }
+                            statics.push((offset, (amount >> 3) as u32));
+                        },
+                        Value::Expr(expr) => dynamics.push(ctx.state.mask_shift_else_err(expr, 0x18, offset as i8 - 3)?),
+                    }
+                },
+                _ => panic!("Invalid argument processor")
+            },
+            FlatArg::Lit { ref ident, .. } => match *command {
+                Command::Barrier => {
+                    let option = *ARMV7_BARRIER_OPTION_MAP.get(&*ident.name).expect("bad command data");
+                    statics.push((0, u32::from(option)));
+                },
+                _ => panic!("Invalid argument processor")
+            },
+            FlatArg::JumpTarget { ref jump } => match *command {
+                Command::Offset(relocation) => {
+                    // what kind of relocation is it
+                    let data = [relocation.to_id()];
+
+                    // encode the complete relocation
+                    let stmt = jump.clone().encode(&data);
+
+                    relocations.push(stmt);
+                },
+                _ => panic!("Invalid argument processor")
+            },
+            FlatArg::Default => match *command {
+                // omitted offsets are a positive zero
+                Command::UImm(_, ubit) => statics.push((ubit, 1)),
+                // omitted barrier options are a full system barrier
+                Command::Barrier => statics.push((0, 0xF)),
+                // omitted immediates and shifts are zero
+                Command::Imm(_)
+                | Command::Shift
+                | Command::IndexLsl(_)
+                | Command::Rotation(_) => (),
+                _ => panic!("Invalid argument processor")
+            }
+        }
+
+        cursor += 1;
+    }
+
+    // sanity
+    if cursor != data.args.len() {
+        panic!("Not enough command processors");
+    }
+
+    // apply all statics to bits
+    let mut bits = data.data.base;
+    for (offset, value) in statics {
+        bits |= value << offset;
+    }
+
+    let narrow = data.data.flags.contains(Flags::NARROW);
+    if ctx.mode == ArmMode::Arm && !data.data.flags.contains(Flags::UNCOND) {
+        bits |= u32::from(data.cond.unwrap_or(COND_AL)) << 28;
+    }
+
+    emit_instruction(ctx, bits, dynamics, narrow)?;
+
+    // generate code to be emitted for relocations
+    for stmt in relocations {
+        ctx.state.push(stmt);
+    }
+
+    Ok(())
+}
+
+/// Encodes a register argument according to the given command.
+#[allow(clippy::too_many_arguments)]
+fn encode_register_command(ctx: &mut Context, span: ErrorSpan, reg: &Register, command: Command, args: &[FlatArg], cursor: usize, statics: &mut Vec<(u8, u32)>, dynamics: &mut Vec<Expr>) -> Result<(), Error> {
+    match command {
+        Command::R(offset) => encode_register(ctx, reg, 0xF, &[(offset, 0)], statics, dynamics),
+        Command::REven(offset) => {
+            if let Register::Static(id) = *reg {
+                if id.code() & 1 != 0 || id.code() == 14 {
+                    ctx.state.emit_error_at(span, format_args!("Field only supports even registers, excluding lr"));
+                    return Err(Error::Fatal);
+                }
+            }
+            encode_register(ctx, reg, 0xF, &[(offset, 0)], statics, dynamics)
+        },
+        Command::RNext => {
+            let prev = match args[cursor - 1] {
+                FlatArg::Direct { ref reg, .. } => reg,
+                _ => panic!("Invalid encoding data, RNext without a previous register")
+            };
+            if let (Register::Static(prev), Register::Static(id)) = (prev, reg) {
+                if id.code() != prev.code() + 1 {
+                    ctx.state.emit_error_at(span, format_args!("Register pair has to consist of consecutive registers"));
+                    return Err(Error::Fatal);
+                }
+            }
+            Ok(())
+        },
+        Command::Rlo(offset) => {
+            if let Register::Static(id) = *reg {
+                if id.code() >= 8 {
+                    ctx.state.emit_error_at(span, format_args!("Field only supports registers r0-r7"));
+                    return Err(Error::Fatal);
+                }
+            }
+            encode_register(ctx, reg, 0x7, &[(offset, 0)], statics, dynamics)
+        },
+        Command::RSplit(offset, bit) => encode_register(ctx, reg, 0xF, &[(offset, 0), (bit, 3)], statics, dynamics),
+        // the low bit of single precision registers is stored separately
+        Command::Sreg(offset, bit) => encode_register(ctx, reg, 0x1F, &[(offset, 1), (bit, 0)], statics, dynamics),
+        // the high bit of double precision registers is stored separately
+        Command::Dreg(offset, bit) => encode_register(ctx, reg, 0x1F, &[(offset, 0), (bit, 4)], statics, dynamics),
+        // quad registers are encoded as the first double precision register they overlap with
+        Command::Qreg(offset, bit) => encode_register(ctx, reg, 0xF, &[(offset + 1, 0), (bit, 3)], statics, dynamics),
+        _ => panic!("Invalid argument processor")
+    }
+}
+
+/// Encodes a register into one or several bitfields, described as (offset, lowest bit of the register code).
+fn encode_register(ctx: &mut Context, reg: &Register, mask: u8, fields: &[(u8, u8)], statics: &mut Vec<(u8, u32)>, dynamics: &mut Vec<Expr>) -> Result<(), Error> {
+    for &(offset, bit) in fields {
+        // each field extends until the lowest bit of the next field
+        let end = fields.iter().map(|&(_, b)| b).filter(|&b| b > bit).min().unwrap_or(8);
+        let field_mask = u64::from(mask) & bitmask64(end) & !bitmask64(bit);
+
+        match *reg {
+            Register::Static(id) => statics.push((offset, ((u64::from(id.code()) & field_mask) >> bit) as u32)),
+            Register::Dynamic(_, expr) => dynamics.push(ctx.state.mask_shift_else_err(expr, field_mask, offset as i8 - bit as i8)?),
+        }
+    }
+
+    Ok(())
+}
+
+/// Encodes an unsigned immediate into a (possibly scattered) immediate field of an instruction.
+fn encode_field(ctx: &mut Context, span: ErrorSpan, field: &ImmField, value: Value, statics: &mut Vec<(u8, u32)>, dynamics: &mut Vec<Expr>) -> Result<(), Error> {
+    match value {
+        Value::Number(number) => encode_static_field(ctx, span, field, as_i128(number), statics),
+        Value::Expr(expr) => encode_dynamic_field(ctx, field, expr, dynamics),
+    }
+}
+
+fn encode_static_field(ctx: &mut Context, span: ErrorSpan, field: &ImmField, value: i128, statics: &mut Vec<(u8, u32)>) -> Result<(), Error> {
+    if value < 0 {
+        ctx.state.emit_error_at(span, format_args!("Value too small"));
+        return Err(Error::Fatal);
+    } else if value & ((1 << field.scale) - 1) != 0 {
+        ctx.state.emit_error_at(span, format_args!("Unrepresentable value"));
+        return Err(Error::Fatal);
+    } else if value >> field.bits != 0 {
+        ctx.state.emit_error_at(span, format_args!("Value too large"));
+        return Err(Error::Fatal);
+    }
+
+    for &(src, len, dst) in field.fields {
+        statics.push((dst, ((value >> src) as u32) & (bitmask64(len) as u32)));
+    }
+    Ok(())
+}
+
+fn encode_dynamic_field(ctx: &mut Context, field: &ImmField, expr: Expr, dynamics: &mut Vec<Expr>) -> Result<(), Error> {
+    for &(src, len, dst) in field.fields {
+        dynamics.push(ctx.state.mask_shift_else_err(expr, bitmask64(len) << src, dst as i8 - src as i8)?);
+    }
+    Ok(())
+}
+
+/// Encodes the amount of a shift. lsr and asr shifts by 32 are encoded as a shift by 0.
+fn encode_shift_amount(ctx: &mut Context, span: ErrorSpan, modifier: Modifier, field: &ImmField, value: Value, statics: &mut Vec<(u8, u32)>, dynamics: &mut Vec<Expr>) -> Result<(), Error> {
+    match value {
+        Value::Number(number) => {
+            let amount = as_i128(number);
+            let (min, max) = match modifier {
+                Modifier::LSL => (0, 31),
+                Modifier::LSR
+                | Modifier::ASR => (1, 32),
+                Modifier::ROR => (1, 31),
+                Modifier::RRX => (0, 0),
+            };
+            if amount < min || amount > max {
+                ctx.state.emit_error_at(span, format_args!("{} shift amount has to be in the range {}-{}", modifier.as_str(), min, max));
+                return Err(Error::Fatal);
+            }
+            encode_static_field(ctx, span, field, amount & 0x1F, statics)
+        },
+        Value::Expr(expr) => encode_dynamic_field(ctx, field, expr, dynamics),
+    }
+}
+
+/// Returns the immediate amount of a modifier that was matched to have one.
+fn shift_immediate(modifier: &ModifyExpr) -> Value {
+    match modifier.amount {
+        Some(ShiftAmount::Immediate(value)) => value,
+        _ => panic!("Invalid argument processor")
+    }
+}
+
+/// Returns the value of the immediate argument that precedes the current one.
+fn previous_immediate(args: &[FlatArg], cursor: usize) -> Value {
+    match args[cursor - 1] {
+        FlatArg::Immediate { value, .. } => value,
+        _ => panic!("Invalid encoding data, bitfield width without a preceding lsb")
+    }
+}
+
+/// Checks that a bitfield with the given static lsb and width fits in a register, returning the width.
+fn check_bitfield(ctx: &mut Context, span: ErrorSpan, lsb: Number, width: Number) -> Result<i128, Error> {
+    let lsb = as_i128(lsb);
+    let width = as_i128(width);
+
+    if width < 1 {
+        ctx.state.emit_error_at(span, format_args!("Bitfield width has to be at least 1"));
+        return Err(Error::Fatal);
+    } else if lsb + width > 32 {
+        ctx.state.emit_error_at(span, format_args!("Bitfield exceeds the size of the register"));
+        return Err(Error::Fatal);
+    }
+    Ok(width)
+}
+
+/// Interprets a number according to its signedness.
+fn as_i128(number: Number) -> i128 {
+    if number.repr().signed {
+        i128::from(number.as_i64())
+    } else {
+        i128::from(number.as_u64())
+    }
+}
+
+/// A signed 64-bit constant, used as an addend to dynamic values.
+fn signed_value(value: i64) -> Value {
+    Value::Number(Number::from_u64_and_repr(value as u64, NumericRepr::I64))
+}
+
+/// Emits a single instruction, combining the static bits with any dynamic bitfields.
+///
+/// Narrow thumb instructions are emitted as a single halfword. Wide thumb instructions are
+/// emitted as two halfwords, the first of which is stored in the high bits of the encoding.
+fn emit_instruction(ctx: &mut Context, bits: u32, dynamics: Vec<Expr>, narrow: bool) -> Result<(), Error> {
+    let swap = ctx.mode == ArmMode::Thumb && !narrow;
+    let bits = if swap { bits.rotate_left(16) } else { bits };
+
+    let mut dynamics = dynamics.into_iter();
+
+    let mut expr = if let Some(expr) = dynamics.next() {
+        expr
+    } else if narrow {
+        ctx.state.push(Stmt::Const(Value::Word(bits as u16)));
+        return Ok(());
+    } else {
+        ctx.state.push(Stmt::Const(Value::Dword(bits)));
+        return Ok(());
+    };
+
+    for field in dynamics {
+        expr = ctx.state.bit_or_else_err(expr, field.into())?;
+    }
+    if swap {
+        let high = ctx.state.mask_shift_else_err(expr, 0xFFFF_0000, -16)?;
+        let low = ctx.state.mask_shift_else_err(expr, 0x0000_FFFF, 16)?;
+        expr = ctx.state.bit_or_else_err(high, low.into())?;
+    }
+    if bits != 0 {
+        expr = ctx.state.bit_or_else_err(expr, Value::Dword(bits))?;
+    }
+
+    expr.repr = if narrow { NumericRepr::U16 } else { NumericRepr::U32 };
+    ctx.state.push(Stmt::Const(Value::Expr(expr)));
+    Ok(())
+}
+
+/*
+ * The it instruction
+ */
+
+const COND_AL: u8 = 0xE;
+const IT: u32 = 0xBF00;
+
+/// Returns true if the mnemonic is it, followed by up to three t or e characters.
+fn is_it_instruction(name: &str) -> bool {
+    match name.strip_prefix("it") {
+        Some(rest) => rest.len() <= 3 && rest.chars().all(|c| c == 't' || c == 'e'),
+        None => false
+    }
+}
+
+/// Encodes an it instruction. These are validated but emit nothing in arm mode, so code that
+/// uses conditional instructions can be shared between arm and thumb mode.
+fn compile_it(ctx: &mut Context, name: &str, args: Vec<RawArg>) -> Result<(), Error> {
+    let firstcond = match &args[..] {
+        [RawArg::Lit { ident }] => match ARMV7_CONDITION_MAP.get(&*ident.name) {
+            Some(&cond) => cond,
+            None => {
+                ctx.state.emit_error_at(ErrorSpan::argument(0), format_args!("'{}' is not a valid condition code", ident.name));
+                return Err(Error::Fatal);
+            }
+        },
+        _ => return Err(format!("'{}': instruction format mismatch, expected one of the following forms:\n>>> {} cond", name, name).into())
+    };
+
+    let pattern = &name[2 ..];
+    if firstcond == COND_AL && pattern.contains('e') {
+        ctx.state.emit_error_at(ErrorSpan::instruction_part(0), format_args!("An it block with condition al cannot contain else instructions"));
+        return Err(Error::Fatal);
+    }
+
+    if ctx.mode == ArmMode::Arm {
+        return Ok(());
+    }
+
+    // each instruction in the block is encoded as the lowest bit of its condition, followed by a terminating 1
+    let base = u32::from(firstcond & 1);
+    let mut mask = 1 << (3 - pattern.len());
+    for (i, c) in pattern.chars().enumerate() {
+        let bit = if c == 't' { base } else { base ^ 1 };
+        mask |= bit << (3 - i);
+    }
+
+    let bits = IT | (u32::from(firstcond) << 4) | mask;
+    ctx.state.push(Stmt::Const(Value::Word(bits as u16)));
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use super::armv7data::{Opdata, Matcher, Flags, Features};
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::format;
+
+
+pub fn format_opdata_list(name: &str, data: &[Opdata]) -> String {
+    let mut forms = Vec::new();
+
+    for data in data {
+        forms.push(format_opdata(name, data));
+    }
+
+    forms.join("\n")
+}
+
+pub fn format_opdata(name: &str, data: &Opdata) -> String {
+    let mut buf = format!(">>> {}", name);
+    if data.flags.contains(Flags::NARROW) {
+        buf.push_str(".n");
+    }
+
+    let mut args = Vec::new();
+    let mut optional = false;
+    let mut regs = 0;
+
+    for matcher in data.matchers {
+        let arg = match matcher {
+            Matcher::R => { regs += 1; format!("r{}", regs) },
+            Matcher::Lo => { regs += 1; format!("r{}(r0-r7)", regs) },
+            Matcher::Hi => { regs += 1; format!("r{}(r8-r15)", regs) },
+            Matcher::Same => "r1".to_string(),
+            Matcher::Reg(id) => format!("{:?}", id).to_lowercase(),
+            Matcher::RBang => { regs += 1; format!("r{}{{!}}", regs) },
+            Matcher::S => { regs += 1; format!("s{}", regs) },
+            Matcher::D => { regs += 1; format!("d{}", regs) },
+            Matcher::Q => { regs += 1; format!("q{}", regs) },
+            Matcher::Imm => "#imm".to_string(),
+            Matcher::SmallImm(field) => format!("#uimm{}", field.bits),
+            Matcher::ModImm(_) => "#modimm".to_string(),
+            Matcher::FImm => "#fimm".to_string(),
+            Matcher::FZero => "#0.0".to_string(),
+            Matcher::Shift => "shift #imm".to_string(),
+            Matcher::ShiftReg => "shift rs".to_string(),
+            Matcher::Ror => "ror #imm".to_string(),
+            Matcher::Ref => "[rn{, #±imm}]{!} | [rn], #±imm".to_string(),
+            Matcher::RefOffset => "[rn{, #±imm}]".to_string(),
+            Matcher::RefPos => "[rn{, #imm}]".to_string(),
+            Matcher::RefLo(field) => format!("[rn(r0-r7){{, #uimm{}}}]", field.bits),
+            Matcher::RefSp(field) => format!("[sp{{, #uimm{}}}]", field.bits),
+            Matcher::RefBase => "[rn]".to_string(),
+            Matcher::RefIdx => "[rn, ±rm{, shift #imm}]{!} | [rn], ±rm{, shift #imm}".to_string(),
+            Matcher::RefIdxPlain => "[rn, ±rm]{!} | [rn], ±rm".to_string(),
+            Matcher::RefIdxLsl => "[rn, rm{, lsl #imm}]".to_string(),
+            Matcher::RefIdxLo => "[rn(r0-r7), rm(r0-r7)]".to_string(),
+            Matcher::List => "{r*}".to_string(),
+            Matcher::ListOne => "{rt}".to_string(),
+            Matcher::ListLo(id) => format!("{{r0-r7, {:?}}}", id).to_lowercase(),
+            Matcher::SList => "{sn-sm}".to_string(),
+            Matcher::DList => "{dn-dm}".to_string(),
+            Matcher::Offset => "label".to_string(),
+            Matcher::Lit(name) => (*name).to_string(),
+            Matcher::Barrier => "option".to_string(),
+            Matcher::End => {
+                optional = true;
+                continue;
+            }
+        };
+        if optional {
+            args.push(format!("{{{}}}", arg));
+        } else {
+            args.push(arg);
+        }
+    }
+
+    if !args.is_empty() {
+        buf.push(' ');
+        buf.push_str(&args.join(", "));
+    }
+
+    if data.features != Features::V7 {
+        buf.push_str(&format!(" ({})", data.features));
+    }
+
+    buf
+}
@@ -0,0 +1,73 @@
+use super::ArmMode;
+
+/// Encodes a value as a modified immediate of the given instruction set.
+pub fn encode_modified_immediate(mode: ArmMode, value: u32) -> Option<u32> {
+    match mode {
+        ArmMode::Arm => encode_a32_modified_immediate(value),
+        ArmMode::Thumb => encode_t32_modified_immediate(value),
+    }
+}
+
+/// Encodes a value as an A32 modified immediate: an 8-bit value rotated right by an even amount.
+/// Returns the 12-bit rotate:imm8 field.
+pub fn encode_a32_modified_immediate(value: u32) -> Option<u32> {
+    for rot in 0 .. 16 {
+        let imm8 = value.rotate_left(rot * 2);
+        if imm8 < 0x100 {
+            return Some((rot << 8) | imm8);
+        }
+    }
+    None
+}
+
+/// Encodes a value as a T32 modified immediate. These are either a replicated byte pattern
+/// or an 8-bit value with its top bit set, rotated right by 8 to 31 bits.
+/// Returns the 12-bit i:imm3:imm8 field, already scattered into its position within the instruction.
+pub fn encode_t32_modified_immediate(value: u32) -> Option<u32> {
+    let low = value & 0xFF;
+    let high = (value >> 8) & 0xFF;
+    let imm12 = if value >> 8 == 0 {
+        low
+    } else if value == low * 0x0001_0001 {
+        0x100 | low
+    } else if value == (high << 8) * 0x0001_0001 {
+        0x200 | high
+    } else if value == low * 0x0101_0101 {
+        0x300 | low
+    } else {
+        // 1bcdefgh ROR rot, where the rotation also encodes the top bit of the pattern
+        let rot = value.leading_zeros() + 8;
+        if rot > 31 {
+            return None;
+        }
+        let imm8 = value.rotate_left(rot);
+        if imm8 >= 0x100 {
+            return None;
+        }
+        (rot << 7) | (imm8 & 0x7F)
+    };
+
+    Some(((imm12 >> 11) << 26) | (((imm12 >> 8) & 0x7) << 12) | (imm12 & 0xFF))
+}
+
+/// Encodes a floating point value as an 8-bit VFP immediate.
+pub fn encode_floating_point_immediate(value: f64) -> Option<u8> {
+    // VFP immediates are encoded as
+    // abcdefgh => aBbbbbbc defgh000 00000000 00000000
+    // where B = !b
+    // which means we can just slice out "a" and "bcdefgh" and assume the rest was correct.
+    // Any representable value is also exactly representable as a single precision float.
+    let single = value as f32;
+    if f64::from(single) != value {
+        return None;
+    }
+
+    let bits = single.to_bits();
+
+    let check = (bits >> 25) & 0x3F;
+    if (check == 0b10_0000 || check == 0b01_1111) && (bits & 0x7_FFFF) == 0 {
+        Some((((bits >> 24) & 0x80) | ((bits >> 19) & 0x7F)) as u8)
+    } else {
+        None
+    }
+}
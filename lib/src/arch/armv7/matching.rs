@@ -0,0 +1,576 @@
+use super::{Context, Error, ArmMode};
+use super::ast::{RawArg, FlatArg, RefItem, RegListItem, Register, RegId, RegFamily, Modifier, ModifyExpr, ShiftAmount, IndexMode};
+use super::armv7data::{Opdata, Matcher, Flags, get_mnemonic_data};
+use super::parser::{ARMV7_CONDITION_MAP, ARMV7_BARRIER_OPTION_MAP};
+use super::encoding_helpers::{encode_modified_immediate, encode_floating_point_immediate};
+use super::debug::format_opdata_list;
+
+use crate::arch::ErrorSpan;
+use crate::common::{Ident, Jump, JumpKind, Number, Value};
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::format;
+
+/// The result of matching an instruction: the matched encoding and a flattened list of arguments.
+#[derive(Debug)]
+pub(super) struct MatchData {
+    pub data: &'static Opdata,
+    pub args: Vec<FlatArg>,
+    /// The condition code suffix of the mnemonic, if one was given.
+    pub cond: Option<u8>,
+}
+
+/// An explicit width qualifier of a mnemonic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Width {
+    /// .n, a 16-bit thumb encoding
+    Narrow,
+    /// .w, a 32-bit encoding
+    Wide,
+}
+
+/// Try finding an appropriate definition that matches the given instruction / arguments.
+pub(super) fn match_instruction(ctx: &mut Context, name: &str, args: Vec<RawArg>) -> Result<MatchData, Error> {
+    // get the possible matchers
+    let (key, cond, width) = if let Some(parts) = split_mnemonic(name, ctx.mode) {
+        parts
+    } else {
+        ctx.state.emit_error_at(ErrorSpan::instruction_part(0), format_args!("'{}' is not a valid instruction", name));
+        return Err(Error::Fatal);
+    };
+    let opdata = get_mnemonic_data(&key, ctx.mode).expect("split_mnemonic returned an unknown mnemonic");
+
+    if width == Some(Width::Narrow) && ctx.mode == ArmMode::Arm {
+        ctx.state.emit_error_at(ErrorSpan::instruction_part(0), format_args!("Narrow encodings are only available in thumb mode"));
+        return Err(Error::Fatal);
+    }
+
+    // validate that only relative jumps are allowed (no extern relocations)
+    for (idx, arg) in args.iter().enumerate() {
+        if let RawArg::JumpTarget { jump } = arg {
+            if let JumpKind::Bare(_) = jump.kind {
+                ctx.state.emit_error_at(ErrorSpan::argument(idx), format_args!("Extern relocations are not allowed in armv7"));
+                return Err(Error::Fatal);
+            }
+        }
+    }
+
+    let args = sanitize_args(ctx, args)?;
+
+    // only consider encodings that can be used with the given condition and width
+    let candidates: Vec<&'static Opdata> = opdata.iter()
+        .filter(|data| is_usable(data, ctx.mode, cond, width))
+        .collect();
+
+    if candidates.is_empty() {
+        let message = if cond.is_some() && opdata.iter().all(|data| data.flags.contains(Flags::UNCOND)) {
+            format!("'{}' cannot be executed conditionally", key)
+        } else {
+            format!("'{}' has no encoding of the requested width", key)
+        };
+        ctx.state.emit_error_at(ErrorSpan::instruction_part(0), format_args!("{}", message));
+        return Err(Error::Fatal);
+    }
+
+    // matching loop
+    for data in candidates.iter().cloned() {
+        if match_args(ctx.mode, &args, data) {
+            return Ok(MatchData {
+                data,
+                args: flatten_args(args, data),
+                cond,
+            });
+        }
+    }
+
+    let candidates: Vec<Opdata> = candidates.into_iter().cloned().collect();
+    Err(format!("'{}': instruction format mismatch, expected one of the following forms:\n{}",
+        name, format_opdata_list(&key, &candidates)).into())
+}
+
+/// Splits a mnemonic into the name it is listed as in the opmap, its condition code and its width qualifier.
+///
+/// Mnemonics are written as `name{cond}{.w|.n}{.dt}`. Names that themselves end in something
+/// that looks like a condition code take precedence over the conditional interpretation.
+fn split_mnemonic(name: &str, mode: ArmMode) -> Option<(String, Option<u8>, Option<Width>)> {
+    let mut parts = name.split('.');
+    let head = parts.next()?;
+    let mut rest: Vec<&str> = parts.collect();
+
+    let width = match rest.first() {
+        Some(&"w") => Some(Width::Wide),
+        Some(&"n") => Some(Width::Narrow),
+        _ => None
+    };
+    if width.is_some() {
+        rest.remove(0);
+    }
+
+    let suffix: String = rest.iter().map(|part| format!(".{}", part)).collect();
+
+    let key = format!("{}{}", head, suffix);
+    if get_mnemonic_data(&key, mode).is_some() {
+        return Some((key, None, width));
+    }
+
+    if head.len() > 2 && head.is_char_boundary(head.len() - 2) {
+        let (base, cond) = head.split_at(head.len() - 2);
+        if let Some(&cond) = ARMV7_CONDITION_MAP.get(cond) {
+            let key = format!("{}{}", base, suffix);
+            if get_mnemonic_data(&key, mode).is_some() {
+                return Some((key, Some(cond), width));
+            }
+        }
+    }
+
+    None
+}
+
+/// Checks if the given encoding can be used with the given condition code and width qualifier.
+fn is_usable(data: &Opdata, mode: ArmMode, cond: Option<u8>, width: Option<Width>) -> bool {
+    if cond.is_some() && data.flags.contains(Flags::UNCOND) {
+        return false;
+    }
+
+    if mode == ArmMode::Arm {
+        return true;
+    }
+
+    // narrow data processing encodings set the flags depending on if they're in an IT block.
+    // we assume that conditional instructions are in an IT block and others are not.
+    if cond.is_some() && data.flags.contains(Flags::OUTSIDE_IT) {
+        return false;
+    }
+    if cond.is_none() && data.flags.contains(Flags::INSIDE_IT) {
+        return false;
+    }
+
+    match width {
+        Some(Width::Narrow) => data.flags.contains(Flags::NARROW),
+        Some(Width::Wide) => !data.flags.contains(Flags::NARROW),
+        None => true
+    }
+}
+
+/// The offset of a memory reference.
+#[derive(Debug)]
+enum RefOffset {
+    None,
+    Immediate(Value),
+    Index {
+        reg: Register,
+        negated: bool,
+        shift: Option<ModifyExpr>,
+    },
+}
+
+/// An argument after memory references have been merged with their post-indexed offsets, and
+/// register lists have been turned into bitmasks.
+#[derive(Debug)]
+enum Arg {
+    Direct {
+        span: ErrorSpan,
+        reg: Register,
+        bang: bool,
+    },
+    Negated,
+    Reference {
+        span: ErrorSpan,
+        base: Register,
+        offset: RefOffset,
+        mode: IndexMode,
+    },
+    RegList {
+        span: ErrorSpan,
+        family: RegFamily,
+        mask: u32,
+    },
+    Immediate {
+        span: ErrorSpan,
+        value: Value,
+    },
+    FloatImmediate {
+        span: ErrorSpan,
+        value: f64,
+    },
+    Modifier {
+        span: ErrorSpan,
+        modifier: ModifyExpr,
+    },
+    JumpTarget {
+        jump: Jump,
+    },
+    Lit {
+        span: ErrorSpan,
+        ident: Ident,
+    },
+}
+
+/// Validates memory references and register lists, and merges post-indexed offsets into the
+/// reference they belong to.
+fn sanitize_args(ctx: &mut Context, args: Vec<RawArg>) -> Result<Vec<Arg>, Error> {
+    let mut res = Vec::new();
+    let mut args = args.into_iter().enumerate().peekable();
+
+    while let Some((idx, arg)) = args.next() {
+        let span = ErrorSpan::argument(idx);
+
+        res.push(match arg {
+            RawArg::Reference { items, bang } => {
+                let (base, mut offset) = sanitize_reference(ctx, span, items)?;
+                let mut mode = if bang { IndexMode::PreIndexed } else { IndexMode::Offset };
+
+                // [base], offset
+                if !bang {
+                    if let RefOffset::None = offset {
+                        let is_post_index = match args.peek() {
+                            Some((_, RawArg::Immediate { .. }))
+                            | Some((_, RawArg::Negated { .. })) => true,
+                            Some((_, RawArg::Direct { reg, bang: false })) => reg.family() == RegFamily::CORE,
+                            _ => false
+                        };
+                        if is_post_index {
+                            offset = match args.next() {
+                                Some((_, RawArg::Immediate { value })) => RefOffset::Immediate(value),
+                                Some((_, RawArg::Direct { reg, .. })) => RefOffset::Index { reg, negated: false, shift: None },
+                                Some((_, RawArg::Negated { reg })) => RefOffset::Index { reg, negated: true, shift: None },
+                                _ => unreachable!()
+                            };
+                        }
+
+                        if let RefOffset::Index { ref mut shift, .. } = offset {
+                            if let Some((_, RawArg::Modifier { .. })) = args.peek() {
+                                if let Some((_, RawArg::Modifier { modifier })) = args.next() {
+                                    *shift = Some(modifier);
+                                }
+                            }
+                        }
+
+                        if let RefOffset::None = offset { } else {
+                            mode = IndexMode::PostIndexed;
+                        }
+                    }
+                }
+
+                Arg::Reference { span, base, offset, mode }
+            },
+            RawArg::RegList { items } => {
+                let (family, mask) = sanitize_reglist(ctx, span, items)?;
+                Arg::RegList { span, family, mask }
+            },
+            RawArg::Direct { reg, bang } => Arg::Direct { span, reg, bang },
+            RawArg::Negated { .. } => Arg::Negated,
+            RawArg::JumpTarget { jump } => Arg::JumpTarget { jump },
+            RawArg::Immediate { value } => Arg::Immediate { span, value },
+            RawArg::FloatImmediate { value } => Arg::FloatImmediate { span, value },
+            RawArg::Modifier { modifier } => Arg::Modifier { span, modifier },
+            RawArg::Lit { ident } => Arg::Lit { span, ident },
+        });
+    }
+
+    Ok(res)
+}
+
+/// Splits a memory reference into its base register and offset.
+fn sanitize_reference(ctx: &mut Context, span: ErrorSpan, items: Vec<RefItem>) -> Result<(Register, RefOffset), Error> {
+    let mut items = items.into_iter();
+
+    let base = match items.next() {
+        Some(RefItem::Direct { reg, negated: false }) if reg.family() == RegFamily::CORE => reg,
+        _ => {
+            ctx.state.emit_error_at(span, format_args!("Memory references have to start with a core base register"));
+            return Err(Error::Fatal);
+        }
+    };
+
+    let offset = match items.next() {
+        None => RefOffset::None,
+        Some(RefItem::Immediate { value }) => RefOffset::Immediate(value),
+        Some(RefItem::Direct { reg, negated }) if reg.family() == RegFamily::CORE => {
+            let shift = match items.next() {
+                None => None,
+                Some(RefItem::Modifier { modifier }) => Some(modifier),
+                Some(_) => {
+                    ctx.state.emit_error_at(span, format_args!("Expected a shift of the index register"));
+                    return Err(Error::Fatal);
+                }
+            };
+            RefOffset::Index { reg, negated, shift }
+        },
+        Some(_) => {
+            ctx.state.emit_error_at(span, format_args!("Memory reference offsets have to be an immediate or a core index register"));
+            return Err(Error::Fatal);
+        }
+    };
+
+    if items.next().is_some() {
+        ctx.state.emit_error_at(span, format_args!("Too many items in memory reference"));
+        return Err(Error::Fatal);
+    }
+
+    Ok((base, offset))
+}
+
+/// Turns a register list into a bitmask of register codes.
+fn sanitize_reglist(ctx: &mut Context, span: ErrorSpan, items: Vec<RegListItem>) -> Result<(RegFamily, u32), Error> {
+    let mut family = None;
+    let mut mask = 0u32;
+
+    for item in items {
+        let (first, last) = match item {
+            RegListItem::Single(reg) => (reg.clone(), reg),
+            RegListItem::Range(first, last) => (first, last),
+        };
+
+        let (first, last) = match (first, last) {
+            (Register::Static(first), Register::Static(last)) => (first, last),
+            _ => {
+                ctx.state.emit_error_at(span, format_args!("Register lists only support static registers"));
+                return Err(Error::Fatal);
+            }
+        };
+
+        if first.family() != last.family() || family.is_some_and(|f| f != first.family()) {
+            ctx.state.emit_error_at(span, format_args!("Register lists cannot mix different register families"));
+            return Err(Error::Fatal);
+        }
+        family = Some(first.family());
+
+        if first.code() > last.code() {
+            ctx.state.emit_error_at(span, format_args!("Register ranges have to be in ascending order"));
+            return Err(Error::Fatal);
+        }
+
+        for code in first.code() ..= last.code() {
+            mask |= 1 << code;
+        }
+    }
+
+    Ok((family.unwrap_or(RegFamily::CORE), mask))
+}
+
+/// Checks if the given arguments can be encoded by the given opdata.
+fn match_args(mode: ArmMode, args: &[Arg], data: &Opdata) -> bool {
+    let mut iter = args.iter();
+    let mut optional = false;
+
+    for matcher in data.matchers {
+        if *matcher == Matcher::End {
+            optional = true;
+            continue;
+        }
+
+        let arg = match iter.next() {
+            Some(arg) => arg,
+            None => return optional,
+        };
+
+        if !match_arg(mode, arg, *matcher, &args[0]) {
+            return false;
+        }
+    }
+
+    iter.next().is_none()
+}
+
+fn match_arg(mode: ArmMode, arg: &Arg, matcher: Matcher, first: &Arg) -> bool {
+    match (matcher, arg) {
+        (Matcher::R, Arg::Direct { reg, bang: false, .. }) => reg.family() == RegFamily::CORE,
+        (Matcher::Lo, Arg::Direct { reg, bang: false, .. }) => reg.is_low(),
+        (Matcher::Hi, Arg::Direct { reg: reg @ Register::Static(_), bang: false, .. }) => reg.family() == RegFamily::CORE && !reg.is_low(),
+        (Matcher::Same, Arg::Direct { reg: Register::Static(id), bang: false, .. }) => match first {
+            Arg::Direct { reg, .. } => reg.is(*id),
+            _ => false
+        },
+        (Matcher::Reg(id), Arg::Direct { reg, bang: false, .. }) => reg.is(id),
+        (Matcher::RBang, Arg::Direct { reg, .. }) => reg.family() == RegFamily::CORE,
+        (Matcher::S, Arg::Direct { reg, bang: false, .. }) => reg.family() == RegFamily::SINGLE,
+        (Matcher::D, Arg::Direct { reg, bang: false, .. }) => reg.family() == RegFamily::DOUBLE,
+        (Matcher::Q, Arg::Direct { reg, bang: false, .. }) => reg.family() == RegFamily::QUAD,
+
+        (Matcher::Imm, Arg::Immediate { .. }) => true,
+        (Matcher::SmallImm(field), Arg::Immediate { value: Value::Number(number), .. }) => match static_unsigned(*number) {
+            Some(value) => field.fits(value),
+            None => false
+        },
+        (Matcher::ModImm(kind), Arg::Immediate { value: Value::Number(number), .. }) => match static_u32(*number) {
+            Some(value) => encode_modified_immediate(mode, kind.apply(value)).is_some(),
+            None => false
+        },
+        (Matcher::FImm, Arg::FloatImmediate { value, .. }) => encode_floating_point_immediate(*value).is_some(),
+        (Matcher::FZero, Arg::FloatImmediate { value, .. }) => *value == 0.0,
+        (Matcher::FZero, Arg::Immediate { value: Value::Number(number), .. }) => number.as_u64() == 0,
+
+        (Matcher::Shift, Arg::Modifier { modifier, .. }) => is_immediate_shift(modifier),
+        (Matcher::ShiftReg, Arg::Modifier { modifier, .. }) => modifier.op != Modifier::RRX && match modifier.amount {
+            Some(ShiftAmount::Register(ref reg)) => reg.family() == RegFamily::CORE,
+            _ => false
+        },
+        (Matcher::Ror, Arg::Modifier { modifier, .. }) => modifier.op == Modifier::ROR && matches!(modifier.amount, Some(ShiftAmount::Immediate(_))),
+
+        (Matcher::Ref, Arg::Reference { offset, .. }) => match offset {
+            RefOffset::None
+            | RefOffset::Immediate(_) => true,
+            RefOffset::Index { .. } => false,
+        },
+        (Matcher::RefOffset, Arg::Reference { offset, mode: IndexMode::Offset, .. }) => match offset {
+            RefOffset::None
+            | RefOffset::Immediate(_) => true,
+            RefOffset::Index { .. } => false,
+        },
+        (Matcher::RefPos, Arg::Reference { offset, mode: IndexMode::Offset, .. }) => match offset {
+            RefOffset::None
+            | RefOffset::Immediate(Value::Expr(_)) => true,
+            RefOffset::Immediate(Value::Number(number)) => static_unsigned(*number).is_some(),
+            RefOffset::Index { .. } => false,
+        },
+        (Matcher::RefLo(field), Arg::Reference { base, offset, mode: IndexMode::Offset, .. }) => base.is_low() && match offset {
+            RefOffset::None => true,
+            RefOffset::Immediate(Value::Number(number)) => static_unsigned(*number).is_some_and(|value| field.fits(value)),
+            _ => false
+        },
+        (Matcher::RefSp(field), Arg::Reference { base, offset, mode: IndexMode::Offset, .. }) => base.is(RegId::SP) && match offset {
+            RefOffset::None => true,
+            RefOffset::Immediate(Value::Number(number)) => static_unsigned(*number).is_some_and(|value| field.fits(value)),
+            _ => false
+        },
+        (Matcher::RefBase, Arg::Reference { offset, mode: IndexMode::Offset, .. }) => match offset {
+            RefOffset::None => true,
+            RefOffset::Immediate(Value::Number(number)) => number.as_u64() == 0,
+            _ => false
+        },
+        (Matcher::RefIdx, Arg::Reference { offset: RefOffset::Index { shift, .. }, .. }) => match shift {
+            None => true,
+            Some(modifier) => is_immediate_shift(modifier),
+        },
+        (Matcher::RefIdxPlain, Arg::Reference { offset: RefOffset::Index { shift: None, .. }, .. }) => true,
+        (Matcher::RefIdxLsl, Arg::Reference { offset: RefOffset::Index { negated: false, shift, .. }, mode: IndexMode::Offset, .. }) => match shift {
+            None => true,
+            Some(modifier) => modifier.op == Modifier::LSL && matches!(modifier.amount, Some(ShiftAmount::Immediate(_)))
+        },
+        (Matcher::RefIdxLo, Arg::Reference { base, offset: RefOffset::Index { reg, negated: false, shift: None }, mode: IndexMode::Offset, .. }) => base.is_low() && reg.is_low(),
+
+        (Matcher::List, Arg::RegList { family: RegFamily::CORE, mask, .. }) => *mask != 0,
+        (Matcher::ListOne, Arg::RegList { family: RegFamily::CORE, mask, .. }) => mask.count_ones() == 1,
+        (Matcher::ListLo(extra), Arg::RegList { family: RegFamily::CORE, mask, .. }) => *mask != 0 && (mask & !(0xFF | 1 << extra.code())) == 0,
+        (Matcher::SList, Arg::RegList { family: RegFamily::SINGLE, mask, .. }) => is_consecutive(*mask),
+        (Matcher::DList, Arg::RegList { family: RegFamily::DOUBLE, mask, .. }) => is_consecutive(*mask) && mask.count_ones() <= 16,
+
+        (Matcher::Offset, Arg::JumpTarget { .. }) => true,
+
+        (Matcher::Lit(name), Arg::Lit { ident, .. }) => ident.name.eq_ignore_ascii_case(name),
+        (Matcher::Barrier, Arg::Lit { ident, .. }) => ARMV7_BARRIER_OPTION_MAP.contains_key(&*ident.name),
+        _ => false
+    }
+}
+
+/// Flattens the argument list, splitting references into their base, addressing mode and offset,
+/// and filling in omitted optional arguments with `FlatArg::Default`.
+fn flatten_args(args: Vec<Arg>, data: &Opdata) -> Vec<FlatArg> {
+    let mut res = Vec::new();
+    let mut args = args.into_iter();
+
+    for matcher in data.matchers {
+        if *matcher == Matcher::End {
+            continue;
+        }
+
+        let arg = match args.next() {
+            Some(arg) => arg,
+            None => {
+                res.push(FlatArg::Default);
+                continue;
+            }
+        };
+
+        match arg {
+            Arg::Direct { span, reg, bang } => {
+                res.push(FlatArg::Direct { span, reg });
+                if *matcher == Matcher::RBang {
+                    res.push(FlatArg::Bang { span, bang });
+                }
+            },
+            Arg::Reference { span, base, offset, mode } => {
+                res.push(FlatArg::Direct { span, reg: base });
+
+                if let Matcher::Ref | Matcher::RefIdx | Matcher::RefIdxPlain = *matcher {
+                    res.push(FlatArg::Mode { span, mode });
+                }
+
+                match offset {
+                    RefOffset::None => if *matcher != Matcher::RefBase {
+                        res.push(FlatArg::Default);
+                    },
+                    RefOffset::Immediate(value) => if *matcher != Matcher::RefBase {
+                        res.push(FlatArg::Immediate { span, value });
+                    },
+                    RefOffset::Index { reg, negated, shift } => {
+                        res.push(FlatArg::Index { span, reg, negated });
+                        if let Matcher::RefIdx | Matcher::RefIdxLsl = *matcher {
+                            res.push(match shift {
+                                Some(modifier) => FlatArg::Modifier { span, modifier },
+                                None => FlatArg::Default
+                            });
+                        }
+                    }
+                }
+            },
+            Arg::RegList { span, family, mask } => res.push(FlatArg::RegList { span, family, mask }),
+            Arg::Immediate { span, value } => res.push(FlatArg::Immediate { span, value }),
+            Arg::FloatImmediate { span, value } => res.push(FlatArg::FloatImmediate { span, value }),
+            Arg::Modifier { span, modifier } => res.push(FlatArg::Modifier { span, modifier }),
+            Arg::JumpTarget { jump } => res.push(FlatArg::JumpTarget { jump }),
+            Arg::Lit { span, ident } => res.push(FlatArg::Lit { span, ident }),
+            Arg::Negated => panic!("Invalid matcher data, matched a negated register")
+        }
+    }
+
+    res
+}
+
+/// Returns true if this is a shift by an immediate amount, or rrx.
+fn is_immediate_shift(modifier: &ModifyExpr) -> bool {
+    match modifier.amount {
+        None => modifier.op == Modifier::RRX,
+        Some(ShiftAmount::Immediate(_)) => modifier.op != Modifier::RRX,
+        Some(ShiftAmount::Register(_)) => false,
+    }
+}
+
+/// Returns true if the set bits of this mask are consecutive.
+fn is_consecutive(mask: u32) -> bool {
+    if mask == 0 {
+        return false;
+    }
+    let mask = mask >> mask.trailing_zeros();
+    mask & mask.wrapping_add(1) == 0
+}
+
+/// Interprets a static number as a 32-bit pattern, accepting both signed and unsigned values.
+pub(super) fn static_u32(number: Number) -> Option<u32> {
+    if number.repr().signed {
+        let value = number.as_i64();
+        if value >= i64::from(i32::MIN) && value <= i64::from(u32::MAX) {
+            Some(value as u32)
+        } else {
+            None
+        }
+    } else {
+        let value = number.as_u64();
+        if value <= u64::from(u32::MAX) {
+            Some(value as u32)
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns the value of a static number, if it is not negative.
+pub(super) fn static_unsigned(number: Number) -> Option<u64> {
+    if number.repr().signed && number.as_i64() < 0 {
+        None
+    } else {
+        Some(number.as_u64())
+    }
+}
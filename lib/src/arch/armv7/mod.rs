@@ -0,0 +1,228 @@
+pub mod ast;
+pub mod parser;
+mod matching;
+mod compiler;
+mod armv7data;
+mod encoding_helpers;
+mod debug;
+
+use alloc::borrow::Cow;
+
+use crate::arch::{Arch, Error as ExprBuilderError, BasicExprBuilder};
+use crate::common::{Size, Stmt, Jump};
+use self::armv7data::{Features, Relocation};
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// The instruction set that is being assembled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArmMode {
+    /// The A32 instruction set, using 32-bit instructions.
+    Arm,
+    /// The T32 (Thumb-2) instruction set, using a mix of 16-bit and 32-bit instructions.
+    Thumb,
+}
+
+struct Context<'a> {
+    pub state: &'a mut dyn BasicExprBuilder,
+    pub mode: ArmMode,
+    pub features: Features,
+}
+
+#[derive(Clone, Debug)]
+pub struct ArchArm {
+    features: Features,
+}
+
+#[derive(Clone, Debug)]
+pub struct ArchThumb {
+    features: Features,
+}
+
+#[derive(Debug)]
+pub struct InstructionArmv7 {
+    pub inst: ast::Instruction,
+    pub args: Vec<ast::RawArg>,
+}
+
+impl Default for ArchArm {
+    fn default() -> ArchArm {
+        ArchArm { features: Features::all() }
+    }
+}
+
+impl Default for ArchThumb {
+    fn default() -> ArchThumb {
+        ArchThumb { features: Features::all() }
+    }
+}
+
+pub trait AssembleArm {
+    /// Turn an expression into binary format.
+    /// May error when dynamic data is present at locations that only support static values.
+    fn compile_instruction(&mut self, arch: &ArchArm, _: InstructionArmv7) -> Result<(), Error>;
+
+    /// Create an instruction composed from dynamic data.
+    /// Only available when the type is also capable of building new composite expressions.
+    fn build_instruction(&mut self, arch: &ArchArm, _: InstructionArmv7) -> Result<(), Error>
+        where Self: BasicExprBuilder;
+}
+
+pub trait AssembleThumb {
+    /// Turn an expression into binary format.
+    /// May error when dynamic data is present at locations that only support static values.
+    fn compile_instruction(&mut self, arch: &ArchThumb, _: InstructionArmv7) -> Result<(), Error>;
+
+    /// Create an instruction composed from dynamic data.
+    /// Only available when the type is also capable of building new composite expressions.
+    fn build_instruction(&mut self, arch: &ArchThumb, _: InstructionArmv7) -> Result<(), Error>
+        where Self: BasicExprBuilder;
+}
+
+/// An error while assembling, either an error in the environment or during processing.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// An error happened within the expression builder.
+    Expr(ExprBuilderError),
+    /// An error without occurred where diagnostics offer no introspection.
+    Generic {
+        message: Cow<'static, str>,
+    },
+    /// Some unspecified consistency check did not succeed.
+    /// When this occurs we have emitted one or several diagnostic messages.
+    Fatal,
+    /// Use of extensions that were disabled or would need to be explicitly enabled.
+    DisabledFeatures(armv7data::Features),
+}
+
+impl From<ExprBuilderError> for Error {
+    fn from(err: ExprBuilderError) -> Self {
+        Error::Expr(err)
+    }
+}
+
+impl From<&'static str> for Error {
+    fn from(message: &'static str) -> Self {
+        Error::Generic {
+            message: Cow::Borrowed(message),
+        }
+    }
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::Generic {
+            message: Cow::Owned(message),
+        }
+    }
+}
+
+fn parse_features(name: &str, features: &[String]) -> Features {
+    // the base instruction set is always available
+    let mut new_features = Features::V7;
+    for ident in features {
+        new_features |= match Features::from_name(ident) {
+            Some(feature) => feature,
+            None => {
+                eprintln!("Architecture {} does not support feature '{}'", name, ident);
+                continue;
+            }
+        }
+    }
+    new_features
+}
+
+fn handle_static_reloc(stmts: &mut Vec<Stmt>, reloc: Jump, size: Size) {
+    let relocation = match size {
+        Size::BYTE => Relocation::LITERAL8,
+        Size::WORD => Relocation::LITERAL16,
+        Size::DWORD => Relocation::LITERAL32,
+        Size::QWORD => Relocation::LITERAL64,
+        _ => {
+            eprintln!("Relocation of unsupported size for the current target architecture");
+            return;
+        }
+    };
+    let data = [relocation.to_id()];
+
+    stmts.push(Stmt::zeroed(size));
+    stmts.push(reloc.encode(&data));
+}
+
+impl Arch for ArchArm {
+    fn name(&self) -> &str {
+        "arm"
+    }
+
+    fn set_features(&mut self, features: &[String]) {
+        self.features = parse_features("arm", features);
+    }
+
+    fn handle_static_reloc(&self, stmts: &mut Vec<Stmt>, reloc: Jump, size: Size) {
+        handle_static_reloc(stmts, reloc, size)
+    }
+
+    fn default_align(&self) -> u8 {
+        0
+    }
+}
+
+impl Arch for ArchThumb {
+    fn name(&self) -> &str {
+        "thumb"
+    }
+
+    fn set_features(&mut self, features: &[String]) {
+        self.features = parse_features("thumb", features);
+    }
+
+    fn handle_static_reloc(&self, stmts: &mut Vec<Stmt>, reloc: Jump, size: Size) {
+        handle_static_reloc(stmts, reloc, size)
+    }
+
+    fn default_align(&self) -> u8 {
+        0
+    }
+}
+
+impl<T: BasicExprBuilder> AssembleArm for T {
+    fn compile_instruction(&mut self, arch: &ArchArm, instruction: InstructionArmv7) -> Result<(), Error> {
+        let InstructionArmv7 { inst, args } = instruction;
+
+        let mut ctx = Context {
+            state: self,
+            mode: ArmMode::Arm,
+            features: arch.features,
+        };
+
+        compiler::compile_instruction(&mut ctx, inst, args)
+    }
+
+    fn build_instruction(&mut self, arch: &ArchArm, instruction: InstructionArmv7) -> Result<(), Error>
+        where Self: BasicExprBuilder
+    {
+        AssembleArm::compile_instruction(self, arch, instruction)
+    }
+}
+
+impl<T: BasicExprBuilder> AssembleThumb for T {
+    fn compile_instruction(&mut self, arch: &ArchThumb, instruction: InstructionArmv7) -> Result<(), Error> {
+        let InstructionArmv7 { inst, args } = instruction;
+
+        let mut ctx = Context {
+            state: self,
+            mode: ArmMode::Thumb,
+            features: arch.features,
+        };
+
+        compiler::compile_instruction(&mut ctx, inst, args)
+    }
+
+    fn build_instruction(&mut self, arch: &ArchThumb, instruction: InstructionArmv7) -> Result<(), Error>
+        where Self: BasicExprBuilder
+    {
+        AssembleThumb::compile_instruction(self, arch, instruction)
+    }
+}
@@ -0,0 +1,820 @@
+// A32 instruction encodings. Conditional instructions have their condition field left empty.
+Ops!(
+
+// data processing
+"and" = [
+    0x02000000 = [R, R, ModImm(Plain)] => [R(12), R(16), ModImm(Plain)], [], V7;
+    0x03C00000 = [R, R, ModImm(Inverted)] => [R(12), R(16), ModImm(Inverted)], [], V7;
+    0x02000000 = [R, ModImm(Plain)] => [R(12), C, R(16), ModImm(Plain)], [], V7;
+    0x03C00000 = [R, ModImm(Inverted)] => [R(12), C, R(16), ModImm(Inverted)], [], V7;
+    0x00000000 = [R, R, R, End, Shift] => [R(12), R(16), R(0), Shift], [], V7;
+    0x00000010 = [R, R, R, ShiftReg] => [R(12), R(16), R(0), ShiftReg], [], V7;
+    0x00000000 = [R, R, End, Shift] => [R(12), C, R(16), R(0), Shift], [], V7;
+    0x00000010 = [R, R, ShiftReg] => [R(12), C, R(16), R(0), ShiftReg], [], V7;
+]
+"ands" = [
+    0x02100000 = [R, R, ModImm(Plain)] => [R(12), R(16), ModImm(Plain)], [], V7;
+    0x03D00000 = [R, R, ModImm(Inverted)] => [R(12), R(16), ModImm(Inverted)], [], V7;
+    0x02100000 = [R, ModImm(Plain)] => [R(12), C, R(16), ModImm(Plain)], [], V7;
+    0x03D00000 = [R, ModImm(Inverted)] => [R(12), C, R(16), ModImm(Inverted)], [], V7;
+    0x00100000 = [R, R, R, End, Shift] => [R(12), R(16), R(0), Shift], [], V7;
+    0x00100010 = [R, R, R, ShiftReg] => [R(12), R(16), R(0), ShiftReg], [], V7;
+    0x00100000 = [R, R, End, Shift] => [R(12), C, R(16), R(0), Shift], [], V7;
+    0x00100010 = [R, R, ShiftReg] => [R(12), C, R(16), R(0), ShiftReg], [], V7;
+]
+"eor" = [
+    0x02200000 = [R, R, ModImm(Plain)] => [R(12), R(16), ModImm(Plain)], [], V7;
+    0x02200000 = [R, ModImm(Plain)] => [R(12), C, R(16), ModImm(Plain)], [], V7;
+    0x00200000 = [R, R, R, End, Shift] => [R(12), R(16), R(0), Shift], [], V7;
+    0x00200010 = [R, R, R, ShiftReg] => [R(12), R(16), R(0), ShiftReg], [], V7;
+    0x00200000 = [R, R, End, Shift] => [R(12), C, R(16), R(0), Shift], [], V7;
+    0x00200010 = [R, R, ShiftReg] => [R(12), C, R(16), R(0), ShiftReg], [], V7;
+]
+"eors" = [
+    0x02300000 = [R, R, ModImm(Plain)] => [R(12), R(16), ModImm(Plain)], [], V7;
+    0x02300000 = [R, ModImm(Plain)] => [R(12), C, R(16), ModImm(Plain)], [], V7;
+    0x00300000 = [R, R, R, End, Shift] => [R(12), R(16), R(0), Shift], [], V7;
+    0x00300010 = [R, R, R, ShiftReg] => [R(12), R(16), R(0), ShiftReg], [], V7;
+    0x00300000 = [R, R, End, Shift] => [R(12), C, R(16), R(0), Shift], [], V7;
+    0x00300010 = [R, R, ShiftReg] => [R(12), C, R(16), R(0), ShiftReg], [], V7;
+]
+"sub" = [
+    0x02400000 = [R, R, ModImm(Plain)] => [R(12), R(16), ModImm(Plain)], [], V7;
+    0x02800000 = [R, R, ModImm(Negated)] => [R(12), R(16), ModImm(Negated)], [], V7;
+    0x02400000 = [R, ModImm(Plain)] => [R(12), C, R(16), ModImm(Plain)], [], V7;
+    0x02800000 = [R, ModImm(Negated)] => [R(12), C, R(16), ModImm(Negated)], [], V7;
+    0x00400000 = [R, R, R, End, Shift] => [R(12), R(16), R(0), Shift], [], V7;
+    0x00400010 = [R, R, R, ShiftReg] => [R(12), R(16), R(0), ShiftReg], [], V7;
+    0x00400000 = [R, R, End, Shift] => [R(12), C, R(16), R(0), Shift], [], V7;
+    0x00400010 = [R, R, ShiftReg] => [R(12), C, R(16), R(0), ShiftReg], [], V7;
+]
+"subs" = [
+    0x02500000 = [R, R, ModImm(Plain)] => [R(12), R(16), ModImm(Plain)], [], V7;
+    0x02900000 = [R, R, ModImm(Negated)] => [R(12), R(16), ModImm(Negated)], [], V7;
+    0x02500000 = [R, ModImm(Plain)] => [R(12), C, R(16), ModImm(Plain)], [], V7;
+    0x02900000 = [R, ModImm(Negated)] => [R(12), C, R(16), ModImm(Negated)], [], V7;
+    0x00500000 = [R, R, R, End, Shift] => [R(12), R(16), R(0), Shift], [], V7;
+    0x00500010 = [R, R, R, ShiftReg] => [R(12), R(16), R(0), ShiftReg], [], V7;
+    0x00500000 = [R, R, End, Shift] => [R(12), C, R(16), R(0), Shift], [], V7;
+    0x00500010 = [R, R, ShiftReg] => [R(12), C, R(16), R(0), ShiftReg], [], V7;
+]
+"rsb" = [
+    0x02600000 = [R, R, ModImm(Plain)] => [R(12), R(16), ModImm(Plain)], [], V7;
+    0x02600000 = [R, ModImm(Plain)] => [R(12), C, R(16), ModImm(Plain)], [], V7;
+    0x00600000 = [R, R, R, End, Shift] => [R(12), R(16), R(0), Shift], [], V7;
+    0x00600010 = [R, R, R, ShiftReg] => [R(12), R(16), R(0), ShiftReg], [], V7;
+    0x00600000 = [R, R, End, Shift] => [R(12), C, R(16), R(0), Shift], [], V7;
+    0x00600010 = [R, R, ShiftReg] => [R(12), C, R(16), R(0), ShiftReg], [], V7;
+]
+"rsbs" = [
+    0x02700000 = [R, R, ModImm(Plain)] => [R(12), R(16), ModImm(Plain)], [], V7;
+    0x02700000 = [R, ModImm(Plain)] => [R(12), C, R(16), ModImm(Plain)], [], V7;
+    0x00700000 = [R, R, R, End, Shift] => [R(12), R(16), R(0), Shift], [], V7;
+    0x00700010 = [R, R, R, ShiftReg] => [R(12), R(16), R(0), ShiftReg], [], V7;
+    0x00700000 = [R, R, End, Shift] => [R(12), C, R(16), R(0), Shift], [], V7;
+    0x00700010 = [R, R, ShiftReg] => [R(12), C, R(16), R(0), ShiftReg], [], V7;
+]
+"add" = [
+    0x02800000 = [R, R, ModImm(Plain)] => [R(12), R(16), ModImm(Plain)], [], V7;
+    0x02400000 = [R, R, ModImm(Negated)] => [R(12), R(16), ModImm(Negated)], [], V7;
+    0x02800000 = [R, ModImm(Plain)] => [R(12), C, R(16), ModImm(Plain)], [], V7;
+    0x02400000 = [R, ModImm(Negated)] => [R(12), C, R(16), ModImm(Negated)], [], V7;
+    0x00800000 = [R, R, R, End, Shift] => [R(12), R(16), R(0), Shift], [], V7;
+    0x00800010 = [R, R, R, ShiftReg] => [R(12), R(16), R(0), ShiftReg], [], V7;
+    0x00800000 = [R, R, End, Shift] => [R(12), C, R(16), R(0), Shift], [], V7;
+    0x00800010 = [R, R, ShiftReg] => [R(12), C, R(16), R(0), ShiftReg], [], V7;
+]
+"adds" = [
+    0x02900000 = [R, R, ModImm(Plain)] => [R(12), R(16), ModImm(Plain)], [], V7;
+    0x02500000 = [R, R, ModImm(Negated)] => [R(12), R(16), ModImm(Negated)], [], V7;
+    0x02900000 = [R, ModImm(Plain)] => [R(12), C, R(16), ModImm(Plain)], [], V7;
+    0x02500000 = [R, ModImm(Negated)] => [R(12), C, R(16), ModImm(Negated)], [], V7;
+    0x00900000 = [R, R, R, End, Shift] => [R(12), R(16), R(0), Shift], [], V7;
+    0x00900010 = [R, R, R, ShiftReg] => [R(12), R(16), R(0), ShiftReg], [], V7;
+    0x00900000 = [R, R, End, Shift] => [R(12), C, R(16), R(0), Shift], [], V7;
+    0x00900010 = [R, R, ShiftReg] => [R(12), C, R(16), R(0), ShiftReg], [], V7;
+]
+"adc" = [
+    0x02A00000 = [R, R, ModImm(Plain)] => [R(12), R(16), ModImm(Plain)], [], V7;
+    0x02C00000 = [R, R, ModImm(Inverted)] => [R(12), R(16), ModImm(Inverted)], [], V7;
+    0x02A00000 = [R, ModImm(Plain)] => [R(12), C, R(16), ModImm(Plain)], [], V7;
+    0x02C00000 = [R, ModImm(Inverted)] => [R(12), C, R(16), ModImm(Inverted)], [], V7;
+    0x00A00000 = [R, R, R, End, Shift] => [R(12), R(16), R(0), Shift], [], V7;
+    0x00A00010 = [R, R, R, ShiftReg] => [R(12), R(16), R(0), ShiftReg], [], V7;
+    0x00A00000 = [R, R, End, Shift] => [R(12), C, R(16), R(0), Shift], [], V7;
+    0x00A00010 = [R, R, ShiftReg] => [R(12), C, R(16), R(0), ShiftReg], [], V7;
+]
+"adcs" = [
+    0x02B00000 = [R, R, ModImm(Plain)] => [R(12), R(16), ModImm(Plain)], [], V7;
+    0x02D00000 = [R, R, ModImm(Inverted)] => [R(12), R(16), ModImm(Inverted)], [], V7;
+    0x02B00000 = [R, ModImm(Plain)] => [R(12), C, R(16), ModImm(Plain)], [], V7;
+    0x02D00000 = [R, ModImm(Inverted)] => [R(12), C, R(16), ModImm(Inverted)], [], V7;
+    0x00B00000 = [R, R, R, End, Shift] => [R(12), R(16), R(0), Shift], [], V7;
+    0x00B00010 = [R, R, R, ShiftReg] => [R(12), R(16), R(0), ShiftReg], [], V7;
+    0x00B00000 = [R, R, End, Shift] => [R(12), C, R(16), R(0), Shift], [], V7;
+    0x00B00010 = [R, R, ShiftReg] => [R(12), C, R(16), R(0), ShiftReg], [], V7;
+]
+"sbc" = [
+    0x02C00000 = [R, R, ModImm(Plain)] => [R(12), R(16), ModImm(Plain)], [], V7;
+    0x02A00000 = [R, R, ModImm(Inverted)] => [R(12), R(16), ModImm(Inverted)], [], V7;
+    0x02C00000 = [R, ModImm(Plain)] => [R(12), C, R(16), ModImm(Plain)], [], V7;
+    0x02A00000 = [R, ModImm(Inverted)] => [R(12), C, R(16), ModImm(Inverted)], [], V7;
+    0x00C00000 = [R, R, R, End, Shift] => [R(12), R(16), R(0), Shift], [], V7;
+    0x00C00010 = [R, R, R, ShiftReg] => [R(12), R(16), R(0), ShiftReg], [], V7;
+    0x00C00000 = [R, R, End, Shift] => [R(12), C, R(16), R(0), Shift], [], V7;
+    0x00C00010 = [R, R, ShiftReg] => [R(12), C, R(16), R(0), ShiftReg], [], V7;
+]
+"sbcs" = [
+    0x02D00000 = [R, R, ModImm(Plain)] => [R(12), R(16), ModImm(Plain)], [], V7;
+    0x02B00000 = [R, R, ModImm(Inverted)] => [R(12), R(16), ModImm(Inverted)], [], V7;
+    0x02D00000 = [R, ModImm(Plain)] => [R(12), C, R(16), ModImm(Plain)], [], V7;
+    0x02B00000 = [R, ModImm(Inverted)] => [R(12), C, R(16), ModImm(Inverted)], [], V7;
+    0x00D00000 = [R, R, R, End, Shift] => [R(12), R(16), R(0), Shift], [], V7;
+    0x00D00010 = [R, R, R, ShiftReg] => [R(12), R(16), R(0), ShiftReg], [], V7;
+    0x00D00000 = [R, R, End, Shift] => [R(12), C, R(16), R(0), Shift], [], V7;
+    0x00D00010 = [R, R, ShiftReg] => [R(12), C, R(16), R(0), ShiftReg], [], V7;
+]
+"rsc" = [
+    0x02E00000 = [R, R, ModImm(Plain)] => [R(12), R(16), ModImm(Plain)], [], V7;
+    0x02E00000 = [R, ModImm(Plain)] => [R(12), C, R(16), ModImm(Plain)], [], V7;
+    0x00E00000 = [R, R, R, End, Shift] => [R(12), R(16), R(0), Shift], [], V7;
+    0x00E00010 = [R, R, R, ShiftReg] => [R(12), R(16), R(0), ShiftReg], [], V7;
+    0x00E00000 = [R, R, End, Shift] => [R(12), C, R(16), R(0), Shift], [], V7;
+    0x00E00010 = [R, R, ShiftReg] => [R(12), C, R(16), R(0), ShiftReg], [], V7;
+]
+"rscs" = [
+    0x02F00000 = [R, R, ModImm(Plain)] => [R(12), R(16), ModImm(Plain)], [], V7;
+    0x02F00000 = [R, ModImm(Plain)] => [R(12), C, R(16), ModImm(Plain)], [], V7;
+    0x00F00000 = [R, R, R, End, Shift] => [R(12), R(16), R(0), Shift], [], V7;
+    0x00F00010 = [R, R, R, ShiftReg] => [R(12), R(16), R(0), ShiftReg], [], V7;
+    0x00F00000 = [R, R, End, Shift] => [R(12), C, R(16), R(0), Shift], [], V7;
+    0x00F00010 = [R, R, ShiftReg] => [R(12), C, R(16), R(0), ShiftReg], [], V7;
+]
+"orr" = [
+    0x03800000 = [R, R, ModImm(Plain)] => [R(12), R(16), ModImm(Plain)], [], V7;
+    0x03800000 = [R, ModImm(Plain)] => [R(12), C, R(16), ModImm(Plain)], [], V7;
+    0x01800000 = [R, R, R, End, Shift] => [R(12), R(16), R(0), Shift], [], V7;
+    0x01800010 = [R, R, R, ShiftReg] => [R(12), R(16), R(0), ShiftReg], [], V7;
+    0x01800000 = [R, R, End, Shift] => [R(12), C, R(16), R(0), Shift], [], V7;
+    0x01800010 = [R, R, ShiftReg] => [R(12), C, R(16), R(0), ShiftReg], [], V7;
+]
+"orrs" = [
+    0x03900000 = [R, R, ModImm(Plain)] => [R(12), R(16), ModImm(Plain)], [], V7;
+    0x03900000 = [R, ModImm(Plain)] => [R(12), C, R(16), ModImm(Plain)], [], V7;
+    0x01900000 = [R, R, R, End, Shift] => [R(12), R(16), R(0), Shift], [], V7;
+    0x01900010 = [R, R, R, ShiftReg] => [R(12), R(16), R(0), ShiftReg], [], V7;
+    0x01900000 = [R, R, End, Shift] => [R(12), C, R(16), R(0), Shift], [], V7;
+    0x01900010 = [R, R, ShiftReg] => [R(12), C, R(16), R(0), ShiftReg], [], V7;
+]
+"bic" = [
+    0x03C00000 = [R, R, ModImm(Plain)] => [R(12), R(16), ModImm(Plain)], [], V7;
+    0x02000000 = [R, R, ModImm(Inverted)] => [R(12), R(16), ModImm(Inverted)], [], V7;
+    0x03C00000 = [R, ModImm(Plain)] => [R(12), C, R(16), ModImm(Plain)], [], V7;
+    0x02000000 = [R, ModImm(Inverted)] => [R(12), C, R(16), ModImm(Inverted)], [], V7;
+    0x01C00000 = [R, R, R, End, Shift] => [R(12), R(16), R(0), Shift], [], V7;
+    0x01C00010 = [R, R, R, ShiftReg] => [R(12), R(16), R(0), ShiftReg], [], V7;
+    0x01C00000 = [R, R, End, Shift] => [R(12), C, R(16), R(0), Shift], [], V7;
+    0x01C00010 = [R, R, ShiftReg] => [R(12), C, R(16), R(0), ShiftReg], [], V7;
+]
+"bics" = [
+    0x03D00000 = [R, R, ModImm(Plain)] => [R(12), R(16), ModImm(Plain)], [], V7;
+    0x02100000 = [R, R, ModImm(Inverted)] => [R(12), R(16), ModImm(Inverted)], [], V7;
+    0x03D00000 = [R, ModImm(Plain)] => [R(12), C, R(16), ModImm(Plain)], [], V7;
+    0x02100000 = [R, ModImm(Inverted)] => [R(12), C, R(16), ModImm(Inverted)], [], V7;
+    0x01D00000 = [R, R, R, End, Shift] => [R(12), R(16), R(0), Shift], [], V7;
+    0x01D00010 = [R, R, R, ShiftReg] => [R(12), R(16), R(0), ShiftReg], [], V7;
+    0x01D00000 = [R, R, End, Shift] => [R(12), C, R(16), R(0), Shift], [], V7;
+    0x01D00010 = [R, R, ShiftReg] => [R(12), C, R(16), R(0), ShiftReg], [], V7;
+]
+"tst" = [
+    0x03100000 = [R, ModImm(Plain)] => [R(16), ModImm(Plain)], [], V7;
+    0x01100000 = [R, R, End, Shift] => [R(16), R(0), Shift], [], V7;
+    0x01100010 = [R, R, ShiftReg] => [R(16), R(0), ShiftReg], [], V7;
+]
+"teq" = [
+    0x03300000 = [R, ModImm(Plain)] => [R(16), ModImm(Plain)], [], V7;
+    0x01300000 = [R, R, End, Shift] => [R(16), R(0), Shift], [], V7;
+    0x01300010 = [R, R, ShiftReg] => [R(16), R(0), ShiftReg], [], V7;
+]
+"cmp" = [
+    0x03500000 = [R, ModImm(Plain)] => [R(16), ModImm(Plain)], [], V7;
+    0x03700000 = [R, ModImm(Negated)] => [R(16), ModImm(Negated)], [], V7;
+    0x01500000 = [R, R, End, Shift] => [R(16), R(0), Shift], [], V7;
+    0x01500010 = [R, R, ShiftReg] => [R(16), R(0), ShiftReg], [], V7;
+]
+"cmn" = [
+    0x03700000 = [R, ModImm(Plain)] => [R(16), ModImm(Plain)], [], V7;
+    0x03500000 = [R, ModImm(Negated)] => [R(16), ModImm(Negated)], [], V7;
+    0x01700000 = [R, R, End, Shift] => [R(16), R(0), Shift], [], V7;
+    0x01700010 = [R, R, ShiftReg] => [R(16), R(0), ShiftReg], [], V7;
+]
+"mov" = [
+    0x03A00000 = [R, ModImm(Plain)] => [R(12), ModImm(Plain)], [], V7;
+    0x03E00000 = [R, ModImm(Inverted)] => [R(12), ModImm(Inverted)], [], V7;
+    0x03000000 = [R, Imm] => [R(12), Imm(&IMM16_A)], [], V7;
+    0x01A00000 = [R, R, End, Shift] => [R(12), R(0), Shift], [], V7;
+    0x01A00010 = [R, R, ShiftReg] => [R(12), R(0), ShiftReg], [], V7;
+]
+"movs" = [
+    0x03B00000 = [R, ModImm(Plain)] => [R(12), ModImm(Plain)], [], V7;
+    0x03F00000 = [R, ModImm(Inverted)] => [R(12), ModImm(Inverted)], [], V7;
+    0x01B00000 = [R, R, End, Shift] => [R(12), R(0), Shift], [], V7;
+    0x01B00010 = [R, R, ShiftReg] => [R(12), R(0), ShiftReg], [], V7;
+]
+"mvn" = [
+    0x03E00000 = [R, ModImm(Plain)] => [R(12), ModImm(Plain)], [], V7;
+    0x03A00000 = [R, ModImm(Inverted)] => [R(12), ModImm(Inverted)], [], V7;
+    0x01E00000 = [R, R, End, Shift] => [R(12), R(0), Shift], [], V7;
+    0x01E00010 = [R, R, ShiftReg] => [R(12), R(0), ShiftReg], [], V7;
+]
+"mvns" = [
+    0x03F00000 = [R, ModImm(Plain)] => [R(12), ModImm(Plain)], [], V7;
+    0x03B00000 = [R, ModImm(Inverted)] => [R(12), ModImm(Inverted)], [], V7;
+    0x01F00000 = [R, R, End, Shift] => [R(12), R(0), Shift], [], V7;
+    0x01F00010 = [R, R, ShiftReg] => [R(12), R(0), ShiftReg], [], V7;
+]
+"movw" = [
+    0x03000000 = [R, Imm] => [R(12), Imm(&IMM16_A)], [], V7;
+]
+"movt" = [
+    0x03400000 = [R, Imm] => [R(12), Imm(&IMM16_A)], [], V7;
+]
+
+// shifts
+"lsl" = [
+    0x01A00000 = [R, R, Imm] => [R(12), R(0), ShiftAmt(LSL, &IMM5_A)], [], V7;
+    0x01A00010 = [R, R, R] => [R(12), R(0), R(8)], [], V7;
+]
+"lsls" = [
+    0x01B00000 = [R, R, Imm] => [R(12), R(0), ShiftAmt(LSL, &IMM5_A)], [], V7;
+    0x01B00010 = [R, R, R] => [R(12), R(0), R(8)], [], V7;
+]
+"lsr" = [
+    0x01A00020 = [R, R, Imm] => [R(12), R(0), ShiftAmt(LSR, &IMM5_A)], [], V7;
+    0x01A00030 = [R, R, R] => [R(12), R(0), R(8)], [], V7;
+]
+"lsrs" = [
+    0x01B00020 = [R, R, Imm] => [R(12), R(0), ShiftAmt(LSR, &IMM5_A)], [], V7;
+    0x01B00030 = [R, R, R] => [R(12), R(0), R(8)], [], V7;
+]
+"asr" = [
+    0x01A00040 = [R, R, Imm] => [R(12), R(0), ShiftAmt(ASR, &IMM5_A)], [], V7;
+    0x01A00050 = [R, R, R] => [R(12), R(0), R(8)], [], V7;
+]
+"asrs" = [
+    0x01B00040 = [R, R, Imm] => [R(12), R(0), ShiftAmt(ASR, &IMM5_A)], [], V7;
+    0x01B00050 = [R, R, R] => [R(12), R(0), R(8)], [], V7;
+]
+"ror" = [
+    0x01A00060 = [R, R, Imm] => [R(12), R(0), ShiftAmt(ROR, &IMM5_A)], [], V7;
+    0x01A00070 = [R, R, R] => [R(12), R(0), R(8)], [], V7;
+]
+"rors" = [
+    0x01B00060 = [R, R, Imm] => [R(12), R(0), ShiftAmt(ROR, &IMM5_A)], [], V7;
+    0x01B00070 = [R, R, R] => [R(12), R(0), R(8)], [], V7;
+]
+"rrx" = [
+    0x01A00060 = [R, R] => [R(12), R(0)], [], V7;
+]
+"rrxs" = [
+    0x01B00060 = [R, R] => [R(12), R(0)], [], V7;
+]
+
+// multiply and divide
+"mul" = [
+    0x00000090 = [R, R, R] => [R(16), R(0), R(8)], [], V7;
+]
+"muls" = [
+    0x00100090 = [R, R, R] => [R(16), R(0), R(8)], [], V7;
+]
+"mla" = [
+    0x00200090 = [R, R, R, R] => [R(16), R(0), R(8), R(12)], [], V7;
+]
+"mlas" = [
+    0x00300090 = [R, R, R, R] => [R(16), R(0), R(8), R(12)], [], V7;
+]
+"mls" = [
+    0x00600090 = [R, R, R, R] => [R(16), R(0), R(8), R(12)], [], V7;
+]
+"umull" = [
+    0x00800090 = [R, R, R, R] => [R(12), R(16), R(0), R(8)], [], V7;
+]
+"umulls" = [
+    0x00900090 = [R, R, R, R] => [R(12), R(16), R(0), R(8)], [], V7;
+]
+"umlal" = [
+    0x00A00090 = [R, R, R, R] => [R(12), R(16), R(0), R(8)], [], V7;
+]
+"umlals" = [
+    0x00B00090 = [R, R, R, R] => [R(12), R(16), R(0), R(8)], [], V7;
+]
+"smull" = [
+    0x00C00090 = [R, R, R, R] => [R(12), R(16), R(0), R(8)], [], V7;
+]
+"smulls" = [
+    0x00D00090 = [R, R, R, R] => [R(12), R(16), R(0), R(8)], [], V7;
+]
+"smlal" = [
+    0x00E00090 = [R, R, R, R] => [R(12), R(16), R(0), R(8)], [], V7;
+]
+"smlals" = [
+    0x00F00090 = [R, R, R, R] => [R(12), R(16), R(0), R(8)], [], V7;
+]
+"sdiv" = [
+    0x0710F010 = [R, R, R] => [R(16), R(0), R(8)], [], IDIV;
+]
+"udiv" = [
+    0x0730F010 = [R, R, R] => [R(16), R(0), R(8)], [], IDIV;
+]
+
+// miscellaneous data processing
+"clz" = [
+    0x016F0F10 = [R, R] => [R(12), R(0)], [], V7;
+]
+"rev" = [
+    0x06BF0F30 = [R, R] => [R(12), R(0)], [], V7;
+]
+"rev16" = [
+    0x06BF0FB0 = [R, R] => [R(12), R(0)], [], V7;
+]
+"revsh" = [
+    0x06FF0FB0 = [R, R] => [R(12), R(0)], [], V7;
+]
+"rbit" = [
+    0x06FF0F30 = [R, R] => [R(12), R(0)], [], V7;
+]
+"sxtb" = [
+    0x06AF0070 = [R, R, End, Ror] => [R(12), R(0), Rotation(10)], [], V7;
+]
+"sxth" = [
+    0x06BF0070 = [R, R, End, Ror] => [R(12), R(0), Rotation(10)], [], V7;
+]
+"uxtb" = [
+    0x06EF0070 = [R, R, End, Ror] => [R(12), R(0), Rotation(10)], [], V7;
+]
+"uxth" = [
+    0x06FF0070 = [R, R, End, Ror] => [R(12), R(0), Rotation(10)], [], V7;
+]
+"ubfx" = [
+    0x07E00050 = [R, R, Imm, Imm] => [R(12), R(0), Imm(&IMM5_A), WidthDec(&IMM5_16)], [], V7;
+]
+"sbfx" = [
+    0x07A00050 = [R, R, Imm, Imm] => [R(12), R(0), Imm(&IMM5_A), WidthDec(&IMM5_16)], [], V7;
+]
+"bfi" = [
+    0x07C00010 = [R, R, Imm, Imm] => [R(12), R(0), Imm(&IMM5_A), Usumdec(&IMM5_16)], [], V7;
+]
+"bfc" = [
+    0x07C0001F = [R, Imm, Imm] => [R(12), Imm(&IMM5_A), Usumdec(&IMM5_16)], [], V7;
+]
+
+// loads and stores
+"ldr" = [
+    0x04100000 = [R, Ref] => [R(12), R(16), Mode(24, 21), UImm(&IMM12, 23)], [], V7;
+    0x06100000 = [R, RefIdx] => [R(12), R(16), Mode(24, 21), Index(0, 23), Shift], [], V7;
+    0x051F0000 = [R, Offset] => [R(12), Offset(A32_LDR)], [], V7;
+]
+"ldrb" = [
+    0x04500000 = [R, Ref] => [R(12), R(16), Mode(24, 21), UImm(&IMM12, 23)], [], V7;
+    0x06500000 = [R, RefIdx] => [R(12), R(16), Mode(24, 21), Index(0, 23), Shift], [], V7;
+    0x055F0000 = [R, Offset] => [R(12), Offset(A32_LDR)], [], V7;
+]
+"str" = [
+    0x04000000 = [R, Ref] => [R(12), R(16), Mode(24, 21), UImm(&IMM12, 23)], [], V7;
+    0x06000000 = [R, RefIdx] => [R(12), R(16), Mode(24, 21), Index(0, 23), Shift], [], V7;
+]
+"strb" = [
+    0x04400000 = [R, Ref] => [R(12), R(16), Mode(24, 21), UImm(&IMM12, 23)], [], V7;
+    0x06400000 = [R, RefIdx] => [R(12), R(16), Mode(24, 21), Index(0, 23), Shift], [], V7;
+]
+"ldrh" = [
+    0x005000B0 = [R, Ref] => [R(12), R(16), Mode(24, 21), UImm(&IMM8_SPLIT, 23)], [], V7;
+    0x001000B0 = [R, RefIdxPlain] => [R(12), R(16), Mode(24, 21), Index(0, 23)], [], V7;
+    0x015F00B0 = [R, Offset] => [R(12), Offset(A32_LDRH)], [], V7;
+]
+"ldrsb" = [
+    0x005000D0 = [R, Ref] => [R(12), R(16), Mode(24, 21), UImm(&IMM8_SPLIT, 23)], [], V7;
+    0x001000D0 = [R, RefIdxPlain] => [R(12), R(16), Mode(24, 21), Index(0, 23)], [], V7;
+    0x015F00D0 = [R, Offset] => [R(12), Offset(A32_LDRH)], [], V7;
+]
+"ldrsh" = [
+    0x005000F0 = [R, Ref] => [R(12), R(16), Mode(24, 21), UImm(&IMM8_SPLIT, 23)], [], V7;
+    0x001000F0 = [R, RefIdxPlain] => [R(12), R(16), Mode(24, 21), Index(0, 23)], [], V7;
+    0x015F00F0 = [R, Offset] => [R(12), Offset(A32_LDRH)], [], V7;
+]
+"strh" = [
+    0x004000B0 = [R, Ref] => [R(12), R(16), Mode(24, 21), UImm(&IMM8_SPLIT, 23)], [], V7;
+    0x000000B0 = [R, RefIdxPlain] => [R(12), R(16), Mode(24, 21), Index(0, 23)], [], V7;
+]
+"ldrd" = [
+    0x004000D0 = [R, R, Ref] => [REven(12), RNext, R(16), Mode(24, 21), UImm(&IMM8_SPLIT, 23)], [], V7;
+    0x000000D0 = [R, R, RefIdxPlain] => [REven(12), RNext, R(16), Mode(24, 21), Index(0, 23)], [], V7;
+    0x014F00D0 = [R, R, Offset] => [REven(12), RNext, Offset(A32_LDRH)], [], V7;
+]
+"strd" = [
+    0x004000F0 = [R, R, Ref] => [REven(12), RNext, R(16), Mode(24, 21), UImm(&IMM8_SPLIT, 23)], [], V7;
+    0x000000F0 = [R, R, RefIdxPlain] => [REven(12), RNext, R(16), Mode(24, 21), Index(0, 23)], [], V7;
+]
+"ldrex" = [
+    0x01900F9F = [R, RefBase] => [R(12), R(16)], [], V7;
+]
+"ldrexb" = [
+    0x01D00F9F = [R, RefBase] => [R(12), R(16)], [], V7;
+]
+"ldrexh" = [
+    0x01F00F9F = [R, RefBase] => [R(12), R(16)], [], V7;
+]
+"strex" = [
+    0x01800F90 = [R, R, RefBase] => [R(12), R(0), R(16)], [], V7;
+]
+"strexb" = [
+    0x01C00F90 = [R, R, RefBase] => [R(12), R(0), R(16)], [], V7;
+]
+"strexh" = [
+    0x01E00F90 = [R, R, RefBase] => [R(12), R(0), R(16)], [], V7;
+]
+
+// load and store multiple
+"ldm" = [
+    0x08900000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"ldmia" = [
+    0x08900000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"ldmfd" = [
+    0x08900000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"ldmib" = [
+    0x09900000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"ldmed" = [
+    0x09900000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"ldmda" = [
+    0x08100000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"ldmfa" = [
+    0x08100000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"ldmdb" = [
+    0x09100000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"ldmea" = [
+    0x09100000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"stm" = [
+    0x08800000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"stmia" = [
+    0x08800000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"stmea" = [
+    0x08800000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"stmib" = [
+    0x09800000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"stmfa" = [
+    0x09800000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"stmda" = [
+    0x08000000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"stmed" = [
+    0x08000000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"stmdb" = [
+    0x09000000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"stmfd" = [
+    0x09000000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"push" = [
+    0x052D0004 = [ListOne] => [ListOne(12)], [], V7;
+    0x092D0000 = [List] => [List], [], V7;
+]
+"pop" = [
+    0x049D0004 = [ListOne] => [ListOne(12)], [], V7;
+    0x08BD0000 = [List] => [List], [], V7;
+]
+
+// branches
+"b" = [
+    0x0A000000 = [Offset] => [Offset(A32_B)], [], V7;
+]
+"bl" = [
+    0x0B000000 = [Offset] => [Offset(A32_B)], [], V7;
+]
+"blx" = [
+    0xFA000000 = [Offset] => [Offset(A32_BLX)], [UNCOND], V7;
+    0x012FFF30 = [R] => [R(0)], [], V7;
+]
+"bx" = [
+    0x012FFF10 = [R] => [R(0)], [], V7;
+]
+
+// miscellaneous
+"nop" = [
+    0x0320F000 = [] => [], [], V7;
+]
+"yield" = [
+    0x0320F001 = [] => [], [], V7;
+]
+"wfe" = [
+    0x0320F002 = [] => [], [], V7;
+]
+"wfi" = [
+    0x0320F003 = [] => [], [], V7;
+]
+"sev" = [
+    0x0320F004 = [] => [], [], V7;
+]
+"svc" = [
+    0x0F000000 = [Imm] => [Imm(&IMM24)], [], V7;
+]
+"bkpt" = [
+    0xE1200070 = [End, Imm] => [Imm(&IMM16_SPLIT)], [UNCOND], V7;
+]
+"udf" = [
+    0xE7F000F0 = [End, Imm] => [Imm(&IMM16_SPLIT)], [UNCOND], V7;
+]
+"dmb" = [
+    0xF57FF050 = [End, Barrier] => [Barrier], [UNCOND], V7;
+]
+"dsb" = [
+    0xF57FF040 = [End, Barrier] => [Barrier], [UNCOND], V7;
+]
+"isb" = [
+    0xF57FF060 = [End, Barrier] => [Barrier], [UNCOND], V7;
+]
+"clrex" = [
+    0xF57FF01F = [] => [], [UNCOND], V7;
+]
+"mrs" = [
+    0x010F0000 = [R, Lit("apsr")] => [R(12), A], [], V7;
+]
+"msr" = [
+    0x0128F000 = [Lit("apsr_nzcvq"), R] => [A, R(0)], [], V7;
+]
+
+// VFP and NEON
+"vadd.f32" = [
+    0x0E300A00 = [S, S, S] => [Sreg(12, 22), Sreg(16, 7), Sreg(0, 5)], [], VFP;
+    0xF2000D00 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xF2000D40 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vadd.f64" = [
+    0x0E300B00 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [], VFP;
+]
+"vsub.f32" = [
+    0x0E300A40 = [S, S, S] => [Sreg(12, 22), Sreg(16, 7), Sreg(0, 5)], [], VFP;
+    0xF2200D00 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xF2200D40 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vsub.f64" = [
+    0x0E300B40 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [], VFP;
+]
+"vmul.f32" = [
+    0x0E200A00 = [S, S, S] => [Sreg(12, 22), Sreg(16, 7), Sreg(0, 5)], [], VFP;
+    0xF3000D10 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xF3000D50 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vmul.f64" = [
+    0x0E200B00 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [], VFP;
+]
+"vnmul.f32" = [
+    0x0E200A40 = [S, S, S] => [Sreg(12, 22), Sreg(16, 7), Sreg(0, 5)], [], VFP;
+]
+"vnmul.f64" = [
+    0x0E200B40 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [], VFP;
+]
+"vdiv.f32" = [
+    0x0E800A00 = [S, S, S] => [Sreg(12, 22), Sreg(16, 7), Sreg(0, 5)], [], VFP;
+]
+"vdiv.f64" = [
+    0x0E800B00 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [], VFP;
+]
+"vmla.f32" = [
+    0x0E000A00 = [S, S, S] => [Sreg(12, 22), Sreg(16, 7), Sreg(0, 5)], [], VFP;
+]
+"vmla.f64" = [
+    0x0E000B00 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [], VFP;
+]
+"vmls.f32" = [
+    0x0E000A40 = [S, S, S] => [Sreg(12, 22), Sreg(16, 7), Sreg(0, 5)], [], VFP;
+]
+"vmls.f64" = [
+    0x0E000B40 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [], VFP;
+]
+"vfma.f32" = [
+    0x0EA00A00 = [S, S, S] => [Sreg(12, 22), Sreg(16, 7), Sreg(0, 5)], [], VFP | VFPV4;
+]
+"vfma.f64" = [
+    0x0EA00B00 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [], VFP | VFPV4;
+]
+"vfms.f32" = [
+    0x0EA00A40 = [S, S, S] => [Sreg(12, 22), Sreg(16, 7), Sreg(0, 5)], [], VFP | VFPV4;
+]
+"vfms.f64" = [
+    0x0EA00B40 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [], VFP | VFPV4;
+]
+"vneg.f32" = [
+    0x0EB10A40 = [S, S] => [Sreg(12, 22), Sreg(0, 5)], [], VFP;
+]
+"vneg.f64" = [
+    0x0EB10B40 = [D, D] => [Dreg(12, 22), Dreg(0, 5)], [], VFP;
+]
+"vabs.f32" = [
+    0x0EB00AC0 = [S, S] => [Sreg(12, 22), Sreg(0, 5)], [], VFP;
+]
+"vabs.f64" = [
+    0x0EB00BC0 = [D, D] => [Dreg(12, 22), Dreg(0, 5)], [], VFP;
+]
+"vsqrt.f32" = [
+    0x0EB10AC0 = [S, S] => [Sreg(12, 22), Sreg(0, 5)], [], VFP;
+]
+"vsqrt.f64" = [
+    0x0EB10BC0 = [D, D] => [Dreg(12, 22), Dreg(0, 5)], [], VFP;
+]
+"vcmp.f32" = [
+    0x0EB40A40 = [S, S] => [Sreg(12, 22), Sreg(0, 5)], [], VFP;
+    0x0EB50A40 = [S, FZero] => [Sreg(12, 22), A], [], VFP;
+]
+"vcmp.f64" = [
+    0x0EB40B40 = [D, D] => [Dreg(12, 22), Dreg(0, 5)], [], VFP;
+    0x0EB50B40 = [D, FZero] => [Dreg(12, 22), A], [], VFP;
+]
+"vcmpe.f32" = [
+    0x0EB40AC0 = [S, S] => [Sreg(12, 22), Sreg(0, 5)], [], VFP;
+    0x0EB50AC0 = [S, FZero] => [Sreg(12, 22), A], [], VFP;
+]
+"vcmpe.f64" = [
+    0x0EB40BC0 = [D, D] => [Dreg(12, 22), Dreg(0, 5)], [], VFP;
+    0x0EB50BC0 = [D, FZero] => [Dreg(12, 22), A], [], VFP;
+]
+"vmov.f32" = [
+    0x0EB00A40 = [S, S] => [Sreg(12, 22), Sreg(0, 5)], [], VFP;
+    0x0EB00A00 = [S, FImm] => [Sreg(12, 22), FImm], [], VFP;
+]
+"vmov.f64" = [
+    0x0EB00B40 = [D, D] => [Dreg(12, 22), Dreg(0, 5)], [], VFP;
+    0x0EB00B00 = [D, FImm] => [Dreg(12, 22), FImm], [], VFP;
+]
+"vmov" = [
+    0x0E000A10 = [S, R] => [Sreg(16, 7), R(12)], [], VFP;
+    0x0E100A10 = [R, S] => [R(12), Sreg(16, 7)], [], VFP;
+    0x0C400B10 = [D, R, R] => [Dreg(0, 5), R(12), R(16)], [], VFP;
+    0x0C500B10 = [R, R, D] => [R(12), R(16), Dreg(0, 5)], [], VFP;
+    0x0EB00A40 = [S, S] => [Sreg(12, 22), Sreg(0, 5)], [], VFP;
+    0xF2200110 = [D, D] => [Dreg(12, 22), Dreg(16, 7), C, Dreg(0, 5)], [UNCOND], NEON;
+    0xF2200150 = [Q, Q] => [Qreg(12, 22), Qreg(16, 7), C, Qreg(0, 5)], [UNCOND], NEON;
+]
+"vcvt.f64.f32" = [
+    0x0EB70AC0 = [D, S] => [Dreg(12, 22), Sreg(0, 5)], [], VFP;
+]
+"vcvt.f32.f64" = [
+    0x0EB70BC0 = [S, D] => [Sreg(12, 22), Dreg(0, 5)], [], VFP;
+]
+"vcvt.s32.f32" = [
+    0x0EBD0AC0 = [S, S] => [Sreg(12, 22), Sreg(0, 5)], [], VFP;
+]
+"vcvt.u32.f32" = [
+    0x0EBC0AC0 = [S, S] => [Sreg(12, 22), Sreg(0, 5)], [], VFP;
+]
+"vcvt.s32.f64" = [
+    0x0EBD0BC0 = [S, D] => [Sreg(12, 22), Dreg(0, 5)], [], VFP;
+]
+"vcvt.u32.f64" = [
+    0x0EBC0BC0 = [S, D] => [Sreg(12, 22), Dreg(0, 5)], [], VFP;
+]
+"vcvt.f32.s32" = [
+    0x0EB80AC0 = [S, S] => [Sreg(12, 22), Sreg(0, 5)], [], VFP;
+]
+"vcvt.f32.u32" = [
+    0x0EB80A40 = [S, S] => [Sreg(12, 22), Sreg(0, 5)], [], VFP;
+]
+"vcvt.f64.s32" = [
+    0x0EB80BC0 = [D, S] => [Dreg(12, 22), Sreg(0, 5)], [], VFP;
+]
+"vcvt.f64.u32" = [
+    0x0EB80B40 = [D, S] => [Dreg(12, 22), Sreg(0, 5)], [], VFP;
+]
+"vldr" = [
+    0x0D100A00 = [S, RefOffset] => [Sreg(12, 22), R(16), UImm(&IMM8_S2, 23)], [], VFP;
+    0x0D100B00 = [D, RefOffset] => [Dreg(12, 22), R(16), UImm(&IMM8_S2, 23)], [], VFP;
+    0x0D1F0A00 = [S, Offset] => [Sreg(12, 22), Offset(A32_VLDR)], [], VFP;
+    0x0D1F0B00 = [D, Offset] => [Dreg(12, 22), Offset(A32_VLDR)], [], VFP;
+]
+"vstr" = [
+    0x0D000A00 = [S, RefOffset] => [Sreg(12, 22), R(16), UImm(&IMM8_S2, 23)], [], VFP;
+    0x0D000B00 = [D, RefOffset] => [Dreg(12, 22), R(16), UImm(&IMM8_S2, 23)], [], VFP;
+]
+"vpush" = [
+    0x0D2D0A00 = [SList] => [VList], [], VFP;
+    0x0D2D0B00 = [DList] => [VList], [], VFP;
+]
+"vpop" = [
+    0x0CBD0A00 = [SList] => [VList], [], VFP;
+    0x0CBD0B00 = [DList] => [VList], [], VFP;
+]
+"vldm" = [
+    0x0C900A00 = [RBang, SList] => [R(16), Bang(21), VList], [], VFP;
+    0x0C900B00 = [RBang, DList] => [R(16), Bang(21), VList], [], VFP;
+]
+"vldmia" = [
+    0x0C900A00 = [RBang, SList] => [R(16), Bang(21), VList], [], VFP;
+    0x0C900B00 = [RBang, DList] => [R(16), Bang(21), VList], [], VFP;
+]
+"vstm" = [
+    0x0C800A00 = [RBang, SList] => [R(16), Bang(21), VList], [], VFP;
+    0x0C800B00 = [RBang, DList] => [R(16), Bang(21), VList], [], VFP;
+]
+"vstmia" = [
+    0x0C800A00 = [RBang, SList] => [R(16), Bang(21), VList], [], VFP;
+    0x0C800B00 = [RBang, DList] => [R(16), Bang(21), VList], [], VFP;
+]
+"vmrs" = [
+    0x0EF1FA10 = [Lit("apsr_nzcv"), Lit("fpscr")] => [A, A], [], VFP;
+    0x0EF10A10 = [R, Lit("fpscr")] => [R(12), A], [], VFP;
+]
+"vmsr" = [
+    0x0EE10A10 = [Lit("fpscr"), R] => [A, R(12)], [], VFP;
+]
+"vadd.i8" = [
+    0xF2000800 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xF2000840 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vsub.i8" = [
+    0xF3000800 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xF3000840 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vadd.i16" = [
+    0xF2100800 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xF2100840 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vsub.i16" = [
+    0xF3100800 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xF3100840 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vadd.i32" = [
+    0xF2200800 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xF2200840 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vsub.i32" = [
+    0xF3200800 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xF3200840 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vadd.i64" = [
+    0xF2300800 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xF2300840 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vsub.i64" = [
+    0xF3300800 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xF3300840 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vmul.i8" = [
+    0xF2000910 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xF2000950 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vmul.i16" = [
+    0xF2100910 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xF2100950 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vmul.i32" = [
+    0xF2200910 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xF2200950 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vand" = [
+    0xF2000110 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xF2000150 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vbic" = [
+    0xF2100110 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xF2100150 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vorr" = [
+    0xF2200110 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xF2200150 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vorn" = [
+    0xF2300110 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xF2300150 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"veor" = [
+    0xF3000110 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xF3000150 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vdup.8" = [
+    0x0EC00B10 = [D, R] => [Dreg(16, 7), R(12)], [], NEON;
+    0x0EE00B10 = [Q, R] => [Qreg(16, 7), R(12)], [], NEON;
+]
+"vdup.16" = [
+    0x0E800B30 = [D, R] => [Dreg(16, 7), R(12)], [], NEON;
+    0x0EA00B30 = [Q, R] => [Qreg(16, 7), R(12)], [], NEON;
+]
+"vdup.32" = [
+    0x0E800B10 = [D, R] => [Dreg(16, 7), R(12)], [], NEON;
+    0x0EA00B10 = [Q, R] => [Qreg(16, 7), R(12)], [], NEON;
+]
+
+)
@@ -0,0 +1,947 @@
+// T32 instruction encodings. Wide instructions contain their first halfword in the high 16 bits,
+// narrow instructions are marked with the NARROW flag.
+Ops!(
+
+// data processing
+"ands" = [
+    0x00004000 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW, OUTSIDE_IT], V7;
+    0x00004000 = [Lo, Same, Lo] => [Rlo(0), A, Rlo(3)], [NARROW, OUTSIDE_IT], V7;
+    0xF0100000 = [R, R, ModImm(Plain)] => [R(8), R(16), ModImm(Plain)], [], V7;
+    0xF0300000 = [R, R, ModImm(Inverted)] => [R(8), R(16), ModImm(Inverted)], [], V7;
+    0xF0100000 = [R, ModImm(Plain)] => [R(8), C, R(16), ModImm(Plain)], [], V7;
+    0xF0300000 = [R, ModImm(Inverted)] => [R(8), C, R(16), ModImm(Inverted)], [], V7;
+    0xEA100000 = [R, R, R, End, Shift] => [R(8), R(16), R(0), Shift], [], V7;
+    0xEA100000 = [R, R, End, Shift] => [R(8), C, R(16), R(0), Shift], [], V7;
+]
+"and" = [
+    0x00004000 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW, INSIDE_IT], V7;
+    0x00004000 = [Lo, Same, Lo] => [Rlo(0), A, Rlo(3)], [NARROW, INSIDE_IT], V7;
+    0xF0000000 = [R, R, ModImm(Plain)] => [R(8), R(16), ModImm(Plain)], [], V7;
+    0xF0200000 = [R, R, ModImm(Inverted)] => [R(8), R(16), ModImm(Inverted)], [], V7;
+    0xF0000000 = [R, ModImm(Plain)] => [R(8), C, R(16), ModImm(Plain)], [], V7;
+    0xF0200000 = [R, ModImm(Inverted)] => [R(8), C, R(16), ModImm(Inverted)], [], V7;
+    0xEA000000 = [R, R, R, End, Shift] => [R(8), R(16), R(0), Shift], [], V7;
+    0xEA000000 = [R, R, End, Shift] => [R(8), C, R(16), R(0), Shift], [], V7;
+]
+"bics" = [
+    0x00004380 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW, OUTSIDE_IT], V7;
+    0x00004380 = [Lo, Same, Lo] => [Rlo(0), A, Rlo(3)], [NARROW, OUTSIDE_IT], V7;
+    0xF0300000 = [R, R, ModImm(Plain)] => [R(8), R(16), ModImm(Plain)], [], V7;
+    0xF0100000 = [R, R, ModImm(Inverted)] => [R(8), R(16), ModImm(Inverted)], [], V7;
+    0xF0300000 = [R, ModImm(Plain)] => [R(8), C, R(16), ModImm(Plain)], [], V7;
+    0xF0100000 = [R, ModImm(Inverted)] => [R(8), C, R(16), ModImm(Inverted)], [], V7;
+    0xEA300000 = [R, R, R, End, Shift] => [R(8), R(16), R(0), Shift], [], V7;
+    0xEA300000 = [R, R, End, Shift] => [R(8), C, R(16), R(0), Shift], [], V7;
+]
+"bic" = [
+    0x00004380 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW, INSIDE_IT], V7;
+    0x00004380 = [Lo, Same, Lo] => [Rlo(0), A, Rlo(3)], [NARROW, INSIDE_IT], V7;
+    0xF0200000 = [R, R, ModImm(Plain)] => [R(8), R(16), ModImm(Plain)], [], V7;
+    0xF0000000 = [R, R, ModImm(Inverted)] => [R(8), R(16), ModImm(Inverted)], [], V7;
+    0xF0200000 = [R, ModImm(Plain)] => [R(8), C, R(16), ModImm(Plain)], [], V7;
+    0xF0000000 = [R, ModImm(Inverted)] => [R(8), C, R(16), ModImm(Inverted)], [], V7;
+    0xEA200000 = [R, R, R, End, Shift] => [R(8), R(16), R(0), Shift], [], V7;
+    0xEA200000 = [R, R, End, Shift] => [R(8), C, R(16), R(0), Shift], [], V7;
+]
+"orrs" = [
+    0x00004300 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW, OUTSIDE_IT], V7;
+    0x00004300 = [Lo, Same, Lo] => [Rlo(0), A, Rlo(3)], [NARROW, OUTSIDE_IT], V7;
+    0xF0500000 = [R, R, ModImm(Plain)] => [R(8), R(16), ModImm(Plain)], [], V7;
+    0xF0700000 = [R, R, ModImm(Inverted)] => [R(8), R(16), ModImm(Inverted)], [], V7;
+    0xF0500000 = [R, ModImm(Plain)] => [R(8), C, R(16), ModImm(Plain)], [], V7;
+    0xF0700000 = [R, ModImm(Inverted)] => [R(8), C, R(16), ModImm(Inverted)], [], V7;
+    0xEA500000 = [R, R, R, End, Shift] => [R(8), R(16), R(0), Shift], [], V7;
+    0xEA500000 = [R, R, End, Shift] => [R(8), C, R(16), R(0), Shift], [], V7;
+]
+"orr" = [
+    0x00004300 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW, INSIDE_IT], V7;
+    0x00004300 = [Lo, Same, Lo] => [Rlo(0), A, Rlo(3)], [NARROW, INSIDE_IT], V7;
+    0xF0400000 = [R, R, ModImm(Plain)] => [R(8), R(16), ModImm(Plain)], [], V7;
+    0xF0600000 = [R, R, ModImm(Inverted)] => [R(8), R(16), ModImm(Inverted)], [], V7;
+    0xF0400000 = [R, ModImm(Plain)] => [R(8), C, R(16), ModImm(Plain)], [], V7;
+    0xF0600000 = [R, ModImm(Inverted)] => [R(8), C, R(16), ModImm(Inverted)], [], V7;
+    0xEA400000 = [R, R, R, End, Shift] => [R(8), R(16), R(0), Shift], [], V7;
+    0xEA400000 = [R, R, End, Shift] => [R(8), C, R(16), R(0), Shift], [], V7;
+]
+"orn" = [
+    0xF0600000 = [R, R, ModImm(Plain)] => [R(8), R(16), ModImm(Plain)], [], V7;
+    0xF0400000 = [R, R, ModImm(Inverted)] => [R(8), R(16), ModImm(Inverted)], [], V7;
+    0xF0600000 = [R, ModImm(Plain)] => [R(8), C, R(16), ModImm(Plain)], [], V7;
+    0xF0400000 = [R, ModImm(Inverted)] => [R(8), C, R(16), ModImm(Inverted)], [], V7;
+    0xEA600000 = [R, R, R, End, Shift] => [R(8), R(16), R(0), Shift], [], V7;
+    0xEA600000 = [R, R, End, Shift] => [R(8), C, R(16), R(0), Shift], [], V7;
+]
+"orns" = [
+    0xF0700000 = [R, R, ModImm(Plain)] => [R(8), R(16), ModImm(Plain)], [], V7;
+    0xF0500000 = [R, R, ModImm(Inverted)] => [R(8), R(16), ModImm(Inverted)], [], V7;
+    0xF0700000 = [R, ModImm(Plain)] => [R(8), C, R(16), ModImm(Plain)], [], V7;
+    0xF0500000 = [R, ModImm(Inverted)] => [R(8), C, R(16), ModImm(Inverted)], [], V7;
+    0xEA700000 = [R, R, R, End, Shift] => [R(8), R(16), R(0), Shift], [], V7;
+    0xEA700000 = [R, R, End, Shift] => [R(8), C, R(16), R(0), Shift], [], V7;
+]
+"eors" = [
+    0x00004040 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW, OUTSIDE_IT], V7;
+    0x00004040 = [Lo, Same, Lo] => [Rlo(0), A, Rlo(3)], [NARROW, OUTSIDE_IT], V7;
+    0xF0900000 = [R, R, ModImm(Plain)] => [R(8), R(16), ModImm(Plain)], [], V7;
+    0xF0900000 = [R, ModImm(Plain)] => [R(8), C, R(16), ModImm(Plain)], [], V7;
+    0xEA900000 = [R, R, R, End, Shift] => [R(8), R(16), R(0), Shift], [], V7;
+    0xEA900000 = [R, R, End, Shift] => [R(8), C, R(16), R(0), Shift], [], V7;
+]
+"eor" = [
+    0x00004040 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW, INSIDE_IT], V7;
+    0x00004040 = [Lo, Same, Lo] => [Rlo(0), A, Rlo(3)], [NARROW, INSIDE_IT], V7;
+    0xF0800000 = [R, R, ModImm(Plain)] => [R(8), R(16), ModImm(Plain)], [], V7;
+    0xF0800000 = [R, ModImm(Plain)] => [R(8), C, R(16), ModImm(Plain)], [], V7;
+    0xEA800000 = [R, R, R, End, Shift] => [R(8), R(16), R(0), Shift], [], V7;
+    0xEA800000 = [R, R, End, Shift] => [R(8), C, R(16), R(0), Shift], [], V7;
+]
+"adds" = [
+    0x00001C00 = [Lo, Lo, SmallImm(&IMM3_N)] => [Rlo(0), Rlo(3), Imm(&IMM3_N)], [NARROW, OUTSIDE_IT], V7;
+    0x00003000 = [Lo, SmallImm(&IMM8)] => [Rlo(8), Imm(&IMM8)], [NARROW, OUTSIDE_IT], V7;
+    0x00003000 = [Lo, Same, SmallImm(&IMM8)] => [Rlo(8), A, Imm(&IMM8)], [NARROW, OUTSIDE_IT], V7;
+    0x00001800 = [Lo, Lo, Lo] => [Rlo(0), Rlo(3), Rlo(6)], [NARROW, OUTSIDE_IT], V7;
+    0x00001800 = [Lo, Lo] => [Rlo(0), C, Rlo(3), Rlo(6)], [NARROW, OUTSIDE_IT], V7;
+    0xF1100000 = [R, R, ModImm(Plain)] => [R(8), R(16), ModImm(Plain)], [], V7;
+    0xF1B00000 = [R, R, ModImm(Negated)] => [R(8), R(16), ModImm(Negated)], [], V7;
+    0xF1100000 = [R, ModImm(Plain)] => [R(8), C, R(16), ModImm(Plain)], [], V7;
+    0xF1B00000 = [R, ModImm(Negated)] => [R(8), C, R(16), ModImm(Negated)], [], V7;
+    0xEB100000 = [R, R, R, End, Shift] => [R(8), R(16), R(0), Shift], [], V7;
+    0xEB100000 = [R, R, End, Shift] => [R(8), C, R(16), R(0), Shift], [], V7;
+]
+"add" = [
+    0x00001C00 = [Lo, Lo, SmallImm(&IMM3_N)] => [Rlo(0), Rlo(3), Imm(&IMM3_N)], [NARROW, INSIDE_IT], V7;
+    0x00003000 = [Lo, SmallImm(&IMM8)] => [Rlo(8), Imm(&IMM8)], [NARROW, INSIDE_IT], V7;
+    0x00003000 = [Lo, Same, SmallImm(&IMM8)] => [Rlo(8), A, Imm(&IMM8)], [NARROW, INSIDE_IT], V7;
+    0x00001800 = [Lo, Lo, Lo] => [Rlo(0), Rlo(3), Rlo(6)], [NARROW, INSIDE_IT], V7;
+    0x00001800 = [Lo, Lo] => [Rlo(0), C, Rlo(3), Rlo(6)], [NARROW, INSIDE_IT], V7;
+    0x00004400 = [R, R] => [RSplit(0, 7), R(3)], [NARROW], V7;
+    0x00004400 = [R, Same, R] => [RSplit(0, 7), A, R(3)], [NARROW], V7;
+    0x0000A800 = [Lo, Reg(SP), SmallImm(&IMM8_S2)] => [Rlo(8), A, Imm(&IMM8_S2)], [NARROW], V7;
+    0x0000B000 = [Reg(SP), SmallImm(&IMM7_S2_N)] => [A, Imm(&IMM7_S2_N)], [NARROW], V7;
+    0x0000B000 = [Reg(SP), Reg(SP), SmallImm(&IMM7_S2_N)] => [A, A, Imm(&IMM7_S2_N)], [NARROW], V7;
+    0xF1000000 = [R, R, ModImm(Plain)] => [R(8), R(16), ModImm(Plain)], [], V7;
+    0xF1A00000 = [R, R, ModImm(Negated)] => [R(8), R(16), ModImm(Negated)], [], V7;
+    0xF1000000 = [R, ModImm(Plain)] => [R(8), C, R(16), ModImm(Plain)], [], V7;
+    0xF1A00000 = [R, ModImm(Negated)] => [R(8), C, R(16), ModImm(Negated)], [], V7;
+    0xF2000000 = [R, R, Imm] => [R(8), R(16), Imm(&IMM12_T)], [], V7;
+    0xF2000000 = [R, Imm] => [R(8), C, R(16), Imm(&IMM12_T)], [], V7;
+    0xEB000000 = [R, R, R, End, Shift] => [R(8), R(16), R(0), Shift], [], V7;
+    0xEB000000 = [R, R, End, Shift] => [R(8), C, R(16), R(0), Shift], [], V7;
+]
+"adcs" = [
+    0x00004140 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW, OUTSIDE_IT], V7;
+    0x00004140 = [Lo, Same, Lo] => [Rlo(0), A, Rlo(3)], [NARROW, OUTSIDE_IT], V7;
+    0xF1500000 = [R, R, ModImm(Plain)] => [R(8), R(16), ModImm(Plain)], [], V7;
+    0xF1700000 = [R, R, ModImm(Inverted)] => [R(8), R(16), ModImm(Inverted)], [], V7;
+    0xF1500000 = [R, ModImm(Plain)] => [R(8), C, R(16), ModImm(Plain)], [], V7;
+    0xF1700000 = [R, ModImm(Inverted)] => [R(8), C, R(16), ModImm(Inverted)], [], V7;
+    0xEB500000 = [R, R, R, End, Shift] => [R(8), R(16), R(0), Shift], [], V7;
+    0xEB500000 = [R, R, End, Shift] => [R(8), C, R(16), R(0), Shift], [], V7;
+]
+"adc" = [
+    0x00004140 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW, INSIDE_IT], V7;
+    0x00004140 = [Lo, Same, Lo] => [Rlo(0), A, Rlo(3)], [NARROW, INSIDE_IT], V7;
+    0xF1400000 = [R, R, ModImm(Plain)] => [R(8), R(16), ModImm(Plain)], [], V7;
+    0xF1600000 = [R, R, ModImm(Inverted)] => [R(8), R(16), ModImm(Inverted)], [], V7;
+    0xF1400000 = [R, ModImm(Plain)] => [R(8), C, R(16), ModImm(Plain)], [], V7;
+    0xF1600000 = [R, ModImm(Inverted)] => [R(8), C, R(16), ModImm(Inverted)], [], V7;
+    0xEB400000 = [R, R, R, End, Shift] => [R(8), R(16), R(0), Shift], [], V7;
+    0xEB400000 = [R, R, End, Shift] => [R(8), C, R(16), R(0), Shift], [], V7;
+]
+"sbcs" = [
+    0x00004180 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW, OUTSIDE_IT], V7;
+    0x00004180 = [Lo, Same, Lo] => [Rlo(0), A, Rlo(3)], [NARROW, OUTSIDE_IT], V7;
+    0xF1700000 = [R, R, ModImm(Plain)] => [R(8), R(16), ModImm(Plain)], [], V7;
+    0xF1500000 = [R, R, ModImm(Inverted)] => [R(8), R(16), ModImm(Inverted)], [], V7;
+    0xF1700000 = [R, ModImm(Plain)] => [R(8), C, R(16), ModImm(Plain)], [], V7;
+    0xF1500000 = [R, ModImm(Inverted)] => [R(8), C, R(16), ModImm(Inverted)], [], V7;
+    0xEB700000 = [R, R, R, End, Shift] => [R(8), R(16), R(0), Shift], [], V7;
+    0xEB700000 = [R, R, End, Shift] => [R(8), C, R(16), R(0), Shift], [], V7;
+]
+"sbc" = [
+    0x00004180 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW, INSIDE_IT], V7;
+    0x00004180 = [Lo, Same, Lo] => [Rlo(0), A, Rlo(3)], [NARROW, INSIDE_IT], V7;
+    0xF1600000 = [R, R, ModImm(Plain)] => [R(8), R(16), ModImm(Plain)], [], V7;
+    0xF1400000 = [R, R, ModImm(Inverted)] => [R(8), R(16), ModImm(Inverted)], [], V7;
+    0xF1600000 = [R, ModImm(Plain)] => [R(8), C, R(16), ModImm(Plain)], [], V7;
+    0xF1400000 = [R, ModImm(Inverted)] => [R(8), C, R(16), ModImm(Inverted)], [], V7;
+    0xEB600000 = [R, R, R, End, Shift] => [R(8), R(16), R(0), Shift], [], V7;
+    0xEB600000 = [R, R, End, Shift] => [R(8), C, R(16), R(0), Shift], [], V7;
+]
+"subs" = [
+    0x00001E00 = [Lo, Lo, SmallImm(&IMM3_N)] => [Rlo(0), Rlo(3), Imm(&IMM3_N)], [NARROW, OUTSIDE_IT], V7;
+    0x00003800 = [Lo, SmallImm(&IMM8)] => [Rlo(8), Imm(&IMM8)], [NARROW, OUTSIDE_IT], V7;
+    0x00003800 = [Lo, Same, SmallImm(&IMM8)] => [Rlo(8), A, Imm(&IMM8)], [NARROW, OUTSIDE_IT], V7;
+    0x00001A00 = [Lo, Lo, Lo] => [Rlo(0), Rlo(3), Rlo(6)], [NARROW, OUTSIDE_IT], V7;
+    0x00001A00 = [Lo, Lo] => [Rlo(0), C, Rlo(3), Rlo(6)], [NARROW, OUTSIDE_IT], V7;
+    0xF1B00000 = [R, R, ModImm(Plain)] => [R(8), R(16), ModImm(Plain)], [], V7;
+    0xF1100000 = [R, R, ModImm(Negated)] => [R(8), R(16), ModImm(Negated)], [], V7;
+    0xF1B00000 = [R, ModImm(Plain)] => [R(8), C, R(16), ModImm(Plain)], [], V7;
+    0xF1100000 = [R, ModImm(Negated)] => [R(8), C, R(16), ModImm(Negated)], [], V7;
+    0xEBB00000 = [R, R, R, End, Shift] => [R(8), R(16), R(0), Shift], [], V7;
+    0xEBB00000 = [R, R, End, Shift] => [R(8), C, R(16), R(0), Shift], [], V7;
+]
+"sub" = [
+    0x00001E00 = [Lo, Lo, SmallImm(&IMM3_N)] => [Rlo(0), Rlo(3), Imm(&IMM3_N)], [NARROW, INSIDE_IT], V7;
+    0x00003800 = [Lo, SmallImm(&IMM8)] => [Rlo(8), Imm(&IMM8)], [NARROW, INSIDE_IT], V7;
+    0x00003800 = [Lo, Same, SmallImm(&IMM8)] => [Rlo(8), A, Imm(&IMM8)], [NARROW, INSIDE_IT], V7;
+    0x00001A00 = [Lo, Lo, Lo] => [Rlo(0), Rlo(3), Rlo(6)], [NARROW, INSIDE_IT], V7;
+    0x00001A00 = [Lo, Lo] => [Rlo(0), C, Rlo(3), Rlo(6)], [NARROW, INSIDE_IT], V7;
+    0x0000B080 = [Reg(SP), SmallImm(&IMM7_S2_N)] => [A, Imm(&IMM7_S2_N)], [NARROW], V7;
+    0x0000B080 = [Reg(SP), Reg(SP), SmallImm(&IMM7_S2_N)] => [A, A, Imm(&IMM7_S2_N)], [NARROW], V7;
+    0xF1A00000 = [R, R, ModImm(Plain)] => [R(8), R(16), ModImm(Plain)], [], V7;
+    0xF1000000 = [R, R, ModImm(Negated)] => [R(8), R(16), ModImm(Negated)], [], V7;
+    0xF1A00000 = [R, ModImm(Plain)] => [R(8), C, R(16), ModImm(Plain)], [], V7;
+    0xF1000000 = [R, ModImm(Negated)] => [R(8), C, R(16), ModImm(Negated)], [], V7;
+    0xF2A00000 = [R, R, Imm] => [R(8), R(16), Imm(&IMM12_T)], [], V7;
+    0xF2A00000 = [R, Imm] => [R(8), C, R(16), Imm(&IMM12_T)], [], V7;
+    0xEBA00000 = [R, R, R, End, Shift] => [R(8), R(16), R(0), Shift], [], V7;
+    0xEBA00000 = [R, R, End, Shift] => [R(8), C, R(16), R(0), Shift], [], V7;
+]
+"rsbs" = [
+    0x00004240 = [Lo, Lo, SmallImm(&ZERO)] => [Rlo(0), Rlo(3), A], [NARROW, OUTSIDE_IT], V7;
+    0xF1D00000 = [R, R, ModImm(Plain)] => [R(8), R(16), ModImm(Plain)], [], V7;
+    0xF1D00000 = [R, ModImm(Plain)] => [R(8), C, R(16), ModImm(Plain)], [], V7;
+    0xEBD00000 = [R, R, R, End, Shift] => [R(8), R(16), R(0), Shift], [], V7;
+    0xEBD00000 = [R, R, End, Shift] => [R(8), C, R(16), R(0), Shift], [], V7;
+]
+"rsb" = [
+    0x00004240 = [Lo, Lo, SmallImm(&ZERO)] => [Rlo(0), Rlo(3), A], [NARROW, INSIDE_IT], V7;
+    0xF1C00000 = [R, R, ModImm(Plain)] => [R(8), R(16), ModImm(Plain)], [], V7;
+    0xF1C00000 = [R, ModImm(Plain)] => [R(8), C, R(16), ModImm(Plain)], [], V7;
+    0xEBC00000 = [R, R, R, End, Shift] => [R(8), R(16), R(0), Shift], [], V7;
+    0xEBC00000 = [R, R, End, Shift] => [R(8), C, R(16), R(0), Shift], [], V7;
+]
+"addw" = [
+    0xF2000000 = [R, R, Imm] => [R(8), R(16), Imm(&IMM12_T)], [], V7;
+]
+"subw" = [
+    0xF2A00000 = [R, R, Imm] => [R(8), R(16), Imm(&IMM12_T)], [], V7;
+]
+"cmp" = [
+    0x00002800 = [Lo, SmallImm(&IMM8)] => [Rlo(8), Imm(&IMM8)], [NARROW], V7;
+    0x00004280 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW], V7;
+    0x00004500 = [Hi, R] => [RSplit(0, 7), R(3)], [NARROW], V7;
+    0x00004500 = [R, Hi] => [RSplit(0, 7), R(3)], [NARROW], V7;
+    0xF1B00F00 = [R, ModImm(Plain)] => [R(16), ModImm(Plain)], [], V7;
+    0xF1100F00 = [R, ModImm(Negated)] => [R(16), ModImm(Negated)], [], V7;
+    0xEBB00F00 = [R, R, End, Shift] => [R(16), R(0), Shift], [], V7;
+]
+"cmn" = [
+    0x000042C0 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW], V7;
+    0xF1100F00 = [R, ModImm(Plain)] => [R(16), ModImm(Plain)], [], V7;
+    0xF1B00F00 = [R, ModImm(Negated)] => [R(16), ModImm(Negated)], [], V7;
+    0xEB100F00 = [R, R, End, Shift] => [R(16), R(0), Shift], [], V7;
+]
+"tst" = [
+    0x00004200 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW], V7;
+    0xF0100F00 = [R, ModImm(Plain)] => [R(16), ModImm(Plain)], [], V7;
+    0xEA100F00 = [R, R, End, Shift] => [R(16), R(0), Shift], [], V7;
+]
+"teq" = [
+    0xF0900F00 = [R, ModImm(Plain)] => [R(16), ModImm(Plain)], [], V7;
+    0xEA900F00 = [R, R, End, Shift] => [R(16), R(0), Shift], [], V7;
+]
+"mov" = [
+    0x00004600 = [R, R] => [RSplit(0, 7), R(3)], [NARROW], V7;
+    0x00002000 = [Lo, SmallImm(&IMM8)] => [Rlo(8), Imm(&IMM8)], [NARROW, INSIDE_IT], V7;
+    0xF04F0000 = [R, ModImm(Plain)] => [R(8), ModImm(Plain)], [], V7;
+    0xF06F0000 = [R, ModImm(Inverted)] => [R(8), ModImm(Inverted)], [], V7;
+    0xF2400000 = [R, Imm] => [R(8), Imm(&IMM16_T)], [], V7;
+    0xEA4F0000 = [R, R, End, Shift] => [R(8), R(0), Shift], [], V7;
+]
+"movs" = [
+    0x00002000 = [Lo, SmallImm(&IMM8)] => [Rlo(8), Imm(&IMM8)], [NARROW, OUTSIDE_IT], V7;
+    0x00000000 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW, OUTSIDE_IT], V7;
+    0xF05F0000 = [R, ModImm(Plain)] => [R(8), ModImm(Plain)], [], V7;
+    0xF07F0000 = [R, ModImm(Inverted)] => [R(8), ModImm(Inverted)], [], V7;
+    0xEA5F0000 = [R, R, End, Shift] => [R(8), R(0), Shift], [], V7;
+]
+"mvns" = [
+    0x000043C0 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW, OUTSIDE_IT], V7;
+    0xF07F0000 = [R, ModImm(Plain)] => [R(8), ModImm(Plain)], [], V7;
+    0xF05F0000 = [R, ModImm(Inverted)] => [R(8), ModImm(Inverted)], [], V7;
+    0xEA7F0000 = [R, R, End, Shift] => [R(8), R(0), Shift], [], V7;
+]
+"mvn" = [
+    0x000043C0 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW, INSIDE_IT], V7;
+    0xF06F0000 = [R, ModImm(Plain)] => [R(8), ModImm(Plain)], [], V7;
+    0xF04F0000 = [R, ModImm(Inverted)] => [R(8), ModImm(Inverted)], [], V7;
+    0xEA6F0000 = [R, R, End, Shift] => [R(8), R(0), Shift], [], V7;
+]
+"movw" = [
+    0xF2400000 = [R, Imm] => [R(8), Imm(&IMM16_T)], [], V7;
+]
+"movt" = [
+    0xF2C00000 = [R, Imm] => [R(8), Imm(&IMM16_T)], [], V7;
+]
+
+// shifts
+"lsls" = [
+    0x00000000 = [Lo, Lo, Imm] => [Rlo(0), Rlo(3), ShiftAmt(LSL, &IMM5_N)], [NARROW, OUTSIDE_IT], V7;
+    0x00004080 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW, OUTSIDE_IT], V7;
+    0x00004080 = [Lo, Same, Lo] => [Rlo(0), A, Rlo(3)], [NARROW, OUTSIDE_IT], V7;
+    0xEA5F0000 = [R, R, Imm] => [R(8), R(0), ShiftAmt(LSL, &IMM5_T)], [], V7;
+    0xFA10F000 = [R, R, R] => [R(8), R(16), R(0)], [], V7;
+]
+"lsl" = [
+    0x00000000 = [Lo, Lo, Imm] => [Rlo(0), Rlo(3), ShiftAmt(LSL, &IMM5_N)], [NARROW, INSIDE_IT], V7;
+    0x00004080 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW, INSIDE_IT], V7;
+    0x00004080 = [Lo, Same, Lo] => [Rlo(0), A, Rlo(3)], [NARROW, INSIDE_IT], V7;
+    0xEA4F0000 = [R, R, Imm] => [R(8), R(0), ShiftAmt(LSL, &IMM5_T)], [], V7;
+    0xFA00F000 = [R, R, R] => [R(8), R(16), R(0)], [], V7;
+]
+"lsrs" = [
+    0x00000800 = [Lo, Lo, Imm] => [Rlo(0), Rlo(3), ShiftAmt(LSR, &IMM5_N)], [NARROW, OUTSIDE_IT], V7;
+    0x000040C0 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW, OUTSIDE_IT], V7;
+    0x000040C0 = [Lo, Same, Lo] => [Rlo(0), A, Rlo(3)], [NARROW, OUTSIDE_IT], V7;
+    0xEA5F0010 = [R, R, Imm] => [R(8), R(0), ShiftAmt(LSR, &IMM5_T)], [], V7;
+    0xFA30F000 = [R, R, R] => [R(8), R(16), R(0)], [], V7;
+]
+"lsr" = [
+    0x00000800 = [Lo, Lo, Imm] => [Rlo(0), Rlo(3), ShiftAmt(LSR, &IMM5_N)], [NARROW, INSIDE_IT], V7;
+    0x000040C0 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW, INSIDE_IT], V7;
+    0x000040C0 = [Lo, Same, Lo] => [Rlo(0), A, Rlo(3)], [NARROW, INSIDE_IT], V7;
+    0xEA4F0010 = [R, R, Imm] => [R(8), R(0), ShiftAmt(LSR, &IMM5_T)], [], V7;
+    0xFA20F000 = [R, R, R] => [R(8), R(16), R(0)], [], V7;
+]
+"asrs" = [
+    0x00001000 = [Lo, Lo, Imm] => [Rlo(0), Rlo(3), ShiftAmt(ASR, &IMM5_N)], [NARROW, OUTSIDE_IT], V7;
+    0x00004100 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW, OUTSIDE_IT], V7;
+    0x00004100 = [Lo, Same, Lo] => [Rlo(0), A, Rlo(3)], [NARROW, OUTSIDE_IT], V7;
+    0xEA5F0020 = [R, R, Imm] => [R(8), R(0), ShiftAmt(ASR, &IMM5_T)], [], V7;
+    0xFA50F000 = [R, R, R] => [R(8), R(16), R(0)], [], V7;
+]
+"asr" = [
+    0x00001000 = [Lo, Lo, Imm] => [Rlo(0), Rlo(3), ShiftAmt(ASR, &IMM5_N)], [NARROW, INSIDE_IT], V7;
+    0x00004100 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW, INSIDE_IT], V7;
+    0x00004100 = [Lo, Same, Lo] => [Rlo(0), A, Rlo(3)], [NARROW, INSIDE_IT], V7;
+    0xEA4F0020 = [R, R, Imm] => [R(8), R(0), ShiftAmt(ASR, &IMM5_T)], [], V7;
+    0xFA40F000 = [R, R, R] => [R(8), R(16), R(0)], [], V7;
+]
+"rors" = [
+    0x000041C0 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW, OUTSIDE_IT], V7;
+    0x000041C0 = [Lo, Same, Lo] => [Rlo(0), A, Rlo(3)], [NARROW, OUTSIDE_IT], V7;
+    0xEA5F0030 = [R, R, Imm] => [R(8), R(0), ShiftAmt(ROR, &IMM5_T)], [], V7;
+    0xFA70F000 = [R, R, R] => [R(8), R(16), R(0)], [], V7;
+]
+"ror" = [
+    0x000041C0 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW, INSIDE_IT], V7;
+    0x000041C0 = [Lo, Same, Lo] => [Rlo(0), A, Rlo(3)], [NARROW, INSIDE_IT], V7;
+    0xEA4F0030 = [R, R, Imm] => [R(8), R(0), ShiftAmt(ROR, &IMM5_T)], [], V7;
+    0xFA60F000 = [R, R, R] => [R(8), R(16), R(0)], [], V7;
+]
+"rrx" = [
+    0xEA4F0030 = [R, R] => [R(8), R(0)], [], V7;
+]
+"rrxs" = [
+    0xEA5F0030 = [R, R] => [R(8), R(0)], [], V7;
+]
+
+// multiply and divide
+"muls" = [
+    0x00004340 = [Lo, Lo, Same] => [Rlo(0), Rlo(3), A], [NARROW, OUTSIDE_IT], V7;
+]
+"mul" = [
+    0x00004340 = [Lo, Lo, Same] => [Rlo(0), Rlo(3), A], [NARROW, INSIDE_IT], V7;
+    0xFB00F000 = [R, R, R] => [R(8), R(16), R(0)], [], V7;
+]
+"mla" = [
+    0xFB000000 = [R, R, R, R] => [R(8), R(16), R(0), R(12)], [], V7;
+]
+"mls" = [
+    0xFB000010 = [R, R, R, R] => [R(8), R(16), R(0), R(12)], [], V7;
+]
+"smull" = [
+    0xFB800000 = [R, R, R, R] => [R(12), R(8), R(16), R(0)], [], V7;
+]
+"umull" = [
+    0xFBA00000 = [R, R, R, R] => [R(12), R(8), R(16), R(0)], [], V7;
+]
+"smlal" = [
+    0xFBC00000 = [R, R, R, R] => [R(12), R(8), R(16), R(0)], [], V7;
+]
+"umlal" = [
+    0xFBE00000 = [R, R, R, R] => [R(12), R(8), R(16), R(0)], [], V7;
+]
+"sdiv" = [
+    0xFB90F0F0 = [R, R, R] => [R(8), R(16), R(0)], [], IDIV;
+]
+"udiv" = [
+    0xFBB0F0F0 = [R, R, R] => [R(8), R(16), R(0)], [], IDIV;
+]
+
+// miscellaneous data processing
+"clz" = [
+    0xFAB0F080 = [R, R] => [R(8), R(16), C, R(0)], [], V7;
+]
+"rev" = [
+    0x0000BA00 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW], V7;
+    0xFA90F080 = [R, R] => [R(8), R(16), C, R(0)], [], V7;
+]
+"rev16" = [
+    0x0000BA40 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW], V7;
+    0xFA90F090 = [R, R] => [R(8), R(16), C, R(0)], [], V7;
+]
+"revsh" = [
+    0x0000BAC0 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW], V7;
+    0xFA90F0B0 = [R, R] => [R(8), R(16), C, R(0)], [], V7;
+]
+"rbit" = [
+    0xFA90F0A0 = [R, R] => [R(8), R(16), C, R(0)], [], V7;
+]
+"sxtb" = [
+    0x0000B240 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW], V7;
+    0xFA4FF080 = [R, R, End, Ror] => [R(8), R(0), Rotation(4)], [], V7;
+]
+"sxth" = [
+    0x0000B200 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW], V7;
+    0xFA0FF080 = [R, R, End, Ror] => [R(8), R(0), Rotation(4)], [], V7;
+]
+"uxtb" = [
+    0x0000B2C0 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW], V7;
+    0xFA5FF080 = [R, R, End, Ror] => [R(8), R(0), Rotation(4)], [], V7;
+]
+"uxth" = [
+    0x0000B280 = [Lo, Lo] => [Rlo(0), Rlo(3)], [NARROW], V7;
+    0xFA1FF080 = [R, R, End, Ror] => [R(8), R(0), Rotation(4)], [], V7;
+]
+"ubfx" = [
+    0xF3C00000 = [R, R, Imm, Imm] => [R(8), R(16), Imm(&IMM5_T), WidthDec(&IMM5_0)], [], V7;
+]
+"sbfx" = [
+    0xF3400000 = [R, R, Imm, Imm] => [R(8), R(16), Imm(&IMM5_T), WidthDec(&IMM5_0)], [], V7;
+]
+"bfi" = [
+    0xF3600000 = [R, R, Imm, Imm] => [R(8), R(16), Imm(&IMM5_T), Usumdec(&IMM5_0)], [], V7;
+]
+"bfc" = [
+    0xF36F0000 = [R, Imm, Imm] => [R(8), Imm(&IMM5_T), Usumdec(&IMM5_0)], [], V7;
+]
+
+// loads and stores
+"ldr" = [
+    0x00006800 = [Lo, RefLo(&IMM5_S2_N)] => [Rlo(0), Rlo(3), Imm(&IMM5_S2_N)], [NARROW], V7;
+    0x00009800 = [Lo, RefSp(&IMM8_S2)] => [Rlo(8), A, Imm(&IMM8_S2)], [NARROW], V7;
+    0x00005800 = [Lo, RefIdxLo] => [Rlo(0), Rlo(3), Rlo(6)], [NARROW], V7;
+    0xF8D00000 = [R, RefPos] => [R(12), R(16), Imm(&IMM12)], [], V7;
+    0xF8500800 = [R, Ref] => [R(12), R(16), Mode(10, 8), UImm(&IMM8, 9)], [], V7;
+    0xF8500000 = [R, RefIdxLsl] => [R(12), R(16), R(0), IndexLsl(4)], [], V7;
+    0xF85F0000 = [R, Offset] => [R(12), Offset(T32_LDR)], [], V7;
+    0x00004800 = [Lo, Offset] => [Rlo(8), Offset(T32_LDR_N)], [NARROW], V7;
+]
+"ldrb" = [
+    0x00007800 = [Lo, RefLo(&IMM5_N)] => [Rlo(0), Rlo(3), Imm(&IMM5_N)], [NARROW], V7;
+    0x00005C00 = [Lo, RefIdxLo] => [Rlo(0), Rlo(3), Rlo(6)], [NARROW], V7;
+    0xF8900000 = [R, RefPos] => [R(12), R(16), Imm(&IMM12)], [], V7;
+    0xF8100800 = [R, Ref] => [R(12), R(16), Mode(10, 8), UImm(&IMM8, 9)], [], V7;
+    0xF8100000 = [R, RefIdxLsl] => [R(12), R(16), R(0), IndexLsl(4)], [], V7;
+    0xF81F0000 = [R, Offset] => [R(12), Offset(T32_LDR)], [], V7;
+]
+"ldrh" = [
+    0x00008800 = [Lo, RefLo(&IMM5_S1_N)] => [Rlo(0), Rlo(3), Imm(&IMM5_S1_N)], [NARROW], V7;
+    0x00005A00 = [Lo, RefIdxLo] => [Rlo(0), Rlo(3), Rlo(6)], [NARROW], V7;
+    0xF8B00000 = [R, RefPos] => [R(12), R(16), Imm(&IMM12)], [], V7;
+    0xF8300800 = [R, Ref] => [R(12), R(16), Mode(10, 8), UImm(&IMM8, 9)], [], V7;
+    0xF8300000 = [R, RefIdxLsl] => [R(12), R(16), R(0), IndexLsl(4)], [], V7;
+    0xF83F0000 = [R, Offset] => [R(12), Offset(T32_LDR)], [], V7;
+]
+"ldrsb" = [
+    0x00005600 = [Lo, RefIdxLo] => [Rlo(0), Rlo(3), Rlo(6)], [NARROW], V7;
+    0xF9900000 = [R, RefPos] => [R(12), R(16), Imm(&IMM12)], [], V7;
+    0xF9100800 = [R, Ref] => [R(12), R(16), Mode(10, 8), UImm(&IMM8, 9)], [], V7;
+    0xF9100000 = [R, RefIdxLsl] => [R(12), R(16), R(0), IndexLsl(4)], [], V7;
+    0xF91F0000 = [R, Offset] => [R(12), Offset(T32_LDR)], [], V7;
+]
+"ldrsh" = [
+    0x00005E00 = [Lo, RefIdxLo] => [Rlo(0), Rlo(3), Rlo(6)], [NARROW], V7;
+    0xF9B00000 = [R, RefPos] => [R(12), R(16), Imm(&IMM12)], [], V7;
+    0xF9300800 = [R, Ref] => [R(12), R(16), Mode(10, 8), UImm(&IMM8, 9)], [], V7;
+    0xF9300000 = [R, RefIdxLsl] => [R(12), R(16), R(0), IndexLsl(4)], [], V7;
+    0xF93F0000 = [R, Offset] => [R(12), Offset(T32_LDR)], [], V7;
+]
+"str" = [
+    0x00006000 = [Lo, RefLo(&IMM5_S2_N)] => [Rlo(0), Rlo(3), Imm(&IMM5_S2_N)], [NARROW], V7;
+    0x00009000 = [Lo, RefSp(&IMM8_S2)] => [Rlo(8), A, Imm(&IMM8_S2)], [NARROW], V7;
+    0x00005000 = [Lo, RefIdxLo] => [Rlo(0), Rlo(3), Rlo(6)], [NARROW], V7;
+    0xF8C00000 = [R, RefPos] => [R(12), R(16), Imm(&IMM12)], [], V7;
+    0xF8400800 = [R, Ref] => [R(12), R(16), Mode(10, 8), UImm(&IMM8, 9)], [], V7;
+    0xF8400000 = [R, RefIdxLsl] => [R(12), R(16), R(0), IndexLsl(4)], [], V7;
+]
+"strb" = [
+    0x00007000 = [Lo, RefLo(&IMM5_N)] => [Rlo(0), Rlo(3), Imm(&IMM5_N)], [NARROW], V7;
+    0x00005400 = [Lo, RefIdxLo] => [Rlo(0), Rlo(3), Rlo(6)], [NARROW], V7;
+    0xF8800000 = [R, RefPos] => [R(12), R(16), Imm(&IMM12)], [], V7;
+    0xF8000800 = [R, Ref] => [R(12), R(16), Mode(10, 8), UImm(&IMM8, 9)], [], V7;
+    0xF8000000 = [R, RefIdxLsl] => [R(12), R(16), R(0), IndexLsl(4)], [], V7;
+]
+"strh" = [
+    0x00008000 = [Lo, RefLo(&IMM5_S1_N)] => [Rlo(0), Rlo(3), Imm(&IMM5_S1_N)], [NARROW], V7;
+    0x00005200 = [Lo, RefIdxLo] => [Rlo(0), Rlo(3), Rlo(6)], [NARROW], V7;
+    0xF8A00000 = [R, RefPos] => [R(12), R(16), Imm(&IMM12)], [], V7;
+    0xF8200800 = [R, Ref] => [R(12), R(16), Mode(10, 8), UImm(&IMM8, 9)], [], V7;
+    0xF8200000 = [R, RefIdxLsl] => [R(12), R(16), R(0), IndexLsl(4)], [], V7;
+]
+"ldrd" = [
+    0xE8500000 = [R, R, Ref] => [R(12), R(8), R(16), Mode(24, 21), UImm(&IMM8_S2, 23)], [], V7;
+]
+"strd" = [
+    0xE8400000 = [R, R, Ref] => [R(12), R(8), R(16), Mode(24, 21), UImm(&IMM8_S2, 23)], [], V7;
+]
+"ldrex" = [
+    0xE8500F00 = [R, RefBase] => [R(12), R(16)], [], V7;
+]
+"ldrexb" = [
+    0xE8D00F4F = [R, RefBase] => [R(12), R(16)], [], V7;
+]
+"ldrexh" = [
+    0xE8D00F5F = [R, RefBase] => [R(12), R(16)], [], V7;
+]
+"strex" = [
+    0xE8400000 = [R, R, RefBase] => [R(8), R(12), R(16)], [], V7;
+]
+"strexb" = [
+    0xE8C00F40 = [R, R, RefBase] => [R(0), R(12), R(16)], [], V7;
+]
+"strexh" = [
+    0xE8C00F50 = [R, R, RefBase] => [R(0), R(12), R(16)], [], V7;
+]
+
+// load and store multiple
+"ldm" = [
+    0xE8900000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"ldmia" = [
+    0xE8900000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"ldmfd" = [
+    0xE8900000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"ldmdb" = [
+    0xE9100000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"ldmea" = [
+    0xE9100000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"stm" = [
+    0xE8800000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"stmia" = [
+    0xE8800000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"stmea" = [
+    0xE8800000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"stmdb" = [
+    0xE9000000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"stmfd" = [
+    0xE9000000 = [RBang, List] => [R(16), Bang(21), List], [], V7;
+]
+"push" = [
+    0x0000B400 = [ListLo(LR)] => [ListLo(8)], [NARROW], V7;
+    0xF84D0D04 = [ListOne] => [ListOne(12)], [], V7;
+    0xE92D0000 = [List] => [List], [], V7;
+]
+"pop" = [
+    0x0000BC00 = [ListLo(PC)] => [ListLo(8)], [NARROW], V7;
+    0xF85D0B04 = [ListOne] => [ListOne(12)], [], V7;
+    0xE8BD0000 = [List] => [List], [], V7;
+]
+
+// branches
+"b" = [
+    0xF0009000 = [Offset] => [Offset(T32_B24)], [], V7;
+    0x0000E000 = [Offset] => [Offset(T32_B11)], [NARROW], V7;
+]
+"beq" = [
+    0xF0008000 = [Offset] => [Offset(T32_B20)], [UNCOND], V7;
+    0x0000D000 = [Offset] => [Offset(T32_B8)], [UNCOND, NARROW], V7;
+]
+"bne" = [
+    0xF0408000 = [Offset] => [Offset(T32_B20)], [UNCOND], V7;
+    0x0000D100 = [Offset] => [Offset(T32_B8)], [UNCOND, NARROW], V7;
+]
+"bcs" = [
+    0xF0808000 = [Offset] => [Offset(T32_B20)], [UNCOND], V7;
+    0x0000D200 = [Offset] => [Offset(T32_B8)], [UNCOND, NARROW], V7;
+]
+"bhs" = [
+    0xF0808000 = [Offset] => [Offset(T32_B20)], [UNCOND], V7;
+    0x0000D200 = [Offset] => [Offset(T32_B8)], [UNCOND, NARROW], V7;
+]
+"bcc" = [
+    0xF0C08000 = [Offset] => [Offset(T32_B20)], [UNCOND], V7;
+    0x0000D300 = [Offset] => [Offset(T32_B8)], [UNCOND, NARROW], V7;
+]
+"blo" = [
+    0xF0C08000 = [Offset] => [Offset(T32_B20)], [UNCOND], V7;
+    0x0000D300 = [Offset] => [Offset(T32_B8)], [UNCOND, NARROW], V7;
+]
+"bmi" = [
+    0xF1008000 = [Offset] => [Offset(T32_B20)], [UNCOND], V7;
+    0x0000D400 = [Offset] => [Offset(T32_B8)], [UNCOND, NARROW], V7;
+]
+"bpl" = [
+    0xF1408000 = [Offset] => [Offset(T32_B20)], [UNCOND], V7;
+    0x0000D500 = [Offset] => [Offset(T32_B8)], [UNCOND, NARROW], V7;
+]
+"bvs" = [
+    0xF1808000 = [Offset] => [Offset(T32_B20)], [UNCOND], V7;
+    0x0000D600 = [Offset] => [Offset(T32_B8)], [UNCOND, NARROW], V7;
+]
+"bvc" = [
+    0xF1C08000 = [Offset] => [Offset(T32_B20)], [UNCOND], V7;
+    0x0000D700 = [Offset] => [Offset(T32_B8)], [UNCOND, NARROW], V7;
+]
+"bhi" = [
+    0xF2008000 = [Offset] => [Offset(T32_B20)], [UNCOND], V7;
+    0x0000D800 = [Offset] => [Offset(T32_B8)], [UNCOND, NARROW], V7;
+]
+"bls" = [
+    0xF2408000 = [Offset] => [Offset(T32_B20)], [UNCOND], V7;
+    0x0000D900 = [Offset] => [Offset(T32_B8)], [UNCOND, NARROW], V7;
+]
+"bge" = [
+    0xF2808000 = [Offset] => [Offset(T32_B20)], [UNCOND], V7;
+    0x0000DA00 = [Offset] => [Offset(T32_B8)], [UNCOND, NARROW], V7;
+]
+"blt" = [
+    0xF2C08000 = [Offset] => [Offset(T32_B20)], [UNCOND], V7;
+    0x0000DB00 = [Offset] => [Offset(T32_B8)], [UNCOND, NARROW], V7;
+]
+"bgt" = [
+    0xF3008000 = [Offset] => [Offset(T32_B20)], [UNCOND], V7;
+    0x0000DC00 = [Offset] => [Offset(T32_B8)], [UNCOND, NARROW], V7;
+]
+"ble" = [
+    0xF3408000 = [Offset] => [Offset(T32_B20)], [UNCOND], V7;
+    0x0000DD00 = [Offset] => [Offset(T32_B8)], [UNCOND, NARROW], V7;
+]
+"bl" = [
+    0xF000D000 = [Offset] => [Offset(T32_B24)], [], V7;
+]
+"blx" = [
+    0xF000C000 = [Offset] => [Offset(T32_BLX)], [], V7;
+    0x00004780 = [R] => [R(3)], [NARROW], V7;
+]
+"bx" = [
+    0x00004700 = [R] => [R(3)], [NARROW], V7;
+]
+"cbz" = [
+    0x0000B100 = [Lo, Offset] => [Rlo(0), Offset(T32_CBZ)], [UNCOND, NARROW], V7;
+]
+"cbnz" = [
+    0x0000B900 = [Lo, Offset] => [Rlo(0), Offset(T32_CBZ)], [UNCOND, NARROW], V7;
+]
+
+// miscellaneous
+"nop" = [
+    0x0000BF00 = [] => [], [NARROW], V7;
+    0xF3AF8000 = [] => [], [], V7;
+]
+"yield" = [
+    0x0000BF10 = [] => [], [NARROW], V7;
+    0xF3AF8001 = [] => [], [], V7;
+]
+"wfe" = [
+    0x0000BF20 = [] => [], [NARROW], V7;
+    0xF3AF8002 = [] => [], [], V7;
+]
+"wfi" = [
+    0x0000BF30 = [] => [], [NARROW], V7;
+    0xF3AF8003 = [] => [], [], V7;
+]
+"sev" = [
+    0x0000BF40 = [] => [], [NARROW], V7;
+    0xF3AF8004 = [] => [], [], V7;
+]
+"svc" = [
+    0x0000DF00 = [Imm] => [Imm(&IMM8)], [NARROW], V7;
+]
+"bkpt" = [
+    0x0000BE00 = [End, Imm] => [Imm(&IMM8)], [UNCOND, NARROW], V7;
+]
+"udf" = [
+    0x0000DE00 = [End, Imm] => [Imm(&IMM8)], [UNCOND, NARROW], V7;
+]
+"dmb" = [
+    0xF3BF8F50 = [End, Barrier] => [Barrier], [UNCOND], V7;
+]
+"dsb" = [
+    0xF3BF8F40 = [End, Barrier] => [Barrier], [UNCOND], V7;
+]
+"isb" = [
+    0xF3BF8F60 = [End, Barrier] => [Barrier], [UNCOND], V7;
+]
+"clrex" = [
+    0xF3BF8F2F = [] => [], [UNCOND], V7;
+]
+"mrs" = [
+    0xF3EF8000 = [R, Lit("apsr")] => [R(8), A], [], V7;
+]
+"msr" = [
+    0xF3808800 = [Lit("apsr_nzcvq"), R] => [A, R(16)], [], V7;
+]
+
+// VFP and NEON
+"vadd.f32" = [
+    0xEE300A00 = [S, S, S] => [Sreg(12, 22), Sreg(16, 7), Sreg(0, 5)], [], VFP;
+    0xEF000D00 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xEF000D40 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vadd.f64" = [
+    0xEE300B00 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [], VFP;
+]
+"vsub.f32" = [
+    0xEE300A40 = [S, S, S] => [Sreg(12, 22), Sreg(16, 7), Sreg(0, 5)], [], VFP;
+    0xEF200D00 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xEF200D40 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vsub.f64" = [
+    0xEE300B40 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [], VFP;
+]
+"vmul.f32" = [
+    0xEE200A00 = [S, S, S] => [Sreg(12, 22), Sreg(16, 7), Sreg(0, 5)], [], VFP;
+    0xFF000D10 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xFF000D50 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vmul.f64" = [
+    0xEE200B00 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [], VFP;
+]
+"vnmul.f32" = [
+    0xEE200A40 = [S, S, S] => [Sreg(12, 22), Sreg(16, 7), Sreg(0, 5)], [], VFP;
+]
+"vnmul.f64" = [
+    0xEE200B40 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [], VFP;
+]
+"vdiv.f32" = [
+    0xEE800A00 = [S, S, S] => [Sreg(12, 22), Sreg(16, 7), Sreg(0, 5)], [], VFP;
+]
+"vdiv.f64" = [
+    0xEE800B00 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [], VFP;
+]
+"vmla.f32" = [
+    0xEE000A00 = [S, S, S] => [Sreg(12, 22), Sreg(16, 7), Sreg(0, 5)], [], VFP;
+]
+"vmla.f64" = [
+    0xEE000B00 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [], VFP;
+]
+"vmls.f32" = [
+    0xEE000A40 = [S, S, S] => [Sreg(12, 22), Sreg(16, 7), Sreg(0, 5)], [], VFP;
+]
+"vmls.f64" = [
+    0xEE000B40 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [], VFP;
+]
+"vfma.f32" = [
+    0xEEA00A00 = [S, S, S] => [Sreg(12, 22), Sreg(16, 7), Sreg(0, 5)], [], VFP | VFPV4;
+]
+"vfma.f64" = [
+    0xEEA00B00 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [], VFP | VFPV4;
+]
+"vfms.f32" = [
+    0xEEA00A40 = [S, S, S] => [Sreg(12, 22), Sreg(16, 7), Sreg(0, 5)], [], VFP | VFPV4;
+]
+"vfms.f64" = [
+    0xEEA00B40 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [], VFP | VFPV4;
+]
+"vneg.f32" = [
+    0xEEB10A40 = [S, S] => [Sreg(12, 22), Sreg(0, 5)], [], VFP;
+]
+"vneg.f64" = [
+    0xEEB10B40 = [D, D] => [Dreg(12, 22), Dreg(0, 5)], [], VFP;
+]
+"vabs.f32" = [
+    0xEEB00AC0 = [S, S] => [Sreg(12, 22), Sreg(0, 5)], [], VFP;
+]
+"vabs.f64" = [
+    0xEEB00BC0 = [D, D] => [Dreg(12, 22), Dreg(0, 5)], [], VFP;
+]
+"vsqrt.f32" = [
+    0xEEB10AC0 = [S, S] => [Sreg(12, 22), Sreg(0, 5)], [], VFP;
+]
+"vsqrt.f64" = [
+    0xEEB10BC0 = [D, D] => [Dreg(12, 22), Dreg(0, 5)], [], VFP;
+]
+"vcmp.f32" = [
+    0xEEB40A40 = [S, S] => [Sreg(12, 22), Sreg(0, 5)], [], VFP;
+    0xEEB50A40 = [S, FZero] => [Sreg(12, 22), A], [], VFP;
+]
+"vcmp.f64" = [
+    0xEEB40B40 = [D, D] => [Dreg(12, 22), Dreg(0, 5)], [], VFP;
+    0xEEB50B40 = [D, FZero] => [Dreg(12, 22), A], [], VFP;
+]
+"vcmpe.f32" = [
+    0xEEB40AC0 = [S, S] => [Sreg(12, 22), Sreg(0, 5)], [], VFP;
+    0xEEB50AC0 = [S, FZero] => [Sreg(12, 22), A], [], VFP;
+]
+"vcmpe.f64" = [
+    0xEEB40BC0 = [D, D] => [Dreg(12, 22), Dreg(0, 5)], [], VFP;
+    0xEEB50BC0 = [D, FZero] => [Dreg(12, 22), A], [], VFP;
+]
+"vmov.f32" = [
+    0xEEB00A40 = [S, S] => [Sreg(12, 22), Sreg(0, 5)], [], VFP;
+    0xEEB00A00 = [S, FImm] => [Sreg(12, 22), FImm], [], VFP;
+]
+"vmov.f64" = [
+    0xEEB00B40 = [D, D] => [Dreg(12, 22), Dreg(0, 5)], [], VFP;
+    0xEEB00B00 = [D, FImm] => [Dreg(12, 22), FImm], [], VFP;
+]
+"vmov" = [
+    0xEE000A10 = [S, R] => [Sreg(16, 7), R(12)], [], VFP;
+    0xEE100A10 = [R, S] => [R(12), Sreg(16, 7)], [], VFP;
+    0xEC400B10 = [D, R, R] => [Dreg(0, 5), R(12), R(16)], [], VFP;
+    0xEC500B10 = [R, R, D] => [R(12), R(16), Dreg(0, 5)], [], VFP;
+    0xEEB00A40 = [S, S] => [Sreg(12, 22), Sreg(0, 5)], [], VFP;
+    0xEF200110 = [D, D] => [Dreg(12, 22), Dreg(16, 7), C, Dreg(0, 5)], [UNCOND], NEON;
+    0xEF200150 = [Q, Q] => [Qreg(12, 22), Qreg(16, 7), C, Qreg(0, 5)], [UNCOND], NEON;
+]
+"vcvt.f64.f32" = [
+    0xEEB70AC0 = [D, S] => [Dreg(12, 22), Sreg(0, 5)], [], VFP;
+]
+"vcvt.f32.f64" = [
+    0xEEB70BC0 = [S, D] => [Sreg(12, 22), Dreg(0, 5)], [], VFP;
+]
+"vcvt.s32.f32" = [
+    0xEEBD0AC0 = [S, S] => [Sreg(12, 22), Sreg(0, 5)], [], VFP;
+]
+"vcvt.u32.f32" = [
+    0xEEBC0AC0 = [S, S] => [Sreg(12, 22), Sreg(0, 5)], [], VFP;
+]
+"vcvt.s32.f64" = [
+    0xEEBD0BC0 = [S, D] => [Sreg(12, 22), Dreg(0, 5)], [], VFP;
+]
+"vcvt.u32.f64" = [
+    0xEEBC0BC0 = [S, D] => [Sreg(12, 22), Dreg(0, 5)], [], VFP;
+]
+"vcvt.f32.s32" = [
+    0xEEB80AC0 = [S, S] => [Sreg(12, 22), Sreg(0, 5)], [], VFP;
+]
+"vcvt.f32.u32" = [
+    0xEEB80A40 = [S, S] => [Sreg(12, 22), Sreg(0, 5)], [], VFP;
+]
+"vcvt.f64.s32" = [
+    0xEEB80BC0 = [D, S] => [Dreg(12, 22), Sreg(0, 5)], [], VFP;
+]
+"vcvt.f64.u32" = [
+    0xEEB80B40 = [D, S] => [Dreg(12, 22), Sreg(0, 5)], [], VFP;
+]
+"vldr" = [
+    0xED100A00 = [S, RefOffset] => [Sreg(12, 22), R(16), UImm(&IMM8_S2, 23)], [], VFP;
+    0xED100B00 = [D, RefOffset] => [Dreg(12, 22), R(16), UImm(&IMM8_S2, 23)], [], VFP;
+    0xED1F0A00 = [S, Offset] => [Sreg(12, 22), Offset(T32_VLDR)], [], VFP;
+    0xED1F0B00 = [D, Offset] => [Dreg(12, 22), Offset(T32_VLDR)], [], VFP;
+]
+"vstr" = [
+    0xED000A00 = [S, RefOffset] => [Sreg(12, 22), R(16), UImm(&IMM8_S2, 23)], [], VFP;
+    0xED000B00 = [D, RefOffset] => [Dreg(12, 22), R(16), UImm(&IMM8_S2, 23)], [], VFP;
+]
+"vpush" = [
+    0xED2D0A00 = [SList] => [VList], [], VFP;
+    0xED2D0B00 = [DList] => [VList], [], VFP;
+]
+"vpop" = [
+    0xECBD0A00 = [SList] => [VList], [], VFP;
+    0xECBD0B00 = [DList] => [VList], [], VFP;
+]
+"vldm" = [
+    0xEC900A00 = [RBang, SList] => [R(16), Bang(21), VList], [], VFP;
+    0xEC900B00 = [RBang, DList] => [R(16), Bang(21), VList], [], VFP;
+]
+"vldmia" = [
+    0xEC900A00 = [RBang, SList] => [R(16), Bang(21), VList], [], VFP;
+    0xEC900B00 = [RBang, DList] => [R(16), Bang(21), VList], [], VFP;
+]
+"vstm" = [
+    0xEC800A00 = [RBang, SList] => [R(16), Bang(21), VList], [], VFP;
+    0xEC800B00 = [RBang, DList] => [R(16), Bang(21), VList], [], VFP;
+]
+"vstmia" = [
+    0xEC800A00 = [RBang, SList] => [R(16), Bang(21), VList], [], VFP;
+    0xEC800B00 = [RBang, DList] => [R(16), Bang(21), VList], [], VFP;
+]
+"vmrs" = [
+    0xEEF1FA10 = [Lit("apsr_nzcv"), Lit("fpscr")] => [A, A], [], VFP;
+    0xEEF10A10 = [R, Lit("fpscr")] => [R(12), A], [], VFP;
+]
+"vmsr" = [
+    0xEEE10A10 = [Lit("fpscr"), R] => [A, R(12)], [], VFP;
+]
+"vadd.i8" = [
+    0xEF000800 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xEF000840 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vsub.i8" = [
+    0xFF000800 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xFF000840 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vadd.i16" = [
+    0xEF100800 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xEF100840 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vsub.i16" = [
+    0xFF100800 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xFF100840 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vadd.i32" = [
+    0xEF200800 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xEF200840 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vsub.i32" = [
+    0xFF200800 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xFF200840 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vadd.i64" = [
+    0xEF300800 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xEF300840 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vsub.i64" = [
+    0xFF300800 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xFF300840 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vmul.i8" = [
+    0xEF000910 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xEF000950 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vmul.i16" = [
+    0xEF100910 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xEF100950 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vmul.i32" = [
+    0xEF200910 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xEF200950 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vand" = [
+    0xEF000110 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xEF000150 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vbic" = [
+    0xEF100110 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xEF100150 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vorr" = [
+    0xEF200110 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xEF200150 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vorn" = [
+    0xEF300110 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xEF300150 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"veor" = [
+    0xFF000110 = [D, D, D] => [Dreg(12, 22), Dreg(16, 7), Dreg(0, 5)], [UNCOND], NEON;
+    0xFF000150 = [Q, Q, Q] => [Qreg(12, 22), Qreg(16, 7), Qreg(0, 5)], [UNCOND], NEON;
+]
+"vdup.8" = [
+    0xEEC00B10 = [D, R] => [Dreg(16, 7), R(12)], [], NEON;
+    0xEEE00B10 = [Q, R] => [Qreg(16, 7), R(12)], [], NEON;
+]
+"vdup.16" = [
+    0xEE800B30 = [D, R] => [Dreg(16, 7), R(12)], [], NEON;
+    0xEEA00B30 = [Q, R] => [Qreg(16, 7), R(12)], [], NEON;
+]
+"vdup.32" = [
+    0xEE800B10 = [D, R] => [Dreg(16, 7), R(12)], [], NEON;
+    0xEEA00B10 = [Q, R] => [Qreg(16, 7), R(12)], [], NEON;
+]
+
+)
@@ -0,0 +1,197 @@
+//! Common map and table definitions useful for parsers.
+use lazy_static::lazy_static;
+
+use super::ast::{RegId, RegFamily, Modifier};
+
+use self::RegId::*;
+
+use crate::collections::HashMap;
+
+/// All armv7 register names, including the aliases of the special purpose core registers.
+pub const ARMV7_REGISTERS: &[(&str, RegId)] = &[
+    ("r0",   R0),
+    ("r1",   R1),
+    ("r2",   R2),
+    ("r3",   R3),
+    ("r4",   R4),
+    ("r5",   R5),
+    ("r6",   R6),
+    ("r7",   R7),
+    ("r8",   R8),
+    ("r9",   R9),
+    ("r10",  R10),
+    ("r11",  R11),
+    ("r12",  R12),
+    ("r13",  SP),
+    ("r14",  LR),
+    ("r15",  PC),
+
+    ("sb",   R9),
+    ("sl",   R10),
+    ("fp",   R11),
+    ("ip",   R12),
+    ("sp",   SP),
+    ("lr",   LR),
+    ("pc",   PC),
+
+    ("s0",   S0),
+    ("s1",   S1),
+    ("s2",   S2),
+    ("s3",   S3),
+    ("s4",   S4),
+    ("s5",   S5),
+    ("s6",   S6),
+    ("s7",   S7),
+    ("s8",   S8),
+    ("s9",   S9),
+    ("s10",  S10),
+    ("s11",  S11),
+    ("s12",  S12),
+    ("s13",  S13),
+    ("s14",  S14),
+    ("s15",  S15),
+    ("s16",  S16),
+    ("s17",  S17),
+    ("s18",  S18),
+    ("s19",  S19),
+    ("s20",  S20),
+    ("s21",  S21),
+    ("s22",  S22),
+    ("s23",  S23),
+    ("s24",  S24),
+    ("s25",  S25),
+    ("s26",  S26),
+    ("s27",  S27),
+    ("s28",  S28),
+    ("s29",  S29),
+    ("s30",  S30),
+    ("s31",  S31),
+
+    ("d0",   D0),
+    ("d1",   D1),
+    ("d2",   D2),
+    ("d3",   D3),
+    ("d4",   D4),
+    ("d5",   D5),
+    ("d6",   D6),
+    ("d7",   D7),
+    ("d8",   D8),
+    ("d9",   D9),
+    ("d10",  D10),
+    ("d11",  D11),
+    ("d12",  D12),
+    ("d13",  D13),
+    ("d14",  D14),
+    ("d15",  D15),
+    ("d16",  D16),
+    ("d17",  D17),
+    ("d18",  D18),
+    ("d19",  D19),
+    ("d20",  D20),
+    ("d21",  D21),
+    ("d22",  D22),
+    ("d23",  D23),
+    ("d24",  D24),
+    ("d25",  D25),
+    ("d26",  D26),
+    ("d27",  D27),
+    ("d28",  D28),
+    ("d29",  D29),
+    ("d30",  D30),
+    ("d31",  D31),
+
+    ("q0",   Q0),
+    ("q1",   Q1),
+    ("q2",   Q2),
+    ("q3",   Q3),
+    ("q4",   Q4),
+    ("q5",   Q5),
+    ("q6",   Q6),
+    ("q7",   Q7),
+    ("q8",   Q8),
+    ("q9",   Q9),
+    ("q10",  Q10),
+    ("q11",  Q11),
+    ("q12",  Q12),
+    ("q13",  Q13),
+    ("q14",  Q14),
+    ("q15",  Q15),
+];
+
+/// The register family prefixes used for dynamic registers.
+pub const ARMV7_FAMILIES: &[(&str, RegFamily)] = &[
+    ("R", RegFamily::CORE),
+    ("S", RegFamily::SINGLE),
+    ("D", RegFamily::DOUBLE),
+    ("Q", RegFamily::QUAD),
+];
+
+/// A list of modifiers in string and value representation.
+pub const ARMV7_MODIFIERS: &[(&str, Modifier)] = &[
+    ("lsl", Modifier::LSL),
+    ("lsr", Modifier::LSR),
+    ("asr", Modifier::ASR),
+    ("ror", Modifier::ROR),
+    ("rrx", Modifier::RRX),
+];
+
+/// The condition codes that can be appended to instructions, and their encoding.
+pub const ARMV7_CONDITIONS: &[(&str, u8)] = &[
+    ("eq", 0x0),
+    ("ne", 0x1),
+    ("cs", 0x2),
+    ("hs", 0x2),
+    ("cc", 0x3),
+    ("lo", 0x3),
+    ("mi", 0x4),
+    ("pl", 0x5),
+    ("vs", 0x6),
+    ("vc", 0x7),
+    ("hi", 0x8),
+    ("ls", 0x9),
+    ("ge", 0xA),
+    ("lt", 0xB),
+    ("gt", 0xC),
+    ("le", 0xD),
+    ("al", 0xE),
+];
+
+/// The options of the dmb, dsb and isb barrier instructions.
+pub const ARMV7_BARRIER_OPTIONS: &[(&str, u8)] = &[
+    ("sy",    0xF),
+    ("st",    0xE),
+    ("ld",    0xD),
+    ("ish",   0xB),
+    ("ishst", 0xA),
+    ("ishld", 0x9),
+    ("nsh",   0x7),
+    ("nshst", 0x6),
+    ("nshld", 0x5),
+    ("osh",   0x3),
+    ("oshst", 0x2),
+    ("oshld", 0x1),
+];
+
+lazy_static!{
+    pub static ref ARMV7_REGISTER_MAP: HashMap<&'static str, RegId> = {
+        ARMV7_REGISTERS.iter().cloned().collect()
+    };
+    pub static ref ARMV7_FAMILIES_MAP: HashMap<&'static str, RegFamily> = {
+        ARMV7_FAMILIES.iter().cloned().collect()
+    };
+    pub static ref ARMV7_CONDITION_MAP: HashMap<&'static str, u8> = {
+        ARMV7_CONDITIONS.iter().cloned().collect()
+    };
+    pub static ref ARMV7_BARRIER_OPTION_MAP: HashMap<&'static str, u8> = {
+        ARMV7_BARRIER_OPTIONS.iter().cloned().collect()
+    };
+}
+
+impl Modifier {
+    /// Looks up a modifier by name, ignoring ASCII case.
+    pub fn from_name(name: &str) -> Option<Modifier> {
+        ARMV7_MODIFIERS.iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|&(_, m)| m)
+    }
+}
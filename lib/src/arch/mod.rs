@@ -1,12 +1,19 @@
 use crate::BasicAssembler;
 use crate::common::{Expr, Jump, Size, Stmt, Value};
 
-use std::fmt::{self, Debug};
+use core::any::Any;
+use core::fmt::{self, Debug};
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
 
 pub mod x64;
-// pub mod aarch64;
+pub mod aarch64;
+pub mod riscv64;
+pub mod armv7;
 
-pub trait Arch: Debug + Send {
+pub trait Arch: Any + Debug + Send {
     fn name(&self) -> &str;
     fn set_features(&mut self, features: &[String]);
     fn handle_static_reloc(&self, stmts: &mut Vec<Stmt>, reloc: Jump, size: Size);
@@ -20,6 +27,11 @@ pub trait Arch: Debug + Send {
 pub trait BasicExprBuilder {
     /// Append a new statement.
     fn push(&mut self, _: Stmt);
+    /// Append a sequence of bytes. Builders can override this to avoid allocating a
+    /// `Stmt::Extend`.
+    fn extend(&mut self, bytes: &[u8]) {
+        self.push(Stmt::Extend(bytes.to_vec()))
+    }
     /// a | b
     fn bit_or(&mut self, _: Expr, _: Value) -> Option<Expr>;
     /// a & b
@@ -50,6 +62,15 @@ pub enum Error {
     },
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::BadExprCombinator { .. } =>
+                f.write_str("This expression can't be combined with other dynamic values here"),
+        }
+    }
+}
+
 /// An opaque description of an error origin.
 #[derive(Debug, Clone, Copy)]
 pub enum ErrorSpan {
@@ -71,6 +92,41 @@ impl ErrorSpan {
     }
 }
 
+/// How an instruction accesses one of its operands or some implicit state. For memory operands
+/// this describes the referenced memory: the registers used in the address are always read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// Not accessed. This is the case for immediates, jump targets and memory operands of which
+    /// only the address is used, like with `lea` on x64 or `prfm` on aarch64.
+    Unused,
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl Access {
+    /// Returns if the value is read.
+    pub fn is_read(self) -> bool {
+        matches!(self, Access::Read | Access::ReadWrite)
+    }
+
+    /// Returns if the value is written.
+    pub fn is_written(self) -> bool {
+        matches!(self, Access::Write | Access::ReadWrite)
+    }
+
+    pub(crate) const fn union(self, other: Access) -> Access {
+        let read = matches!(self, Access::Read | Access::ReadWrite) || matches!(other, Access::Read | Access::ReadWrite);
+        let written = matches!(self, Access::Write | Access::ReadWrite) || matches!(other, Access::Write | Access::ReadWrite);
+        match (read, written) {
+            (false, false) => Access::Unused,
+            (true, false) => Access::Read,
+            (false, true) => Access::Write,
+            (true, true) => Access::ReadWrite,
+        }
+    }
+}
+
 pub trait BasicExprBuilderExt: BasicExprBuilder {
     fn bit_or_else_err(&mut self, a: Expr, b: Value) -> Result<Expr, Error> {
         self.bit_or(a, b).ok_or_else(|| Error::BadExprCombinator { expr: a })
@@ -144,10 +200,6 @@ pub trait BasicExprBuilderExt: BasicExprBuilder {
         self.log2(a).ok_or_else(|| Error::BadExprCombinator { expr: a })
     }
 
-    fn dynscale(&mut self, _: Expr, _: Value) -> Result<(Expr, Expr), Error> {
-        unimplemented!()
-    }
-
     fn mask_shift_else_err(&mut self, val: Expr, mask: u64, shift: i8) -> Result<Expr, Error> {
         self.mask_shift(val, mask, shift).ok_or_else(|| Error::BadExprCombinator { expr: val })
     }
@@ -237,7 +289,10 @@ pub(crate) fn from_str(s: &str) -> Option<Box<dyn Arch>> {
     match s {
         "x64" => Some(Box::new(x64::Archx64::default())),
         "x86" => Some(Box::new(x64::Archx86::default())),
-        // "aarch64" => Some(Box::new(aarch64::ArchAarch64::default())),
+        "aarch64" => Some(Box::new(aarch64::ArchAarch64::default())),
+        "riscv64" => Some(Box::new(riscv64::ArchRiscv64::default())),
+        "arm" => Some(Box::new(armv7::ArchArm::default())),
+        "thumb" => Some(Box::new(armv7::ArchThumb::default())),
         "unknown" => Some(Box::new(DummyArch::new("unknown"))),
         _ => None
     }
@@ -249,5 +304,9 @@ pub const CURRENT_ARCH: &str = "x64";
 pub const CURRENT_ARCH: &str = "x86";
 #[cfg(target_arch="aarch64")]
 pub const CURRENT_ARCH: &str = "aarch64";
-#[cfg(not(any(target_arch="x86", target_arch="x86_64", target_arch="aarch64")))]
+#[cfg(target_arch="riscv64")]
+pub const CURRENT_ARCH: &str = "riscv64";
+#[cfg(target_arch="arm")]
+pub const CURRENT_ARCH: &str = "arm";
+#[cfg(not(any(target_arch="x86", target_arch="x86_64", target_arch="aarch64", target_arch="riscv64", target_arch="arm")))]
 pub const CURRENT_ARCH: &str = "unknown";
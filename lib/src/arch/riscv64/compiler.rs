@@ -0,0 +1,499 @@
+use super::matching::{self, MatchData, fence_bits};
+use super::riscv64data::{Command, ImmField, Relocation, Features, get_mnemonic_data};
+use super::parser::{RISCV_CSR_MAP, RISCV_ROUNDING_MODE_MAP};
+use super::{Context, Error};
+use super::ast::{Instruction, RawArg, FlatArg, Register, RegId, RegFamily};
+
+use crate::arch::{BasicExprBuilderExt, ErrorSpan};
+use crate::common::{Expr, Number, NumericRepr, Stmt, Size, Value, JumpKind, bitmask64};
+
+use alloc::vec::Vec;
+use alloc::{format, vec};
+
+pub(super) fn compile_instruction(ctx: &mut Context, instruction: Instruction, args: Vec<RawArg>) -> Result<(), Error> {
+    let name = &*instruction.ident.name;
+
+    // pseudo-instructions that expand to a sequence of several instructions
+    match name {
+        "li" => return compile_li(ctx, args),
+        "la" | "lla" | "call" | "tail" => return compile_pc_pair(ctx, name, args),
+        _ => ()
+    }
+
+    // atomics take ordering suffixes which are not part of the opmap.
+    let (name, ordering) = split_ordering_suffix(name);
+
+    let match_data = matching::match_instruction(ctx, name, args)?;
+
+    // determine if the features required for this op are fulfilled
+    if !ctx.features.contains(match_data.data.features) {
+        return Err(Error::DisabledFeatures(match_data.data.features - ctx.features));
+    }
+
+    encode_instruction(ctx, match_data, ordering)
+}
+
+/// Splits the .aq, .rl and .aqrl suffixes off of atomic memory operations, returning the
+/// remaining mnemonic and the bits the ordering constraint encodes to.
+fn split_ordering_suffix(name: &str) -> (&str, u32) {
+    const SUFFIXES: [(&str, u32); 3] = [(".aqrl", 0b11 << 25), (".aq", 0b10 << 25), (".rl", 0b01 << 25)];
+
+    for &(suffix, bits) in SUFFIXES.iter() {
+        if let Some(base) = name.strip_suffix(suffix) {
+            if let Some(data) = get_mnemonic_data(base) {
+                if data.iter().all(|op| op.features.contains(Features::A)) {
+                    return (base, bits);
+                }
+            }
+        }
+    }
+
+    (name, 0)
+}
+
+fn encode_instruction(ctx: &mut Context, data: MatchData, extra_bits: u32) -> Result<(), Error> {
+    let mut cursor = 0usize;
+
+    // All static bitfields (compile-time constant) will be encoded into this map of (offset, bitfield)
+    let mut statics = Vec::new();
+    // All dynamic bitfields (run-time determined) will be encoded into this list of already shifted expressions
+    let mut dynamics = Vec::new();
+    // Any relocations will be encoded into this list
+    let mut relocations = Vec::new();
+
+    for command in data.data.commands.iter() {
+        // special commands that don't check the current arg
+        if let Command::C = *command {
+            cursor -= 1;
+            continue;
+        }
+
+        let arg = data.args.get(cursor).expect("Invalid encoding data, tried to process more arguments than given");
+
+        match *arg {
+            FlatArg::Direct { span, reg: Register::Static(id) } => {
+                let code = id.code();
+                match *command {
+                    Command::R(offset) => {
+                        statics.push((offset, u32::from(code)));
+                    },
+                    Command::RNz(offset) => {
+                        if code == 0 {
+                            ctx.state.emit_error_at(span, format_args!("Field does not support register x0"));
+                            return Err(Error::Fatal);
+                        }
+                        statics.push((offset, u32::from(code)));
+                    },
+                    Command::RNzSp(offset) => {
+                        if code == 0 || code == 2 {
+                            ctx.state.emit_error_at(span, format_args!("Field does not support registers x0 and x2"));
+                            return Err(Error::Fatal);
+                        }
+                        statics.push((offset, u32::from(code)));
+                    },
+                    Command::RC(offset) => {
+                        if !(8 .. 16).contains(&code) {
+                            ctx.state.emit_error_at(span, format_args!("Field only supports registers x8-x15 or f8-f15"));
+                            return Err(Error::Fatal);
+                        }
+                        statics.push((offset, u32::from(code - 8)));
+                    },
+                    Command::RSp => {
+                        if id != RegId::X2 {
+                            ctx.state.emit_error_at(span, format_args!("Field only supports the sp register"));
+                            return Err(Error::Fatal);
+                        }
+                    },
+                    _ => panic!("Invalid argument processor")
+                }
+            },
+            FlatArg::Direct { span, reg: Register::Dynamic(_, expr) } => match *command {
+                Command::R(offset)
+                | Command::RNz(offset)
+                | Command::RNzSp(offset) => {
+                    dynamics.push(ctx.state.mask_shift_else_err(expr, 0x1F, offset as i8)?);
+                },
+                // x8-x15 have the same low 3 bits as their compressed encoding
+                Command::RC(offset) => {
+                    dynamics.push(ctx.state.mask_shift_else_err(expr, 0x7, offset as i8)?);
+                },
+                Command::RSp => {
+                    ctx.state.emit_error_at(span, format_args!("Field only supports the sp register, and therefore does not support dynamic registers"));
+                    return Err(Error::Fatal);
+                },
+                _ => panic!("Invalid argument processor")
+            },
+            FlatArg::Immediate { span, value } => match *command {
+                Command::Imm(field) => encode_field(ctx, span, field, value, &mut statics, &mut dynamics)?,
+                Command::Csr => match value {
+                    Value::Number(number) => {
+                        let csr = number.as_u64();
+                        if csr > 0xFFF {
+                            ctx.state.emit_error_at(span, format_args!("Value too large"));
+                            return Err(Error::Fatal);
+                        }
+                        statics.push((20, csr as u32));
+                    },
+                    Value::Expr(expr) => dynamics.push(ctx.state.mask_shift_else_err(expr, 0xFFF, 20)?),
+                },
+                // jump targets also accept immediates
+                Command::Offset(relocation) => {
+                    let field = relocation.field().expect("Invalid encoding data, offset without an immediate field");
+                    encode_field(ctx, span, field, value, &mut statics, &mut dynamics)?;
+                },
+                _ => panic!("Invalid argument processor")
+            },
+            FlatArg::Lit { span, ref ident } => match *command {
+                Command::Csr => {
+                    let csr = *RISCV_CSR_MAP.get(&*ident.name).expect("bad command data");
+                    statics.push((20, u32::from(csr)));
+                },
+                Command::Fence(offset) => {
+                    let bits = fence_bits(&ident.name).expect("bad command data");
+                    statics.push((offset, bits));
+                },
+                Command::Rm(offset) => {
+                    if let Some(&rm) = RISCV_ROUNDING_MODE_MAP.get(&*ident.name) {
+                        statics.push((offset, u32::from(rm)));
+                    } else {
+                        ctx.state.emit_error_at(span, format_args!("Unknown rounding mode"));
+                        return Err(Error::Fatal);
+                    }
+                },
+                _ => panic!("Invalid argument processor")
+            },
+            FlatArg::JumpTarget { ref jump } => match *command {
+                Command::Offset(relocation) => {
+                    // what kind of relocation is it
+                    let data = [relocation.to_id()];
+
+                    // encode the complete relocation
+                    let stmt = jump.clone().encode(&data);
+
+                    relocations.push(stmt);
+                },
+                _ => panic!("Invalid argument processor")
+            },
+            FlatArg::Default => match *command {
+                // omitted rounding modes use the dynamic rounding mode from frm
+                Command::Rm(offset) => {
+                    statics.push((offset, 0b111));
+                },
+                // omitted reference offsets are zero
+                Command::Imm(_) => (),
+                _ => panic!("Invalid argument processor")
+            }
+        }
+
+        cursor += 1;
+    }
+
+    // sanity
+    if cursor != data.args.len() {
+        panic!("Not enough command processors");
+    }
+
+    // apply all statics to bits
+    let mut bits = data.data.base | extra_bits;
+    for (offset, value) in statics {
+        bits |= value << offset;
+    }
+
+    emit_instruction(ctx, bits, dynamics, data.data.is_compressed())?;
+
+    // generate code to be emitted for relocations
+    for stmt in relocations {
+        ctx.state.push(stmt);
+    }
+
+    Ok(())
+}
+
+/// Encodes an immediate into a (possibly scattered) immediate field of an instruction.
+fn encode_field(ctx: &mut Context, span: ErrorSpan, field: &ImmField, value: Value, statics: &mut Vec<(u8, u32)>, dynamics: &mut Vec<Expr>) -> Result<(), Error> {
+    match value {
+        Value::Number(number) => {
+            let value = if number.repr().signed {
+                i128::from(number.as_i64())
+            } else {
+                i128::from(number.as_u64())
+            };
+
+            let (min, max) = if field.signed {
+                (-1i128 << (field.bits - 1), (1i128 << (field.bits - 1)) - 1)
+            } else {
+                (0, (1i128 << field.bits) - 1)
+            };
+
+            if value & ((1 << field.scale) - 1) != 0 {
+                ctx.state.emit_error_at(span, format_args!("Unrepresentable value"));
+                return Err(Error::Fatal);
+            } else if value > max {
+                ctx.state.emit_error_at(span, format_args!("Value too large"));
+                return Err(Error::Fatal);
+            } else if value < min {
+                ctx.state.emit_error_at(span, format_args!("Value too small"));
+                return Err(Error::Fatal);
+            } else if field.nonzero && value == 0 {
+                ctx.state.emit_error_at(span, format_args!("Value cannot be zero"));
+                return Err(Error::Fatal);
+            }
+
+            for &(src, len, dst) in field.fields {
+                statics.push((dst, ((value >> src) as u32) & (bitmask64(len) as u32)));
+            }
+        },
+        Value::Expr(expr) => {
+            for &(src, len, dst) in field.fields {
+                dynamics.push(ctx.state.mask_shift_else_err(expr, bitmask64(len) << src, dst as i8 - src as i8)?);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Emits a single instruction, combining the static bits with any dynamic bitfields.
+/// Compressed instructions are emitted as a single halfword.
+fn emit_instruction(ctx: &mut Context, bits: u32, dynamics: Vec<Expr>, compressed: bool) -> Result<(), Error> {
+    let mut dynamics = dynamics.into_iter();
+
+    let mut expr = if let Some(expr) = dynamics.next() {
+        expr
+    } else if compressed {
+        ctx.state.push(Stmt::Const(Value::Word(bits as u16)));
+        return Ok(());
+    } else {
+        ctx.state.push(Stmt::Const(Value::Dword(bits)));
+        return Ok(());
+    };
+
+    for field in dynamics {
+        expr = ctx.state.bit_or_else_err(expr, field.into())?;
+    }
+    if bits != 0 {
+        expr = ctx.state.bit_or_else_err(expr, Value::Dword(bits))?;
+    }
+
+    expr.repr = if compressed { NumericRepr::U16 } else { NumericRepr::U32 };
+    ctx.state.push(Stmt::Const(Value::Expr(expr)));
+    Ok(())
+}
+
+/// A signed 64-bit constant, used as an addend to dynamic values.
+fn signed_value(value: i64) -> Value {
+    Value::Number(Number::from_u64_and_repr(value as u64, NumericRepr::I64))
+}
+
+fn format_mismatch(name: &str, forms: &[&str]) -> Error {
+    let forms: Vec<_> = forms.iter().map(|form| format!(">>> {} {}", name, form)).collect();
+    format!("'{}': instruction format mismatch, expected one of the following forms:\n{}",
+        name, forms.join("\n")).into()
+}
+
+/*
+ * Pseudo-instructions that expand to several instructions
+ */
+
+const LUI:   u32 = 0x0000_0037;
+const AUIPC: u32 = 0x0000_0017;
+const ADDI:  u32 = 0x0000_0013;
+const ADDIW: u32 = 0x0000_001B;
+const SLLI:  u32 = 0x0000_1013;
+const SRLI:  u32 = 0x0000_5013;
+const JALR:  u32 = 0x0000_0067;
+
+const RA: u32 = 1;
+const T1: u32 = 6;
+
+/// The destination (and source) register of a pseudo-instruction expansion.
+#[derive(Clone, Copy)]
+enum Dest {
+    Static(u32),
+    Dynamic(Expr),
+}
+
+impl Dest {
+    fn new(reg: &Register) -> Dest {
+        match *reg {
+            Register::Static(id) => Dest::Static(u32::from(id.code())),
+            Register::Dynamic(_, expr) => Dest::Dynamic(expr),
+        }
+    }
+}
+
+/// Emits a single 32-bit instruction with `dest` encoded in the register fields at `offsets`.
+fn emit_with_dest(ctx: &mut Context, mut bits: u32, dest: Dest, offsets: &[u8], mut dynamics: Vec<Expr>) -> Result<(), Error> {
+    for &offset in offsets {
+        match dest {
+            Dest::Static(code) => bits |= code << offset,
+            Dest::Dynamic(expr) => dynamics.push(ctx.state.mask_shift_else_err(expr, 0x1F, offset as i8)?),
+        }
+    }
+    emit_instruction(ctx, bits, dynamics, false)
+}
+
+/// Interprets a number as a 64-bit two's complement value, accepting both signed and unsigned representations.
+fn as_i64_number(number: Number) -> i64 {
+    if number.repr().signed {
+        number.as_i64()
+    } else {
+        number.as_u64() as i64
+    }
+}
+
+/// Loads an arbitrary immediate into an integer register.
+///
+/// Static values are materialized with a minimal lui/addi(w)/slli sequence. Dynamic values are
+/// limited to 32 bits and always use a lui/addiw pair, followed by a zero extension for unsigned
+/// values.
+fn compile_li(ctx: &mut Context, args: Vec<RawArg>) -> Result<(), Error> {
+    let (dest, value) = match &args[..] {
+        [RawArg::Direct { reg }, RawArg::Immediate { value }] if reg.family() == RegFamily::INTEGER => (Dest::new(reg), *value),
+        _ => return Err(format_mismatch("li", &["x1, imm"]))
+    };
+
+    match value {
+        Value::Number(number) => {
+            let mut sequence = Vec::new();
+            materialize(as_i64_number(number), &mut sequence);
+
+            for (i, bits) in sequence.into_iter().enumerate() {
+                // the first instruction in the sequence builds on x0
+                let offsets: &[u8] = if i == 0 { &[7] } else { &[7, 15] };
+                emit_with_dest(ctx, bits, dest, offsets, Vec::new())?;
+            }
+        },
+        Value::Expr(expr) => {
+            let repr = expr.repr;
+            if repr.size > Size::DWORD {
+                ctx.state.emit_error_at(ErrorSpan::argument(1), format_args!("Dynamic immediates for li are limited to 32 bits"));
+                return Err(Error::Fatal);
+            }
+
+            // widen the value so the rounding addend cannot overflow
+            let expr = Expr { idx: expr.idx, repr: NumericRepr::I64 };
+
+            let hi = ctx.state.add_else_err(expr, signed_value(0x800))?;
+            let hi = ctx.state.mask_shift_else_err(hi, 0xFFFF_F000, 0)?;
+            emit_with_dest(ctx, LUI, dest, &[7], vec![hi])?;
+
+            let lo = ctx.state.mask_shift_else_err(expr, 0xFFF, 20)?;
+            emit_with_dest(ctx, ADDIW, dest, &[7, 15], vec![lo])?;
+
+            if repr.size == Size::DWORD && !repr.signed {
+                emit_with_dest(ctx, SLLI | (32 << 20), dest, &[7, 15], Vec::new())?;
+                emit_with_dest(ctx, SRLI | (32 << 20), dest, &[7, 15], Vec::new())?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Computes the instruction sequence that loads `value` into a register, with all register fields left empty.
+fn materialize(value: i64, sequence: &mut Vec<u32>) {
+    // sign-extended low 12 bits
+    let lo12 = (value << 52) >> 52;
+
+    if value == i64::from(value as i32) {
+        let hi20 = (value.wrapping_add(0x800) >> 12) & 0xF_FFFF;
+        if hi20 != 0 {
+            sequence.push(LUI | ((hi20 as u32) << 12));
+        }
+        if lo12 != 0 || hi20 == 0 {
+            let op = if hi20 != 0 { ADDIW } else { ADDI };
+            sequence.push(op | ((lo12 as u32) << 20));
+        }
+        return;
+    }
+
+    // materialize the upper bits, shifted down as far as possible, then shift them back into place.
+    let hi52 = value.wrapping_sub(lo12) >> 12;
+    let shift = 12 + hi52.trailing_zeros();
+    let upper = value.wrapping_sub(lo12) >> shift;
+
+    materialize(upper, sequence);
+    sequence.push(SLLI | (shift << 20));
+    if lo12 != 0 {
+        sequence.push(ADDI | ((lo12 as u32) << 20));
+    }
+}
+
+/// Expands the pc-relative pseudo-instructions `la`, `lla`, `call` and `tail` into an auipc
+/// instruction followed by an addi or jalr instruction. Labels are resolved using a relocation
+/// spanning both instructions.
+fn compile_pc_pair(ctx: &mut Context, name: &str, args: Vec<RawArg>) -> Result<(), Error> {
+    let (dest, target, second, relocation) = match (name, &args[..]) {
+        ("la", [RawArg::Direct { reg }, target])
+        | ("lla", [RawArg::Direct { reg }, target]) if reg.family() == RegFamily::INTEGER => {
+            let dest = Dest::new(reg);
+            (dest, target, (ADDI, dest, dest), Relocation::AUIPC_ADDI)
+        },
+        ("call", [target]) => (Dest::Static(RA), target, (JALR, Dest::Static(RA), Dest::Static(RA)), Relocation::AUIPC_JALR),
+        ("call", [RawArg::Direct { reg }, target]) if reg.family() == RegFamily::INTEGER => {
+            let dest = Dest::new(reg);
+            (dest, target, (JALR, dest, dest), Relocation::AUIPC_JALR)
+        },
+        ("tail", [target]) => (Dest::Static(T1), target, (JALR, Dest::Static(0), Dest::Static(T1)), Relocation::AUIPC_JALR),
+        _ => return Err(match name {
+            "la" | "lla" => format_mismatch(name, &["x1, offset"]),
+            "call" => format_mismatch(name, &["offset", "x1, offset"]),
+            _ => format_mismatch(name, &["offset"]),
+        })
+    };
+    let (second, second_rd, second_rs) = second;
+    let span = ErrorSpan::argument(args.len() - 1);
+
+    let mut hi_bits = 0;
+    let mut lo_bits = 0;
+    let mut hi_dynamics = Vec::new();
+    let mut lo_dynamics = Vec::new();
+    let mut jump = None;
+
+    match *target {
+        RawArg::JumpTarget { jump: ref target } => {
+            if let JumpKind::Bare(_) = target.kind {
+                ctx.state.emit_error_at(span, format_args!("Extern relocations are not allowed in riscv64"));
+                return Err(Error::Fatal);
+            }
+            jump = Some(target.clone());
+        },
+        RawArg::Immediate { value: Value::Number(number) } => {
+            let value = as_i64_number(number);
+            if value > i64::from(i32::MAX) - 0x800 {
+                ctx.state.emit_error_at(span, format_args!("Value too large"));
+                return Err(Error::Fatal);
+            } else if value < i64::from(i32::MIN) {
+                ctx.state.emit_error_at(span, format_args!("Value too small"));
+                return Err(Error::Fatal);
+            }
+            hi_bits = ((value + 0x800) as u32) & 0xFFFF_F000;
+            lo_bits = (value as u32) << 20;
+        },
+        RawArg::Immediate { value: Value::Expr(expr) } => {
+            // widen the value so the rounding addend cannot overflow
+            let expr = Expr { idx: expr.idx, repr: NumericRepr::I64 };
+            let hi = ctx.state.add_else_err(expr, signed_value(0x800))?;
+            hi_dynamics.push(ctx.state.mask_shift_else_err(hi, 0xFFFF_F000, 0)?);
+            lo_dynamics.push(ctx.state.mask_shift_else_err(expr, 0xFFF, 20)?);
+        },
+        _ => return Err(format_mismatch(name, &["offset"]))
+    }
+
+    emit_with_dest(ctx, AUIPC | hi_bits, dest, &[7], hi_dynamics)?;
+
+    let mut bits = second | lo_bits;
+    match second_rs {
+        Dest::Static(code) => bits |= code << 15,
+        Dest::Dynamic(expr) => lo_dynamics.push(ctx.state.mask_shift_else_err(expr, 0x1F, 15)?),
+    }
+    emit_with_dest(ctx, bits, second_rd, &[7], lo_dynamics)?;
+
+    // the relocation covers both instructions
+    if let Some(jump) = jump {
+        ctx.state.push(jump.encode(&[relocation.to_id()]));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use super::riscv64data::{Opdata, Matcher, Features};
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::format;
+
+
+pub fn format_opdata_list(name: &str, data: &[Opdata]) -> String {
+    let mut forms = Vec::new();
+
+    for data in data {
+        forms.push(format_opdata(name, data));
+    }
+
+    forms.join("\n")
+}
+
+pub fn format_opdata(name: &str, data: &Opdata) -> String {
+    let mut buf = format!(">>> {}", name);
+    let mut args = Vec::new();
+    let mut optional = false;
+    let mut regs = 0;
+
+    for matcher in data.matchers {
+        let arg = match matcher {
+            Matcher::X => { regs += 1; format!("x{}", regs) },
+            Matcher::F => { regs += 1; format!("f{}", regs) },
+            Matcher::Imm => "imm".to_string(),
+            Matcher::Offset => "offset".to_string(),
+            Matcher::Ref => "imm(xn)".to_string(),
+            Matcher::RefBase => "(xn)".to_string(),
+            Matcher::Csr => "csr".to_string(),
+            Matcher::Fence => "iorw".to_string(),
+            Matcher::Rm => "rm".to_string(),
+            Matcher::End => {
+                optional = true;
+                continue;
+            }
+        };
+        if optional {
+            args.push(format!("[{}]", arg));
+        } else {
+            args.push(arg);
+        }
+    }
+
+    if !args.is_empty() {
+        buf.push(' ');
+        buf.push_str(&args.join(", "));
+    }
+
+    if data.features != Features::I {
+        buf.push_str(&format!(" ({})", data.features));
+    }
+
+    buf
+}
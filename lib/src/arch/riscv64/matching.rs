@@ -0,0 +1,161 @@
+use super::{Context, Error};
+use super::ast::{RawArg, FlatArg, RegFamily};
+use super::riscv64data::{Opdata, Matcher, get_mnemonic_data};
+use super::parser::{RISCV_CSR_MAP, RISCV_ROUNDING_MODE_MAP};
+use super::debug::format_opdata_list;
+
+use crate::arch::ErrorSpan;
+use crate::common::{JumpKind, Value};
+
+use alloc::vec::Vec;
+use alloc::format;
+
+/// The result of matching an instruction: the matched encoding and a flattened list of arguments.
+#[derive(Debug)]
+pub(super) struct MatchData {
+    pub data: &'static Opdata,
+    pub args: Vec<FlatArg>,
+}
+
+/// Try finding an appropriate definition that matches the given instruction / arguments.
+pub(super) fn match_instruction(ctx: &mut Context, name: &str, args: Vec<RawArg>) -> Result<MatchData, Error> {
+    // get the possible matchers
+    let opdata = if let Some(o) = get_mnemonic_data(name) {
+        o
+    } else {
+        ctx.state.emit_error_at(ErrorSpan::instruction_part(0), format_args!("'{}' is not a valid instruction", name));
+        return Err(Error::Fatal);
+    };
+
+    // validate that only relative jumps are allowed (no extern relocations)
+    for (idx, arg) in args.iter().enumerate() {
+        if let RawArg::JumpTarget { jump } = arg {
+            if let JumpKind::Bare(_) = jump.kind {
+                ctx.state.emit_error_at(ErrorSpan::argument(idx), format_args!("Extern relocations are not allowed in riscv64"));
+                return Err(Error::Fatal);
+            }
+        }
+    }
+
+    // matching loop
+    for data in opdata {
+        if match_args(&args, data) {
+            return Ok(MatchData {
+                data,
+                args: flatten_args(args, data),
+            });
+        }
+    }
+
+    Err(format!("'{}': instruction format mismatch, expected one of the following forms:\n{}",
+        name, format_opdata_list(name, opdata)).into())
+}
+
+/// Checks if the given arguments can be encoded by the given opdata.
+fn match_args(args: &[RawArg], data: &Opdata) -> bool {
+    let mut args = args.iter();
+    let mut optional = false;
+
+    for matcher in data.matchers {
+        if *matcher == Matcher::End {
+            optional = true;
+            continue;
+        }
+
+        let arg = match args.next() {
+            Some(arg) => arg,
+            None => return optional,
+        };
+
+        if !match_arg(arg, *matcher) {
+            return false;
+        }
+    }
+
+    args.next().is_none()
+}
+
+fn match_arg(arg: &RawArg, matcher: Matcher) -> bool {
+    match (matcher, arg) {
+        (Matcher::X, RawArg::Direct { reg }) => reg.family() == RegFamily::INTEGER,
+        (Matcher::F, RawArg::Direct { reg }) => reg.family() == RegFamily::FP,
+        (Matcher::Imm, RawArg::Immediate { .. }) => true,
+        (Matcher::Offset, RawArg::Immediate { .. })
+        | (Matcher::Offset, RawArg::JumpTarget { .. }) => true,
+        (Matcher::Ref, RawArg::Reference { base, .. }) => base.family() == RegFamily::INTEGER,
+        (Matcher::RefBase, RawArg::Reference { base, offset }) => base.family() == RegFamily::INTEGER && match offset {
+            None => true,
+            Some(Value::Number(number)) => number.as_u64() == 0,
+            Some(Value::Expr(_)) => false,
+        },
+        (Matcher::Csr, RawArg::Immediate { .. }) => true,
+        (Matcher::Csr, RawArg::Lit { ident }) => RISCV_CSR_MAP.contains_key(&*ident.name),
+        (Matcher::Fence, RawArg::Lit { ident }) => fence_bits(&ident.name).is_some(),
+        (Matcher::Rm, RawArg::Lit { ident }) => RISCV_ROUNDING_MODE_MAP.contains_key(&*ident.name),
+        _ => false
+    }
+}
+
+/// Flattens the argument list, splitting references into their base and offset and
+/// filling in omitted optional arguments with `FlatArg::Default`.
+fn flatten_args(args: Vec<RawArg>, data: &Opdata) -> Vec<FlatArg> {
+    let mut res = Vec::new();
+    let mut args = args.into_iter().enumerate();
+
+    for matcher in data.matchers {
+        if *matcher == Matcher::End {
+            continue;
+        }
+
+        let (idx, arg) = match args.next() {
+            Some(arg) => arg,
+            None => {
+                res.push(FlatArg::Default);
+                continue;
+            }
+        };
+        let span = ErrorSpan::argument(idx);
+
+        match arg {
+            RawArg::Direct { reg } => res.push(FlatArg::Direct { span, reg }),
+            RawArg::Immediate { value } => res.push(FlatArg::Immediate { span, value }),
+            RawArg::JumpTarget { jump } => res.push(FlatArg::JumpTarget { jump }),
+            RawArg::Lit { ident } => res.push(FlatArg::Lit { span, ident }),
+            RawArg::Reference { base, offset } => {
+                res.push(FlatArg::Direct { span, reg: base });
+                if *matcher == Matcher::Ref {
+                    res.push(match offset {
+                        Some(value) => FlatArg::Immediate { span, value },
+                        None => FlatArg::Default
+                    });
+                }
+            }
+        }
+    }
+
+    res
+}
+
+/// Parses a fence set such as "rw" or "iorw" into its 4-bit encoding.
+pub(super) fn fence_bits(set: &str) -> Option<u32> {
+    let mut bits = 0;
+    for c in set.chars() {
+        let bit = match c {
+            'i' => 8,
+            'o' => 4,
+            'r' => 2,
+            'w' => 1,
+            _ => return None
+        };
+        if bits & bit != 0 {
+            return None;
+        }
+        bits |= bit;
+    }
+
+    if bits == 0 {
+        None
+    } else {
+        Some(bits)
+    }
+}
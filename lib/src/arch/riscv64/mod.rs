@@ -0,0 +1,148 @@
+pub mod ast;
+pub mod parser;
+mod matching;
+mod compiler;
+mod riscv64data;
+mod debug;
+
+use alloc::borrow::Cow;
+
+use crate::arch::{Arch, Error as ExprBuilderError, BasicExprBuilder};
+use crate::common::{Size, Stmt, Jump};
+use self::riscv64data::{Features, Relocation};
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+struct Context<'a> {
+    pub state: &'a mut dyn BasicExprBuilder,
+    pub features: Features,
+}
+
+#[derive(Clone, Debug)]
+pub struct ArchRiscv64 {
+    features: Features,
+}
+
+#[derive(Debug)]
+pub struct InstructionRiscv {
+    pub inst: ast::Instruction,
+    pub args: Vec<ast::RawArg>,
+}
+
+pub trait AssembleRiscv {
+    /// Turn an expression into binary format.
+    /// May error when dynamic data is present at locations that only support static values.
+    fn compile_instruction(&mut self, arch: &ArchRiscv64, _: InstructionRiscv) -> Result<(), Error>;
+
+    /// Create an instruction composed from dynamic data.
+    /// Only available when the type is also capable of building new composite expressions.
+    fn build_instruction(&mut self, arch: &ArchRiscv64, _: InstructionRiscv) -> Result<(), Error>
+        where Self: BasicExprBuilder;
+}
+
+/// An error while assembling, either an error in the environment or during processing.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// An error happened within the expression builder.
+    Expr(ExprBuilderError),
+    /// An error without occurred where diagnostics offer no introspection.
+    Generic {
+        message: Cow<'static, str>,
+    },
+    /// Some unspecified consistency check did not succeed.
+    /// When this occurs we have emitted one or several diagnostic messages.
+    Fatal,
+    /// Use of extensions that were disabled or would need to be explicitly enabled.
+    DisabledFeatures(riscv64data::Features),
+}
+
+impl From<ExprBuilderError> for Error {
+    fn from(err: ExprBuilderError) -> Self {
+        Error::Expr(err)
+    }
+}
+
+impl From<&'static str> for Error {
+    fn from(message: &'static str) -> Self {
+        Error::Generic {
+            message: Cow::Borrowed(message),
+        }
+    }
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::Generic {
+            message: Cow::Owned(message),
+        }
+    }
+}
+
+impl Default for ArchRiscv64 {
+    fn default() -> ArchRiscv64 {
+        ArchRiscv64 { features: Features::all() }
+    }
+}
+
+impl Arch for ArchRiscv64 {
+    fn name(&self) -> &str {
+        "riscv64"
+    }
+
+    fn set_features(&mut self, features: &[String]) {
+        // the base integer instruction set is always available
+        let mut new_features = Features::I;
+        for ident in features {
+            new_features |= match Features::from_name(ident) {
+                Some(feature) => feature,
+                None => {
+                    eprintln!("Architecture riscv64 does not support feature '{}'", ident);
+                    continue;
+                }
+            }
+        }
+        self.features = new_features;
+    }
+
+    fn handle_static_reloc(&self, stmts: &mut Vec<Stmt>, reloc: Jump, size: Size) {
+        let relocation = match size {
+            Size::BYTE => Relocation::LITERAL8,
+            Size::WORD => Relocation::LITERAL16,
+            Size::DWORD => Relocation::LITERAL32,
+            Size::QWORD => Relocation::LITERAL64,
+            _ => {
+                eprintln!("Relocation of unsupported size for the current target architecture");
+                return;
+            }
+        };
+        let data = [relocation.to_id()];
+
+        stmts.push(Stmt::zeroed(size));
+        stmts.push(reloc.encode(&data));
+    }
+
+    fn default_align(&self) -> u8 {
+        0
+    }
+}
+
+impl<T: BasicExprBuilder> AssembleRiscv for T {
+    fn compile_instruction(&mut self, arch: &ArchRiscv64, instruction: InstructionRiscv) -> Result<(), Error> {
+        let InstructionRiscv { inst, args } = instruction;
+
+        let mut ctx = Context {
+            state: self,
+            features: arch.features,
+        };
+
+        compiler::compile_instruction(&mut ctx, inst, args)
+    }
+
+    fn build_instruction(&mut self, arch: &ArchRiscv64, instruction: InstructionRiscv) -> Result<(), Error>
+        where Self: BasicExprBuilder
+    {
+        AssembleRiscv::compile_instruction(self, arch, instruction)
+    }
+}
@@ -0,0 +1,824 @@
+// RV64GC instruction encodings, including the Zba, Zbb, Zicsr and Zifencei extensions.
+// Compressed instructions are recognized by their base not ending in 0b11.
+Ops!(
+
+// RV64I
+"lui" = [
+    0x00000037 = [X, Imm] => [R(7), Imm(&U_IMM)], I;
+]
+"auipc" = [
+    0x00000017 = [X, Imm] => [R(7), Imm(&U_IMM)], I;
+]
+"jal" = [
+    0x0000006F = [X, Offset] => [R(7), Offset(J)], I;
+    0x000000EF = [Offset] => [Offset(J)], I;
+]
+"jalr" = [
+    0x00000067 = [X, Ref] => [R(7), R(15), Imm(&I_IMM)], I;
+    0x00000067 = [X, X, Imm] => [R(7), R(15), Imm(&I_IMM)], I;
+    0x000000E7 = [X] => [R(15)], I;
+]
+"beq" = [
+    0x00000063 = [X, X, Offset] => [R(15), R(20), Offset(B)], I;
+]
+"bne" = [
+    0x00001063 = [X, X, Offset] => [R(15), R(20), Offset(B)], I;
+]
+"blt" = [
+    0x00004063 = [X, X, Offset] => [R(15), R(20), Offset(B)], I;
+]
+"bge" = [
+    0x00005063 = [X, X, Offset] => [R(15), R(20), Offset(B)], I;
+]
+"bltu" = [
+    0x00006063 = [X, X, Offset] => [R(15), R(20), Offset(B)], I;
+]
+"bgeu" = [
+    0x00007063 = [X, X, Offset] => [R(15), R(20), Offset(B)], I;
+]
+"lb" = [
+    0x00000003 = [X, Ref] => [R(7), R(15), Imm(&I_IMM)], I;
+]
+"lh" = [
+    0x00001003 = [X, Ref] => [R(7), R(15), Imm(&I_IMM)], I;
+]
+"lw" = [
+    0x00002003 = [X, Ref] => [R(7), R(15), Imm(&I_IMM)], I;
+]
+"ld" = [
+    0x00003003 = [X, Ref] => [R(7), R(15), Imm(&I_IMM)], I;
+]
+"lbu" = [
+    0x00004003 = [X, Ref] => [R(7), R(15), Imm(&I_IMM)], I;
+]
+"lhu" = [
+    0x00005003 = [X, Ref] => [R(7), R(15), Imm(&I_IMM)], I;
+]
+"lwu" = [
+    0x00006003 = [X, Ref] => [R(7), R(15), Imm(&I_IMM)], I;
+]
+"sb" = [
+    0x00000023 = [X, Ref] => [R(20), R(15), Imm(&S_IMM)], I;
+]
+"sh" = [
+    0x00001023 = [X, Ref] => [R(20), R(15), Imm(&S_IMM)], I;
+]
+"sw" = [
+    0x00002023 = [X, Ref] => [R(20), R(15), Imm(&S_IMM)], I;
+]
+"sd" = [
+    0x00003023 = [X, Ref] => [R(20), R(15), Imm(&S_IMM)], I;
+]
+"addi" = [
+    0x00000013 = [X, X, Imm] => [R(7), R(15), Imm(&I_IMM)], I;
+]
+"slti" = [
+    0x00002013 = [X, X, Imm] => [R(7), R(15), Imm(&I_IMM)], I;
+]
+"sltiu" = [
+    0x00003013 = [X, X, Imm] => [R(7), R(15), Imm(&I_IMM)], I;
+]
+"xori" = [
+    0x00004013 = [X, X, Imm] => [R(7), R(15), Imm(&I_IMM)], I;
+]
+"ori" = [
+    0x00006013 = [X, X, Imm] => [R(7), R(15), Imm(&I_IMM)], I;
+]
+"andi" = [
+    0x00007013 = [X, X, Imm] => [R(7), R(15), Imm(&I_IMM)], I;
+]
+"slli" = [
+    0x00001013 = [X, X, Imm] => [R(7), R(15), Imm(&SHAMT6)], I;
+]
+"srli" = [
+    0x00005013 = [X, X, Imm] => [R(7), R(15), Imm(&SHAMT6)], I;
+]
+"srai" = [
+    0x40005013 = [X, X, Imm] => [R(7), R(15), Imm(&SHAMT6)], I;
+]
+"addiw" = [
+    0x0000001B = [X, X, Imm] => [R(7), R(15), Imm(&I_IMM)], I;
+]
+"slliw" = [
+    0x0000101B = [X, X, Imm] => [R(7), R(15), Imm(&SHAMT5)], I;
+]
+"srliw" = [
+    0x0000501B = [X, X, Imm] => [R(7), R(15), Imm(&SHAMT5)], I;
+]
+"sraiw" = [
+    0x4000501B = [X, X, Imm] => [R(7), R(15), Imm(&SHAMT5)], I;
+]
+"add" = [
+    0x00000033 = [X, X, X] => [R(7), R(15), R(20)], I;
+]
+"sub" = [
+    0x40000033 = [X, X, X] => [R(7), R(15), R(20)], I;
+]
+"sll" = [
+    0x00001033 = [X, X, X] => [R(7), R(15), R(20)], I;
+]
+"slt" = [
+    0x00002033 = [X, X, X] => [R(7), R(15), R(20)], I;
+]
+"sltu" = [
+    0x00003033 = [X, X, X] => [R(7), R(15), R(20)], I;
+]
+"xor" = [
+    0x00004033 = [X, X, X] => [R(7), R(15), R(20)], I;
+]
+"srl" = [
+    0x00005033 = [X, X, X] => [R(7), R(15), R(20)], I;
+]
+"sra" = [
+    0x40005033 = [X, X, X] => [R(7), R(15), R(20)], I;
+]
+"or" = [
+    0x00006033 = [X, X, X] => [R(7), R(15), R(20)], I;
+]
+"and" = [
+    0x00007033 = [X, X, X] => [R(7), R(15), R(20)], I;
+]
+"addw" = [
+    0x0000003B = [X, X, X] => [R(7), R(15), R(20)], I;
+]
+"subw" = [
+    0x4000003B = [X, X, X] => [R(7), R(15), R(20)], I;
+]
+"sllw" = [
+    0x0000103B = [X, X, X] => [R(7), R(15), R(20)], I;
+]
+"srlw" = [
+    0x0000503B = [X, X, X] => [R(7), R(15), R(20)], I;
+]
+"sraw" = [
+    0x4000503B = [X, X, X] => [R(7), R(15), R(20)], I;
+]
+"fence" = [
+    0x0FF0000F = [] => [], I;
+    0x0000000F = [Fence, Fence] => [Fence(24), Fence(20)], I;
+]
+"fence.tso" = [
+    0x8330000F = [] => [], I;
+]
+"ecall" = [
+    0x00000073 = [] => [], I;
+]
+"ebreak" = [
+    0x00100073 = [] => [], I;
+]
+// RV64I pseudo-instructions
+"nop" = [
+    0x00000013 = [] => [], I;
+]
+"mv" = [
+    0x00000013 = [X, X] => [R(7), R(15)], I;
+]
+"not" = [
+    0xFFF04013 = [X, X] => [R(7), R(15)], I;
+]
+"neg" = [
+    0x40000033 = [X, X] => [R(7), R(20)], I;
+]
+"negw" = [
+    0x4000003B = [X, X] => [R(7), R(20)], I;
+]
+"sext.w" = [
+    0x0000001B = [X, X] => [R(7), R(15)], I;
+]
+"zext.b" = [
+    0x0FF07013 = [X, X] => [R(7), R(15)], I;
+]
+"seqz" = [
+    0x00103013 = [X, X] => [R(7), R(15)], I;
+]
+"snez" = [
+    0x00003033 = [X, X] => [R(7), R(20)], I;
+]
+"sltz" = [
+    0x00002033 = [X, X] => [R(7), R(15)], I;
+]
+"sgtz" = [
+    0x00002033 = [X, X] => [R(7), R(20)], I;
+]
+"beqz" = [
+    0x00000063 = [X, Offset] => [R(15), Offset(B)], I;
+]
+"bnez" = [
+    0x00001063 = [X, Offset] => [R(15), Offset(B)], I;
+]
+"blez" = [
+    0x00005063 = [X, Offset] => [R(20), Offset(B)], I;
+]
+"bgez" = [
+    0x00005063 = [X, Offset] => [R(15), Offset(B)], I;
+]
+"bltz" = [
+    0x00004063 = [X, Offset] => [R(15), Offset(B)], I;
+]
+"bgtz" = [
+    0x00004063 = [X, Offset] => [R(20), Offset(B)], I;
+]
+"bgt" = [
+    0x00004063 = [X, X, Offset] => [R(20), R(15), Offset(B)], I;
+]
+"ble" = [
+    0x00005063 = [X, X, Offset] => [R(20), R(15), Offset(B)], I;
+]
+"bgtu" = [
+    0x00006063 = [X, X, Offset] => [R(20), R(15), Offset(B)], I;
+]
+"bleu" = [
+    0x00007063 = [X, X, Offset] => [R(20), R(15), Offset(B)], I;
+]
+"j" = [
+    0x0000006F = [Offset] => [Offset(J)], I;
+]
+"jr" = [
+    0x00000067 = [X] => [R(15)], I;
+]
+"ret" = [
+    0x00008067 = [] => [], I;
+]
+// Zifencei
+"fence.i" = [
+    0x0000100F = [] => [], ZIFENCEI;
+]
+// M
+"mul" = [
+    0x02000033 = [X, X, X] => [R(7), R(15), R(20)], M;
+]
+"mulh" = [
+    0x02001033 = [X, X, X] => [R(7), R(15), R(20)], M;
+]
+"mulhsu" = [
+    0x02002033 = [X, X, X] => [R(7), R(15), R(20)], M;
+]
+"mulhu" = [
+    0x02003033 = [X, X, X] => [R(7), R(15), R(20)], M;
+]
+"div" = [
+    0x02004033 = [X, X, X] => [R(7), R(15), R(20)], M;
+]
+"divu" = [
+    0x02005033 = [X, X, X] => [R(7), R(15), R(20)], M;
+]
+"rem" = [
+    0x02006033 = [X, X, X] => [R(7), R(15), R(20)], M;
+]
+"remu" = [
+    0x02007033 = [X, X, X] => [R(7), R(15), R(20)], M;
+]
+"mulw" = [
+    0x0200003B = [X, X, X] => [R(7), R(15), R(20)], M;
+]
+"divw" = [
+    0x0200403B = [X, X, X] => [R(7), R(15), R(20)], M;
+]
+"divuw" = [
+    0x0200503B = [X, X, X] => [R(7), R(15), R(20)], M;
+]
+"remw" = [
+    0x0200603B = [X, X, X] => [R(7), R(15), R(20)], M;
+]
+"remuw" = [
+    0x0200703B = [X, X, X] => [R(7), R(15), R(20)], M;
+]
+// A. The .aq, .rl and .aqrl suffixes are handled by the compiler
+"lr.w" = [
+    0x1000202F = [X, RefBase] => [R(7), R(15)], A;
+]
+"sc.w" = [
+    0x1800202F = [X, X, RefBase] => [R(7), R(20), R(15)], A;
+]
+"amoswap.w" = [
+    0x0800202F = [X, X, RefBase] => [R(7), R(20), R(15)], A;
+]
+"amoadd.w" = [
+    0x0000202F = [X, X, RefBase] => [R(7), R(20), R(15)], A;
+]
+"amoxor.w" = [
+    0x2000202F = [X, X, RefBase] => [R(7), R(20), R(15)], A;
+]
+"amoand.w" = [
+    0x6000202F = [X, X, RefBase] => [R(7), R(20), R(15)], A;
+]
+"amoor.w" = [
+    0x4000202F = [X, X, RefBase] => [R(7), R(20), R(15)], A;
+]
+"amomin.w" = [
+    0x8000202F = [X, X, RefBase] => [R(7), R(20), R(15)], A;
+]
+"amomax.w" = [
+    0xA000202F = [X, X, RefBase] => [R(7), R(20), R(15)], A;
+]
+"amominu.w" = [
+    0xC000202F = [X, X, RefBase] => [R(7), R(20), R(15)], A;
+]
+"amomaxu.w" = [
+    0xE000202F = [X, X, RefBase] => [R(7), R(20), R(15)], A;
+]
+"lr.d" = [
+    0x1000302F = [X, RefBase] => [R(7), R(15)], A;
+]
+"sc.d" = [
+    0x1800302F = [X, X, RefBase] => [R(7), R(20), R(15)], A;
+]
+"amoswap.d" = [
+    0x0800302F = [X, X, RefBase] => [R(7), R(20), R(15)], A;
+]
+"amoadd.d" = [
+    0x0000302F = [X, X, RefBase] => [R(7), R(20), R(15)], A;
+]
+"amoxor.d" = [
+    0x2000302F = [X, X, RefBase] => [R(7), R(20), R(15)], A;
+]
+"amoand.d" = [
+    0x6000302F = [X, X, RefBase] => [R(7), R(20), R(15)], A;
+]
+"amoor.d" = [
+    0x4000302F = [X, X, RefBase] => [R(7), R(20), R(15)], A;
+]
+"amomin.d" = [
+    0x8000302F = [X, X, RefBase] => [R(7), R(20), R(15)], A;
+]
+"amomax.d" = [
+    0xA000302F = [X, X, RefBase] => [R(7), R(20), R(15)], A;
+]
+"amominu.d" = [
+    0xC000302F = [X, X, RefBase] => [R(7), R(20), R(15)], A;
+]
+"amomaxu.d" = [
+    0xE000302F = [X, X, RefBase] => [R(7), R(20), R(15)], A;
+]
+// Zicsr
+"csrrw" = [
+    0x00001073 = [X, Csr, X] => [R(7), Csr, R(15)], ZICSR;
+]
+"csrrs" = [
+    0x00002073 = [X, Csr, X] => [R(7), Csr, R(15)], ZICSR;
+]
+"csrrc" = [
+    0x00003073 = [X, Csr, X] => [R(7), Csr, R(15)], ZICSR;
+]
+"csrrwi" = [
+    0x00005073 = [X, Csr, Imm] => [R(7), Csr, Imm(&CSR_UIMM)], ZICSR;
+]
+"csrrsi" = [
+    0x00006073 = [X, Csr, Imm] => [R(7), Csr, Imm(&CSR_UIMM)], ZICSR;
+]
+"csrrci" = [
+    0x00007073 = [X, Csr, Imm] => [R(7), Csr, Imm(&CSR_UIMM)], ZICSR;
+]
+"csrr" = [
+    0x00002073 = [X, Csr] => [R(7), Csr], ZICSR;
+]
+"csrw" = [
+    0x00001073 = [Csr, X] => [Csr, R(15)], ZICSR;
+]
+"csrs" = [
+    0x00002073 = [Csr, X] => [Csr, R(15)], ZICSR;
+]
+"csrc" = [
+    0x00003073 = [Csr, X] => [Csr, R(15)], ZICSR;
+]
+"csrwi" = [
+    0x00005073 = [Csr, Imm] => [Csr, Imm(&CSR_UIMM)], ZICSR;
+]
+"csrsi" = [
+    0x00006073 = [Csr, Imm] => [Csr, Imm(&CSR_UIMM)], ZICSR;
+]
+"csrci" = [
+    0x00007073 = [Csr, Imm] => [Csr, Imm(&CSR_UIMM)], ZICSR;
+]
+"rdcycle" = [
+    0xC0002073 = [X] => [R(7)], ZICSR;
+]
+"rdtime" = [
+    0xC0102073 = [X] => [R(7)], ZICSR;
+]
+"rdinstret" = [
+    0xC0202073 = [X] => [R(7)], ZICSR;
+]
+// F
+"flw" = [
+    0x00002007 = [F, Ref] => [R(7), R(15), Imm(&I_IMM)], F;
+]
+"fsw" = [
+    0x00002027 = [F, Ref] => [R(20), R(15), Imm(&S_IMM)], F;
+]
+"fmadd.s" = [
+    0x00000043 = [F, F, F, F, End, Rm] => [R(7), R(15), R(20), R(27), Rm(12)], F;
+]
+"fmsub.s" = [
+    0x00000047 = [F, F, F, F, End, Rm] => [R(7), R(15), R(20), R(27), Rm(12)], F;
+]
+"fnmsub.s" = [
+    0x0000004B = [F, F, F, F, End, Rm] => [R(7), R(15), R(20), R(27), Rm(12)], F;
+]
+"fnmadd.s" = [
+    0x0000004F = [F, F, F, F, End, Rm] => [R(7), R(15), R(20), R(27), Rm(12)], F;
+]
+"fadd.s" = [
+    0x00000053 = [F, F, F, End, Rm] => [R(7), R(15), R(20), Rm(12)], F;
+]
+"fsub.s" = [
+    0x08000053 = [F, F, F, End, Rm] => [R(7), R(15), R(20), Rm(12)], F;
+]
+"fmul.s" = [
+    0x10000053 = [F, F, F, End, Rm] => [R(7), R(15), R(20), Rm(12)], F;
+]
+"fdiv.s" = [
+    0x18000053 = [F, F, F, End, Rm] => [R(7), R(15), R(20), Rm(12)], F;
+]
+"fsqrt.s" = [
+    0x58000053 = [F, F, End, Rm] => [R(7), R(15), Rm(12)], F;
+]
+"fsgnj.s" = [
+    0x20000053 = [F, F, F] => [R(7), R(15), R(20)], F;
+]
+"fsgnjn.s" = [
+    0x20001053 = [F, F, F] => [R(7), R(15), R(20)], F;
+]
+"fsgnjx.s" = [
+    0x20002053 = [F, F, F] => [R(7), R(15), R(20)], F;
+]
+"fmin.s" = [
+    0x28000053 = [F, F, F] => [R(7), R(15), R(20)], F;
+]
+"fmax.s" = [
+    0x28001053 = [F, F, F] => [R(7), R(15), R(20)], F;
+]
+"feq.s" = [
+    0xA0002053 = [X, F, F] => [R(7), R(15), R(20)], F;
+]
+"flt.s" = [
+    0xA0001053 = [X, F, F] => [R(7), R(15), R(20)], F;
+]
+"fle.s" = [
+    0xA0000053 = [X, F, F] => [R(7), R(15), R(20)], F;
+]
+"fclass.s" = [
+    0xE0001053 = [X, F] => [R(7), R(15)], F;
+]
+"fcvt.w.s" = [
+    0xC0000053 = [X, F, End, Rm] => [R(7), R(15), Rm(12)], F;
+]
+"fcvt.s.w" = [
+    0xD0000053 = [F, X, End, Rm] => [R(7), R(15), Rm(12)], F;
+]
+"fcvt.wu.s" = [
+    0xC0100053 = [X, F, End, Rm] => [R(7), R(15), Rm(12)], F;
+]
+"fcvt.s.wu" = [
+    0xD0100053 = [F, X, End, Rm] => [R(7), R(15), Rm(12)], F;
+]
+"fcvt.l.s" = [
+    0xC0200053 = [X, F, End, Rm] => [R(7), R(15), Rm(12)], F;
+]
+"fcvt.s.l" = [
+    0xD0200053 = [F, X, End, Rm] => [R(7), R(15), Rm(12)], F;
+]
+"fcvt.lu.s" = [
+    0xC0300053 = [X, F, End, Rm] => [R(7), R(15), Rm(12)], F;
+]
+"fcvt.s.lu" = [
+    0xD0300053 = [F, X, End, Rm] => [R(7), R(15), Rm(12)], F;
+]
+"fmv.x.w" = [
+    0xE0000053 = [X, F] => [R(7), R(15)], F;
+]
+"fmv.w.x" = [
+    0xF0000053 = [F, X] => [R(7), R(15)], F;
+]
+"fmv.s" = [
+    0x20000053 = [F, F] => [R(7), R(15), C, R(20)], F;
+]
+"fneg.s" = [
+    0x20001053 = [F, F] => [R(7), R(15), C, R(20)], F;
+]
+"fabs.s" = [
+    0x20002053 = [F, F] => [R(7), R(15), C, R(20)], F;
+]
+// D
+"fld" = [
+    0x00003007 = [F, Ref] => [R(7), R(15), Imm(&I_IMM)], D;
+]
+"fsd" = [
+    0x00003027 = [F, Ref] => [R(20), R(15), Imm(&S_IMM)], D;
+]
+"fmadd.d" = [
+    0x02000043 = [F, F, F, F, End, Rm] => [R(7), R(15), R(20), R(27), Rm(12)], D;
+]
+"fmsub.d" = [
+    0x02000047 = [F, F, F, F, End, Rm] => [R(7), R(15), R(20), R(27), Rm(12)], D;
+]
+"fnmsub.d" = [
+    0x0200004B = [F, F, F, F, End, Rm] => [R(7), R(15), R(20), R(27), Rm(12)], D;
+]
+"fnmadd.d" = [
+    0x0200004F = [F, F, F, F, End, Rm] => [R(7), R(15), R(20), R(27), Rm(12)], D;
+]
+"fadd.d" = [
+    0x02000053 = [F, F, F, End, Rm] => [R(7), R(15), R(20), Rm(12)], D;
+]
+"fsub.d" = [
+    0x0A000053 = [F, F, F, End, Rm] => [R(7), R(15), R(20), Rm(12)], D;
+]
+"fmul.d" = [
+    0x12000053 = [F, F, F, End, Rm] => [R(7), R(15), R(20), Rm(12)], D;
+]
+"fdiv.d" = [
+    0x1A000053 = [F, F, F, End, Rm] => [R(7), R(15), R(20), Rm(12)], D;
+]
+"fsqrt.d" = [
+    0x5A000053 = [F, F, End, Rm] => [R(7), R(15), Rm(12)], D;
+]
+"fsgnj.d" = [
+    0x22000053 = [F, F, F] => [R(7), R(15), R(20)], D;
+]
+"fsgnjn.d" = [
+    0x22001053 = [F, F, F] => [R(7), R(15), R(20)], D;
+]
+"fsgnjx.d" = [
+    0x22002053 = [F, F, F] => [R(7), R(15), R(20)], D;
+]
+"fmin.d" = [
+    0x2A000053 = [F, F, F] => [R(7), R(15), R(20)], D;
+]
+"fmax.d" = [
+    0x2A001053 = [F, F, F] => [R(7), R(15), R(20)], D;
+]
+"feq.d" = [
+    0xA2002053 = [X, F, F] => [R(7), R(15), R(20)], D;
+]
+"flt.d" = [
+    0xA2001053 = [X, F, F] => [R(7), R(15), R(20)], D;
+]
+"fle.d" = [
+    0xA2000053 = [X, F, F] => [R(7), R(15), R(20)], D;
+]
+"fclass.d" = [
+    0xE2001053 = [X, F] => [R(7), R(15)], D;
+]
+"fcvt.w.d" = [
+    0xC2000053 = [X, F, End, Rm] => [R(7), R(15), Rm(12)], D;
+]
+"fcvt.d.w" = [
+    0xD2000053 = [F, X] => [R(7), R(15)], D;
+]
+"fcvt.wu.d" = [
+    0xC2100053 = [X, F, End, Rm] => [R(7), R(15), Rm(12)], D;
+]
+"fcvt.d.wu" = [
+    0xD2100053 = [F, X] => [R(7), R(15)], D;
+]
+"fcvt.l.d" = [
+    0xC2200053 = [X, F, End, Rm] => [R(7), R(15), Rm(12)], D;
+]
+"fcvt.d.l" = [
+    0xD2200053 = [F, X, End, Rm] => [R(7), R(15), Rm(12)], D;
+]
+"fcvt.lu.d" = [
+    0xC2300053 = [X, F, End, Rm] => [R(7), R(15), Rm(12)], D;
+]
+"fcvt.d.lu" = [
+    0xD2300053 = [F, X, End, Rm] => [R(7), R(15), Rm(12)], D;
+]
+"fmv.x.d" = [
+    0xE2000053 = [X, F] => [R(7), R(15)], D;
+]
+"fmv.d.x" = [
+    0xF2000053 = [F, X] => [R(7), R(15)], D;
+]
+"fcvt.s.d" = [
+    0x40100053 = [F, F, End, Rm] => [R(7), R(15), Rm(12)], D;
+]
+"fcvt.d.s" = [
+    0x42000053 = [F, F] => [R(7), R(15)], D;
+]
+"fmv.d" = [
+    0x22000053 = [F, F] => [R(7), R(15), C, R(20)], D;
+]
+"fneg.d" = [
+    0x22001053 = [F, F] => [R(7), R(15), C, R(20)], D;
+]
+"fabs.d" = [
+    0x22002053 = [F, F] => [R(7), R(15), C, R(20)], D;
+]
+// Zba
+"sh1add" = [
+    0x20002033 = [X, X, X] => [R(7), R(15), R(20)], ZBA;
+]
+"sh1add.uw" = [
+    0x2000203B = [X, X, X] => [R(7), R(15), R(20)], ZBA;
+]
+"sh2add" = [
+    0x20004033 = [X, X, X] => [R(7), R(15), R(20)], ZBA;
+]
+"sh2add.uw" = [
+    0x2000403B = [X, X, X] => [R(7), R(15), R(20)], ZBA;
+]
+"sh3add" = [
+    0x20006033 = [X, X, X] => [R(7), R(15), R(20)], ZBA;
+]
+"sh3add.uw" = [
+    0x2000603B = [X, X, X] => [R(7), R(15), R(20)], ZBA;
+]
+"add.uw" = [
+    0x0800003B = [X, X, X] => [R(7), R(15), R(20)], ZBA;
+]
+"slli.uw" = [
+    0x0800101B = [X, X, Imm] => [R(7), R(15), Imm(&SHAMT6)], ZBA;
+]
+"zext.w" = [
+    0x0800003B = [X, X] => [R(7), R(15)], ZBA;
+]
+// Zbb
+"andn" = [
+    0x40007033 = [X, X, X] => [R(7), R(15), R(20)], ZBB;
+]
+"orn" = [
+    0x40006033 = [X, X, X] => [R(7), R(15), R(20)], ZBB;
+]
+"xnor" = [
+    0x40004033 = [X, X, X] => [R(7), R(15), R(20)], ZBB;
+]
+"max" = [
+    0x0A006033 = [X, X, X] => [R(7), R(15), R(20)], ZBB;
+]
+"maxu" = [
+    0x0A007033 = [X, X, X] => [R(7), R(15), R(20)], ZBB;
+]
+"min" = [
+    0x0A004033 = [X, X, X] => [R(7), R(15), R(20)], ZBB;
+]
+"minu" = [
+    0x0A005033 = [X, X, X] => [R(7), R(15), R(20)], ZBB;
+]
+"rol" = [
+    0x60001033 = [X, X, X] => [R(7), R(15), R(20)], ZBB;
+]
+"ror" = [
+    0x60005033 = [X, X, X] => [R(7), R(15), R(20)], ZBB;
+]
+"rolw" = [
+    0x6000103B = [X, X, X] => [R(7), R(15), R(20)], ZBB;
+]
+"rorw" = [
+    0x6000503B = [X, X, X] => [R(7), R(15), R(20)], ZBB;
+]
+"clz" = [
+    0x60001013 = [X, X] => [R(7), R(15)], ZBB;
+]
+"ctz" = [
+    0x60101013 = [X, X] => [R(7), R(15)], ZBB;
+]
+"cpop" = [
+    0x60201013 = [X, X] => [R(7), R(15)], ZBB;
+]
+"clzw" = [
+    0x6000101B = [X, X] => [R(7), R(15)], ZBB;
+]
+"ctzw" = [
+    0x6010101B = [X, X] => [R(7), R(15)], ZBB;
+]
+"cpopw" = [
+    0x6020101B = [X, X] => [R(7), R(15)], ZBB;
+]
+"sext.b" = [
+    0x60401013 = [X, X] => [R(7), R(15)], ZBB;
+]
+"sext.h" = [
+    0x60501013 = [X, X] => [R(7), R(15)], ZBB;
+]
+"zext.h" = [
+    0x0800403B = [X, X] => [R(7), R(15)], ZBB;
+]
+"orc.b" = [
+    0x28705013 = [X, X] => [R(7), R(15)], ZBB;
+]
+"rev8" = [
+    0x6B805013 = [X, X] => [R(7), R(15)], ZBB;
+]
+"rori" = [
+    0x60005013 = [X, X, Imm] => [R(7), R(15), Imm(&SHAMT6)], ZBB;
+]
+"roriw" = [
+    0x6000501B = [X, X, Imm] => [R(7), R(15), Imm(&SHAMT5)], ZBB;
+]
+// C, quadrant 0
+"c.addi4spn" = [
+    0x0000 = [X, X, Imm] => [RC(2), RSp, Imm(&CIW_IMM)], C;
+]
+"c.fld" = [
+    0x2000 = [F, Ref] => [RC(2), RC(7), Imm(&CLD_IMM)], C | D;
+]
+"c.lw" = [
+    0x4000 = [X, Ref] => [RC(2), RC(7), Imm(&CLW_IMM)], C;
+]
+"c.ld" = [
+    0x6000 = [X, Ref] => [RC(2), RC(7), Imm(&CLD_IMM)], C;
+]
+"c.fsd" = [
+    0xA000 = [F, Ref] => [RC(2), RC(7), Imm(&CLD_IMM)], C | D;
+]
+"c.sw" = [
+    0xC000 = [X, Ref] => [RC(2), RC(7), Imm(&CLW_IMM)], C;
+]
+"c.sd" = [
+    0xE000 = [X, Ref] => [RC(2), RC(7), Imm(&CLD_IMM)], C;
+]
+// C, quadrant 1
+"c.nop" = [
+    0x0001 = [] => [], C;
+]
+"c.addi" = [
+    0x0001 = [X, Imm] => [RNz(7), Imm(&CI_NZIMM)], C;
+]
+"c.addiw" = [
+    0x2001 = [X, Imm] => [RNz(7), Imm(&CI_IMM)], C;
+]
+"c.li" = [
+    0x4001 = [X, Imm] => [R(7), Imm(&CI_IMM)], C;
+]
+"c.addi16sp" = [
+    0x6101 = [X, Imm] => [RSp, Imm(&CI16SP_IMM)], C;
+]
+"c.lui" = [
+    0x6001 = [X, Imm] => [RNzSp(7), Imm(&CI_NZIMM)], C;
+]
+"c.srli" = [
+    0x8001 = [X, Imm] => [RC(7), Imm(&CI_SHAMT)], C;
+]
+"c.srai" = [
+    0x8401 = [X, Imm] => [RC(7), Imm(&CI_SHAMT)], C;
+]
+"c.andi" = [
+    0x8801 = [X, Imm] => [RC(7), Imm(&CI_IMM)], C;
+]
+"c.sub" = [
+    0x8C01 = [X, X] => [RC(7), RC(2)], C;
+]
+"c.xor" = [
+    0x8C21 = [X, X] => [RC(7), RC(2)], C;
+]
+"c.or" = [
+    0x8C41 = [X, X] => [RC(7), RC(2)], C;
+]
+"c.and" = [
+    0x8C61 = [X, X] => [RC(7), RC(2)], C;
+]
+"c.subw" = [
+    0x9C01 = [X, X] => [RC(7), RC(2)], C;
+]
+"c.addw" = [
+    0x9C21 = [X, X] => [RC(7), RC(2)], C;
+]
+"c.j" = [
+    0xA001 = [Offset] => [Offset(C_J)], C;
+]
+"c.beqz" = [
+    0xC001 = [X, Offset] => [RC(7), Offset(C_BRANCH)], C;
+]
+"c.bnez" = [
+    0xE001 = [X, Offset] => [RC(7), Offset(C_BRANCH)], C;
+]
+// C, quadrant 2
+"c.slli" = [
+    0x0002 = [X, Imm] => [RNz(7), Imm(&CI_SHAMT)], C;
+]
+"c.fldsp" = [
+    0x2002 = [F, Ref] => [R(7), RSp, Imm(&CLDSP_IMM)], C | D;
+]
+"c.lwsp" = [
+    0x4002 = [X, Ref] => [RNz(7), RSp, Imm(&CLWSP_IMM)], C;
+]
+"c.ldsp" = [
+    0x6002 = [X, Ref] => [RNz(7), RSp, Imm(&CLDSP_IMM)], C;
+]
+"c.jr" = [
+    0x8002 = [X] => [RNz(7)], C;
+]
+"c.mv" = [
+    0x8002 = [X, X] => [RNz(7), RNz(2)], C;
+]
+"c.ebreak" = [
+    0x9002 = [] => [], C;
+]
+"c.jalr" = [
+    0x9002 = [X] => [RNz(7)], C;
+]
+"c.add" = [
+    0x9002 = [X, X] => [RNz(7), RNz(2)], C;
+]
+"c.fsdsp" = [
+    0xA002 = [F, Ref] => [R(2), RSp, Imm(&CSDSP_IMM)], C | D;
+]
+"c.swsp" = [
+    0xC002 = [X, Ref] => [R(2), RSp, Imm(&CSWSP_IMM)], C;
+]
+"c.sdsp" = [
+    0xE002 = [X, Ref] => [R(2), RSp, Imm(&CSDSP_IMM)], C;
+]
+
+)
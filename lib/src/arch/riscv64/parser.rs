@@ -0,0 +1,213 @@
+//! Common map and table definitions useful for parsers.
+use lazy_static::lazy_static;
+
+use super::ast::{RegId, RegFamily};
+
+use self::RegId::*;
+
+use crate::collections::HashMap;
+
+/// All riscv64 register names, both the architectural and the ABI names.
+pub const RISCV_REGISTERS: &[(&str, RegId)] = &[
+    ("x0",   X0),
+    ("x1",   X1),
+    ("x2",   X2),
+    ("x3",   X3),
+    ("x4",   X4),
+    ("x5",   X5),
+    ("x6",   X6),
+    ("x7",   X7),
+    ("x8",   X8),
+    ("x9",   X9),
+    ("x10",  X10),
+    ("x11",  X11),
+    ("x12",  X12),
+    ("x13",  X13),
+    ("x14",  X14),
+    ("x15",  X15),
+    ("x16",  X16),
+    ("x17",  X17),
+    ("x18",  X18),
+    ("x19",  X19),
+    ("x20",  X20),
+    ("x21",  X21),
+    ("x22",  X22),
+    ("x23",  X23),
+    ("x24",  X24),
+    ("x25",  X25),
+    ("x26",  X26),
+    ("x27",  X27),
+    ("x28",  X28),
+    ("x29",  X29),
+    ("x30",  X30),
+    ("x31",  X31),
+
+    ("zero", X0),
+    ("ra",   X1),
+    ("sp",   X2),
+    ("gp",   X3),
+    ("tp",   X4),
+    ("t0",   X5),
+    ("t1",   X6),
+    ("t2",   X7),
+    ("s0",   X8),
+    ("s1",   X9),
+    ("a0",   X10),
+    ("a1",   X11),
+    ("a2",   X12),
+    ("a3",   X13),
+    ("a4",   X14),
+    ("a5",   X15),
+    ("a6",   X16),
+    ("a7",   X17),
+    ("s2",   X18),
+    ("s3",   X19),
+    ("s4",   X20),
+    ("s5",   X21),
+    ("s6",   X22),
+    ("s7",   X23),
+    ("s8",   X24),
+    ("s9",   X25),
+    ("s10",  X26),
+    ("s11",  X27),
+    ("t3",   X28),
+    ("t4",   X29),
+    ("t5",   X30),
+    ("t6",   X31),
+    ("fp",   X8),
+
+    ("f0",   F0),
+    ("f1",   F1),
+    ("f2",   F2),
+    ("f3",   F3),
+    ("f4",   F4),
+    ("f5",   F5),
+    ("f6",   F6),
+    ("f7",   F7),
+    ("f8",   F8),
+    ("f9",   F9),
+    ("f10",  F10),
+    ("f11",  F11),
+    ("f12",  F12),
+    ("f13",  F13),
+    ("f14",  F14),
+    ("f15",  F15),
+    ("f16",  F16),
+    ("f17",  F17),
+    ("f18",  F18),
+    ("f19",  F19),
+    ("f20",  F20),
+    ("f21",  F21),
+    ("f22",  F22),
+    ("f23",  F23),
+    ("f24",  F24),
+    ("f25",  F25),
+    ("f26",  F26),
+    ("f27",  F27),
+    ("f28",  F28),
+    ("f29",  F29),
+    ("f30",  F30),
+    ("f31",  F31),
+
+    ("ft0",  F0),
+    ("ft1",  F1),
+    ("ft2",  F2),
+    ("ft3",  F3),
+    ("ft4",  F4),
+    ("ft5",  F5),
+    ("ft6",  F6),
+    ("ft7",  F7),
+    ("fs0",  F8),
+    ("fs1",  F9),
+    ("fa0",  F10),
+    ("fa1",  F11),
+    ("fa2",  F12),
+    ("fa3",  F13),
+    ("fa4",  F14),
+    ("fa5",  F15),
+    ("fa6",  F16),
+    ("fa7",  F17),
+    ("fs2",  F18),
+    ("fs3",  F19),
+    ("fs4",  F20),
+    ("fs5",  F21),
+    ("fs6",  F22),
+    ("fs7",  F23),
+    ("fs8",  F24),
+    ("fs9",  F25),
+    ("fs10", F26),
+    ("fs11", F27),
+    ("ft8",  F28),
+    ("ft9",  F29),
+    ("ft10", F30),
+    ("ft11", F31),
+];
+
+/// The register family prefixes used for dynamic registers.
+pub const RISCV_FAMILIES: &[(&str, RegFamily)] = &[
+    ("X", RegFamily::INTEGER),
+    ("F", RegFamily::FP),
+];
+
+/// Names of the standard control and status registers.
+pub const RISCV_CSRS: &[(&str, u16)] = &[
+    ("fflags",      0x001),
+    ("frm",         0x002),
+    ("fcsr",        0x003),
+    ("cycle",       0xC00),
+    ("time",        0xC01),
+    ("instret",     0xC02),
+    ("sstatus",     0x100),
+    ("sie",         0x104),
+    ("stvec",       0x105),
+    ("scounteren",  0x106),
+    ("sscratch",    0x140),
+    ("sepc",        0x141),
+    ("scause",      0x142),
+    ("stval",       0x143),
+    ("sip",         0x144),
+    ("satp",        0x180),
+    ("mvendorid",   0xF11),
+    ("marchid",     0xF12),
+    ("mimpid",      0xF13),
+    ("mhartid",     0xF14),
+    ("mstatus",     0x300),
+    ("misa",        0x301),
+    ("medeleg",     0x302),
+    ("mideleg",     0x303),
+    ("mie",         0x304),
+    ("mtvec",       0x305),
+    ("mcounteren",  0x306),
+    ("mscratch",    0x340),
+    ("mepc",        0x341),
+    ("mcause",      0x342),
+    ("mtval",       0x343),
+    ("mip",         0x344),
+    ("mcycle",      0xB00),
+    ("minstret",    0xB02),
+];
+
+/// The floating point rounding modes.
+pub const RISCV_ROUNDING_MODES: &[(&str, u8)] = &[
+    ("rne", 0b000),
+    ("rtz", 0b001),
+    ("rdn", 0b010),
+    ("rup", 0b011),
+    ("rmm", 0b100),
+    ("dyn", 0b111),
+];
+
+lazy_static!{
+    pub static ref RISCV_REGISTER_MAP: HashMap<&'static str, RegId> = {
+        RISCV_REGISTERS.iter().cloned().collect()
+    };
+    pub static ref RISCV_FAMILIES_MAP: HashMap<&'static str, RegFamily> = {
+        RISCV_FAMILIES.iter().cloned().collect()
+    };
+    pub static ref RISCV_CSR_MAP: HashMap<&'static str, u16> = {
+        RISCV_CSRS.iter().cloned().collect()
+    };
+    pub static ref RISCV_ROUNDING_MODE_MAP: HashMap<&'static str, u8> = {
+        RISCV_ROUNDING_MODES.iter().cloned().collect()
+    };
+}
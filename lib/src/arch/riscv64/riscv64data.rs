@@ -0,0 +1,289 @@
+use crate::collections::{HashMap, hash_map};
+use core::fmt::{self, Display};
+
+use lazy_static::lazy_static;
+use bitflags::bitflags;
+
+use alloc::vec::Vec;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Matcher {
+    // integer registers
+    X,
+    // floating point registers
+    F,
+
+    // immediate
+    Imm,
+
+    // jump offsets
+    Offset,
+
+    // references
+    /// offset(base), with an optional offset
+    Ref,
+    /// (base), without offset
+    RefBase,
+
+    // literals
+    /// a control and status register, either by name or number
+    Csr,
+    /// a fence predecessor/successor set, i.e. some combination of "iorw"
+    Fence,
+    /// a rounding mode
+    Rm,
+
+    // possible op mnemnonic end (everything after this point uses the default encoding)
+    End,
+}
+
+/// The layout of an immediate field within an instruction.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ImmField {
+    /// If the immediate is interpreted as a signed value.
+    pub signed: bool,
+    /// The amount of significant bits of the immediate.
+    pub bits: u8,
+    /// The amount of low bits that have to be zero and are not encoded.
+    pub scale: u8,
+    /// If zero is not an allowed value.
+    pub nonzero: bool,
+    /// Slices of the immediate that are scattered over the instruction, as (source bit, length, destination bit).
+    pub fields: &'static [(u8, u8, u8)],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    // commands that advance the argument pointer
+    R(u8), // encode a register, or reference base, into a 5-bit bitfield.
+    RNz(u8), // same as R, but does not allow x0.
+    RC(u8), // encode a register in the range x8-x15 / f8-f15 into a 3-bit bitfield.
+    RNzSp(u8), // same as R, but does not allow x0 or x2.
+    RSp, // checks that the register is sp. Encodes nothing.
+
+    Imm(&'static ImmField), // encodes an immediate according to the given field layout
+
+    Csr, // encodes a csr name or number into bits 20-31
+    Fence(u8), // encodes a fence set into a 4-bit bitfield
+    Rm(u8), // encodes a rounding mode into a 3-bit bitfield, defaulting to dyn
+
+    // Offsets
+    Offset(Relocation),
+
+    // special commands
+    C, // moves the argument pointer back.
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum Relocation {
+    // beq, bne, blt, bge, bltu, bgeu: 13 bits, halfword aligned
+    B = 0,
+    // jal: 21 bits, halfword aligned
+    J = 1,
+    // auipc + jalr: 32 bits, split into hi20 and lo12
+    AUIPC_JALR = 2,
+    // auipc + addi: 32 bits, split into hi20 and lo12
+    AUIPC_ADDI = 3,
+    // c.beqz, c.bnez: 9 bits, halfword aligned
+    C_BRANCH = 4,
+    // c.j: 12 bits, halfword aligned
+    C_J = 5,
+    // 8-bit literal
+    LITERAL8 = 6,
+    // 16-bit literal
+    LITERAL16 = 7,
+    // 32-bit literal
+    LITERAL32 = 9,
+    // 64-bit literal
+    LITERAL64 = 13,
+}
+
+impl Relocation {
+    pub fn to_id(self) -> u8 {
+        self as u8
+    }
+
+    /// The immediate field a static offset gets encoded into.
+    pub fn field(self) -> Option<&'static ImmField> {
+        match self {
+            Relocation::B => Some(&B_IMM),
+            Relocation::J => Some(&J_IMM),
+            Relocation::C_BRANCH => Some(&CB_IMM),
+            Relocation::C_J => Some(&CJ_IMM),
+            _ => None
+        }
+    }
+}
+
+bitflags! {
+    pub struct Features: u32 {
+        const I        = 0x0000_0001;
+        const M        = 0x0000_0002;
+        const A        = 0x0000_0004;
+        const F        = 0x0000_0008;
+        const D        = 0x0000_0010;
+        const C        = 0x0000_0020;
+        const ZBA      = 0x0000_0040;
+        const ZBB      = 0x0000_0080;
+        const ZICSR    = 0x0000_0100;
+        const ZIFENCEI = 0x0000_0200;
+    }
+}
+
+impl Features {
+    const fn make(bits: u32) -> Features {
+        Features { bits }
+    }
+
+    pub fn from_name(name: &str) -> Option<Features> {
+        match name {
+            "i" => Some(Features::I),
+            "m" => Some(Features::M),
+            "a" => Some(Features::A),
+            "f" => Some(Features::F),
+            "d" => Some(Features::D),
+            "c" => Some(Features::C),
+            "zba" => Some(Features::ZBA),
+            "zbb" => Some(Features::ZBB),
+            "zicsr" => Some(Features::ZICSR),
+            "zifencei" => Some(Features::ZIFENCEI),
+            _ => None
+        }
+    }
+}
+
+impl Display for Features {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut keys = Vec::new();
+        if self.contains(Features::I) { keys.push("i"); }
+        if self.contains(Features::M) { keys.push("m"); }
+        if self.contains(Features::A) { keys.push("a"); }
+        if self.contains(Features::F) { keys.push("f"); }
+        if self.contains(Features::D) { keys.push("d"); }
+        if self.contains(Features::C) { keys.push("c"); }
+        if self.contains(Features::ZBA) { keys.push("zba"); }
+        if self.contains(Features::ZBB) { keys.push("zbb"); }
+        if self.contains(Features::ZICSR) { keys.push("zicsr"); }
+        if self.contains(Features::ZIFENCEI) { keys.push("zifencei"); }
+        for (i, k) in keys.into_iter().enumerate() {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str(k)?;
+        }
+        Ok(())
+    }
+}
+
+/*
+ * Immediate field layouts
+ */
+
+macro_rules! ImmField {
+    ($signed:expr, $bits:expr, $scale:expr, $nonzero:expr, [ $( ($src:expr, $len:expr, $dst:expr) ),* ]) => {
+        ImmField { signed: $signed, bits: $bits, scale: $scale, nonzero: $nonzero, fields: &[ $( ($src, $len, $dst) ),* ] }
+    }
+}
+
+// base instruction formats
+pub const I_IMM: ImmField = ImmField!(true, 12, 0, false, [(0, 12, 20)]);
+pub const S_IMM: ImmField = ImmField!(true, 12, 0, false, [(0, 5, 7), (5, 7, 25)]);
+pub const B_IMM: ImmField = ImmField!(true, 13, 1, false, [(11, 1, 7), (1, 4, 8), (5, 6, 25), (12, 1, 31)]);
+pub const U_IMM: ImmField = ImmField!(false, 20, 0, false, [(0, 20, 12)]);
+pub const J_IMM: ImmField = ImmField!(true, 21, 1, false, [(12, 8, 12), (11, 1, 20), (1, 10, 21), (20, 1, 31)]);
+pub const SHAMT6: ImmField = ImmField!(false, 6, 0, false, [(0, 6, 20)]);
+pub const SHAMT5: ImmField = ImmField!(false, 5, 0, false, [(0, 5, 20)]);
+pub const CSR_UIMM: ImmField = ImmField!(false, 5, 0, false, [(0, 5, 15)]);
+
+// compressed instruction formats
+pub const CI_IMM: ImmField = ImmField!(true, 6, 0, false, [(5, 1, 12), (0, 5, 2)]);
+pub const CI_NZIMM: ImmField = ImmField!(true, 6, 0, true, [(5, 1, 12), (0, 5, 2)]);
+pub const CI_SHAMT: ImmField = ImmField!(false, 6, 0, true, [(5, 1, 12), (0, 5, 2)]);
+pub const CI16SP_IMM: ImmField = ImmField!(true, 10, 4, true, [(9, 1, 12), (4, 1, 6), (6, 1, 5), (7, 2, 3), (5, 1, 2)]);
+pub const CIW_IMM: ImmField = ImmField!(false, 10, 2, true, [(4, 2, 11), (6, 4, 7), (2, 1, 6), (3, 1, 5)]);
+pub const CLW_IMM: ImmField = ImmField!(false, 7, 2, false, [(3, 3, 10), (2, 1, 6), (6, 1, 5)]);
+pub const CLD_IMM: ImmField = ImmField!(false, 8, 3, false, [(3, 3, 10), (6, 2, 5)]);
+pub const CLWSP_IMM: ImmField = ImmField!(false, 8, 2, false, [(5, 1, 12), (2, 3, 4), (6, 2, 2)]);
+pub const CLDSP_IMM: ImmField = ImmField!(false, 9, 3, false, [(5, 1, 12), (3, 2, 5), (6, 3, 2)]);
+pub const CSWSP_IMM: ImmField = ImmField!(false, 8, 2, false, [(2, 4, 9), (6, 2, 7)]);
+pub const CSDSP_IMM: ImmField = ImmField!(false, 9, 3, false, [(3, 3, 10), (6, 3, 7)]);
+pub const CB_IMM: ImmField = ImmField!(true, 9, 1, false, [(8, 1, 12), (3, 2, 10), (6, 2, 5), (1, 2, 3), (5, 1, 2)]);
+pub const CJ_IMM: ImmField = ImmField!(true, 12, 1, false, [(11, 1, 12), (4, 1, 11), (8, 2, 9), (10, 1, 8), (6, 1, 7), (7, 1, 6), (1, 3, 3), (5, 1, 2)]);
+
+#[derive(Debug, Clone, Copy)]
+pub struct Opdata {
+    /// The base template for the encoding. Compressed instructions only use the low 16 bits.
+    pub base: u32,
+    /// A set of matchers capable of matching the instruction encoding that this instruction represents.
+    pub matchers: &'static [Matcher],
+    /// A sequence of encoder commands that check the matched instruction on validity and whose output gets orred together with the original template at runtime.
+    pub commands: &'static [Command],
+    /// The extension this instruction is part of.
+    pub features: Features,
+}
+
+impl Opdata {
+    /// Returns true if this is a 16-bit compressed instruction.
+    pub fn is_compressed(&self) -> bool {
+        self.base & 0b11 != 0b11
+    }
+}
+
+macro_rules! SingleOp {
+    ( $base:expr, [ $( $matcher:expr ),* ], [ $( $command:expr ),* ], $( $feature:ident )|+ ) => {
+        {
+            const MATCHERS: &'static [Matcher] = {
+                #[allow(unused_imports)]
+                use self::Matcher::*;
+                &[ $(
+                    $matcher
+                ),* ]
+            };
+            const COMMANDS: &'static [Command] = {
+                #[allow(unused_imports)]
+                use self::Command::*;
+                &[ $(
+                    $command
+                ),* ]
+            };
+            Opdata {
+                base: $base,
+                matchers: MATCHERS,
+                commands: COMMANDS,
+                features: Features::make( $( Features::$feature.bits() )|+ ),
+            }
+        }
+    }
+}
+
+macro_rules! Ops {
+    ( $( $name:tt = [ $( $base:tt = [ $( $matcher:expr ),* ] => [ $( $command:expr ),* ], $( $feature:ident )|+ ; )+ ] )* ) => {
+        [ $(
+            (
+                $name,
+                &[ $(
+                    SingleOp!( $base, [ $( $matcher ),* ], [ $( $command ),* ], $( $feature )|+ )
+                ),+ ] as &[_]
+            )
+        ),* ]
+    }
+}
+
+pub fn get_mnemonic_data(name: &str) -> Option<&'static [Opdata]> {
+    OPMAP.get(&name).cloned()
+}
+
+#[allow(dead_code)]
+pub fn mnemnonics() -> hash_map::Keys<'static, &'static str, &'static [Opdata]> {
+    OPMAP.keys()
+}
+
+lazy_static! {
+    static ref OPMAP: HashMap<&'static str, &'static [Opdata]> = {
+        use self::Relocation::*;
+
+        static MAP: &[(&str, &[Opdata])] = &include!("opmap.rs");
+        MAP.iter().cloned().collect()
+    };
+}
@@ -1,6 +1,8 @@
 use crate::common::{Expr, Ident, Jump, Size, Value};
 
-use std::cmp::PartialEq;
+use core::cmp::PartialEq;
+
+use alloc::vec::Vec;
 
 
 /**
@@ -290,7 +292,7 @@ pub enum RawArg {
     Invalid
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum CleanArg {
     // memory reference
     Indirect {
@@ -362,7 +364,7 @@ pub enum Scale {
  * Parsed instruction
  */
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Instruction {
     pub idents: Vec<Ident>
 }
@@ -1,16 +1,24 @@
-use crate::common::{Expr, Ident, Stmt, Size, Jump, JumpKind, NumericRepr, Value};
+use crate::common::{Expr, Stmt, Size, Jump, JumpKind, NumericRepr, Value};
 use crate::arch::{BasicExprBuilderExt, ErrorSpan};
 
-use super::{Context, Error, X86Mode};
+use super::{Context, Error, NameKind, X86Mode};
+use super::explain::{Bits, FieldKind};
 use super::ast::{CleanArg, SizedArg, Instruction, Register, RegKind, RegFamily, RegId};
-use super::x64data::get_mnemnonic_data;
+use super::x64data::{get_mnemnonic_data, mnemnonics};
 use super::x64data::Flags;
 use super::x64data::Features;
 use super::debug::format_opdata_list;
+use super::semantics::Semantics;
+use super::parser::PREFIXES;
 
-use std::mem::swap;
-use std::slice;
-use std::iter;
+use core::mem::swap;
+use core::array;
+use core::slice;
+use core::iter;
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use alloc::format;
 
 /*
  * Instruction encoding data formats
@@ -22,7 +30,9 @@ pub struct Opdata {
     pub ops:   &'static [u8],
     pub reg:   u8,
     pub flags: Flags,
-    pub features: Features
+    pub features: Features,
+    pub signature: u64, // operand kinds accepted by the format string, see format_signature
+    pub semantics: Option<Semantics>, // the effects of the instruction, if they are known
 }
 
 pub struct FormatStringIterator<'a> {
@@ -48,6 +58,164 @@ impl<'a> Iterator for FormatStringIterator<'a> {
     }
 }
 
+/*
+ * Operand signatures. Every operand position gets a 16-bit lane, in which a format string sets
+ * the bit of every kind of operand it accepts at that position, and an instruction sets the bit
+ * of the kind of operand it has there. Positions without an operand are of kind NONE. A format
+ * string can then only match an instruction if the instruction's signature is contained in it,
+ * which is checked before the more expensive match_format_string.
+ */
+
+const KIND_NONE: u64 = 1 << 0;
+const KIND_IMM: u64 = 1 << 1;
+const KIND_JUMP: u64 = 1 << 2;
+const KIND_LEGACY: u64 = 1 << 3;
+const KIND_MMX: u64 = 1 << 4;
+const KIND_XMM: u64 = 1 << 5;
+const KIND_FP: u64 = 1 << 6;
+const KIND_SEGMENT: u64 = 1 << 7;
+const KIND_CONTROL: u64 = 1 << 8;
+const KIND_DEBUG: u64 = 1 << 9;
+const KIND_BOUND: u64 = 1 << 10;
+const KIND_MEM: u64 = 1 << 11;
+const KIND_VSIB: u64 = 1 << 12;
+// registers no format string accepts, like rip
+const KIND_OTHER: u64 = 1 << 13;
+
+const KIND_LANE_BITS: usize = 16;
+
+/// The operand kinds accepted by a format string.
+pub const fn format_signature(fmtstr: &[u8]) -> u64 {
+    assert!(fmtstr.len() <= MAX_OPERANDS * 2, "Invalid format string data");
+
+    let mut signature = 0;
+    let mut i = 0;
+    while i < MAX_OPERANDS {
+        let kinds = if i * 2 < fmtstr.len() {
+            match fmtstr[i * 2] {
+                b'i' => KIND_IMM,
+                b'o' => KIND_IMM | KIND_JUMP,
+                b'm' => KIND_MEM,
+                b'k' | b'l' => KIND_VSIB,
+                b'r' | b'A' ..= b'P' => KIND_LEGACY,
+                b'v' => KIND_LEGACY | KIND_MEM,
+                b'x' => KIND_MMX,
+                b'u' => KIND_MMX | KIND_MEM,
+                b'y' => KIND_XMM,
+                b'w' => KIND_XMM | KIND_MEM,
+                b'f' | b'X' => KIND_FP,
+                b's' | b'Q' ..= b'V' => KIND_SEGMENT,
+                b'c' | b'W' => KIND_CONTROL,
+                b'd' => KIND_DEBUG,
+                b'b' => KIND_BOUND,
+                _ => panic!("Invalid format string data"),
+            }
+        } else {
+            KIND_NONE
+        };
+        signature |= kinds << (i * KIND_LANE_BITS);
+        i += 1;
+    }
+    signature
+}
+
+/// The operand kinds of an instruction, in the same layout as `format_signature`.
+fn operand_signature(args: &Operands<CleanArg>) -> u64 {
+    let mut signature = 0;
+    for i in 0 .. MAX_OPERANDS {
+        let kind = match args.items[i] {
+            None => KIND_NONE,
+            Some(CleanArg::Immediate { .. }) => KIND_IMM,
+            Some(CleanArg::JumpTarget { .. }) => KIND_JUMP,
+            Some(CleanArg::IndirectJumpTarget { .. }) => KIND_MEM,
+            Some(CleanArg::Indirect { index: Some((ref index, _, _)), .. })
+                if index.kind.family() == RegFamily::XMM => KIND_VSIB,
+            Some(CleanArg::Indirect { .. }) => KIND_MEM,
+            Some(CleanArg::Direct { ref reg }) => match reg.kind.family() {
+                RegFamily::LEGACY |
+                RegFamily::HIGHBYTE => KIND_LEGACY,
+                RegFamily::MMX => KIND_MMX,
+                RegFamily::XMM => KIND_XMM,
+                RegFamily::FP => KIND_FP,
+                RegFamily::SEGMENT => KIND_SEGMENT,
+                RegFamily::CONTROL => KIND_CONTROL,
+                RegFamily::DEBUG => KIND_DEBUG,
+                RegFamily::BOUND => KIND_BOUND,
+                RegFamily::RIP => KIND_OTHER,
+            },
+        };
+        signature |= kind << (i * KIND_LANE_BITS);
+    }
+    signature
+}
+
+/// The most operands any instruction takes.
+pub(super) const MAX_OPERANDS: usize = 4;
+
+/// The operands of an instruction while it is being compiled. They're stored inline, as
+/// compiling an instruction with only static operands shouldn't allocate.
+pub(super) struct Operands<T> {
+    items: [Option<T>; MAX_OPERANDS],
+    len: usize,
+}
+
+impl<T> Operands<T> {
+    pub fn new() -> Operands<T> {
+        Operands { items: [None, None, None, None], len: 0 }
+    }
+
+    pub fn push(&mut self, item: T) {
+        assert!(self.len < MAX_OPERANDS, "too many arguments");
+        self.items[self.len] = Some(item);
+        self.len += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=&T> {
+        self.items[.. self.len].iter().map(|item| item.as_ref().unwrap())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item=&mut T> {
+        self.items[.. self.len].iter_mut().map(|item| item.as_mut().unwrap())
+    }
+
+    pub fn remove_first(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        let first = self.items[0].take();
+        self.items.rotate_left(1);
+        self.len -= 1;
+        first
+    }
+}
+
+impl<T> iter::FromIterator<T> for Operands<T> {
+    fn from_iter<I: IntoIterator<Item=T>>(iter: I) -> Operands<T> {
+        let mut operands = Operands::new();
+        for item in iter {
+            operands.push(item);
+        }
+        operands
+    }
+}
+
+impl<T> IntoIterator for Operands<T> {
+    type Item = T;
+    type IntoIter = iter::Flatten<array::IntoIter<Option<T>, MAX_OPERANDS>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIterator::into_iter(self.items).flatten()
+    }
+}
+
 /*
  * Instruction encoding constants
  */
@@ -59,8 +227,9 @@ const MOD_DISP8:  u8 = 0b01;
 const MOD_DISP32: u8 = 0b10;
 
 
-#[derive(Debug, Clone, Copy)]
-enum RelocationKind {
+/// How the target of a relocation is encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelocationKind {
     /// A rip-relative relocation. No need to keep track of.
     Relative,
     /// An absolute offset to a rip-relative location.
@@ -83,13 +252,26 @@ impl RelocationKind {
  * Implementation
  */
 
-pub(super) fn compile_instruction(ref mut ctx: Context, instruction: Instruction, mut args: Vec<CleanArg>)
+pub(super) fn compile_instruction(mut ctx: Context, instruction: Instruction, args: Vec<CleanArg>)
     -> Result<(), Error>
 {
-    let mut ops = instruction.idents;
-    let op = ops.pop().unwrap();
-    let op_span = ErrorSpan::InstructionPart { idx: ops.len() };
-    let prefixes = ops;
+    let mut idents = instruction.idents;
+    let op = idents.pop().unwrap();
+    let prefixes = idents.iter().map(|ident| ident.name.as_str());
+
+    if args.len() > MAX_OPERANDS {
+        return Err(too_many_operands(&ctx, &op.name));
+    }
+
+    compile_operands(&mut ctx, prefixes, &op.name, args.into_iter().collect())
+}
+
+/// Compiles an instruction with the given prefixes, mnemonic and operands. This doesn't allocate
+/// as long as all operands are static and compilation succeeds.
+pub(super) fn compile_operands<'p, P>(ctx: &mut Context, prefixes: P, op: &str, mut args: Operands<CleanArg>)
+    -> Result<(), Error>
+    where P: IntoIterator<Item=&'p str>
+{
 
     // sanitize memory references, determine address size, and size immediates/displacements if possible
     let addr_size = sanitize_indirects_and_sizes(ctx, &mut args)?;
@@ -113,7 +295,8 @@ pub(super) fn compile_instruction(ref mut ctx: Context, instruction: Instruction
     };
 
     // find a matching op
-    let data = match_op_format(ctx, op_span, &op.name, &args)?;
+    let data = match_op_format(ctx, op, &args)?;
+    ctx.explain_form(op, data);
 
     // determine if the features required for this op are fulfilled
     if !ctx.features.contains(data.features) {
@@ -222,10 +405,12 @@ pub(super) fn compile_instruction(ref mut ctx: Context, instruction: Instruction
 
     // legacy-only prefixes
     if let Some(pref) = pref_seg {
-        ctx.state.push(Stmt::u8(pref));
+        ctx.explain(FieldKind::SegmentPrefix(pref));
+        ctx.push(Stmt::u8(pref));
     }
     if pref_addr {
-        ctx.state.push(Stmt::u8(0x67));
+        ctx.explain(FieldKind::AddressSizePrefix);
+        ctx.push(Stmt::u8(0x67));
     }
 
     // VEX/XOP prefixes embed the operand size prefix / modification prefixes in them.
@@ -242,10 +427,12 @@ pub(super) fn compile_instruction(ref mut ctx: Context, instruction: Instruction
     // otherwise, the size/mod prefixes have to be pushed and check if a rex prefix has to be generated.
     } else {
         if let Some(pref) = pref_mod {
-            ctx.state.push(Stmt::u8(pref));
+            ctx.explain(FieldKind::LockRepPrefix(pref));
+            ctx.push(Stmt::u8(pref));
         }
         if pref_size {
-            ctx.state.push(Stmt::u8(0x66));
+            ctx.explain(FieldKind::OperandSizePrefix);
+            ctx.push(Stmt::u8(0x66));
         }
         if need_rex {
             // Certain SSE/AVX legacy encoded operations are not available in 32-bit mode
@@ -266,7 +453,10 @@ pub(super) fn compile_instruction(ref mut ctx: Context, instruction: Instruction
     if data.flags.contains(Flags::SHORT_ARG) {
         let (last, head) = ops.split_last().expect("bad formatting data");
         ops = head;
-        ctx.state.push(Stmt::Extend(Vec::from(ops)));
+        if !ops.is_empty() {
+            ctx.explain(FieldKind::Opcode);
+            ctx.extend(ops);
+        }
 
         let rm_k = if let Some(SizedArg::Direct {reg, ..}) = rm.take() {
             reg.kind
@@ -274,17 +464,19 @@ pub(super) fn compile_instruction(ref mut ctx: Context, instruction: Instruction
             panic!("bad formatting data")
         };
 
+        ctx.explain(FieldKind::OpcodeRegister { reg: Bits::register(&rm_k, 7) });
         if let RegKind::Dynamic(_, expr) = rm_k {
             let last = Value::Byte((*last).into());
             let mut expr = ctx.state.mask_shift_or_else_err(last, expr, 7, 0)?;
             expr.repr = NumericRepr::U8;
-            ctx.state.push(Stmt::Const(Value::Expr(expr)));
+            ctx.push(Stmt::Const(Value::Expr(expr)));
         } else {
-            ctx.state.push(Stmt::u8(last + (rm_k.encode() & 7)));
+            ctx.push(Stmt::u8(last + (rm_k.encode() & 7)));
         }
     // just push the opcode
     } else {
-        ctx.state.push(Stmt::Extend(Vec::from(ops)));
+        ctx.explain(FieldKind::Opcode);
+        ctx.extend(ops);
     }
 
     // Direct ModRM addressing
@@ -295,7 +487,7 @@ pub(super) fn compile_instruction(ref mut ctx: Context, instruction: Instruction
             RegKind::from_number(data.reg)
         };
 
-        compile_modrm_sib(ctx, MOD_DIRECT, reg_k, rm.kind)?;
+        compile_modrm(ctx, MOD_DIRECT, reg_k, rm.kind)?;
     // Indirect ModRM (+SIB) addressing
     } else if let Some(SizedArg::Indirect {disp_size, base, index, disp, ..}) = rm {
         let reg_k = if let Some(SizedArg::Direct {reg, ..}) = reg {
@@ -326,24 +518,25 @@ pub(super) fn compile_instruction(ref mut ctx: Context, instruction: Instruction
             };
 
             // always need a SIB byte for VSIB addressing
-            compile_modrm_sib(ctx, mode, reg_k, RegKind::Static(RegId::RSP))?;
+            compile_modrm(ctx, mode, reg_k, RegKind::Static(RegId::RSP))?;
 
             if let Some(expr) = scale_expr {
                 compile_sib_dynscale(ctx, scale as u8, expr, index, base)?;
             } else {
-                compile_modrm_sib(ctx, encode_scale(scale).unwrap(), index, base)?;
+                compile_sib(ctx, encode_scale(scale).unwrap(), index, base)?;
             }
 
+            ctx.explain(FieldKind::Displacement);
             if let Some(disp) = disp {
                 let repr = if mode == MOD_DISP8 { NumericRepr::I8 } else { NumericRepr::I32 };
                 let disp = disp.convert(repr).expect("FIXME");
-                ctx.state.push(Stmt::Const(disp));
+                ctx.push(Stmt::Const(disp));
             } else if mode == MOD_DISP8 {
                 // no displacement was asked for, but we have to encode one as there's a base
-                ctx.state.push(Stmt::u8(0));
+                ctx.push(Stmt::u8(0));
             } else {
                 // MODE_NOBASE requires a dword displacement, and if we got here no displacement was asked for.
-                ctx.state.push(Stmt::u32(0));
+                ctx.push(Stmt::u32(0));
             }
 
         } else if mode_16bit {
@@ -357,31 +550,35 @@ pub(super) fn compile_instruction(ref mut ctx: Context, instruction: Instruction
             };
 
             // only need a mod.r/m byte for 16-bit addressing
-            compile_modrm_sib(ctx, mode, reg_k, base_k)?;
+            compile_modrm(ctx, mode, reg_k, base_k)?;
 
+            if disp.is_some() || mode == MOD_DISP8 {
+                ctx.explain(FieldKind::Displacement);
+            }
             if let Some(disp) = disp {
                 let repr = if mode == MOD_DISP8 { NumericRepr::I8 } else { NumericRepr::I16 };
                 let disp = disp.convert(repr).expect("FIXME");
-                ctx.state.push(Stmt::Const(disp));
+                ctx.push(Stmt::Const(disp));
             } else if mode == MOD_DISP8 {
-                ctx.state.push(Stmt::u8(0));
+                ctx.push(Stmt::u8(0));
             }
 
         } else if mode_rip_relative {
             // encode the RIP + disp32 or disp32 form
-            compile_modrm_sib(ctx, MOD_NODISP, reg_k, RegKind::Static(RegId::RBP))?;
+            compile_modrm(ctx, MOD_NODISP, reg_k, RegKind::Static(RegId::RBP))?;
 
+            ctx.explain(FieldKind::Displacement);
             match ctx.mode {
                 X86Mode::Long => if let Some(disp) = disp {
                     let disp = disp.convert(NumericRepr::I32).expect("FIXME");
-                    ctx.state.push(Stmt::Const(disp));
+                    ctx.push(Stmt::Const(disp));
                 } else {
-                    ctx.state.push(Stmt::u32(0))
+                    ctx.push(Stmt::u32(0))
                 },
                 X86Mode::Protected => {
                     // x86 doesn't actually allow RIP-relative addressing
                     // but we can work around it with relocations
-                    ctx.state.push(Stmt::u32(0));
+                    ctx.push(Stmt::u32(0));
                     // FIXME: that was somewhat hacky here, and the fix is hack too.
                     relocations.push((Jump::new(JumpKind::Bare(Value::Byte(0)), None), 0, Size::DWORD, RelocationKind::Absolute));
                 },
@@ -413,40 +610,43 @@ pub(super) fn compile_instruction(ref mut ctx: Context, instruction: Instruction
                 };
 
                 // escape into the SIB byte
-                compile_modrm_sib(ctx, mode, reg_k, RegKind::Static(RegId::RSP))?;
+                compile_modrm(ctx, mode, reg_k, RegKind::Static(RegId::RSP))?;
 
                 if let Some(expr) = scale_expr {
                     compile_sib_dynscale(ctx, scale as u8, expr, index.kind, base)?;
                 } else {
-                    compile_modrm_sib(ctx, encode_scale(scale).unwrap(), index.kind, base)?;
+                    compile_sib(ctx, encode_scale(scale).unwrap(), index.kind, base)?;
                 }
 
             // no index, only a base. RBP at MOD_NODISP is used to encode RIP, but this is already handled
             } else if let Some(base) = base {
-                compile_modrm_sib(ctx, mode, reg_k, base.kind)?;
+                compile_modrm(ctx, mode, reg_k, base.kind)?;
 
             // no base, no index. only disp. Easy in x86, but in x64 escape, use RBP as base and RSP as index
             } else {
                 match ctx.mode {
                     X86Mode::Protected => {
-                        compile_modrm_sib(ctx, mode, reg_k, RegKind::Static(RegId::RBP))?;
+                        compile_modrm(ctx, mode, reg_k, RegKind::Static(RegId::RBP))?;
                     },
                     X86Mode::Long => {
-                        compile_modrm_sib(ctx, mode, reg_k, RegKind::Static(RegId::RSP))?;
-                        compile_modrm_sib(ctx, 0, RegKind::Static(RegId::RSP), RegKind::Static(RegId::RBP))?;
+                        compile_modrm(ctx, mode, reg_k, RegKind::Static(RegId::RSP))?;
+                        compile_sib(ctx, 0, RegKind::Static(RegId::RSP), RegKind::Static(RegId::RBP))?;
                     }
                 }
             }
 
             // Disp
+            if disp.is_some() || no_base || mode == MOD_DISP8 {
+                ctx.explain(FieldKind::Displacement);
+            }
             if let Some(disp) = disp {
                 let repr = if mode == MOD_DISP8 {NumericRepr::I8} else {NumericRepr::I32};
                 let disp = disp.convert(repr).expect("FIXME");
-                ctx.state.push(Stmt::Const(disp));
+                ctx.push(Stmt::Const(disp));
             } else if no_base {
-                ctx.state.push(Stmt::u32(0));
+                ctx.push(Stmt::u32(0));
             } else if mode == MOD_DISP8 {
-                ctx.state.push(Stmt::u8(0));
+                ctx.push(Stmt::u8(0));
             }
         }
 
@@ -457,9 +657,10 @@ pub(super) fn compile_instruction(ref mut ctx: Context, instruction: Instruction
         } else {
             RegKind::from_number(data.reg)
         };
-        compile_modrm_sib(ctx, MOD_NODISP, reg_k, RegKind::Static(RegId::RBP))?;
+        compile_modrm(ctx, MOD_NODISP, reg_k, RegKind::Static(RegId::RBP))?;
 
-        ctx.state.push(Stmt::u32(0));
+        ctx.explain(FieldKind::Displacement);
+        ctx.push(Stmt::u32(0));
         match ctx.mode {
             X86Mode::Long      => relocations.push((jump, 0, Size::DWORD, RelocationKind::Relative)),
             X86Mode::Protected => relocations.push((jump, 0, Size::DWORD, RelocationKind::Absolute))
@@ -468,7 +669,8 @@ pub(super) fn compile_instruction(ref mut ctx: Context, instruction: Instruction
 
     // opcode encoded after the displacement
     if let Some(code) = immediate_opcode {
-        ctx.state.push(Stmt::u8(code));
+        ctx.explain(FieldKind::ImmediateOpcode(code));
+        ctx.push(Stmt::u8(code));
 
         // bump relocations
         relocations.iter_mut().for_each(|r| r.1 += 1);
@@ -486,7 +688,7 @@ pub(super) fn compile_instruction(ref mut ctx: Context, instruction: Instruction
         // if immediates are present, the register argument will be merged into the
         // first immediate byte.
         if !args.is_empty() {
-            let first_immediate = args.remove(0);
+            let first_immediate = args.remove_first().unwrap();
             if let SizedArg::Immediate {value: Value::Expr(expr)} = first_immediate {
                 if expr.repr.size == Size::BYTE {
                     byte = ctx.state.mask_shift_or_else_err(byte, expr, 0xF, 0)?.into();
@@ -511,7 +713,8 @@ pub(super) fn compile_instruction(ref mut ctx: Context, instruction: Instruction
             }
         }
         let byte = byte.convert(NumericRepr::U8).unwrap();
-        ctx.state.push(Stmt::Const(byte));
+        ctx.explain(FieldKind::RegisterImmediate { reg: Bits::register(&ireg, 0xF) });
+        ctx.push(Stmt::Const(byte));
 
         // bump relocations
         relocations.iter_mut().for_each(|r| r.1 += 1);
@@ -521,14 +724,16 @@ pub(super) fn compile_instruction(ref mut ctx: Context, instruction: Instruction
     for arg in args {
         match arg {
             SizedArg::Immediate {value} => {
-                ctx.state.push(Stmt::Const(value));
+                ctx.explain(FieldKind::Immediate);
+                ctx.push(Stmt::Const(value));
 
                 // bump relocations
                 relocations.iter_mut().for_each(|r| r.1 += value.size().in_bytes());
             },
             SizedArg::JumpTarget {jump, size} => {
                 // placeholder
-                ctx.state.push(Stmt::zeroed(size));
+                ctx.explain(FieldKind::Immediate);
+                ctx.push(Stmt::zeroed(size));
 
                 // bump relocations
                 relocations.iter_mut().for_each(|r| r.1 += size.in_bytes());
@@ -549,13 +754,14 @@ pub(super) fn compile_instruction(ref mut ctx: Context, instruction: Instruction
 
     // push relocations
     for (target, offset, size, kind) in relocations {
+        ctx.explain_relocation(offset, size, kind);
         let data = [offset, size.in_bytes(), kind.to_id()];
         let data = match ctx.mode {
             X86Mode::Protected => &data,
             X86Mode::Long      => &data[..2],
         };
 
-        ctx.state.push(target.encode(data));
+        ctx.push(target.encode(data));
     }
 
     Ok(())
@@ -563,7 +769,7 @@ pub(super) fn compile_instruction(ref mut ctx: Context, instruction: Instruction
 
 // Go through the CleanArgs, check for impossible to encode indirect arguments, fill in immediate/displacement size information
 // and return the effective address size
-fn sanitize_indirects_and_sizes(ctx: &mut Context, args: &mut [CleanArg]) -> Result<Option<Size>, Error> {
+fn sanitize_indirects_and_sizes(ctx: &mut Context, args: &mut Operands<CleanArg>) -> Result<Option<Size>, Error> {
     // determine if an address size prefix is necessary, and sanitize the register choice for memoryrefs
     let mut addr_size = None;
     let mut encountered_indirect = false;
@@ -813,28 +1019,53 @@ fn sanitize_indirect(ctx: &mut Context, span: ErrorSpan, nosplit: bool, base: &m
     Ok(Some(size))
 }
 
-fn match_op_format(ctx: &mut Context, span: ErrorSpan, ident: &str, args: &[CleanArg]) -> Result<&'static Opdata, Error> {
-    let name = ident.to_string();
-    let name = name.as_str();
+fn match_op_format(ctx: &mut Context, name: &str, args: &Operands<CleanArg>) -> Result<&'static Opdata, Error> {
+    let data = mnemonic_forms(ctx, name)?;
+    let signature = operand_signature(args);
+
+    for format in data {
+        if format.signature & signature != signature {
+            continue;
+        }
+        if let Ok(()) = match_format_string(ctx, format, args) {
+            return Ok(format);
+        }
+    }
+
+    Err(format_mismatch(name, data))
+}
 
+fn mnemonic_forms(ctx: &Context, name: &str) -> Result<&'static [Opdata], Error> {
     let data = if let Some(data) = get_mnemnonic_data(name) {
         data
     } else {
-        ctx.state.emit_error_at(span, format_args!("'{}' is not a valid instruction", name));
-        return Err(Error::Fatal);
+        return Err(Error::unknown_name(NameKind::Mnemonic, name, mnemnonics()));
     };
 
-    for format in data {
-        if let Ok(()) = match_format_string(ctx, format, args) {
-            return Ok(format);
-        }
+    if ctx.mode == X86Mode::Long && data.iter().all(|format| format.flags.contains(Flags::X86_ONLY)) {
+        return Err(Error::UnsupportedInThisMode {
+            message: format!("'{}' is only available in 32-bit mode", name).into(),
+            mode_hint: Some(X86Mode::Protected),
+        });
     }
 
-    Err(format!("'{}': argument type/size mismatch, expected one of the following forms:\n{}",
-        name, format_opdata_list(name, data)).into())
+    Ok(data)
+}
+
+/// The error for an instruction with more operands than any form takes.
+pub(super) fn too_many_operands(ctx: &Context, name: &str) -> Error {
+    match mnemonic_forms(ctx, name) {
+        Ok(data) => format_mismatch(name, data),
+        Err(e) => e
+    }
+}
+
+fn format_mismatch(name: &str, data: &'static [Opdata]) -> Error {
+    format!("'{}': argument type/size mismatch, expected one of the following forms:\n{}",
+        name, format_opdata_list(name, data)).into()
 }
 
-fn match_format_string(ctx: &Context, fmt: &Opdata, args: &[CleanArg]) -> Result<(), Error> {
+fn match_format_string(ctx: &Context, fmt: &Opdata, args: &Operands<CleanArg>) -> Result<(), Error> {
     let fmtstr = &fmt.args;
 
     if ctx.mode != X86Mode::Protected && fmt.flags.intersects(Flags::X86_ONLY) {
@@ -993,7 +1224,7 @@ fn match_format_string(ctx: &Context, fmt: &Opdata, args: &[CleanArg]) -> Result
     Ok(())
 }
 
-fn size_operands(fmt: &Opdata, args: Vec<CleanArg>) -> Result<(Option<Size>, Vec<SizedArg>), Error> {
+fn size_operands(fmt: &Opdata, args: Operands<CleanArg>) -> Result<(Option<Size>, Operands<SizedArg>), Error> {
     // sizing operands requires two passes.
     // In the first one, we determine the effective operand size if necessary (if *'s are present)
     // In the second one, we create the final sized AST
@@ -1070,7 +1301,7 @@ fn size_operands(fmt: &Opdata, args: Vec<CleanArg>) -> Result<(Option<Size>, Vec
     }
 
     // fill-in loop. default should never be used.
-    let mut new_args = Vec::new();
+    let mut new_args = Operands::new();
     for (arg, (code, fsize)) in args.into_iter().zip(FormatStringIterator::new(&fmt.args)) {
         
         //get the specified operand size from the format string
@@ -1098,9 +1329,15 @@ fn size_operands(fmt: &Opdata, args: Vec<CleanArg>) -> Result<(Option<Size>, Vec
                 SizedArg::JumpTarget {jump, size},
             CleanArg::IndirectJumpTarget {jump, ..} =>
                 SizedArg::IndirectJumpTarget {jump},
-            CleanArg::Immediate {value, ..} =>
-                // TODO: cast to the size determined.
-                SizedArg::Immediate {value},
+            CleanArg::Immediate {value, ..} => {
+                // immediates are emitted at the size of the operand, not the size they were given at
+                let repr = NumericRepr { size, signed: value.repr().signed };
+                let value = match value {
+                    Value::Number(nr) => Value::Number(nr.cast_as(repr)),
+                    Value::Expr(expr) => Value::Expr(Expr { idx: expr.idx, repr }),
+                };
+                SizedArg::Immediate {value}
+            },
             CleanArg::Indirect {disp_size, base, index, disp, ..} => 
                 SizedArg::Indirect {disp_size, base, index, disp},
         });
@@ -1109,39 +1346,40 @@ fn size_operands(fmt: &Opdata, args: Vec<CleanArg>) -> Result<(Option<Size>, Vec
     Ok((op_size, new_args))
 }
 
-fn get_legacy_prefixes(ctx: &mut Context, fmt: &'static Opdata, idents: Vec<Ident>)
+fn get_legacy_prefixes<'p, P>(ctx: &mut Context, fmt: &'static Opdata, prefixes: P)
     -> Result<(Option<u8>, Option<u8>), Error>
+    where P: IntoIterator<Item=&'p str>
 {
     let mut group1 = None;
     let mut group2 = None;
 
-    for (idx, prefix) in idents.into_iter().enumerate() {
+    for (idx, prefix) in prefixes.into_iter().enumerate() {
         let span = ErrorSpan::InstructionPart { idx };
-        let (group, value) = match prefix.name.as_str() {
+        let (group, value) = match prefix {
             "rep"   => if fmt.flags.contains(Flags::REP) {
                 (&mut group1, 0xF3)
             } else {
-                ctx.state.emit_error_at(span, format_args!("Cannot use prefix {} on this instruction", prefix.name));
+                ctx.state.emit_error_at(span, format_args!("Cannot use prefix {} on this instruction", prefix));
                 return Err(Error::Fatal);
             },
             "repe"  |
             "repz"  => if fmt.flags.contains(Flags::REPE) {
                 (&mut group1, 0xF3)
             } else {
-                ctx.state.emit_error_at(span, format_args!("Cannot use prefix {} on this instruction", prefix.name));
+                ctx.state.emit_error_at(span, format_args!("Cannot use prefix {} on this instruction", prefix));
                 return Err(Error::Fatal);
             },
             "repnz" |
             "repne" => if fmt.flags.contains(Flags::REP) {
                 (&mut group1, 0xF2)
             } else {
-                ctx.state.emit_error_at(span, format_args!("Cannot use prefix {} on this instruction", prefix.name));
+                ctx.state.emit_error_at(span, format_args!("Cannot use prefix {} on this instruction", prefix));
                 return Err(Error::Fatal);
             },
             "lock"  => if fmt.flags.contains(Flags::LOCK) {
                 (&mut group1, 0xF0)
             } else {
-                ctx.state.emit_error_at(span, format_args!("Cannot use prefix {} on this instruction", prefix.name));
+                ctx.state.emit_error_at(span, format_args!("Cannot use prefix {} on this instruction", prefix));
                 return Err(Error::Fatal);
             },
             "ss"    => (&mut group2, 0x36),
@@ -1150,7 +1388,7 @@ fn get_legacy_prefixes(ctx: &mut Context, fmt: &'static Opdata, idents: Vec<Iden
             "es"    => (&mut group2, 0x26),
             "fs"    => (&mut group2, 0x64),
             "gs"    => (&mut group2, 0x65),
-            _       => panic!("unimplemented prefix")
+            name    => return Err(Error::unknown_name(NameKind::Prefix, name, PREFIXES.iter().cloned()))
         };
         if group.is_some() {
             ctx.state.emit_error_at(span, format_args!("Duplicate prefix group"));
@@ -1162,7 +1400,7 @@ fn get_legacy_prefixes(ctx: &mut Context, fmt: &'static Opdata, idents: Vec<Iden
     Ok((group1, group2))
 }
 
-fn check_rex(ctx: &Context, fmt: &'static Opdata, args: &[SizedArg], rex_w: bool) -> Result<bool, Error> {
+fn check_rex(ctx: &Context, fmt: &'static Opdata, args: &Operands<SizedArg>, rex_w: bool) -> Result<bool, Error> {
     // performs checks for not encodable arg combinations
     // output arg indicates if a rex prefix can be encoded
     if ctx.mode == X86Mode::Protected {
@@ -1212,8 +1450,9 @@ fn check_rex(ctx: &Context, fmt: &'static Opdata, args: &[SizedArg], rex_w: bool
     }
 }
 
-fn extract_args(fmt: &'static Opdata, args: Vec<SizedArg>)
-    -> (Option<SizedArg>, Option<SizedArg>, Option<SizedArg>, Option<SizedArg>, Vec<SizedArg>)
+pub(super) fn extract_args<T, I>(fmt: &'static Opdata, args: I)
+    -> (Option<T>, Option<T>, Option<T>, Option<T>, Operands<T>)
+    where I: IntoIterator<Item=T>
 {
     // way operand order works:
 
@@ -1228,8 +1467,8 @@ fn extract_args(fmt: &'static Opdata, args: Vec<SizedArg>)
 
     let mut memarg = None;
     let mut regarg = None;
-    let mut regs = Vec::new();
-    let mut immediates = Vec::new();
+    let mut regs = Operands::new();
+    let mut immediates = Operands::new();
 
     for (arg, (c, _)) in args.into_iter().zip(FormatStringIterator::new(fmt.args)) {
         match c {
@@ -1255,7 +1494,7 @@ fn extract_args(fmt: &'static Opdata, args: Vec<SizedArg>)
     if len > 4 {
         panic!("too many arguments");
     }
-    let mut regs = regs.drain(..).fuse();
+    let mut regs = regs.into_iter().fuse();
 
     let mut m = None;
     let mut r = None;
@@ -1350,8 +1589,14 @@ fn compile_rex(ctx: &mut Context, rex_w: bool, reg: &Option<SizedArg>, rm: &Opti
                      (reg_k.encode()   & 8) >> 1 |
                      (index_k.encode() & 8) >> 2 |
                      (base_k.encode()  & 8) >> 3 ;
+    ctx.explain(FieldKind::Rex {
+        w: rex_w,
+        r: Bits::register(&reg_k, 8),
+        x: Bits::register(&index_k, 8),
+        b: Bits::register(&base_k, 8),
+    });
     if !reg_k.is_dynamic() && !index_k.is_dynamic() && !base_k.is_dynamic() {
-        ctx.state.push(Stmt::u8(rex));
+        ctx.push(Stmt::u8(rex));
         return Ok(());
     }
 
@@ -1368,7 +1613,7 @@ fn compile_rex(ctx: &mut Context, rex_w: bool, reg: &Option<SizedArg>, rm: &Opti
     }
 
     assert_eq!(rex.size(), Size::BYTE);
-    ctx.state.push(Stmt::Const(rex));
+    ctx.push(Stmt::Const(rex));
     Ok(())
 }
 
@@ -1425,14 +1670,29 @@ fn compile_vex_xop(
                 (!vvvv_k.encode() & 0xF) << 3 |
                 (vex_l            as u8) << 2 ;
 
-    if data.flags.contains(Flags::VEX_OP) && (byte1 & 0x7F) == 0x61 && (byte2 & 0x80) == 0 &&
-    ((!index_k.is_dynamic() && !base_k.is_dynamic()) || mode == X86Mode::Protected) {
+    let two_byte = data.flags.contains(Flags::VEX_OP) && (byte1 & 0x7F) == 0x61 && (byte2 & 0x80) == 0 &&
+        ((!index_k.is_dynamic() && !base_k.is_dynamic()) || mode == X86Mode::Protected);
+
+    ctx.explain(FieldKind::Vex {
+        xop: !data.flags.contains(Flags::VEX_OP),
+        two_byte,
+        map_sel: map_sel & 0x1F,
+        w: rex_w,
+        l: vex_l,
+        pp: prefix & 0x3,
+        r: Bits::register(&reg_k, 8),
+        x: Bits::register(&index_k, 8),
+        b: Bits::register(&base_k, 8),
+        vvvv: Bits::register(&vvvv_k, 0xF),
+    });
+
+    if two_byte {
         // 2-byte vex
-        ctx.state.push(Stmt::u8(0xC5));
+        ctx.push(Stmt::u8(0xC5));
 
         let byte1 = (byte1 & 0x80) | (byte2 & 0x7F);
         if !reg_k.is_dynamic() && !vvvv_k.is_dynamic() {
-            ctx.state.push(Stmt::u8(byte1));
+            ctx.push(Stmt::u8(byte1));
             return Ok(());
         }
 
@@ -1444,11 +1704,11 @@ fn compile_vex_xop(
             byte1 = ctx.state.mask_shift_inverted_and_else_err(byte1, expr, 0xF, 3)?.into();
         }
         assert_eq!(byte1.size(), Size::BYTE);
-        ctx.state.push(Stmt::Const(byte1));
+        ctx.push(Stmt::Const(byte1));
         return Ok(());
     }
 
-    ctx.state.push(Stmt::u8(if data.flags.contains(Flags::VEX_OP) {0xC4} else {0x8F}));
+    ctx.push(Stmt::u8(if data.flags.contains(Flags::VEX_OP) {0xC4} else {0x8F}));
 
     if mode == X86Mode::Long && (reg_k.is_dynamic() || index_k.is_dynamic() || base_k.is_dynamic()) {
         let mut byte1 = Value::Byte(byte1);
@@ -1463,9 +1723,9 @@ fn compile_vex_xop(
             byte1 = ctx.state.mask_shift_inverted_and_else_err(byte1, expr, 8, 2)?.into();
         }
         assert_eq!(byte1.size(), Size::BYTE);
-        ctx.state.push(Stmt::Const(byte1));
+        ctx.push(Stmt::Const(byte1));
     } else {
-        ctx.state.push(Stmt::u8(byte1));
+        ctx.push(Stmt::u8(byte1));
     }
 
     if vvvv_k.is_dynamic() {
@@ -1475,14 +1735,24 @@ fn compile_vex_xop(
             byte2 = ctx.state.mask_shift_inverted_and_else_err(byte2, expr, 0xF, 3)?.into();
         }
         assert_eq!(byte2.size(), Size::BYTE);
-        ctx.state.push(Stmt::Const(byte2));
+        ctx.push(Stmt::Const(byte2));
     } else {
-        ctx.state.push(Stmt::u8(byte2));
+        ctx.push(Stmt::u8(byte2));
     }
 
     Ok(())
 }
 
+fn compile_modrm(ctx: &mut Context, mode: u8, reg: RegKind, rm: RegKind) -> Result<(), Error> {
+    ctx.explain(FieldKind::ModRm { mode, reg: Bits::register(&reg, 7), rm: Bits::register(&rm, 7) });
+    compile_modrm_sib(ctx, mode, reg, rm)
+}
+
+fn compile_sib(ctx: &mut Context, scale: u8, index: RegKind, base: RegKind) -> Result<(), Error> {
+    ctx.explain(FieldKind::Sib { scale: Bits::Static(scale), index: Bits::register(&index, 7), base: Bits::register(&base, 7) });
+    compile_modrm_sib(ctx, scale, index, base)
+}
+
 fn compile_modrm_sib(ctx: &mut Context, mode: u8, reg1: RegKind, reg2: RegKind)
     -> Result<(), Error>
 {
@@ -1491,7 +1761,7 @@ fn compile_modrm_sib(ctx: &mut Context, mode: u8, reg1: RegKind, reg2: RegKind)
               (reg2.encode()  & 7)      ;
 
     if !reg1.is_dynamic() && !reg2.is_dynamic() {
-        ctx.state.push(Stmt::u8(byte));
+        ctx.push(Stmt::u8(byte));
         return Ok(());
     }
 
@@ -1504,13 +1774,15 @@ fn compile_modrm_sib(ctx: &mut Context, mode: u8, reg1: RegKind, reg2: RegKind)
         byte = ctx.state.mask_shift_or_else_err(byte, expr, 7, 0)?.into();
     }
     assert_eq!(byte.size(), Size::BYTE);
-    ctx.state.push(Stmt::Const(byte));
+    ctx.push(Stmt::Const(byte));
     Ok(())
 }
 
 fn compile_sib_dynscale(ctx: &mut Context, scale: u8, scale_expr: Expr, reg1: RegKind, reg2: RegKind)
     -> Result<(), Error>
 {
+    ctx.explain(FieldKind::Sib { scale: Bits::Dynamic, index: Bits::register(&reg1, 7), base: Bits::register(&reg2, 7) });
+
     let byte = (reg1.encode()  & 7) << 3 |
                (reg2.encode()  & 7)      ;
 
@@ -1523,12 +1795,13 @@ fn compile_sib_dynscale(ctx: &mut Context, scale: u8, scale_expr: Expr, reg1: Re
         byte = ctx.state.mask_shift_or_else_err(byte, expr, 7, 0)?.into();
     }
 
-    let scaled = ctx.state.mul_else_err(scale_expr, scale.into())?.into();
-    let (expr1, expr2) = ctx.state.dynscale(scaled, byte)?;
+    // the scale field holds log2 of the total scale
+    let scaled = ctx.state.mul_else_err(scale_expr, scale.into())?;
+    let scaled = ctx.state.log2_else_err(scaled)?;
+    let mut byte = ctx.state.mask_shift_or_else_err(byte, scaled, 3, 6)?;
+    byte.repr = NumericRepr::U8;
 
-    ctx.state.push(Stmt::Stmt(expr1));
-    assert_eq!(expr2.repr.size, Size::BYTE);
-    ctx.state.push(Stmt::Const(expr2.into()));
+    ctx.push(Stmt::Const(byte.into()));
     Ok(())
 }
 
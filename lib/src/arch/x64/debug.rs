@@ -1,8 +1,12 @@
-use std::borrow::Cow;
+use alloc::borrow::Cow;
 
 use super::compiler::{Opdata, FormatStringIterator};
 use super::x64data::Flags;
 
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::format;
+
 pub fn format_opdata_list(name: &str, data: &[Opdata]) -> String {
     let mut forms = Vec::new();
     for data in data {
@@ -27,7 +31,7 @@ pub fn format_opdata(name: &str, data: &Opdata) -> Vec<String> {
         let mut first = true;
         for (ty, size) in FormatStringIterator::new(data.args) {
             if first {
-                buf.push_str(" ");
+                buf.push(' ');
                 first = false;
             } else {
                 buf.push_str(", ");
@@ -123,16 +127,12 @@ fn format_arg(ty: u8, mut size: u8, opsize: u8) -> Cow<'static, str> {
 pub fn create_opmap() -> String {
     let mut s = String::new();
 
-    let mut mnemnonics: Vec<_> = super::x64data::mnemnonics().cloned().collect();
-    mnemnonics.sort();
-
-    for mnemnonic in mnemnonics {
+    for mnemnonic in super::x64data::mnemnonics() {
         // get the data for this mnemnonic
         let data = super::x64data::get_mnemnonic_data(mnemnonic).unwrap();
         // format the data for the opmap docs
-        let mut formats = data.into_iter()
-            .map(|x| format_opdata(mnemnonic, x))
-            .flat_map(|x| x)
+        let mut formats = data.iter()
+            .flat_map(|x| format_opdata(mnemnonic, x))
             .map(|x| x.replace(">>> ", ""))
             .collect::<Vec<_>>();
         formats.sort();
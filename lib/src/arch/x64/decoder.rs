@@ -0,0 +1,779 @@
+//! A disassembler for x64 machine code.
+//!
+//! Just like the assembler, this is driven by the opmap. After the legacy, REX and VEX/XOP
+//! prefixes have been split off, every template whose opcode bytes match is used to
+//! reconstruct operands from the ModRM, SIB, displacement and immediate bytes that follow.
+//! As many templates overlap, such a reconstruction is only accepted if the assembler encodes
+//! it to exactly the same bytes again, which guarantees that the result can be reassembled.
+
+use super::{Context, InstructionX64, X86Mode};
+use super::ast::{Instruction, CleanArg, Register, RegKind, RegId, RegFamily};
+use super::compiler::{self, Opdata, FormatStringIterator};
+use super::x64data::{Flags, Features, get_mnemnonic_data, mnemnonics};
+use super::parser::X64_REGISTERS;
+
+use crate::arch::{BasicExprBuilder, ErrorSpan};
+use crate::common::{Expr, Ident, Number, NumericRepr, Size, Stmt, Value};
+
+use lazy_static::lazy_static;
+
+use core::cmp::{self, Reverse};
+use crate::collections::HashMap;
+use core::fmt::{self, Write};
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+
+/// An instruction decoded from x64 machine code.
+#[derive(Debug)]
+pub struct Decoded {
+    /// The instruction, in the form that is accepted by the assembler.
+    pub instruction: InstructionX64,
+    /// The length of the instruction in bytes.
+    pub len: usize,
+    /// The offset from the end of the instruction that a relative operand refers to.
+    target: Option<i64>,
+    /// The template that the instruction was decoded with.
+    data: &'static Opdata,
+}
+
+/// Decodes the first instruction in `buf` as 64-bit code.
+///
+/// Relative jump targets are decoded as immediate offsets from the end of the instruction.
+/// Returns `None` if the bytes don't form an instruction that the assembler would encode in
+/// exactly the same way.
+pub fn decode(buf: &[u8]) -> Option<Decoded> {
+    let (prefixes, start) = decode_prefixes(buf)?;
+    let opcode = *buf.get(start)?;
+
+    let key = match prefixes.vex {
+        Some(Vex { xop: false, map_sel, .. }) => (Encoding::Vex, map_sel, opcode),
+        Some(Vex { xop: true, map_sel, .. }) => (Encoding::Xop, map_sel, opcode),
+        None => (Encoding::Legacy, 0, opcode)
+    };
+
+    CANDIDATES.get(&key)?.iter().find_map(|candidate| decode_candidate(buf, &prefixes, start, candidate))
+}
+
+/// Disassembles a buffer of 64-bit code into a listing.
+///
+/// Every line contains the address, the instruction bytes and their disassembly. Bytes that
+/// cannot be decoded are shown as `.byte` directives, and relative operands are annotated with
+/// the absolute address they refer to, assuming that the buffer was loaded at `address`.
+pub fn disassemble_listing(buf: &[u8], address: u64) -> String {
+    let mut listing = String::new();
+    let mut offset = 0;
+
+    while offset < buf.len() {
+        let pc = address.wrapping_add(offset as u64);
+
+        let decoded = match decode(&buf[offset ..]) {
+            Some(decoded) => decoded,
+            None => {
+                writeln!(listing, "{:08x}: {:24}  .byte 0x{:02x}", pc, format!("{:02x}", buf[offset]), buf[offset]).unwrap();
+                offset += 1;
+                continue;
+            }
+        };
+
+        let bytes: Vec<_> = buf[offset .. offset + decoded.len].iter().map(|b| format!("{:02x}", b)).collect();
+        let bytes = bytes.join(" ");
+        offset += decoded.len;
+
+        match decoded.target {
+            Some(target) => {
+                let target = address.wrapping_add(offset as u64).wrapping_add(target as u64);
+                writeln!(listing, "{:08x}: {:24}  {:40} // => 0x{:x}", pc, bytes, decoded.to_string(), target).unwrap();
+            },
+            None => writeln!(listing, "{:08x}: {:24}  {}", pc, bytes, decoded).unwrap()
+        }
+    }
+
+    listing
+}
+
+/// The prefixes in front of an opcode.
+#[derive(Debug, Default)]
+struct Prefixes {
+    /// A segment override prefix.
+    segment: Option<u8>,
+    /// A lock or repeat prefix. These are also used as mandatory prefixes.
+    group1: Option<u8>,
+    /// Operand size override. This is also used as a mandatory prefix.
+    operand_size: bool,
+    /// Address size override.
+    address_size: bool,
+    /// The REX prefix, or the equivalent bits of a VEX/XOP prefix.
+    rex: Option<u8>,
+    /// The remaining fields of a VEX/XOP prefix.
+    vex: Option<Vex>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Vex {
+    xop: bool,
+    map_sel: u8,
+    vvvv: u8,
+    l: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Encoding {
+    Legacy,
+    Vex,
+    Xop,
+}
+
+/// An opmap template.
+struct Candidate {
+    name: &'static str,
+    data: &'static Opdata,
+}
+
+/// Aliases that should only be picked if nothing else encodes to the same bytes.
+const DEPRIORITIZED: &[&str] = &["sal", "retn", "xchg"];
+
+lazy_static! {
+    /// All templates, indexed by their encoding, opcode map and first opcode byte. They're sorted
+    /// so that longer opcodes are tried first, followed by anything but the obsolete Cyrix
+    /// extensions that overlap with SSE, as well as to keep the choice between aliases stable.
+    static ref CANDIDATES: HashMap<(Encoding, u8, u8), Vec<Candidate>> = {
+        let mut names: Vec<_> = mnemnonics().collect();
+        names.sort_by_key(|&name| (DEPRIORITIZED.contains(&name), name));
+
+        let mut map: HashMap<_, Vec<Candidate>> = HashMap::new();
+        for name in names {
+            for data in get_mnemnonic_data(name).unwrap() {
+                if data.flags.contains(Flags::X86_ONLY) {
+                    continue;
+                }
+
+                let mut ops = data.ops;
+                if data.flags.contains(Flags::IMM_OP) {
+                    ops = &ops[.. ops.len() - 1];
+                }
+
+                let (encoding, map_sel, ops) = if data.flags.contains(Flags::VEX_OP) {
+                    (Encoding::Vex, ops[0], &ops[1 ..])
+                } else if data.flags.contains(Flags::XOP_OP) {
+                    (Encoding::Xop, ops[0], &ops[1 ..])
+                } else {
+                    (Encoding::Legacy, 0, ops)
+                };
+
+                // the register of short forms is encoded in the low bits of the last opcode byte
+                let variants = if data.flags.contains(Flags::SHORT_ARG) && ops.len() == 1 { 8 } else { 1 };
+                for i in 0 .. variants {
+                    map.entry((encoding, map_sel, ops[0] + i)).or_default().push(Candidate { name, data });
+                }
+            }
+        }
+
+        for candidates in map.values_mut() {
+            candidates.sort_by_key(|c| (Reverse(c.data.ops.len()), c.data.features.contains(Features::CYRIX)));
+        }
+        map
+    };
+}
+
+fn decode_prefixes(buf: &[u8]) -> Option<(Prefixes, usize)> {
+    let mut prefixes = Prefixes::default();
+    let mut pos = 0;
+
+    loop {
+        let byte = *buf.get(pos)?;
+        let duplicate = match byte {
+            0xF0 | 0xF2 | 0xF3 => prefixes.group1.replace(byte).is_some(),
+            0x26 | 0x2E | 0x36 | 0x3E | 0x64 | 0x65 => prefixes.segment.replace(byte).is_some(),
+            0x66 => core::mem::replace(&mut prefixes.operand_size, true),
+            0x67 => core::mem::replace(&mut prefixes.address_size, true),
+            _ => break
+        };
+        if duplicate {
+            return None;
+        }
+        pos += 1;
+    }
+
+    let byte = *buf.get(pos)?;
+    let is_xop = byte == 0x8F && buf.get(pos + 1).is_some_and(|&b| b & 0x1F >= 8);
+
+    if let 0x40 ..= 0x4F = byte {
+        prefixes.rex = Some(byte);
+        pos += 1;
+    } else if byte == 0xC4 || byte == 0xC5 || is_xop {
+        // VEX/XOP prefixes embed the mandatory prefixes, so these can't appear separately.
+        if prefixes.group1.is_some() || prefixes.operand_size {
+            return None;
+        }
+
+        let (byte1, byte2) = if byte == 0xC5 {
+            let byte = *buf.get(pos + 1)?;
+            pos += 2;
+            ((byte & 0x80) | 0x61, byte & 0x7F)
+        } else {
+            let bytes = buf.get(pos + 1 ..= pos + 2)?;
+            pos += 3;
+            (bytes[0], bytes[1])
+        };
+
+        // rex bits are stored inverted
+        prefixes.rex = Some(0x40 | (byte2 & 0x80) >> 4 | (!byte1 & 0xE0) >> 5);
+        prefixes.vex = Some(Vex {
+            xop: is_xop,
+            map_sel: byte1 & 0x1F,
+            vvvv: (!byte2 >> 3) & 0xF,
+            l: byte2 & 0x04 != 0,
+        });
+        match byte2 & 3 {
+            1 => prefixes.operand_size = true,
+            2 => prefixes.group1 = Some(0xF3),
+            3 => prefixes.group1 = Some(0xF2),
+            _ => ()
+        }
+    }
+
+    Some((prefixes, pos))
+}
+
+fn decode_candidate(buf: &[u8], prefixes: &Prefixes, start: usize, candidate: &Candidate) -> Option<Decoded> {
+    let data = candidate.data;
+    let flags = data.flags;
+    let mut pos = start;
+
+    // match the opcode
+    let mut ops = data.ops;
+    let mut immediate_opcode = None;
+    if flags.contains(Flags::IMM_OP) {
+        let (&last, rest) = ops.split_last()?;
+        immediate_opcode = Some(last);
+        ops = rest;
+    }
+    if flags.intersects(Flags::VEX_OP | Flags::XOP_OP) {
+        ops = &ops[1 ..];
+    }
+
+    let mut short_reg = None;
+    for (i, &op) in ops.iter().enumerate() {
+        let byte = *buf.get(pos + i)?;
+        if flags.contains(Flags::SHORT_ARG) && i + 1 == ops.len() {
+            if byte & !7 != op {
+                return None;
+            }
+            short_reg = Some(byte & 7);
+        } else if byte != op {
+            return None;
+        }
+    }
+    pos += ops.len();
+
+    // split off mandatory prefixes from those that were written by the user
+    let mut idents = Vec::new();
+    if let Some(segment) = prefixes.segment {
+        idents.push(ident(match segment {
+            0x26 => "es",
+            0x2E => "cs",
+            0x36 => "ss",
+            0x3E => "ds",
+            0x64 => "fs",
+            _    => "gs"
+        }));
+    }
+    if !flags.intersects(Flags::PREF_F0 | Flags::PREF_F2 | Flags::PREF_F3) {
+        match prefixes.group1 {
+            Some(0xF0) => idents.push(ident("lock")),
+            Some(0xF2) => idents.push(ident("repne")),
+            Some(_) => idents.push(ident(if flags.contains(Flags::REPE) { "repe" } else { "rep" })),
+            None => ()
+        }
+    }
+    idents.push(ident(candidate.name));
+
+    // determine the operand size
+    let rex = prefixes.rex.unwrap_or(0);
+    let size_prefix = prefixes.operand_size && !flags.contains(Flags::WORD_SIZE);
+    let address_size = if prefixes.address_size { Size::DWORD } else { Size::QWORD };
+
+    // templates without automatic sizing still use the operand size to size wildcard operands
+    let auto_size = flags.intersects(Flags::AUTO_SIZE | Flags::AUTO_NO32 | Flags::AUTO_REXW | Flags::AUTO_VEXL);
+    let op_size = if flags.contains(Flags::AUTO_NO32) {
+        if size_prefix { Size::WORD } else { Size::QWORD }
+    } else if flags.contains(Flags::AUTO_REXW) {
+        if rex & 8 != 0 { Size::QWORD } else { Size::DWORD }
+    } else if flags.contains(Flags::AUTO_VEXL) || (!flags.contains(Flags::AUTO_SIZE) && prefixes.vex.is_some()) {
+        if prefixes.vex.is_some_and(|vex| vex.l) { Size::HWORD } else { Size::OWORD }
+    } else if rex & 8 != 0 {
+        Size::QWORD
+    } else if size_prefix {
+        Size::WORD
+    } else {
+        Size::DWORD
+    };
+    let im_size = cmp::min(op_size, Size::DWORD);
+
+    let formats: Vec<_> = FormatStringIterator::new(data.args).collect();
+    let sizes: Vec<_> = formats.iter().map(|&(code, size)| operand_size(code, size, op_size, im_size)).collect();
+    let mut args: Vec<Option<CleanArg>> = vec![None; formats.len()];
+
+    // registers implied by the template
+    for (arg, (&(code, _), &size)) in args.iter_mut().zip(formats.iter().zip(&sizes)) {
+        let reg = match code {
+            b'A' ..= b'P' => register(RegFamily::LEGACY, code - b'A', size?)?,
+            b'Q' ..= b'V' => register(RegFamily::SEGMENT, code - b'Q', size?)?,
+            b'W' => Register::new_static(size?, RegId::CR8),
+            b'X' => Register::new_static(size?, RegId::ST0),
+            _ => continue
+        };
+        *arg = Some(CleanArg::Direct { reg });
+    }
+
+    let (rm, reg, vvvv, ireg, immediates) = compiler::extract_args(data, 0 .. formats.len());
+    let mut target = None;
+
+    // registers and memory references encoded in the opcode or ModRM (+SIB) bytes
+    if let Some(rm) = rm {
+        let code = formats[rm].0;
+
+        if let Some(low) = short_reg {
+            args[rm] = Some(CleanArg::Direct { reg: direct(code, low | (rex & 1) << 3, sizes[rm]?, prefixes.rex.is_some())? });
+        } else {
+            let modrm = *buf.get(pos)?;
+            pos += 1;
+
+            if data.reg != 0xFF && (modrm >> 3) & 7 != data.reg {
+                return None;
+            }
+            if let Some(reg) = reg {
+                let code = (modrm >> 3) & 7 | (rex & 4) << 1;
+                args[reg] = Some(CleanArg::Direct { reg: direct(formats[reg].0, code, sizes[reg]?, prefixes.rex.is_some())? });
+            }
+
+            if modrm >> 6 == 0b11 {
+                let code = modrm & 7 | (rex & 1) << 3;
+                args[rm] = Some(CleanArg::Direct { reg: direct(formats[rm].0, code, sizes[rm]?, prefixes.rex.is_some())? });
+            } else {
+                let vsib = match code {
+                    b'm' | b'u' | b'v' | b'w' => None,
+                    b'k' | b'l' => Some(sizes[rm]?),
+                    _ => return None
+                };
+                let size = if vsib.is_some() { None } else { sizes[rm] };
+                let arg = decode_memory(buf, &mut pos, modrm, rex, address_size, vsib, size)?;
+                if let CleanArg::Indirect { base: Some(ref base), disp, .. } = arg {
+                    if base.kind.family() == RegFamily::RIP {
+                        target = disp.map(|disp| value(&disp).as_i64());
+                    }
+                }
+                args[rm] = Some(arg);
+            }
+        }
+    }
+
+    // the register encoded in the VEX/XOP prefix
+    if let Some(vvvv) = vvvv {
+        let code = prefixes.vex?.vvvv;
+        args[vvvv] = Some(CleanArg::Direct { reg: direct(formats[vvvv].0, code, sizes[vvvv]?, true)? });
+    }
+
+    if let Some(opcode) = immediate_opcode {
+        if *buf.get(pos)? != opcode {
+            return None;
+        }
+        pos += 1;
+    }
+
+    // a register encoded in an immediate, which shares its byte with the first immediate
+    let mut immediates = immediates.into_iter().peekable();
+    if let Some(ireg) = ireg {
+        let byte = *buf.get(pos)?;
+        pos += 1;
+
+        args[ireg] = Some(CleanArg::Direct { reg: direct(formats[ireg].0, byte >> 4, sizes[ireg]?, true)? });
+        if let Some(imm) = immediates.next() {
+            args[imm] = Some(CleanArg::Immediate { value: Value::Number(Number::byte(byte & 0xF)) });
+        }
+    }
+
+    for imm in immediates {
+        let size = sizes[imm]?;
+        let bytes = buf.get(pos .. pos + usize::from(size.in_bytes()))?;
+        pos += bytes.len();
+
+        // immediates smaller than the operand size and jump offsets get sign extended
+        let raw = bytes.iter().rev().fold(0, |raw, &byte| raw << 8 | u64::from(byte));
+        let signed = formats[imm].0 == b'o' || (auto_size && size < op_size);
+        let number = Number::from_u64_and_size(raw, size).make_signed(signed);
+
+        if formats[imm].0 == b'o' {
+            target = Some(number.as_i64());
+        }
+        args[imm] = Some(CleanArg::Immediate { value: Value::Number(number) });
+    }
+
+    let args: Vec<CleanArg> = args.into_iter().collect::<Option<_>>()?;
+    let instruction = Instruction { idents };
+
+    // leave out memory operand sizes that are implied by a register operand, if that is unambiguous
+    let register_sizes: Vec<Size> = args.iter().filter_map(|arg| match arg {
+        CleanArg::Direct { reg } => Some(reg.size()),
+        _ => None
+    }).collect();
+    let unsized_args: Vec<_> = args.iter().cloned().map(|mut arg| {
+        if let CleanArg::Indirect { ref mut size, .. } = arg {
+            if size.is_some_and(|s| register_sizes.contains(&s)) {
+                *size = None;
+            }
+        }
+        arg
+    }).collect();
+
+    let bytes = &buf[.. pos];
+    let args = if reassembles_to(&instruction, unsized_args.clone(), bytes) {
+        unsized_args
+    } else if reassembles_to(&instruction, args.clone(), bytes) {
+        args
+    } else {
+        return None;
+    };
+
+    Some(Decoded {
+        instruction: InstructionX64 { inst: instruction, args },
+        len: pos,
+        target,
+        data,
+    })
+}
+
+/// Decodes a memory reference from the ModRM byte and the SIB and displacement bytes following it.
+fn decode_memory(buf: &[u8], pos: &mut usize, modrm: u8, rex: u8, address_size: Size, vsib: Option<Size>, size: Option<Size>)
+    -> Option<CleanArg>
+{
+    let mode = modrm >> 6;
+    let mut base = None;
+    let mut index = None;
+    let mut disp_size = match mode {
+        0b01 => Some(Size::BYTE),
+        0b10 => Some(Size::DWORD),
+        _ => None
+    };
+
+    if modrm & 7 == 4 {
+        let sib = *buf.get(*pos)?;
+        *pos += 1;
+
+        let scale = 1 << (sib >> 6);
+        let index_code = (sib >> 3) & 7 | (rex & 2) << 2;
+        if let Some(size) = vsib {
+            index = Some((register(RegFamily::XMM, index_code, size)?, scale, None));
+        } else if index_code != 4 {
+            index = Some((register(RegFamily::LEGACY, index_code, address_size)?, scale, None));
+        } else if scale != 1 {
+            return None;
+        }
+
+        if mode == 0b00 && sib & 7 == 5 {
+            disp_size = Some(Size::DWORD);
+        } else {
+            base = Some(register(RegFamily::LEGACY, sib & 7 | (rex & 1) << 3, address_size)?);
+        }
+    } else if vsib.is_some() {
+        return None;
+    } else if mode == 0b00 && modrm & 7 == 5 {
+        base = Some(Register::new_static(address_size, RegId::RIP));
+        disp_size = Some(Size::DWORD);
+    } else {
+        base = Some(register(RegFamily::LEGACY, modrm & 7 | (rex & 1) << 3, address_size)?);
+    }
+
+    let disp = match disp_size {
+        Some(Size::BYTE) => {
+            let byte = *buf.get(*pos)?;
+            *pos += 1;
+            Some(Number::byte(byte).make_signed(true))
+        },
+        Some(_) => {
+            let bytes = buf.get(*pos .. *pos + 4)?;
+            *pos += 4;
+            Some(Number::dword(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])).make_signed(true))
+        },
+        None => None
+    };
+
+    // registers that can't be encoded without a displacement always get an empty one
+    let needs_disp = vsib.is_some() || base.as_ref().is_some_and(|b| b == &RegId::RBP || b == &RegId::R13);
+    let disp = match disp {
+        Some(disp) if needs_disp && disp_size == Some(Size::BYTE) && disp.as_i64() == 0 => None,
+        disp => disp
+    };
+
+    // the displacement size only needs to be specified if it isn't the smallest possible one
+    let has_base = base.as_ref().is_some_and(|b| b.kind.family() != RegFamily::RIP);
+    let disp_size = match disp {
+        Some(disp) if has_base && disp_size == Some(Size::DWORD) && disp.convert(NumericRepr::I8).is_some() => disp_size,
+        _ => None
+    };
+
+    // without nosplit, [index * 2] would be encoded as [index + index * 1]
+    let nosplit = base.is_none() && index.as_ref().is_some_and(|&(_, scale, _)| scale == 2);
+
+    Some(CleanArg::Indirect {
+        nosplit,
+        size,
+        disp_size,
+        base,
+        index,
+        disp: disp.map(Value::Number),
+    })
+}
+
+/// Determines the size of an operand from its format string entry.
+fn operand_size(code: u8, size: u8, op_size: Size, im_size: Size) -> Option<Size> {
+    match size {
+        b'b' => Some(Size::BYTE),
+        b'w' => Some(Size::WORD),
+        b'd' => Some(Size::DWORD),
+        b'f' => Some(Size::FWORD),
+        b'q' => Some(Size::QWORD),
+        b'p' => Some(Size::PWORD),
+        b'o' => Some(Size::OWORD),
+        b'h' => Some(Size::HWORD),
+        b'*' if code == b'i' || code == b'o' => Some(im_size),
+        b'*' => Some(op_size),
+        _ => None
+    }
+}
+
+/// Creates a register of the kind that can be used for a format string entry.
+fn direct(code: u8, id: u8, size: Size, rex: bool) -> Option<Register> {
+    let family = match code {
+        // without rex prefix, the byte registers 4 - 7 are ah, ch, dh and bh
+        b'r' | b'v' if size == Size::BYTE && !rex && (4 .. 8).contains(&id) => return register(RegFamily::HIGHBYTE, id, size),
+        b'r' | b'v' => RegFamily::LEGACY,
+        b'x' | b'u' => RegFamily::MMX,
+        b'y' | b'w' => RegFamily::XMM,
+        b'f' => RegFamily::FP,
+        b's' => RegFamily::SEGMENT,
+        b'c' => RegFamily::CONTROL,
+        b'd' => RegFamily::DEBUG,
+        b'b' => RegFamily::BOUND,
+        _ => return None
+    };
+    register(family, id, size)
+}
+
+fn register(family: RegFamily, id: u8, size: Size) -> Option<Register> {
+    X64_REGISTERS.iter()
+        .find(|&&(_, (reg, s))| reg.family() == family && reg.code() == id && s == size)
+        .map(|&(_, (reg, _))| Register::new_static(size, reg))
+}
+
+fn ident(name: &str) -> Ident {
+    Ident { name: name.to_string() }
+}
+
+fn value(value: &Value) -> Number {
+    match value {
+        Value::Number(number) => *number,
+        Value::Expr(_) => unreachable!()
+    }
+}
+
+/// Checks that the assembler encodes the instruction to exactly the given bytes.
+fn reassembles_to(instruction: &Instruction, args: Vec<CleanArg>, bytes: &[u8]) -> bool {
+    let mut verifier = Verifier { buf: Vec::new(), dynamic: false };
+    let ctx = Context {
+        state: &mut verifier,
+        mode: X86Mode::Long,
+        features: Features::all(),
+        explainer: None,
+    };
+
+    if compiler::compile_instruction(ctx, instruction.clone(), args).is_err() {
+        return false;
+    }
+
+    !verifier.dynamic && verifier.buf == bytes
+}
+
+/// An expression builder that only accepts static values, and silently drops any errors.
+struct Verifier {
+    buf: Vec<u8>,
+    dynamic: bool,
+}
+
+impl BasicExprBuilder for Verifier {
+    fn push(&mut self, stmt: Stmt) {
+        match stmt {
+            Stmt::Const(Value::Number(number)) => number.write_le_bytes(&mut self.buf),
+            Stmt::Extend(bytes) => self.buf.extend(bytes),
+            _ => self.dynamic = true
+        }
+    }
+
+    fn bit_or(&mut self, _: Expr, _: Value) -> Option<Expr> {
+        None
+    }
+
+    fn bit_and(&mut self, _: Expr, _: Value) -> Option<Expr> {
+        None
+    }
+
+    fn bit_xor(&mut self, _: Expr, _: Value) -> Option<Expr> {
+        None
+    }
+
+    fn add(&mut self, _: Expr, _: Value) -> Option<Expr> {
+        None
+    }
+
+    fn mul(&mut self, _: Expr, _: Value) -> Option<Expr> {
+        None
+    }
+
+    fn neg(&mut self, _: Expr) -> Option<Expr> {
+        None
+    }
+
+    fn log2(&mut self, _: Expr) -> Option<Expr> {
+        None
+    }
+
+    fn mask_shift(&mut self, _: Expr, _: u64, _: i8) -> Option<Expr> {
+        None
+    }
+
+    fn emit_error_at(&mut self, _: ErrorSpan, _: fmt::Arguments) {}
+}
+
+/*
+ * Formatting of the decoded instruction
+ */
+
+impl fmt::Display for Decoded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut buf = String::new();
+        for ident in &self.instruction.inst.idents {
+            if !buf.is_empty() {
+                buf.push(' ');
+            }
+            buf.push_str(&ident.name);
+        }
+
+        let formats = FormatStringIterator::new(self.data.args);
+        for (i, (arg, (code, _))) in self.instruction.args.iter().zip(formats).enumerate() {
+            buf.push_str(if i == 0 { " " } else { ", " });
+            format_arg(&mut buf, arg, code);
+        }
+
+        f.write_str(&buf)
+    }
+}
+
+fn format_arg(buf: &mut String, arg: &CleanArg, code: u8) {
+    match arg {
+        CleanArg::Indirect { nosplit, size, disp_size, base, index, disp } => {
+            if let Some(size) = size {
+                buf.push_str(size_name(*size));
+                buf.push(' ');
+            }
+            buf.push('[');
+            if *nosplit {
+                buf.push_str("NOSPLIT ");
+            }
+            if let Some(disp_size) = disp_size {
+                buf.push_str(size_name(*disp_size));
+                buf.push(' ');
+            }
+
+            let mut first = true;
+            if let Some(base) = base {
+                format_register(buf, base);
+                first = false;
+            }
+            if let Some((index, scale, _)) = index {
+                if !first {
+                    buf.push_str(" + ");
+                }
+                format_register(buf, index);
+                write!(buf, " * {}", scale).unwrap();
+                first = false;
+            }
+            if let Some(disp) = disp {
+                let disp = value(disp);
+                if first {
+                    buf.push_str(&number(disp));
+                } else if disp.repr().signed && disp.as_i64() < 0 {
+                    buf.push_str(" - ");
+                    buf.push_str(&number(Number::qword(disp.as_i64().unsigned_abs())));
+                } else {
+                    buf.push_str(" + ");
+                    buf.push_str(&number(disp));
+                }
+            }
+            buf.push(']');
+        },
+        CleanArg::Direct { reg } => format_register(buf, reg),
+        CleanArg::JumpTarget { .. } |
+        CleanArg::IndirectJumpTarget { .. } => unreachable!(),
+        CleanArg::Immediate { value: immediate } => {
+            let immediate = value(immediate);
+            let size = immediate.repr().size;
+
+            // jump offsets default to the largest size, other immediates to the smallest one
+            let explicit = if code == b'o' {
+                size == Size::BYTE
+            } else {
+                [Size::BYTE, Size::WORD, Size::DWORD].iter()
+                    .take_while(|&&s| s < size)
+                    .any(|&s| immediate.convert(NumericRepr { size: s, signed: immediate.repr().signed }).is_some())
+            };
+            if explicit {
+                buf.push_str(size_name(size));
+                buf.push(' ');
+            }
+            buf.push_str(&number(immediate));
+        }
+    }
+}
+
+fn format_register(buf: &mut String, reg: &Register) {
+    let id = match reg.kind {
+        RegKind::Static(id) => id,
+        RegKind::Dynamic(_, _) => unreachable!()
+    };
+    let name = X64_REGISTERS.iter()
+        .find(|&&(_, (i, size))| i == id && size == reg.size())
+        .map(|&(name, _)| name)
+        .expect("register missing from the register table");
+    buf.push_str(name);
+}
+
+fn size_name(size: Size) -> &'static str {
+    match size {
+        Size::BYTE  => "BYTE",
+        Size::WORD  => "WORD",
+        Size::DWORD => "DWORD",
+        Size::FWORD => "FWORD",
+        Size::QWORD => "QWORD",
+        Size::PWORD => "TWORD",
+        Size::OWORD => "OWORD",
+        Size::HWORD => "YWORD",
+    }
+}
+
+/// Formats a number. Small values are printed in decimal, larger ones in hexadecimal.
+fn number(number: Number) -> String {
+    let (negative, magnitude) = if number.repr().signed && number.as_i64() < 0 {
+        (true, number.as_i64().unsigned_abs())
+    } else {
+        (false, number.as_u64())
+    };
+
+    let sign = if negative { "-" } else { "" };
+    if magnitude < 10 {
+        format!("{}{}", sign, magnitude)
+    } else {
+        format!("{}0x{:x}", sign, magnitude)
+    }
+}
@@ -0,0 +1,220 @@
+//! Direct encoding of instructions with only static operands.
+//!
+//! Compiling through a `BasicAssembler` allocates for the instruction, its operands and every
+//! emitted statement. When all operands are known up front none of that is necessary: the
+//! compiler is run against a builder that writes the encoded bytes straight into a fixed size
+//! buffer, and nothing is allocated unless an error has to be reported.
+
+use super::{compiler, Archx64, Archx86, Context, Error, X86Mode};
+use super::ast::{CleanArg, RegFamily};
+use super::x64data::Features;
+use crate::arch::{BasicExprBuilder, ErrorSpan};
+use crate::common::{Expr, Stmt, Value};
+
+use core::fmt;
+
+use alloc::string::{String, ToString};
+
+/// The maximum length of an x86 instruction.
+pub const MAX_INSTRUCTION_LEN: usize = 15;
+
+impl Archx64 {
+    /// Encodes an instruction into `buf` and returns its length. `idents` are the prefixes of
+    /// the instruction followed by its mnemonic, like in `Instruction::idents`. All operands have
+    /// to be static: jump targets, dynamic registers and expressions are rejected.
+    pub fn encode_static(&self, idents: &[&str], args: &[CleanArg], buf: &mut [u8; MAX_INSTRUCTION_LEN])
+        -> Result<usize, Error>
+    {
+        encode_static(X86Mode::Long, self.features, idents, args, buf)
+    }
+
+    /// Like `encode_static`, but appends the encoded instruction to `out`.
+    pub fn encode_static_extend<E: Extend<u8>>(&self, idents: &[&str], args: &[CleanArg], out: &mut E)
+        -> Result<usize, Error>
+    {
+        let mut buf = [0; MAX_INSTRUCTION_LEN];
+        let len = self.encode_static(idents, args, &mut buf)?;
+        out.extend(buf[.. len].iter().cloned());
+        Ok(len)
+    }
+}
+
+impl Archx86 {
+    /// Encodes an instruction into `buf` and returns its length. `idents` are the prefixes of
+    /// the instruction followed by its mnemonic, like in `Instruction::idents`. All operands have
+    /// to be static: jump targets, dynamic registers, expressions and rip-relative memory
+    /// references are rejected.
+    pub fn encode_static(&self, idents: &[&str], args: &[CleanArg], buf: &mut [u8; MAX_INSTRUCTION_LEN])
+        -> Result<usize, Error>
+    {
+        encode_static(X86Mode::Protected, self.features, idents, args, buf)
+    }
+
+    /// Like `encode_static`, but appends the encoded instruction to `out`.
+    pub fn encode_static_extend<E: Extend<u8>>(&self, idents: &[&str], args: &[CleanArg], out: &mut E)
+        -> Result<usize, Error>
+    {
+        let mut buf = [0; MAX_INSTRUCTION_LEN];
+        let len = self.encode_static(idents, args, &mut buf)?;
+        out.extend(buf[.. len].iter().cloned());
+        Ok(len)
+    }
+}
+
+fn encode_static(mode: X86Mode, features: Features, idents: &[&str], args: &[CleanArg], buf: &mut [u8; MAX_INSTRUCTION_LEN])
+    -> Result<usize, Error>
+{
+    let (&op, prefixes) = match idents.split_last() {
+        Some(split) => split,
+        None => return Err("An instruction needs a mnemonic".into())
+    };
+
+    for arg in args {
+        check_static(mode, arg)?;
+    }
+
+    let mut encoder = StaticEncoder {
+        buf,
+        len: 0,
+        overflow: false,
+        dynamic: false,
+        error: None,
+    };
+    let mut ctx = Context {
+        state: &mut encoder,
+        mode,
+        features,
+        explainer: None,
+    };
+
+    if args.len() > compiler::MAX_OPERANDS {
+        return Err(compiler::too_many_operands(&ctx, op));
+    }
+
+    let result = compiler::compile_operands(&mut ctx, prefixes.iter().cloned(), op, args.iter().cloned().collect());
+
+    // errors can be emitted without failing compilation
+    if let Some(message) = encoder.error {
+        return Err(message.into());
+    }
+    result?;
+
+    if encoder.dynamic {
+        Err("Instruction could not be encoded statically".into())
+    } else if encoder.overflow {
+        Err("Instruction is longer than 15 bytes".into())
+    } else {
+        Ok(encoder.len)
+    }
+}
+
+/// Rejects operands that can only be encoded with the help of expressions or relocations.
+fn check_static(mode: X86Mode, arg: &CleanArg) -> Result<(), Error> {
+    match *arg {
+        CleanArg::Direct { ref reg } => if reg.kind.is_dynamic() {
+            return Err("Dynamic registers cannot be encoded statically".into());
+        },
+        CleanArg::Immediate { value: Value::Expr(_) } => {
+            return Err("Immediate expressions cannot be encoded statically".into());
+        },
+        CleanArg::Immediate { .. } => (),
+        CleanArg::JumpTarget { .. } |
+        CleanArg::IndirectJumpTarget { .. } => {
+            return Err("Jump targets cannot be encoded statically".into());
+        },
+        CleanArg::Indirect { ref base, ref index, ref disp, .. } => {
+            if let Some(ref base) = *base {
+                if base.kind.is_dynamic() {
+                    return Err("Dynamic registers cannot be encoded statically".into());
+                }
+                // x86 emulates rip-relative addressing with a relocation
+                if mode == X86Mode::Protected && base.kind.family() == RegFamily::RIP {
+                    return Err("Rip-relative addressing cannot be encoded statically in x86 mode".into());
+                }
+            }
+            if let Some((ref index, _, ref scale_expr)) = *index {
+                if index.kind.is_dynamic() || scale_expr.is_some() {
+                    return Err("Dynamic registers cannot be encoded statically".into());
+                }
+            }
+            if let Some(Value::Expr(_)) = *disp {
+                return Err("Displacement expressions cannot be encoded statically".into());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// An expression builder that writes static bytes into a fixed size buffer.
+struct StaticEncoder<'a> {
+    buf: &'a mut [u8; MAX_INSTRUCTION_LEN],
+    len: usize,
+    overflow: bool,
+    dynamic: bool,
+    error: Option<String>,
+}
+
+impl<'a> Extend<u8> for StaticEncoder<'a> {
+    fn extend<I: IntoIterator<Item=u8>>(&mut self, iter: I) {
+        for byte in iter {
+            if self.len == MAX_INSTRUCTION_LEN {
+                self.overflow = true;
+                return;
+            }
+            self.buf[self.len] = byte;
+            self.len += 1;
+        }
+    }
+}
+
+impl<'a> BasicExprBuilder for StaticEncoder<'a> {
+    fn push(&mut self, stmt: Stmt) {
+        match stmt {
+            Stmt::Const(Value::Number(number)) => number.write_le_bytes(self),
+            Stmt::Extend(bytes) => BasicExprBuilder::extend(self, &bytes),
+            _ => self.dynamic = true
+        }
+    }
+
+    fn extend(&mut self, bytes: &[u8]) {
+        Extend::extend(self, bytes.iter().cloned());
+    }
+
+    fn bit_or(&mut self, _: Expr, _: Value) -> Option<Expr> {
+        None
+    }
+
+    fn bit_and(&mut self, _: Expr, _: Value) -> Option<Expr> {
+        None
+    }
+
+    fn bit_xor(&mut self, _: Expr, _: Value) -> Option<Expr> {
+        None
+    }
+
+    fn add(&mut self, _: Expr, _: Value) -> Option<Expr> {
+        None
+    }
+
+    fn mul(&mut self, _: Expr, _: Value) -> Option<Expr> {
+        None
+    }
+
+    fn neg(&mut self, _: Expr) -> Option<Expr> {
+        None
+    }
+
+    fn log2(&mut self, _: Expr) -> Option<Expr> {
+        None
+    }
+
+    fn mask_shift(&mut self, _: Expr, _: u64, _: i8) -> Option<Expr> {
+        None
+    }
+
+    fn emit_error_at(&mut self, _: ErrorSpan, args: fmt::Arguments) {
+        if self.error.is_none() {
+            self.error = Some(args.to_string());
+        }
+    }
+}
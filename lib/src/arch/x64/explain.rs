@@ -0,0 +1,221 @@
+//! Field-by-field descriptions of how the compiler encoded an instruction.
+//!
+//! While compiling, the compiler announces every field it is about to emit. All statements pushed
+//! afterwards are attributed to that field, so the offsets and lengths reported here always match
+//! the emitted statements.
+
+use super::Context;
+use super::ast::RegKind;
+use super::compiler::Opdata;
+use super::introspect::Form;
+use crate::common::{Size, Stmt};
+
+use core::fmt;
+
+pub use super::compiler::RelocationKind;
+
+use alloc::vec::Vec;
+
+/// A description of how an instruction was encoded.
+#[derive(Debug, Clone)]
+pub struct Explanation {
+    /// The form of the mnemonic that was used to encode the instruction.
+    pub form: Form,
+    /// The encoded fields, in the order in which they were emitted.
+    pub fields: Vec<Field>,
+    /// The relocations that were emitted for the instruction.
+    pub relocations: Vec<Relocation>,
+}
+
+/// A single field of an encoded instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Field {
+    /// The offset of the field from the start of the instruction, in bytes.
+    pub offset: usize,
+    /// The length of the field in bytes.
+    pub len: usize,
+    /// What the field encodes.
+    pub kind: FieldKind,
+}
+
+/// The different fields an instruction encoding consists of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    /// A segment override prefix.
+    SegmentPrefix(u8),
+    /// The address size override prefix, 0x67.
+    AddressSizePrefix,
+    /// The operand size override prefix, 0x66. Some instructions use it as a mandatory prefix.
+    OperandSizePrefix,
+    /// A lock or repeat prefix, 0xF0, 0xF2 or 0xF3. Some instructions use it as a mandatory prefix.
+    LockRepPrefix(u8),
+    /// A REX prefix.
+    Rex { w: bool, r: Bits, x: Bits, b: Bits },
+    /// A VEX or XOP prefix. The register extension bits and vvvv are listed as they are used,
+    /// not inverted as they are encoded.
+    Vex { xop: bool, two_byte: bool, map_sel: u8, w: bool, l: bool, pp: u8, r: Bits, x: Bits, b: Bits, vvvv: Bits },
+    /// Opcode bytes.
+    Opcode,
+    /// An opcode byte that has a register encoded in its low bits.
+    OpcodeRegister { reg: Bits },
+    /// A ModRM byte.
+    ModRm { mode: u8, reg: Bits, rm: Bits },
+    /// A SIB byte.
+    Sib { scale: Bits, index: Bits, base: Bits },
+    /// An address displacement.
+    Displacement,
+    /// An opcode byte that is encoded in the position of an immediate.
+    ImmediateOpcode(u8),
+    /// An immediate byte with a register encoded in its high bits.
+    RegisterImmediate { reg: Bits },
+    /// An immediate or a jump offset.
+    Immediate,
+}
+
+/// The value of a field that is only known at runtime if it is derived from a dynamic register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bits {
+    Static(u8),
+    Dynamic,
+}
+
+/// A relocation that will be applied to the encoded instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Relocation {
+    /// The offset of the relocated field from the start of the instruction, in bytes.
+    pub offset: usize,
+    /// The size of the relocated field.
+    pub size: Size,
+    /// How the target is encoded.
+    pub kind: RelocationKind,
+}
+
+impl Bits {
+    /// The bits of a register number selected by `mask`, shifted down to the lowest bit.
+    pub(super) fn register(kind: &RegKind, mask: u8) -> Bits {
+        match kind {
+            RegKind::Static(id) => Bits::Static((id.code() & mask) >> mask.trailing_zeros()),
+            RegKind::Dynamic(_, _) => Bits::Dynamic,
+        }
+    }
+}
+
+/// Collects the fields of an instruction while it is being compiled.
+#[derive(Debug, Default)]
+pub(super) struct Explainer {
+    form: Option<Form>,
+    fields: Vec<Field>,
+    relocations: Vec<Relocation>,
+    len: usize,
+}
+
+impl Explainer {
+    /// The explanation of the compiled instruction. Returns `None` if compilation did not finish.
+    pub(super) fn finish(self) -> Option<Explanation> {
+        Some(Explanation {
+            form: self.form?,
+            fields: self.fields,
+            relocations: self.relocations,
+        })
+    }
+}
+
+impl<'a> Context<'a> {
+    /// Pushes a statement, attributing it to the last announced field.
+    pub(super) fn push(&mut self, stmt: Stmt) {
+        let len = match &stmt {
+            Stmt::Const(value) => usize::from(value.size().in_bytes()),
+            Stmt::Extend(bytes) => bytes.len(),
+            _ => 0
+        };
+        self.record(len);
+        self.state.push(stmt);
+    }
+
+    /// Appends bytes, attributing them to the last announced field.
+    pub(super) fn extend(&mut self, bytes: &[u8]) {
+        self.record(bytes.len());
+        self.state.extend(bytes);
+    }
+
+    fn record(&mut self, len: usize) {
+        if let Some(explainer) = self.explainer.as_mut() {
+            if let Some(field) = explainer.fields.last_mut() {
+                field.len += len;
+            }
+            explainer.len += len;
+        }
+    }
+
+    /// Announces the field that the following statements encode.
+    pub(super) fn explain(&mut self, kind: FieldKind) {
+        if let Some(explainer) = self.explainer.as_mut() {
+            let offset = explainer.len;
+            explainer.fields.push(Field { offset, len: 0, kind });
+        }
+    }
+
+    /// Records the form that was chosen for the instruction.
+    pub(super) fn explain_form(&mut self, mnemonic: &str, data: &'static Opdata) {
+        if let Some(explainer) = self.explainer.as_mut() {
+            explainer.form = Form::new(mnemonic, data);
+        }
+    }
+
+    /// Records a relocation of a field that ends `from_end` bytes before the end of the instruction.
+    pub(super) fn explain_relocation(&mut self, from_end: u8, size: Size, kind: RelocationKind) {
+        if let Some(explainer) = self.explainer.as_mut() {
+            let offset = explainer.len - usize::from(from_end) - usize::from(size.in_bytes());
+            explainer.relocations.push(Relocation { offset, size, kind });
+        }
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for syntax in self.form.syntax() {
+            writeln!(f, "{}", syntax.trim_end())?;
+        }
+        for field in &self.fields {
+            writeln!(f, "{:4} {:2}  {}", field.offset, field.len, field.kind)?;
+        }
+        for relocation in &self.relocations {
+            writeln!(f, "{:4} {:2}  relocation ({:?})", relocation.offset, relocation.size.in_bytes(), relocation.kind)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for FieldKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FieldKind::SegmentPrefix(byte) => write!(f, "segment prefix {:02X}", byte),
+            FieldKind::AddressSizePrefix => write!(f, "address size prefix 67"),
+            FieldKind::OperandSizePrefix => write!(f, "operand size prefix 66"),
+            FieldKind::LockRepPrefix(byte) => write!(f, "lock/rep prefix {:02X}", byte),
+            FieldKind::Rex { w, r, x, b } => write!(f, "REX W={} R={} X={} B={}", u8::from(w), r, x, b),
+            FieldKind::Vex { xop, two_byte, map_sel, w, l, pp, r, x, b, vvvv } => write!(f,
+                "{}{} map={} W={} L={} pp={} R={} X={} B={} vvvv={}",
+                if xop { "XOP" } else { "VEX" }, if two_byte { " (2 bytes)" } else { "" },
+                map_sel, u8::from(w), u8::from(l), pp, r, x, b, vvvv
+            ),
+            FieldKind::Opcode => write!(f, "opcode"),
+            FieldKind::OpcodeRegister { reg } => write!(f, "opcode + reg={}", reg),
+            FieldKind::ModRm { mode, reg, rm } => write!(f, "ModRM mod={} reg={} rm={}", mode, reg, rm),
+            FieldKind::Sib { scale, index, base } => write!(f, "SIB scale={} index={} base={}", scale, index, base),
+            FieldKind::Displacement => write!(f, "displacement"),
+            FieldKind::ImmediateOpcode(byte) => write!(f, "opcode {:02X} in immediate position", byte),
+            FieldKind::RegisterImmediate { reg } => write!(f, "immediate + reg={}", reg),
+            FieldKind::Immediate => write!(f, "immediate"),
+        }
+    }
+}
+
+impl fmt::Display for Bits {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Bits::Static(bits) => write!(f, "{}", bits),
+            Bits::Dynamic => write!(f, "?"),
+        }
+    }
+}
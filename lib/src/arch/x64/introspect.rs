@@ -0,0 +1,305 @@
+//! Public, read-only view of the instruction tables used by the assembler.
+//!
+//! Every mnemonic maps to a list of forms, each describing one operand combination the
+//! assembler accepts. This allows code generators to check which operand combinations are legal
+//! before emitting an instruction, instead of finding out through an assembly error.
+
+use super::X86Mode;
+use super::ast::{RegFamily, RegId};
+use super::compiler::{Opdata, FormatStringIterator};
+use super::debug::format_opdata;
+use super::semantics::Semantics;
+use super::x64data::{Flags, Features, get_mnemnonic_entry, mnemnonics};
+use crate::common::Size;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// A single operand combination that an instruction accepts.
+#[derive(Debug, Clone, Copy)]
+pub struct Form {
+    mnemonic: &'static str,
+    data: &'static Opdata,
+}
+
+/// A description of a single operand of a form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Operand {
+    pub kind: OperandKind,
+    pub size: OperandSize,
+}
+
+/// What kind of argument can be used for an operand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperandKind {
+    /// An immediate.
+    Immediate,
+    /// A jump target, encoded as an offset relative to the end of the instruction.
+    Offset,
+    /// A memory reference.
+    Memory,
+    /// A memory reference using a vector register as index (VSIB addressing). The element size
+    /// of the index register is given, while the operand size determines if an xmm or ymm
+    /// register is used.
+    VsibMemory(Size),
+    /// Any register of the given family.
+    Register(RegFamily),
+    /// Any register of the given family, or a memory reference.
+    RegisterOrMemory(RegFamily),
+    /// Only this specific register.
+    FixedRegister(RegId),
+}
+
+/// What size an operand can have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperandSize {
+    /// Exactly this size.
+    Fixed(Size),
+    /// The operand size of the instruction. See `Form::operand_sizes` for the options.
+    Auto,
+    /// The operand can have any size, and doesn't affect the operand size of the instruction.
+    Any,
+    /// The operand can't have a size. This is only used for memory references.
+    Unsized,
+}
+
+/// Returns all mnemonics known to the assembler, in alphabetical order.
+pub fn mnemonics() -> impl Iterator<Item=&'static str> {
+    mnemnonics()
+}
+
+/// Returns the forms of a mnemonic, in the order in which the assembler tries to match them.
+pub fn forms(mnemonic: &str) -> Option<impl Iterator<Item=Form>> {
+    let (mnemonic, data) = get_mnemnonic_entry(mnemonic)?;
+    Some(data.iter().map(move |data| Form { mnemonic, data }))
+}
+
+impl Form {
+    pub(super) fn new(mnemonic: &str, data: &'static Opdata) -> Option<Form> {
+        let (mnemonic, _) = get_mnemnonic_entry(mnemonic)?;
+        Some(Form { mnemonic, data })
+    }
+
+    /// The mnemonic this form belongs to.
+    pub fn mnemonic(&self) -> &'static str {
+        self.mnemonic
+    }
+
+    /// The operands of this form.
+    pub fn operands(&self) -> impl Iterator<Item=Operand> {
+        FormatStringIterator::new(self.data.args).map(|(code, size)| Operand {
+            kind: operand_kind(code, size),
+            size: match size {
+                b'*' => OperandSize::Auto,
+                b'?' => OperandSize::Any,
+                b'!' => OperandSize::Unsized,
+                size => OperandSize::Fixed(format_size(size))
+            }
+        })
+    }
+
+    /// The operand sizes that operands of size `OperandSize::Auto` can have in the given mode.
+    /// Immediates and offsets are at most a dword in size. Returns an empty slice if this form
+    /// has no such operands.
+    pub fn operand_sizes(&self, mode: X86Mode) -> &'static [Size] {
+        let flags = self.data.flags;
+        let sizes: &'static [Size] = if flags.contains(Flags::AUTO_SIZE) {
+            &[Size::WORD, Size::DWORD, Size::QWORD]
+        } else if flags.contains(Flags::AUTO_NO32) {
+            match mode {
+                X86Mode::Long => &[Size::WORD, Size::QWORD],
+                X86Mode::Protected => &[Size::WORD, Size::DWORD],
+            }
+        } else if flags.contains(Flags::AUTO_REXW) {
+            &[Size::DWORD, Size::QWORD]
+        } else if flags.contains(Flags::AUTO_VEXL) {
+            &[Size::OWORD, Size::HWORD]
+        } else {
+            // without any size flags, the size isn't encoded and anything the operands allow matches
+            let mut auto = self.operands().filter(|o| o.size == OperandSize::Auto);
+            match auto.next() {
+                Some(Operand { kind: OperandKind::Register(RegFamily::XMM), .. }) |
+                Some(Operand { kind: OperandKind::RegisterOrMemory(RegFamily::XMM), .. }) => &[Size::OWORD, Size::HWORD],
+                Some(_) => &[Size::WORD, Size::DWORD, Size::QWORD],
+                None => &[]
+            }
+        };
+
+        match mode {
+            X86Mode::Protected if sizes.last() == Some(&Size::QWORD) => &sizes[.. sizes.len() - 1],
+            _ => sizes
+        }
+    }
+
+    /// The cpu features required by this form.
+    pub fn features(&self) -> Features {
+        self.data.features
+    }
+
+    /// The effects of this form: which operands, registers and flags it reads and writes.
+    /// Returns `None` for the few instructions of which these aren't known.
+    pub fn semantics(&self) -> Option<Semantics> {
+        self.data.semantics
+    }
+
+    /// The encoding flags of this form.
+    pub fn flags(&self) -> Flags {
+        self.data.flags
+    }
+
+    /// The opcode bytes of this form. For VEX/XOP encoded forms, the first byte is the opcode map.
+    pub fn opcode(&self) -> &'static [u8] {
+        self.data.ops
+    }
+
+    /// The value that is encoded in the reg field of the ModRM byte, if the form uses it as an
+    /// opcode extension.
+    pub fn opcode_extension(&self) -> Option<u8> {
+        if self.data.reg == 0xFF {
+            None
+        } else {
+            Some(self.data.reg)
+        }
+    }
+
+    /// A human readable description of the syntax of this form, listed once for every possible
+    /// operand size.
+    pub fn syntax(&self) -> Vec<String> {
+        format_opdata(self.mnemonic, self.data)
+            .into_iter()
+            .map(|form| form.replace(">>> ", ""))
+            .collect()
+    }
+
+    /// Returns whether this form can be assembled in the given mode.
+    pub fn available_in(&self, mode: X86Mode) -> bool {
+        let flags = self.data.flags;
+        match mode {
+            X86Mode::Long => !flags.contains(Flags::X86_ONLY),
+            X86Mode::Protected => {
+                // legacy encoded forms can't set REX.W, and there are no 64-bit general purpose registers
+                let needs_rex_w = flags.contains(Flags::WITH_REXW) && !flags.intersects(Flags::VEX_OP | Flags::XOP_OP);
+                let needs_qword_register = FormatStringIterator::new(self.data.args)
+                    .any(|(code, size)| matches!(code, b'r' | b'A' ..= b'P') && size == b'q');
+                !needs_rex_w && !needs_qword_register
+            }
+        }
+    }
+}
+
+fn operand_kind(code: u8, size: u8) -> OperandKind {
+    match code {
+        b'i' => OperandKind::Immediate,
+        b'o' => OperandKind::Offset,
+        b'm' => OperandKind::Memory,
+        b'k' => OperandKind::VsibMemory(Size::DWORD),
+        b'l' => OperandKind::VsibMemory(Size::QWORD),
+        // byte sized legacy registers also include ah, ch, dh and bh
+        b'r' => OperandKind::Register(RegFamily::LEGACY),
+        b'f' => OperandKind::Register(RegFamily::FP),
+        b'x' => OperandKind::Register(RegFamily::MMX),
+        b'y' => OperandKind::Register(RegFamily::XMM),
+        b's' => OperandKind::Register(RegFamily::SEGMENT),
+        b'c' => OperandKind::Register(RegFamily::CONTROL),
+        b'd' => OperandKind::Register(RegFamily::DEBUG),
+        b'b' => OperandKind::Register(RegFamily::BOUND),
+        b'v' => OperandKind::RegisterOrMemory(RegFamily::LEGACY),
+        b'u' => OperandKind::RegisterOrMemory(RegFamily::MMX),
+        b'w' => OperandKind::RegisterOrMemory(RegFamily::XMM),
+        b'A' ..= b'P' => OperandKind::FixedRegister(RegId::from_number(code - b'A')),
+        b'Q' ..= b'V' => OperandKind::FixedRegister(
+            [RegId::ES, RegId::CS, RegId::SS, RegId::DS, RegId::FS, RegId::GS][usize::from(code - b'Q')]
+        ),
+        b'W' => OperandKind::FixedRegister(RegId::CR8),
+        b'X' => OperandKind::FixedRegister(RegId::ST0),
+        _ => panic!("invalid formatting data {}{}", code as char, size as char)
+    }
+}
+
+fn format_size(size: u8) -> Size {
+    match size {
+        b'b' => Size::BYTE,
+        b'w' => Size::WORD,
+        b'd' => Size::DWORD,
+        b'f' => Size::FWORD,
+        b'q' => Size::QWORD,
+        b'p' => Size::PWORD,
+        b'o' => Size::OWORD,
+        b'h' => Size::HWORD,
+        _ => panic!("invalid formatting data {}", size as char)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(mnemonic: &str, operands: &[Operand]) -> Form {
+        forms(mnemonic).unwrap()
+            .find(|form| form.operands().eq(operands.iter().cloned()))
+            .unwrap_or_else(|| panic!("no matching form of {}", mnemonic))
+    }
+
+    #[test]
+    fn every_mnemonic_has_forms() {
+        let names: Vec<_> = mnemonics().collect();
+        assert!(names.windows(2).all(|w| w[0] < w[1]));
+        for name in names {
+            let mut forms = forms(name).unwrap_or_else(|| panic!("no forms for {}", name)).peekable();
+            assert!(forms.peek().is_some(), "{}", name);
+            assert!(forms.all(|form| form.mnemonic() == name), "{}", name);
+        }
+        assert!(forms("nonexistent").is_none());
+    }
+
+    #[test]
+    fn add_rm32_imm8() {
+        let imm8 = Operand { kind: OperandKind::Immediate, size: OperandSize::Fixed(Size::BYTE) };
+        let reg = Operand { kind: OperandKind::Register(RegFamily::LEGACY), size: OperandSize::Auto };
+        let mem = Operand { kind: OperandKind::Memory, size: OperandSize::Auto };
+
+        for form in [find("add", &[reg, imm8]), find("add", &[mem, imm8])].iter() {
+            assert_eq!(form.opcode(), &[0x83]);
+            assert_eq!(form.opcode_extension(), Some(0));
+            assert_eq!(form.features(), Features::empty());
+            assert_eq!(form.operand_sizes(X86Mode::Long), &[Size::WORD, Size::DWORD, Size::QWORD]);
+            assert_eq!(form.operand_sizes(X86Mode::Protected), &[Size::WORD, Size::DWORD]);
+            assert!(form.available_in(X86Mode::Long));
+            assert!(form.available_in(X86Mode::Protected));
+        }
+    }
+
+    #[test]
+    fn avx_form() {
+        let xmm = Operand { kind: OperandKind::Register(RegFamily::XMM), size: OperandSize::Auto };
+        let xmm_mem = Operand { kind: OperandKind::RegisterOrMemory(RegFamily::XMM), size: OperandSize::Auto };
+
+        let form = find("vaddps", &[xmm, xmm, xmm_mem]);
+        assert_eq!(form.features(), Features::AVX);
+        assert!(form.flags().contains(Flags::VEX_OP));
+        assert_eq!(form.operand_sizes(X86Mode::Long), &[Size::OWORD, Size::HWORD]);
+        assert_eq!(form.operand_sizes(X86Mode::Protected), &[Size::OWORD, Size::HWORD]);
+        assert!(form.available_in(X86Mode::Long));
+        assert!(form.available_in(X86Mode::Protected));
+    }
+
+    #[test]
+    fn x86_only_form() {
+        let form = find("aaa", &[]);
+        assert_eq!(form.features(), Features::empty());
+        assert_eq!(form.operand_sizes(X86Mode::Protected), &[]);
+        assert!(!form.available_in(X86Mode::Long));
+        assert!(form.available_in(X86Mode::Protected));
+    }
+
+    #[test]
+    fn qword_register_form() {
+        let form = find("adcx", &[
+            Operand { kind: OperandKind::Register(RegFamily::LEGACY), size: OperandSize::Fixed(Size::QWORD) },
+            Operand { kind: OperandKind::RegisterOrMemory(RegFamily::LEGACY), size: OperandSize::Fixed(Size::QWORD) },
+        ]);
+        assert!(form.available_in(X86Mode::Long));
+        assert!(!form.available_in(X86Mode::Protected));
+    }
+}
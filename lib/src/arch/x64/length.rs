@@ -0,0 +1,118 @@
+//! Predicting the length of an instruction without emitting it.
+//!
+//! The encoder picks the size of every field while compiling: displacements and immediates that
+//! are only known at runtime are always given the size of their operand, and dynamic registers
+//! are encoded with the prefixes and escape bytes that any register of their family could need.
+//! Because of that, the length of an encoded instruction never depends on runtime values, and it
+//! can be computed by running the compiler against a builder that only counts bytes.
+
+use super::{compiler, Archx64, Archx86, Context, Error, InstructionX64, InstructionX86, X86Mode};
+use super::x64data::Features;
+use crate::arch::{BasicExprBuilder, ErrorSpan};
+use crate::common::{Expr, Stmt, Value};
+
+use core::fmt;
+
+use alloc::string::{String, ToString};
+
+impl Archx64 {
+    /// Returns the number of bytes `instruction` will occupy once it is compiled, without
+    /// emitting any statements. Instructions using dynamic registers, displacements, immediates
+    /// or jump targets always compile to the same length, so the result is exact.
+    pub fn predict_length(&self, instruction: &InstructionX64) -> Result<usize, Error> {
+        predict_length(X86Mode::Long, self.features, &instruction.inst, &instruction.args)
+    }
+}
+
+impl Archx86 {
+    /// Returns the number of bytes `instruction` will occupy once it is compiled, without
+    /// emitting any statements. Instructions using dynamic registers, displacements, immediates
+    /// or jump targets always compile to the same length, so the result is exact.
+    pub fn predict_length(&self, instruction: &InstructionX86) -> Result<usize, Error> {
+        predict_length(X86Mode::Protected, self.features, &instruction.inst, &instruction.args)
+    }
+}
+
+fn predict_length(mode: X86Mode, features: Features, inst: &super::ast::Instruction, args: &[super::ast::CleanArg])
+    -> Result<usize, Error>
+{
+    let mut counter = LengthCounter::default();
+    let ctx = Context {
+        state: &mut counter,
+        mode,
+        features,
+        explainer: None,
+    };
+
+    let result = compiler::compile_instruction(ctx, inst.clone(), args.to_vec());
+
+    // errors can be emitted without failing compilation, but they do fail the macro
+    if let Some(message) = counter.error {
+        return Err(message.into());
+    }
+    result?;
+    Ok(counter.len)
+}
+
+/// An expression builder that counts the emitted bytes. Like the builder of the front end, it
+/// accepts any combination of expressions, combined expressions keep the representation of the
+/// dynamic operand, and any emitted error makes the instruction fail.
+#[derive(Default)]
+pub(super) struct LengthCounter {
+    len: usize,
+    error: Option<String>,
+}
+
+impl LengthCounter {
+    fn combine(a: Expr, _: Value) -> Option<Expr> {
+        Some(a)
+    }
+}
+
+impl BasicExprBuilder for LengthCounter {
+    fn push(&mut self, stmt: Stmt) {
+        self.len += match stmt {
+            Stmt::Const(value) => usize::from(value.size().in_bytes()),
+            Stmt::Extend(bytes) => bytes.len(),
+            _ => 0
+        };
+    }
+
+    fn bit_or(&mut self, a: Expr, b: Value) -> Option<Expr> {
+        LengthCounter::combine(a, b)
+    }
+
+    fn bit_and(&mut self, a: Expr, b: Value) -> Option<Expr> {
+        LengthCounter::combine(a, b)
+    }
+
+    fn bit_xor(&mut self, a: Expr, b: Value) -> Option<Expr> {
+        LengthCounter::combine(a, b)
+    }
+
+    fn add(&mut self, a: Expr, b: Value) -> Option<Expr> {
+        LengthCounter::combine(a, b)
+    }
+
+    fn mul(&mut self, a: Expr, b: Value) -> Option<Expr> {
+        LengthCounter::combine(a, b)
+    }
+
+    fn neg(&mut self, a: Expr) -> Option<Expr> {
+        Some(a)
+    }
+
+    fn log2(&mut self, a: Expr) -> Option<Expr> {
+        Some(a)
+    }
+
+    fn mask_shift(&mut self, val: Expr, _: u64, _: i8) -> Option<Expr> {
+        Some(val)
+    }
+
+    fn emit_error_at(&mut self, _: ErrorSpan, args: fmt::Arguments) {
+        if self.error.is_none() {
+            self.error = Some(args.to_string());
+        }
+    }
+}
@@ -3,15 +3,36 @@ mod compiler;
 pub mod parser;
 mod debug;
 mod x64data;
+mod decoder;
+mod explain;
+mod length;
+mod encoder;
+mod semantics;
+mod requirements;
+mod placeholder;
+pub mod introspect;
+pub mod typed;
+
+use alloc::borrow::Cow;
+use core::fmt;
 
-use std::borrow::Cow;
-
-use crate::BasicAssembler;
 use crate::arch::{Arch, Error as ExprBuilderError, BasicExprBuilder};
-use crate::common::{Size, Stmt, Jump};
+use crate::common::{Size, Stmt, Jump, similar_names};
 
 #[cfg(feature = "dynasm_opmap")]
 pub use debug::create_opmap;
+pub use decoder::{Decoded, decode, disassemble_listing};
+pub use explain::{Explanation, Field, FieldKind, Bits, Relocation, RelocationKind};
+pub use encoder::MAX_INSTRUCTION_LEN;
+pub use semantics::{RegSet, Rflags, Semantics};
+pub use requirements::Requirements;
+pub use placeholder::{Operand, RegClass};
+pub use crate::arch::Access;
+pub use x64data::{Features, Flags};
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::format;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum X86Mode {
@@ -22,7 +43,8 @@ pub enum X86Mode {
 struct Context<'a> {
     pub state: &'a mut dyn BasicExprBuilder,
     pub mode: X86Mode,
-    pub features: x64data::Features
+    pub features: x64data::Features,
+    pub explainer: Option<&'a mut explain::Explainer>,
 }
 
 #[derive(Clone, Debug)]
@@ -64,6 +86,10 @@ pub trait AssembleX64 {
     /// May error when dynamic data is present at bad locations such as memory address scaling.
     fn compile_instruction(&mut self, arch: &Archx64, _: InstructionX64) -> Result<(), Error>;
 
+    /// Like `compile_instruction`, but also returns a description of the encoding that was
+    /// chosen and of every field that was emitted.
+    fn explain_instruction(&mut self, arch: &Archx64, _: InstructionX64) -> Result<Explanation, Error>;
+
     /// Create an instruction composed from dynamic data.
     /// Only available when the type is also capable of building new composite expressions.
     fn build_instruction(&mut self, arch: &Archx64, _: InstructionX64) -> Result<(), Error>
@@ -75,6 +101,10 @@ pub trait AssembleX86 {
     /// May error when dynamic data is present at bad locations such as memory address scaling.
     fn compile_instruction(&mut self, arch: &Archx86, _: InstructionX86) -> Result<(), Error>;
 
+    /// Like `compile_instruction`, but also returns a description of the encoding that was
+    /// chosen and of every field that was emitted.
+    fn explain_instruction(&mut self, arch: &Archx86, _: InstructionX86) -> Result<Explanation, Error>;
+
     /// Create an instruction composed from dynamic data.
     /// Only available when the type is also capable of building new composite expressions.
     fn build_instruction(&mut self, arch: &Archx86, _: InstructionX86) -> Result<(), Error>
@@ -114,11 +144,69 @@ pub enum Error {
     Generic {
         message: Cow<'static, str>,
     },
+    /// A mnemonic, register, prefix or feature name that does not exist.
+    UnknownName {
+        /// What the name was used as.
+        kind: NameKind,
+        /// The name as it was written.
+        name: String,
+        /// Hints about what might have been meant instead.
+        notes: Vec<Note>,
+    },
+    /// The instruction compiled, but the effects of the form it uses aren't known.
+    NoSemantics {
+        /// The mnemonic of the instruction.
+        mnemonic: &'static str,
+    },
     /// Some unspecified consistency check did not succeed.
     /// When this occurs we have emitted one or several diagnostic messages.
     Fatal,
 }
 
+/// The different kinds of names that are looked up while assembling.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NameKind {
+    Mnemonic,
+    Register,
+    Prefix,
+    Feature,
+}
+
+/// A hint attached to an `Error::UnknownName`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Note {
+    /// A known name that is spelled similarly.
+    DidYouMean(&'static str),
+    /// The name is known, but can only be used in the given mode.
+    OnlyAvailableIn(X86Mode),
+}
+
+/// Formats the similar names among the notes as a suffix for an error message.
+fn did_you_mean(notes: &[Note]) -> String {
+    let names: Vec<_> = notes.iter().filter_map(|note| match note {
+        Note::DidYouMean(name) => Some(format!("'{}'", name)),
+        _ => None
+    }).collect();
+
+    if names.is_empty() {
+        String::new()
+    } else {
+        format!(", did you mean {}?", names.join(" or "))
+    }
+}
+
+impl Error {
+    fn unknown_name<I>(kind: NameKind, name: &str, candidates: I) -> Error
+        where I: IntoIterator<Item=&'static str>
+    {
+        Error::UnknownName {
+            kind,
+            name: name.to_string(),
+            notes: similar_names(name, candidates).into_iter().map(Note::DidYouMean).collect(),
+        }
+    }
+}
+
 impl From<ExprBuilderError> for Error {
     fn from(err: ExprBuilderError) -> Self {
         Error::Expr(err)
@@ -133,6 +221,57 @@ impl From<&'static str> for Error {
     }
 }
 
+impl fmt::Display for X86Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            X86Mode::Long => "long mode",
+            X86Mode::Protected => "protected mode",
+        })
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Expr(e) => e.fmt(f),
+            Error::DisabledFeatures(features) =>
+                write!(f, "This instruction uses features that are not indicated to be available: {}", features),
+            Error::UnsupportedOperandInThisMode { operand, op_size, mode, mode_hint } => {
+                write!(f, "{} of size {} is not supported in {}", operand, op_size.in_bytes(), mode)?;
+                match mode_hint {
+                    Some(hint) => write!(f, ", only in {}", hint),
+                    None => Ok(())
+                }
+            },
+            Error::UnsupportedInThisMode { message, mode_hint } => {
+                f.write_str(message)?;
+                match mode_hint {
+                    Some(hint) => write!(f, ", only in {}", hint),
+                    None => Ok(())
+                }
+            },
+            Error::Generic { message } => f.write_str(message),
+            Error::UnknownName { kind, name, notes } => {
+                let kind = match kind {
+                    NameKind::Mnemonic => "instruction",
+                    NameKind::Register => "register",
+                    NameKind::Prefix => "prefix",
+                    NameKind::Feature => "feature",
+                };
+                write!(f, "Unknown {} '{}'{}", kind, name, did_you_mean(notes))?;
+                for note in notes {
+                    if let Note::OnlyAvailableIn(mode) = note {
+                        write!(f, ", only available in {}", mode)?;
+                    }
+                }
+                Ok(())
+            },
+            Error::NoSemantics { mnemonic } => write!(f, "The effects of '{}' are not known", mnemonic),
+            Error::Fatal => f.write_str("Failed to assemble the instruction"),
+        }
+    }
+}
+
 impl From<String> for Error {
     fn from(message: String) -> Self {
         Error::Generic {
@@ -149,12 +288,13 @@ impl Arch for Archx64 {
     fn set_features(&mut self, features: &[String]) {
         let mut new_features = x64data::Features::empty();
         for ident in features {
-            new_features |= match x64data::Features::from_str(&ident.to_string()) {
-                Some(feature) => feature,
-                None => {
-                    eprintln!("Architecture x64 does not support feature '{}'", ident.to_string());
+            new_features |= match parser::lookup_feature(ident) {
+                Ok(feature) => feature,
+                Err(Error::UnknownName { notes, .. }) => {
+                    eprintln!("Architecture x64 does not support feature '{}'{}", ident, did_you_mean(&notes));
                     continue;
                 }
+                Err(_) => unreachable!()
             }
         }
         self.features = new_features;
@@ -172,7 +312,7 @@ impl Arch for Archx64 {
     }
 }
 
-impl AssembleX64 for BasicAssembler {
+impl<T: BasicExprBuilder> AssembleX64 for T {
     fn compile_instruction(&mut self, arch: &Archx64, instruction: InstructionX64) -> Result<(), Error> {
         let InstructionX64 { inst, args } = instruction;
 
@@ -180,15 +320,31 @@ impl AssembleX64 for BasicAssembler {
             state: self,
             mode: X86Mode::Long,
             features: arch.features,
+            explainer: None,
         };
 
         compiler::compile_instruction(ctx, inst, args)
     }
 
-    fn build_instruction(&mut self, _: &Archx64, _: InstructionX64) -> Result<(), Error>
+    fn explain_instruction(&mut self, arch: &Archx64, instruction: InstructionX64) -> Result<Explanation, Error> {
+        let InstructionX64 { inst, args } = instruction;
+
+        let mut explainer = explain::Explainer::default();
+        let ctx = Context {
+            state: self,
+            mode: X86Mode::Long,
+            features: arch.features,
+            explainer: Some(&mut explainer),
+        };
+
+        compiler::compile_instruction(ctx, inst, args)?;
+        Ok(explainer.finish().expect("compiled instruction without a form"))
+    }
+
+    fn build_instruction(&mut self, arch: &Archx64, instruction: InstructionX64) -> Result<(), Error>
         where Self: BasicExprBuilder
     {
-        unreachable!("Statically uncallable, Self is not BasicExprBuilder")
+        AssembleX64::compile_instruction(self, arch, instruction)
     }
 }
 
@@ -200,12 +356,13 @@ impl Arch for Archx86 {
     fn set_features(&mut self, features: &[String]) {
         let mut new_features = x64data::Features::empty();
         for ident in features {
-            new_features |= match x64data::Features::from_str(&ident.to_string()) {
-                Some(feature) => feature,
-                None => {
-                    eprintln!("Architecture x86 does not support feature '{}'", ident.to_string());
+            new_features |= match parser::lookup_feature(ident) {
+                Ok(feature) => feature,
+                Err(Error::UnknownName { notes, .. }) => {
+                    eprintln!("Architecture x86 does not support feature '{}'{}", ident, did_you_mean(&notes));
                     continue;
                 }
+                Err(_) => unreachable!()
             }
         }
         self.features = new_features;
@@ -223,7 +380,7 @@ impl Arch for Archx86 {
     }
 }
 
-impl AssembleX86 for BasicAssembler {
+impl<T: BasicExprBuilder> AssembleX86 for T {
     fn compile_instruction(&mut self, arch: &Archx86, instruction: InstructionX86) -> Result<(), Error> {
         let InstructionX86 { inst, args } = instruction;
 
@@ -231,14 +388,99 @@ impl AssembleX86 for BasicAssembler {
             state: self,
             mode: X86Mode::Protected,
             features: arch.features,
+            explainer: None,
         };
 
         compiler::compile_instruction(ctx, inst, args)
     }
 
-    fn build_instruction(&mut self, _: &Archx86, _: InstructionX86) -> Result<(), Error>
+    fn explain_instruction(&mut self, arch: &Archx86, instruction: InstructionX86) -> Result<Explanation, Error> {
+        let InstructionX86 { inst, args } = instruction;
+
+        let mut explainer = explain::Explainer::default();
+        let ctx = Context {
+            state: self,
+            mode: X86Mode::Protected,
+            features: arch.features,
+            explainer: Some(&mut explainer),
+        };
+
+        compiler::compile_instruction(ctx, inst, args)?;
+        Ok(explainer.finish().expect("compiled instruction without a form"))
+    }
+
+    fn build_instruction(&mut self, arch: &Archx86, instruction: InstructionX86) -> Result<(), Error>
         where Self: BasicExprBuilder
     {
-        unreachable!("Statically uncallable, Self is not BasicExprBuilder")
+        AssembleX86::compile_instruction(self, arch, instruction)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::typed::inst;
+
+    fn unknown_name(error: Error) -> (NameKind, String, Vec<Note>) {
+        match error {
+            Error::UnknownName { kind, name, notes } => (kind, name, notes),
+            error => panic!("expected an unknown name, found {:?}", error)
+        }
+    }
+
+    #[test]
+    fn unknown_mnemonic() {
+        let error = Archx64::default().predict_length(&inst("addd", [])).unwrap_err();
+        assert_eq!(error.to_string(), "Unknown instruction 'addd', did you mean 'add' or 'addpd' or 'addsd'?");
+        let (kind, name, notes) = unknown_name(error);
+        assert_eq!(kind, NameKind::Mnemonic);
+        assert_eq!(name, "addd");
+        assert_eq!(notes, [Note::DidYouMean("add"), Note::DidYouMean("addpd"), Note::DidYouMean("addsd")]);
+
+        let error = Archx64::default().predict_length(&inst("qwertyuiop", [])).unwrap_err();
+        assert_eq!(error.to_string(), "Unknown instruction 'qwertyuiop'");
+        assert_eq!(unknown_name(error).2, []);
+    }
+
+    #[test]
+    fn unknown_prefix() {
+        let error = Archx64::default().predict_length(&inst("movsb", []).prefix("rex")).unwrap_err();
+        assert_eq!(error.to_string(), "Unknown prefix 'rex', did you mean 'rep'?");
+        let (kind, _, notes) = unknown_name(error);
+        assert_eq!(kind, NameKind::Prefix);
+        assert_eq!(notes, [Note::DidYouMean("rep")]);
+
+        assert_eq!(unknown_name(parser::lookup_prefix("rex").unwrap_err()).2, [Note::DidYouMean("rep")]);
+    }
+
+    #[test]
+    fn unknown_register() {
+        let error = parser::lookup_register("eaz", X86Mode::Long).unwrap_err();
+        assert_eq!(error.to_string(), "Unknown register 'eaz', did you mean 'eax'?");
+        let (kind, _, notes) = unknown_name(error);
+        assert_eq!(kind, NameKind::Register);
+        assert_eq!(notes, [Note::DidYouMean("eax")]);
+    }
+
+    #[test]
+    fn register_in_other_mode() {
+        assert!(parser::lookup_register("r8", X86Mode::Long).is_ok());
+
+        let error = parser::lookup_register("r8", X86Mode::Protected).unwrap_err();
+        assert_eq!(error.to_string(), "Unknown register 'r8', only available in long mode");
+        let (kind, name, notes) = unknown_name(error);
+        assert_eq!(kind, NameKind::Register);
+        assert_eq!(name, "r8");
+        assert_eq!(notes, [Note::OnlyAvailableIn(X86Mode::Long)]);
+    }
+
+    #[test]
+    fn unknown_feature() {
+        let error = parser::lookup_feature("avx3").unwrap_err();
+        assert_eq!(error.to_string(), "Unknown feature 'avx3', did you mean 'avx' or 'avx2'?");
+        let (kind, _, notes) = unknown_name(error);
+        assert_eq!(kind, NameKind::Feature);
+        assert_eq!(notes, [Note::DidYouMean("avx"), Note::DidYouMean("avx2")]);
     }
 }
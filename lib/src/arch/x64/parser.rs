@@ -1,13 +1,16 @@
 //! Common map and table definitions useful for parsers.
-use lazy_static::lazy_static;
 
-use crate::common::Size;
+use crate::common::{Size, NameTable, name_table_slots};
 use super::ast::{RegId, RegFamily};
+use super::x64data::Features;
+use super::{Error, NameKind, Note, X86Mode};
 
 use self::RegId::*;
 use crate::common::Size::*;
 
-use std::collections::HashMap;
+use alloc::string::ToString;
+use alloc::vec;
+
 
 /// A list of valid x86 prefixes
 pub const PREFIXES: [&str; 12] = [
@@ -259,7 +262,7 @@ pub const X64_REGISTERS: &[(&str, (RegId, Size))] = &[
     ("bnd3", (BND3, OWORD)),
 ];
 
-static X86_REGISTERS: &[(&str, (RegId, Size))] = &[
+const X86_REGISTERS: &[(&str, (RegId, Size))] = &[
     ("eax", (RAX, DWORD)),
     ("ecx", (RCX, DWORD)),
     ("edx", (RDX, DWORD)),
@@ -390,17 +393,147 @@ pub const X86_FAMILIES: &[(&str, (Size, RegFamily))] = &[
     ("RB",(Size::OWORD, RegFamily::BOUND)),
 ];
 
-lazy_static!{
-    pub static ref X64_REGISTER_MAP: HashMap<&'static str, (RegId, Size)> = {
-        X64_REGISTERS.iter().cloned().collect()
-    };
-    pub static ref X86_REGISTERS_MAP: HashMap<&'static str, (RegId, Size)> = {
-        X86_REGISTERS.iter().cloned().collect()
+/// Mnemonics that are spelled differently in AT&T syntax, the mnemonic they stand for, and the
+/// size of their memory operand.
+pub const ATT_MNEMONICS: &[(&str, &str, Option<Size>)] = &[
+    ("cbtw",   "cbw",    None),
+    ("cwtl",   "cwde",   None),
+    ("cltq",   "cdqe",   None),
+    ("cwtd",   "cwd",    None),
+    ("cltd",   "cdq",    None),
+    ("cqto",   "cqo",    None),
+    ("movzbw", "movzx",  Some(BYTE)),
+    ("movzbl", "movzx",  Some(BYTE)),
+    ("movzbq", "movzx",  Some(BYTE)),
+    ("movzwl", "movzx",  Some(WORD)),
+    ("movzwq", "movzx",  Some(WORD)),
+    ("movsbw", "movsx",  Some(BYTE)),
+    ("movsbl", "movsx",  Some(BYTE)),
+    ("movsbq", "movsx",  Some(BYTE)),
+    ("movswl", "movsx",  Some(WORD)),
+    ("movswq", "movsx",  Some(WORD)),
+    ("movslq", "movsxd", Some(DWORD)),
+];
+
+/// Splits an AT&T mnemonic into the mnemonic it stands for and the size of its memory operand.
+/// Returns `None` if it isn't a renamed mnemonic and doesn't end in a size suffix.
+///
+/// x87 instructions use their own suffixes, which mean different sizes for floating point
+/// and integer operands.
+pub fn split_att_mnemonic(mnemonic: &str) -> Option<(&str, Option<Size>)> {
+    if let Some(&(_, intel, size)) = ATT_MNEMONICS.iter().find(|&&(att, _, _)| att == mnemonic) {
+        return Some((intel, size));
+    }
+
+    if mnemonic.starts_with("fi") {
+        if let Some(stripped) = mnemonic.strip_suffix("ll") {
+            return Some((stripped, Some(QWORD)));
+        }
+    }
+
+    let suffix = mnemonic.chars().last()?;
+    let stripped = &mnemonic[..mnemonic.len() - 1];
+    let size = if mnemonic.starts_with("fi") {
+        match suffix {
+            's' => WORD,
+            'l' => DWORD,
+            'q' => QWORD,
+            _ => return None,
+        }
+    } else if mnemonic.starts_with('f') {
+        match suffix {
+            's' => DWORD,
+            'l' => QWORD,
+            't' => PWORD,
+            _ => return None,
+        }
+    } else {
+        match suffix {
+            'b' => BYTE,
+            'w' => WORD,
+            'l' => DWORD,
+            'q' => QWORD,
+            _ => return None,
+        }
     };
-    pub static ref X64_FAMILIES_MAP:  HashMap<&'static str, (Size, RegFamily)> = {
-        X64_FAMILIES.iter().cloned().collect()
+
+    if stripped.is_empty() {
+        None
+    } else {
+        Some((stripped, Some(size)))
+    }
+}
+
+// built at compile time, so lookups don't need any initialization
+pub static X64_REGISTER_MAP: NameTable<(RegId, Size), { X64_REGISTERS.len() }, { name_table_slots(X64_REGISTERS.len()) }> =
+    NameTable::new(X64_REGISTERS);
+pub static X86_REGISTERS_MAP: NameTable<(RegId, Size), { X86_REGISTERS.len() }, { name_table_slots(X86_REGISTERS.len()) }> =
+    NameTable::new(X86_REGISTERS);
+pub static X64_FAMILIES_MAP: NameTable<(Size, RegFamily), { X64_FAMILIES.len() }, { name_table_slots(X64_FAMILIES.len()) }> =
+    NameTable::new(X64_FAMILIES);
+pub static X86_FAMILIES_MAP: NameTable<(Size, RegFamily), { X86_FAMILIES.len() }, { name_table_slots(X86_FAMILIES.len()) }> =
+    NameTable::new(X86_FAMILIES);
+
+/// Looks up a register by name. If the register does not exist in this mode, the error notes
+/// similarly named registers, or the mode where the register is available.
+pub fn lookup_register(name: &str, mode: X86Mode) -> Result<(RegId, Size), Error> {
+    let (entry, names) = match mode {
+        X86Mode::Long => (X64_REGISTER_MAP.get(name), X64_REGISTER_MAP.entries()),
+        X86Mode::Protected => (X86_REGISTERS_MAP.get(name), X86_REGISTERS_MAP.entries()),
     };
-    pub static ref X86_FAMILIES_MAP:  HashMap<&'static str, (Size, RegFamily)> = {
-        X86_FAMILIES.iter().cloned().collect()
+
+    if let Some(&(_, register)) = entry {
+        return Ok(register);
+    }
+
+    let (in_other_mode, other_mode) = match mode {
+        X86Mode::Long => (X86_REGISTERS_MAP.get(name).is_some(), X86Mode::Protected),
+        X86Mode::Protected => (X64_REGISTER_MAP.get(name).is_some(), X86Mode::Long),
     };
+
+    if in_other_mode {
+        return Err(Error::UnknownName {
+            kind: NameKind::Register,
+            name: name.to_string(),
+            notes: vec![Note::OnlyAvailableIn(other_mode)],
+        });
+    }
+
+    Err(Error::unknown_name(NameKind::Register, name, names.iter().map(|&(name, _)| name)))
+}
+
+/// Looks up an instruction prefix by name.
+pub fn lookup_prefix(name: &str) -> Result<&'static str, Error> {
+    PREFIXES.iter()
+        .find(|&&prefix| prefix == name)
+        .cloned()
+        .ok_or_else(|| Error::unknown_name(NameKind::Prefix, name, PREFIXES.iter().cloned()))
+}
+
+/// Looks up a cpu feature by name.
+pub fn lookup_feature(name: &str) -> Result<Features, Error> {
+    Features::from_name(name)
+        .ok_or_else(|| Error::unknown_name(NameKind::Feature, name, Features::names()))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_tables_find_every_name() {
+        for &(name, value) in X64_REGISTERS {
+            assert_eq!(X64_REGISTER_MAP.get(name), Some(&(name, value)), "{}", name);
+        }
+        for &(name, value) in X86_REGISTERS {
+            assert_eq!(X86_REGISTERS_MAP.get(name), Some(&(name, value)), "{}", name);
+        }
+        for &(name, value) in X64_FAMILIES {
+            assert_eq!(X64_FAMILIES_MAP.get(name), Some(&(name, value)), "{}", name);
+        }
+        for &(name, value) in X86_FAMILIES {
+            assert_eq!(X86_FAMILIES_MAP.get(name), Some(&(name, value)), "{}", name);
+        }
+    }
 }
@@ -0,0 +1,131 @@
+//! Operands bound to the placeholders of an instruction, like the operands of `asm!`.
+//!
+//! A placeholder stands for a register that is chosen at runtime, an immediate or a memory
+//! reference. Registers are declared with the register classes of `asm!`, and a placeholder
+//! can select another view of the register with a template modifier, like `{0:e}` for the
+//! dword register of a general purpose register.
+
+use super::{Error, X86Mode};
+use super::ast::{CleanArg, RegFamily, Register};
+use crate::common::{Expr, Size, Value};
+
+use core::str::FromStr;
+
+use alloc::format;
+
+/// The classes of registers that an operand can be declared with, named like those of `asm!`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegClass {
+    /// `reg`, any general purpose register.
+    Reg,
+    /// `reg_abcd`, one of `rax`, `rcx`, `rdx` and `rbx`, which also have a high byte register.
+    RegAbcd,
+    /// `reg_byte`, the low byte of any general purpose register.
+    RegByte,
+    /// `xmm_reg`
+    Xmm,
+    /// `ymm_reg`
+    Ymm,
+    /// `mmx_reg`
+    Mmx,
+    /// `x87_reg`
+    X87,
+}
+
+/// An operand that placeholders can be bound to.
+#[derive(Debug, Clone)]
+pub enum Operand {
+    /// A register of a class, chosen at runtime by its number.
+    Register {
+        class: RegClass,
+        number: Expr,
+    },
+    /// An immediate.
+    Immediate(Value),
+    /// A memory reference, either `CleanArg::Indirect` or `CleanArg::IndirectJumpTarget`.
+    Memory(CleanArg),
+}
+
+impl FromStr for RegClass {
+    type Err = Error;
+
+    /// Looks up a register class by its name in `asm!`.
+    fn from_str(name: &str) -> Result<RegClass, Error> {
+        Ok(match name {
+            "reg" => RegClass::Reg,
+            "reg_abcd" => RegClass::RegAbcd,
+            "reg_byte" => RegClass::RegByte,
+            "xmm_reg" => RegClass::Xmm,
+            "ymm_reg" => RegClass::Ymm,
+            "mmx_reg" => RegClass::Mmx,
+            "x87_reg" => RegClass::X87,
+            "zmm_reg" | "kreg" | "tmm_reg" =>
+                return Err(format!("The register class '{}' is not supported", name).into()),
+            _ => return Err(format!("Unknown register class '{}'", name).into()),
+        })
+    }
+}
+
+impl RegClass {
+    pub fn name(self) -> &'static str {
+        match self {
+            RegClass::Reg => "reg",
+            RegClass::RegAbcd => "reg_abcd",
+            RegClass::RegByte => "reg_byte",
+            RegClass::Xmm => "xmm_reg",
+            RegClass::Ymm => "ymm_reg",
+            RegClass::Mmx => "mmx_reg",
+            RegClass::X87 => "x87_reg",
+        }
+    }
+
+    /// The size and family of the register a placeholder with the given modifier stands for.
+    fn view(self, modifier: Option<&str>, mode: X86Mode) -> Result<(Size, RegFamily), Error> {
+        use self::RegClass::*;
+
+        let long = mode == X86Mode::Long;
+        Ok(match (self, modifier) {
+            (Reg, None) | (RegAbcd, None) => (if long { Size::QWORD } else { Size::DWORD }, RegFamily::LEGACY),
+            (Reg, Some("r")) | (RegAbcd, Some("r")) if long => (Size::QWORD, RegFamily::LEGACY),
+            (Reg, Some("e")) | (RegAbcd, Some("e")) => (Size::DWORD, RegFamily::LEGACY),
+            (Reg, Some("x")) | (RegAbcd, Some("x")) => (Size::WORD, RegFamily::LEGACY),
+            (Reg, Some("l")) if long => (Size::BYTE, RegFamily::LEGACY),
+            (RegAbcd, Some("l")) => (Size::BYTE, RegFamily::LEGACY),
+            (RegAbcd, Some("h")) => (Size::BYTE, RegFamily::HIGHBYTE),
+            (RegByte, None) => (Size::BYTE, RegFamily::LEGACY),
+            (Xmm, None) | (Xmm, Some("x")) | (Ymm, Some("x")) => (Size::OWORD, RegFamily::XMM),
+            (Ymm, None) | (Ymm, Some("y")) | (Xmm, Some("y")) => (Size::HWORD, RegFamily::XMM),
+            (Mmx, None) => (Size::QWORD, RegFamily::MMX),
+            (X87, None) => (Size::PWORD, RegFamily::FP),
+            (_, Some(modifier)) => return Err(format!(
+                "The modifier '{}' can't be used with the {} class in {}", modifier, self.name(), mode).into()),
+        })
+    }
+}
+
+impl Operand {
+    /// The argument that a placeholder bound to this operand stands for, checking that the
+    /// template modifier it was used with is valid for it.
+    pub fn argument(&self, modifier: Option<&str>, mode: X86Mode) -> Result<CleanArg, Error> {
+        match *self {
+            Operand::Register { .. } => Ok(CleanArg::Direct { reg: self.register(modifier, mode)? }),
+            Operand::Immediate(_) | Operand::Memory(_) if modifier.is_some() =>
+                Err("Modifiers can only be used on register operands".into()),
+            Operand::Immediate(value) => Ok(CleanArg::Immediate { value }),
+            Operand::Memory(ref arg) => Ok(arg.clone()),
+        }
+    }
+
+    /// The register that a placeholder bound to this operand stands for, which is also how
+    /// placeholders are used as the base or index of a memory reference.
+    pub fn register(&self, modifier: Option<&str>, mode: X86Mode) -> Result<Register, Error> {
+        match *self {
+            Operand::Register { class, number } => {
+                let (size, family) = class.view(modifier, mode)?;
+                Ok(Register::new_dynamic(size, family, number))
+            },
+            Operand::Immediate(_) => Err("Expected a register operand, but found an immediate".into()),
+            Operand::Memory(_) => Err("Expected a register operand, but found a memory reference".into()),
+        }
+    }
+}
@@ -0,0 +1,228 @@
+//! Inferring the cpu features and modes that a sequence of instructions needs.
+//!
+//! The assembler picks the form of an instruction from its operands alone, and only then checks
+//! whether the features of that form are enabled. Compiling an instruction without any enabled
+//! features thus fails with exactly the features it needs, after which it is compiled once more
+//! with those to check that it is valid otherwise.
+//!
+//! Instructions are taken as the arch specific `InstructionX64` and `InstructionX86` values,
+//! which can be built with the `typed` module. Template source can't be passed directly: its
+//! lines have to be parsed first, and any directives in it like `.feature` are not looked at.
+
+use super::{compiler, Context, Error, InstructionX64, InstructionX86, X86Mode};
+use super::ast::{CleanArg, Instruction};
+use super::length::LengthCounter;
+use super::x64data::Features;
+
+use alloc::vec::Vec;
+
+/// The cpu features and modes that a sequence of instructions needs, together with the
+/// instructions that made them necessary. Instructions are identified by the order in which
+/// they were added, starting at zero.
+#[derive(Debug, Clone, Default)]
+pub struct Requirements {
+    count: usize,
+    features: Features,
+    introductions: Vec<(usize, Features)>,
+    long_excluded_by: Option<usize>,
+    protected_excluded_by: Option<usize>,
+}
+
+impl Requirements {
+    /// Requirements without any instructions, which are valid in both modes.
+    pub fn new() -> Requirements {
+        Requirements::default()
+    }
+
+    /// Infers the requirements of a sequence of x64 instructions. On failure, returns the index
+    /// of the instruction that failed to compile in long mode, with its error.
+    pub fn of_x64<'a, I>(instructions: I) -> Result<Requirements, (usize, Error)>
+        where I: IntoIterator<Item=&'a InstructionX64>
+    {
+        let mut requirements = Requirements::new();
+        for (i, instruction) in instructions.into_iter().enumerate() {
+            requirements.add_x64(instruction).map_err(|e| (i, e))?;
+        }
+        Ok(requirements)
+    }
+
+    /// Infers the requirements of a sequence of x86 instructions. On failure, returns the index
+    /// of the instruction that failed to compile in protected mode, with its error.
+    pub fn of_x86<'a, I>(instructions: I) -> Result<Requirements, (usize, Error)>
+        where I: IntoIterator<Item=&'a InstructionX86>
+    {
+        let mut requirements = Requirements::new();
+        for (i, instruction) in instructions.into_iter().enumerate() {
+            requirements.add_x86(instruction).map_err(|e| (i, e))?;
+        }
+        Ok(requirements)
+    }
+
+    /// Adds an instruction that is compiled in long mode. Instructions that fail to compile are
+    /// not added, and the error is returned.
+    pub fn add_x64(&mut self, instruction: &InstructionX64) -> Result<(), Error> {
+        self.add(X86Mode::Long, &instruction.inst, &instruction.args)
+    }
+
+    /// Adds an instruction that is compiled in protected mode. Instructions that fail to compile
+    /// are not added, and the error is returned.
+    pub fn add_x86(&mut self, instruction: &InstructionX86) -> Result<(), Error> {
+        self.add(X86Mode::Protected, &instruction.inst, &instruction.args)
+    }
+
+    /// The minimal set of features that all instructions together need.
+    pub fn features(&self) -> Features {
+        self.features
+    }
+
+    /// Every instruction that needed features that no instruction before it did, in order,
+    /// together with those features.
+    pub fn introductions(&self) -> &[(usize, Features)] {
+        &self.introductions
+    }
+
+    /// The first instruction that needed any of `features`, if any did.
+    pub fn introduced_by(&self, features: Features) -> Option<usize> {
+        self.introductions.iter()
+            .find(|(_, introduced)| introduced.intersects(features))
+            .map(|&(i, _)| i)
+    }
+
+    /// Returns if all instructions are also valid in `mode`.
+    pub fn is_valid_in(&self, mode: X86Mode) -> bool {
+        self.excluded_by(mode).is_none()
+    }
+
+    /// The modes that all instructions are valid in.
+    pub fn modes(&self) -> impl Iterator<Item=X86Mode> + '_ {
+        [X86Mode::Long, X86Mode::Protected].iter().cloned().filter(move |&mode| self.is_valid_in(mode))
+    }
+
+    /// The first instruction that isn't valid in `mode`, if any isn't.
+    pub fn excluded_by(&self, mode: X86Mode) -> Option<usize> {
+        match mode {
+            X86Mode::Long => self.long_excluded_by,
+            X86Mode::Protected => self.protected_excluded_by,
+        }
+    }
+
+    fn add(&mut self, mode: X86Mode, inst: &Instruction, args: &[CleanArg]) -> Result<(), Error> {
+        let features = required_features(mode, inst, args)?;
+
+        let other = match mode {
+            X86Mode::Long => X86Mode::Protected,
+            X86Mode::Protected => X86Mode::Long,
+        };
+        if required_features(other, inst, args).is_err() {
+            let excluded_by = match other {
+                X86Mode::Long => &mut self.long_excluded_by,
+                X86Mode::Protected => &mut self.protected_excluded_by,
+            };
+            excluded_by.get_or_insert(self.count);
+        }
+
+        let introduced = features - self.features;
+        if !introduced.is_empty() {
+            self.introductions.push((self.count, introduced));
+            self.features |= introduced;
+        }
+
+        self.count += 1;
+        Ok(())
+    }
+}
+
+/// The features that the form chosen for an instruction needs in `mode`.
+fn required_features(mode: X86Mode, inst: &Instruction, args: &[CleanArg]) -> Result<Features, Error> {
+    let features = match compile(mode, Features::empty(), inst, args) {
+        Ok(()) => return Ok(Features::empty()),
+        Err(Error::DisabledFeatures(features)) => features,
+        Err(e) => return Err(e)
+    };
+
+    compile(mode, features, inst, args)?;
+    Ok(features)
+}
+
+fn compile(mode: X86Mode, features: Features, inst: &Instruction, args: &[CleanArg]) -> Result<(), Error> {
+    let mut counter = LengthCounter::default();
+    let ctx = Context {
+        state: &mut counter,
+        mode,
+        features,
+        explainer: None,
+    };
+
+    compiler::compile_instruction(ctx, inst.clone(), args.to_vec())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::typed::{self, inst, Reg, Ymm};
+
+    fn x86(instruction: InstructionX64) -> InstructionX86 {
+        InstructionX86 { inst: instruction.inst, args: instruction.args }
+    }
+
+    #[test]
+    fn mixed_features() {
+        let instructions = [
+            typed::add(Reg::EAX, Reg::ECX),
+            inst("vaddps", [Ymm::Y0.into(), Ymm::Y1.into(), Ymm::Y2.into()]),
+            inst("popcnt", [Reg::EAX.into(), Reg::ECX.into()]),
+            inst("andn", [Reg::EAX.into(), Reg::ECX.into(), Reg::EDX.into()]),
+            inst("vaddps", [Ymm::Y3.into(), Ymm::Y4.into(), Ymm::Y5.into()]),
+        ];
+        let requirements = Requirements::of_x64(instructions.iter()).unwrap();
+
+        assert_eq!(requirements.features(), Features::AVX | Features::BMI1);
+        assert_eq!(requirements.introductions(), &[(1, Features::AVX), (3, Features::BMI1)]);
+        assert_eq!(requirements.introduced_by(Features::AVX), Some(1));
+        assert_eq!(requirements.introduced_by(Features::BMI1), Some(3));
+        assert_eq!(requirements.introduced_by(Features::SSE42), None);
+        assert_eq!(requirements.modes().collect::<Vec<_>>(), [X86Mode::Long, X86Mode::Protected]);
+    }
+
+    #[test]
+    fn modes() {
+        let instructions = [
+            x86(typed::add(Reg::EAX, Reg::ECX)),
+            x86(inst("aaa", [])),
+        ];
+        let requirements = Requirements::of_x86(instructions.iter()).unwrap();
+        assert_eq!(requirements.features(), Features::empty());
+        assert_eq!(requirements.modes().collect::<Vec<_>>(), [X86Mode::Protected]);
+        assert_eq!(requirements.excluded_by(X86Mode::Long), Some(1));
+        assert_eq!(requirements.excluded_by(X86Mode::Protected), None);
+
+        let instructions = [
+            typed::add(Reg::EAX, Reg::ECX),
+            typed::add(Reg::RAX, Reg::RCX),
+        ];
+        let requirements = Requirements::of_x64(instructions.iter()).unwrap();
+        assert_eq!(requirements.modes().collect::<Vec<_>>(), [X86Mode::Long]);
+        assert_eq!(requirements.excluded_by(X86Mode::Protected), Some(1));
+    }
+
+    #[test]
+    fn invalid_instruction() {
+        let instructions = [
+            typed::add(Reg::EAX, Reg::ECX),
+            typed::mov(Reg::EAX, Reg::CL),
+        ];
+        match Requirements::of_x64(instructions.iter()) {
+            Err((1, _)) => (),
+            result => panic!("unexpected result {:?}", result)
+        }
+
+        let mut requirements = Requirements::new();
+        requirements.add_x64(&instructions[0]).unwrap();
+        assert!(requirements.add_x64(&instructions[1]).is_err());
+        requirements.add_x64(&instructions[0]).unwrap();
+        assert!(requirements.add_x64(&inst("aaa", [])).is_err());
+        assert_eq!(requirements.features(), Features::empty());
+        assert_eq!(requirements.excluded_by(X86Mode::Long), None);
+    }
+}
@@ -0,0 +1,784 @@
+//! Which operands, registers and flags an instruction reads and writes.
+//!
+//! Every form in the instruction tables carries a description of its effects on the state that
+//! is visible to a program: how each of its operands is accessed, which registers and memory it
+//! accesses implicitly, and which bits of RFLAGS it uses and defines. The descriptions are
+//! computed while the tables are built. General purpose, system and x87 instructions are listed
+//! explicitly, while SIMD instructions follow a few regular patterns that are derived from their
+//! encoding, with exceptions for the instructions that don't.
+//!
+//! Where the effects of an instruction depend on runtime values, the description is
+//! conservative: a register or flag that is only written under some condition, like the
+//! destination of `cmovcc` or the flags after a shift by zero, is listed as both read and written.
+
+use super::{compiler, Archx64, Archx86, Context, Error, InstructionX64, InstructionX86, X86Mode};
+use super::ast::{CleanArg, Instruction, RegId};
+use super::compiler::MAX_OPERANDS;
+use super::explain::Explainer;
+use super::length::LengthCounter;
+use super::x64data::{Flags, Features};
+
+use crate::arch::Access;
+
+use bitflags::bitflags;
+
+bitflags! {
+    /// The bits of RFLAGS that instructions use or define.
+    pub struct Rflags: u32 {
+        const CF = 0x0000_0001; // carry
+        const PF = 0x0000_0004; // parity
+        const AF = 0x0000_0010; // auxiliary carry
+        const ZF = 0x0000_0040; // zero
+        const SF = 0x0000_0080; // sign
+        const TF = 0x0000_0100; // trap
+        const IF = 0x0000_0200; // interrupt enable
+        const DF = 0x0000_0400; // direction
+        const OF = 0x0000_0800; // overflow
+        const AC = 0x0004_0000; // alignment check
+
+        // the flags that are defined by arithmetic
+        const STATUS = Self::CF.bits | Self::PF.bits | Self::AF.bits | Self::ZF.bits | Self::SF.bits | Self::OF.bits;
+    }
+}
+
+/// A set of registers. Registers are identified by their full width, so `ah` and `eax` both
+/// stand for `rax`. Only general purpose, x87, MMX, XMM and segment registers are tracked. The
+/// MMX registers share their storage with the x87 registers, but are tracked separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RegSet {
+    bits: u64,
+}
+
+/// The tracked registers, in the order of their bits in a `RegSet`.
+const TRACKED: [RegId; 54] = [
+    RegId::RAX, RegId::RCX, RegId::RDX, RegId::RBX, RegId::RSP, RegId::RBP, RegId::RSI, RegId::RDI,
+    RegId::R8, RegId::R9, RegId::R10, RegId::R11, RegId::R12, RegId::R13, RegId::R14, RegId::R15,
+    RegId::ST0, RegId::ST1, RegId::ST2, RegId::ST3, RegId::ST4, RegId::ST5, RegId::ST6, RegId::ST7,
+    RegId::MMX0, RegId::MMX1, RegId::MMX2, RegId::MMX3, RegId::MMX4, RegId::MMX5, RegId::MMX6, RegId::MMX7,
+    RegId::XMM0, RegId::XMM1, RegId::XMM2, RegId::XMM3, RegId::XMM4, RegId::XMM5, RegId::XMM6, RegId::XMM7,
+    RegId::XMM8, RegId::XMM9, RegId::XMM10, RegId::XMM11, RegId::XMM12, RegId::XMM13, RegId::XMM14, RegId::XMM15,
+    RegId::ES, RegId::CS, RegId::SS, RegId::DS, RegId::FS, RegId::GS,
+];
+
+impl RegSet {
+    const ST: RegSet = RegSet { bits: 0xFF << 16 };
+    const MMX: RegSet = RegSet { bits: 0xFF << 24 };
+    const XMM: RegSet = RegSet { bits: 0xFFFF << 32 };
+
+    /// The set without any registers.
+    pub const fn empty() -> RegSet {
+        RegSet { bits: 0 }
+    }
+
+    const fn of(regs: &[RegId]) -> RegSet {
+        let mut bits = 0;
+        let mut i = 0;
+        while i < regs.len() {
+            bits |= RegSet::bit(regs[i]);
+            i += 1;
+        }
+        RegSet { bits }
+    }
+
+    const fn bit(reg: RegId) -> u64 {
+        let code = reg as u8 & 0xF;
+        let index = match reg as u8 >> 4 {
+            0 => code,
+            2 => code - 4, // ah, ch, dh and bh are part of rax, rcx, rdx and rbx
+            3 => 16 + code,
+            4 => 24 + code,
+            5 => 32 + code,
+            6 => 48 + code,
+            _ => return 0
+        };
+        1 << index
+    }
+
+    const fn union(self, other: RegSet) -> RegSet {
+        RegSet { bits: self.bits | other.bits }
+    }
+
+    /// Returns if `reg`, or the full width register it is part of, is in the set. Registers that
+    /// aren't tracked are never in the set.
+    pub fn contains(&self, reg: RegId) -> bool {
+        self.bits & RegSet::bit(reg) != 0
+    }
+
+    /// Returns if the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    /// The registers in the set, in the order of their families and numbers.
+    pub fn iter(&self) -> impl Iterator<Item=RegId> {
+        let bits = self.bits;
+        TRACKED.iter().enumerate().filter(move |&(i, _)| bits & (1 << i) != 0).map(|(_, &reg)| reg)
+    }
+}
+
+/// The effects of an instruction form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Semantics {
+    operands: [Access; MAX_OPERANDS],
+    operand_count: u8,
+    implicit_reads: RegSet,
+    implicit_writes: RegSet,
+    implicit_memory: Access,
+    flags_read: Rflags,
+    flags_written: Rflags,
+    flags_undefined: Rflags,
+}
+
+impl Semantics {
+    /// How each of the operands is accessed, in the order in which they are written.
+    pub fn operands(&self) -> &[Access] {
+        &self.operands[.. usize::from(self.operand_count)]
+    }
+
+    /// The registers that are read without being an operand, like `rdx:rax` for `div`.
+    pub fn implicit_reads(&self) -> RegSet {
+        self.implicit_reads
+    }
+
+    /// The registers that are written without being an operand.
+    pub fn implicit_writes(&self) -> RegSet {
+        self.implicit_writes
+    }
+
+    /// How memory that isn't referenced by an operand is accessed, like the stack by `push` or
+    /// the strings that `movs` copies.
+    pub fn implicit_memory(&self) -> Access {
+        self.implicit_memory
+    }
+
+    /// The flags that are used.
+    pub fn flags_read(&self) -> Rflags {
+        self.flags_read
+    }
+
+    /// The flags that are defined. This includes the flags that are left undefined.
+    pub fn flags_written(&self) -> Rflags {
+        self.flags_written
+    }
+
+    /// The flags that are left with an undefined value.
+    pub fn flags_undefined(&self) -> Rflags {
+        self.flags_undefined
+    }
+
+    /// The effects of the instruction when it's used with a repeat prefix. It then runs zero or
+    /// more times, counting down `rcx`, so anything it writes may also be left unchanged.
+    pub(super) fn repeated(self) -> Semantics {
+        let rcx = RegSet::of(&[RegId::RCX]);
+        Semantics {
+            implicit_reads: self.implicit_reads.union(self.implicit_writes).union(rcx),
+            implicit_writes: self.implicit_writes.union(rcx),
+            flags_read: self.flags_read | self.flags_written,
+            ..self
+        }
+    }
+
+    /// Describes a form with the given format string and operand accesses. Immediates and jump
+    /// targets are never accessed, so their access is ignored. Forms that are described together
+    /// can have less operands than listed.
+    const fn form(fmt: &[u8], access: &[Access]) -> Semantics {
+        let count = fmt.len() / 2;
+        assert!(access.len() >= count, "missing operand access");
+
+        let mut operands = [Access::Unused; MAX_OPERANDS];
+        let mut i = 0;
+        while i < count {
+            if !matches!(fmt[i * 2], b'i' | b'o') {
+                operands[i] = access[i];
+            }
+            i += 1;
+        }
+
+        Semantics {
+            operands,
+            operand_count: count as u8,
+            implicit_reads: RegSet::empty(),
+            implicit_writes: RegSet::empty(),
+            implicit_memory: Access::Unused,
+            flags_read: Rflags::empty(),
+            flags_written: Rflags::empty(),
+            flags_undefined: Rflags::empty(),
+        }
+    }
+
+    const fn reads(mut self, regs: RegSet) -> Semantics {
+        self.implicit_reads = self.implicit_reads.union(regs);
+        self
+    }
+
+    const fn writes(mut self, regs: RegSet) -> Semantics {
+        self.implicit_writes = self.implicit_writes.union(regs);
+        self
+    }
+
+    const fn modifies(self, regs: RegSet) -> Semantics {
+        self.reads(regs).writes(regs)
+    }
+
+    /// Pushing to or popping from the x87 register stack renames all registers on it.
+    const fn stack(self) -> Semantics {
+        self.modifies(RegSet::ST)
+    }
+
+    const fn memory(mut self, access: Access) -> Semantics {
+        self.implicit_memory = self.implicit_memory.union(access);
+        self
+    }
+
+    const fn uses(mut self, flags: Rflags) -> Semantics {
+        self.flags_read = self.flags_read.union(flags);
+        self
+    }
+
+    const fn sets(mut self, flags: Rflags) -> Semantics {
+        self.flags_written = self.flags_written.union(flags);
+        self
+    }
+
+    const fn undefines(mut self, flags: Rflags) -> Semantics {
+        self.flags_undefined = self.flags_undefined.union(flags);
+        self.sets(flags)
+    }
+}
+
+const U: Access = Access::Unused;
+const R: Access = Access::Read;
+const W: Access = Access::Write;
+const RW: Access = Access::ReadWrite;
+
+const CF: Rflags = Rflags::CF;
+const PF: Rflags = Rflags::PF;
+const AF: Rflags = Rflags::AF;
+const ZF: Rflags = Rflags::ZF;
+const SF: Rflags = Rflags::SF;
+const OF: Rflags = Rflags::OF;
+const STATUS: Rflags = Rflags::STATUS;
+const ALL: Rflags = Rflags::all();
+
+/// All state that is saved by `fxsave` and `xsave`.
+const SAVED_STATE: RegSet = RegSet::ST.union(RegSet::MMX).union(RegSet::XMM);
+
+/// Describes the effects of a form of the mnemonic `name`. Returns `None` for instructions that
+/// can't be described, like those that change the state of the whole machine or that use
+/// registers implied by their operands.
+pub(super) const fn describe(name: &[u8], fmt: &[u8], flags: Flags, features: Features) -> Option<Semantics> {
+    if let Some(semantics) = general_purpose(name, fmt) {
+        Some(semantics)
+    } else if let Some(semantics) = x87(name, fmt) {
+        Some(semantics)
+    } else if is_simd(name, fmt, flags, features) {
+        Some(simd(name, fmt, flags, features))
+    } else {
+        None
+    }
+}
+
+/// The flags tested by a condition code.
+const fn condition(cc: &[u8]) -> Option<Rflags> {
+    Some(match cc {
+        b"o" | b"no" => OF,
+        b"b" | b"c" | b"nae" | b"ae" | b"nb" | b"nc" => CF,
+        b"e" | b"z" | b"ne" | b"nz" => ZF,
+        b"be" | b"na" | b"a" | b"nbe" => CF.union(ZF),
+        b"s" | b"ns" => SF,
+        b"p" | b"pe" | b"np" | b"po" => PF,
+        b"l" | b"nge" | b"ge" | b"nl" => SF.union(OF),
+        b"le" | b"ng" | b"g" | b"nle" => ZF.union(SF).union(OF),
+        _ => return None
+    })
+}
+
+const fn is_byte(fmt: &[u8]) -> bool {
+    fmt.len() >= 2 && fmt[1] == b'b'
+}
+
+const fn general_purpose(name: &[u8], fmt: &[u8]) -> Option<Semantics> {
+    use super::ast::RegId::*;
+
+    let form = Semantics::form;
+    let regs = RegSet::of;
+
+    // conditional jumps, moves and sets
+    if let [b'j', cc @ ..] | [b's', b'e', b't', cc @ ..] | [b'c', b'm', b'o', b'v', cc @ ..] = name {
+        if let Some(tested) = condition(cc) {
+            let semantics = match name[0] {
+                b'j' => form(fmt, &[U]),
+                b's' => form(fmt, &[W]),
+                _ => form(fmt, &[RW, R]),
+            };
+            return Some(semantics.uses(tested));
+        }
+    }
+
+    Some(match name {
+        // arithmetic
+        b"add" | b"sub" => form(fmt, &[RW, R]).sets(STATUS),
+        b"adc" | b"sbb" => form(fmt, &[RW, R]).uses(CF).sets(STATUS),
+        b"and" | b"or" | b"xor" => form(fmt, &[RW, R]).sets(STATUS).undefines(AF),
+        b"cmp" => form(fmt, &[R, R]).sets(STATUS),
+        b"test" => form(fmt, &[R, R]).sets(STATUS).undefines(AF),
+        b"adcx" => form(fmt, &[RW, R]).uses(CF).sets(CF),
+        b"adox" => form(fmt, &[RW, R]).uses(OF).sets(OF),
+        b"inc" | b"dec" => form(fmt, &[RW]).sets(STATUS.difference(CF)),
+        b"neg" => form(fmt, &[RW]).sets(STATUS),
+        b"not" => form(fmt, &[RW]),
+        b"mul" | b"imul" if fmt.len() == 2 => {
+            let semantics = form(fmt, &[R]).sets(CF.union(OF)).undefines(SF.union(ZF).union(AF).union(PF));
+            if is_byte(fmt) {
+                semantics.modifies(regs(&[RAX]))
+            } else {
+                semantics.reads(regs(&[RAX])).writes(regs(&[RAX, RDX]))
+            }
+        },
+        b"imul" if fmt.len() == 4 => form(fmt, &[RW, R]).sets(CF.union(OF)).undefines(SF.union(ZF).union(AF).union(PF)),
+        b"imul" => form(fmt, &[W, R, U]).sets(CF.union(OF)).undefines(SF.union(ZF).union(AF).union(PF)),
+        b"div" | b"idiv" if is_byte(fmt) => form(fmt, &[R]).modifies(regs(&[RAX])).undefines(STATUS),
+        b"div" | b"idiv" => form(fmt, &[R]).modifies(regs(&[RAX, RDX])).undefines(STATUS),
+        b"cbw" | b"cwde" | b"cdqe" => form(fmt, &[]).modifies(regs(&[RAX])),
+        b"cwd" | b"cdq" | b"cqo" => form(fmt, &[]).reads(regs(&[RAX])).writes(regs(&[RDX])),
+        b"aaa" | b"aas" => form(fmt, &[]).modifies(regs(&[RAX])).uses(AF).sets(AF.union(CF)).undefines(OF.union(SF).union(ZF).union(PF)),
+        b"daa" | b"das" => form(fmt, &[]).modifies(regs(&[RAX])).uses(AF.union(CF)).sets(STATUS).undefines(OF),
+        b"aam" | b"aad" => form(fmt, &[]).modifies(regs(&[RAX])).sets(STATUS).undefines(OF.union(AF).union(CF)),
+
+        // bit manipulation
+        b"andn" | b"bzhi" => form(fmt, &[W, R, R]).sets(STATUS).undefines(AF.union(PF)),
+        b"bextr" => form(fmt, &[W, R, R]).sets(STATUS).undefines(AF.union(SF).union(PF)),
+        b"blsi" | b"blsmsk" | b"blsr" | b"blcfill" | b"blci" | b"blcic" | b"blcmsk" | b"blcs" | b"blsfill" |
+        b"blsic" | b"t1mskc" | b"tzmsk" => form(fmt, &[W, R]).sets(STATUS).undefines(AF.union(PF)),
+        b"mulx" => form(fmt, &[W, W, R]).reads(regs(&[RDX])),
+        b"pdep" | b"pext" | b"sarx" | b"shlx" | b"shrx" => form(fmt, &[W, R, R]),
+        b"rorx" => form(fmt, &[W, R, U]),
+        b"lzcnt" | b"tzcnt" => form(fmt, &[W, R]).sets(STATUS).undefines(OF.union(SF).union(AF).union(PF)),
+        b"popcnt" => form(fmt, &[W, R]).sets(STATUS),
+        // the destination is left unchanged if the source is zero
+        b"bsf" | b"bsr" => form(fmt, &[RW, R]).sets(STATUS).undefines(STATUS.difference(ZF)),
+        b"bswap" => form(fmt, &[RW]),
+        b"bt" => form(fmt, &[R, R]).sets(CF).undefines(OF.union(SF).union(AF).union(PF)),
+        b"btc" | b"btr" | b"bts" => form(fmt, &[RW, R]).sets(CF).undefines(OF.union(SF).union(AF).union(PF)),
+        b"crc32" => form(fmt, &[RW, R]),
+
+        // shifts and rotates leave the flags unchanged when shifting by zero
+        b"shl" | b"sal" | b"shr" | b"sar" => form(fmt, &[RW, R]).uses(STATUS).sets(STATUS).undefines(AF.union(OF)),
+        b"shld" | b"shrd" => form(fmt, &[RW, R, R]).uses(STATUS).sets(STATUS).undefines(AF.union(OF)),
+        b"rol" | b"ror" | b"rcl" | b"rcr" => form(fmt, &[RW, R]).uses(CF.union(OF)).sets(CF).undefines(OF),
+
+        // control flow
+        b"jmp" | b"jmpf" => form(fmt, &[R, U]),
+        b"jecxz" | b"jrcxz" => form(fmt, &[U]).reads(regs(&[RCX])),
+        b"loop" => form(fmt, &[U]).modifies(regs(&[RCX])),
+        b"loope" | b"loopz" | b"loopne" | b"loopnz" => form(fmt, &[U]).modifies(regs(&[RCX])).uses(ZF),
+        b"call" | b"callf" => form(fmt, &[R, U]).modifies(regs(&[RSP])).memory(W),
+        b"ret" | b"retn" | b"retf" => form(fmt, &[U]).modifies(regs(&[RSP])).memory(R),
+        b"iret" | b"iretw" | b"iretd" | b"iretq" => form(fmt, &[]).modifies(regs(&[RSP])).memory(R).sets(ALL),
+        b"int" | b"int01" | b"int03" | b"int1" | b"int3" | b"icebp" => form(fmt, &[U]),
+        b"into" => form(fmt, &[]).uses(OF),
+        b"syscall" => form(fmt, &[]).writes(regs(&[RCX, R11])).uses(ALL).sets(ALL),
+        b"sysret" => form(fmt, &[]).reads(regs(&[RCX, R11])).sets(ALL),
+        b"sysenter" => form(fmt, &[]).writes(regs(&[RSP])),
+        b"sysexit" => form(fmt, &[]).reads(regs(&[RCX, RDX])).writes(regs(&[RSP])),
+
+        // the stack
+        b"push" => form(fmt, &[R]).modifies(regs(&[RSP])).memory(W),
+        b"pop" => form(fmt, &[W]).modifies(regs(&[RSP])).memory(R),
+        b"pusha" | b"pushad" => form(fmt, &[]).reads(regs(&[RAX, RCX, RDX, RBX, RBP, RSI, RDI])).modifies(regs(&[RSP])).memory(W),
+        b"popa" | b"popad" => form(fmt, &[]).writes(regs(&[RAX, RCX, RDX, RBX, RBP, RSI, RDI])).modifies(regs(&[RSP])).memory(R),
+        b"pushf" | b"pushfw" | b"pushfq" => form(fmt, &[]).modifies(regs(&[RSP])).memory(W).uses(ALL),
+        b"popf" | b"popfw" | b"popfq" => form(fmt, &[]).modifies(regs(&[RSP])).memory(R).sets(ALL),
+        b"enter" => form(fmt, &[U, U]).modifies(regs(&[RSP, RBP])).memory(RW),
+        b"leave" => form(fmt, &[]).reads(regs(&[RBP])).writes(regs(&[RSP, RBP])).memory(R),
+
+        // flags
+        b"lahf" => form(fmt, &[]).writes(regs(&[RAX])).uses(STATUS.difference(OF)),
+        b"sahf" => form(fmt, &[]).reads(regs(&[RAX])).sets(STATUS.difference(OF)),
+        b"clc" | b"stc" => form(fmt, &[]).sets(CF),
+        b"cmc" => form(fmt, &[]).uses(CF).sets(CF),
+        b"cld" | b"std" => form(fmt, &[]).sets(Rflags::DF),
+        b"cli" | b"sti" => form(fmt, &[]).sets(Rflags::IF),
+        b"clac" | b"stac" => form(fmt, &[]).sets(Rflags::AC),
+
+        // data movement
+        b"mov" if has_operand(fmt, b"cdW") => form(fmt, &[W, R]).undefines(STATUS),
+        b"mov" | b"movbe" | b"movnti" | b"movsx" | b"movsxd" | b"movzx" => form(fmt, &[W, R]),
+        b"movabs" if fmt[0] == b'i' => form(fmt, &[U, R]).memory(W),
+        b"movabs" => form(fmt, &[W, U]).memory(R),
+        b"lea" => form(fmt, &[W, U]),
+        b"xchg" => form(fmt, &[RW, RW]),
+        b"xadd" => form(fmt, &[RW, RW]).sets(STATUS),
+        b"cmpxchg" => form(fmt, &[RW, R]).modifies(regs(&[RAX])).sets(STATUS),
+        b"cmpxchg8b" | b"cmpxchg16b" => form(fmt, &[RW]).reads(regs(&[RBX, RCX])).modifies(regs(&[RAX, RDX])).sets(ZF),
+        b"xlat" | b"xlatb" => form(fmt, &[]).modifies(regs(&[RAX])).reads(regs(&[RBX])).memory(R),
+        b"lds" => form(fmt, &[W, R]).writes(regs(&[DS])),
+        b"les" => form(fmt, &[W, R]).writes(regs(&[ES])),
+        b"lfs" => form(fmt, &[W, R]).writes(regs(&[FS])),
+        b"lgs" => form(fmt, &[W, R]).writes(regs(&[GS])),
+        b"lss" => form(fmt, &[W, R]).writes(regs(&[SS])),
+
+        // strings
+        b"movsb" | b"movsw" | b"movsq" => string_move(),
+        b"movsd" if fmt.is_empty() => string_move(),
+        b"cmpsb" | b"cmpsw" | b"cmpsq" => string_compare(),
+        b"cmpsd" if fmt.is_empty() => string_compare(),
+        b"scasb" | b"scasw" | b"scasd" | b"scasq" => form(fmt, &[]).reads(regs(&[RAX])).modifies(regs(&[RDI])).uses(Rflags::DF).memory(R).sets(STATUS),
+        b"lodsb" | b"lodsw" | b"lodsd" | b"lodsq" => form(fmt, &[]).writes(regs(&[RAX])).modifies(regs(&[RSI])).uses(Rflags::DF).memory(R),
+        b"stosb" | b"stosw" | b"stosd" | b"stosq" => form(fmt, &[]).reads(regs(&[RAX])).modifies(regs(&[RDI])).uses(Rflags::DF).memory(W),
+        b"insb" | b"insw" | b"insd" => form(fmt, &[]).reads(regs(&[RDX])).modifies(regs(&[RDI])).uses(Rflags::DF).memory(W),
+        b"outsb" | b"outsw" | b"outsd" => form(fmt, &[]).reads(regs(&[RDX])).modifies(regs(&[RSI])).uses(Rflags::DF).memory(R),
+        b"in" => form(fmt, &[W, R]),
+        b"out" => form(fmt, &[R, R]),
+
+        // processor state
+        b"cpuid" => form(fmt, &[]).modifies(regs(&[RAX, RCX])).writes(regs(&[RBX, RDX])),
+        b"rdtsc" => form(fmt, &[]).writes(regs(&[RAX, RDX])),
+        b"rdtscp" => form(fmt, &[]).writes(regs(&[RAX, RCX, RDX])),
+        b"rdmsr" | b"rdpmc" | b"rdpkru" | b"xgetbv" => form(fmt, &[]).reads(regs(&[RCX])).writes(regs(&[RAX, RDX])),
+        b"wrmsr" | b"wrpkru" | b"xsetbv" => form(fmt, &[]).reads(regs(&[RAX, RCX, RDX])),
+        b"rdrand" | b"rdseed" => form(fmt, &[W]).sets(STATUS),
+        b"rdpid" | b"rdfsbase" | b"rdgsbase" | b"rdshr" | b"sgdt" | b"sidt" | b"sldt" | b"smsw" | b"str" |
+        b"slwpcb" | b"svldt" | b"svts" => form(fmt, &[W]),
+        b"wrfsbase" | b"wrgsbase" | b"wrshr" | b"lgdt" | b"lidt" | b"lldt" | b"lmsw" | b"ltr" |
+        b"llwpcb" | b"rsldt" | b"rsts" => form(fmt, &[R]),
+        b"svdc" | b"rsdc" => form(fmt, &[W, R]),
+        b"lwpins" => form(fmt, &[R, R, U]).sets(CF),
+        b"lwpval" => form(fmt, &[R, R, U]),
+        // the destination is left unchanged if the selector is invalid
+        b"lar" | b"lsl" => form(fmt, &[RW, R]).sets(ZF),
+        b"verr" | b"verw" => form(fmt, &[R]).sets(ZF),
+        b"arpl" => form(fmt, &[RW, R]).sets(ZF),
+        b"bound" => form(fmt, &[R, R]),
+        b"invlpg" | b"prefetchnta" | b"prefetcht0" | b"prefetcht1" | b"prefetcht2" | b"prefetchwt1" => form(fmt, &[U]),
+        b"invept" | b"invvpid" | b"invpcid" => form(fmt, &[R, R]),
+        b"invlpga" if fmt.is_empty() => form(fmt, &[]).reads(regs(&[RAX, RCX])),
+        b"invlpga" => form(fmt, &[R, R]),
+        b"monitor" | b"monitorx" if fmt.is_empty() => form(fmt, &[]).reads(regs(&[RAX, RCX, RDX])),
+        b"monitor" | b"monitorx" => form(fmt, &[R, R, R]),
+        b"mwait" if fmt.is_empty() => form(fmt, &[]).reads(regs(&[RAX, RCX])),
+        b"mwait" => form(fmt, &[R, R]),
+        b"mwaitx" if fmt.is_empty() => form(fmt, &[]).reads(regs(&[RAX, RBX, RCX])),
+        b"mwaitx" => form(fmt, &[R, R]).reads(regs(&[RBX])),
+        b"clzero" => form(fmt, &[]).reads(regs(&[RAX])).memory(W),
+        b"skinit" | b"vmload" | b"vmrun" | b"vmsave" => form(fmt, &[]).reads(regs(&[RAX])),
+        b"vmfunc" => form(fmt, &[]).reads(regs(&[RAX, RCX])),
+        b"getsec" => form(fmt, &[]).modifies(regs(&[RAX, RBX, RCX, RDX])),
+        b"xsave" | b"xsave64" | b"xsavec64" | b"xsaveopt64" | b"xsaves64" => form(fmt, &[W]).reads(regs(&[RAX, RDX]).union(SAVED_STATE)),
+        b"xrstor" | b"xrstor64" | b"xrstors64" => form(fmt, &[R]).reads(regs(&[RAX, RDX])).writes(SAVED_STATE),
+        b"vmcall" | b"vmlaunch" | b"vmresume" | b"vmxoff" => form(fmt, &[]).sets(STATUS),
+        b"vmclear" | b"vmptrld" | b"vmxon" => form(fmt, &[R]).sets(STATUS),
+        b"vmptrst" => form(fmt, &[W]).sets(STATUS),
+        b"vmread" => form(fmt, &[W, R]).sets(STATUS),
+        b"vmwrite" => form(fmt, &[R, R]).sets(STATUS),
+        // rax is only written when the transaction aborts
+        b"xbegin" => form(fmt, &[U]).modifies(regs(&[RAX])),
+        b"xabort" => form(fmt, &[U]),
+        b"xtest" => form(fmt, &[]).sets(STATUS),
+        b"nop" | b"pause" | b"hlt" | b"ud2" | b"ud2a" | b"clts" | b"invd" | b"wbinvd" | b"swapgs" |
+        b"clgi" | b"stgi" | b"vmmcall" | b"xend" | b"lfence" | b"mfence" | b"sfence" | b"clflush" => form(fmt, &[U]),
+
+        // bounds checking
+        b"bndcl" | b"bndcu" | b"bndcn" if fmt[2] == b'm' => form(fmt, &[R, U]),
+        b"bndcl" | b"bndcu" | b"bndcn" => form(fmt, &[R, R]),
+        b"bndmk" => form(fmt, &[W, U]),
+        b"bndmov" | b"bndldx" | b"bndstx" => form(fmt, &[W, R]),
+
+        _ => return None
+    })
+}
+
+const fn string_move() -> Semantics {
+    Semantics::form(b"", &[]).modifies(RegSet::of(&[RegId::RSI, RegId::RDI])).uses(Rflags::DF).memory(RW)
+}
+
+const fn string_compare() -> Semantics {
+    Semantics::form(b"", &[]).modifies(RegSet::of(&[RegId::RSI, RegId::RDI])).uses(Rflags::DF).memory(R).sets(STATUS)
+}
+
+/// Returns if any of the operands of `fmt` has one of the given type codes.
+const fn has_operand(fmt: &[u8], codes: &[u8]) -> bool {
+    let mut i = 0;
+    while i < fmt.len() {
+        let mut j = 0;
+        while j < codes.len() {
+            if fmt[i] == codes[j] {
+                return true;
+            }
+            j += 1;
+        }
+        i += 2;
+    }
+    false
+}
+
+/// The x87 instructions. The default forms without operands use `st1`, except for those that
+/// operate on the top of the stack only.
+const fn x87(name: &[u8], fmt: &[u8]) -> Option<Semantics> {
+    use super::ast::RegId::*;
+
+    let form = Semantics::form;
+    let regs = RegSet::of;
+
+    Some(match name {
+        // the default form of these pops the stack, as it's the same as the popping form
+        b"fadd" | b"fsub" | b"fsubr" | b"fmul" | b"fdiv" | b"fdivr" => match fmt {
+            b"" => form(fmt, &[]).stack(),
+            b"fp" | b"md" | b"mq" => form(fmt, &[R]).modifies(regs(&[ST0])),
+            _ => form(fmt, &[RW, R]),
+        },
+        b"faddp" | b"fsubp" | b"fsubrp" | b"fmulp" | b"fdivp" | b"fdivrp" => form(fmt, &[RW, R]).stack(),
+        b"fiadd" | b"fisub" | b"fisubr" | b"fimul" | b"fidiv" | b"fidivr" => form(fmt, &[R]).modifies(regs(&[ST0])),
+
+        b"fcom" | b"fucom" | b"fcomi" | b"fucomi" => {
+            let semantics = match fmt {
+                b"" => form(fmt, &[]).reads(regs(&[ST0, ST1])),
+                b"Xpfp" => form(fmt, &[R, R]),
+                _ => form(fmt, &[R]).reads(regs(&[ST0])),
+            };
+            if name[name.len() - 1] == b'i' {
+                semantics.sets(STATUS)
+            } else {
+                semantics
+            }
+        },
+        b"fcomp" | b"fucomp" | b"fcompp" | b"fucompp" | b"ficomp" => form(fmt, &[R, R]).stack(),
+        b"fcomip" | b"fucomip" => form(fmt, &[R, R]).stack().sets(STATUS),
+        b"ficom" => form(fmt, &[R]).reads(regs(&[ST0])),
+        b"ftst" | b"fxam" => form(fmt, &[]).reads(regs(&[ST0])),
+
+        b"fcmovb" | b"fcmove" | b"fcmovbe" | b"fcmovu" | b"fcmovnb" | b"fcmovne" | b"fcmovnbe" | b"fcmovnu" => {
+            let tested = match name {
+                b"fcmovu" | b"fcmovnu" => PF,
+                b"fcmovbe" | b"fcmovnbe" => CF.union(ZF),
+                b"fcmove" | b"fcmovne" => ZF,
+                _ => CF,
+            };
+            let semantics = match fmt {
+                b"" => form(fmt, &[]).modifies(regs(&[ST0])).reads(regs(&[ST1])),
+                b"fp" => form(fmt, &[R]).modifies(regs(&[ST0])),
+                _ => form(fmt, &[RW, R]),
+            };
+            semantics.uses(tested)
+        },
+
+        b"fld" | b"fild" | b"fbld" => form(fmt, &[R]).stack(),
+        b"fld1" | b"fldz" | b"fldpi" | b"fldl2e" | b"fldl2t" | b"fldlg2" | b"fldln2" => form(fmt, &[]).stack(),
+        b"fst" if fmt.is_empty() => form(fmt, &[]).reads(regs(&[ST0])).writes(regs(&[ST1])),
+        b"fst" | b"fist" => form(fmt, &[W]).reads(regs(&[ST0])),
+        b"fstp" | b"fistp" | b"fisttp" | b"fbstp" => form(fmt, &[W]).stack(),
+        b"fxch" => match fmt {
+            b"" => form(fmt, &[]).modifies(regs(&[ST0, ST1])),
+            b"fp" => form(fmt, &[RW]).modifies(regs(&[ST0])),
+            _ => form(fmt, &[RW, RW]),
+        },
+        b"ffree" if fmt.is_empty() => form(fmt, &[]).writes(regs(&[ST1])),
+        b"ffree" => form(fmt, &[W]),
+
+        b"fabs" | b"fchs" | b"frndint" | b"fsqrt" | b"fsin" | b"fcos" | b"f2xm1" => form(fmt, &[]).modifies(regs(&[ST0])),
+        b"fscale" | b"fprem" | b"fprem1" => form(fmt, &[]).modifies(regs(&[ST0])).reads(regs(&[ST1])),
+        b"fxtract" | b"fptan" | b"fsincos" | b"fpatan" | b"fyl2x" | b"fyl2xp1" | b"fdecstp" | b"fincstp" => form(fmt, &[]).stack(),
+
+        b"finit" | b"fninit" => form(fmt, &[]).writes(RegSet::ST.union(RegSet::MMX)),
+        b"fsave" | b"fnsave" => form(fmt, &[W]).modifies(RegSet::ST.union(RegSet::MMX)),
+        b"frstor" => form(fmt, &[R]).writes(RegSet::ST.union(RegSet::MMX)),
+        b"fxsave" | b"fxsave64" => form(fmt, &[W]).reads(SAVED_STATE),
+        b"fxrstor" | b"fxrstor64" => form(fmt, &[R]).writes(SAVED_STATE),
+        b"fldcw" | b"fldenv" => form(fmt, &[R]),
+        b"fstcw" | b"fnstcw" | b"fstenv" | b"fnstenv" | b"fstsw" | b"fnstsw" => form(fmt, &[W]),
+        b"fclex" | b"fnclex" | b"fdisi" | b"fndisi" | b"feni" | b"fneni" | b"fsetpm" | b"fnop" | b"fwait" => form(fmt, &[]),
+
+        _ => return None
+    })
+}
+
+/// Returns if a form is a SIMD instruction that isn't listed explicitly. Instructions with
+/// registers implied by their operands are left out, as those can't be described.
+const fn is_simd(name: &[u8], fmt: &[u8], flags: Flags, features: Features) -> bool {
+    if matches!(name, b"paddsiw" | b"psubsiw" | b"pmulhriw" | b"pmachriw" | b"pdistib") {
+        return false;
+    }
+
+    let simd_features = Features::MMX.union(Features::TDNOW).union(Features::SSE).union(Features::SSE2)
+        .union(Features::SSE3).union(Features::SSSE3).union(Features::SSE4A).union(Features::SSE41)
+        .union(Features::SSE42).union(Features::SSE5).union(Features::AVX).union(Features::AVX2)
+        .union(Features::FMA).union(Features::SHA);
+
+    features.intersects(simd_features) || flags.intersects(Flags::VEX_OP.union(Flags::XOP_OP)) || has_operand(fmt, b"xyuwkl")
+}
+
+const fn simd(name: &[u8], fmt: &[u8], flags: Flags, features: Features) -> Semantics {
+    use super::ast::RegId::*;
+
+    let form = Semantics::form;
+    let regs = RegSet::of;
+
+    // string comparisons, which leave their result in a fixed register
+    match name {
+        b"pcmpestri" | b"vpcmpestri" => return form(fmt, &[R, R, U]).reads(regs(&[RAX, RDX])).writes(regs(&[RCX])).sets(STATUS),
+        b"pcmpestrm" | b"vpcmpestrm" => return form(fmt, &[R, R, U]).reads(regs(&[RAX, RDX])).writes(regs(&[XMM0])).sets(STATUS),
+        b"pcmpistri" | b"vpcmpistri" => return form(fmt, &[R, R, U]).writes(regs(&[RCX])).sets(STATUS),
+        b"pcmpistrm" | b"vpcmpistrm" => return form(fmt, &[R, R, U]).writes(regs(&[XMM0])).sets(STATUS),
+        _ => ()
+    }
+
+    if flags.intersects(Flags::VEX_OP.union(Flags::XOP_OP)) {
+        // VEX and XOP encoded instructions don't overwrite their sources
+        match name {
+            b"vzeroupper" => form(fmt, &[]).modifies(RegSet::XMM),
+            b"vzeroall" => form(fmt, &[]).writes(RegSet::XMM),
+            b"vcomiss" | b"vcomisd" | b"vucomiss" | b"vucomisd" | b"vptest" | b"vtestps" | b"vtestpd" => form(fmt, &[R, R]).sets(STATUS),
+            b"vldmxcsr" => form(fmt, &[R]),
+            b"vstmxcsr" => form(fmt, &[W]),
+            b"vmaskmovdqu" => form(fmt, &[R, R]).reads(regs(&[RDI])).memory(W),
+            // the mask is cleared as elements are loaded
+            b"vgatherdpd" | b"vgatherdps" | b"vgatherqpd" | b"vgatherqps" |
+            b"vpgatherdd" | b"vpgatherdq" | b"vpgatherqd" | b"vpgatherqq" => form(fmt, &[RW, R, RW]),
+            // masked stores leave some of the elements unchanged
+            b"vmaskmovps" | b"vmaskmovpd" | b"vpmaskmovd" | b"vpmaskmovq" if fmt[0] == b'm' => form(fmt, &[RW, R, R]),
+            _ if features.contains(Features::FMA) => form(fmt, &[RW, R, R]),
+            _ => form(fmt, &[W, R, R, R]),
+        }
+    } else {
+        // legacy encoded instructions overwrite their first source, unless they only have one
+        match name {
+            b"movss" | b"movsd" if matches!(fmt, b"yoyo") => form(fmt, &[RW, R]),
+            b"movlps" | b"movhps" | b"movlpd" | b"movhpd" if fmt[0] == b'y' => form(fmt, &[RW, R]),
+            b"comiss" | b"comisd" | b"ucomiss" | b"ucomisd" | b"ptest" => form(fmt, &[R, R]).sets(STATUS),
+            b"ldmxcsr" => form(fmt, &[R]),
+            b"stmxcsr" => form(fmt, &[W]),
+            b"maskmovq" | b"maskmovdqu" => form(fmt, &[R, R]).reads(regs(&[RDI])).memory(W),
+            b"blendvps" | b"blendvpd" | b"pblendvb" | b"sha256rnds2" => form(fmt, &[RW, R]).reads(regs(&[XMM0])),
+            b"emms" | b"femms" => form(fmt, &[]).writes(RegSet::ST.union(RegSet::MMX)),
+            b"prefetch" | b"prefetchw" => form(fmt, &[U]),
+            b"movss" | b"movsd" | b"movlps" | b"movhps" | b"movlpd" | b"movhpd" |
+            b"movaps" | b"movapd" | b"movups" | b"movupd" | b"movdqa" | b"movdqu" | b"movd" | b"movq" |
+            b"movntdqa" | b"movntps" | b"movntpd" | b"movntdq" | b"movntq" | b"movntss" | b"movntsd" |
+            b"lddqu" | b"movddup" | b"movshdup" | b"movsldup" | b"movq2dq" | b"movdq2q" |
+            b"movmskps" | b"movmskpd" | b"pmovmskb" | b"pshufd" | b"pshufhw" | b"pshuflw" | b"pshufw" |
+            b"cvtdq2pd" | b"cvtdq2ps" | b"cvtpd2dq" | b"cvtpd2pi" | b"cvtpd2ps" | b"cvtpi2pd" | b"cvtps2dq" |
+            b"cvtps2pd" | b"cvtps2pi" | b"cvtsd2si" | b"cvtss2si" | b"cvttpd2dq" | b"cvttpd2pi" |
+            b"cvttps2dq" | b"cvttps2pi" | b"cvttsd2si" | b"cvttss2si" |
+            b"extractps" | b"pextrb" | b"pextrw" | b"pextrd" | b"pextrq" |
+            b"sqrtps" | b"sqrtpd" | b"rcpps" | b"rsqrtps" | b"roundps" | b"roundpd" |
+            b"pabsb" | b"pabsw" | b"pabsd" | b"phminposuw" | b"aesimc" | b"aeskeygenassist" |
+            b"pmovsxbw" | b"pmovsxbd" | b"pmovsxbq" | b"pmovsxwd" | b"pmovsxwq" | b"pmovsxdq" |
+            b"pmovzxbw" | b"pmovzxbd" | b"pmovzxbq" | b"pmovzxwd" | b"pmovzxwq" | b"pmovzxdq" |
+            b"pi2fd" | b"pi2fw" | b"pf2id" | b"pf2iw" | b"pfrcp" | b"pfrsqrt" | b"pswapd" |
+            b"pfrcpv" | b"pfrsqrtv" => form(fmt, &[W, R, U]),
+            _ => form(fmt, &[RW, R, U, U]),
+        }
+    }
+}
+
+impl Archx64 {
+    /// Returns the effects of `instruction` as it is compiled with the enabled features. This
+    /// includes the effects of its repeat prefix.
+    pub fn semantics(&self, instruction: &InstructionX64) -> Result<Semantics, Error> {
+        semantics(X86Mode::Long, self.features, &instruction.inst, &instruction.args)
+    }
+}
+
+impl Archx86 {
+    /// Returns the effects of `instruction` as it is compiled with the enabled features. This
+    /// includes the effects of its repeat prefix.
+    pub fn semantics(&self, instruction: &InstructionX86) -> Result<Semantics, Error> {
+        semantics(X86Mode::Protected, self.features, &instruction.inst, &instruction.args)
+    }
+}
+
+fn semantics(mode: X86Mode, features: Features, inst: &Instruction, args: &[CleanArg]) -> Result<Semantics, Error> {
+    let mut counter = LengthCounter::default();
+    let mut explainer = Explainer::default();
+    let ctx = Context {
+        state: &mut counter,
+        mode,
+        features,
+        explainer: Some(&mut explainer),
+    };
+
+    compiler::compile_instruction(ctx, inst.clone(), args.to_vec())?;
+    let form = explainer.finish().expect("compiled instruction without a form").form;
+    let semantics = form.semantics().ok_or(Error::NoSemantics { mnemonic: form.mnemonic() })?;
+
+    let repeated = inst.idents.iter()
+        .any(|ident| matches!(ident.name.as_str(), "rep" | "repe" | "repz" | "repne" | "repnz"));
+    Ok(if repeated { semantics.repeated() } else { semantics })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::typed::{self, inst, Reg};
+    use super::super::ast::RegId::*;
+
+    fn semantics(instruction: InstructionX64) -> Semantics {
+        Archx64::default().semantics(&instruction).unwrap()
+    }
+
+    #[test]
+    fn operand_access() {
+        let add = semantics(typed::add(Reg::RAX, Reg::RBX));
+        assert_eq!(add.operands(), [RW, R]);
+        assert_eq!(add.flags_read(), Rflags::empty());
+        assert_eq!(add.flags_written(), STATUS);
+        assert_eq!(add.flags_undefined(), Rflags::empty());
+
+        let mov = semantics(typed::mov(Reg::RAX, Reg::RBX));
+        assert_eq!(mov.operands(), [W, R]);
+        assert_eq!(mov.flags_read(), Rflags::empty());
+        assert_eq!(mov.flags_written(), Rflags::empty());
+        assert!(mov.implicit_reads().is_empty() && mov.implicit_writes().is_empty());
+    }
+
+    #[test]
+    fn implicit_registers() {
+        let div = semantics(typed::div(Reg::RCX));
+        assert_eq!(div.operands(), [R]);
+        assert_eq!(div.implicit_reads(), RegSet::of(&[RAX, RDX]));
+        assert_eq!(div.implicit_writes(), RegSet::of(&[RAX, RDX]));
+        assert_eq!(div.flags_written(), STATUS);
+        assert_eq!(div.flags_undefined(), STATUS);
+
+        // byte division only uses ax
+        let div = semantics(typed::div(Reg::CL));
+        assert_eq!(div.implicit_reads(), RegSet::of(&[RAX]));
+        assert_eq!(div.implicit_writes(), RegSet::of(&[RAX]));
+    }
+
+    #[test]
+    fn repeat_prefix() {
+        let movsb = semantics(inst("movsb", []));
+        assert_eq!(movsb.implicit_reads(), RegSet::of(&[RSI, RDI]));
+        assert_eq!(movsb.implicit_writes(), RegSet::of(&[RSI, RDI]));
+
+        let rep = semantics(inst("movsb", []).prefix("rep"));
+        assert_eq!(rep.operands(), []);
+        assert_eq!(rep.implicit_reads(), RegSet::of(&[RCX, RSI, RDI]));
+        assert_eq!(rep.implicit_writes(), RegSet::of(&[RCX, RSI, RDI]));
+        assert_eq!(rep.implicit_memory(), RW);
+        assert_eq!(rep.flags_read(), Rflags::DF);
+        assert_eq!(rep.flags_written(), Rflags::empty());
+    }
+
+    #[test]
+    fn flags() {
+        // a conditional move may leave its destination unchanged
+        let cmove = semantics(typed::cmove(Reg::RAX, Reg::RBX));
+        assert_eq!(cmove.operands(), [RW, R]);
+        assert_eq!(cmove.flags_read(), ZF);
+        assert_eq!(cmove.flags_written(), Rflags::empty());
+        assert_eq!(semantics(inst("cmovl", [Reg::EAX.into(), Reg::EBX.into()])).flags_read(), SF | OF);
+
+        // shifts by zero leave the flags unchanged
+        let shl = semantics(typed::shl(Reg::RAX, Reg::CL));
+        assert_eq!(shl.operands(), [RW, R]);
+        assert_eq!(shl.flags_read(), STATUS);
+        assert_eq!(shl.flags_written(), STATUS);
+        assert_eq!(shl.flags_undefined(), AF | OF);
+
+        // immediates aren't accessed
+        assert_eq!(semantics(typed::shl(Reg::RAX, 4)).operands(), [RW, U]);
+    }
+}
@@ -0,0 +1,540 @@
+//! A typed way to build x64 instructions from rust code, instead of assembling them from
+//! `ast::Instruction` and `CleanArg`s by hand.
+//!
+//! Registers are constants of their own types, memory references are built with `mem()`, and
+//! labels with `Label`. Anything that can be an operand converts into an `Arg`, and instructions
+//! are created with the function of their mnemonic or with `inst`. The result is an ordinary
+//! `InstructionX64` that is assembled like any other.
+//!
+//! ```
+//! use dynasm::BasicAssembler;
+//! use dynasm::arch::x64::{Archx64, AssembleX64};
+//! use dynasm::arch::x64::typed::{inst, mem, add, mov, ret, Label, Reg, Xmm};
+//!
+//! let mut ops = BasicAssembler { stmts: Vec::new() };
+//! let arch = Archx64::default();
+//! ops.compile_instruction(&arch, mov(Reg::RAX, mem().base(Reg::RBX).index(Reg::RCX, 8).disp(16).qword())).unwrap();
+//! ops.compile_instruction(&arch, add(Reg::EAX, 5)).unwrap();
+//! ops.compile_instruction(&arch, inst("vaddps", [Xmm::X1.into(), Xmm::X2.into(), Xmm::X3.into()])).unwrap();
+//! ops.compile_instruction(&arch, inst("jmp", [Label::global("start").into()])).unwrap();
+//! ops.compile_instruction(&arch, ret()).unwrap();
+//! ```
+//!
+//! Integer immediates and displacements get the smallest size that holds them, like literals
+//! in assembly. `Imm::sized` gives an immediate an exact size instead.
+
+use super::InstructionX64;
+use super::ast::{CleanArg, Instruction, RegFamily, RegId, Register};
+use crate::common::{Expr, Ident, Jump, JumpKind, Number, NumericRepr, Size, Value};
+
+use core::convert::TryFrom;
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    Static(RegId),
+    Dynamic(Expr),
+}
+
+/// A general purpose register of any size, or `rip` as the base of a memory reference.
+#[derive(Debug, Clone, Copy)]
+pub struct Reg {
+    size: Size,
+    kind: Kind,
+}
+
+/// An xmm register.
+#[derive(Debug, Clone, Copy)]
+pub struct Xmm(Kind);
+
+/// A ymm register.
+#[derive(Debug, Clone, Copy)]
+pub struct Ymm(Kind);
+
+/// An mmx register.
+#[derive(Debug, Clone, Copy)]
+pub struct Mmx(Kind);
+
+/// A register of the x87 stack.
+#[derive(Debug, Clone, Copy)]
+pub struct St(Kind);
+
+macro_rules! registers {
+    ($ty:ident; $($size:ident: $($name:ident = $id:ident),*;)*) => {
+        impl $ty {
+            $($(
+                pub const $name: $ty = $ty { size: Size::$size, kind: Kind::Static(RegId::$id) };
+            )*)*
+        }
+    };
+    ($ty:ident($size:ident); $($name:ident = $id:ident),*) => {
+        impl $ty {
+            $(
+                pub const $name: $ty = $ty(Kind::Static(RegId::$id));
+            )*
+        }
+    };
+}
+
+registers!(Reg;
+    QWORD: RAX = RAX, RCX = RCX, RDX = RDX, RBX = RBX, RSP = RSP, RBP = RBP, RSI = RSI, RDI = RDI,
+           R8 = R8, R9 = R9, R10 = R10, R11 = R11, R12 = R12, R13 = R13, R14 = R14, R15 = R15,
+           RIP = RIP;
+    DWORD: EAX = RAX, ECX = RCX, EDX = RDX, EBX = RBX, ESP = RSP, EBP = RBP, ESI = RSI, EDI = RDI,
+           R8D = R8, R9D = R9, R10D = R10, R11D = R11, R12D = R12, R13D = R13, R14D = R14, R15D = R15;
+    WORD:  AX = RAX, CX = RCX, DX = RDX, BX = RBX, SP = RSP, BP = RBP, SI = RSI, DI = RDI,
+           R8W = R8, R9W = R9, R10W = R10, R11W = R11, R12W = R12, R13W = R13, R14W = R14, R15W = R15;
+    BYTE:  AL = RAX, CL = RCX, DL = RDX, BL = RBX, SPL = RSP, BPL = RBP, SIL = RSI, DIL = RDI,
+           R8B = R8, R9B = R9, R10B = R10, R11B = R11, R12B = R12, R13B = R13, R14B = R14, R15B = R15,
+           AH = AH, CH = CH, DH = DH, BH = BH;
+);
+
+registers!(Xmm(OWORD);
+    X0 = XMM0, X1 = XMM1, X2 = XMM2, X3 = XMM3, X4 = XMM4, X5 = XMM5, X6 = XMM6, X7 = XMM7,
+    X8 = XMM8, X9 = XMM9, X10 = XMM10, X11 = XMM11, X12 = XMM12, X13 = XMM13, X14 = XMM14, X15 = XMM15
+);
+
+registers!(Ymm(HWORD);
+    Y0 = XMM0, Y1 = XMM1, Y2 = XMM2, Y3 = XMM3, Y4 = XMM4, Y5 = XMM5, Y6 = XMM6, Y7 = XMM7,
+    Y8 = XMM8, Y9 = XMM9, Y10 = XMM10, Y11 = XMM11, Y12 = XMM12, Y13 = XMM13, Y14 = XMM14, Y15 = XMM15
+);
+
+registers!(Mmx(QWORD);
+    MM0 = MMX0, MM1 = MMX1, MM2 = MMX2, MM3 = MMX3, MM4 = MMX4, MM5 = MMX5, MM6 = MMX6, MM7 = MMX7
+);
+
+registers!(St(PWORD);
+    ST0 = ST0, ST1 = ST1, ST2 = ST2, ST3 = ST3, ST4 = ST4, ST5 = ST5, ST6 = ST6, ST7 = ST7
+);
+
+impl Reg {
+    /// A general purpose register of the given size, chosen at runtime by its number.
+    pub fn dynamic(size: Size, number: Expr) -> Reg {
+        Reg { size, kind: Kind::Dynamic(number) }
+    }
+
+    pub fn size(self) -> Size {
+        self.size
+    }
+
+    /// The register as it appears in the AST.
+    pub fn register(self) -> Register {
+        make_register(self.size, RegFamily::LEGACY, self.kind)
+    }
+}
+
+macro_rules! vector_registers {
+    ($($ty:ident: $size:ident, $family:ident;)*) => {
+        $(
+            impl $ty {
+                /// A register chosen at runtime by its number.
+                pub fn dynamic(number: Expr) -> $ty {
+                    $ty(Kind::Dynamic(number))
+                }
+
+                /// The register as it appears in the AST.
+                pub fn register(self) -> Register {
+                    make_register(Size::$size, RegFamily::$family, self.0)
+                }
+            }
+
+            impl From<$ty> for Arg {
+                fn from(reg: $ty) -> Arg {
+                    Arg(CleanArg::Direct { reg: reg.register() })
+                }
+            }
+        )*
+    };
+}
+
+vector_registers!(
+    Xmm: OWORD, XMM;
+    Ymm: HWORD, XMM;
+    Mmx: QWORD, MMX;
+    St: PWORD, FP;
+);
+
+fn make_register(size: Size, family: RegFamily, kind: Kind) -> Register {
+    match kind {
+        Kind::Static(id) => Register::new_static(size, id),
+        Kind::Dynamic(number) => Register::new_dynamic(size, family, number),
+    }
+}
+
+/// Registers that can be the index of a memory reference. Vector registers are used for the
+/// vector indices of gathers and scatters.
+pub trait IndexReg {
+    fn index_register(self) -> Register;
+}
+
+impl IndexReg for Reg {
+    fn index_register(self) -> Register {
+        self.register()
+    }
+}
+
+impl IndexReg for Xmm {
+    fn index_register(self) -> Register {
+        self.register()
+    }
+}
+
+impl IndexReg for Ymm {
+    fn index_register(self) -> Register {
+        self.register()
+    }
+}
+
+/// An immediate operand.
+#[derive(Debug, Clone, Copy)]
+pub struct Imm(Value);
+
+impl Imm {
+    /// An immediate of exactly the given size.
+    pub fn sized(value: i64, size: Size) -> Imm {
+        Imm(Value::Number(Number::from_u64_and_repr(value as u64, NumericRepr::signed(size))))
+    }
+
+    /// An immediate computed at runtime. Its size is that of the representation of `expr`.
+    pub fn expr(expr: Expr) -> Imm {
+        Imm(Value::Expr(expr))
+    }
+}
+
+/// A label, used as a jump target or in a memory reference.
+#[derive(Debug, Clone)]
+pub struct Label(Jump);
+
+impl Label {
+    /// A global label, like `->name`.
+    pub fn global(name: &str) -> Label {
+        Label::new(JumpKind::Global(Ident { name: name.to_string() }))
+    }
+
+    /// The closest local label before the instruction, like `<name`.
+    pub fn backward(name: &str) -> Label {
+        Label::new(JumpKind::Backward(Ident { name: name.to_string() }))
+    }
+
+    /// The closest local label after the instruction, like `>name`.
+    pub fn forward(name: &str) -> Label {
+        Label::new(JumpKind::Forward(Ident { name: name.to_string() }))
+    }
+
+    /// A dynamic label, like `=>label`.
+    pub fn dynamic(label: Expr) -> Label {
+        Label::new(JumpKind::Dynamic(label))
+    }
+
+    /// An absolute address, like `extern address`.
+    pub fn bare(address: Value) -> Label {
+        Label::new(JumpKind::Bare(address))
+    }
+
+    fn new(kind: JumpKind) -> Label {
+        Label(Jump::new(kind, None))
+    }
+
+    /// Offsets the target from the label by a runtime amount.
+    pub fn offset(mut self, offset: Expr) -> Label {
+        self.0.offset = Some(offset);
+        self
+    }
+
+    /// A memory reference to the label, like `[->name]`.
+    pub fn mem(self) -> LabelMem {
+        LabelMem { jump: self.0, size: None }
+    }
+}
+
+/// A memory reference built from a base, an index and a displacement.
+#[derive(Debug, Clone)]
+pub struct Mem {
+    size: Option<Size>,
+    base: Option<Register>,
+    index: Option<(Register, isize)>,
+    disp: Option<Value>,
+}
+
+/// A memory reference to a label.
+#[derive(Debug, Clone)]
+pub struct LabelMem {
+    jump: Jump,
+    size: Option<Size>,
+}
+
+/// Starts an empty memory reference.
+pub fn mem() -> Mem {
+    Mem { size: None, base: None, index: None, disp: None }
+}
+
+impl Mem {
+    pub fn base(mut self, base: Reg) -> Mem {
+        self.base = Some(base.register());
+        self
+    }
+
+    /// Adds `index * scale`, where the scale is 1, 2, 4 or 8.
+    pub fn index<R: IndexReg>(mut self, index: R, scale: u8) -> Mem {
+        self.index = Some((index.index_register(), scale as isize));
+        self
+    }
+
+    pub fn disp(mut self, disp: i32) -> Mem {
+        let size = signed_size(disp as i64);
+        self.disp = Some(Value::Number(Number::from_u64_and_repr(disp as i64 as u64, NumericRepr::signed(size))));
+        self
+    }
+
+    /// A displacement computed at runtime.
+    pub fn disp_expr(mut self, disp: Expr) -> Mem {
+        self.disp = Some(Value::Expr(disp));
+        self
+    }
+}
+
+macro_rules! size_methods {
+    ($($ty:ident),*) => {
+        $(
+            impl $ty {
+                /// Gives the memory reference an explicit size.
+                pub fn sized(mut self, size: Size) -> $ty {
+                    self.size = Some(size);
+                    self
+                }
+
+                pub fn byte(self) -> $ty { self.sized(Size::BYTE) }
+                pub fn word(self) -> $ty { self.sized(Size::WORD) }
+                pub fn dword(self) -> $ty { self.sized(Size::DWORD) }
+                pub fn fword(self) -> $ty { self.sized(Size::FWORD) }
+                pub fn qword(self) -> $ty { self.sized(Size::QWORD) }
+                pub fn tword(self) -> $ty { self.sized(Size::PWORD) }
+                pub fn oword(self) -> $ty { self.sized(Size::OWORD) }
+                pub fn yword(self) -> $ty { self.sized(Size::HWORD) }
+            }
+        )*
+    };
+}
+
+size_methods!(Mem, LabelMem);
+
+/// The smallest size that holds a signed value.
+fn signed_size(value: i64) -> Size {
+    if i8::try_from(value).is_ok() {
+        Size::BYTE
+    } else if i16::try_from(value).is_ok() {
+        Size::WORD
+    } else if i32::try_from(value).is_ok() {
+        Size::DWORD
+    } else {
+        Size::QWORD
+    }
+}
+
+/// The smallest size that holds an unsigned value.
+fn unsigned_size(value: u64) -> Size {
+    if u8::try_from(value).is_ok() {
+        Size::BYTE
+    } else if u16::try_from(value).is_ok() {
+        Size::WORD
+    } else if u32::try_from(value).is_ok() {
+        Size::DWORD
+    } else {
+        Size::QWORD
+    }
+}
+
+/// An operand of an instruction.
+#[derive(Debug, Clone)]
+pub struct Arg(CleanArg);
+
+impl From<Arg> for CleanArg {
+    fn from(arg: Arg) -> CleanArg {
+        arg.0
+    }
+}
+
+impl From<CleanArg> for Arg {
+    fn from(arg: CleanArg) -> Arg {
+        Arg(arg)
+    }
+}
+
+impl From<Reg> for Arg {
+    fn from(reg: Reg) -> Arg {
+        Arg(CleanArg::Direct { reg: reg.register() })
+    }
+}
+
+impl From<Mem> for Arg {
+    fn from(mem: Mem) -> Arg {
+        Arg(CleanArg::Indirect {
+            nosplit: false,
+            size: mem.size,
+            disp_size: None,
+            base: mem.base,
+            index: mem.index.map(|(reg, scale)| (reg, scale, None)),
+            disp: mem.disp,
+        })
+    }
+}
+
+impl From<LabelMem> for Arg {
+    fn from(mem: LabelMem) -> Arg {
+        Arg(CleanArg::IndirectJumpTarget { jump: mem.jump, size: mem.size })
+    }
+}
+
+impl From<Label> for Arg {
+    fn from(label: Label) -> Arg {
+        Arg(CleanArg::JumpTarget { jump: label.0, size: None })
+    }
+}
+
+impl From<Imm> for Arg {
+    fn from(imm: Imm) -> Arg {
+        Arg(CleanArg::Immediate { value: imm.0 })
+    }
+}
+
+impl From<Expr> for Arg {
+    fn from(expr: Expr) -> Arg {
+        Imm::expr(expr).into()
+    }
+}
+
+macro_rules! integer_args {
+    ($sized:ident, $wide:ty, $signed:expr; $($ty:ty),*) => {
+        $(
+            impl From<$ty> for Arg {
+                fn from(value: $ty) -> Arg {
+                    let repr = NumericRepr { size: $sized(value as $wide), signed: $signed };
+                    Imm(Value::Number(Number::from_u64_and_repr(value as $wide as u64, repr))).into()
+                }
+            }
+        )*
+    };
+}
+
+integer_args!(signed_size, i64, true; i8, i16, i32, i64);
+integer_args!(unsigned_size, u64, false; u8, u16, u32, u64);
+
+/// Builds an instruction from its mnemonic and operands.
+pub fn inst<I: IntoIterator<Item=Arg>>(mnemonic: &str, args: I) -> InstructionX64 {
+    InstructionX64 {
+        inst: Instruction { idents: alloc::vec![Ident { name: mnemonic.to_string() }] },
+        args: args.into_iter().map(CleanArg::from).collect::<Vec<_>>(),
+    }
+}
+
+impl InstructionX64 {
+    /// Adds a prefix like `lock` or `rep` to the instruction.
+    pub fn prefix(mut self, prefix: &str) -> InstructionX64 {
+        let at = self.inst.idents.len().saturating_sub(1);
+        self.inst.idents.insert(at, Ident { name: prefix.to_string() });
+        self
+    }
+}
+
+macro_rules! mnemonics {
+    ($($name:ident($($arg:ident),*);)*) => {
+        $(
+            #[doc = concat!("Builds a `", stringify!($name), "` instruction.")]
+            pub fn $name($($arg: impl Into<Arg>),*) -> InstructionX64 {
+                inst(stringify!($name), [$($arg.into()),*])
+            }
+        )*
+    };
+}
+
+mnemonics! {
+    nop();
+    ret();
+    int3();
+    ud2();
+    cdq();
+    cqo();
+    cdqe();
+    leave();
+    mfence();
+    push(src);
+    pop(dst);
+    inc(dst);
+    dec(dst);
+    neg(dst);
+    not(dst);
+    mul(src);
+    div(src);
+    idiv(src);
+    call(target);
+    jmp(target);
+    je(target);
+    jne(target);
+    jl(target);
+    jle(target);
+    jg(target);
+    jge(target);
+    jb(target);
+    jbe(target);
+    ja(target);
+    jae(target);
+    js(target);
+    jns(target);
+    sete(dst);
+    setne(dst);
+    mov(dst, src);
+    movzx(dst, src);
+    movsx(dst, src);
+    movsxd(dst, src);
+    lea(dst, src);
+    add(dst, src);
+    adc(dst, src);
+    sub(dst, src);
+    sbb(dst, src);
+    and(dst, src);
+    or(dst, src);
+    xor(dst, src);
+    cmp(dst, src);
+    test(dst, src);
+    xchg(dst, src);
+    shl(dst, count);
+    shr(dst, count);
+    sar(dst, count);
+    rol(dst, count);
+    ror(dst, count);
+    imul(dst, src);
+    cmove(dst, src);
+    cmovne(dst, src);
+    movd(dst, src);
+    movq(dst, src);
+    movss(dst, src);
+    movsd(dst, src);
+    movaps(dst, src);
+    movups(dst, src);
+    movdqa(dst, src);
+    movdqu(dst, src);
+    addss(dst, src);
+    addsd(dst, src);
+    subss(dst, src);
+    subsd(dst, src);
+    mulss(dst, src);
+    mulsd(dst, src);
+    divss(dst, src);
+    divsd(dst, src);
+    addps(dst, src);
+    mulps(dst, src);
+    xorps(dst, src);
+    pxor(dst, src);
+    vmovaps(dst, src);
+    vmovups(dst, src);
+    vaddps(dst, a, b);
+    vsubps(dst, a, b);
+    vmulps(dst, a, b);
+    vdivps(dst, a, b);
+    vxorps(dst, a, b);
+    vfmadd231ps(dst, a, b);
+    shld(dst, src, count);
+    shrd(dst, src, count);
+}
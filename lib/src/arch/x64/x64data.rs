@@ -1,20 +1,28 @@
-use std::collections::{HashMap, hash_map};
+use super::compiler::{Opdata, format_signature};
+use super::semantics::describe;
+use crate::common::{NameTable, name_table_slots};
+use core::fmt::{self, Display};
 
-use super::compiler::Opdata;
-use std::fmt::{self, Display};
-
-use lazy_static::lazy_static;
 use bitflags::bitflags;
 
+use alloc::vec::Vec;
+
 macro_rules! constify {
     ($t:ty, $e:expr) => { {const C: &$t = &$e; C} }
 }
 
 macro_rules! OpInner {
-    ($fmt:expr, $ops:expr, $reg:expr)          => { Opdata {args: $fmt, ops: constify!([u8], $ops), reg: $reg, flags: Flags::DEFAULT,  features: Features::X64_IMPLICIT}  };
-    ($fmt:expr, $ops:expr, $reg:expr, $f:expr) => { Opdata {args: $fmt, ops: constify!([u8], $ops), reg: $reg, flags: Flags::make($f), features: Features::X64_IMPLICIT}  };
-    ($fmt:expr, $ops:expr, $reg:expr, $f:expr, $ft:expr) => { Opdata {args: $fmt, ops: constify!([u8], $ops), reg: $reg, flags: Flags::make($f), features: Features::make($ft)}  };
-
+    ($name:expr; $fmt:expr, $ops:expr, $reg:expr)          => { OpInner!($name; $fmt, $ops, $reg, DEFAULT, X64_IMPLICIT) };
+    ($name:expr; $fmt:expr, $ops:expr, $reg:expr, $f:expr) => { OpInner!($name; $fmt, $ops, $reg, $f, X64_IMPLICIT) };
+    ($name:expr; $fmt:expr, $ops:expr, $reg:expr, $f:expr, $ft:expr) => { Opdata {
+        args: $fmt,
+        ops: constify!([u8], $ops),
+        reg: $reg,
+        flags: Flags::make($f),
+        features: Features::make($ft),
+        signature: format_signature($fmt),
+        semantics: describe($name.as_bytes(), $fmt, Flags::make($f), Features::make($ft)),
+    } };
 }
 
 macro_rules! Ops {
@@ -23,7 +31,7 @@ macro_rules! Ops {
             (
                 $name,
                 {
-                    const OPDATA: &[Opdata] = &[$( OpInner!($( $e ),*) ,)+];
+                    const OPDATA: &[Opdata] = &[$( OpInner!($name; $( $e ),*) ,)+];
                     OPDATA
                 }
             )
@@ -32,7 +40,12 @@ macro_rules! Ops {
 }
 
 pub fn get_mnemnonic_data(name: &str) -> Option<&'static [Opdata]> {
-    OPMAP.get(&name).cloned()
+    OPMAP.get(name).map(|&(_, data)| data)
+}
+
+/// Like `get_mnemnonic_data`, but also returns the static name of the mnemonic.
+pub fn get_mnemnonic_entry(name: &str) -> Option<(&'static str, &'static [Opdata])> {
+    OPMAP.get(name).cloned()
 }
 
 bitflags! {
@@ -77,6 +90,7 @@ impl Flags {
 }
 
 bitflags! {
+    #[derive(Default)]
     pub struct Features: u32 {
         const X64_IMPLICIT = 0x0000_0000;
         const FPU          = 0x0000_0001;
@@ -112,38 +126,44 @@ impl Features {
         Features { bits }
     }
 
-    pub fn from_str(name: &str) -> Option<Features> {
-        match name {
-            "fpu"   => Some(Features::FPU),
-            "mmx"   => Some(Features::MMX),
-            "tdnow" => Some(Features::TDNOW),
-            "sse"   => Some(Features::SSE),
-            "sse2"  => Some(Features::SSE2),
-            "sse3"  => Some(Features::SSE3),
-            "vmx"   => Some(Features::VMX),
-            "ssse3" => Some(Features::SSSE3),
-            "sse4a" => Some(Features::SSE4A),
-            "sse41" => Some(Features::SSE41),
-            "sse42" => Some(Features::SSE42),
-            "sse5"  => Some(Features::SSE5),
-            "avx"   => Some(Features::AVX),
-            "avx2"  => Some(Features::AVX2),
-            "fma"   => Some(Features::FMA),
-            "bmi1"  => Some(Features::BMI1),
-            "bmi2"  => Some(Features::BMI2),
-            "tbm"   => Some(Features::TBM),
-            "rtm"   => Some(Features::RTM),
-            "invpcid" => Some(Features::INVPCID),
-            "mpx"   => Some(Features::MPX),
-            "sha"   => Some(Features::SHA),
-            "prefetchwt1" => Some(Features::PREFETCHWT1),
-            "cyrix" => Some(Features::CYRIX),
-            "amd"   => Some(Features::AMD),
-            _ => None
-        }
+    pub fn from_name(name: &str) -> Option<Features> {
+        FEATURE_NAMES.iter().find(|&&(n, _)| n == name).map(|&(_, feature)| feature)
+    }
+
+    /// The names of all features that can be enabled.
+    pub fn names() -> impl Iterator<Item=&'static str> {
+        FEATURE_NAMES.iter().map(|&(name, _)| name)
     }
 }
 
+const FEATURE_NAMES: &[(&str, Features)] = &[
+    ("fpu",   Features::FPU),
+    ("mmx",   Features::MMX),
+    ("tdnow", Features::TDNOW),
+    ("sse",   Features::SSE),
+    ("sse2",  Features::SSE2),
+    ("sse3",  Features::SSE3),
+    ("vmx",   Features::VMX),
+    ("ssse3", Features::SSSE3),
+    ("sse4a", Features::SSE4A),
+    ("sse41", Features::SSE41),
+    ("sse42", Features::SSE42),
+    ("sse5",  Features::SSE5),
+    ("avx",   Features::AVX),
+    ("avx2",  Features::AVX2),
+    ("fma",   Features::FMA),
+    ("bmi1",  Features::BMI1),
+    ("bmi2",  Features::BMI2),
+    ("tbm",   Features::TBM),
+    ("rtm",   Features::RTM),
+    ("invpcid", Features::INVPCID),
+    ("mpx",   Features::MPX),
+    ("sha",   Features::SHA),
+    ("prefetchwt1", Features::PREFETCHWT1),
+    ("cyrix", Features::CYRIX),
+    ("amd",   Features::AMD),
+];
+
 impl Display for Features {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut keys = Vec::new();
@@ -182,9 +202,9 @@ impl Display for Features {
     }
 }
 
-#[allow(dead_code)]
-pub fn mnemnonics() -> hash_map::Keys<'static, &'static str, &'static [Opdata]> {
-    OPMAP.keys()
+/// All mnemonics, in sorted order.
+pub fn mnemnonics() -> impl Iterator<Item=&'static str> {
+    OPMAP.entries().iter().map(|&(name, _)| name)
 }
 
 // workaround until bitflags can be used in const
@@ -214,7 +234,6 @@ const ENC_VM     : u32 = Flags::ENC_VM.bits;
 const ENC_MIB    : u32 = Flags::ENC_MIB.bits;
 const X86_ONLY   : u32 = Flags::X86_ONLY.bits;
 
-#[allow(dead_code)]
 const X64_IMPLICIT : u32 = Features::X64_IMPLICIT.bits;
 const FPU          : u32 = Features::FPU.bits;
 const MMX          : u32 = Features::MMX.bits;
@@ -243,10 +262,24 @@ const CYRIX        : u32 = Features::CYRIX.bits;
 const AMD          : u32 = Features::AMD.bits;
 
 
-lazy_static! {
-    static ref OPMAP: HashMap<&'static str, &'static [Opdata]> = {
-        const X: u8 = 0xFF;
-        static MAP: &[(&str, &[Opdata])] = &include!("gen_opmap.rs");
-        MAP.iter().cloned().collect()
-    };
+const X: u8 = 0xFF;
+const UNSORTED_OPMAP: &[(&str, &[Opdata])] = &include!("gen_opmap.rs");
+
+const OPMAP_LEN: usize = UNSORTED_OPMAP.len();
+
+// built at compile time, so lookups don't need any initialization
+static OPMAP: NameTable<&[Opdata], OPMAP_LEN, { name_table_slots(OPMAP_LEN) }> = NameTable::new(UNSORTED_OPMAP);
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opmap_finds_every_mnemonic() {
+        for &(name, data) in UNSORTED_OPMAP {
+            let found = get_mnemnonic_data(name).unwrap_or_else(|| panic!("{} is missing", name));
+            assert_eq!(alloc::format!("{:?}", found), alloc::format!("{:?}", data), "{}", name);
+        }
+    }
 }
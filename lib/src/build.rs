@@ -0,0 +1,322 @@
+//! Compiles assembly files into rust modules from build scripts.
+//!
+//! This makes the assembler usable without the `dynasm!` procedural macro. A `.dasm` file holds
+//! templates of assembly, and each template is compiled into a function that emits it into an
+//! assembler.
+//!
+//! ```text
+//! // templates.dasm
+//! .arch x64
+//!
+//! add_const(value: i32):
+//!     add rax, value
+//!     ret
+//!
+//! call_dynamic(target: DynamicLabel):
+//!     call =>target
+//! ```
+//!
+//! Every line is parsed like a line of the `dynasm!` macro, with the same directives, labels
+//! and instructions. A line of the form `name(param: Type, ...):` starts a new template, and the
+//! parameters can be used in place of any runtime expression. Lines before the first template
+//! may only contain directives that don't emit anything, like `.arch`, `.feature`, `.alias` and the
+//! definitions of macros, which can then be used by every template that follows.
+//! A template uses the relocations of the architecture that was selected when it started.
+//!
+//! The generated functions take the assembler as `ops: &mut D`, where `D` implements
+//! `dynasmrt::DynasmLabelApi` for that architecture. In `build.rs`:
+//!
+//! ```no_run
+//! dynasm::build::Build::new()
+//!     .file("src/templates.dasm")
+//!     .compile()
+//!     .unwrap();
+//! ```
+//!
+//! And then in the crate, where the module is included from `OUT_DIR`:
+//!
+//! ```text
+//! mod templates {
+//!     use dynasmrt::DynamicLabel;
+//!     include!(concat!(env!("OUT_DIR"), "/templates.rs"));
+//! }
+//!
+//! templates::add_const(&mut ops, 5);
+//! ```
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use syn::parse::{ParseStream, Parser};
+use syn::Token;
+use quote::quote;
+
+use crate::frontend::{compile_line, finish, serialize, split_lines, Builder};
+use crate::DynasmData;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::format;
+
+use core::fmt;
+use core::str::FromStr;
+use std::path::{Path, PathBuf};
+use std::{env, fs, io};
+
+/// Compiles a set of `.dasm` files into rust modules.
+///
+/// Every file is written to a module with the same name in the output directory, which is
+/// `OUT_DIR` unless another one is given.
+#[derive(Debug, Default)]
+pub struct Build {
+    files: Vec<PathBuf>,
+    out_dir: Option<PathBuf>,
+}
+
+impl Build {
+    pub fn new() -> Build {
+        Build::default()
+    }
+
+    /// Adds a file to compile.
+    pub fn file<P: AsRef<Path>>(&mut self, path: P) -> &mut Build {
+        self.files.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sets the directory the modules are written to.
+    pub fn out_dir<P: AsRef<Path>>(&mut self, path: P) -> &mut Build {
+        self.out_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Compiles every file, and tells cargo to rerun the build script if any of them change.
+    pub fn compile(&self) -> Result<(), Error> {
+        let out_dir = match self.out_dir {
+            Some(ref out_dir) => out_dir.clone(),
+            None => env::var_os("OUT_DIR").map(PathBuf::from).ok_or(Error::NoOutDir)?,
+        };
+
+        for path in &self.files {
+            std::println!("cargo:rerun-if-changed={}", path.display());
+
+            let source = fs::read_to_string(path)
+                .map_err(|error| Error::Io { path: path.clone(), error })?;
+            let module = compile_str(&source)
+                .map_err(|diagnostics| Error::Assembly { path: path.clone(), diagnostics })?;
+
+            let mut dest = out_dir.join(path.file_stem().unwrap_or(path.as_os_str()));
+            dest.set_extension("rs");
+            let module = format!("// Generated by dynasm::build from {}. Do not edit.\n{}\n", path.display(), module);
+            fs::write(&dest, module)
+                .map_err(|error| Error::Io { path: dest.clone(), error })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// An error while compiling a `.dasm` file.
+#[derive(Debug)]
+pub enum Error {
+    /// No output directory was given, and `OUT_DIR` isn't set.
+    NoOutDir,
+    /// A file couldn't be read or written.
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    /// A file couldn't be assembled.
+    Assembly {
+        path: PathBuf,
+        diagnostics: Vec<Diagnostic>,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::NoOutDir => f.write_str("OUT_DIR is not set, and no output directory was given"),
+            Error::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            Error::Assembly { path, diagnostics } => {
+                for (i, diagnostic) in diagnostics.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str("\n")?;
+                    }
+                    write!(f, "{}:{}", path.display(), diagnostic)?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// An error on a line of assembly.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The line it occurred on, starting at 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.line, self.message)
+    }
+}
+
+/// A template that is being compiled.
+struct Template {
+    header: TokenStream,
+    builder: Builder,
+}
+
+/// Compiles the source of a `.dasm` file into the source of a rust module.
+pub fn compile_str(source: &str) -> Result<String, Vec<Diagnostic>> {
+    let mut data = DynasmData::new();
+    let mut diagnostics = Vec::new();
+    let mut functions = Vec::new();
+    let mut template: Option<Template> = None;
+
+    for (idx, text) in source.lines().enumerate() {
+        let line = idx + 1;
+        let mut report = |message: String| diagnostics.push(Diagnostic { line, message });
+
+        let tokens = match TokenStream::from_str(text) {
+            Ok(tokens) => tokens,
+            Err(e) => {
+                report(e.to_string());
+                continue;
+            }
+        };
+        if tokens.is_empty() {
+            continue;
+        }
+
+        if data.recording.is_none() && data.is_assembling() && is_template_header(&tokens) {
+            functions.extend(template.take().map(finish_template));
+            match parse_header(&data, tokens) {
+                Ok(header) => template = Some(Template { header, builder: Builder::new() }),
+                Err(e) => e.into_iter().for_each(|e| report(e.to_string())),
+            }
+            continue;
+        }
+
+        // lines outside of templates are only allowed to change the state of the assembler
+        let mut scratch = Builder::new();
+        let builder = match template {
+            Some(ref mut template) => &mut template.builder,
+            None => &mut scratch,
+        };
+
+        for statement in split_lines(tokens) {
+            if statement.is_empty() {
+                continue;
+            }
+            if let Err(e) = compile_line(&mut data, builder, statement) {
+                e.into_iter().for_each(|e| report(e.to_string()));
+            }
+        }
+
+        if !scratch.stmts.is_empty() {
+            report("Only directives that don't emit anything can be used outside of a template".to_string());
+        }
+    }
+    functions.extend(template.take().map(finish_template));
+
+    if let Err(e) = finish(&mut data) {
+        let line = source.lines().count();
+        diagnostics.push(Diagnostic { line, message: e.to_string() });
+    }
+
+    if !diagnostics.is_empty() {
+        return Err(diagnostics);
+    }
+    Ok(quote! { #(#functions)* }.to_string())
+}
+
+/// If the line is of the form `name(...):`.
+fn is_template_header(tokens: &TokenStream) -> bool {
+    let tokens: Vec<_> = tokens.clone().into_iter().collect();
+    match tokens.as_slice() {
+        [TokenTree::Ident(_), TokenTree::Group(params), TokenTree::Punct(colon)] =>
+            params.delimiter() == Delimiter::Parenthesis && colon.as_char() == ':',
+        _ => false,
+    }
+}
+
+/// Parses the header of a template into the signature of the function it becomes.
+///
+/// ```text
+/// name "(" (ident ":" type ("," ident ":" type)* ","?)? ")" ":"
+/// ```
+fn parse_header(data: &DynasmData, tokens: TokenStream) -> syn::Result<TokenStream> {
+    let relocation = match data.current_arch.name() {
+        "x64" => quote! { ::dynasmrt::x64::X64Relocation },
+        "x86" => quote! { ::dynasmrt::x86::X86Relocation },
+        "aarch64" => quote! { ::dynasmrt::aarch64::Aarch64Relocation },
+        arch => return Err(syn::Error::new(proc_macro2::Span::call_site(),
+            format!("Templates can't be compiled for the '{}' architecture", arch))),
+    };
+
+    let parser = |input: ParseStream| {
+        let name: syn::Ident = input.parse()?;
+        let content;
+        syn::parenthesized!(content in input);
+        let params = content.parse_terminated(|param: ParseStream| {
+            let name: syn::Ident = param.parse()?;
+            let _: Token![:] = param.parse()?;
+            let ty: syn::Type = param.parse()?;
+            Ok(quote! { #name: #ty })
+        }, Token![,])?;
+        let _: Token![:] = input.parse()?;
+
+        let params = params.into_iter();
+        Ok(quote! {
+            pub fn #name<D>(ops: &mut D, #(#params),*)
+                where D: ::dynasmrt::DynasmLabelApi<Relocation = #relocation>
+        })
+    };
+    parser.parse2(tokens)
+}
+
+/// Turns a template into a function.
+fn finish_template(template: Template) -> TokenStream {
+    let Template { header, builder } = template;
+    let body = serialize(&quote! { ops }, builder);
+    quote! {
+        #[allow(unused_variables)]
+        #header {
+            #body
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compiles_templates_into_functions() {
+        let module = compile_str(".arch x64\n\nadd_const(value: i32):\n    add rax, value\n    ret\n").unwrap();
+        let expected = quote! {
+            pub fn add_const<D>(ops: &mut D, value: i32)
+                where D: ::dynasmrt::DynasmLabelApi<Relocation = ::dynasmrt::x64::X64Relocation>
+        }.to_string();
+        assert!(module.contains(&expected), "{}", module);
+    }
+
+    #[test]
+    fn reports_the_line_of_errors() {
+        let diagnostics = compile_str(".arch x64\nmov rax, rbx\nfoo():\n    mov rax, rbx\n    frobnicate rax\n").unwrap_err();
+        let lines: Vec<usize> = diagnostics.iter().map(|d| d.line).collect();
+        assert_eq!(lines, [2, 5]);
+    }
+
+    #[test]
+    fn reports_registers_of_the_other_mode() {
+        let diagnostics = compile_str(".arch x86\nfoo():\n    mov r8, eax\n").unwrap_err();
+        let messages: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+        assert_eq!(messages, ["3: Unknown register 'r8', only available in long mode"]);
+    }
+}
@@ -1,5 +1,11 @@
 //! This module contains various infrastructure that is common across all assembler backends
 
+use core::cmp;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::vec;
+
 /// Enum representing the result size of a value/expression/register/etc in bytes.
 /// Uses the NASM syntax for sizes (a word is 16 bits)
 #[derive(Debug, PartialOrd, PartialEq, Ord, Eq, Hash, Clone, Copy)]
@@ -24,9 +30,13 @@ pub struct NumericRepr {
 /// An integral value in a particular `Numeric` representation.
 #[derive(Debug, PartialOrd, PartialEq, Ord, Eq, Hash, Clone, Copy)]
 pub struct Number {
-    /// The bit representation of the number.
-    /// TODO: comment on the actual representation chosen.
+    /// The low 64 bits of the bit representation of the number.
+    ///
+    /// The bits beyond the size of the representation are a sign or zero extension of the
+    /// number, up to the full 256 bits of `value` and `upper`.
     value: u64,
+    /// The bits above the low 64 bits, least significant word first.
+    upper: [u64; 3],
     repr: NumericRepr,
 }
 
@@ -75,7 +85,23 @@ impl Number {
     }
 
     pub const fn from_u64_and_repr(value: u64, repr: NumericRepr) -> Self {
-        Number { value, repr }
+        let extension = if repr.signed && (value as i64) < 0 { !0 } else { 0 };
+        Number { value, upper: [extension; 3], repr }
+    }
+
+    /// Cast a 128-bit constant to a specific representation.
+    pub fn from_u128_and_repr(value: u128, repr: NumericRepr) -> Self {
+        let extension = if repr.signed && (value as i128) < 0 { !0 } else { 0 };
+        let words = [value as u64, (value >> 64) as u64, extension, extension];
+        Self::from_words_and_repr(words, repr)
+    }
+
+    /// Cast a constant of up to 256 bits, given as 64-bit words with the least significant
+    /// word first, to a specific representation.
+    pub fn from_words_and_repr(words: [u64; 4], repr: NumericRepr) -> Self {
+        let mut number = Number { value: words[0], upper: [words[1], words[2], words[3]], repr };
+        number.correct_extension_bits_for_sign();
+        number
     }
 
     pub const fn repr(self) -> NumericRepr {
@@ -127,7 +153,28 @@ impl Number {
     }
 
     pub fn as_i64(self) -> i64 {
-        self.cast_as(NumericRepr::signed(Size::DWORD)).value as i64
+        self.cast_as(NumericRepr::signed(Size::QWORD)).value as i64
+    }
+
+    pub fn as_u128(self) -> u128 {
+        let words = self.cast_as(NumericRepr::unsigned(Size::OWORD)).words();
+        u128::from(words[0]) | u128::from(words[1]) << 64
+    }
+
+    pub fn as_i128(self) -> i128 {
+        let words = self.cast_as(NumericRepr::signed(Size::OWORD)).words();
+        (u128::from(words[0]) | u128::from(words[1]) << 64) as i128
+    }
+
+    /// If the value is zero, looking at all bits of its size.
+    pub fn is_zero(self) -> bool {
+        let words = self.words();
+        (0 .. 4).all(|index| words[index] & self.mask(index) == 0)
+    }
+
+    /// The full 256-bit representation, as 64-bit words with the least significant word first.
+    pub fn words(self) -> [u64; 4] {
+        [self.value, self.upper[0], self.upper[1], self.upper[2]]
     }
 
     /// Perform a cast in 2-complement.
@@ -150,10 +197,8 @@ impl Number {
     pub fn convert(self, repr: NumericRepr) -> Option<Number> {
         let cast = self.cast_as(repr);
 
-        let max = self.repr_of_max().min(cast.repr_of_max());
-        let below_min = self.repr_below_min().max(cast.repr_below_min());
-
-        if cast.value <= max && cast.value > below_min {
+        // the same bits only have the same value if both are negative or both are not
+        if cast.words() == self.words() && cast.is_negative() == self.is_negative() {
             Some(cast)
         } else {
             None
@@ -171,17 +216,15 @@ impl Number {
         // Because resizing does not change signedness this yields correct extension bits.
         Number {
             value: self.value,
+            upper: self.upper,
             repr: NumericRepr { size, signed: self.repr.signed },
         }
     }
 
-    pub fn write_le_bytes(self, buf: &mut Vec<u8>) {
-        let bytes = (0..self.repr.size.in_bytes())
-            .scan(self.value, |value, _| {
-                let byte = *value & 0xff;
-                *value >>= 8;
-                Some(byte as u8)
-            });
+    pub fn write_le_bytes<E: Extend<u8>>(self, buf: &mut E) {
+        let words = self.words();
+        let bytes = (0..usize::from(self.byte_len()))
+            .map(|i| (words[i / 8] >> (i % 8 * 8)) as u8);
         buf.extend(bytes)
     }
 
@@ -191,52 +234,43 @@ impl Number {
         out
     }
 
-    /// The value bitmask for the size.
-    fn mask(self) -> u64 {
-        use core::convert::TryInto;
-        #[allow(non_snake_case)]
-        let ALL_BITS: u8 = core::mem::size_of::<u64>().try_into().unwrap();
+    /// The value bitmask for the size, for the 64-bit word at `index`.
+    fn mask(self, index: usize) -> u64 {
+        let len = u32::from(self.byte_len()) * 8;
+        let start = index as u32 * 64;
+        if len >= start + 64 {
+            !0
+        } else if len <= start {
+            0
+        } else {
+            (!0u64) >> (start + 64 - len)
+        }
+    }
 
-        let len: u8 = self.byte_len() * 8;
-        (!0u64) >> ALL_BITS.checked_sub(len).unwrap()
+    /// If the mathematical value, independent of the representation, is negative.
+    fn is_negative(self) -> bool {
+        self.repr.signed && (self.upper[2] as i64) < 0
     }
 
     fn byte_len(self) -> u8 {
         self.repr.size.in_bytes()
     }
 
-    /// The maximum value representation.
-    /// Used to check the value range in unsigned representation of any length.
-    fn repr_of_max(self) -> u64 {
-        self.mask() ^ (if self.repr.signed { self.sign_bit() } else { 0 })
-    }
-
-    /// The representation below minimum value.
-    /// Used to check the value range in unsigned representation of any length.
-    fn repr_below_min(self) -> u64 {
-        if self.repr.signed {
-            ((!0u64) ^ self.repr_of_max()) - 1
-        } else {
-            0
-        }
-    }
-
-    fn sign_bit(self) -> u64 {
-        let right_shift = (self.byte_len() * 8) - 1;
-        1 << right_shift
-    }
-
     fn is_sign_bit_set(self) -> bool {
-        self.value & self.sign_bit() != 0
+        let bit = usize::from(self.byte_len()) * 8 - 1;
+        self.words()[bit / 64] & (1 << (bit % 64)) != 0
     }
 
     /// Fix the sign extension after a cast.
     fn correct_extension_bits_for_sign(&mut self) {
-        if self.repr.signed && self.is_sign_bit_set() {
-            self.value |= !self.mask();
-        } else {
-            self.value &= self.mask();
+        let extension = if self.repr.signed && self.is_sign_bit_set() { !0 } else { 0 };
+        let mut words = self.words();
+        for (index, word) in words.iter_mut().enumerate() {
+            let mask = self.mask(index);
+            *word = (*word & mask) | (extension & !mask);
         }
+        self.value = words[0];
+        self.upper = [words[1], words[2], words[3]];
     }
 }
 
@@ -248,6 +282,9 @@ pub enum Const {
 
     /// Add a simple value.
     Value(Expr),
+
+    /// Add a value that is known at compile time.
+    Number(Number),
 }
 
 /**
@@ -441,6 +478,182 @@ pub fn bitmask64(scale: u8) -> u64 {
     1u64.checked_shl(u32::from(scale)).unwrap_or(0).wrapping_sub(1)
 }
 
+/// Find the candidates that are closest to `name`, for "did you mean" style hints.
+/// Returns at most three candidates, best match first. Candidates that need more than
+/// a single edit for every three characters of `name` are not considered similar.
+pub fn similar_names<'a, I>(name: &str, candidates: I) -> Vec<&'a str>
+    where I: IntoIterator<Item=&'a str>
+{
+    let name = name.to_ascii_lowercase();
+    let max_distance = cmp::max(1, name.len() / 3);
+
+    let mut similar: Vec<_> = candidates.into_iter()
+        .map(|candidate| (edit_distance(&name, &candidate.to_ascii_lowercase()), candidate))
+        .filter(|&(distance, _)| distance <= max_distance)
+        .collect();
+    similar.sort_unstable();
+    similar.dedup_by_key(|&mut (_, candidate)| candidate);
+    similar.into_iter().take(3).map(|(_, candidate)| candidate).collect()
+}
+
+/// The amount of insertions, deletions, substitutions and transpositions of adjacent
+/// characters needed to turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.as_bytes();
+    let b = b.as_bytes();
+
+    // rows of the distance matrix for the last two and the current prefix of a
+    let mut prev2: Vec<usize> = vec![0; b.len() + 1];
+    let mut prev: Vec<usize> = (0 ..= b.len()).collect();
+    let mut row = vec![0; b.len() + 1];
+
+    for i in 1 ..= a.len() {
+        row[0] = i;
+        for j in 1 ..= b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = cmp::min(cmp::min(prev[j] + 1, row[j - 1] + 1), prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = cmp::min(row[j], prev2[j - 2] + 1);
+            }
+        }
+        core::mem::swap(&mut prev2, &mut prev);
+        core::mem::swap(&mut prev, &mut row);
+    }
+
+    prev[b.len()]
+}
+
+/// A table of named entries that is built at compile time. The entries are sorted by name, and
+/// a hash index over them allows looking up a name without any initialization at runtime.
+/// `N` is the amount of entries, and `M` the amount of index slots, see `name_table_slots`.
+pub struct NameTable<T: 'static, const N: usize, const M: usize> {
+    entries: [(&'static str, T); N],
+    slots: [u16; M],
+}
+
+const EMPTY_SLOT: u16 = u16::MAX;
+
+/// The amount of index slots a `NameTable` of `len` entries has.
+pub const fn name_table_slots(len: usize) -> usize {
+    (len * 2).next_power_of_two()
+}
+
+impl<T: Copy, const N: usize, const M: usize> NameTable<T, N, M> {
+    /// Builds the table. Fails to compile if a name occurs more than once.
+    pub const fn new(table: &[(&'static str, T)]) -> NameTable<T, N, M> {
+        assert!(N == table.len() && N != 0 && N < EMPTY_SLOT as usize, "table length mismatch");
+        assert!(M == name_table_slots(N), "table slot count mismatch");
+
+        let mut entries = [table[0]; N];
+        let mut i = 0;
+        while i < N {
+            entries[i] = table[i];
+            i += 1;
+        }
+
+        // heapsort, as const fns can't use the sorting methods of slices
+        let mut start = N / 2;
+        while start > 0 {
+            start -= 1;
+            entries = sift_down(entries, start, N);
+        }
+        let mut end = N;
+        while end > 1 {
+            end -= 1;
+            let top = entries[0];
+            entries[0] = entries[end];
+            entries[end] = top;
+            entries = sift_down(entries, 0, end);
+        }
+
+        let mut i = 1;
+        while i < N {
+            assert!(name_less(entries[i - 1].0, entries[i].0), "duplicate name in table");
+            i += 1;
+        }
+
+        // the index is an open addressing hash table with linear probing
+        let mut slots = [EMPTY_SLOT; M];
+        let mut i = 0;
+        while i < N {
+            let mut slot = name_hash(entries[i].0) as usize & (M - 1);
+            while slots[slot] != EMPTY_SLOT {
+                slot = (slot + 1) & (M - 1);
+            }
+            slots[slot] = i as u16;
+            i += 1;
+        }
+
+        NameTable { entries, slots }
+    }
+
+    /// Looks up an entry by name.
+    pub fn get(&self, name: &str) -> Option<&(&'static str, T)> {
+        let mut slot = name_hash(name) as usize & (M - 1);
+        loop {
+            let entry = self.entries.get(usize::from(self.slots[slot]))?;
+            if entry.0 == name {
+                return Some(entry);
+            }
+            slot = (slot + 1) & (M - 1);
+        }
+    }
+
+    /// All entries, sorted by name.
+    pub fn entries(&self) -> &[(&'static str, T)] {
+        &self.entries
+    }
+}
+
+// the heap is passed by value, as mutable references in const fns need rust 1.83
+const fn sift_down<T: Copy, const N: usize>(mut heap: [(&'static str, T); N], mut root: usize, end: usize)
+    -> [(&'static str, T); N]
+{
+    loop {
+        let mut child = 2 * root + 1;
+        if child >= end {
+            return heap;
+        }
+        if child + 1 < end && name_less(heap[child].0, heap[child + 1].0) {
+            child += 1;
+        }
+        if !name_less(heap[root].0, heap[child].0) {
+            return heap;
+        }
+        let parent = heap[root];
+        heap[root] = heap[child];
+        heap[child] = parent;
+        root = child;
+    }
+}
+
+/// Byte-wise string ordering, which is the same ordering `str` uses.
+const fn name_less(a: &str, b: &str) -> bool {
+    let a = a.as_bytes();
+    let b = b.as_bytes();
+    let mut i = 0;
+    while i < a.len() && i < b.len() {
+        if a[i] != b[i] {
+            return a[i] < b[i];
+        }
+        i += 1;
+    }
+    a.len() < b.len()
+}
+
+/// FNV-1a, which is cheap for the short names these tables hold.
+const fn name_hash(name: &str) -> u32 {
+    let bytes = name.as_bytes();
+    let mut hash: u32 = 0x811c_9dc5;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+        i += 1;
+    }
+    hash
+}
+
 impl From<Option<Expr>> for JumpOffset {
     fn from(val: Option<Expr>) -> JumpOffset {
         match val {
@@ -497,3 +710,71 @@ impl From<&'_ Value> for Stmt {
         Stmt::Const(*val)
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distance_counts_edits() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("mov", "mov"), 0);
+        assert_eq!(edit_distance("", "mov"), 3);
+        assert_eq!(edit_distance("mov", ""), 3);
+        // insertion, deletion and substitution
+        assert_eq!(edit_distance("mov", "movq"), 1);
+        assert_eq!(edit_distance("movq", "mov"), 1);
+        assert_eq!(edit_distance("mov", "mvv"), 1);
+        // a transposition of adjacent characters is a single edit
+        assert_eq!(edit_distance("mvo", "mov"), 1);
+        assert_eq!(edit_distance("rxa", "rax"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn similar_names_ranks_and_limits() {
+        let candidates = ["mov", "movq", "movd", "movs", "add", "Mov"];
+
+        // closest first, no more than three
+        assert_eq!(similar_names("mvo", candidates.iter().cloned()), ["Mov", "mov"]);
+        assert_eq!(similar_names("movx", candidates.iter().cloned()), ["Mov", "mov", "movd"]);
+        // matching ignores case
+        assert_eq!(similar_names("ADD", candidates.iter().cloned()), ["add"]);
+        // too different to be a typo
+        assert!(similar_names("xor", candidates.iter().cloned()).is_empty());
+        assert!(similar_names("m", candidates.iter().cloned()).is_empty());
+    }
+
+    const NAMES: &[(&str, u32)] = &[
+        ("rax", 0), ("eax", 1), ("ax", 2), ("al", 3), ("r8", 4), ("r8d", 5), ("r8w", 6), ("r8b", 7),
+        ("mov", 8), ("movq", 9), ("movd", 10), ("a", 11), ("b", 12), ("ab", 13), ("ba", 14),
+    ];
+
+    static TABLE: NameTable<u32, { NAMES.len() }, { name_table_slots(NAMES.len()) }> = NameTable::new(NAMES);
+
+    #[test]
+    fn name_table_finds_every_name() {
+        for &(name, value) in NAMES {
+            assert_eq!(TABLE.get(name), Some(&(name, value)), "{}", name);
+        }
+        assert!(TABLE.get("").is_none());
+        assert!(TABLE.get("rbx").is_none());
+        assert!(TABLE.get("RAX").is_none());
+
+        let names: Vec<&str> = TABLE.entries().iter().map(|&(name, _)| name).collect();
+        let mut sorted = names.clone();
+        sorted.sort();
+        assert_eq!(names, sorted);
+    }
+
+    #[test]
+    fn is_zero_looks_at_every_word() {
+        assert!(Number::from_u64_and_repr(0, NumericRepr::I64).is_zero());
+        assert!(Number::from_u64_and_repr(0x100, NumericRepr::U8).is_zero());
+        assert!(!Number::from_u64_and_repr(!0, NumericRepr::I8).is_zero());
+        assert!(!Number::from_u128_and_repr(1 << 64, NumericRepr::unsigned(Size::OWORD)).is_zero());
+        assert!(!Number::from_words_and_repr([0, 0, 0, 1], NumericRepr::unsigned(Size::HWORD)).is_zero());
+        assert!(Number::from_words_and_repr([0, 1, 0, 0], NumericRepr::U64).is_zero());
+    }
+}
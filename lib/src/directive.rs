@@ -1,13 +1,18 @@
-use std::collections::hash_map::Entry;
+use crate::collections::hash_map::Entry;
 
-use crate::common::{Const, Expr, NumericRepr, Stmt, Size, Value};
+use crate::common::{Const, Expr, Number, NumericRepr, Stmt, Size, Value};
 use crate::arch;
 use crate::DynasmData;
 
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
 /// Common directives.
 pub enum Directive {
     /// Set the architcture.
     Arch(String),
+    /// Set the syntax of x64 and x86 instructions.
+    Syntax(String),
     /// Activate an architecture feature, or none to remove all.
     Feature(Vec<String>),
     /// Directly add some inline data words.
@@ -22,17 +27,68 @@ pub enum Directive {
     Alias {
         /// The alias to use.
         alias: String,
-        /// The target register which is given an alias.
+        /// The target register which is given an alias, either a name or a dynamic register.
         reg: String,
     },
+    /// Remove an alias.
+    Unalias(String),
+    /// Save the current aliases, so they can be restored by `AliasPop`.
+    AliasPush,
+    /// Restore the aliases saved by the last `AliasPush`.
+    AliasPop,
+    /// Start recording the body of a macro.
+    Macro {
+        name: String,
+        /// The names of the parameters, in order.
+        params: Vec<String>,
+    },
+    /// Finish the macro that is being recorded.
+    EndMacro,
+    /// Define or redefine a named constant.
+    Equ {
+        name: String,
+        value: Number,
+    },
+    /// Start a conditional block, which is assembled if the condition isn't zero.
+    If(Number),
+    /// Start another branch of the current conditional block.
+    ElseIf(Number),
+    /// Start the branch of the current conditional block that is assembled if no other was.
+    Else,
+    /// End the current conditional block.
+    EndIf,
     /// A direct expression to add as bytes to the output.
     Expr(Expr),
 }
 
+/// An assembler macro, defined by the lines between `.macro` and `.endm`.
+#[derive(Debug, Clone)]
+pub struct Macro {
+    /// The names of the parameters, which are replaced by the arguments in every expansion.
+    pub params: Vec<String>,
+    /// The lines of the body, as source text.
+    pub body: Vec<String>,
+}
+
+/// A conditional block that is open, started by `.if`.
+#[derive(Debug, Clone, Copy)]
+pub struct Conditional {
+    /// If the current branch is assembled.
+    pub active: bool,
+    /// If no later branch can be assembled anymore, because a branch was already taken or the
+    /// whole block is within a branch that isn't.
+    pub taken: bool,
+    /// If the block reached its `.else`.
+    pub has_else: bool,
+}
+
 pub enum MalformedDirectiveError {
     /// The architecture that was set was not recognized.
     UnknownArchitecture(String),
 
+    /// The syntax that was set was not recognized.
+    UnknownSyntax(String),
+
     /// The feature at the index was unknown.
     UnknownFeature {
         /// The index, to match to an input span for example.
@@ -46,15 +102,38 @@ pub enum MalformedDirectiveError {
         reused: String,
     },
 
+    /// An alias that was removed doesn't exist.
+    UnknownAlias(String),
+
+    /// A `.alias_pop` without a matching `.alias_push`.
+    UnmatchedAliasPop,
+
+    DuplicateMacro {
+        /// The name of the macro that was already defined.
+        reused: String,
+    },
+
+    /// A macro was defined within the body of another macro.
+    NestedMacro,
+
+    /// A `.endm` without a macro that is being recorded.
+    UnmatchedEndMacro,
+
+    /// A `.elseif`, `.else` or `.endif` without an open conditional block.
+    UnmatchedConditional,
+
+    /// A `.elseif` or `.else` after the `.else` of a conditional block.
+    BranchAfterElse,
+
     /// Not a recognized directive.
     UnknownDirective,
 }
 
-pub(crate) fn evaluate_directive(file_data: &mut DynasmData, stmts: &mut Vec<Stmt>, directive: &Directive)
+/// Applies a directive to the assembler state, adding any data it emits to `stmts`.
+pub fn evaluate_directive(file_data: &mut DynasmData, stmts: &mut Vec<Stmt>, directive: &Directive)
     -> Result<(), MalformedDirectiveError>
 {
     match directive {
-        // TODO: oword, qword, float, double, long double
         Directive::Arch(arch) => {
             // ; .arch ident
             if let Some(a) = arch::from_str(&arch) {
@@ -63,6 +142,10 @@ pub(crate) fn evaluate_directive(file_data: &mut DynasmData, stmts: &mut Vec<Stm
                 return Err(MalformedDirectiveError::UnknownArchitecture(arch.to_string()));
             }
         },
+        Directive::Syntax(syntax) => {
+            // ; .syntax ident
+            file_data.syntax = syntax.parse()?;
+        },
         Directive::Feature(features) => {
             // ;.feature none  cancels all features
             if features.len() == 1 && features[0] == "none" {
@@ -93,6 +176,10 @@ pub(crate) fn evaluate_directive(file_data: &mut DynasmData, stmts: &mut Vec<Stm
         Directive::Alias { alias, reg, } => {
             // ; .alias ident, ident
             match file_data.aliases.entry(alias.clone()) {
+                // aliases from before the last .alias_push can be redefined
+                Entry::Occupied(mut o) if file_data.alias_scopes.last().and_then(|s| s.get(alias)) == Some(o.get()) => {
+                    o.insert(reg.clone());
+                },
                 Entry::Occupied(_) => {
                     return Err(MalformedDirectiveError::DuplicateAlias {
                         reused: alias.clone(),
@@ -103,7 +190,80 @@ pub(crate) fn evaluate_directive(file_data: &mut DynasmData, stmts: &mut Vec<Stm
                 }
             }
         },
-        d => {
+        Directive::Unalias(alias) => {
+            // ; .unalias ident
+            if file_data.aliases.remove(alias).is_none() {
+                return Err(MalformedDirectiveError::UnknownAlias(alias.clone()));
+            }
+        },
+        Directive::AliasPush => {
+            // ; .alias_push
+            file_data.alias_scopes.push(file_data.aliases.clone());
+        },
+        Directive::AliasPop => {
+            // ; .alias_pop
+            match file_data.alias_scopes.pop() {
+                Some(aliases) => file_data.aliases = aliases,
+                None => return Err(MalformedDirectiveError::UnmatchedAliasPop),
+            }
+        },
+        Directive::Macro { name, params } => {
+            // ; .macro ident (ident ("," ident)*)?
+            if file_data.recording.is_some() {
+                return Err(MalformedDirectiveError::NestedMacro);
+            }
+            if file_data.macros.contains_key(name) {
+                return Err(MalformedDirectiveError::DuplicateMacro { reused: name.clone() });
+            }
+            file_data.recording = Some((name.clone(), Macro { params: params.clone(), body: Vec::new() }));
+        },
+        Directive::EndMacro => {
+            // ; .endm
+            match file_data.recording.take() {
+                Some((name, body)) => {
+                    file_data.macros.insert(name, body);
+                },
+                None => return Err(MalformedDirectiveError::UnmatchedEndMacro),
+            }
+        },
+        Directive::Equ { name, value } => {
+            // ; .equ ident, expr
+            file_data.constants.insert(name.clone(), *value);
+        },
+        Directive::If(condition) => {
+            // ; .if expr
+            let assembling = file_data.is_assembling();
+            let condition = !condition.is_zero();
+            file_data.conditionals.push(Conditional {
+                active: assembling && condition,
+                taken: !assembling || condition,
+                has_else: false,
+            });
+        },
+        Directive::ElseIf(condition) => {
+            // ; .elseif expr
+            let block = file_data.conditionals.last_mut().ok_or(MalformedDirectiveError::UnmatchedConditional)?;
+            if block.has_else {
+                return Err(MalformedDirectiveError::BranchAfterElse);
+            }
+            block.active = !block.taken && !condition.is_zero();
+            block.taken |= block.active;
+        },
+        Directive::Else => {
+            // ; .else
+            let block = file_data.conditionals.last_mut().ok_or(MalformedDirectiveError::UnmatchedConditional)?;
+            if block.has_else {
+                return Err(MalformedDirectiveError::BranchAfterElse);
+            }
+            block.active = !block.taken;
+            block.taken = true;
+            block.has_else = true;
+        },
+        Directive::EndIf => {
+            // ; .endif
+            file_data.conditionals.pop().ok_or(MalformedDirectiveError::UnmatchedConditional)?;
+        },
+        _ => {
             // unknown directive. skip ahead until we hit a ; so the parser can recover
             return Err(MalformedDirectiveError::UnknownDirective);
         }
@@ -122,6 +282,38 @@ fn directive_const(file_data: &mut DynasmData, stmts: &mut Vec<Stmt>, values: &[
                 expr.repr = NumericRepr::signed(size);
                 stmts.push(Stmt::Const(expr.into()));
             },
+            Const::Number(number) => {
+                stmts.push(Stmt::Const(Value::Number(number.cast_as(NumericRepr::signed(size)))));
+            },
         }
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn taken(conditions: &[Number]) -> Vec<bool> {
+        let mut data = DynasmData::new();
+        let mut stmts = Vec::new();
+        let mut taken = Vec::new();
+        for (i, &condition) in conditions.iter().enumerate() {
+            let directive = if i == 0 { Directive::If(condition) } else { Directive::ElseIf(condition) };
+            assert!(evaluate_directive(&mut data, &mut stmts, &directive).is_ok());
+            taken.push(data.is_assembling());
+        }
+        taken
+    }
+
+    #[test]
+    fn conditions_use_every_bit() {
+        let zero = Number::from_u64_and_repr(0, NumericRepr::I64);
+        let wide = Number::from_u128_and_repr(1 << 64, NumericRepr::unsigned(Size::OWORD));
+        let top = Number::from_words_and_repr([0, 0, 0, 1 << 63], NumericRepr::unsigned(Size::HWORD));
+
+        assert_eq!(taken(&[wide]), [true]);
+        assert_eq!(taken(&[zero, top]), [false, true]);
+        assert_eq!(taken(&[zero, zero]), [false, false]);
+    }
+}
@@ -0,0 +1,402 @@
+//! Parsing of aarch64 instructions.
+use syn::ext::IdentExt;
+use syn::parse::{self, ParseStream};
+use syn::Token;
+use quote::quote;
+
+use crate::arch::aarch64::ast::{Instruction, Modifier, ModifyExpr, RawArg, RefItem, RegKind, RegScalar, RegVector, Register};
+use crate::arch::aarch64::parser::{AARCH64_FAMILIES_MAP, AARCH64_REGISTER_MAP};
+use crate::arch::aarch64::{ArchAarch64, AssembleAarch64, InstructionAarch64};
+use crate::{Ident, Number, NumericRepr, Size, Value};
+
+use super::{parse_jump, Spans};
+use crate::frontend::builder::Builder;
+use crate::frontend::parse_helpers::{as_float, as_ident, as_number, as_signed_number, parse_ident_or_rust_keyword};
+
+use crate::collections::HashMap;
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+struct Parser<'a> {
+    builder: &'a mut Builder,
+    aliases: &'a HashMap<String, String>,
+}
+
+/// Parses and assembles an instruction.
+///
+/// ```text
+/// mnemonic ("." ident)? (arg ("," arg)*)?
+/// ```
+pub(super) fn compile_instruction(arch: &ArchAarch64, builder: &mut Builder, input: ParseStream, aliases: &HashMap<String, String>)
+    -> parse::Result<()>
+{
+    let mut spans = Spans {
+        instruction: input.span(),
+        parts: Vec::new(),
+        args: Vec::new(),
+    };
+
+    let mnemonic = parse_ident_or_rust_keyword(input)?;
+    spans.parts.push(mnemonic.span());
+
+    let mut parser = Parser { builder, aliases };
+    let mut args = Vec::new();
+
+    // a condition suffix like `b.eq` is passed as the first arguments
+    if input.peek(Token![.]) {
+        spans.args.push(input.span());
+        let _: Token![.] = input.parse()?;
+        args.push(RawArg::Dot);
+
+        let ident = parse_ident_or_rust_keyword(input)?;
+        spans.args.push(ident.span());
+        args.push(RawArg::Lit { ident: Ident { name: ident.to_string() } });
+    }
+
+    // bare identifiers can either be literal keywords or rust variables
+    let mut ambiguous = Vec::new();
+    while !input.is_empty() {
+        spans.args.push(input.span());
+        let arg = parser.parse_arg(input)?;
+        if let RawArg::Lit { ref ident } = arg {
+            ambiguous.push((args.len(), ident.name.clone()));
+        }
+        args.push(arg);
+
+        if input.is_empty() {
+            break;
+        }
+        let _: Token![,] = input.parse()?;
+    }
+
+    let inst = Instruction { ident: Ident { name: mnemonic.to_string() } };
+    let builder = parser.builder;
+    let checkpoint = builder.checkpoint();
+
+    let result = AssembleAarch64::compile_instruction(builder, arch, InstructionAarch64 { inst, args: args.clone() });
+    let emitted = builder.take_errors(checkpoint);
+    if result.is_ok() && emitted.is_empty() {
+        return Ok(());
+    }
+    builder.reset(checkpoint);
+
+    // try again, with any bare identifiers as immediates
+    if !ambiguous.is_empty() {
+        for (idx, name) in &ambiguous {
+            let ident = syn::Ident::new(name, spans.args[*idx]);
+            let expr = builder.expr(quote! { #ident }, NumericRepr::I64);
+            args[*idx] = RawArg::Immediate { prefixed: false, value: Value::Expr(expr) };
+        }
+
+        let inst = Instruction { ident: Ident { name: mnemonic.to_string() } };
+        let retry = AssembleAarch64::compile_instruction(builder, arch, InstructionAarch64 { inst, args });
+        let retry_emitted = builder.take_errors(checkpoint);
+        if retry.is_ok() && retry_emitted.is_empty() {
+            return Ok(());
+        }
+        builder.reset(checkpoint);
+    }
+
+    Err(spans.error(emitted, result.err()))
+}
+
+impl Parser<'_> {
+    /// Parses an argument.
+    ///
+    /// ```text
+    /// jump
+    /// "{" register_list "}" ("[" expr "]")?
+    /// "[" (ref_item ("," ref_item)*)? "]" "!"?
+    /// "#" expr
+    /// modifier expr?
+    /// register
+    /// ident
+    /// expr
+    /// ```
+    fn parse_arg(&mut self, input: ParseStream) -> parse::Result<RawArg> {
+        if let Some(jump) = parse_jump(self.builder, input)? {
+            return Ok(RawArg::JumpTarget { jump });
+        }
+
+        if input.peek(syn::token::Brace) {
+            let content;
+            syn::braced!(content in input);
+            let mut list = self.parse_register_list(&content)?;
+
+            if input.peek(syn::token::Bracket) {
+                let element = Some(self.parse_element(input)?);
+                match list {
+                    RawArg::DashList { element: ref mut e, .. }
+                    | RawArg::CommaList { element: ref mut e, .. }
+                    | RawArg::AmountList { element: ref mut e, .. } => *e = element,
+                    _ => unreachable!(),
+                }
+            }
+            return Ok(list);
+        }
+
+        if input.peek(syn::token::Bracket) {
+            let content;
+            syn::bracketed!(content in input);
+
+            let mut items = Vec::new();
+            while !content.is_empty() {
+                items.push(self.parse_ref_item(&content)?);
+                if content.is_empty() {
+                    break;
+                }
+                let _: Token![,] = content.parse()?;
+            }
+
+            let bang = if input.peek(Token![!]) {
+                let _: Token![!] = input.parse()?;
+                true
+            } else {
+                false
+            };
+            return Ok(RawArg::Reference { items, bang });
+        }
+
+        if input.peek(Token![#]) {
+            let _: Token![#] = input.parse()?;
+            let expr: syn::Expr = input.parse()?;
+            return Ok(self.immediate(&expr, true));
+        }
+
+        if let Some(modifier) = self.parse_modifier(input)? {
+            return Ok(RawArg::Modifier { modifier });
+        }
+
+        if let Some(reg) = self.parse_register(input)? {
+            return Ok(RawArg::Direct { reg });
+        }
+
+        let expr: syn::Expr = input.parse()?;
+        if let Some(ident) = as_ident(&expr) {
+            return Ok(RawArg::Lit { ident: Ident { name: ident.to_string() } });
+        }
+        Ok(self.immediate(&expr, false))
+    }
+
+    /// Parses the contents of a register list.
+    ///
+    /// ```text
+    /// register "*" expr
+    /// register "-" register
+    /// register ("," register)*
+    /// ```
+    fn parse_register_list(&mut self, input: ParseStream) -> parse::Result<RawArg> {
+        let first = self.expect_register(input)?;
+
+        if input.peek(Token![*]) {
+            let _: Token![*] = input.parse()?;
+            let amount: syn::Expr = input.parse()?;
+            let amount = self.value(&amount);
+            return Ok(RawArg::AmountList { first, amount, element: None });
+        }
+
+        if input.peek(Token![-]) {
+            let _: Token![-] = input.parse()?;
+            let last = self.expect_register(input)?;
+            return Ok(RawArg::DashList { first, last, element: None });
+        }
+
+        let mut items = vec![first];
+        while !input.is_empty() {
+            let _: Token![,] = input.parse()?;
+            items.push(self.expect_register(input)?);
+        }
+        Ok(RawArg::CommaList { items, element: None })
+    }
+
+    /// Parses an item of a memory reference.
+    ///
+    /// ```text
+    /// register
+    /// modifier ("#"? expr)?
+    /// "#"? expr
+    /// ```
+    fn parse_ref_item(&mut self, input: ParseStream) -> parse::Result<RefItem> {
+        if let Some(modifier) = self.parse_modifier(input)? {
+            return Ok(RefItem::Modifier { modifier });
+        }
+
+        if let Some(reg) = self.parse_register(input)? {
+            return Ok(RefItem::Direct { reg });
+        }
+
+        if input.peek(Token![#]) {
+            let _: Token![#] = input.parse()?;
+        }
+        let expr: syn::Expr = input.parse()?;
+        Ok(RefItem::Immediate { value: self.value(&expr) })
+    }
+
+    /// Parses a modifier, if there is one, with its amount if it was given.
+    ///
+    /// ```text
+    /// modifier ("#"? expr)?
+    /// ```
+    fn parse_modifier(&mut self, input: ParseStream) -> parse::Result<Option<ModifyExpr>> {
+        let fork = input.fork();
+        let op = match fork.call(syn::Ident::parse_any).ok().and_then(|name| Modifier::from_name(&name.to_string())) {
+            Some(op) => op,
+            None => return Ok(None),
+        };
+        // a modifier name can still be a variable that is used as an immediate
+        let is_modifier = op.expr_required()
+            || fork.is_empty()
+            || fork.peek(Token![,])
+            || fork.peek(Token![#])
+            || fork.peek(syn::Lit);
+        if !is_modifier {
+            return Ok(None);
+        }
+        let _ = input.call(syn::Ident::parse_any)?;
+
+        let expr = if input.is_empty() || input.peek(Token![,]) {
+            None
+        } else {
+            if input.peek(Token![#]) {
+                let _: Token![#] = input.parse()?;
+            }
+            let expr: syn::Expr = input.parse()?;
+            Some(self.value(&expr))
+        };
+
+        Ok(Some(ModifyExpr::new(op, expr)))
+    }
+
+    fn expect_register(&mut self, input: ParseStream) -> parse::Result<Register> {
+        match self.parse_register(input)? {
+            Some(reg) => Ok(reg),
+            None => Err(input.error("Expected a register")),
+        }
+    }
+
+    /// Parses a register, if there is one.
+    ///
+    /// ```text
+    /// name
+    /// family "(" expr ")"
+    /// vector_name "." element_size lanes? ("[" expr "]")?
+    /// vector_family "(" expr ")" "." element_size lanes? ("[" expr "]")?
+    /// ```
+    fn parse_register(&mut self, input: ParseStream) -> parse::Result<Option<Register>> {
+        let fork = input.fork();
+        let ident = match fork.call(syn::Ident::parse_any) {
+            Ok(ident) => ident,
+            Err(_) => return Ok(None),
+        };
+        let name = ident.to_string();
+
+        // registers can't be shadowed by aliases
+        let (kind, size) = if let Some(&(id, size)) = AARCH64_REGISTER_MAP.get(&*name) {
+            let _ = input.call(syn::Ident::parse_any)?;
+            (RegKind::Static(id), size)
+        } else if let Some(&(family, size)) = AARCH64_FAMILIES_MAP.get(&*name) {
+            if !fork.peek(syn::token::Paren) {
+                return Ok(None);
+            }
+            let _ = input.call(syn::Ident::parse_any)?;
+            let content;
+            syn::parenthesized!(content in input);
+            let id: syn::Expr = content.parse()?;
+            (RegKind::Dynamic(family, self.builder.expr(quote! { #id }, NumericRepr::U8)), size)
+        } else if self.aliases.contains_key(&name) {
+            let _ = input.call(syn::Ident::parse_any)?;
+            self.alias_register(&ident)?
+        } else {
+            return Ok(None);
+        };
+
+        if let Some(size) = size {
+            return Ok(Some(Register::Scalar(RegScalar { kind, size })));
+        }
+
+        // vector registers have to specify their layout
+        let _: Token![.] = input.parse()?;
+        let layout = parse_ident_or_rust_keyword(input)?;
+        let layout_name = layout.to_string().to_ascii_uppercase();
+        let (element_size, lanes) = layout_name.split_at(1);
+        let element_size = match element_size {
+            "B" => Size::BYTE,
+            "H" => Size::WORD,
+            "S" => Size::DWORD,
+            "D" => Size::QWORD,
+            "Q" => Size::OWORD,
+            _ => return Err(syn::Error::new(layout.span(), "Invalid vector element size")),
+        };
+        let lanes = if lanes.is_empty() {
+            None
+        } else {
+            match lanes.parse() {
+                Ok(lanes) => Some(lanes),
+                Err(_) => return Err(syn::Error::new(layout.span(), "Invalid vector lane count")),
+            }
+        };
+
+        let element = if input.peek(syn::token::Bracket) {
+            Some(self.parse_element(input)?)
+        } else {
+            None
+        };
+
+        Ok(Some(Register::Vector(RegVector { kind, element_size, lanes, element })))
+    }
+
+    /// The register an alias stands for, which is either the name of a register or a dynamic
+    /// register. Vector registers still take their layout from where the alias is used.
+    fn alias_register(&mut self, alias: &syn::Ident) -> parse::Result<(RegKind, Option<Size>)> {
+        let reg = &self.aliases[&alias.to_string()];
+        let target: syn::Expr = syn::parse_str(reg)?;
+        let kind = match target {
+            syn::Expr::Call(ref call) if call.args.len() == 1 => as_ident(&call.func)
+                .and_then(|family| AARCH64_FAMILIES_MAP.get(&*family.to_string()))
+                .map(|&(family, size)| {
+                    let id = &call.args[0];
+                    (RegKind::Dynamic(family, self.builder.expr(quote! { #id }, NumericRepr::U8)), size)
+                }),
+            _ => as_ident(&target)
+                .and_then(|name| AARCH64_REGISTER_MAP.get(&*name.to_string()))
+                .map(|&(id, size)| (RegKind::Static(id), size)),
+        };
+        kind.ok_or_else(|| syn::Error::new(alias.span(), format!(
+            "The alias '{}' stands for '{}', which isn't a register", alias, reg)))
+    }
+
+    /// Parses a vector element index.
+    fn parse_element(&mut self, input: ParseStream) -> parse::Result<Value> {
+        let content;
+        syn::bracketed!(content in input);
+        let expr: syn::Expr = content.parse()?;
+        Ok(self.value(&expr))
+    }
+
+    fn immediate(&mut self, expr: &syn::Expr, prefixed: bool) -> RawArg {
+        match as_float(expr) {
+            Some(value) => RawArg::FloatImmediate { prefixed, value },
+            None => RawArg::Immediate { prefixed, value: self.value(expr) },
+        }
+    }
+
+    /// A literal as a number, or else the expression as a runtime value.
+    fn value(&mut self, expr: &syn::Expr) -> Value {
+        match as_signed_number(expr) {
+            Some(value) if value < 0 => Value::Number(Number::from_u64_and_repr(value as u64, NumericRepr::I64)),
+            Some(value) => Value::Number(Number::from_u64_and_repr(value as u64, NumericRepr::U64)),
+            None => match as_number(expr) {
+                Some(value) => Value::Number(Number::from_u64_and_repr(value, NumericRepr::U64)),
+                None => Value::Expr(self.builder.expr(quote! { #expr }, NumericRepr::I64)),
+            },
+        }
+    }
+}
+
+/// If the name is a register.
+pub(super) fn is_register(name: &str) -> bool {
+    AARCH64_REGISTER_MAP.contains_key(name)
+}
@@ -0,0 +1,184 @@
+//! The instruction parsers of every architecture, and what they share.
+use proc_macro2::Span;
+use syn::parse::{self, ParseStream};
+use syn::Token;
+use quote::quote;
+
+use crate::arch::aarch64::ArchAarch64;
+use crate::arch::x64::{Archx64, Archx86, X86Mode};
+use crate::arch::ErrorSpan;
+use crate::common::{Jump, JumpKind};
+use crate::{DynasmData, Expr, Ident, NumericRepr, Syntax, Value};
+
+use crate::frontend::builder::Builder;
+use crate::frontend::parse_helpers::parse_ident_or_rust_keyword;
+
+use core::any::Any;
+use core::fmt::Display;
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+mod aarch64;
+mod x64;
+
+/// Parses and assembles an instruction for the current architecture.
+pub(crate) fn compile_instruction(data: &mut DynasmData, builder: &mut Builder, input: ParseStream) -> parse::Result<()> {
+    let arch: &dyn Any = &*data.current_arch;
+
+    if let Some(arch) = arch.downcast_ref::<Archx64>() {
+        x64::compile_instruction(x64::Target::X64(arch), builder, input, &data.aliases, data.syntax)
+    } else if let Some(arch) = arch.downcast_ref::<Archx86>() {
+        x64::compile_instruction(x64::Target::X86(arch), builder, input, &data.aliases, data.syntax)
+    } else if data.syntax != Syntax::Intel {
+        Err(input.error(format!("The '{}' architecture can only be written in the default syntax", data.current_arch.name())))
+    } else if let Some(arch) = arch.downcast_ref::<ArchAarch64>() {
+        aarch64::compile_instruction(arch, builder, input, &data.aliases)
+    } else {
+        Err(input.error(format!("The '{}' architecture is not supported by this front end", data.current_arch.name())))
+    }
+}
+
+/// Parses the declaration of an operand that placeholders can be bound to.
+pub(crate) fn compile_operand(data: &mut DynasmData, builder: &mut Builder, input: ParseStream) -> parse::Result<()> {
+    let arch: &dyn Any = &*data.current_arch;
+
+    if let Some(arch) = arch.downcast_ref::<Archx64>() {
+        x64::compile_operand(x64::Target::X64(arch), builder, input, &data.aliases)
+    } else if let Some(arch) = arch.downcast_ref::<Archx86>() {
+        x64::compile_operand(x64::Target::X86(arch), builder, input, &data.aliases)
+    } else {
+        Err(input.error(format!("Placeholders are not supported for the '{}' architecture", data.current_arch.name())))
+    }
+}
+
+/// If the name is a register of the current architecture, which can't be used as an alias as
+/// registers are looked up before aliases.
+pub(crate) fn is_register(data: &DynasmData, name: &str) -> bool {
+    let arch: &dyn Any = &*data.current_arch;
+
+    if arch.is::<Archx64>() {
+        x64::is_register(name, X86Mode::Long)
+    } else if arch.is::<Archx86>() {
+        x64::is_register(name, X86Mode::Protected)
+    } else if arch.is::<ArchAarch64>() {
+        aarch64::is_register(name)
+    } else {
+        false
+    }
+}
+
+/// The spans of the parts of an instruction, to locate errors of the assembler.
+pub(crate) struct Spans {
+    /// The span of the whole instruction.
+    pub instruction: Span,
+    pub parts: Vec<Span>,
+    pub args: Vec<Span>,
+}
+
+impl Spans {
+    fn locate(&self, span: ErrorSpan) -> Span {
+        match span {
+            ErrorSpan::InstructionPart { idx } => self.parts.get(idx),
+            ErrorSpan::Argument { idx } => self.args.get(idx),
+        }.cloned().unwrap_or(self.instruction)
+    }
+
+    /// Turns the errors emitted by the assembler, and the error it returned, into a single error.
+    pub fn error<E: Display>(&self, emitted: Vec<(ErrorSpan, String)>, returned: Option<E>) -> syn::Error {
+        let mut errors = emitted.into_iter().map(|(span, message)| syn::Error::new(self.locate(span), message));
+        let mut error = match errors.next() {
+            Some(error) => error,
+            None => {
+                let message = returned.map_or_else(|| "Failed to assemble the instruction".to_string(), |e| e.to_string());
+                return syn::Error::new(self.instruction, message);
+            }
+        };
+        for e in errors {
+            error.combine(e);
+        }
+        error
+    }
+}
+
+/// Parses a jump target, if there is one.
+///
+/// ```text
+/// -> label (("+" | "-") expr)?
+///  > label (("+" | "-") expr)?
+///  < label (("+" | "-") expr)?
+/// int ("b" | "f") (("+" | "-") expr)?
+/// => expr
+/// extern expr
+/// ```
+pub(crate) fn parse_jump(builder: &mut Builder, input: ParseStream) -> parse::Result<Option<Jump>> {
+    let kind = if input.peek(Token![->]) {
+        let _: Token![->] = input.parse()?;
+        JumpKind::Global(parse_label(input)?)
+    } else if input.peek(Token![>]) {
+        let _: Token![>] = input.parse()?;
+        JumpKind::Forward(parse_label(input)?)
+    } else if input.peek(Token![<]) {
+        let _: Token![<] = input.parse()?;
+        JumpKind::Backward(parse_label(input)?)
+    } else if input.peek(Token![=>]) {
+        let _: Token![=>] = input.parse()?;
+        let expr: syn::Expr = input.parse()?;
+        let expr = builder.expr(quote! { #expr }, NumericRepr::U32);
+        return Ok(Some(Jump::new(JumpKind::Dynamic(expr), None)));
+    } else if input.peek(Token![extern]) {
+        let _: Token![extern] = input.parse()?;
+        let expr: syn::Expr = input.parse()?;
+        let expr = builder.expr(quote! { #expr }, NumericRepr::U64);
+        return Ok(Some(Jump::new(JumpKind::Bare(Value::Expr(expr)), None)));
+    } else if let Some(kind) = parse_numeric_label(input)? {
+        kind
+    } else {
+        return Ok(None);
+    };
+
+    let offset = parse_offset(builder, input)?;
+    Ok(Some(Jump::new(kind, offset)))
+}
+
+fn parse_label(input: ParseStream) -> parse::Result<Ident> {
+    let name = parse_ident_or_rust_keyword(input)?;
+    Ok(Ident { name: name.to_string() })
+}
+
+/// Parses a reference to a numeric local label, `1b` or `1f`, if there is one. These are the
+/// local label with the digits as its name, so they resolve just like `<label` and `>label`.
+fn parse_numeric_label(input: ParseStream) -> parse::Result<Option<JumpKind>> {
+    if !input.peek(syn::LitInt) {
+        return Ok(None);
+    }
+
+    let label: syn::LitInt = input.fork().parse()?;
+    let backward = match label.suffix() {
+        "b" => true,
+        "f" => false,
+        _ => return Ok(None),
+    };
+    let _: syn::LitInt = input.parse()?;
+
+    let name = Ident { name: label.base10_digits().to_string() };
+    Ok(Some(if backward { JumpKind::Backward(name) } else { JumpKind::Forward(name) }))
+}
+
+/// Parses the offset of a jump to a label.
+fn parse_offset(builder: &mut Builder, input: ParseStream) -> parse::Result<Option<Expr>> {
+    let negative = if input.peek(Token![+]) {
+        let _: Token![+] = input.parse()?;
+        false
+    } else if input.peek(Token![-]) {
+        let _: Token![-] = input.parse()?;
+        true
+    } else {
+        return Ok(None);
+    };
+
+    let expr: syn::Expr = input.parse()?;
+    let tokens = if negative { quote! { -(#expr) } } else { quote! { #expr } };
+    Ok(Some(builder.expr(tokens, NumericRepr::I64)))
+}
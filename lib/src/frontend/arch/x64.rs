@@ -0,0 +1,1009 @@
+//! Parsing of x64 and x86 instructions.
+use proc_macro2::{Delimiter, Span, TokenStream, TokenTree};
+use syn::ext::IdentExt;
+use syn::parse::{self, ParseStream};
+use syn::spanned::Spanned;
+use syn::Token;
+use quote::quote;
+
+use crate::arch::x64::ast::{CleanArg, Instruction, RegFamily, RegKind, Register};
+use crate::arch::x64::ast::RegId;
+use crate::arch::x64::introspect::forms;
+use crate::arch::x64::parser::{lookup_register, split_att_mnemonic, PREFIXES, X64_FAMILIES_MAP, X64_SIZES, X86_FAMILIES_MAP, X86_SIZES};
+use crate::arch::x64::{Archx64, Archx86, AssembleX64, AssembleX86, Error, InstructionX64, InstructionX86, Note, Operand, RegClass, X86Mode};
+use crate::{Expr, Ident, Number, NumericRepr, Size, Syntax, Value};
+
+use super::{parse_jump, Spans};
+use crate::frontend::builder::{Builder, Placeholder};
+use crate::frontend::parse_helpers::{as_ident, as_number, as_signed_number, eat_pseudo_keyword, parse_ident_or_rust_keyword};
+
+use crate::collections::HashMap;
+use core::convert::TryFrom;
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// The architecture to assemble for.
+pub(super) enum Target<'a> {
+    X64(&'a Archx64),
+    X86(&'a Archx86),
+}
+
+impl Target<'_> {
+    fn mode(&self) -> X86Mode {
+        match self {
+            Target::X64(_) => X86Mode::Long,
+            Target::X86(_) => X86Mode::Protected,
+        }
+    }
+
+    fn assemble(&self, builder: &mut Builder, inst: Instruction, args: Vec<CleanArg>) -> Result<(), Error> {
+        match self {
+            Target::X64(arch) => AssembleX64::compile_instruction(builder, arch, InstructionX64 { inst, args }),
+            Target::X86(arch) => AssembleX86::compile_instruction(builder, arch, InstructionX86 { inst, args }),
+        }
+    }
+}
+
+/// An immediate whose size wasn't given, so it's sized to fit the instruction.
+enum Unsized {
+    /// A literal, which is sized by its value. Positive values can also be sized as if they
+    /// were unsigned.
+    Literal {
+        signed: Number,
+        unsigned: Number,
+    },
+    /// A value only known at runtime.
+    Dynamic(Expr),
+}
+
+struct Parser<'a> {
+    builder: &'a mut Builder,
+    aliases: &'a HashMap<String, String>,
+    mode: X86Mode,
+}
+
+/// Parses and assembles an instruction, written in the given syntax.
+///
+/// ```text
+/// prefix* mnemonic (arg ("," arg)*)?
+/// ```
+pub(super) fn compile_instruction(target: Target, builder: &mut Builder, input: ParseStream, aliases: &HashMap<String, String>, syntax: Syntax)
+    -> parse::Result<()>
+{
+    let mut spans = Spans {
+        instruction: input.span(),
+        parts: Vec::new(),
+        args: Vec::new(),
+    };
+
+    // any prefixes, and the mnemonic
+    let mut idents = Vec::new();
+    loop {
+        let ident = parse_ident_or_rust_keyword(input)?;
+        let name = ident.to_string();
+        spans.parts.push(ident.span());
+
+        let is_prefix = PREFIXES.contains(&name.as_str());
+        idents.push(Ident { name });
+        if !is_prefix || !input.peek(syn::Ident::peek_any) {
+            break;
+        }
+    }
+
+    let mut parser = Parser {
+        builder,
+        aliases,
+        mode: target.mode(),
+    };
+
+    let mut args = Vec::new();
+    let mut unsized_immediates = Vec::new();
+    while !input.is_empty() {
+        spans.args.push(input.span());
+        let (arg, unsized_immediate) = match syntax {
+            Syntax::Intel => parser.parse_arg(input)?,
+            Syntax::Att => {
+                let (segment, arg, unsized_immediate) = parser.parse_att_arg(input)?;
+                // segment overrides are prefixes of the instruction
+                if let Some(segment) = segment {
+                    spans.parts.insert(idents.len() - 1, segment.span());
+                    idents.insert(idents.len() - 1, Ident { name: segment.to_string() });
+                }
+                (arg, unsized_immediate)
+            },
+        };
+        if let Some(immediate) = unsized_immediate {
+            unsized_immediates.push((args.len(), immediate));
+        }
+        args.push(arg);
+
+        if input.is_empty() {
+            break;
+        }
+        let _: Token![,] = input.parse()?;
+    }
+
+    let inst = Instruction { idents };
+    let variants = match syntax {
+        Syntax::Intel => vec![(inst, args)],
+        Syntax::Att => {
+            // the operands are reversed, except for pairs of immediates like those of `enter`
+            let immediates = args.iter().filter(|arg| matches!(arg, CleanArg::Immediate { .. })).count();
+            if !(args.len() == 2 && immediates == 2) {
+                let len = args.len();
+                args.reverse();
+                spans.args.reverse();
+                for (idx, _) in &mut unsized_immediates {
+                    *idx = len - 1 - *idx;
+                }
+            }
+            att_variants(inst, args)
+        },
+    };
+
+    // Try the possible sizes of any unsized immediates, from the most to the least preferable.
+    // The literals are sized by their signed value, and dynamic values as dwords at first.
+    let mut attempts = vec![(false, Size::DWORD)];
+    for (_, immediate) in &unsized_immediates {
+        match immediate {
+            Unsized::Literal { signed, unsigned } if signed != unsigned => {
+                if !attempts.contains(&(true, Size::DWORD)) {
+                    attempts.push((true, Size::DWORD));
+                }
+            },
+            Unsized::Literal { .. } => (),
+            Unsized::Dynamic(_) => {
+                if !attempts.contains(&(false, Size::BYTE)) {
+                    attempts.push((false, Size::WORD));
+                    attempts.push((false, Size::BYTE));
+                }
+            },
+        }
+    }
+
+    let builder = parser.builder;
+    let checkpoint = builder.checkpoint();
+    let mut first_failure = None;
+    for (inst, args) in &variants {
+        for &(unsigned, dynamic_size) in &attempts {
+            let mut args = args.clone();
+            for (idx, immediate) in &unsized_immediates {
+                let value = match *immediate {
+                    Unsized::Literal { unsigned: number, .. } if unsigned => Value::Number(number),
+                    Unsized::Literal { signed, .. } => Value::Number(signed),
+                    Unsized::Dynamic(expr) => Value::Expr(Expr { idx: expr.idx, repr: NumericRepr::signed(dynamic_size) }),
+                };
+                args[*idx] = CleanArg::Immediate { value };
+            }
+
+            let result = target.assemble(builder, inst.clone(), args);
+            let emitted = builder.take_errors(checkpoint);
+            if result.is_ok() && emitted.is_empty() {
+                return Ok(());
+            }
+
+            builder.reset(checkpoint);
+            if first_failure.is_none() {
+                first_failure = Some((emitted, result.err()));
+            }
+        }
+    }
+
+    let (emitted, returned) = first_failure.unwrap();
+    Err(spans.error(emitted, returned))
+}
+
+/// Parses the declaration of an operand, which placeholders can refer to by its position or
+/// name. Registers are declared with a register class, optionally wrapped in the direction of
+/// an `asm!` operand, and the number of the register that is chosen at runtime.
+///
+/// ```text
+/// (ident "=")? ("in" | "out" | "inout" | "lateout" | "inlateout") "(" class ")" expr
+/// (ident "=")? class expr
+/// (ident "=")? "const" expr
+/// (ident "=")? "mem" size? "[" memory "]"
+/// ```
+pub(super) fn compile_operand(target: Target, builder: &mut Builder, input: ParseStream, aliases: &HashMap<String, String>)
+    -> parse::Result<()>
+{
+    let name = if input.peek(syn::Ident::peek_any) && input.peek2(Token![=]) {
+        let name = parse_ident_or_rust_keyword(input)?;
+        let _: Token![=] = input.parse()?;
+        if builder.placeholder(&name.to_string()).is_some() {
+            return Err(syn::Error::new(name.span(), format!("Duplicate operand '{}'", name)));
+        }
+        Some(name.to_string())
+    } else {
+        None
+    };
+
+    let kind = parse_ident_or_rust_keyword(input)?;
+    let (operand, expr) = match kind.to_string().as_str() {
+        "const" => {
+            let expr: syn::Expr = input.parse()?;
+            let value = Value::Expr(builder.expr(quote! { #expr }, NumericRepr::I32));
+            (Operand::Immediate(value), Some(expr))
+        },
+        "mem" => {
+            let span = input.span();
+            let mut parser = Parser { builder, aliases, mode: target.mode() };
+            match parser.parse_arg(input)? {
+                (arg @ CleanArg::Indirect { .. }, _) | (arg @ CleanArg::IndirectJumpTarget { .. }, _) => (Operand::Memory(arg), None),
+                _ => return Err(syn::Error::new(span, "Expected a memory reference")),
+            }
+        },
+        direction => {
+            let class = if ["in", "out", "inout", "lateout", "inlateout"].contains(&direction) {
+                let content;
+                syn::parenthesized!(content in input);
+                parse_ident_or_rust_keyword(&content)?
+            } else {
+                kind
+            };
+            let class = class.to_string().parse::<RegClass>()
+                .map_err(|e| syn::Error::new(class.span(), e.to_string()))?;
+
+            let expr: syn::Expr = input.parse()?;
+            let number = builder.expr(quote! { #expr }, NumericRepr::U8);
+            (Operand::Register { class, number }, None)
+        },
+    };
+
+    builder.placeholders.push(Placeholder { name, operand, expr });
+    Ok(())
+}
+
+/// The instructions that an instruction in AT&T syntax can stand for, from the most to the least
+/// preferable. A mnemonic that exists as written is tried first, and then the mnemonic it stands
+/// for without its size suffix, with that size given to any memory operand that wasn't sized,
+/// and without it.
+fn att_variants(inst: Instruction, args: Vec<CleanArg>) -> Vec<(Instruction, Vec<CleanArg>)> {
+    let mnemonic = inst.idents.last().map(|ident| ident.name.clone()).unwrap_or_default();
+    let split = split_att_mnemonic(&mnemonic)
+        .filter(|&(name, _)| forms(name).is_some())
+        .map(|(name, size)| (name.to_string(), size));
+
+    let mut variants = Vec::new();
+    if forms(&mnemonic).is_some() || split.is_none() {
+        variants.push((inst.clone(), args.clone()));
+    }
+
+    if let Some((name, size)) = split {
+        let mut inst = inst;
+        if let Some(ident) = inst.idents.last_mut() {
+            ident.name = name;
+        }
+
+        // some memory operands, like that of `lea`, can't be sized
+        let mut sized = args.clone();
+        let mut resized = false;
+        for arg in &mut sized {
+            match arg {
+                CleanArg::Indirect { size: arg_size @ None, .. }
+                | CleanArg::IndirectJumpTarget { size: arg_size @ None, .. } if size.is_some() => {
+                    *arg_size = size;
+                    resized = true;
+                },
+                _ => (),
+            }
+        }
+        variants.push((inst.clone(), sized));
+        if resized {
+            variants.push((inst, args));
+        }
+    }
+
+    variants
+}
+
+/// The registers and displacement that a memory reference adds up.
+#[derive(Default)]
+struct MemoryTerms {
+    /// The registers, their scale, and if that scale was explicitly given.
+    registers: Vec<(Register, isize, bool)>,
+    /// The sum of the literal displacements.
+    literal: i64,
+    /// If any literal displacement was given, even if it was zero.
+    has_literal: bool,
+    /// The displacements only known at runtime, and whether they're subtracted.
+    dynamic: Vec<(bool, syn::Expr)>,
+}
+
+impl Parser<'_> {
+    /// Parses an argument, returning the immediate it is if its size wasn't given.
+    ///
+    /// ```text
+    /// size? jump
+    /// size? "[" memory "]"
+    /// size? register "=>" typemap
+    /// size? register
+    /// size? expr
+    /// ```
+    fn parse_arg(&mut self, input: ParseStream) -> parse::Result<(CleanArg, Option<Unsized>)> {
+        let size = self.parse_size(input);
+
+        if let Some(jump) = parse_jump(self.builder, input)? {
+            return Ok((CleanArg::JumpTarget { jump, size }, None));
+        }
+
+        if let Some((span, placeholder, modifier)) = self.parse_placeholder(input)? {
+            return self.placeholder_arg(span, placeholder, modifier, size);
+        }
+
+        if input.peek(syn::token::Bracket) {
+            let content;
+            syn::bracketed!(content in input);
+            return Ok((self.parse_memory(&content, size)?, None));
+        }
+
+        let expr: syn::Expr = input.parse()?;
+
+        if input.peek(Token![=>]) {
+            let base = match self.as_register(&expr)? {
+                Some(base) => base,
+                None => return Err(syn::Error::new(expr.span(), "Expected a register to use as base of the type map")),
+            };
+            let _: Token![=>] = input.parse()?;
+            return Ok((self.parse_typemap(input, base, size)?, None));
+        }
+
+        if let Some(reg) = self.as_register(&expr)? {
+            if size.is_some() {
+                return Err(syn::Error::new(expr.span(), "Registers can't be given a size"));
+            }
+            return Ok((CleanArg::Direct { reg }, None));
+        }
+
+        Ok(self.immediate(&expr, size))
+    }
+
+    /// Turns an expression into an immediate, returning if its size wasn't given.
+    fn immediate(&mut self, expr: &syn::Expr, size: Option<Size>) -> (CleanArg, Option<Unsized>) {
+        if let Some(value) = as_literal(expr) {
+            let signed = match value {
+                Literal::Signed(value) => Number::from_u64_and_repr(value as u64, NumericRepr::signed(signed_size(value))),
+                Literal::Unsigned(value) => Number::from_u64_and_repr(value, NumericRepr::U64),
+            };
+
+            if let Some(size) = size {
+                let value = signed.cast_as(NumericRepr::signed(size));
+                return (CleanArg::Immediate { value: Value::Number(value) }, None);
+            }
+
+            let unsigned = match value {
+                Literal::Signed(value) if value >= 0 => Number::from_u64_and_repr(value as u64, NumericRepr::unsigned(unsigned_size(value as u64))),
+                _ => signed,
+            };
+            let value = Value::Number(signed);
+            return (CleanArg::Immediate { value }, Some(Unsized::Literal { signed, unsigned }));
+        }
+
+        let repr = NumericRepr::signed(size.unwrap_or(Size::DWORD));
+        let expr = self.builder.expr(quote! { #expr }, repr);
+        let value = Value::Expr(expr);
+        let unsized_immediate = if size.is_none() { Some(Unsized::Dynamic(expr)) } else { None };
+        (CleanArg::Immediate { value }, unsized_immediate)
+    }
+
+    /// Parses an argument in AT&T syntax, returning the segment it overrides and the immediate
+    /// it is if its size wasn't given. Indirect jumps and calls mark their operand with `*`.
+    ///
+    /// ```text
+    /// "$" expr
+    /// "$"? placeholder
+    /// "*"? register
+    /// "*"? jump ("(" "%rip" ")")?
+    /// "*"? ("%" segment ":")? disp? ("(" register? ("," register ("," scale)?)? ")")?
+    /// ```
+    fn parse_att_arg(&mut self, input: ParseStream) -> parse::Result<(Option<syn::Ident>, CleanArg, Option<Unsized>)> {
+        if input.peek(Token![$]) {
+            let _: Token![$] = input.parse()?;
+            if let Some((span, placeholder, modifier)) = self.parse_placeholder(input)? {
+                if placeholder.expr.is_none() {
+                    return Err(syn::Error::new(span, "Expected an immediate operand"));
+                }
+                let (arg, unsized_immediate) = self.placeholder_arg(span, placeholder, modifier, None)?;
+                return Ok((None, arg, unsized_immediate));
+            }
+            let expr: syn::Expr = input.parse()?;
+            let (arg, unsized_immediate) = self.immediate(&expr, None);
+            return Ok((None, arg, unsized_immediate));
+        }
+
+        if input.peek(Token![*]) {
+            let _: Token![*] = input.parse()?;
+        }
+
+        if let Some((span, placeholder, modifier)) = self.parse_placeholder(input)? {
+            let (arg, unsized_immediate) = self.placeholder_arg(span, placeholder, modifier, None)?;
+            return Ok((None, arg, unsized_immediate));
+        }
+
+        let segment = if input.peek(Token![%]) && input.peek3(Token![:]) {
+            let _: Token![%] = input.parse()?;
+            let segment = parse_ident_or_rust_keyword(input)?;
+            let _: Token![:] = input.parse()?;
+            if !["es", "cs", "ss", "ds", "fs", "gs"].contains(&segment.to_string().as_str()) {
+                return Err(syn::Error::new(segment.span(), "Expected a segment register"));
+            }
+            Some(segment)
+        } else {
+            if input.peek(Token![%]) {
+                let reg = self.parse_att_register(input)?;
+                return Ok((None, CleanArg::Direct { reg }, None));
+            }
+            None
+        };
+
+        if let Some(jump) = parse_jump(self.builder, input)? {
+            if !input.peek(syn::token::Paren) {
+                return Ok((segment, CleanArg::JumpTarget { jump, size: None }, None));
+            }
+
+            let content;
+            let parens = syn::parenthesized!(content in input);
+            let reg = self.parse_att_register(&content)?;
+            if reg != RegId::RIP || !content.is_empty() {
+                return Err(syn::Error::new(parens.span.join(), "Labels can only be addressed relative to %rip"));
+            }
+            return Ok((segment, CleanArg::IndirectJumpTarget { jump, size: None }, None));
+        }
+
+        // the displacement is everything before the parenthesized registers
+        let span = input.span();
+        let mut disp = TokenStream::new();
+        let mut registers = None;
+        while !input.is_empty() && !input.peek(Token![,]) {
+            let token: TokenTree = input.parse()?;
+            if let TokenTree::Group(ref group) = token {
+                let is_last = input.is_empty() || input.peek(Token![,]);
+                let first = group.stream().into_iter().next();
+                let has_registers = match first {
+                    Some(TokenTree::Punct(ref punct)) => punct.as_char() == '%' || punct.as_char() == ',',
+                    Some(TokenTree::Group(ref group)) => group.delimiter() == Delimiter::Brace,
+                    _ => false,
+                };
+                if group.delimiter() == Delimiter::Parenthesis && is_last && has_registers {
+                    registers = Some(group.stream());
+                    break;
+                }
+            }
+            disp.extend(Some(token));
+        }
+
+        if disp.is_empty() && registers.is_none() {
+            return Err(syn::Error::new(span, "Expected an operand"));
+        }
+
+        let disp = if disp.is_empty() {
+            None
+        } else {
+            let mut expr: syn::Expr = syn::parse2(disp)?;
+            if let Some(placeholder) = self.as_placeholder(&expr) {
+                expr = match placeholder.expr {
+                    Some(ref bound) => bound.clone(),
+                    None => return Err(syn::Error::new(expr.span(), "Expected an immediate operand as displacement")),
+                };
+            }
+            match as_signed_number(&expr) {
+                Some(value) => self.displacement(value, true, Vec::new()),
+                None => self.displacement(0, false, vec![(false, expr)]),
+            }
+        };
+
+        let (base, index) = match registers {
+            Some(registers) => {
+                let parser = |input: ParseStream| self.parse_att_registers(input);
+                parse::Parser::parse2(parser, registers)?
+            },
+            None => (None, None),
+        };
+
+        Ok((segment, CleanArg::Indirect { nosplit: false, size: None, disp_size: None, base, index, disp }, None))
+    }
+
+    /// Parses the registers of a memory reference in AT&T syntax.
+    ///
+    /// ```text
+    /// register? ("," register ("," scale)?)?
+    /// ```
+    #[allow(clippy::type_complexity)]
+    fn parse_att_registers(&mut self, input: ParseStream) -> parse::Result<(Option<Register>, Option<(Register, isize, Option<Expr>)>)> {
+        let base = if input.peek(Token![%]) || input.peek(syn::token::Brace) {
+            Some(self.parse_att_register(input)?)
+        } else {
+            None
+        };
+
+        if input.is_empty() {
+            return Ok((base, None));
+        }
+
+        let _: Token![,] = input.parse()?;
+        let index = self.parse_att_register(input)?;
+        let mut scale = 1;
+        if !input.is_empty() {
+            let _: Token![,] = input.parse()?;
+            let expr: syn::Expr = input.parse()?;
+            scale = match as_number(&expr) {
+                Some(scale) => scale as isize,
+                None => return Err(syn::Error::new(expr.span(), "The scale of an index has to be a literal")),
+            };
+        }
+
+        Ok((base, Some((index, scale, None))))
+    }
+
+    /// Parses a register in AT&T syntax.
+    ///
+    /// ```text
+    /// "%" ident
+    /// "%" "st" ("(" number ")")?
+    /// "%" family "(" expr ")"
+    /// placeholder
+    /// ```
+    fn parse_att_register(&mut self, input: ParseStream) -> parse::Result<Register> {
+        if let Some((span, placeholder, modifier)) = self.parse_placeholder(input)? {
+            return placeholder.operand.register(modifier.as_deref(), self.mode)
+                .map_err(|e| syn::Error::new(span, e.to_string()));
+        }
+
+        let _: Token![%] = input.parse()?;
+        let name = parse_ident_or_rust_keyword(input)?;
+
+        let expr: syn::Expr = if input.peek(syn::token::Paren) {
+            let content;
+            syn::parenthesized!(content in input);
+            let arg: syn::Expr = content.parse()?;
+            if name == "st" {
+                let ident = match as_number(&arg) {
+                    Some(idx) => syn::Ident::new(&format!("st{}", idx), name.span()),
+                    None => return Err(syn::Error::new(arg.span(), "Expected the index of a floating point register")),
+                };
+                syn::parse_quote! { #ident }
+            } else {
+                syn::parse_quote! { #name(#arg) }
+            }
+        } else if name == "st" {
+            let ident = syn::Ident::new("st0", name.span());
+            syn::parse_quote! { #ident }
+        } else {
+            syn::parse_quote! { #name }
+        };
+
+        match self.as_register(&expr)? {
+            Some(reg) => Ok(reg),
+            None => {
+                let message = match lookup_register(&name.to_string(), self.mode) {
+                    Ok(_) => "Expected a register".to_string(),
+                    Err(e) => e.to_string(),
+                };
+                Err(syn::Error::new(name.span(), message))
+            }
+        }
+    }
+
+    /// Parses a placeholder that makes up a whole argument or register, if there is one. Braces
+    /// that don't refer to a declared operand are left to be parsed as a block expression.
+    ///
+    /// ```text
+    /// "{" (ident | integer) (":" modifier)? "}"
+    /// ```
+    fn parse_placeholder(&self, input: ParseStream) -> parse::Result<Option<(Span, Placeholder, Option<String>)>> {
+        if !input.peek(syn::token::Brace) {
+            return Ok(None);
+        }
+
+        let fork = input.fork();
+        let content;
+        let braces = syn::braced!(content in fork);
+        if !fork.is_empty() && !fork.peek(Token![,]) {
+            return Ok(None);
+        }
+
+        let key = if content.peek(syn::LitInt) {
+            content.parse::<syn::LitInt>()?.to_string()
+        } else if content.peek(syn::Ident::peek_any) {
+            parse_ident_or_rust_keyword(&content)?.to_string()
+        } else {
+            return Ok(None);
+        };
+        let modifier = if content.peek(Token![:]) {
+            let _: Token![:] = content.parse()?;
+            Some(parse_ident_or_rust_keyword(&content)?.to_string())
+        } else {
+            None
+        };
+        if !content.is_empty() {
+            return Ok(None);
+        }
+
+        let span = braces.span.join();
+        let placeholder = match self.builder.placeholder(&key) {
+            Some(placeholder) => placeholder.clone(),
+            None if modifier.is_some() => return Err(syn::Error::new(span, format!("Unknown operand '{}'", key))),
+            None => return Ok(None),
+        };
+
+        let _: TokenTree = input.parse()?;
+        Ok(Some((span, placeholder, modifier)))
+    }
+
+    /// The argument a placeholder stands for, giving a memory reference the size it was used with.
+    fn placeholder_arg(&mut self, span: Span, placeholder: Placeholder, modifier: Option<String>, size: Option<Size>)
+        -> parse::Result<(CleanArg, Option<Unsized>)>
+    {
+        let arg = placeholder.operand.argument(modifier.as_deref(), self.mode)
+            .map_err(|e| syn::Error::new(span, e.to_string()))?;
+
+        match arg {
+            CleanArg::Immediate { .. } => match placeholder.expr {
+                Some(ref expr) => Ok(self.immediate(expr, size)),
+                None => Ok((arg, None)),
+            },
+            CleanArg::Direct { .. } if size.is_some() => Err(syn::Error::new(span, "Registers can't be given a size")),
+            CleanArg::Indirect { nosplit, size: None, disp_size, base, index, disp } =>
+                Ok((CleanArg::Indirect { nosplit, size, disp_size, base, index, disp }, None)),
+            CleanArg::IndirectJumpTarget { jump, size: None } => Ok((CleanArg::IndirectJumpTarget { jump, size }, None)),
+            arg => Ok((arg, None)),
+        }
+    }
+
+    /// The operand of a placeholder within an expression, like a term of a memory reference.
+    fn as_placeholder(&self, expr: &syn::Expr) -> Option<&Placeholder> {
+        let block = match expr {
+            syn::Expr::Block(syn::ExprBlock { block, label: None, .. }) => block,
+            _ => return None,
+        };
+        let key = match block.stmts.as_slice() {
+            [syn::Stmt::Expr(expr, None)] => match as_ident(expr) {
+                Some(ident) => ident.to_string(),
+                None => as_number(expr)?.to_string(),
+            },
+            _ => return None,
+        };
+        self.builder.placeholder(&key)
+    }
+
+    /// Parses a size keyword, if there is one.
+    fn parse_size(&self, input: ParseStream) -> Option<Size> {
+        let sizes = match self.mode {
+            X86Mode::Long => &X64_SIZES,
+            X86Mode::Protected => &X86_SIZES,
+        };
+
+        sizes.iter()
+            .find(|&&(name, _)| eat_pseudo_keyword(input, name))
+            .map(|&(_, size)| size)
+    }
+
+    /// Parses the contents of a memory reference.
+    ///
+    /// ```text
+    /// "NOSPLIT"? size? jump
+    /// "NOSPLIT"? size? expr
+    /// ```
+    fn parse_memory(&mut self, input: ParseStream, size: Option<Size>) -> parse::Result<CleanArg> {
+        let nosplit = eat_pseudo_keyword(input, "NOSPLIT");
+        let disp_size = self.parse_size(input);
+
+        if let Some(jump) = parse_jump(self.builder, input)? {
+            return Ok(CleanArg::IndirectJumpTarget { jump, size });
+        }
+
+        let expr: syn::Expr = input.parse()?;
+        let mut terms = MemoryTerms::default();
+        self.add_terms(&expr, false, &mut terms)?;
+
+        let (base, index) = split_registers(&expr, terms.registers)?;
+        let disp = self.displacement(terms.literal, terms.has_literal, terms.dynamic);
+
+        Ok(CleanArg::Indirect { nosplit, size, disp_size, base, index, disp })
+    }
+
+    /// Parses a type map, a memory reference to a field of a struct in an array.
+    ///
+    /// ```text
+    /// path ("[" size? expr "]")? ("." ident)*
+    /// ```
+    fn parse_typemap(&mut self, input: ParseStream, base: Register, size: Option<Size>) -> parse::Result<CleanArg> {
+        let ty = syn::Path::parse_mod_style(input)?;
+
+        let mut disp_size = None;
+        let mut index = None;
+        let mut offset = quote! { 0u64 };
+        if input.peek(syn::token::Bracket) {
+            let content;
+            syn::bracketed!(content in input);
+            disp_size = self.parse_size(&content);
+
+            let expr: syn::Expr = content.parse()?;
+            let mut terms = MemoryTerms::default();
+            self.add_terms(&expr, false, &mut terms)?;
+
+            let mut registers = terms.registers.into_iter();
+            if let Some((reg, scale, _)) = registers.next() {
+                let scale_expr = self.builder.expr(quote! { ::core::mem::size_of::<#ty>() }, NumericRepr::U64);
+                index = Some((reg, scale, Some(scale_expr)));
+            }
+            if registers.next().is_some() {
+                return Err(syn::Error::new(expr.span(), "Type maps can only be indexed by a single register"));
+            }
+
+            offset = sum_tokens(terms.literal, &terms.dynamic);
+        }
+
+        let mut fields = Vec::new();
+        while input.peek(Token![.]) {
+            let _: Token![.] = input.parse()?;
+            fields.push(parse_ident_or_rust_keyword(input)?);
+        }
+
+        let field_offset = if fields.is_empty() {
+            quote! { 0u64 }
+        } else {
+            quote! { (::core::mem::offset_of!(#ty, #(#fields).*) as u64) }
+        };
+        let disp = self.builder.computed(quote! {
+            (#offset).wrapping_mul(::core::mem::size_of::<#ty>() as u64).wrapping_add(#field_offset)
+        }, NumericRepr::I32);
+
+        Ok(CleanArg::Indirect {
+            nosplit: false,
+            size,
+            disp_size,
+            base: Some(base),
+            index,
+            disp: Some(Value::Expr(disp)),
+        })
+    }
+
+    /// Splits the expression of a memory reference into the registers and displacements it adds.
+    fn add_terms(&mut self, expr: &syn::Expr, negative: bool, terms: &mut MemoryTerms) -> parse::Result<()> {
+        match expr {
+            syn::Expr::Binary(syn::ExprBinary { left, op: syn::BinOp::Add(_), right, .. }) => {
+                self.add_terms(left, negative, terms)?;
+                self.add_terms(right, negative, terms)
+            },
+            syn::Expr::Binary(syn::ExprBinary { left, op: syn::BinOp::Sub(_), right, .. }) => {
+                self.add_terms(left, negative, terms)?;
+                self.add_terms(right, !negative, terms)
+            },
+            syn::Expr::Binary(syn::ExprBinary { left, op: syn::BinOp::Mul(_), right, .. }) => {
+                let scaled = match (self.as_register(left)?, self.as_register(right)?) {
+                    (Some(reg), None) => as_number(right).map(|scale| (reg, scale)),
+                    (None, Some(reg)) => as_number(left).map(|scale| (reg, scale)),
+                    (Some(_), Some(_)) => return Err(syn::Error::new(expr.span(), "Registers can't be multiplied with each other")),
+                    (None, None) => None,
+                };
+
+                match scaled {
+                    Some(_) if negative => Err(syn::Error::new(expr.span(), "Registers can't be subtracted")),
+                    Some((reg, scale)) => {
+                        add_register(terms, reg, scale as isize, true);
+                        Ok(())
+                    },
+                    None => {
+                        terms.dynamic.push((negative, expr.clone()));
+                        Ok(())
+                    }
+                }
+            },
+            syn::Expr::Paren(syn::ExprParen { expr, .. })
+            | syn::Expr::Group(syn::ExprGroup { expr, .. }) => self.add_terms(expr, negative, terms),
+            _ => {
+                if let Some(value) = as_signed_number(expr) {
+                    terms.literal = if negative { terms.literal.wrapping_sub(value) } else { terms.literal.wrapping_add(value) };
+                    terms.has_literal = true;
+                } else if let Some(reg) = self.as_register(expr)? {
+                    if negative {
+                        return Err(syn::Error::new(expr.span(), "Registers can't be subtracted"));
+                    }
+                    add_register(terms, reg, 1, false);
+                } else if let Some(placeholder) = self.as_placeholder(expr) {
+                    match placeholder.expr.clone() {
+                        Some(bound) => return self.add_terms(&bound, negative, terms),
+                        None => return Err(syn::Error::new(expr.span(), "Memory references can't be used within a memory reference")),
+                    }
+                } else {
+                    terms.dynamic.push((negative, expr.clone()));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// The displacement of a memory reference. Literals are folded into a number, while any
+    /// dynamic displacement is summed at runtime.
+    fn displacement(&mut self, literal: i64, has_literal: bool, dynamic: Vec<(bool, syn::Expr)>) -> Option<Value> {
+        match dynamic.as_slice() {
+            [] if has_literal => Some(Value::Number(Number::from_u64_and_repr(literal as u64, NumericRepr::signed(signed_size(literal))))),
+            [] => None,
+            [(false, expr)] if literal == 0 => Some(Value::Expr(self.builder.expr(quote! { #expr }, NumericRepr::I32))),
+            _ => {
+                let sum = sum_tokens(literal, &dynamic);
+                Some(Value::Expr(self.builder.computed(sum, NumericRepr::I32)))
+            }
+        }
+    }
+
+    /// Interprets an expression as a register, if it names one or is a dynamic register.
+    ///
+    /// ```text
+    /// ident
+    /// family "(" expr ")"
+    /// ```
+    fn as_register(&mut self, expr: &syn::Expr) -> parse::Result<Option<Register>> {
+        match expr {
+            syn::Expr::Group(syn::ExprGroup { expr, .. }) => self.as_register(expr),
+            syn::Expr::Block(_) => match self.as_placeholder(expr) {
+                Some(Placeholder { operand: operand @ Operand::Register { .. }, .. }) => operand.register(None, self.mode)
+                    .map(Some)
+                    .map_err(|e| syn::Error::new(expr.span(), e.to_string())),
+                _ => Ok(None),
+            },
+            syn::Expr::Call(syn::ExprCall { func, args, .. }) => {
+                let family = match as_ident(func) {
+                    Some(family) => family.to_string(),
+                    None => return Ok(None),
+                };
+                let entry = match self.mode {
+                    X86Mode::Long => X64_FAMILIES_MAP.get(&family),
+                    X86Mode::Protected => X86_FAMILIES_MAP.get(&family),
+                };
+                let &(_, (size, family)) = match entry {
+                    Some(entry) => entry,
+                    None => return Ok(None),
+                };
+                if args.len() != 1 {
+                    return Err(syn::Error::new(expr.span(), "Dynamic registers take a single argument"));
+                }
+
+                let id = &args[0];
+                let id = self.builder.expr(quote! { #id }, NumericRepr::U8);
+                Ok(Some(Register::new_dynamic(size, family, id)))
+            },
+            _ => {
+                let name = match as_ident(expr) {
+                    Some(name) => name.to_string(),
+                    None => return Ok(None),
+                };
+
+                // registers can't be shadowed by aliases
+                match lookup_register(&name, self.mode) {
+                    Ok((id, size)) => Ok(Some(Register::new_static(size, id))),
+                    Err(_) if self.aliases.contains_key(&name) => self.alias_register(expr, &name).map(Some),
+                    Err(Error::UnknownName { notes, .. }) if notes.iter().any(|note| matches!(note, Note::OnlyAvailableIn(_))) => {
+                        let error = lookup_register(&name, self.mode).unwrap_err();
+                        Err(syn::Error::new(expr.span(), error.to_string()))
+                    },
+                    Err(_) => Ok(None),
+                }
+            }
+        }
+    }
+
+    /// The register an alias stands for, which is either the name of a register or a dynamic
+    /// register.
+    fn alias_register(&mut self, expr: &syn::Expr, alias: &str) -> parse::Result<Register> {
+        let target: syn::Expr = syn::parse_str(&self.aliases[alias])?;
+        let reg = match target {
+            syn::Expr::Call(_) => self.as_register(&target)?,
+            _ => as_ident(&target)
+                .and_then(|name| lookup_register(&name.to_string(), self.mode).ok())
+                .map(|(id, size)| Register::new_static(size, id)),
+        };
+        reg.ok_or_else(|| syn::Error::new(expr.span(), format!(
+            "The alias '{}' stands for '{}', which isn't a register", alias, self.aliases[alias])))
+    }
+}
+
+/// If the name is a register in the mode.
+pub(super) fn is_register(name: &str, mode: X86Mode) -> bool {
+    lookup_register(name, mode).is_ok()
+}
+
+/// Adds a register to the terms of a memory reference, merging it with the same static
+/// register if it was already used.
+fn add_register(terms: &mut MemoryTerms, reg: Register, scale: isize, explicit: bool) {
+    if let RegKind::Static(_) = reg.kind {
+        if let Some(entry) = terms.registers.iter_mut().find(|(r, _, _)| *r == reg) {
+            entry.1 += scale;
+            entry.2 = true;
+            return;
+        }
+    }
+    terms.registers.push((reg, scale, explicit));
+}
+
+/// Decides which registers of a memory reference are the base and the index.
+///
+/// A single register is the base, unless it was scaled. Of two registers, an unscaled one is
+/// the base, preferring one that isn't a vector register as those can only be an index.
+#[allow(clippy::type_complexity)]
+fn split_registers(expr: &syn::Expr, mut registers: Vec<(Register, isize, bool)>)
+    -> parse::Result<(Option<Register>, Option<(Register, isize, Option<Expr>)>)>
+{
+    match registers.len() {
+        0 => Ok((None, None)),
+        1 => {
+            let (reg, scale, explicit) = registers.pop().unwrap();
+            if scale == 1 && !explicit {
+                Ok((Some(reg), None))
+            } else {
+                Ok((None, Some((reg, scale, None))))
+            }
+        },
+        2 => {
+            let rank = |&(ref reg, scale, explicit): &(Register, isize, bool)| {
+                (scale != 1, explicit, reg.kind.family() == RegFamily::XMM)
+            };
+            let base_idx = if rank(&registers[1]) < rank(&registers[0]) { 1 } else { 0 };
+            let (base, base_scale, _) = registers.remove(base_idx);
+            let (index, scale, _) = registers.pop().unwrap();
+
+            if base_scale != 1 {
+                return Err(syn::Error::new(expr.span(), "Only one register in a memory reference can be scaled"));
+            }
+            Ok((Some(base), Some((index, scale, None))))
+        },
+        _ => Err(syn::Error::new(expr.span(), "Memory references can only use a base and an index register")),
+    }
+}
+
+/// Tokens that sum a literal and dynamic values at runtime, as an u64.
+fn sum_tokens(literal: i64, dynamic: &[(bool, syn::Expr)]) -> TokenStream {
+    let literal = proc_macro2::Literal::u64_suffixed(literal as u64);
+    let mut sum = quote! { #literal };
+    for (negative, expr) in dynamic {
+        sum = if *negative {
+            quote! { #sum.wrapping_sub((#expr) as u64) }
+        } else {
+            quote! { #sum.wrapping_add((#expr) as u64) }
+        };
+    }
+    sum
+}
+
+enum Literal {
+    Signed(i64),
+    /// Values that don't fit in an i64.
+    Unsigned(u64),
+}
+
+fn as_literal(expr: &syn::Expr) -> Option<Literal> {
+    if let Some(value) = as_signed_number(expr) {
+        Some(Literal::Signed(value))
+    } else {
+        as_number(expr).map(Literal::Unsigned)
+    }
+}
+
+/// The smallest size that holds a signed value.
+fn signed_size(value: i64) -> Size {
+    if i8::try_from(value).is_ok() {
+        Size::BYTE
+    } else if i16::try_from(value).is_ok() {
+        Size::WORD
+    } else if i32::try_from(value).is_ok() {
+        Size::DWORD
+    } else {
+        Size::QWORD
+    }
+}
+
+/// The smallest size that holds an unsigned value.
+fn unsigned_size(value: u64) -> Size {
+    if u8::try_from(value).is_ok() {
+        Size::BYTE
+    } else if u16::try_from(value).is_ok() {
+        Size::WORD
+    } else if u32::try_from(value).is_ok() {
+        Size::DWORD
+    } else {
+        Size::QWORD
+    }
+}
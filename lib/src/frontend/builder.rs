@@ -0,0 +1,243 @@
+//! The expression builder that the assembler backends emit their statements into.
+//!
+//! Every expression handed to the backends is stored here as a token stream, and any expression
+//! the backends compose from them is built as a new token stream that computes the combination
+//! at runtime. All composite expressions compute in `u64`, and are only cast to their final
+//! representation when they are serialized.
+use proc_macro2::{Literal, Span, TokenStream};
+use quote::quote;
+
+use crate::arch::{BasicExprBuilder, ErrorSpan};
+use crate::arch::x64::Operand;
+use crate::{Expr, Number, NumericRepr, Size, Stmt, Value};
+
+use core::fmt;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// An expression of the user, or one composed from them.
+struct Entry {
+    tokens: TokenStream,
+    /// If this expression was composed by us, its tokens evaluate to an `u64`.
+    composite: bool,
+}
+
+pub struct Builder {
+    pub stmts: Vec<Stmt>,
+    /// The operands that placeholders can be bound to, in the order they were declared.
+    pub placeholders: Vec<Placeholder>,
+    exprs: Vec<Entry>,
+    errors: Vec<(ErrorSpan, String)>,
+}
+
+/// An operand declared with `.operand`, which placeholders refer to by its name or position.
+#[derive(Clone)]
+pub struct Placeholder {
+    pub name: Option<String>,
+    pub operand: Operand,
+    /// The expression of an immediate, which is used as is in displacements.
+    pub expr: Option<syn::Expr>,
+}
+
+/// The point a builder can be reset to, to undo a failed attempt at assembling.
+#[derive(Clone, Copy)]
+pub struct Checkpoint {
+    stmts: usize,
+    exprs: usize,
+    errors: usize,
+}
+
+impl Default for Builder {
+    fn default() -> Builder {
+        Builder::new()
+    }
+}
+
+impl Builder {
+    pub fn new() -> Builder {
+        Builder {
+            stmts: Vec::new(),
+            placeholders: Vec::new(),
+            exprs: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Stores an expression of the user, which is emitted as is.
+    pub fn expr(&mut self, tokens: TokenStream, repr: NumericRepr) -> Expr {
+        self.store(tokens, false, repr)
+    }
+
+    /// Stores an expression that evaluates to an `u64`, which is cast to its final
+    /// representation when it's emitted.
+    pub fn computed(&mut self, tokens: TokenStream, repr: NumericRepr) -> Expr {
+        self.store(tokens, true, repr)
+    }
+
+    fn store(&mut self, tokens: TokenStream, composite: bool, repr: NumericRepr) -> Expr {
+        self.exprs.push(Entry { tokens, composite });
+        Expr { idx: self.exprs.len() - 1, repr }
+    }
+
+    /// The tokens of an expression in its final representation.
+    pub fn tokens(&self, expr: Expr) -> TokenStream {
+        let entry = &self.exprs[expr.idx];
+        let tokens = &entry.tokens;
+        if entry.composite {
+            let ty = repr_type(expr.repr);
+            quote! { ((#tokens) as #ty) }
+        } else {
+            tokens.clone()
+        }
+    }
+
+    /// The tokens of a value in its final representation.
+    pub fn value_tokens(&self, value: Value) -> TokenStream {
+        match value {
+            Value::Number(number) => number_literal(number),
+            Value::Expr(expr) => self.tokens(expr),
+        }
+    }
+
+    /// The tokens of a value as an `u64`, as used within composite expressions.
+    fn operand(&self, value: Value) -> TokenStream {
+        match value {
+            Value::Number(number) => {
+                let lit = Literal::u64_suffixed(number.as_u64());
+                quote! { #lit }
+            },
+            Value::Expr(expr) => {
+                let entry = &self.exprs[expr.idx];
+                let tokens = &entry.tokens;
+                if entry.composite {
+                    quote! { (#tokens) }
+                } else {
+                    quote! { ((#tokens) as u64) }
+                }
+            }
+        }
+    }
+
+    fn combine(&mut self, a: Expr, tokens: TokenStream) -> Option<Expr> {
+        Some(self.computed(tokens, a.repr))
+    }
+
+    /// Looks up the operand of a placeholder, which is either a position or a name.
+    pub fn placeholder(&self, key: &str) -> Option<&Placeholder> {
+        match key.parse::<usize>() {
+            Ok(idx) => self.placeholders.get(idx),
+            Err(_) => self.placeholders.iter().find(|p| p.name.as_deref() == Some(key)),
+        }
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            stmts: self.stmts.len(),
+            exprs: self.exprs.len(),
+            errors: self.errors.len(),
+        }
+    }
+
+    /// Undoes everything that was added since the checkpoint was taken.
+    pub fn reset(&mut self, checkpoint: Checkpoint) {
+        self.stmts.truncate(checkpoint.stmts);
+        self.exprs.truncate(checkpoint.exprs);
+        self.errors.truncate(checkpoint.errors);
+    }
+
+    /// Removes the errors that were emitted since the checkpoint was taken.
+    pub fn take_errors(&mut self, checkpoint: Checkpoint) -> Vec<(ErrorSpan, String)> {
+        self.errors.split_off(checkpoint.errors)
+    }
+}
+
+impl BasicExprBuilder for Builder {
+    fn push(&mut self, stmt: Stmt) {
+        self.stmts.push(stmt)
+    }
+
+    fn bit_or(&mut self, a: Expr, b: Value) -> Option<Expr> {
+        let (a_tokens, b) = (self.operand(a.into()), self.operand(b));
+        self.combine(a, quote! { #a_tokens | #b })
+    }
+
+    fn bit_and(&mut self, a: Expr, b: Value) -> Option<Expr> {
+        let (a_tokens, b) = (self.operand(a.into()), self.operand(b));
+        self.combine(a, quote! { #a_tokens & #b })
+    }
+
+    fn bit_xor(&mut self, a: Expr, b: Value) -> Option<Expr> {
+        let (a_tokens, b) = (self.operand(a.into()), self.operand(b));
+        self.combine(a, quote! { #a_tokens ^ #b })
+    }
+
+    fn add(&mut self, a: Expr, b: Value) -> Option<Expr> {
+        let (a_tokens, b) = (self.operand(a.into()), self.operand(b));
+        self.combine(a, quote! { #a_tokens.wrapping_add(#b) })
+    }
+
+    fn mul(&mut self, a: Expr, b: Value) -> Option<Expr> {
+        let (a_tokens, b) = (self.operand(a.into()), self.operand(b));
+        self.combine(a, quote! { #a_tokens.wrapping_mul(#b) })
+    }
+
+    fn neg(&mut self, a: Expr) -> Option<Expr> {
+        let a_tokens = self.operand(a.into());
+        self.combine(a, quote! { !#a_tokens })
+    }
+
+    fn log2(&mut self, a: Expr) -> Option<Expr> {
+        let a_tokens = self.operand(a.into());
+        self.combine(a, quote! {
+            {
+                let value: u64 = #a_tokens;
+                assert!(value.is_power_of_two(), "Value not representable as a power of two");
+                u64::from(value.trailing_zeros())
+            }
+        })
+    }
+
+    fn mask_shift(&mut self, val: Expr, mask: u64, shift: i8) -> Option<Expr> {
+        let val_tokens = self.operand(val.into());
+        let mask = Literal::u64_suffixed(mask);
+        let tokens = if shift >= 0 {
+            let shift = Literal::u8_unsuffixed(shift as u8);
+            quote! { ((#val_tokens & #mask) << #shift) }
+        } else {
+            let shift = Literal::u8_unsuffixed(shift.unsigned_abs());
+            quote! { ((#val_tokens & #mask) >> #shift) }
+        };
+        self.combine(val, tokens)
+    }
+
+    fn emit_error_at(&mut self, span: ErrorSpan, args: fmt::Arguments) {
+        self.errors.push((span, args.to_string()));
+    }
+}
+
+/// The rust integer type that corresponds to a representation.
+pub fn repr_type(repr: NumericRepr) -> syn::Ident {
+    let name = match (repr.size, repr.signed) {
+        (Size::BYTE, false) => "u8",
+        (Size::BYTE, true) => "i8",
+        (Size::WORD, false) => "u16",
+        (Size::WORD, true) => "i16",
+        (Size::DWORD, false) => "u32",
+        (Size::DWORD, true) => "i32",
+        (_, false) => "u64",
+        (_, true) => "i64",
+    };
+    syn::Ident::new(name, Span::call_site())
+}
+
+/// A number as an unsuffixed literal, so it can be used as any integer type.
+pub fn number_literal(number: Number) -> TokenStream {
+    if number.repr().signed && number.as_i64() < 0 {
+        let lit = Literal::i64_unsuffixed(number.as_i64());
+        quote! { #lit }
+    } else {
+        let lit = Literal::u64_unsuffixed(number.as_u64());
+        quote! { #lit }
+    }
+}
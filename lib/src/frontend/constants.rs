@@ -0,0 +1,196 @@
+//! Named constants defined with `.equ`, and the compile time expressions of `.equ` and `.if`.
+//!
+//! Expressions are evaluated like rust would evaluate them on `i64`s and `bool`s: comparisons
+//! and `&&`, `||` give booleans, and `!` is a logical not on booleans and a bitwise not on
+//! integers. A condition holds if it is `true` or an integer that isn't zero.
+//!
+//! Constants are thus limited to 64 bits. Integer literals have to fit in a `u64` and are
+//! reinterpreted as an `i64`, so the wider values of `.oword` and `.yword` can't be named.
+//!
+//! In instructions and data directives, the name of a constant is replaced by its value, so a
+//! constant is used anywhere a literal can be.
+use proc_macro2::{Literal, Punct, Spacing, TokenStream, TokenTree};
+use syn::spanned::Spanned;
+use syn::parse;
+
+use core::convert::TryFrom;
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::collections::HashMap;
+use crate::{Number, NumericRepr};
+use crate::frontend::parse_helpers::{as_ident, as_lit};
+
+/// The value of a compile time expression.
+#[derive(Debug, Clone, Copy)]
+enum Constant {
+    Int(i64),
+    Bool(bool),
+}
+
+impl Constant {
+    fn into_number(self) -> Number {
+        let value = match self {
+            Constant::Int(value) => value,
+            Constant::Bool(value) => value as i64,
+        };
+        Number::from_u64_and_repr(value as u64, NumericRepr::I64)
+    }
+}
+
+/// Evaluates a compile time expression.
+pub(crate) fn evaluate(constants: &HashMap<String, Number>, expr: &syn::Expr) -> parse::Result<Number> {
+    eval(constants, expr).map(Constant::into_number)
+}
+
+fn eval(constants: &HashMap<String, Number>, expr: &syn::Expr) -> parse::Result<Constant> {
+    use self::Constant::*;
+
+    let span = expr.span();
+    Ok(match expr {
+        syn::Expr::Paren(syn::ExprParen { expr, .. })
+        | syn::Expr::Group(syn::ExprGroup { expr, .. }) => eval(constants, expr)?,
+        syn::Expr::Unary(syn::ExprUnary { op, expr, .. }) => match (op, eval(constants, expr)?) {
+            (syn::UnOp::Neg(_), Int(value)) => Int(value.wrapping_neg()),
+            (syn::UnOp::Not(_), Int(value)) => Int(!value),
+            (syn::UnOp::Not(_), Bool(value)) => Bool(!value),
+            _ => return Err(syn::Error::new(span, "This operation can't be used on a boolean")),
+        },
+        syn::Expr::Binary(syn::ExprBinary { left, op, right, .. }) => {
+            let left = eval(constants, left)?;
+            let right = eval(constants, right)?;
+            binary(op, left, right).ok_or_else(|| syn::Error::new(span, match (left, right) {
+                (Int(_), Int(_)) => "Division by zero, or shift out of range",
+                _ => "The operands of this operation have the wrong types",
+            }))?
+        },
+        _ => match as_lit(expr) {
+            Some(syn::Lit::Int(lit)) => match lit.base10_parse::<u64>() {
+                Ok(value) => Int(value as i64),
+                Err(e) => return Err(e),
+            },
+            Some(syn::Lit::Bool(lit)) => Bool(lit.value),
+            Some(_) => return Err(syn::Error::new(span, "Only integers and booleans can be used in constants")),
+            None => match as_ident(expr) {
+                Some(name) => match constants.get(&name.to_string()) {
+                    Some(value) => Int(value.as_i64()),
+                    None => return Err(syn::Error::new(span, format!("Unknown constant '{}'", name))),
+                },
+                None => return Err(syn::Error::new(span, "Expected an expression that can be evaluated at compile time")),
+            },
+        },
+    })
+}
+
+fn binary(op: &syn::BinOp, left: Constant, right: Constant) -> Option<Constant> {
+    use self::Constant::*;
+    use syn::BinOp;
+
+    Some(match (op, left, right) {
+        (BinOp::Add(_), Int(l), Int(r)) => Int(l.wrapping_add(r)),
+        (BinOp::Sub(_), Int(l), Int(r)) => Int(l.wrapping_sub(r)),
+        (BinOp::Mul(_), Int(l), Int(r)) => Int(l.wrapping_mul(r)),
+        (BinOp::Div(_), Int(l), Int(r)) => Int(l.checked_div(r)?),
+        (BinOp::Rem(_), Int(l), Int(r)) => Int(l.checked_rem(r)?),
+        (BinOp::Shl(_), Int(l), Int(r)) => Int(l.checked_shl(u32::try_from(r).ok()?)?),
+        (BinOp::Shr(_), Int(l), Int(r)) => Int(l.checked_shr(u32::try_from(r).ok()?)?),
+        (BinOp::BitAnd(_), Int(l), Int(r)) => Int(l & r),
+        (BinOp::BitOr(_), Int(l), Int(r)) => Int(l | r),
+        (BinOp::BitXor(_), Int(l), Int(r)) => Int(l ^ r),
+        (BinOp::BitAnd(_), Bool(l), Bool(r)) => Bool(l & r),
+        (BinOp::BitOr(_), Bool(l), Bool(r)) => Bool(l | r),
+        (BinOp::BitXor(_), Bool(l), Bool(r)) => Bool(l ^ r),
+        (BinOp::And(_), Bool(l), Bool(r)) => Bool(l && r),
+        (BinOp::Or(_), Bool(l), Bool(r)) => Bool(l || r),
+        (BinOp::Eq(_), Int(l), Int(r)) => Bool(l == r),
+        (BinOp::Ne(_), Int(l), Int(r)) => Bool(l != r),
+        (BinOp::Lt(_), Int(l), Int(r)) => Bool(l < r),
+        (BinOp::Le(_), Int(l), Int(r)) => Bool(l <= r),
+        (BinOp::Gt(_), Int(l), Int(r)) => Bool(l > r),
+        (BinOp::Ge(_), Int(l), Int(r)) => Bool(l >= r),
+        (BinOp::Eq(_), Bool(l), Bool(r)) => Bool(l == r),
+        (BinOp::Ne(_), Bool(l), Bool(r)) => Bool(l != r),
+        _ => return None,
+    })
+}
+
+/// If the line is a `.if`, `.elseif`, `.else` or `.endif` directive.
+pub(crate) fn is_conditional(line: &TokenStream) -> bool {
+    let mut tokens = line.clone().into_iter();
+    match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Punct(dot)), Some(TokenTree::Ident(name))) =>
+            dot.as_char() == '.' && (name == "if" || name == "elseif" || name == "else" || name == "endif"),
+        _ => false,
+    }
+}
+
+/// The directives whose arguments can use constants.
+const SUBSTITUTED_DIRECTIVES: &[&str] = &[
+    "byte", "word", "dword", "qword", "oword", "yword", "f32", "f64", "f80", "tword", "bytes", "align", "operand"
+];
+
+/// Replaces the names of constants in a line with their values, if the line is an instruction
+/// or a directive that takes values.
+pub(crate) fn substitute(constants: &HashMap<String, Number>, line: TokenStream) -> TokenStream {
+    if constants.is_empty() {
+        return line;
+    }
+
+    let tokens: Vec<TokenTree> = line.into_iter().collect();
+    if let [TokenTree::Punct(dot), TokenTree::Ident(name), ..] = tokens.as_slice() {
+        if dot.as_char() == '.' && !SUBSTITUTED_DIRECTIVES.iter().any(|d| name == d) {
+            return tokens.into_iter().collect();
+        }
+    }
+
+    // the first token is a mnemonic, prefix, label or directive
+    let mut out: Vec<TokenTree> = tokens.iter().take(1).cloned().collect();
+    substitute_tokens(constants, &tokens, 1, &mut out);
+    out.into_iter().collect()
+}
+
+fn substitute_tokens(constants: &HashMap<String, Number>, tokens: &[TokenTree], start: usize, out: &mut Vec<TokenTree>) {
+    for idx in start .. tokens.len() {
+        match tokens[idx] {
+            TokenTree::Ident(ref ident) => {
+                // not a label, directive, field or path segment
+                let prev = if idx == 0 { None } else { as_punct(&tokens[idx - 1]) };
+                let next = tokens.get(idx + 1).and_then(as_punct);
+                let named = !matches!(prev, Some('.') | Some('<') | Some('>') | Some(':')) && next != Some(':');
+
+                match constants.get(&ident.to_string()) {
+                    Some(value) if named => {
+                        let value = value.as_i64();
+                        if value < 0 {
+                            let mut minus = Punct::new('-', Spacing::Alone);
+                            minus.set_span(ident.span());
+                            out.push(TokenTree::Punct(minus));
+                        }
+                        let mut literal = Literal::u64_unsuffixed(value.unsigned_abs());
+                        literal.set_span(ident.span());
+                        out.push(TokenTree::Literal(literal));
+                    },
+                    _ => out.push(tokens[idx].clone()),
+                }
+            },
+            TokenTree::Group(ref group) => {
+                let inner: Vec<TokenTree> = group.stream().into_iter().collect();
+                let mut substituted = Vec::new();
+                substitute_tokens(constants, &inner, 0, &mut substituted);
+                let mut new = proc_macro2::Group::new(group.delimiter(), substituted.into_iter().collect());
+                new.set_span(group.span());
+                out.push(TokenTree::Group(new));
+            },
+            ref token => out.push(token.clone()),
+        }
+    }
+}
+
+fn as_punct(token: &TokenTree) -> Option<char> {
+    match token {
+        TokenTree::Punct(punct) => Some(punct.as_char()),
+        _ => None,
+    }
+}
@@ -0,0 +1,301 @@
+//! Parsing of the directives that are common to all architectures.
+use syn::parse::{self, ParseStream};
+use syn::spanned::Spanned;
+use syn::Token;
+use quote::quote;
+
+use crate::{evaluate_directive, Const, Directive, DynasmData, MalformedDirectiveError, Number, NumericRepr, Size};
+
+use crate::frontend::arch::{compile_operand, is_register, parse_jump};
+use crate::frontend::builder::Builder;
+use crate::frontend::constants::evaluate;
+use crate::frontend::extended::extended_precision;
+use crate::frontend::parse_helpers::{as_lit_with_negation, parse_ident_or_rust_keyword};
+
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+/// Parses a directive and applies it, adding any data it emits to the builder.
+pub(crate) fn compile_directive(data: &mut DynasmData, builder: &mut Builder, input: ParseStream) -> parse::Result<()> {
+    let _: Token![.] = input.parse()?;
+    let name = parse_ident_or_rust_keyword(input)?;
+    let span = name.span();
+
+    let directive = match name.to_string().as_str() {
+        // ; .arch ident
+        "arch" => {
+            let arch = parse_ident_or_rust_keyword(input)?;
+            Directive::Arch(arch.to_string())
+        },
+        // ; .syntax ident
+        "syntax" => {
+            let syntax = parse_ident_or_rust_keyword(input)?;
+            Directive::Syntax(syntax.to_string())
+        },
+        // ; .operand (ident "=")? class expr
+        "operand" => return compile_operand(data, builder, input),
+        // ; .feature ident ("," ident)*
+        "feature" => {
+            let features = input.parse_terminated(parse_ident_or_rust_keyword, Token![,])?;
+            Directive::Feature(features.iter().map(|f| f.to_string()).collect())
+        },
+        // ; .alias ident, ident ("(" expr ")")?
+        "alias" => {
+            let alias = parse_ident_or_rust_keyword(input)?;
+            if is_register(data, &alias.to_string()) {
+                return Err(syn::Error::new(alias.span(), format!("'{}' is a register, so it can't be an alias", alias)));
+            }
+            let _: Token![,] = input.parse()?;
+            let reg = parse_ident_or_rust_keyword(input)?;
+            let reg = if input.peek(syn::token::Paren) {
+                let number: proc_macro2::Group = input.parse()?;
+                quote! { #reg #number }.to_string()
+            } else {
+                reg.to_string()
+            };
+            Directive::Alias { alias: alias.to_string(), reg }
+        },
+        // ; .unalias ident
+        "unalias" => Directive::Unalias(parse_ident_or_rust_keyword(input)?.to_string()),
+        // ; .alias_push
+        "alias_push" => Directive::AliasPush,
+        // ; .alias_pop
+        "alias_pop" => Directive::AliasPop,
+        // ; .macro ident (ident ("," ident)*)?
+        "macro" => {
+            let name = parse_ident_or_rust_keyword(input)?;
+            let params = input.parse_terminated(parse_ident_or_rust_keyword, Token![,])?;
+            Directive::Macro { name: name.to_string(), params: params.iter().map(|p| p.to_string()).collect() }
+        },
+        // ; .endm
+        "endm" => Directive::EndMacro,
+        // ; .equ ident, expr
+        "equ" => {
+            let name = parse_ident_or_rust_keyword(input)?;
+            let _: Token![,] = input.parse()?;
+            let value: syn::Expr = input.parse()?;
+            Directive::Equ { name: name.to_string(), value: evaluate(&data.constants, &value)? }
+        },
+        // ; .if expr
+        "if" => match parse_condition(data, input, data.is_assembling()) {
+            Ok(condition) => Directive::If(condition),
+            Err(e) => {
+                // the block is opened anyway, so its .endif still has something to end
+                let _ = evaluate_directive(data, &mut builder.stmts, &Directive::If(Number::from_u64_and_repr(0, NumericRepr::I64)));
+                return Err(e);
+            },
+        },
+        // ; .elseif expr
+        "elseif" => {
+            // only evaluated if the branch could be taken
+            let needed = matches!(data.conditionals.last(), Some(block) if !block.taken);
+            Directive::ElseIf(parse_condition(data, input, needed)?)
+        },
+        // ; .else
+        "else" => Directive::Else,
+        // ; .endif
+        "endif" => Directive::EndIf,
+        // ; .byte (expr ("," expr)*)?
+        "byte" => parse_data(builder, input, Size::BYTE)?,
+        "word" => parse_data(builder, input, Size::WORD)?,
+        "dword" => parse_data(builder, input, Size::DWORD)?,
+        "qword" => parse_data(builder, input, Size::QWORD)?,
+        // ; .oword (expr ("," expr)*)?
+        "oword" => parse_wide_data(builder, input, Size::OWORD)?,
+        "yword" => parse_wide_data(builder, input, Size::HWORD)?,
+        // ; .f32 (expr ("," expr)*)?
+        "f32" => parse_float_data(builder, input, Size::DWORD)?,
+        "f64" => parse_float_data(builder, input, Size::QWORD)?,
+        "f80" | "tword" => parse_float_data(builder, input, Size::PWORD)?,
+        // ; .bytes expr
+        "bytes" => {
+            let expr: syn::Expr = input.parse()?;
+            Directive::Byte(builder.expr(quote! { #expr }, NumericRepr::U8))
+        },
+        // ; .align expr ("," expr)?
+        "align" => {
+            let value: syn::Expr = input.parse()?;
+            let value = builder.expr(quote! { #value }, NumericRepr::U64);
+            let with = if input.peek(Token![,]) {
+                let _: Token![,] = input.parse()?;
+                let with: syn::Expr = input.parse()?;
+                Some(builder.expr(quote! { #with }, NumericRepr::U8))
+            } else {
+                None
+            };
+            Directive::Align { value, with }
+        },
+        d => return Err(syn::Error::new(span, format!("unknown directive '{}'", d)))
+    };
+
+    match evaluate_directive(data, &mut builder.stmts, &directive) {
+        Ok(()) => Ok(()),
+        Err(MalformedDirectiveError::UnknownArchitecture(arch)) =>
+            Err(syn::Error::new(span, format!("Unknown architecture '{}'", arch))),
+        Err(MalformedDirectiveError::UnknownSyntax(syntax)) =>
+            Err(syn::Error::new(span, format!("Unknown syntax '{}'", syntax))),
+        Err(MalformedDirectiveError::UnknownFeature { what, .. }) =>
+            Err(syn::Error::new(span, format!("Unknown feature '{}'", what))),
+        Err(MalformedDirectiveError::DuplicateAlias { reused }) =>
+            Err(syn::Error::new(span, format!("Duplicate alias definition, alias '{}' was already defined", reused))),
+        Err(MalformedDirectiveError::UnknownAlias(alias)) =>
+            Err(syn::Error::new(span, format!("Unknown alias '{}'", alias))),
+        Err(MalformedDirectiveError::UnmatchedAliasPop) =>
+            Err(syn::Error::new(span, "Found .alias_pop without a matching .alias_push")),
+        Err(MalformedDirectiveError::DuplicateMacro { reused }) =>
+            Err(syn::Error::new(span, format!("Duplicate macro definition, macro '{}' was already defined", reused))),
+        Err(MalformedDirectiveError::NestedMacro) =>
+            Err(syn::Error::new(span, "Macros can't be defined within the body of another macro")),
+        Err(MalformedDirectiveError::UnmatchedEndMacro) =>
+            Err(syn::Error::new(span, "Found .endm without a macro to end")),
+        Err(MalformedDirectiveError::UnmatchedConditional) =>
+            Err(syn::Error::new(span, format!("Found .{} without a matching .if", name))),
+        Err(MalformedDirectiveError::BranchAfterElse) =>
+            Err(syn::Error::new(span, format!("Found .{} after the .else of its .if", name))),
+        Err(MalformedDirectiveError::UnknownDirective) =>
+            Err(syn::Error::new(span, "This directive is not supported here")),
+    }
+}
+
+/// Parses the condition of a `.if` or `.elseif`, which is only evaluated if it's `needed`.
+fn parse_condition(data: &DynasmData, input: ParseStream, needed: bool) -> parse::Result<Number> {
+    let condition: syn::Expr = input.parse()?;
+    if needed {
+        evaluate(&data.constants, &condition)
+    } else {
+        Ok(Number::from_u64_and_repr(0, NumericRepr::I64))
+    }
+}
+
+/// Parses the values of a data directive, which are either expressions or label references.
+fn parse_data(builder: &mut Builder, input: ParseStream, size: Size) -> parse::Result<Directive> {
+    let mut values = Vec::new();
+
+    while !input.is_empty() {
+        if let Some(jump) = parse_jump(builder, input)? {
+            values.push(Const::Relocate(jump));
+        } else {
+            let expr: syn::Expr = input.parse()?;
+            // the expression is passed on as is, so `as _` casts infer the right type
+            values.push(Const::Value(builder.expr(quote! { #expr }, NumericRepr::signed(size))));
+        }
+
+        if input.is_empty() {
+            break;
+        }
+        let _: Token![,] = input.parse()?;
+    }
+
+    Ok(Directive::Data(size, values))
+}
+
+/// Parses the values of a 128 or 256-bit data directive. Literals are encoded at compile time,
+/// as rust has no 256-bit integers and a 128-bit literal might not fit in an `i128`.
+fn parse_wide_data(builder: &mut Builder, input: ParseStream, size: Size) -> parse::Result<Directive> {
+    let mut values = Vec::new();
+
+    while !input.is_empty() {
+        let expr: syn::Expr = input.parse()?;
+        if let Some((syn::Lit::Int(lit), negated)) = as_lit_with_negation(&expr) {
+            let number = wide_number(lit.base10_digits(), negated, size).ok_or_else(||
+                syn::Error::new(expr.span(), format!("Value does not fit in a {}-bit integer", size.in_bytes() * 8))
+            )?;
+            values.push(Const::Number(number));
+        } else if size == Size::OWORD {
+            values.push(Const::Value(builder.expr(quote! { #expr }, NumericRepr::signed(size))));
+        } else {
+            return Err(syn::Error::new(expr.span(), "256-bit values have to be integer literals or constants"));
+        }
+
+        if input.is_empty() {
+            break;
+        }
+        let _: Token![,] = input.parse()?;
+    }
+
+    Ok(Directive::Data(size, values))
+}
+
+/// Converts the decimal digits of an integer literal to a number of the given size, if it fits.
+fn wide_number(digits: &str, negated: bool, size: Size) -> Option<Number> {
+    let mut words = [0u64; 4];
+    for digit in digits.bytes() {
+        let mut carry = u128::from(digit - b'0');
+        for word in words.iter_mut() {
+            let product = u128::from(*word) * 10 + carry;
+            *word = product as u64;
+            carry = product >> 64;
+        }
+        if carry != 0 {
+            return None;
+        }
+    }
+
+    if !negated {
+        return Number::from_words_and_repr(words, NumericRepr::unsigned(Size::HWORD))
+            .convert(NumericRepr::unsigned(size));
+    }
+
+    // negate in two's complement, the result is only valid if it is negative or zero
+    let zero = words == [0; 4];
+    let mut carry = true;
+    for word in words.iter_mut() {
+        let (sum, overflow) = (!*word).overflowing_add(carry as u64);
+        *word = sum;
+        carry = overflow;
+    }
+    if !zero && (words[3] as i64) >= 0 {
+        return None;
+    }
+    Number::from_words_and_repr(words, NumericRepr::signed(Size::HWORD))
+        .convert(NumericRepr::signed(size))
+}
+
+/// Parses the values of a floating point data directive. Literals are encoded at compile time,
+/// other expressions have to be of the float type of the same size.
+fn parse_float_data(builder: &mut Builder, input: ParseStream, size: Size) -> parse::Result<Directive> {
+    let mut values = Vec::new();
+
+    while !input.is_empty() {
+        let expr: syn::Expr = input.parse()?;
+        let literal = match as_lit_with_negation(&expr) {
+            Some((syn::Lit::Float(lit), negated)) => Some((lit.base10_digits().to_string(), lit.span(), negated)),
+            Some((syn::Lit::Int(lit), negated)) => Some((lit.base10_digits().to_string(), lit.span(), negated)),
+            _ => None,
+        };
+
+        if let Some((digits, span, negated)) = literal {
+            // every size is parsed directly from the digits, as rounding twice can be off by one
+            let invalid = || syn::Error::new(span, "Invalid float literal");
+            let number = match size {
+                Size::DWORD => {
+                    let value: f32 = digits.parse().map_err(|_| invalid())?;
+                    let value = if negated { -value } else { value };
+                    Number::from_u64_and_size(u64::from(value.to_bits()), size)
+                },
+                Size::QWORD => {
+                    let value: f64 = digits.parse().map_err(|_| invalid())?;
+                    let value = if negated { -value } else { value };
+                    Number::from_u64_and_size(value.to_bits(), size)
+                },
+                _ => extended_precision(&digits, negated).ok_or_else(invalid)?,
+            };
+            values.push(Const::Number(number));
+        } else {
+            let bits = match size {
+                Size::DWORD => quote! { f32::to_bits(#expr) as i32 },
+                Size::QWORD => quote! { f64::to_bits(#expr) as i64 },
+                _ => return Err(syn::Error::new(expr.span(), "80-bit floats have to be literals")),
+            };
+            values.push(Const::Value(builder.expr(bits, NumericRepr::signed(size))));
+        }
+
+        if input.is_empty() {
+            break;
+        }
+        let _: Token![,] = input.parse()?;
+    }
+
+    Ok(Directive::Data(size, values))
+}
@@ -0,0 +1,277 @@
+//! Conversion of decimal literals to the x87 80-bit extended precision format.
+//!
+//! Going through an `f64` would lose the 11 extra bits of mantissa the format has, so the
+//! literal is converted exactly, as a fraction of two big integers, and then rounded once to
+//! the nearest representable value, ties to even.
+use crate::{Number, NumericRepr, Size};
+
+use core::cmp::Ordering;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// The bias of the exponent field.
+const BIAS: i64 = 16383;
+/// The largest exponent field of a finite number.
+const MAX_EXPONENT: i64 = 0x7FFE;
+/// Denormals have an exponent field of 0, and are worth `mantissa * 2^-DENORMAL_SCALE`.
+const DENORMAL_SCALE: i64 = BIAS - 1 + 63;
+
+/// Literals that are at least `10^OVERFLOW_DIGITS` are too large to be represented.
+const OVERFLOW_DIGITS: i64 = 4933;
+/// Literals that are smaller than `10^UNDERFLOW_DIGITS` round to zero.
+const UNDERFLOW_DIGITS: i64 = -4960;
+
+/// Encodes the digits of a literal, as returned by `base10_digits`, as an extended precision
+/// float with an explicit integer bit. Returns `None` if the digits aren't a decimal number.
+pub(crate) fn extended_precision(digits: &str, negated: bool) -> Option<Number> {
+    let (mantissa, exponent) = match digits.find(&['e', 'E'][..]) {
+        Some(idx) => (&digits[.. idx], digits[idx + 1 ..].parse::<i64>().ok()?),
+        None => (digits, 0),
+    };
+    let (integer, fraction) = match mantissa.find('.') {
+        Some(idx) => (&mantissa[.. idx], &mantissa[idx + 1 ..]),
+        None => (mantissa, ""),
+    };
+
+    let mut value = Big::zero();
+    for c in integer.chars().chain(fraction.chars()) {
+        value.mul_add(10, c.to_digit(10)?);
+    }
+    let exponent = exponent.checked_sub(fraction.len() as i64)?;
+    let significant = integer.chars().chain(fraction.chars()).skip_while(|&c| c == '0').count() as i64;
+
+    // the value is now `value * 10^exponent`, and smaller than `10^magnitude`
+    let magnitude = exponent.saturating_add(significant);
+    let (exponent, mantissa) = if value.is_zero() || magnitude < UNDERFLOW_DIGITS {
+        (0, 0)
+    } else if magnitude > OVERFLOW_DIGITS {
+        (MAX_EXPONENT + 1, 1 << 63)
+    } else {
+        round(value, exponent)
+    };
+
+    let sign = u64::from(negated);
+    Some(Number::from_words_and_repr([mantissa, sign << 15 | exponent as u64, 0, 0], NumericRepr::unsigned(Size::PWORD)))
+}
+
+/// Rounds `value * 10^exponent` to the nearest extended precision float, returning its exponent
+/// field and mantissa.
+fn round(value: Big, exponent: i64) -> (i64, u64) {
+    let mut num = value;
+    let mut den = Big::one();
+    for _ in 0 .. exponent.unsigned_abs() {
+        if exponent > 0 {
+            num.mul_add(10, 0);
+        } else {
+            den.mul_add(10, 0);
+        }
+    }
+
+    // find the scale that puts the quotient in the range of the 64-bit mantissa
+    let mut scale = 64 - (num.bits() as i64 - den.bits() as i64);
+    let (mut quotient, mut remainder, mut divisor) = divide(&num, &den, scale);
+    loop {
+        if quotient >> 64 != 0 {
+            scale -= 1;
+        } else if quotient >> 63 == 0 {
+            scale += 1;
+        } else {
+            break;
+        }
+        let (q, r, d) = divide(&num, &den, scale);
+        quotient = q;
+        remainder = r;
+        divisor = d;
+    }
+
+    let mut biased = BIAS + 63 - scale;
+    if biased < 1 {
+        let (q, r, d) = divide(&num, &den, DENORMAL_SCALE);
+        quotient = q;
+        remainder = r;
+        divisor = d;
+        biased = 0;
+    }
+
+    let round_up = match remainder.shl(1).cmp(&divisor) {
+        Ordering::Greater => true,
+        Ordering::Equal => quotient & 1 != 0,
+        Ordering::Less => false,
+    };
+    if round_up {
+        quotient += 1;
+    }
+
+    if biased == 0 {
+        // a denormal that was rounded up to the smallest normal number
+        if quotient >> 63 != 0 {
+            biased = 1;
+        }
+    } else if quotient >> 64 != 0 {
+        quotient >>= 1;
+        biased += 1;
+    }
+
+    if biased > MAX_EXPONENT {
+        (MAX_EXPONENT + 1, 1 << 63)
+    } else {
+        (biased, quotient as u64)
+    }
+}
+
+/// Divides `num * 2^scale` by `den`, returning the quotient, the remainder and the divisor.
+fn divide(num: &Big, den: &Big, scale: i64) -> (u128, Big, Big) {
+    let mut remainder = num.shl(scale.max(0) as usize);
+    let divisor = den.shl((-scale).max(0) as usize);
+
+    let mut quotient = 0u128;
+    let bits = remainder.bits().saturating_sub(divisor.bits());
+    debug_assert!(bits < 128);
+    for bit in (0 ..= bits).rev() {
+        let shifted = divisor.shl(bit);
+        if remainder.cmp(&shifted) != Ordering::Less {
+            remainder.sub(&shifted);
+            quotient |= 1 << bit;
+        }
+    }
+
+    (quotient, remainder, divisor)
+}
+
+/// An arbitrary size unsigned integer, as little endian 32-bit limbs without trailing zeros.
+#[derive(Clone, Debug)]
+struct Big {
+    limbs: Vec<u32>,
+}
+
+impl Big {
+    fn zero() -> Big {
+        Big { limbs: Vec::new() }
+    }
+
+    fn one() -> Big {
+        Big { limbs: vec![1] }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// The amount of bits needed to represent the number.
+    fn bits(&self) -> usize {
+        match self.limbs.last() {
+            Some(last) => self.limbs.len() * 32 - last.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    /// `self = self * factor + addend`
+    fn mul_add(&mut self, factor: u32, addend: u32) {
+        let mut carry = u64::from(addend);
+        for limb in self.limbs.iter_mut() {
+            let product = u64::from(*limb) * u64::from(factor) + carry;
+            *limb = product as u32;
+            carry = product >> 32;
+        }
+        if carry != 0 {
+            self.limbs.push(carry as u32);
+        }
+        self.trim();
+    }
+
+    fn shl(&self, bits: usize) -> Big {
+        if self.is_zero() {
+            return Big::zero();
+        }
+        let (limbs, bits) = (bits / 32, bits % 32);
+        let mut result = vec![0; limbs];
+        let mut carry = 0u32;
+        for &limb in &self.limbs {
+            result.push(limb << bits | carry);
+            carry = if bits == 0 { 0 } else { limb >> (32 - bits) };
+        }
+        result.push(carry);
+        let mut result = Big { limbs: result };
+        result.trim();
+        result
+    }
+
+    /// `self = self - other`, where `other` is not larger than `self`.
+    fn sub(&mut self, other: &Big) {
+        let mut borrow = 0i64;
+        for (idx, limb) in self.limbs.iter_mut().enumerate() {
+            let rhs = other.limbs.get(idx).cloned().unwrap_or(0);
+            let difference = i64::from(*limb) - i64::from(rhs) - borrow;
+            *limb = difference as u32;
+            borrow = i64::from(difference < 0);
+        }
+        debug_assert!(borrow == 0);
+        self.trim();
+    }
+
+    fn cmp(&self, other: &Big) -> Ordering {
+        self.limbs.len().cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+
+    fn trim(&mut self) {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the exponent field and mantissa of each literal, as produced by glibc's strtold
+    const REFERENCE: &[(&str, u64, u64)] = &[
+        ("0", 0x0000, 0x0000000000000000),
+        ("1.0", 0x3FFF, 0x8000000000000000),
+        ("2.5", 0x4000, 0xA000000000000000),
+        ("0.1", 0x3FFB, 0xCCCCCCCCCCCCCCCD),
+        ("0.000001", 0x3FEB, 0x8637BD05AF6C69B6),
+        ("12345.6789e-3", 0x4002, 0xC587E69926AD12F3),
+        ("9007199254740993", 0x4034, 0x8000000000000400),
+        ("123456789012345678901234567890", 0x405F, 0xC77487FB61B9F077),
+        ("4.9406564584124654e-324", 0x3BCC, 0xFFFFFFFFFFFFFF64),
+        // ties round to even
+        ("18446744073709551615", 0x403E, 0xFFFFFFFFFFFFFFFF),
+        ("18446744073709551617", 0x403F, 0x8000000000000000),
+        ("18446744073709551619", 0x403F, 0x8000000000000002),
+        ("18446744073709551621", 0x403F, 0x8000000000000002),
+        // the largest numbers, and overflow to infinity
+        ("1e4932", 0x7FFE, 0xD72CB2A95C7EF6CD),
+        ("1.18973149535723176502e4932", 0x7FFE, 0xFFFFFFFFFFFFFFFF),
+        ("1.18973149535723176508575932662800702e4932", 0x7FFF, 0x8000000000000000),
+        ("1e4933", 0x7FFF, 0x8000000000000000),
+        // the smallest normal numbers, denormals and underflow to zero
+        ("3.3621031431120935063e-4932", 0x0001, 0x8000000000000000),
+        ("3.3621031431120935062e-4932", 0x0001, 0x8000000000000000),
+        ("1e-4940", 0x0000, 0x0000000663278E62),
+        ("3.6451995318824746025e-4951", 0x0000, 0x0000000000000001),
+        ("1.8225997659412373013e-4951", 0x0000, 0x0000000000000001),
+        ("1.8225997659412373012e-4951", 0x0000, 0x0000000000000000),
+        ("1e-4960", 0x0000, 0x0000000000000000),
+    ];
+
+    #[test]
+    fn correctly_rounded() {
+        for &(digits, exponent, mantissa) in REFERENCE {
+            let words = extended_precision(digits, false).unwrap().words();
+            assert_eq!((words[1], words[0]), (exponent, mantissa), "{}", digits);
+
+            let words = extended_precision(digits, true).unwrap().words();
+            assert_eq!((words[1], words[0]), (exponent | 0x8000, mantissa), "-{}", digits);
+        }
+    }
+
+    #[test]
+    fn invalid_digits() {
+        assert!(extended_precision("1x", false).is_none());
+        assert!(extended_precision("1e", false).is_none());
+    }
+}
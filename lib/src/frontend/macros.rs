@@ -0,0 +1,217 @@
+//! Recording and expanding the assembler macros defined with `.macro` and `.endm`.
+//!
+//! ```text
+//! ; .macro call_helper target, arg
+//! ;     mov rdi, arg
+//! ;     mov rax, QWORD target as _
+//! ;     call rax
+//! ;     test rax, rax
+//! ;     jz >ok
+//! ;     ud2
+//! ; ok:
+//! ; .endm
+//! ; call_helper helper, rbx
+//! ```
+//!
+//! The lines of a macro are recorded as they are, and assembled when the macro is invoked like an
+//! instruction. Every identifier in the body that names a parameter is replaced by the tokens of
+//! the matching argument. Local labels defined in the body are renamed in every expansion, so an
+//! expansion only ever refers to its own labels. Macros may invoke other macros, up to a depth of
+//! `MAX_DEPTH`.
+use proc_macro2::{Group, Spacing, Span, TokenStream, TokenTree};
+use syn::parse::{self, ParseStream};
+
+use core::str::FromStr;
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::DynasmData;
+use crate::frontend::builder::Builder;
+use crate::frontend::compile_line_at;
+use crate::frontend::parse_helpers::parse_ident_or_rust_keyword;
+
+/// How deep macro invocations can nest before expanding is given up on.
+const MAX_DEPTH: usize = 64;
+
+/// If the line is a `.macro` or `.endm` directive, which are assembled even while a macro is
+/// being recorded.
+pub(crate) fn is_definition(line: &TokenStream) -> bool {
+    let mut tokens = line.clone().into_iter();
+    match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Punct(dot)), Some(TokenTree::Ident(name))) =>
+            dot.as_char() == '.' && (name == "macro" || name == "endm"),
+        _ => false,
+    }
+}
+
+/// If the line invokes a macro.
+pub(crate) fn is_invocation(data: &DynasmData, input: ParseStream) -> bool {
+    let fork = input.fork();
+    match parse_ident_or_rust_keyword(&fork) {
+        Ok(name) => data.macros.contains_key(&name.to_string()),
+        Err(_) => false,
+    }
+}
+
+/// Expands an invocation of a macro, `name (arg ("," arg)*)?`, and assembles the lines it
+/// expands into. Errors are reported at the invocation.
+pub(crate) fn expand_macro(data: &mut DynasmData, builder: &mut Builder, input: ParseStream, depth: usize)
+    -> parse::Result<()>
+{
+    let name = parse_ident_or_rust_keyword(input)?;
+    let span = name.span();
+    let name = name.to_string();
+    let args = split_args(input.parse()?);
+
+    let mac = data.macros[&name].clone();
+    if args.len() != mac.params.len() {
+        return Err(syn::Error::new(span, format!(
+            "Macro '{}' takes {} arguments but {} were given", name, mac.params.len(), args.len())));
+    }
+    if depth >= MAX_DEPTH {
+        return Err(syn::Error::new(span, format!(
+            "Macro '{}' was invoked more than {} levels deep, is it recursive?", name, MAX_DEPTH)));
+    }
+
+    data.expansions += 1;
+    let expansion = Expansion {
+        params: &mac.params,
+        args: &args,
+        labels: local_labels(&mac.body),
+        suffix: format!("__{}", data.expansions),
+        span,
+    };
+
+    let mut errors: Option<syn::Error> = None;
+    for line in &mac.body {
+        let result = TokenStream::from_str(line)
+            .map_err(|e| syn::Error::new(span, e.to_string()))
+            .and_then(|line| compile_line_at(data, builder, expansion.substitute(line), depth + 1));
+
+        if let Err(e) = result {
+            match errors {
+                Some(ref mut errors) => errors.combine(e),
+                None => errors = Some(e),
+            }
+        }
+    }
+
+    match errors {
+        // nested expansions are reported by the outermost one
+        Some(errors) if depth == 0 => Err(errors.into_iter()
+            .map(|e| syn::Error::new(span, format!("In expansion of macro '{}': {}", name, e)))
+            .reduce(|mut errors, e| { errors.combine(e); errors })
+            .unwrap()),
+        Some(errors) => Err(errors),
+        None => Ok(()),
+    }
+}
+
+/// Splits the arguments of an invocation at every comma that is not nested in a group.
+fn split_args(tokens: TokenStream) -> Vec<TokenStream> {
+    let mut args = Vec::new();
+    let mut arg = Vec::new();
+
+    for token in tokens {
+        match token {
+            TokenTree::Punct(ref punct) if punct.as_char() == ',' => {
+                args.push(arg.drain(..).collect());
+            },
+            token => arg.push(token),
+        }
+    }
+    if !arg.is_empty() || !args.is_empty() {
+        args.push(arg.into_iter().collect());
+    }
+    args
+}
+
+/// The names of the local labels that the lines of a body define.
+fn local_labels(body: &[String]) -> Vec<String> {
+    body.iter()
+        .filter_map(|line| TokenStream::from_str(line).ok())
+        .filter_map(|line| label_definition(&line))
+        .collect()
+}
+
+/// If the line defines a local label, `ident ":"`, the name of the label.
+fn label_definition(line: &TokenStream) -> Option<String> {
+    let tokens: Vec<_> = line.clone().into_iter().take(3).collect();
+    match tokens.as_slice() {
+        [TokenTree::Ident(label), TokenTree::Punct(colon), ..]
+            if colon.as_char() == ':' && colon.spacing() == Spacing::Alone => Some(label.to_string()),
+        _ => None,
+    }
+}
+
+/// A single expansion of a macro.
+struct Expansion<'a> {
+    params: &'a [String],
+    args: &'a [TokenStream],
+    labels: Vec<String>,
+    /// Appended to the local labels of the body.
+    suffix: String,
+    /// The span of the invocation, which the tokens of the body are given.
+    span: Span,
+}
+
+impl Expansion<'_> {
+    /// Replaces the parameters in a line of the body with the arguments, and gives the local
+    /// labels of the body their names in this expansion.
+    fn substitute(&self, line: TokenStream) -> TokenStream {
+        let definition = label_definition(&line).is_some();
+        self.substitute_tokens(line, definition)
+    }
+
+    /// `label_position` is true if the first token is the name of a label.
+    fn substitute_tokens(&self, tokens: TokenStream, mut label_position: bool) -> TokenStream {
+        let mut out = Vec::new();
+        let mut prev: Option<(char, Spacing)> = None;
+
+        for token in tokens {
+            // a local label follows `<` or `>`, but not `->` or `=>`
+            let punct = match token {
+                TokenTree::Punct(ref p) => Some((p.as_char(), p.spacing())),
+                _ => None,
+            };
+            let next_label_position = match (prev, punct) {
+                (Some(('-', Spacing::Joint)), _) | (Some(('=', Spacing::Joint)), _) => false,
+                (_, Some(('<', _))) | (_, Some(('>', _))) => true,
+                _ => false,
+            };
+
+            match token {
+                TokenTree::Ident(mut ident) => {
+                    let name = ident.to_string();
+                    if let Some(idx) = self.params.iter().position(|p| *p == name) {
+                        out.extend(self.args[idx].clone());
+                        prev = None;
+                        label_position = false;
+                        continue;
+                    }
+                    if label_position && self.labels.contains(&name) {
+                        ident = syn::Ident::new(&format!("{}{}", name, self.suffix), self.span);
+                    }
+                    ident.set_span(self.span);
+                    out.push(TokenTree::Ident(ident));
+                },
+                TokenTree::Group(group) => {
+                    let mut new = Group::new(group.delimiter(), self.substitute_tokens(group.stream(), false));
+                    new.set_span(self.span);
+                    out.push(TokenTree::Group(new));
+                },
+                mut token => {
+                    token.set_span(self.span);
+                    out.push(token);
+                },
+            }
+
+            prev = punct;
+            label_position = next_label_position;
+        }
+
+        out.into_iter().collect()
+    }
+}
@@ -0,0 +1,202 @@
+//! A front end that parses assembly written as rust tokens.
+//!
+//! It parses the lines of assembly, assembles every line with the architecture that is currently
+//! selected and serializes the result into method calls on a `dynasmrt` assembler. Any values
+//! that are only known at runtime are left as rust expressions in the output. This is what the
+//! `dynasm!` procedural macro and the build script API are built on.
+use proc_macro2::{TokenStream, TokenTree};
+use syn::ext::IdentExt;
+use syn::parse::{self, ParseStream, Parser};
+use syn::Token;
+use quote::quote;
+
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use crate::arch::BasicExprBuilder;
+use crate::{DynasmData, Ident, NumericRepr, Stmt};
+
+mod arch;
+mod builder;
+mod constants;
+mod directive;
+mod extended;
+mod macros;
+mod parse_helpers;
+mod serialize;
+
+pub use self::builder::Builder;
+pub use self::serialize::serialize;
+
+use self::parse_helpers::parse_ident_or_rust_keyword;
+
+/// Expands the body of a `dynasm!(ops ; line ; line ...)` invocation.
+///
+/// The lines are assembled and emitted into `ops`, which has to implement `DynasmApi`, and
+/// `DynasmLabelApi` if any labels are used. A line is an instruction, a label or a directive.
+/// Two consecutive semicolons are followed by a rust statement, which is inserted as is. Any
+/// errors are returned as `compile_error!` invocations.
+pub fn expand(data: &mut DynasmData, tokens: TokenStream) -> TokenStream {
+    let mut segments = split_lines(tokens).into_iter();
+
+    let target = segments.next().unwrap_or_default();
+    if target.is_empty() {
+        return syn::Error::new(proc_macro2::Span::call_site(), "Expected the assembler to emit into")
+            .to_compile_error();
+    }
+
+    let mut builder = Builder::new();
+    let mut errors: Option<syn::Error> = None;
+
+    while let Some(segment) = segments.next() {
+        // an empty line is followed by a rust statement
+        if segment.is_empty() {
+            let stmt = segments.next();
+            if let Some((ref name, _)) = data.recording {
+                let e = syn::Error::new(proc_macro2::Span::call_site(),
+                    format!("Rust statements can't be used in the body of macro '{}'", name));
+                match errors {
+                    Some(ref mut errors) => errors.combine(e),
+                    None => errors = Some(e),
+                }
+            } else if let Some(stmt) = stmt.filter(|_| data.is_assembling()) {
+                let stmt = builder.expr(stmt, NumericRepr::U8);
+                builder.push(Stmt::Stmt(stmt));
+            }
+            continue;
+        }
+
+        if let Err(e) = compile_line(data, &mut builder, segment) {
+            match errors {
+                Some(ref mut errors) => errors.combine(e),
+                None => errors = Some(e),
+            }
+        }
+    }
+
+    if let Err(e) = finish(data) {
+        match errors {
+            Some(ref mut errors) => errors.combine(e),
+            None => errors = Some(e),
+        }
+    }
+
+    if let Some(errors) = errors {
+        return errors.to_compile_error();
+    }
+
+    serialize(&target, builder)
+}
+
+/// Splits the input at every semicolon that is not nested in a group.
+pub(crate) fn split_lines(tokens: TokenStream) -> Vec<TokenStream> {
+    let mut lines = Vec::new();
+    let mut line = Vec::new();
+
+    for token in tokens {
+        match token {
+            TokenTree::Punct(ref punct) if punct.as_char() == ';' => {
+                lines.push(line.drain(..).collect());
+            },
+            token => line.push(token),
+        }
+    }
+    lines.push(line.into_iter().collect());
+    lines
+}
+
+/// Parses and assembles a single line, adding the statements it emits to the builder.
+///
+/// Between `.macro` and `.endm`, lines are recorded as the body of the macro instead.
+pub fn compile_line(data: &mut DynasmData, builder: &mut Builder, line: TokenStream) -> parse::Result<()> {
+    if let Some((_, ref mut mac)) = data.recording {
+        if !macros::is_definition(&line) {
+            mac.body.push(line.to_string());
+            return Ok(());
+        }
+    }
+    compile_line_at(data, builder, line, 0)
+}
+
+/// Checks that no macro or conditional block is left unfinished at the end of the input.
+pub fn finish(data: &mut DynasmData) -> parse::Result<()> {
+    if let Some((name, _)) = data.recording.take() {
+        return Err(syn::Error::new(proc_macro2::Span::call_site(),
+            format!("The body of macro '{}' is missing its .endm", name)));
+    }
+    if !data.conditionals.is_empty() {
+        data.conditionals.clear();
+        return Err(syn::Error::new(proc_macro2::Span::call_site(), "A .if is missing its .endif"));
+    }
+    Ok(())
+}
+
+/// Assembles a line that is `depth` macro expansions deep.
+pub(crate) fn compile_line_at(data: &mut DynasmData, builder: &mut Builder, line: TokenStream, depth: usize)
+    -> parse::Result<()>
+{
+    // lines in branches that aren't taken are skipped, apart from those that end the branch
+    if !data.is_assembling() && !constants::is_conditional(&line) {
+        return Ok(());
+    }
+    let line = constants::substitute(&data.constants, line);
+
+    let parser = |input: ParseStream| parse_line(data, builder, input, depth);
+    parser.parse2(line)
+}
+
+fn parse_line(data: &mut DynasmData, builder: &mut Builder, input: ParseStream, depth: usize) -> parse::Result<()> {
+    // ; . directive
+    if input.peek(Token![.]) {
+        return directive::compile_directive(data, builder, input);
+    }
+
+    // ; -> label :
+    if input.peek(Token![->]) {
+        let _: Token![->] = input.parse()?;
+        let name = parse_ident_or_rust_keyword(input)?;
+        let _: Token![:] = input.parse()?;
+        builder.push(Stmt::GlobalLabel(Ident { name: name.to_string() }));
+        return Ok(());
+    }
+
+    // ; => expr
+    if input.peek(Token![=>]) {
+        let _: Token![=>] = input.parse()?;
+        let expr: syn::Expr = input.parse()?;
+        let expr = builder.expr(quote! { #expr }, NumericRepr::U32);
+        builder.push(Stmt::DynamicLabel(expr));
+        return Ok(());
+    }
+
+    // ; label :
+    if input.peek(syn::Ident::peek_any) && input.peek2(Token![:]) && !input.peek2(Token![::]) {
+        let name = parse_ident_or_rust_keyword(input)?;
+        let _: Token![:] = input.parse()?;
+        builder.push(Stmt::LocalLabel(Ident { name: name.to_string() }));
+        return Ok(());
+    }
+
+    // ; int :
+    if input.peek(syn::LitInt) && input.peek2(Token![:]) {
+        let label: syn::LitInt = input.parse()?;
+        if !label.suffix().is_empty() {
+            return Err(syn::Error::new(label.span(), "Numeric labels can't have a suffix"));
+        }
+        // a reference to it would be read as the binary literal 0b
+        if label.base10_digits() == "0" {
+            return Err(syn::Error::new(label.span(), "Numeric labels start at 1, 0 can't be referenced"));
+        }
+        let _: Token![:] = input.parse()?;
+        builder.push(Stmt::LocalLabel(Ident { name: label.base10_digits().to_string() }));
+        return Ok(());
+    }
+
+    // ; macro (arg ("," arg)*)?
+    if macros::is_invocation(data, input) {
+        return macros::expand_macro(data, builder, input, depth);
+    }
+
+    arch::compile_instruction(data, builder, input)
+}
@@ -1,26 +1,6 @@
 //! This file contains parsing helpers used by multiple parsing backends
 use syn::parse;
-use std::convert::TryInto;
-
-/**
- * Jump types
- */
-
-pub trait ParseOpt: Sized {
-    fn parse(input: parse::ParseStream) -> parse::Result<Option<Self>>;
-}
-
-pub trait ParseOptExt {
-    /// Parses a syntax tree node of type `T`, advancing the position of our
-    /// parse stream past it if it was found.
-    fn parse_opt<T: ParseOpt>(&self) -> parse::Result<Option<T>>;
-}
-
-impl<'a> ParseOptExt for parse::ParseBuffer<'a> {
-    fn parse_opt<T: ParseOpt>(&self) -> parse::Result<Option<T>> {
-        T::parse(self)
-    }
-}
+use core::convert::TryInto;
 
 /// Tries to parse an ident that has a specific name as a keyword. Returns true if it worked.
 pub fn eat_pseudo_keyword(input: parse::ParseStream, kw: &str) -> bool {
@@ -56,7 +36,7 @@ pub fn as_ident(expr: &syn::Expr) -> Option<&syn::Ident> {
     }
 
     let segment = &path.segments[0];
-    if segment.arguments != syn::PathArguments::None {
+    if !segment.arguments.is_none() {
         return None;
     }
 
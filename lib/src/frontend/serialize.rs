@@ -0,0 +1,133 @@
+use proc_macro2::{Literal, Span, TokenStream};
+use quote::{quote, ToTokens};
+
+use crate::{Ident, JumpOffset, Size, Stmt, Value};
+
+use crate::frontend::builder::Builder;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+
+/// Converts a sequence of abstract Statements to actual tokens
+pub fn serialize(name: &TokenStream, mut builder: Builder) -> TokenStream {
+    let stmts = core::mem::take(&mut builder.stmts);
+
+    // first, try to fold constants into a byte stream
+    let mut folded_stmts = Vec::new();
+    let mut const_buffer = Vec::new();
+    for stmt in stmts {
+        match stmt {
+            Stmt::Const(Value::Number(number)) => {
+                number.write_le_bytes(&mut const_buffer);
+            },
+            Stmt::Extend(data) => {
+                const_buffer.extend(data);
+            },
+            s => {
+                // empty the const buffer
+                if !const_buffer.is_empty() {
+                    folded_stmts.push(Stmt::Extend(const_buffer));
+                    const_buffer = Vec::new();
+                }
+                folded_stmts.push(s);
+            }
+        }
+        while const_buffer.len() > 32 {
+            let new_buffer = const_buffer.split_off(32);
+            folded_stmts.push(Stmt::Extend(const_buffer));
+            const_buffer = new_buffer;
+        }
+    }
+    if !const_buffer.is_empty() {
+        folded_stmts.push(Stmt::Extend(const_buffer));
+    }
+
+    // and now do the final output pass in one go
+    let mut output = TokenStream::new();
+
+    for stmt in folded_stmts {
+        let (method, args) = match stmt {
+            Stmt::Const(Value::Number(_)) => unreachable!(),
+            Stmt::Const(Value::Expr(expr)) if expr.repr.size == Size::OWORD => {
+                // there are no push methods for 128-bit values
+                let signed = expr.repr.signed;
+                let expr = builder.tokens(expr);
+                let bytes = if signed {
+                    quote! { &i128::to_le_bytes(#expr) }
+                } else {
+                    quote! { &u128::to_le_bytes(#expr) }
+                };
+                ("extend", vec![bytes])
+            },
+            Stmt::Const(Value::Expr(expr)) => {
+                let method = match (expr.repr.size, expr.repr.signed) {
+                    (Size::BYTE, false)  => "push",
+                    (Size::WORD, false)  => "push_u16",
+                    (Size::DWORD, false) => "push_u32",
+                    (Size::QWORD, false) => "push_u64",
+                    (Size::BYTE, true)   => "push_i8",
+                    (Size::WORD, true)   => "push_i16",
+                    (Size::DWORD, true)  => "push_i32",
+                    (Size::QWORD, true)  => "push_i64",
+                    _ => unimplemented!()
+                };
+                (method, vec![builder.tokens(expr)])
+            },
+            Stmt::Extend(data)     => ("extend", vec![Literal::byte_string(&data).into_token_stream()]),
+            Stmt::ExprExtend(expr) => ("extend", vec![builder.value_tokens(expr)]),
+            Stmt::Align(expr, with)      => ("align", vec![builder.tokens(expr), builder.value_tokens(with)]),
+            Stmt::GlobalLabel(n) => ("global_label", vec![expr_string_from_ident(&n)]),
+            Stmt::LocalLabel(n)  => ("local_label", vec![expr_string_from_ident(&n)]),
+            Stmt::DynamicLabel(expr) => ("dynamic_label", vec![builder.tokens(expr)]),
+            Stmt::GlobalJumpTarget(n,     offset, reloc) => ("global_reloc"  , vec![expr_string_from_ident(&n), offset_tokens(&builder, offset), reloc_tokens(&reloc)]),
+            Stmt::ForwardJumpTarget(n,    offset, reloc) => ("forward_reloc" , vec![expr_string_from_ident(&n), offset_tokens(&builder, offset), reloc_tokens(&reloc)]),
+            Stmt::BackwardJumpTarget(n,   offset, reloc) => ("backward_reloc", vec![expr_string_from_ident(&n), offset_tokens(&builder, offset), reloc_tokens(&reloc)]),
+            Stmt::DynamicJumpTarget(expr, offset, reloc) => ("dynamic_reloc" , vec![offset_tokens(&builder, expr), offset_tokens(&builder, offset), reloc_tokens(&reloc)]),
+            Stmt::BareJumpTarget(expr, reloc)    => ("bare_reloc"    , vec![offset_tokens(&builder, expr), reloc_tokens(&reloc)]),
+            Stmt::Stmt(s) => {
+                let s = builder.tokens(s);
+                output.extend(quote! {
+                    #s ;
+                });
+                continue;
+            }
+        };
+
+        // and construct the appropriate method call
+        let method = syn::Ident::new(method, Span::call_site());
+        output.extend(quote! {
+            #name . #method ( #( #args ),* ) ;
+        })
+    }
+
+    // if we have nothing to emit, expand to nothing. Else, wrap it into a block.
+    if output.is_empty() {
+        output
+    } else {
+        quote!{
+            {
+                #output
+            }
+        }
+    }
+}
+
+/// The name of a label as a string literal.
+fn expr_string_from_ident(ident: &Ident) -> TokenStream {
+    Literal::string(&ident.name).into_token_stream()
+}
+
+/// A jump offset, which is zero if it was elided.
+fn offset_tokens(builder: &Builder, offset: JumpOffset) -> TokenStream {
+    match offset {
+        JumpOffset::Zero => quote! { 0 },
+        JumpOffset::Injected(value) => builder.value_tokens(value),
+    }
+}
+
+/// The architecture specific relocation data, as a tuple of bytes.
+fn reloc_tokens(reloc: &[u8]) -> TokenStream {
+    let bytes = reloc.iter().map(|&b| Literal::u8_unsuffixed(b));
+    quote! { ( #( #bytes , )* ) }
+}
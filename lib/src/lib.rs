@@ -1,9 +1,38 @@
+#![no_std]
+
+#[cfg(feature = "std")]
+extern crate std;
+extern crate alloc;
+
 // utility
 extern crate lazy_static;
 extern crate bitflags;
 extern crate byteorder;
 
-use std::collections::HashMap;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use crate::collections::HashMap;
+
+/// Prints a diagnostic on standard error. Without std there's nowhere to print it, so it's
+/// dropped.
+#[cfg(feature = "std")]
+macro_rules! eprintln {
+    ($($arg:tt)*) => { std::eprintln!($($arg)*) }
+}
+
+#[cfg(not(feature = "std"))]
+macro_rules! eprintln {
+    ($($arg:tt)*) => { { let _ = format_args!($($arg)*); } }
+}
+
+/// The maps used throughout the crate, from std if it's available, or from hashbrown otherwise.
+mod collections {
+    #[cfg(feature = "std")]
+    pub use std::collections::{HashMap, hash_map};
+    #[cfg(not(feature = "std"))]
+    pub use hashbrown::{HashMap, hash_map};
+}
 
 /// Module with common infrastructure across assemblers
 pub mod common;
@@ -11,9 +40,15 @@ pub mod common;
 pub mod arch;
 /// Module containing the implementation of directives
 mod directive;
+/// Module with the front end that parses assembly written as rust tokens
+#[cfg(feature = "frontend")]
+pub mod frontend;
+/// Module to compile assembly files into rust modules from build scripts
+#[cfg(feature = "build")]
+pub mod build;
 
 pub use common::{Const, Expr, Ident, Number, NumericRepr, JumpOffset, Size, Stmt, Value};
-pub use directive::{Directive, MalformedDirectiveError};
+pub use directive::{Conditional, Directive, Macro, MalformedDirectiveError, evaluate_directive};
 
 /// An assembler that simply collects all statements in order.
 ///
@@ -30,6 +65,43 @@ pub struct BasicAssembler {
 pub struct DynasmData {
     pub current_arch: Box<dyn arch::Arch>,
     pub aliases: HashMap<String, String>,
+    /// The aliases saved by every `.alias_push` that wasn't popped yet.
+    pub alias_scopes: Vec<HashMap<String, String>>,
+    /// The syntax that x64 and x86 instructions are written in.
+    pub syntax: Syntax,
+    /// The macros defined with `.macro`, by name.
+    pub macros: HashMap<String, Macro>,
+    /// The name and body of the macro that is being recorded, between `.macro` and `.endm`.
+    pub recording: Option<(String, Macro)>,
+    /// The number of macro expansions so far, which gives the local labels of every expansion
+    /// their own names.
+    pub expansions: usize,
+    /// The constants defined with `.equ`, by name.
+    pub constants: HashMap<String, Number>,
+    /// The conditional blocks that are open, innermost last.
+    pub conditionals: Vec<Conditional>,
+}
+
+/// The syntax of x64 and x86 instructions, selected with `.syntax`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    /// The NASM-like syntax dynasm uses by default, with the destination first.
+    Intel,
+    /// The AT&T syntax of GAS, with the destination last, `%` before registers, `$` before
+    /// immediates and the operand size as a suffix of the mnemonic.
+    Att,
+}
+
+impl core::str::FromStr for Syntax {
+    type Err = MalformedDirectiveError;
+
+    fn from_str(name: &str) -> Result<Syntax, MalformedDirectiveError> {
+        match name {
+            "intel" => Ok(Syntax::Intel),
+            "att" => Ok(Syntax::Att),
+            _ => Err(MalformedDirectiveError::UnknownSyntax(String::from(name))),
+        }
+    }
 }
 
 impl DynasmData {
@@ -39,6 +111,19 @@ impl DynasmData {
             current_arch:
                 arch::from_str(arch::CURRENT_ARCH).expect("Default architecture is invalid"),
             aliases: HashMap::new(),
+            alias_scopes: Vec::new(),
+            syntax: Syntax::Intel,
+            macros: HashMap::new(),
+            recording: None,
+            expansions: 0,
+            constants: HashMap::new(),
+            conditionals: Vec::new(),
         }
     }
+
+    /// If lines are assembled, which is when every open conditional block is in a branch that
+    /// is taken.
+    pub fn is_assembling(&self) -> bool {
+        self.conditionals.iter().all(|block| block.active)
+    }
 }
@@ -0,0 +1,36 @@
+//! The `dynasm!` procedural macro.
+//!
+//! This is a thin wrapper around the front end of dynasm-lib. It parses the assembly that is
+//! written within rust code, assembles every line with the architecture that is currently
+//! selected and serializes the result into method calls on a `dynasmrt` assembler. Any values
+//! that are only known at runtime are left as rust expressions in the output.
+extern crate proc_macro;
+
+use dynasm_lib::frontend::expand;
+use dynasm_lib::DynasmData;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+thread_local! {
+    /// The assembler state of every file, as `.arch` and `.alias` apply to the rest of the file
+    /// they're used in.
+    static STATE: RefCell<HashMap<String, DynasmData>> = RefCell::new(HashMap::new());
+}
+
+/// The whole point.
+///
+/// `dynasm!(ops ; line ; line ...)` assembles the lines and emits the result into `ops`, which
+/// has to implement `DynasmApi`, and `DynasmLabelApi` if any labels are used. A line is an
+/// instruction, a label or a directive. Two consecutive semicolons are followed by a rust
+/// statement, which is inserted as is.
+#[proc_macro]
+pub fn dynasm(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let file = proc_macro::Span::call_site().file();
+
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let data = state.entry(file).or_insert_with(DynasmData::new);
+        expand(data, tokens.into())
+    }).into()
+}
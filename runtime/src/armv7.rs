@@ -0,0 +1,420 @@
+use crate::relocations::{Relocation, RelocationSize, RelocationKind, ImpossibleRelocation, fits_signed_bitfield};
+use byteorder::{ByteOrder, LittleEndian};
+use std::convert::TryFrom;
+
+/// Relocation implementation for the ARMv7 architecture, covering both the A32 and T32 instruction sets.
+///
+/// Relocations in T32 code that are relative to the word-aligned PC (literal loads and `blx`)
+/// assume that the instruction containing them is located at a 4-byte aligned address.
+#[derive(Debug, Clone)]
+#[allow(non_camel_case_types)]
+pub enum ArmRelocation {
+    // b, bl: 26 bits, word aligned
+    A32_B,
+    // blx: 26 bits, halfword aligned
+    A32_BLX,
+    // ldr, ldrb: 12 bits + sign
+    A32_LDR,
+    // ldrh, ldrsb, ldrsh, ldrd: 8 bits + sign, split over two fields
+    A32_LDRH,
+    // vldr: 10 bits + sign, word aligned
+    A32_VLDR,
+    // cbz, cbnz: 7 bits, halfword aligned, forward only
+    T32_CBZ,
+    // 16-bit b<c>: 9 bits, halfword aligned
+    T32_B8,
+    // 16-bit b: 12 bits, halfword aligned
+    T32_B11,
+    // b<c>.w: 21 bits, halfword aligned
+    T32_B20,
+    // b.w, bl: 25 bits, halfword aligned
+    T32_B24,
+    // blx: 25 bits, word aligned
+    T32_BLX,
+    // 16-bit ldr: 10 bits, word aligned, forward only
+    T32_LDR_N,
+    // ldr, ldrb, ldrh, ldrsb, ldrsh: 12 bits + sign
+    T32_LDR,
+    // vldr: 10 bits + sign, word aligned
+    T32_VLDR,
+    // Anything in directives
+    Plain(RelocationSize),
+}
+
+impl ArmRelocation {
+    fn op_mask(&self) -> u32 {
+        match self {
+            Self::A32_B => 0xFF00_0000,
+            Self::A32_BLX => 0xFE00_0000,
+            Self::A32_LDR => 0xFF7F_F000,
+            Self::A32_LDRH => 0xFF7F_F0F0,
+            Self::A32_VLDR
+            | Self::T32_VLDR => 0xFF7F_FF00,
+            Self::T32_CBZ => 0xFD07,
+            Self::T32_B8
+            | Self::T32_LDR_N => 0xFF00,
+            Self::T32_B11 => 0xF800,
+            Self::T32_B20 => 0xFBC0_D000,
+            Self::T32_B24
+            | Self::T32_BLX => 0xF800_D000,
+            Self::T32_LDR => 0xFF7F_F000,
+            Self::Plain(_) => 0
+        }
+    }
+
+    /// The offset between the start of the instruction and the value of the PC during its execution.
+    fn pc_bias(&self) -> i64 {
+        match self {
+            Self::A32_B
+            | Self::A32_BLX
+            | Self::A32_LDR
+            | Self::A32_LDRH
+            | Self::A32_VLDR => 8,
+            _ => 4
+        }
+    }
+
+    fn encode(&self, value: isize) -> Result<u32, ImpossibleRelocation> {
+        let value = i64::try_from(value).map_err(|_| ImpossibleRelocation { } )?;
+        let value = value - self.pc_bias();
+        let magnitude = value.unsigned_abs() as u32;
+        let up = u32::from(value >= 0) << 23;
+
+        Ok(match self {
+            Self::A32_B => {
+                if value & 3 != 0 || !fits_signed_bitfield(value, 26) {
+                    return Err(ImpossibleRelocation { } );
+                }
+                ((value >> 2) as u32) & 0xFF_FFFF
+            },
+            Self::A32_BLX => {
+                if value & 1 != 0 || !fits_signed_bitfield(value, 26) {
+                    return Err(ImpossibleRelocation { } );
+                }
+                (((value >> 2) as u32) & 0xFF_FFFF)
+                | (((value >> 1) as u32 & 1) << 24)
+            },
+            Self::A32_LDR
+            | Self::T32_LDR => {
+                if magnitude > 0xFFF {
+                    return Err(ImpossibleRelocation { } );
+                }
+                up | magnitude
+            },
+            Self::A32_LDRH => {
+                if magnitude > 0xFF {
+                    return Err(ImpossibleRelocation { } );
+                }
+                up | ((magnitude & 0xF0) << 4) | (magnitude & 0xF)
+            },
+            Self::A32_VLDR
+            | Self::T32_VLDR => {
+                if magnitude & 3 != 0 || magnitude > 0x3FC {
+                    return Err(ImpossibleRelocation { } );
+                }
+                up | (magnitude >> 2)
+            },
+            Self::T32_CBZ => {
+                if value & 1 != 0 || !(0 .. 0x80).contains(&value) {
+                    return Err(ImpossibleRelocation { } );
+                }
+                let value = value as u32;
+                (((value >> 6) & 1) << 9)
+                | (((value >> 1) & 0x1F) << 3)
+            },
+            Self::T32_B8 => {
+                if value & 1 != 0 || !fits_signed_bitfield(value, 9) {
+                    return Err(ImpossibleRelocation { } );
+                }
+                ((value >> 1) as u32) & 0xFF
+            },
+            Self::T32_B11 => {
+                if value & 1 != 0 || !fits_signed_bitfield(value, 12) {
+                    return Err(ImpossibleRelocation { } );
+                }
+                ((value >> 1) as u32) & 0x7FF
+            },
+            Self::T32_B20 => {
+                if value & 1 != 0 || !fits_signed_bitfield(value, 21) {
+                    return Err(ImpossibleRelocation { } );
+                }
+                let value = value as u32;
+                (((value >> 20) & 1) << 26)
+                | (((value >> 12) & 0x3F) << 16)
+                | (((value >> 18) & 1) << 13)
+                | (((value >> 19) & 1) << 11)
+                | ((value >> 1) & 0x7FF)
+            },
+            Self::T32_B24
+            | Self::T32_BLX => {
+                let align = if let Self::T32_BLX = self { 3 } else { 1 };
+                if value & align != 0 || !fits_signed_bitfield(value, 25) {
+                    return Err(ImpossibleRelocation { } );
+                }
+                let value = value as u32;
+                let s = (value >> 24) & 1;
+                // J1 = NOT(I1 XOR S), J2 = NOT(I2 XOR S)
+                let j1 = !((value >> 23) ^ s) & 1;
+                let j2 = !((value >> 22) ^ s) & 1;
+                (s << 26)
+                | (((value >> 12) & 0x3FF) << 16)
+                | (j1 << 13)
+                | (j2 << 11)
+                | ((value >> 1) & 0x7FF)
+            },
+            Self::T32_LDR_N => {
+                if value & 3 != 0 || !(0 .. 0x400).contains(&value) {
+                    return Err(ImpossibleRelocation { } );
+                }
+                (value >> 2) as u32
+            },
+            Self::Plain(_) => return Err(ImpossibleRelocation { } )
+        })
+    }
+
+    fn decode(&self, bits: u32) -> i64 {
+        let (unpacked, bits) = match self {
+            Self::A32_B => ((bits & 0xFF_FFFF) << 2, 26),
+            Self::A32_BLX => (((bits & 0xFF_FFFF) << 2) | (((bits >> 24) & 1) << 1), 26),
+            Self::A32_LDR
+            | Self::T32_LDR => return signed_magnitude(bits, bits & 0xFFF),
+            Self::A32_LDRH => return signed_magnitude(bits, ((bits >> 4) & 0xF0) | (bits & 0xF)),
+            Self::A32_VLDR
+            | Self::T32_VLDR => return signed_magnitude(bits, (bits & 0xFF) << 2),
+            Self::T32_CBZ => return i64::from((((bits >> 9) & 1) << 6) | (((bits >> 3) & 0x1F) << 1)),
+            Self::T32_B8 => ((bits & 0xFF) << 1, 9),
+            Self::T32_B11 => ((bits & 0x7FF) << 1, 12),
+            Self::T32_B20 => ((((bits >> 26) & 1) << 20)
+                | (((bits >> 11) & 1) << 19)
+                | (((bits >> 13) & 1) << 18)
+                | (((bits >> 16) & 0x3F) << 12)
+                | ((bits & 0x7FF) << 1), 21),
+            Self::T32_B24
+            | Self::T32_BLX => {
+                let s = (bits >> 26) & 1;
+                let i1 = !(((bits >> 13) & 1) ^ s) & 1;
+                let i2 = !(((bits >> 11) & 1) ^ s) & 1;
+                ((s << 24)
+                | (i1 << 23)
+                | (i2 << 22)
+                | (((bits >> 16) & 0x3FF) << 12)
+                | ((bits & 0x7FF) << 1), 25)
+            },
+            Self::T32_LDR_N => return i64::from((bits & 0xFF) << 2),
+            Self::Plain(_) => unreachable!()
+        };
+
+        // Sign extend.
+        let unpacked = u64::from(unpacked);
+        let offset = 1u64 << (bits - 1);
+        let value: u64 = (unpacked ^ offset).wrapping_sub(offset);
+
+        value as i64
+    }
+}
+
+/// Applies the U bit of a load/store instruction to its offset magnitude.
+fn signed_magnitude(bits: u32, magnitude: u32) -> i64 {
+    if bits & (1 << 23) != 0 {
+        i64::from(magnitude)
+    } else {
+        -i64::from(magnitude)
+    }
+}
+
+/// 32-bit T32 instructions are stored as two little-endian halfwords, with the first halfword
+/// containing the most significant bits of the instruction.
+fn read_t32(buf: &[u8]) -> u32 {
+    (u32::from(LittleEndian::read_u16(&buf[0 .. 2])) << 16) | u32::from(LittleEndian::read_u16(&buf[2 .. 4]))
+}
+
+fn write_t32(buf: &mut [u8], value: u32) {
+    LittleEndian::write_u16(&mut buf[0 .. 2], (value >> 16) as u16);
+    LittleEndian::write_u16(&mut buf[2 .. 4], value as u16);
+}
+
+impl Relocation for ArmRelocation {
+    type Encoding = (u8,);
+    fn from_encoding(encoding: Self::Encoding) -> Self {
+        match encoding.0 {
+            0 => Self::A32_B,
+            1 => Self::A32_BLX,
+            2 => Self::A32_LDR,
+            3 => Self::A32_LDRH,
+            4 => Self::A32_VLDR,
+            5 => Self::T32_CBZ,
+            6 => Self::T32_B8,
+            7 => Self::T32_B11,
+            8 => Self::T32_B20,
+            9 => Self::T32_B24,
+            10 => Self::T32_BLX,
+            11 => Self::T32_LDR_N,
+            12 => Self::T32_LDR,
+            13 => Self::T32_VLDR,
+            x  => Self::Plain(RelocationSize::from_encoding(x - 13))
+        }
+    }
+    fn from_size(size: RelocationSize) -> Self {
+        Self::Plain(size)
+    }
+    fn size(&self) -> usize {
+        match self {
+            Self::Plain(s) => s.size(),
+            Self::T32_CBZ
+            | Self::T32_B8
+            | Self::T32_B11
+            | Self::T32_LDR_N => RelocationSize::Word.size(),
+            _ => RelocationSize::DWord.size(),
+        }
+    }
+    fn write_value(&self, buf: &mut [u8], value: isize) -> Result<(), ImpossibleRelocation> {
+        let mask = self.op_mask();
+        match self {
+            Self::Plain(s) => s.write_value(buf, value),
+            Self::A32_B
+            | Self::A32_BLX
+            | Self::A32_LDR
+            | Self::A32_LDRH
+            | Self::A32_VLDR => {
+                let template = LittleEndian::read_u32(buf) & mask;
+
+                let packed = self.encode(value)?;
+
+                LittleEndian::write_u32(buf, template | packed);
+                Ok(())
+            },
+            Self::T32_CBZ
+            | Self::T32_B8
+            | Self::T32_B11
+            | Self::T32_LDR_N => {
+                let template = u32::from(LittleEndian::read_u16(buf)) & mask;
+
+                let packed = self.encode(value)?;
+
+                LittleEndian::write_u16(buf, (template | packed) as u16);
+                Ok(())
+            },
+            _ => {
+                let template = read_t32(buf) & mask;
+
+                let packed = self.encode(value)?;
+
+                write_t32(buf, template | packed);
+                Ok(())
+            }
+        }
+    }
+    fn read_value(&self, buf: &[u8]) -> isize {
+        let bits = match self {
+            Self::Plain(s) => return s.read_value(buf),
+            Self::A32_B
+            | Self::A32_BLX
+            | Self::A32_LDR
+            | Self::A32_LDRH
+            | Self::A32_VLDR => LittleEndian::read_u32(buf),
+            Self::T32_CBZ
+            | Self::T32_B8
+            | Self::T32_B11
+            | Self::T32_LDR_N => u32::from(LittleEndian::read_u16(buf)),
+            _ => read_t32(buf)
+        };
+
+        (self.decode(bits) + self.pc_bias()) as isize
+    }
+    fn kind(&self) -> RelocationKind {
+        RelocationKind::Relative
+    }
+    fn page_size() -> usize {
+        4096
+    }
+}
+
+
+pub type Assembler = crate::Assembler<ArmRelocation>;
+pub type AssemblyModifier<'a> = crate::Modifier<'a, ArmRelocation>;
+pub type UncommittedModifier<'a> = crate::UncommittedModifier<'a>;
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that every value in `min ..= max` that is a multiple of `align` can be written into
+    /// a relocation, and reads back unchanged without disturbing the rest of the instruction, while
+    /// values just out of range or misaligned are rejected.
+    fn check_range(reloc: ArmRelocation, min: isize, max: isize, align: isize) {
+        let stride = ((max - min) / 4096 / align).max(1) * align;
+        let values = (min ..= max).step_by(stride as usize).chain([min, min + align, max - align, max]);
+
+        for value in values {
+            let mut buf = vec![0xFF; reloc.size()];
+            reloc.write_value(&mut buf, value).unwrap_or_else(|_| panic!("{:?} rejected {}", reloc, value));
+            assert_eq!(reloc.read_value(&buf), value, "{:?} round trip", reloc);
+
+            let mask = reloc.op_mask();
+            let template = match reloc {
+                ArmRelocation::A32_B
+                | ArmRelocation::A32_BLX
+                | ArmRelocation::A32_LDR
+                | ArmRelocation::A32_LDRH
+                | ArmRelocation::A32_VLDR => LittleEndian::read_u32(&buf),
+                _ if reloc.size() == 2 => u32::from(LittleEndian::read_u16(&buf)),
+                _ => read_t32(&buf)
+            };
+            assert_eq!(template & mask, mask, "{:?} clobbered the instruction", reloc);
+        }
+
+        let mut buf = vec![0xFF; reloc.size()];
+        assert!(reloc.write_value(&mut buf, min - align).is_err(), "{:?} accepted {}", reloc, min - align);
+        assert!(reloc.write_value(&mut buf, max + align).is_err(), "{:?} accepted {}", reloc, max + align);
+        if align > 1 {
+            assert!(reloc.write_value(&mut buf, min + 1).is_err(), "{:?} accepted {}", reloc, min + 1);
+        }
+    }
+
+    // these have to agree with the relocation ids the assembler emits
+    #[test]
+    fn encodings() {
+        let sizes: Vec<usize> = (0 ..= 13).map(|id| ArmRelocation::from_encoding((id,)).size()).collect();
+        assert_eq!(sizes, [4, 4, 4, 4, 4, 2, 2, 2, 4, 4, 4, 2, 4, 4]);
+
+        for &(id, size) in &[(14, RelocationSize::Byte), (15, RelocationSize::Word), (17, RelocationSize::DWord), (21, RelocationSize::QWord)] {
+            match ArmRelocation::from_encoding((id,)) {
+                ArmRelocation::Plain(s) => assert_eq!(s, size),
+                reloc => panic!("{} decoded as {:?}", id, reloc)
+            }
+        }
+    }
+
+    // offsets are relative to the start of the instruction, while the encoded values are relative
+    // to the PC, which is 8 bytes ahead in A32 and 4 bytes ahead in T32.
+    #[test]
+    fn round_trip_a32() {
+        check_range(ArmRelocation::A32_B, -0x200_0000 + 8, 0x1FF_FFFC + 8, 4);
+        check_range(ArmRelocation::A32_BLX, -0x200_0000 + 8, 0x1FF_FFFE + 8, 2);
+        check_range(ArmRelocation::A32_LDR, -0xFFF + 8, 0xFFF + 8, 1);
+        check_range(ArmRelocation::A32_LDRH, -0xFF + 8, 0xFF + 8, 1);
+        check_range(ArmRelocation::A32_VLDR, -0x3FC + 8, 0x3FC + 8, 4);
+    }
+
+    #[test]
+    fn round_trip_t32() {
+        check_range(ArmRelocation::T32_CBZ, 4, 0x7E + 4, 2);
+        check_range(ArmRelocation::T32_B8, -0x100 + 4, 0xFE + 4, 2);
+        check_range(ArmRelocation::T32_B11, -0x800 + 4, 0x7FE + 4, 2);
+        check_range(ArmRelocation::T32_B20, -0x10_0000 + 4, 0xF_FFFE + 4, 2);
+        check_range(ArmRelocation::T32_B24, -0x100_0000 + 4, 0xFF_FFFE + 4, 2);
+        check_range(ArmRelocation::T32_BLX, -0x100_0000 + 4, 0xFF_FFFC + 4, 4);
+        check_range(ArmRelocation::T32_LDR_N, 4, 0x3FC + 4, 4);
+        check_range(ArmRelocation::T32_LDR, -0xFFF + 4, 0xFFF + 4, 1);
+        check_range(ArmRelocation::T32_VLDR, -0x3FC + 4, 0x3FC + 4, 4);
+    }
+
+    // bl with the J1 and J2 bits set, checked against llvm-mc
+    #[test]
+    fn t32_bl() {
+        let mut buf = [0x00, 0xF0, 0x00, 0xF8];
+        ArmRelocation::T32_B24.write_value(&mut buf, 0x12_3456).unwrap();
+        assert_eq!(buf, [0x23, 0xF1, 0x29, 0xFA]);
+    }
+}
@@ -550,6 +550,16 @@ mod tests {
         test_litpool::<aarch64::Aarch64Relocation>();
     }
 
+    #[test]
+    fn test_litpool_riscv64() {
+        test_litpool::<riscv64::RiscvRelocation>();
+    }
+
+    #[test]
+    fn test_litpool_armv7() {
+        test_litpool::<armv7::ArmRelocation>();
+    }
+
     fn test_litpool<R: Relocation + Debug>() {
         let mut ops = Assembler::<R>::new().unwrap();
         let dynamic1 = ops.new_dynamic_label();
@@ -7,6 +7,8 @@ pub mod relocations;
 pub mod x64;
 pub mod x86;
 pub mod aarch64;
+pub mod riscv64;
+pub mod armv7;
 
 pub use crate::mmap::ExecutableBuffer;
 use crate::components::{MemoryManager, LabelRegistry, RelocRegistry, ManagedRelocs, PatchLoc};
@@ -0,0 +1,295 @@
+use crate::relocations::{Relocation, RelocationSize, RelocationKind, ImpossibleRelocation, fits_signed_bitfield};
+use byteorder::{ByteOrder, LittleEndian};
+use std::convert::TryFrom;
+
+/// Relocation implementation for the riscv64 architecture.
+#[derive(Debug, Clone)]
+#[allow(non_camel_case_types)]
+pub enum RiscvRelocation {
+    // beq, bne, blt, bge, bltu, bgeu: 13 bits, halfword aligned
+    B,
+    // jal: 21 bits, halfword aligned
+    J,
+    // auipc + jalr: 32 bits, split over two instructions as hi20 and lo12
+    AUIPC_JALR,
+    // auipc + addi: 32 bits, split over two instructions as hi20 and lo12
+    AUIPC_ADDI,
+    // c.beqz, c.bnez: 9 bits, halfword aligned
+    C_BRANCH,
+    // c.j: 12 bits, halfword aligned
+    C_J,
+    // Anything in directives
+    Plain(RelocationSize),
+}
+
+impl RiscvRelocation {
+    fn op_mask(&self) -> u32 {
+        match self {
+            Self::B => 0x01FF_F07F,
+            Self::J => 0x0000_0FFF,
+            Self::AUIPC_JALR
+            | Self::AUIPC_ADDI => 0x0000_0FFF,
+            Self::C_BRANCH => 0xE383,
+            Self::C_J => 0xE003,
+            Self::Plain(_) => 0
+        }
+    }
+
+    fn encode(&self, value: isize) -> Result<u32, ImpossibleRelocation> {
+        let value = i64::try_from(value).map_err(|_| ImpossibleRelocation { } )?;
+        Ok(match self {
+            Self::B => {
+                if value & 1 != 0 || !fits_signed_bitfield(value, 13) {
+                    return Err(ImpossibleRelocation { } );
+                }
+                let value = value as u32;
+                (((value >> 11) & 1) << 7)
+                | (((value >> 1) & 0xF) << 8)
+                | (((value >> 5) & 0x3F) << 25)
+                | (((value >> 12) & 1) << 31)
+            },
+            Self::J => {
+                if value & 1 != 0 || !fits_signed_bitfield(value, 21) {
+                    return Err(ImpossibleRelocation { } );
+                }
+                let value = value as u32;
+                (((value >> 12) & 0xFF) << 12)
+                | (((value >> 11) & 1) << 20)
+                | (((value >> 1) & 0x3FF) << 21)
+                | (((value >> 20) & 1) << 31)
+            },
+            // only returns the hi20 part. The lo12 part is sign extended by the second
+            // instruction, so the hi20 part needs to be rounded to compensate.
+            Self::AUIPC_JALR
+            | Self::AUIPC_ADDI => {
+                let hi = (value + 0x800) >> 12;
+                if !fits_signed_bitfield(hi, 20) {
+                    return Err(ImpossibleRelocation { } );
+                }
+                (hi as u32) << 12
+            },
+            Self::C_BRANCH => {
+                if value & 1 != 0 || !fits_signed_bitfield(value, 9) {
+                    return Err(ImpossibleRelocation { } );
+                }
+                let value = value as u32;
+                (((value >> 8) & 1) << 12)
+                | (((value >> 3) & 3) << 10)
+                | (((value >> 6) & 3) << 5)
+                | (((value >> 1) & 3) << 3)
+                | (((value >> 5) & 1) << 2)
+            },
+            Self::C_J => {
+                if value & 1 != 0 || !fits_signed_bitfield(value, 12) {
+                    return Err(ImpossibleRelocation { } );
+                }
+                let value = value as u32;
+                (((value >> 11) & 1) << 12)
+                | (((value >> 4) & 1) << 11)
+                | (((value >> 8) & 3) << 9)
+                | (((value >> 10) & 1) << 8)
+                | (((value >> 6) & 1) << 7)
+                | (((value >> 7) & 1) << 6)
+                | (((value >> 1) & 7) << 3)
+                | (((value >> 5) & 1) << 2)
+            },
+            Self::Plain(_) => return Err(ImpossibleRelocation { } )
+        })
+    }
+}
+
+impl Relocation for RiscvRelocation {
+    type Encoding = (u8,);
+    fn from_encoding(encoding: Self::Encoding) -> Self {
+        match encoding.0 {
+            0 => Self::B,
+            1 => Self::J,
+            2 => Self::AUIPC_JALR,
+            3 => Self::AUIPC_ADDI,
+            4 => Self::C_BRANCH,
+            5 => Self::C_J,
+            x  => Self::Plain(RelocationSize::from_encoding(x - 5))
+        }
+    }
+    fn from_size(size: RelocationSize) -> Self {
+        Self::Plain(size)
+    }
+    fn size(&self) -> usize {
+        match self {
+            Self::Plain(s) => s.size(),
+            Self::AUIPC_JALR
+            | Self::AUIPC_ADDI => RelocationSize::QWord.size(),
+            Self::C_BRANCH
+            | Self::C_J => RelocationSize::Word.size(),
+            _ => RelocationSize::DWord.size(),
+        }
+    }
+    fn write_value(&self, buf: &mut [u8], value: isize) -> Result<(), ImpossibleRelocation> {
+        match self {
+            Self::Plain(s) => s.write_value(buf, value),
+            Self::AUIPC_JALR
+            | Self::AUIPC_ADDI => {
+                let mask = self.op_mask();
+                let hi = LittleEndian::read_u32(&buf[0 .. 4]) & mask;
+                let lo = LittleEndian::read_u32(&buf[4 .. 8]) & 0x000F_FFFF;
+
+                let packed = self.encode(value)?;
+
+                LittleEndian::write_u32(&mut buf[0 .. 4], hi | packed);
+                LittleEndian::write_u32(&mut buf[4 .. 8], lo | ((value as u32) << 20));
+                Ok(())
+            },
+            Self::C_BRANCH
+            | Self::C_J => {
+                let mask = self.op_mask();
+                let template = u32::from(LittleEndian::read_u16(buf)) & mask;
+
+                let packed = self.encode(value)?;
+
+                LittleEndian::write_u16(buf, (template | packed) as u16);
+                Ok(())
+            },
+            _ => {
+                let mask = self.op_mask();
+                let template = LittleEndian::read_u32(buf) & mask;
+
+                let packed = self.encode(value)?;
+
+                LittleEndian::write_u32(buf, template | packed);
+                Ok(())
+            }
+        }
+    }
+    fn read_value(&self, buf: &[u8]) -> isize {
+        let (unpacked, bits) = match self {
+            Self::Plain(s) => return s.read_value(buf),
+            Self::AUIPC_JALR
+            | Self::AUIPC_ADDI => {
+                let hi = LittleEndian::read_u32(&buf[0 .. 4]) & 0xFFFF_F000;
+                let lo = LittleEndian::read_u32(&buf[4 .. 8]) as i32 >> 20;
+                return (i64::from(hi as i32) + i64::from(lo)) as isize;
+            },
+            Self::B => {
+                let value = LittleEndian::read_u32(buf);
+                (((value >> 7) & 1) << 11
+                | ((value >> 8) & 0xF) << 1
+                | ((value >> 25) & 0x3F) << 5
+                | ((value >> 31) & 1) << 12, 13)
+            },
+            Self::J => {
+                let value = LittleEndian::read_u32(buf);
+                (((value >> 12) & 0xFF) << 12
+                | ((value >> 20) & 1) << 11
+                | ((value >> 21) & 0x3FF) << 1
+                | ((value >> 31) & 1) << 20, 21)
+            },
+            Self::C_BRANCH => {
+                let value = u32::from(LittleEndian::read_u16(buf));
+                (((value >> 12) & 1) << 8
+                | ((value >> 10) & 3) << 3
+                | ((value >> 5) & 3) << 6
+                | ((value >> 3) & 3) << 1
+                | ((value >> 2) & 1) << 5, 9)
+            },
+            Self::C_J => {
+                let value = u32::from(LittleEndian::read_u16(buf));
+                (((value >> 12) & 1) << 11
+                | ((value >> 11) & 1) << 4
+                | ((value >> 9) & 3) << 8
+                | ((value >> 8) & 1) << 10
+                | ((value >> 7) & 1) << 6
+                | ((value >> 6) & 1) << 7
+                | ((value >> 3) & 7) << 1
+                | ((value >> 2) & 1) << 5, 12)
+            },
+        };
+
+        // Sign extend.
+        let unpacked = u64::from(unpacked);
+        let offset = 1u64 << (bits - 1);
+        let value: u64 = (unpacked ^ offset).wrapping_sub(offset);
+
+        value as i64 as isize
+    }
+    fn kind(&self) -> RelocationKind {
+        RelocationKind::Relative
+    }
+    fn page_size() -> usize {
+        4096
+    }
+}
+
+
+pub type Assembler = crate::Assembler<RiscvRelocation>;
+pub type AssemblyModifier<'a> = crate::Modifier<'a, RiscvRelocation>;
+pub type UncommittedModifier<'a> = crate::UncommittedModifier<'a>;
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that every value in `min ..= max` that is a multiple of `align` can be written into
+    /// a relocation, and reads back unchanged without disturbing the rest of the instruction, while
+    /// values just out of range or misaligned are rejected.
+    fn check_range(reloc: RiscvRelocation, min: isize, max: isize, align: isize) {
+        let stride = ((max - min) / 4096 / align).max(1) * align;
+        let values = (min ..= max).step_by(stride as usize).chain([min, min + align, -align, 0, align, max - align, max]);
+
+        for value in values {
+            let mut buf = vec![0xFF; reloc.size()];
+            reloc.write_value(&mut buf, value).unwrap_or_else(|_| panic!("{:?} rejected {}", reloc, value));
+            assert_eq!(reloc.read_value(&buf), value, "{:?} round trip", reloc);
+
+            let mask = reloc.op_mask();
+            let template = match reloc.size() {
+                2 => u32::from(LittleEndian::read_u16(&buf)),
+                _ => LittleEndian::read_u32(&buf),
+            };
+            assert_eq!(template & mask, mask, "{:?} clobbered the instruction", reloc);
+            if let RiscvRelocation::AUIPC_JALR | RiscvRelocation::AUIPC_ADDI = reloc {
+                assert_eq!(LittleEndian::read_u32(&buf[4 ..]) & 0x000F_FFFF, 0x000F_FFFF, "{:?} clobbered the instruction", reloc);
+            }
+        }
+
+        let mut buf = vec![0xFF; reloc.size()];
+        assert!(reloc.write_value(&mut buf, min - align).is_err(), "{:?} accepted {}", reloc, min - align);
+        assert!(reloc.write_value(&mut buf, max + align).is_err(), "{:?} accepted {}", reloc, max + align);
+        if align > 1 {
+            assert!(reloc.write_value(&mut buf, align + 1).is_err(), "{:?} accepted {}", reloc, align + 1);
+        }
+    }
+
+    // these have to agree with the relocation ids the assembler emits
+    #[test]
+    fn encodings() {
+        let sizes: Vec<usize> = (0 ..= 5).map(|id| RiscvRelocation::from_encoding((id,)).size()).collect();
+        assert_eq!(sizes, [4, 4, 8, 8, 2, 2]);
+
+        for &(id, size) in &[(6, RelocationSize::Byte), (7, RelocationSize::Word), (9, RelocationSize::DWord), (13, RelocationSize::QWord)] {
+            match RiscvRelocation::from_encoding((id,)) {
+                RiscvRelocation::Plain(s) => assert_eq!(s, size),
+                reloc => panic!("{} decoded as {:?}", id, reloc)
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        check_range(RiscvRelocation::B, -0x1000, 0xFFE, 2);
+        check_range(RiscvRelocation::J, -0x10_0000, 0xF_FFFE, 2);
+        check_range(RiscvRelocation::C_BRANCH, -0x100, 0xFE, 2);
+        check_range(RiscvRelocation::C_J, -0x800, 0x7FE, 2);
+        check_range(RiscvRelocation::AUIPC_JALR, -0x8000_0800, 0x7FFF_F7FF, 1);
+        check_range(RiscvRelocation::AUIPC_ADDI, -0x8000_0800, 0x7FFF_F7FF, 1);
+    }
+
+    // the lo12 part is negative here, so the hi20 part has to be rounded up. Checked against llvm-mc
+    #[test]
+    fn auipc_jalr() {
+        let mut buf = [0x97, 0x00, 0x00, 0x00, 0xE7, 0x80, 0x00, 0x00];
+        RiscvRelocation::AUIPC_JALR.write_value(&mut buf, 0x1_2345_0FFC).unwrap_err();
+        RiscvRelocation::AUIPC_JALR.write_value(&mut buf, 0x1234_5FFC).unwrap();
+        assert_eq!(buf, [0x97, 0x60, 0x34, 0x12, 0xE7, 0x80, 0xC0, 0xFF]);
+    }
+}
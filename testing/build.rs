@@ -0,0 +1,8 @@
+extern crate dynasm_lib;
+
+fn main() {
+    dynasm_lib::build::Build::new()
+        .file("tests/build_templates.dasm")
+        .compile()
+        .unwrap();
+}
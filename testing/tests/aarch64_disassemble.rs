@@ -0,0 +1,74 @@
+#![allow(unused_imports)]
+
+extern crate dynasmrt;
+extern crate dynasm;
+extern crate dynasm_lib;
+
+use dynasm::dynasm;
+use dynasmrt::{DynasmApi, DynasmLabelApi};
+
+use dynasm_lib::arch::aarch64::{disassemble, disassemble_listing};
+
+fn words(buf: &[u8]) -> Vec<u32> {
+    buf.chunks(4).map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]])).collect()
+}
+
+// every instruction dynasm! emits disassembles back to something it would accept
+#[test]
+fn disassemble_instructions() {
+    let mut ops = dynasmrt::aarch64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch aarch64
+        ; back:
+        ; add x0, x1, x2
+        ; ldr w3, [x4, 8]
+        ; orr x4, xzr, 0x5555_5555_5555_5555
+        ; movz x1, 0x1234, LSL 16
+        ; b <back
+        ; cbz x5, >fwd
+        ; fwd:
+        ; ret
+    );
+    let buf = ops.finalize().unwrap();
+
+    let text: Vec<String> = words(&buf).into_iter().map(|word| disassemble(word).unwrap()).collect();
+    assert_eq!(text, [
+        "add x0, x1, x2",
+        "ldr w3, [x4, #8]",
+        "mov.logical x4, #0x5555555555555555",
+        "mov x1, #0x12340000",
+        "b #-0x10",
+        "cbz x5, #4",
+        "ret",
+    ]);
+}
+
+#[test]
+fn disassemble_unknown() {
+    assert_eq!(disassemble(0xFFFF_FFFF), None);
+}
+
+// branch targets are resolved against the load address, and leftover bytes are kept
+#[test]
+fn disassemble_listing_targets() {
+    let mut ops = dynasmrt::aarch64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch aarch64
+        ; back:
+        ; cbz x5, >fwd
+        ; fwd:
+        ; b <back
+        ; .dword 0xFFFF_FFFF_u32 as i32
+        ; .byte 1
+    );
+    let buf = ops.finalize().unwrap();
+
+    let listing = disassemble_listing(&buf, 0x1000);
+    let lines: Vec<&str> = listing.lines().map(str::trim_end).collect();
+    assert_eq!(lines, [
+        "00001000: b4000025  cbz x5, #4                               // => 0x1004",
+        "00001004: 17ffffff  b #-4                                    // => 0x1000",
+        "00001008: ffffffff  .dword 0xffffffff",
+        "0000100c:           .byte 0x01",
+    ]);
+}
@@ -0,0 +1,119 @@
+#![allow(unused_imports)]
+
+extern crate dynasmrt;
+extern crate dynasm;
+
+use dynasm::dynasm;
+use dynasmrt::DynasmApi;
+
+fn hex(buf: &[u8]) -> String {
+    let hex: Vec<String> = buf.iter().map(|x| format!("{:02X}", *x)).collect();
+    hex.join(", ")
+}
+
+// immediates that a single movz or movn can load
+#[test]
+fn mov_single_instruction() {
+    let mut ops = dynasmrt::aarch64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch aarch64
+        ; mov x0, 0x1234
+        ; mov x1, 0x1234_0000
+        ; mov x2, -2
+        ; mov w3, 0xFFFE_FFFF
+    );
+    let buf = ops.finalize().unwrap();
+    assert_eq!(hex(&buf), "80, 46, 82, D2, 81, 46, A2, D2, 22, 00, 80, 92, 23, 00, A0, 12", "mov_single_instruction");
+
+    let mut ops = dynasmrt::aarch64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch aarch64
+        ; movz x0, 0x1234
+        ; movz x1, 0x1234, LSL 16
+        ; movn x2, 1
+        ; movn w3, 1, LSL 16
+    );
+    let explicit = ops.finalize().unwrap();
+    assert_eq!(hex(&buf), hex(&explicit), "mov_single_instruction");
+}
+
+// immediates that are a valid logical immediate are loaded with orr from the zero register
+#[test]
+fn mov_logical_immediate() {
+    let mut ops = dynasmrt::aarch64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch aarch64
+        ; mov x4, 0x5555_5555_5555_5555
+        ; mov w5, 0x00FF_00FF
+    );
+    let buf = ops.finalize().unwrap();
+    assert_eq!(&hex(&buf)[.. 14], "E4, F3, 00, B2", "mov_logical_immediate");
+
+    let mut ops = dynasmrt::aarch64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch aarch64
+        ; orr x4, xzr, 0x5555_5555_5555_5555
+        ; orr w5, wzr, 0x00FF_00FF
+    );
+    let explicit = ops.finalize().unwrap();
+    assert_eq!(hex(&buf), hex(&explicit), "mov_logical_immediate");
+}
+
+// other immediates start with whichever of movz and movn leaves the fewest halfwords to movk
+#[test]
+fn mov_multiple_instructions() {
+    let mut ops = dynasmrt::aarch64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch aarch64
+        ; mov x5, 0x1234_0000_5678
+        ; mov x6, 0xFFFF_1234_FFFF_5678
+        ; mov w7, 0x1234_5678
+        ; mov x8, 0x1234_5678_9ABC_DEF0
+    );
+    let buf = ops.finalize().unwrap();
+
+    let mut ops = dynasmrt::aarch64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch aarch64
+        ; movz x5, 0x5678
+        ; movk x5, 0x1234, LSL 32
+        ; movn x6, 0xA987
+        ; movk x6, 0x1234, LSL 32
+        ; movz w7, 0x5678
+        ; movk w7, 0x1234, LSL 16
+        ; movz x8, 0xDEF0
+        ; movk x8, 0x9ABC, LSL 16
+        ; movk x8, 0x5678, LSL 32
+        ; movk x8, 0x1234, LSL 48
+    );
+    let explicit = ops.finalize().unwrap();
+    assert_eq!(hex(&buf), hex(&explicit), "mov_multiple_instructions");
+}
+
+// dynamic immediates always use a movz and a movk for every other halfword
+#[test]
+fn mov_dynamic() {
+    let value = 0x1234_5678_9ABC_DEF0_u64;
+    let small = 0x1234_u32;
+
+    let mut ops = dynasmrt::aarch64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch aarch64
+        ; mov x8, value
+        ; mov w9, small
+    );
+    let buf = ops.finalize().unwrap();
+
+    let mut ops = dynasmrt::aarch64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch aarch64
+        ; movz x8, 0xDEF0
+        ; movk x8, 0x9ABC, LSL 16
+        ; movk x8, 0x5678, LSL 32
+        ; movk x8, 0x1234, LSL 48
+        ; movz w9, 0x1234
+        ; movk w9, 0, LSL 16
+    );
+    let explicit = ops.finalize().unwrap();
+    assert_eq!(hex(&buf), hex(&explicit), "mov_dynamic");
+}
@@ -0,0 +1,89 @@
+#![allow(unused_imports)]
+
+extern crate dynasmrt;
+extern crate dynasm;
+
+use dynasm::dynasm;
+use dynasmrt::{DynasmApi, DynasmLabelApi};
+
+fn hex(buf: &[u8]) -> String {
+    let hex: Vec<String> = buf.iter().map(|x| format!("0x{:02X}", *x)).collect();
+    hex.join(", ")
+}
+
+// aliases are scoped by .alias_push and .alias_pop, and can stand for dynamic registers
+#[test]
+fn aliases_scoped() {
+    let scratch = 11_u8;
+
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch x64
+        ; .alias acc, rax
+        ; .alias tmp, Rq(scratch)
+        ; .alias_push
+        ; .alias acc, rbx
+        ; .alias ptr, rsi
+        ; mov acc, [ptr + 8]
+        ; add acc, tmp
+        ; .alias_pop
+        ; .alias_push
+        ; .alias acc, rcx
+        ; .unalias tmp
+        ; .alias tmp, rdx
+        ; mov acc, tmp
+        ; .alias_pop
+        ; mov acc, tmp
+    );
+    let aliases = ops.finalize().unwrap();
+
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch x64
+        ; mov rbx, [rsi + 8]
+        ; add rbx, r11
+        ; mov rcx, rdx
+        ; mov rax, r11
+    );
+    let registers = ops.finalize().unwrap();
+
+    assert_eq!(hex(&aliases), hex(&registers), "aliases_scoped");
+}
+
+// aarch64 aliases work the same way, and vector aliases take their layout where they're used.
+// Aliases are file-scoped, so this test keeps its own in a scope.
+#[test]
+fn aliases_aarch64() {
+    let scratch = 9_u8;
+
+    let mut ops = dynasmrt::aarch64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch aarch64
+        ; .alias_push
+        ; .alias acc, x0
+        ; .alias tmp, X(scratch)
+        ; .alias vec, v3
+        ; .alias_push
+        ; .alias acc, x1
+        ; .alias ptr, x2
+        ; ldr acc, [ptr, 8]
+        ; add acc, acc, tmp
+        ; .alias_pop
+        ; add vec.B16, vec.B16, vec.B16
+        ; mov acc, tmp
+        ; .alias_pop
+    );
+    let aliases = ops.finalize().unwrap();
+
+    let mut ops = dynasmrt::aarch64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch aarch64
+        ; ldr x1, [x2, 8]
+        ; add x1, x1, x9
+        ; add v3.B16, v3.B16, v3.B16
+        ; mov x0, x9
+    );
+    let registers = ops.finalize().unwrap();
+
+    assert_eq!(hex(&aliases), hex(&registers), "aliases_aarch64");
+}
@@ -0,0 +1,140 @@
+// The dynasm! front end does not parse armv7 yet, so these tests build instructions through
+// the assembler library and link the statements it emits with the runtime assembler.
+extern crate dynasmrt;
+extern crate dynasm_lib;
+
+use dynasm_lib::arch::Arch;
+use dynasm_lib::arch::armv7::{ArchArm, ArchThumb, AssembleArm, AssembleThumb, InstructionArmv7};
+use dynasm_lib::arch::armv7::ast::{Instruction, RawArg, Register, RegId};
+use dynasm_lib::common::{Jump, JumpKind};
+use dynasm_lib::{BasicAssembler, Ident, JumpOffset, Size, Stmt, Value};
+use dynasmrt::DynasmLabelApi;
+
+fn hex(buf: &[u8]) -> String {
+    let hex: Vec<String> = buf.iter().map(|x| format!("0x{:02X}", *x)).collect();
+    hex.join(", ")
+}
+
+fn r(id: RegId) -> RawArg {
+    RawArg::Direct { reg: Register::Static(id), bang: false }
+}
+
+fn backward(name: &str) -> Jump {
+    Jump::new(JumpKind::Backward(Ident { name: name.to_string() }), None)
+}
+
+fn forward(name: &str) -> Jump {
+    Jump::new(JumpKind::Forward(Ident { name: name.to_string() }), None)
+}
+
+fn target(jump: Jump) -> RawArg {
+    RawArg::JumpTarget { jump }
+}
+
+fn instruction(name: &str, args: Vec<RawArg>) -> InstructionArmv7 {
+    InstructionArmv7 { inst: Instruction { ident: Ident { name: name.to_string() } }, args }
+}
+
+fn arm(ops: &mut BasicAssembler, name: &str, args: Vec<RawArg>) {
+    AssembleArm::compile_instruction(ops, &ArchArm::default(), instruction(name, args)).unwrap();
+}
+
+fn thumb(ops: &mut BasicAssembler, name: &str, args: Vec<RawArg>) {
+    AssembleThumb::compile_instruction(ops, &ArchThumb::default(), instruction(name, args)).unwrap();
+}
+
+fn label(ops: &mut BasicAssembler, name: &str) {
+    ops.stmts.push(Stmt::LocalLabel(Ident { name: name.to_string() }));
+}
+
+/// Feeds the statements into a runtime assembler, which resolves the relocations.
+fn link(stmts: Vec<Stmt>) -> Vec<u8> {
+    fn name(ident: Ident) -> &'static str {
+        Box::leak(ident.name.into_boxed_str())
+    }
+
+    let mut ops = dynasmrt::armv7::Assembler::new().unwrap();
+    for stmt in stmts {
+        match stmt {
+            Stmt::Const(Value::Number(number)) => ops.extend(number.to_le_bytes()),
+            Stmt::Extend(bytes) => ops.extend(bytes),
+            Stmt::LocalLabel(ident) => ops.local_label(name(ident)),
+            Stmt::ForwardJumpTarget(ident, JumpOffset::Zero, data) => ops.forward_reloc(name(ident), 0, (data[0],)),
+            Stmt::BackwardJumpTarget(ident, JumpOffset::Zero, data) => ops.backward_reloc(name(ident), 0, (data[0],)),
+            stmt => panic!("unexpected statement {:?}", stmt),
+        }
+    }
+    ops.finalize().unwrap().to_vec()
+}
+
+// every A32 relocation kind, checked against the output of llvm-mc
+#[test]
+fn arm_relocations() {
+    use RegId::*;
+
+    let mut ops = BasicAssembler { stmts: Vec::new() };
+    label(&mut ops, "back");
+    arm(&mut ops, "sub", vec![r(R0), r(R0), r(R1)]);
+    arm(&mut ops, "beq", vec![target(backward("back"))]);
+    arm(&mut ops, "bl", vec![target(forward("fwd"))]);
+    arm(&mut ops, "ldr", vec![r(R2), target(backward("back"))]);
+    arm(&mut ops, "ldrh", vec![r(R3), target(forward("fwd"))]);
+    arm(&mut ops, "vldr", vec![r(D0), target(forward("fwd"))]);
+    arm(&mut ops, "b", vec![target(backward("back"))]);
+    label(&mut ops, "fwd");
+    ArchArm::default().handle_static_reloc(&mut ops.stmts, backward("back"), Size::DWORD);
+
+    let buf = link(ops.stmts);
+    let expected = [
+        0x01, 0x00, 0x40, 0xE0, // sub r0, r0, r1
+        0xFD, 0xFF, 0xFF, 0x0A, // beq back
+        0x03, 0x00, 0x00, 0xEB, // bl fwd
+        0x14, 0x20, 0x1F, 0xE5, // ldr r2, back
+        0xB4, 0x30, 0xDF, 0xE1, // ldrh r3, fwd
+        0x00, 0x0B, 0x9F, 0xED, // vldr d0, fwd
+        0xF8, 0xFF, 0xFF, 0xEA, // b back
+        0xE4, 0xFF, 0xFF, 0xFF, // .dword back - .
+    ];
+    assert_eq!(hex(&buf), hex(&expected), "arm_relocations");
+}
+
+// every T32 relocation kind, checked against the output of llvm-mc
+#[test]
+fn thumb_relocations() {
+    use RegId::*;
+
+    let mut ops = BasicAssembler { stmts: Vec::new() };
+    label(&mut ops, "back");
+    thumb(&mut ops, "sub.w", vec![r(R0), r(R0), r(R1)]);
+    thumb(&mut ops, "cbz", vec![r(R0), target(forward("fwd"))]);
+    thumb(&mut ops, "beq.n", vec![target(backward("back"))]);
+    thumb(&mut ops, "ldr.n", vec![r(R1), target(forward("fwd"))]);
+    thumb(&mut ops, "b.n", vec![target(forward("fwd"))]);
+    thumb(&mut ops, "beq.w", vec![target(backward("back"))]);
+    thumb(&mut ops, "b.w", vec![target(forward("fwd"))]);
+    thumb(&mut ops, "bl", vec![target(forward("fwd"))]);
+    thumb(&mut ops, "ldr.w", vec![r(R2), target(backward("back"))]);
+    thumb(&mut ops, "vldr", vec![r(D1), target(forward("fwd"))]);
+    thumb(&mut ops, "ldrh.w", vec![r(R3), target(backward("back"))]);
+    thumb(&mut ops, "sub.w", vec![r(R0), r(R0), r(R1)]);
+    label(&mut ops, "fwd");
+    ArchThumb::default().handle_static_reloc(&mut ops.stmts, backward("back"), Size::DWORD);
+
+    let buf = link(ops.stmts);
+    let expected = [
+        0xA0, 0xEB, 0x01, 0x00, // sub.w r0, r0, r1
+        0x80, 0xB1,             // cbz r0, fwd
+        0xFB, 0xD0,             // beq.n back
+        0x07, 0x49,             // ldr.n r1, fwd
+        0x0D, 0xE0,             // b.n fwd
+        0x3F, 0xF4, 0xF8, 0xAF, // beq.w back
+        0x00, 0xF0, 0x0A, 0xB8, // b.w fwd
+        0x00, 0xF0, 0x08, 0xF8, // bl fwd
+        0x5F, 0xF8, 0x1C, 0x20, // ldr.w r2, back
+        0x9F, 0xED, 0x02, 0x1B, // vldr d1, fwd
+        0x3F, 0xF8, 0x24, 0x30, // ldrh.w r3, back
+        0xA0, 0xEB, 0x01, 0x00, // sub.w r0, r0, r1
+        0xD8, 0xFF, 0xFF, 0xFF, // .dword back - .
+    ];
+    assert_eq!(hex(&buf), hex(&expected), "thumb_relocations");
+}
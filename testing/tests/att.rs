@@ -0,0 +1,86 @@
+#![allow(unused_imports)]
+
+extern crate dynasmrt;
+extern crate dynasm;
+
+use dynasm::dynasm;
+use dynasmrt::{DynasmApi, DynasmLabelApi};
+
+fn hex(buf: &[u8]) -> String {
+    let hex: Vec<String> = buf.iter().map(|x| format!("0x{:02X}", *x)).collect();
+    hex.join(", ")
+}
+
+// AT&T syntax assembles to the same bytes as the equivalent instructions in the default syntax
+#[test]
+fn att_matches_intel() {
+    let value = 0x1234_i32;
+    let index = 3_u8;
+
+    let mut att = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(att
+        ; .arch x64
+        ; .syntax att
+        ; movq %rax, 8(%rbx,%rcx,4)
+        ; movl $5, %eax
+        ; addq $1, %rax
+        ; movl $value, -8(%rbp)
+        ; incb (%rax)
+        ; leaq (,%rdx,8), %rsi
+        ; movzbl (%rdi), %eax
+        ; movslq %ecx, %rdx
+        ; cltq
+        ; movq %fs:0x28, %rax
+        ; movq %Rq(index), %rcx
+        ; fldl 16(%rsp)
+        ; fadd %st(1), %st
+        ; enter $16, $0
+        ; call *%rax
+        ; jmpq *(%rax)
+        ; ret
+    );
+    let att = att.finalize().unwrap();
+
+    let mut intel = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(intel
+        ; .arch x64
+        ; .syntax intel
+        ; mov [rbx + rcx * 4 + 8], rax
+        ; mov eax, 5
+        ; add rax, 1
+        ; mov DWORD [rbp - 8], value
+        ; inc BYTE [rax]
+        ; lea rsi, [rdx * 8]
+        ; movzx eax, BYTE [rdi]
+        ; movsxd rdx, ecx
+        ; cdqe
+        ; fs mov rax, [0x28]
+        ; mov rcx, Rq(index)
+        ; fld QWORD [rsp + 16]
+        ; fadd st0, st1
+        ; enter 16, 0
+        ; call rax
+        ; jmp QWORD [rax]
+        ; ret
+    );
+    let intel = intel.finalize().unwrap();
+
+    assert_eq!(hex(&att), hex(&intel), "att_matches_intel");
+}
+
+// labels can be jumped to and addressed relative to rip
+#[test]
+fn att_labels() {
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch x64
+        ; .syntax att
+        ; ->start:
+        ; leaq ->start(%rip), %rax
+        ; jmp ->start
+        ; .syntax intel
+        ; ret
+    );
+    let buf = ops.finalize().unwrap();
+    assert_eq!(hex(&buf), "0x48, 0x8D, 0x05, 0xF9, 0xFF, 0xFF, 0xFF, 0xE9, 0xF4, 0xFF, 0xFF, 0xFF, 0xC3", "att_labels");
+}
@@ -0,0 +1,46 @@
+#![allow(unused_imports)]
+
+extern crate dynasmrt;
+extern crate dynasm;
+
+use dynasm::dynasm;
+use dynasmrt::{DynasmApi, DynasmLabelApi};
+
+// the functions that the build script compiled from tests/build_templates.dasm
+mod templates {
+    use dynasmrt::DynamicLabel;
+    include!(concat!(env!("OUT_DIR"), "/build_templates.rs"));
+}
+
+fn hex(buf: &[u8]) -> String {
+    let hex: Vec<String> = buf.iter().map(|x| format!("0x{:02X}", *x)).collect();
+    hex.join(", ")
+}
+
+// templates emit the same code as the same lines in dynasm!
+#[test]
+fn templates() {
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    let label = ops.new_dynamic_label();
+    templates::add_const(&mut ops, 0x1234);
+    templates::count_down(&mut ops, label);
+    templates::load(&mut ops, -8);
+    let buf = ops.finalize().unwrap();
+
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    let label = ops.new_dynamic_label();
+    let value = 0x1234;
+    let offset = -8;
+    dynasm!(ops
+        ; .arch x64
+        ; add rax, value
+        ; ret
+        ; =>label
+        ; dec rcx
+        ; jnz =>label
+        ; mov eax, DWORD [rsp + offset]
+    );
+    let expected = ops.finalize().unwrap();
+
+    assert_eq!(hex(&buf), hex(&expected), "templates");
+}
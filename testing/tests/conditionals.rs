@@ -0,0 +1,72 @@
+#![allow(unused_imports)]
+
+extern crate dynasmrt;
+extern crate dynasm;
+
+use dynasm::dynasm;
+use dynasmrt::{DynasmApi, DynasmLabelApi};
+
+fn hex(buf: &[u8]) -> String {
+    let hex: Vec<String> = buf.iter().map(|x| format!("0x{:02X}", *x)).collect();
+    hex.join(", ")
+}
+
+// constants are used like literals, in immediates, displacements and data
+#[test]
+fn constants_match_literals() {
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch x64
+        ; .equ FRAME, 0x20
+        ; .equ SLOT, -(FRAME / 4)
+        ; .equ SCALE, 1 << 3
+        ; sub rsp, FRAME
+        ; mov rax, [rbp + SLOT]
+        ; mov rcx, [rbx + rdx * SCALE + FRAME]
+        ; .dword FRAME, SLOT
+    );
+    let constants = ops.finalize().unwrap();
+
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch x64
+        ; sub rsp, 0x20
+        ; mov rax, [rbp - 8]
+        ; mov rcx, [rbx + rdx * 8 + 0x20]
+        ; .dword 0x20, -8
+    );
+    let literals = ops.finalize().unwrap();
+
+    assert_eq!(hex(&constants), hex(&literals), "constants_match_literals");
+}
+
+// only the first branch whose condition holds is assembled
+#[test]
+fn conditional_branches() {
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch x64
+        ; .equ LEVEL, 2
+        ; .if LEVEL >= 3
+        ;     ud2
+        ; .elseif LEVEL == 2 && true
+        ;     .if LEVEL & 1
+        ;         int3
+        ;     .else
+        ;         nop
+        ;     .endif
+        ; .elseif LEVEL > 0
+        ;     ud2
+        ; .else
+        ;     ud2
+        ; .endif
+        ; .if 0
+        ;     .if UNDEFINED
+        ;     .endif
+        ;     ud2
+        ; .endif
+        ; ret
+    );
+    let buf = ops.finalize().unwrap();
+    assert_eq!(hex(&buf), "0x90, 0xC3", "conditional_branches");
+}
@@ -0,0 +1,91 @@
+#![allow(unused_imports)]
+
+extern crate dynasmrt;
+extern crate dynasm;
+
+use dynasm::dynasm;
+use dynasmrt::{DynasmApi, DynasmLabelApi};
+
+fn hex(buf: &[u8]) -> String {
+    let hex: Vec<String> = buf.iter().map(|x| format!("0x{:02X}", *x)).collect();
+    hex.join(", ")
+}
+
+// float literals and expressions are emitted in their IEEE 754 encoding
+#[test]
+fn float_data() {
+    let half = 0.5_f32;
+    let third = 1.0_f64 / 3.0;
+
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch x64
+        ; .f32 1.5, -2, half
+        ; .f64 -0.25, third
+    );
+    let floats = ops.finalize().unwrap();
+
+    let mut expected = Vec::new();
+    expected.extend(&1.5_f32.to_le_bytes());
+    expected.extend(&(-2.0_f32).to_le_bytes());
+    expected.extend(&half.to_le_bytes());
+    expected.extend(&(-0.25_f64).to_le_bytes());
+    expected.extend(&third.to_le_bytes());
+
+    assert_eq!(hex(&floats), hex(&expected), "float_data");
+}
+
+// x87 extended precision floats have an explicit integer bit
+#[test]
+fn extended_float_data() {
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch x64
+        ; .f80 1.0, -2.5
+        ; .tword 0.0
+    );
+    let buf = ops.finalize().unwrap();
+    assert_eq!(hex(&buf), "0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0xFF, 0x3F, \
+                           0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xA0, 0x00, 0xC0, \
+                           0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00", "extended_float_data");
+}
+
+// literals are rounded once, to the precision of the directive
+#[test]
+fn float_literal_rounding() {
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch x64
+        // just above the midpoint of 1.0 and the next f32, which is exactly the midpoint as an f64
+        ; .f32 1.0000000596046447753906250001
+        ; .f80 0.1, -0.1
+    );
+    let buf = ops.finalize().unwrap();
+    assert_eq!(hex(&buf), "0x01, 0x00, 0x80, 0x3F, \
+                           0xCD, 0xCC, 0xCC, 0xCC, 0xCC, 0xCC, 0xCC, 0xCC, 0xFB, 0x3F, \
+                           0xCD, 0xCC, 0xCC, 0xCC, 0xCC, 0xCC, 0xCC, 0xCC, 0xFB, 0xBF", "float_literal_rounding");
+}
+
+// 128 and 256-bit integers are emitted in little endian, sign extended if negative
+#[test]
+fn wide_integer_data() {
+    let mask = 0x7FFF_FFFF_FFFF_FFFF_i128;
+
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch x64
+        ; .oword 0xFFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF, -2, mask
+        ; .yword 0x8000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0001, -1
+    );
+    let buf = ops.finalize().unwrap();
+
+    let mut expected = Vec::new();
+    expected.extend(&u128::max_value().to_le_bytes());
+    expected.extend(&(-2_i128).to_le_bytes());
+    expected.extend(&mask.to_le_bytes());
+    expected.extend(&1_u128.to_le_bytes());
+    expected.extend(&(1_u128 << 127).to_le_bytes());
+    expected.extend(&[0xFF; 32]);
+
+    assert_eq!(hex(&buf), hex(&expected), "wide_integer_data");
+}
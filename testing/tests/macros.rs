@@ -0,0 +1,89 @@
+#![allow(unused_imports)]
+
+extern crate dynasmrt;
+extern crate dynasm;
+
+use dynasm::dynasm;
+use dynasmrt::{DynasmApi, DynasmLabelApi};
+
+fn hex(buf: &[u8]) -> String {
+    let hex: Vec<String> = buf.iter().map(|x| format!("0x{:02X}", *x)).collect();
+    hex.join(", ")
+}
+
+// macros expand into the same bytes as their body written out with the arguments
+#[test]
+fn macros_match_expanded() {
+    let frame = 0x20_i32;
+
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch x64
+        ; .macro prologue size
+        ;     push rbp
+        ;     mov rbp, rsp
+        ;     sub rsp, size
+        ; .endm
+        ; .macro epilogue
+        ;     mov rsp, rbp
+        ;     pop rbp
+        ;     ret
+        ; .endm
+        ; .macro store dst, src
+        ;     mov QWORD [dst + 8], src
+        ; .endm
+        ; .macro function size, value
+        ;     prologue size
+        ;     store rbp, value
+        ;     epilogue
+        ; .endm
+        ; function frame, rax
+        ; function 0x10, rcx
+    );
+    let macros = ops.finalize().unwrap();
+
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch x64
+        ; push rbp
+        ; mov rbp, rsp
+        ; sub rsp, frame
+        ; mov QWORD [rbp + 8], rax
+        ; mov rsp, rbp
+        ; pop rbp
+        ; ret
+        ; push rbp
+        ; mov rbp, rsp
+        ; sub rsp, 0x10
+        ; mov QWORD [rbp + 8], rcx
+        ; mov rsp, rbp
+        ; pop rbp
+        ; ret
+    );
+    let expanded = ops.finalize().unwrap();
+
+    assert_eq!(hex(&macros), hex(&expanded), "macros_match_expanded");
+}
+
+// the local labels of every expansion are its own
+#[test]
+fn macros_local_labels() {
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch x64
+        ; .macro skip_if_zero reg
+        ;     test reg, reg
+        ;     jz >skip
+        ;     inc reg
+        ; skip:
+        ; .endm
+        ; jmp >skip
+        ; skip_if_zero eax
+        ; skip_if_zero ecx
+        ; skip:
+        ; ret
+    );
+    let buf = ops.finalize().unwrap();
+    assert_eq!(hex(&buf), "0xE9, 0x14, 0x00, 0x00, 0x00, 0x85, 0xC0, 0x0F, 0x84, 0x02, 0x00, 0x00, 0x00, 0xFF, 0xC0, \
+                           0x85, 0xC9, 0x0F, 0x84, 0x02, 0x00, 0x00, 0x00, 0xFF, 0xC1, 0xC3", "macros_local_labels");
+}
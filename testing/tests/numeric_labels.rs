@@ -0,0 +1,71 @@
+#![allow(unused_imports)]
+
+extern crate dynasmrt;
+extern crate dynasm;
+extern crate dynasm_lib;
+
+use dynasm::dynasm;
+use dynasmrt::{DynasmApi, DynasmLabelApi};
+
+fn hex(buf: &[u8]) -> String {
+    let hex: Vec<String> = buf.iter().map(|x| format!("0x{:02X}", *x)).collect();
+    hex.join(", ")
+}
+
+// numeric labels resolve like local labels, to the nearest definition in either direction
+#[test]
+fn numeric_labels_match_local() {
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch x64
+        ; .macro skip_if_zero reg
+        ;     test reg, reg
+        ;     jz 1f
+        ;     inc reg
+        ;     1:
+        ; .endm
+        ; 1:
+        ; dec rax
+        ; jnz 1b
+        ; skip_if_zero rcx
+        ; skip_if_zero rdx
+        ; jmp 1f
+        ; 1:
+        ; .dword 1b, 2f - 4
+        ; 2:
+    );
+    let numeric = ops.finalize().unwrap();
+
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch x64
+        ; top:
+        ; dec rax
+        ; jnz <top
+        ; test rcx, rcx
+        ; jz >a
+        ; inc rcx
+        ; a:
+        ; test rdx, rdx
+        ; jz >b
+        ; inc rdx
+        ; b:
+        ; jmp >c
+        ; c:
+        ; .dword <c, >d - 4
+        ; d:
+    );
+    let local = ops.finalize().unwrap();
+
+    assert_eq!(hex(&numeric), hex(&local), "numeric_labels_match_local");
+}
+
+// 0b is a binary literal, so a label 0 could never be referenced
+#[test]
+fn numeric_label_zero() {
+    let diagnostics = dynasm_lib::build::compile_str(".arch x64\nfoo():\n    0:\n    jmp 10f\n    10:\n    ret\n").unwrap_err();
+    let messages: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+    assert_eq!(messages, ["3: Numeric labels start at 1, 0 can't be referenced"]);
+
+    assert!(dynasm_lib::build::compile_str(".arch x64\nfoo():\n    1:\n    jmp 10f\n    10:\n    jmp 1b\n").is_ok());
+}
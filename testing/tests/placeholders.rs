@@ -0,0 +1,95 @@
+#![allow(unused_imports)]
+
+extern crate dynasmrt;
+extern crate dynasm;
+extern crate dynasm_lib;
+
+use dynasm::dynasm;
+use dynasmrt::DynasmApi;
+
+fn hex(buf: &[u8]) -> String {
+    let hex: Vec<String> = buf.iter().map(|x| format!("0x{:02X}", *x)).collect();
+    hex.join(", ")
+}
+
+// placeholders assemble to the same bytes as the dynamic registers and values they are bound to
+#[test]
+fn placeholders_match_dynamic_registers() {
+    let (a, b, v) = (3u8, 1u8, 2u8);
+
+    let mut bound = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(bound
+        ; .arch x64
+        ; .operand dst = out(reg) a
+        ; .operand in(reg_abcd) b
+        ; .operand n = const 42
+        ; .operand m = mem [rsp + 16]
+        ; .operand x = xmm_reg v
+        ; mov {dst}, {1}
+        ; add {dst:e}, {n}
+        ; mov {1:x}, [{dst} + {1} * 8 + {n}]
+        ; mov QWORD {m}, {dst}
+        ; movaps {x}, [{dst}]
+        ; vaddps {x:y}, {x:y}, {x:y}
+    );
+    let bound = bound.finalize().unwrap();
+
+    let mut dynamic = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(dynamic
+        ; .arch x64
+        ; mov Rq(a), Rq(b)
+        ; add Rd(a), 42
+        ; mov Rw(b), [Rq(a) + Rq(b) * 8 + 42]
+        ; mov QWORD [rsp + 16], Rq(a)
+        ; movaps Rx(v), [Rq(a)]
+        ; vaddps Ry(v), Ry(v), Ry(v)
+    );
+    let dynamic = dynamic.finalize().unwrap();
+
+    assert_eq!(hex(&bound), hex(&dynamic), "placeholders_match_dynamic_registers");
+}
+
+// placeholders can be used in AT&T syntax as well
+#[test]
+fn placeholders_att() {
+    let (a, b) = (3u8, 9u8);
+
+    let mut bound = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(bound
+        ; .arch x64
+        ; .syntax att
+        ; .operand a = reg a
+        ; .operand b = reg b
+        ; .operand c = const 4
+        ; movq ${c}, {a}
+        ; movl {c}({a},{b},8), {b:e}
+        ; .syntax intel
+    );
+    let bound = bound.finalize().unwrap();
+
+    let mut dynamic = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(dynamic
+        ; .arch x64
+        ; mov Rq(a), 4
+        ; mov Rd(b), [Rq(a) + Rq(b) * 8 + 4]
+    );
+    let dynamic = dynamic.finalize().unwrap();
+
+    assert_eq!(hex(&bound), hex(&dynamic), "placeholders_att");
+}
+
+// placeholders used as a kind of operand they don't stand for fail to compile
+#[test]
+fn placeholders_wrong_kind() {
+    let errors = |line: &str| -> Vec<String> {
+        let source = format!(".arch x64\nf():\n    .operand n = const 4\n    .operand m = mem [rsp + 16]\n    {}\n", line);
+        match dynasm_lib::build::compile_str(&source) {
+            Ok(module) => panic!("{} compiled to {}", line, module),
+            Err(diagnostics) => diagnostics.iter().map(|d| d.to_string()).collect(),
+        }
+    };
+
+    assert_eq!(errors("mov {n:e}, eax"), ["5: Modifiers can only be used on register operands"]);
+    assert_eq!(errors("mov rax, [{m} + 8]"), ["5: Memory references can't be used within a memory reference"]);
+    assert_eq!(errors(".syntax att\n    movq ({n}), %rax"), ["6: Expected a register operand, but found an immediate"]);
+}
@@ -0,0 +1,118 @@
+// The dynasm! front end does not parse riscv64 yet, so these tests build instructions through
+// the assembler library and link the statements it emits with the runtime assembler.
+extern crate dynasmrt;
+extern crate dynasm_lib;
+
+use dynasm_lib::arch::Arch;
+use dynasm_lib::arch::riscv64::{ArchRiscv64, AssembleRiscv, InstructionRiscv};
+use dynasm_lib::arch::riscv64::ast::{Instruction, RawArg, Register, RegId};
+use dynasm_lib::common::{Jump, JumpKind};
+use dynasm_lib::{BasicAssembler, Ident, JumpOffset, Number, NumericRepr, Size, Stmt, Value};
+use dynasmrt::DynasmLabelApi;
+
+fn hex(buf: &[u8]) -> String {
+    let hex: Vec<String> = buf.iter().map(|x| format!("0x{:02X}", *x)).collect();
+    hex.join(", ")
+}
+
+fn x(id: RegId) -> RawArg {
+    RawArg::Direct { reg: Register::Static(id) }
+}
+
+fn imm(value: i64) -> RawArg {
+    RawArg::Immediate { value: Value::Number(Number::from_u64_and_repr(value as u64, NumericRepr::I64)) }
+}
+
+fn backward(name: &str) -> Jump {
+    Jump::new(JumpKind::Backward(Ident { name: name.to_string() }), None)
+}
+
+fn forward(name: &str) -> Jump {
+    Jump::new(JumpKind::Forward(Ident { name: name.to_string() }), None)
+}
+
+fn target(jump: Jump) -> RawArg {
+    RawArg::JumpTarget { jump }
+}
+
+fn inst(ops: &mut BasicAssembler, name: &str, args: Vec<RawArg>) {
+    let arch = ArchRiscv64::default();
+    let instruction = InstructionRiscv { inst: Instruction { ident: Ident { name: name.to_string() } }, args };
+    ops.compile_instruction(&arch, instruction).unwrap();
+}
+
+fn label(ops: &mut BasicAssembler, name: &str) {
+    ops.stmts.push(Stmt::LocalLabel(Ident { name: name.to_string() }));
+}
+
+/// Feeds the statements into a runtime assembler, which resolves the relocations.
+fn link(stmts: Vec<Stmt>) -> Vec<u8> {
+    fn name(ident: Ident) -> &'static str {
+        Box::leak(ident.name.into_boxed_str())
+    }
+
+    let mut ops = dynasmrt::riscv64::Assembler::new().unwrap();
+    for stmt in stmts {
+        match stmt {
+            Stmt::Const(Value::Number(number)) => ops.extend(number.to_le_bytes()),
+            Stmt::Extend(bytes) => ops.extend(bytes),
+            Stmt::LocalLabel(ident) => ops.local_label(name(ident)),
+            Stmt::ForwardJumpTarget(ident, JumpOffset::Zero, data) => ops.forward_reloc(name(ident), 0, (data[0],)),
+            Stmt::BackwardJumpTarget(ident, JumpOffset::Zero, data) => ops.backward_reloc(name(ident), 0, (data[0],)),
+            stmt => panic!("unexpected statement {:?}", stmt),
+        }
+    }
+    ops.finalize().unwrap().to_vec()
+}
+
+// every relocation kind, checked against the output of llvm-mc
+#[test]
+fn relocations() {
+    use RegId::*;
+
+    let mut ops = BasicAssembler { stmts: Vec::new() };
+    label(&mut ops, "back");
+    inst(&mut ops, "sub", vec![x(X10), x(X10), x(X11)]);
+    inst(&mut ops, "beq", vec![x(X10), x(X11), target(backward("back"))]);
+    inst(&mut ops, "jal", vec![x(X1), target(forward("fwd"))]);
+    inst(&mut ops, "c.beqz", vec![x(X10), target(backward("back"))]);
+    inst(&mut ops, "c.j", vec![target(forward("fwd"))]);
+    inst(&mut ops, "call", vec![target(forward("fwd"))]);
+    inst(&mut ops, "la", vec![x(X12), target(backward("back"))]);
+    inst(&mut ops, "bne", vec![x(X12), x(X13), target(forward("fwd"))]);
+    label(&mut ops, "fwd");
+    // data directives
+    let arch = ArchRiscv64::default();
+    arch.handle_static_reloc(&mut ops.stmts, backward("back"), Size::DWORD);
+    arch.handle_static_reloc(&mut ops.stmts, backward("fwd"), Size::QWORD);
+
+    let buf = link(ops.stmts);
+    let expected = [
+        0x33, 0x05, 0xB5, 0x40, // sub a0, a0, a1
+        0xE3, 0x0E, 0xB5, 0xFE, // beq a0, a1, back
+        0xEF, 0x00, 0xC0, 0x01, // jal ra, fwd
+        0x75, 0xD9,             // c.beqz a0, back
+        0x19, 0xA8,             // c.j fwd
+        0x97, 0x00, 0x00, 0x00, // call fwd
+        0xE7, 0x80, 0x40, 0x01,
+        0x17, 0x06, 0x00, 0x00, // la a2, back
+        0x13, 0x06, 0x86, 0xFE,
+        0x63, 0x12, 0xD6, 0x00, // bne a2, a3, fwd
+        0xDC, 0xFF, 0xFF, 0xFF, // .dword back - .
+        0xFC, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, // .qword fwd - .
+    ];
+    assert_eq!(hex(&buf), hex(&expected), "relocations");
+}
+
+// static offsets are encoded directly, without a relocation
+#[test]
+fn static_offsets() {
+    use RegId::*;
+
+    let mut ops = BasicAssembler { stmts: Vec::new() };
+    inst(&mut ops, "beq", vec![x(X10), x(X11), imm(-4)]);
+    inst(&mut ops, "addi", vec![x(X10), x(X10), imm(-1)]);
+
+    let buf = link(ops.stmts);
+    assert_eq!(hex(&buf), "0xE3, 0x0E, 0xB5, 0xFE, 0x13, 0x05, 0xF5, 0xFF", "static_offsets");
+}
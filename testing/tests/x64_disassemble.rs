@@ -0,0 +1,97 @@
+#![allow(unused_imports)]
+
+extern crate dynasmrt;
+extern crate dynasm;
+extern crate dynasm_lib;
+
+use dynasm::dynasm;
+use dynasmrt::{DynasmApi, DynasmLabelApi};
+
+use dynasm_lib::arch::x64::{decode, disassemble_listing, Archx64, AssembleX64};
+use dynasm_lib::{BasicAssembler, Stmt, Value};
+
+fn hex(buf: &[u8]) -> String {
+    let hex: Vec<String> = buf.iter().map(|x| format!("0x{:02X}", *x)).collect();
+    hex.join(", ")
+}
+
+/// Compiles a decoded instruction again with only static operands.
+fn reassemble(instruction: dynasm_lib::arch::x64::InstructionX64) -> Vec<u8> {
+    let mut ops = BasicAssembler { stmts: Vec::new() };
+    ops.compile_instruction(&Archx64::default(), instruction).unwrap();
+
+    let mut buf = Vec::new();
+    for stmt in ops.stmts {
+        match stmt {
+            Stmt::Const(Value::Number(number)) => buf.extend(number.to_le_bytes()),
+            Stmt::Extend(bytes) => buf.extend(bytes),
+            stmt => panic!("unexpected statement {:?}", stmt),
+        }
+    }
+    buf
+}
+
+// every instruction decodes to text dynasm! accepts, and encodes to the same bytes again
+#[test]
+fn decode_instructions() {
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch x64
+        ; back:
+        ; mov rax, QWORD [rbx + rcx * 8 + 0x10]
+        ; add eax, 5
+        ; vaddps ymm1, ymm2, ymm3
+        ; lock add DWORD [rdi], 1
+        ; jmp <back
+        ; ret
+    );
+    let buf = ops.finalize().unwrap();
+
+    let mut offset = 0;
+    let mut text = Vec::new();
+    while offset < buf.len() {
+        let decoded = decode(&buf[offset ..]).unwrap();
+        let bytes = &buf[offset .. offset + decoded.len];
+        text.push(decoded.to_string());
+        assert_eq!(hex(&reassemble(decoded.instruction)), hex(bytes), "{}", text.last().unwrap());
+        offset += decoded.len;
+    }
+
+    assert_eq!(text, [
+        "mov rax, [rbx + rcx * 8 + 0x10]",
+        "add eax, 5",
+        "vaddps ymm1, ymm2, ymm3",
+        "lock add DWORD [rdi], DWORD 1",
+        "jmp -0x18",
+        "ret",
+    ]);
+}
+
+#[test]
+fn decode_invalid() {
+    assert!(decode(&[0x0F, 0xFF]).is_none());
+    assert!(decode(&[0x48, 0x8B]).is_none());
+    assert!(decode(&[]).is_none());
+}
+
+// jump targets are resolved against the load address, and undecodable bytes are kept
+#[test]
+fn decode_listing() {
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch x64
+        ; back:
+        ; add eax, 5
+        ; jmp <back
+        ; .byte 0x0F
+    );
+    let buf = ops.finalize().unwrap();
+
+    let listing = disassemble_listing(&buf, 0x1000);
+    let lines: Vec<&str> = listing.lines().map(str::trim_end).collect();
+    assert_eq!(lines, [
+        "00001000: 83 c0 05                  add eax, 5",
+        "00001003: e9 f8 ff ff ff            jmp -8                                   // => 0x1000",
+        "00001008: 0f                        .byte 0x0f",
+    ]);
+}
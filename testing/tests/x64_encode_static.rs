@@ -0,0 +1,110 @@
+#![allow(unused_imports)]
+
+extern crate dynasmrt;
+extern crate dynasm;
+extern crate dynasm_lib;
+
+use dynasm::dynasm;
+use dynasmrt::DynasmApi;
+
+use dynasm_lib::arch::x64::{Archx64, Archx86, InstructionX64, MAX_INSTRUCTION_LEN};
+use dynasm_lib::arch::x64::typed::{inst, mem, Imm, Label, Reg, Xmm};
+use dynasm_lib::{Expr, NumericRepr, Size};
+
+fn hex(buf: &[u8]) -> String {
+    let hex: Vec<String> = buf.iter().map(|x| format!("0x{:02X}", *x)).collect();
+    hex.join(", ")
+}
+
+fn idents(instruction: &InstructionX64) -> Vec<&str> {
+    instruction.inst.idents.iter().map(|ident| &ident.name[..]).collect()
+}
+
+fn encode(instruction: &InstructionX64) -> Vec<u8> {
+    let mut buf = [0; MAX_INSTRUCTION_LEN];
+    let len = Archx64::default().encode_static(&idents(instruction), &instruction.args, &mut buf).unwrap();
+    buf[.. len].to_vec()
+}
+
+// the same bytes as dynasm! emits for the same instructions
+#[test]
+fn encode_static_bytes() {
+    let instructions = [
+        inst("mov", [Reg::RAX.into(), mem().base(Reg::RBX).index(Reg::RCX, 8).disp(0x10).qword().into()]),
+        inst("add", [Reg::EAX.into(), 5.into()]),
+        inst("mov", [Reg::R9D.into(), 0x1234_5678.into()]),
+        inst("vaddps", [Xmm::X1.into(), Xmm::X2.into(), mem().base(Reg::R12).into()]),
+        inst("add", [mem().base(Reg::RDI).dword().into(), 1.into()]).prefix("lock"),
+        inst("mov", [Reg::RAX.into(), Imm::sized(-1, Size::QWORD).into()]),
+        inst("ret", []),
+    ];
+
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch x64
+        ; mov rax, QWORD [rbx + rcx * 8 + 0x10]
+        ; add eax, 5
+        ; mov r9d, 0x1234_5678
+        ; vaddps xmm1, xmm2, [r12]
+        ; lock add DWORD [rdi], 1
+        ; mov rax, QWORD -1
+        ; ret
+    );
+    let expected = ops.finalize().unwrap();
+
+    let mut buf = Vec::new();
+    for instruction in &instructions {
+        buf.extend(encode(instruction));
+    }
+    assert_eq!(hex(&buf), hex(&expected), "encode_static_bytes");
+
+    // appending produces the same bytes
+    let mut appended = Vec::new();
+    for instruction in &instructions {
+        let len = Archx64::default().encode_static_extend(&idents(instruction), &instruction.args, &mut appended).unwrap();
+        assert_eq!(len, encode(instruction).len());
+    }
+    assert_eq!(hex(&appended), hex(&expected), "encode_static_extend");
+}
+
+#[test]
+fn encode_static_x86() {
+    let instruction = inst("mov", [Reg::EAX.into(), mem().base(Reg::EBX).disp(8).dword().into()]);
+
+    let mut ops = dynasmrt::x86::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch x86
+        ; mov eax, DWORD [ebx + 8]
+    );
+    let expected = ops.finalize().unwrap();
+
+    let mut buf = Vec::new();
+    Archx86::default().encode_static_extend(&idents(&instruction), &instruction.args, &mut buf).unwrap();
+    assert_eq!(hex(&buf), hex(&expected), "encode_static_x86");
+}
+
+// anything that needs a runtime value or a relocation is rejected
+#[test]
+fn encode_static_rejects_dynamic() {
+    let number = Expr { idx: 0, repr: NumericRepr::U8 };
+    let instructions = [
+        inst("jmp", [Label::global("target").into()]),
+        inst("lea", [Reg::RAX.into(), Label::global("target").mem().into()]),
+        inst("mov", [Reg::dynamic(Size::QWORD, number).into(), Reg::RAX.into()]),
+        inst("add", [Reg::EAX.into(), Imm::expr(Expr { idx: 0, repr: NumericRepr::I32 }).into()]),
+        inst("mov", [Reg::RAX.into(), Reg::EAX.into()]),
+    ];
+
+    for instruction in &instructions {
+        let mut buf = [0; MAX_INSTRUCTION_LEN];
+        assert!(Archx64::default().encode_static(&idents(instruction), &instruction.args, &mut buf).is_err(), "{:?}", instruction);
+    }
+}
+
+#[test]
+fn encode_static_without_mnemonic() {
+    let mut buf = [0; MAX_INSTRUCTION_LEN];
+    let error = Archx64::default().encode_static(&[], &[], &mut buf).unwrap_err();
+    assert_eq!(error.to_string(), "An instruction needs a mnemonic");
+    assert!(Archx86::default().encode_static(&[], &[], &mut buf).is_err());
+}
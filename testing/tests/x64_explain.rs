@@ -0,0 +1,138 @@
+#![allow(unused_imports)]
+
+extern crate dynasmrt;
+extern crate dynasm;
+extern crate dynasm_lib;
+
+use dynasm::dynasm;
+use dynasmrt::DynasmApi;
+
+use dynasm_lib::arch::x64::{Archx64, AssembleX64, Bits, Explanation, Field, FieldKind, InstructionX64, Relocation, RelocationKind};
+use dynasm_lib::arch::x64::typed::{inst, mem, Label, Reg, Ymm};
+use dynasm_lib::arch::{BasicExprBuilder, ErrorSpan};
+use dynasm_lib::{BasicAssembler, Expr, NumericRepr, Size, Stmt, Value};
+
+use std::fmt;
+
+fn hex(buf: &[u8]) -> String {
+    let hex: Vec<String> = buf.iter().map(|x| format!("0x{:02X}", *x)).collect();
+    hex.join(", ")
+}
+
+/// Explains an instruction, and returns the static bytes that were emitted along with it.
+fn explain(instruction: InstructionX64) -> (Explanation, Vec<u8>) {
+    let mut ops = BasicAssembler { stmts: Vec::new() };
+    let explanation = ops.explain_instruction(&Archx64::default(), instruction).unwrap();
+
+    let mut buf = Vec::new();
+    for stmt in ops.stmts {
+        match stmt {
+            Stmt::Const(Value::Number(number)) => buf.extend(number.to_le_bytes()),
+            Stmt::Extend(bytes) => buf.extend(bytes),
+            _ => ()
+        }
+    }
+    (explanation, buf)
+}
+
+fn field(offset: usize, len: usize, kind: FieldKind) -> Field {
+    Field { offset, len, kind }
+}
+
+#[test]
+fn explain_legacy() {
+    let instruction = inst("add", [mem().base(Reg::R12).index(Reg::RCX, 4).disp(8).dword().into(), 0x100.into()]).prefix("lock");
+    let (explanation, buf) = explain(instruction);
+
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch x64
+        ; lock add DWORD [r12 + rcx * 4 + 8], 0x100
+    );
+    assert_eq!(hex(&buf), hex(&ops.finalize().unwrap()), "explain_legacy");
+
+    assert_eq!(explanation.form.mnemonic(), "add");
+    assert_eq!(explanation.fields, [
+        field(0, 1, FieldKind::LockRepPrefix(0xF0)),
+        field(1, 1, FieldKind::Rex { w: false, r: Bits::Static(0), x: Bits::Static(0), b: Bits::Static(1) }),
+        field(2, 1, FieldKind::Opcode),
+        field(3, 1, FieldKind::ModRm { mode: 1, reg: Bits::Static(0), rm: Bits::Static(4) }),
+        field(4, 1, FieldKind::Sib { scale: Bits::Static(2), index: Bits::Static(1), base: Bits::Static(4) }),
+        field(5, 1, FieldKind::Displacement),
+        field(6, 4, FieldKind::Immediate),
+    ]);
+    assert!(explanation.relocations.is_empty());
+}
+
+#[test]
+fn explain_vex() {
+    let (explanation, buf) = explain(inst("vaddps", [Ymm::Y1.into(), Ymm::Y2.into(), Ymm::Y11.into()]));
+
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch x64
+        ; vaddps ymm1, ymm2, ymm11
+    );
+    assert_eq!(hex(&buf), hex(&ops.finalize().unwrap()), "explain_vex");
+
+    let vex = FieldKind::Vex {
+        xop: false, two_byte: false, map_sel: 1, w: false, l: true, pp: 0,
+        r: Bits::Static(0), x: Bits::Static(0), b: Bits::Static(1), vvvv: Bits::Static(2),
+    };
+    assert_eq!(explanation.fields, [
+        field(0, 3, vex),
+        field(3, 1, FieldKind::Opcode),
+        field(4, 1, FieldKind::ModRm { mode: 3, reg: Bits::Static(1), rm: Bits::Static(3) }),
+    ]);
+}
+
+// relocated fields are reported at the offset of the field they patch
+#[test]
+fn explain_relocations() {
+    let (explanation, _) = explain(inst("jmp", [Label::global("target").into()]));
+    assert_eq!(explanation.fields, [field(0, 1, FieldKind::Opcode), field(1, 4, FieldKind::Immediate)]);
+    assert_eq!(explanation.relocations, [Relocation { offset: 1, size: Size::DWORD, kind: RelocationKind::Relative }]);
+
+    let (explanation, _) = explain(inst("lea", [Reg::RAX.into(), Label::global("target").mem().into()]));
+    assert_eq!(explanation.fields[3], field(3, 4, FieldKind::Displacement));
+    assert_eq!(explanation.relocations, [Relocation { offset: 3, size: Size::DWORD, kind: RelocationKind::Relative }]);
+}
+
+/// Collects statements and accepts any combination of expressions, like the builder of dynasm!.
+struct Combining {
+    stmts: Vec<Stmt>,
+}
+
+impl BasicExprBuilder for Combining {
+    fn push(&mut self, stmt: Stmt) {
+        self.stmts.push(stmt);
+    }
+
+    fn bit_or(&mut self, a: Expr, _: Value) -> Option<Expr> { Some(a) }
+    fn bit_and(&mut self, a: Expr, _: Value) -> Option<Expr> { Some(a) }
+    fn bit_xor(&mut self, a: Expr, _: Value) -> Option<Expr> { Some(a) }
+    fn add(&mut self, a: Expr, _: Value) -> Option<Expr> { Some(a) }
+    fn mul(&mut self, a: Expr, _: Value) -> Option<Expr> { Some(a) }
+    fn neg(&mut self, a: Expr) -> Option<Expr> { Some(a) }
+    fn log2(&mut self, a: Expr) -> Option<Expr> { Some(a) }
+    fn mask_shift(&mut self, val: Expr, _: u64, _: i8) -> Option<Expr> { Some(val) }
+
+    fn emit_error_at(&mut self, _: ErrorSpan, args: fmt::Arguments) {
+        panic!("{}", args);
+    }
+}
+
+// fields that depend on a register chosen at runtime can't be given a value
+#[test]
+fn explain_dynamic_register() {
+    let number = Expr { idx: 0, repr: NumericRepr::U8 };
+    let mut ops = Combining { stmts: Vec::new() };
+    let instruction = inst("mov", [Reg::dynamic(Size::QWORD, number).into(), Reg::RAX.into()]);
+    let explanation = ops.explain_instruction(&Archx64::default(), instruction).unwrap();
+
+    assert_eq!(explanation.fields, [
+        field(0, 1, FieldKind::Rex { w: true, r: Bits::Static(0), x: Bits::Static(0), b: Bits::Dynamic }),
+        field(1, 1, FieldKind::Opcode),
+        field(2, 1, FieldKind::ModRm { mode: 3, reg: Bits::Static(0), rm: Bits::Dynamic }),
+    ]);
+}
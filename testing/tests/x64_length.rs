@@ -0,0 +1,175 @@
+// Checks that the length predicted for an instruction matches what dynasm! emits for it.
+#![allow(unused_imports)]
+
+extern crate dynasmrt;
+extern crate dynasm;
+extern crate dynasm_lib;
+
+use dynasm::dynasm;
+use dynasmrt::{DynasmApi, DynasmLabelApi};
+
+use dynasm_lib::arch::x64::Archx64;
+use dynasm_lib::arch::x64::InstructionX64;
+use dynasm_lib::arch::x64::ast::{CleanArg, Instruction, RegFamily, RegId, Register};
+use dynasm_lib::common::{Jump, JumpKind};
+use dynasm_lib::{Expr, Ident, Number, NumericRepr, Size, Value};
+
+fn predict(name: &str, args: Vec<CleanArg>) -> usize {
+    let instruction = InstructionX64 {
+        inst: Instruction { idents: vec![Ident { name: name.to_string() }] },
+        args,
+    };
+    Archx64::default().predict_length(&instruction).unwrap()
+}
+
+fn expr(idx: usize, repr: NumericRepr) -> Expr {
+    Expr { idx, repr }
+}
+
+fn reg(size: Size, id: RegId) -> CleanArg {
+    CleanArg::Direct { reg: Register::new_static(size, id) }
+}
+
+fn dynamic(size: Size, idx: usize) -> Register {
+    Register::new_dynamic(size, RegFamily::LEGACY, expr(idx, NumericRepr::U8))
+}
+
+fn mem(size: Size, base: Register, disp: Option<Value>) -> CleanArg {
+    CleanArg::Indirect { nosplit: false, size: Some(size), disp_size: None, base: Some(base), index: None, disp }
+}
+
+fn imm(value: i64, repr: NumericRepr) -> CleanArg {
+    CleanArg::Immediate { value: Value::Number(Number::from_u64_and_repr(value as u64, repr)) }
+}
+
+fn global(name: &str) -> Jump {
+    Jump::new(JumpKind::Global(Ident { name: name.to_string() }), None)
+}
+
+#[test]
+fn length_static() {
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    let start = ops.offset().0;
+    dynasm!(ops
+        ; .arch x64
+        ; add eax, 5
+    );
+    let add = ops.offset().0 - start;
+    dynasm!(ops
+        ; .arch x64
+        ; mov r9, QWORD [rbx + 0x100]
+    );
+    let mov = ops.offset().0 - start - add;
+
+    assert_eq!(predict("add", vec![reg(Size::DWORD, RegId::RAX), imm(5, NumericRepr::I8)]), add);
+    let disp = Value::Number(Number::from_u64_and_repr(0x100, NumericRepr::I32));
+    let args = vec![reg(Size::QWORD, RegId::R9), mem(Size::QWORD, Register::new_static(Size::QWORD, RegId::RBX), Some(disp))];
+    assert_eq!(predict("mov", args), mov);
+}
+
+#[test]
+fn length_dynamic_register() {
+    let (a, b) = (9u8, 3u8);
+
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    let start = ops.offset().0;
+    dynasm!(ops
+        ; .arch x64
+        ; mov Rq(a), Rq(b)
+    );
+    let mov = ops.offset().0 - start;
+    dynasm!(ops
+        ; .arch x64
+        ; add Rd(a), DWORD [Rq(b)]
+    );
+    let add = ops.offset().0 - start - mov;
+
+    let args = vec![CleanArg::Direct { reg: dynamic(Size::QWORD, 0) }, CleanArg::Direct { reg: dynamic(Size::QWORD, 1) }];
+    assert_eq!(predict("mov", args), mov);
+    let args = vec![CleanArg::Direct { reg: dynamic(Size::DWORD, 0) }, mem(Size::DWORD, dynamic(Size::QWORD, 1), None)];
+    assert_eq!(predict("add", args), add);
+}
+
+#[test]
+fn length_dynamic_displacement() {
+    let disp = 0x12i32;
+
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    let start = ops.offset().0;
+    dynasm!(ops
+        ; .arch x64
+        ; mov rax, QWORD [rbp + disp]
+    );
+    let mov = ops.offset().0 - start;
+
+    let disp = Some(Value::Expr(expr(0, NumericRepr::I32)));
+    let args = vec![reg(Size::QWORD, RegId::RAX), mem(Size::QWORD, Register::new_static(Size::QWORD, RegId::RBP), disp)];
+    assert_eq!(predict("mov", args), mov);
+}
+
+#[test]
+fn length_dynamic_immediate() {
+    let value = 1i32;
+    let wide = 1i64;
+
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    let start = ops.offset().0;
+    dynasm!(ops
+        ; .arch x64
+        ; add ecx, value
+    );
+    let add = ops.offset().0 - start;
+    dynasm!(ops
+        ; .arch x64
+        ; mov rdx, QWORD wide
+    );
+    let mov = ops.offset().0 - start - add;
+
+    let args = vec![reg(Size::DWORD, RegId::RCX), CleanArg::Immediate { value: Value::Expr(expr(0, NumericRepr::I32)) }];
+    assert_eq!(predict("add", args), add);
+    let args = vec![reg(Size::QWORD, RegId::RDX), CleanArg::Immediate { value: Value::Expr(expr(0, NumericRepr::I64)) }];
+    assert_eq!(predict("mov", args), mov);
+}
+
+#[test]
+fn length_label_relative() {
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    let start = ops.offset().0;
+    dynasm!(ops
+        ; .arch x64
+        ; jmp ->target
+    );
+    let jmp = ops.offset().0 - start;
+    dynasm!(ops
+        ; .arch x64
+        ; lea rax, [->target]
+    );
+    let lea = ops.offset().0 - start - jmp;
+    dynasm!(ops
+        ; .arch x64
+        ; ->target:
+    );
+
+    assert_eq!(predict("jmp", vec![CleanArg::JumpTarget { jump: global("target"), size: None }]), jmp);
+    let args = vec![reg(Size::QWORD, RegId::RAX), CleanArg::IndirectJumpTarget { jump: global("target"), size: None }];
+    assert_eq!(predict("lea", args), lea);
+}
+
+// instructions that dynasm! refuses to compile can't have their length predicted either
+#[test]
+fn length_rejected() {
+    let base = Register::new_static(Size::QWORD, RegId::RAX);
+    let sized = CleanArg::Indirect {
+        nosplit: false,
+        size: Some(Size::QWORD),
+        disp_size: Some(Size::DWORD),
+        base: Some(base),
+        index: None,
+        disp: None,
+    };
+    let instruction = InstructionX64 {
+        inst: Instruction { idents: vec![Ident { name: "mov".to_string() }] },
+        args: vec![reg(Size::QWORD, RegId::RDX), sized],
+    };
+    assert!(Archx64::default().predict_length(&instruction).is_err());
+}
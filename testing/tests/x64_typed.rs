@@ -0,0 +1,103 @@
+#![allow(unused_imports)]
+
+extern crate dynasmrt;
+extern crate dynasm;
+extern crate dynasm_lib;
+
+use dynasm::dynasm;
+use dynasmrt::{DynasmApi, DynasmLabelApi};
+
+use dynasm_lib::arch::x64::{Archx64, AssembleX64, InstructionX64};
+use dynasm_lib::arch::x64::ast::CleanArg;
+use dynasm_lib::arch::x64::typed::{self, inst, mem, Imm, Label, Reg, Xmm, Ymm};
+use dynasm_lib::{BasicAssembler, Ident, JumpOffset, Size, Stmt, Value};
+
+fn hex(buf: &[u8]) -> String {
+    let hex: Vec<String> = buf.iter().map(|x| format!("0x{:02X}", *x)).collect();
+    hex.join(", ")
+}
+
+/// Assembles the instructions, and feeds the statements into a runtime assembler.
+fn link(instructions: Vec<InstructionX64>) -> Vec<u8> {
+    fn name(ident: Ident) -> &'static str {
+        Box::leak(ident.name.into_boxed_str())
+    }
+
+    let mut lib = BasicAssembler { stmts: Vec::new() };
+    for instruction in instructions {
+        lib.compile_instruction(&Archx64::default(), instruction).unwrap();
+    }
+
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    for stmt in lib.stmts {
+        match stmt {
+            Stmt::Const(Value::Number(number)) => ops.extend(number.to_le_bytes()),
+            Stmt::Extend(bytes) => ops.extend(bytes),
+            Stmt::GlobalJumpTarget(ident, JumpOffset::Zero, data) => ops.global_reloc(name(ident), 0, (data[0], data[1])),
+            Stmt::BackwardJumpTarget(ident, JumpOffset::Zero, data) => ops.backward_reloc(name(ident), 0, (data[0], data[1])),
+            stmt => panic!("unexpected statement {:?}", stmt),
+        }
+    }
+    ops.global_label("end");
+    ops.finalize().unwrap().to_vec()
+}
+
+// the typed builder produces the same instructions as their dynasm! syntax
+#[test]
+fn typed_instructions() {
+    let buf = link(vec![
+        typed::mov(Reg::RAX, mem().base(Reg::RBX).index(Reg::RCX, 8).disp(16).qword()),
+        typed::add(Reg::EAX, 5),
+        typed::sub(Reg::R10W, -1i16),
+        typed::mov(Reg::AH, 0xFFu8),
+        typed::mov(Reg::RDX, Imm::sized(1, Size::QWORD)),
+        typed::lea(Reg::RSI, mem().base(Reg::RIP).disp(0x100)),
+        typed::movaps(Xmm::X9, mem().index(Reg::RDI, 4).oword()),
+        inst("vaddps", [Ymm::Y1.into(), Ymm::Y2.into(), Ymm::Y3.into()]),
+        typed::add(mem().base(Reg::RDI).dword(), 1).prefix("lock"),
+        typed::jmp(Label::global("end")),
+        typed::lea(Reg::RAX, Label::global("end").mem()),
+        typed::ret(),
+    ]);
+
+    let mut ops = dynasmrt::x64::Assembler::new().unwrap();
+    dynasm!(ops
+        ; .arch x64
+        ; mov rax, QWORD [rbx + rcx * 8 + 16]
+        ; add eax, 5
+        ; sub r10w, -1
+        ; mov ah, 0xFF
+        ; mov rdx, QWORD 1
+        ; lea rsi, [rip + 0x100]
+        ; movaps xmm9, OWORD [rdi * 4]
+        ; vaddps ymm1, ymm2, ymm3
+        ; lock add DWORD [rdi], 1
+        ; jmp ->end
+        ; lea rax, [->end]
+        ; ret
+        ; ->end:
+    );
+    let expected = ops.finalize().unwrap();
+    assert_eq!(hex(&buf), hex(&expected), "typed_instructions");
+}
+
+// operands convert into the same arguments the parser produces
+#[test]
+fn typed_arguments() {
+    match CleanArg::from(typed::Arg::from(mem().base(Reg::RBX).index(Reg::RCX, 2).disp(-8).word())) {
+        CleanArg::Indirect { size: Some(Size::WORD), base: Some(_), index: Some((_, 2, None)), disp: Some(Value::Number(disp)), .. } => {
+            assert_eq!(disp.as_i64(), -8);
+            assert_eq!(disp.repr().size, Size::BYTE);
+        },
+        arg => panic!("unexpected argument {:?}", arg),
+    }
+
+    match CleanArg::from(typed::Arg::from(0x1234u16)) {
+        CleanArg::Immediate { value: Value::Number(value) } => assert_eq!(value.repr().size, Size::WORD),
+        arg => panic!("unexpected argument {:?}", arg),
+    }
+
+    let instruction = typed::mov(Reg::RAX, 1).prefix("lock");
+    let idents: Vec<&str> = instruction.inst.idents.iter().map(|ident| &ident.name[..]).collect();
+    assert_eq!(idents, ["lock", "mov"]);
+}